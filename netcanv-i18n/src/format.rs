@@ -54,19 +54,9 @@ impl<'f> Formatter<'f> {
    /// Finishes formatting the string.
    pub fn done(self) -> String {
       let mut errors = Vec::new();
-      let message = match self.language.bundle.get_message(self.key) {
-         Some(message) => message,
-         None => {
-            tracing::error!("message {:?} is missing", self.key);
-            return self.key.to_owned();
-         }
-      };
-      let pattern = match message.value() {
-         Some(value) => value,
-         None => {
-            tracing::error!("message {:?} doesn't have a value", self.key);
-            return self.key.to_owned();
-         }
+      let pattern = match self.language.get_pattern(self.key) {
+         Some(pattern) => pattern,
+         None => return self.key.to_owned(),
       };
       self.language.bundle.format_pattern(pattern, Some(&self.args), &mut errors).into_owned()
    }
@@ -3,7 +3,22 @@ use serde::{Deserialize, Serialize};
 /// The version constant. Increased by 100 every minor client version, and by 10000 every major
 /// version. eg. 200 is 0.2.0, 10000 is 1.0.0, 10203 is 1.2.3.
 /// If two versions' hundreds places differ, the versions are incompatible.
-pub const PROTOCOL_VERSION: u32 = 400;
+pub const PROTOCOL_VERSION: u32 = 405;
+
+/// The first protocol version that understands [`Packet::Ping`] and [`Packet::Pong`].
+pub const PING_VERSION: u32 = 401;
+
+/// The first protocol version that understands [`Packet::ClearCanvas`].
+pub const CLEAR_CANVAS_VERSION: u32 = 402;
+
+/// The first protocol version that understands [`Packet::ChunkPatch`].
+pub const CHUNK_PATCH_VERSION: u32 = 403;
+
+/// The first protocol version that understands [`Packet::Viewport`].
+pub const VIEWPORT_VERSION: u32 = 404;
+
+/// The first protocol version that understands [`Packet::GetChangedChunks`].
+pub const CHANGED_CHUNKS_VERSION: u32 = 405;
 
 pub fn versions_compatible(v1: u32, v2: u32) -> bool {
    v1 / 100 == v2 / 100
@@ -70,4 +85,73 @@ pub enum Packet {
     * Cursor and Stroke packets were removed in favor of the generic Tool packet.
     * Each tool is responsible for decoding its own packets now.
     */
+   /// Sent by the host to a client upon connection, if the room's canvas is bounded. Contains the
+   /// left, top, right, and bottom sides of the region that can be drawn to, in chunk
+   /// coordinates (inclusive).
+   ///
+   /// Since this is only sent for bounded rooms, unbounded rooms remain compatible with clients
+   /// that don't know about this packet.
+   DrawingBounds((i32, i32, i32, i32)),
+
+   /* ---
+    * VERSION 0.4.1 (protocol 401)
+    * ---
+    * Peers now reply to HiThere with their Version, so that both sides know which packets the
+    * other one understands.
+    */
+   /// Asks the peer to reply with a `Pong` carrying the same ID, to measure the latency between
+   /// the two peers.
+   ///
+   /// This is only sent to peers whose version is at least [`PING_VERSION`], so that older
+   /// clients, which don't know about this packet, remain compatible.
+   Ping(u64),
+
+   /// The reply to a `Ping` with the given ID.
+   Pong(u64),
+
+   /* ---
+    * VERSION 0.4.2 (protocol 402)
+    * ---
+    */
+   /// Sent by the host to make everyone in the room clear their canvas.
+   ///
+   /// This is only sent to peers whose version is at least [`CLEAR_CANVAS_VERSION`], as older
+   /// clients don't know about this packet.
+   ClearCanvas,
+
+   /* ---
+    * VERSION 0.4.3 (protocol 403)
+    * ---
+    */
+   /// Replaces a rectangular region of a chunk. Contains the chunk's position, the offset of the
+   /// region's top-left corner within the chunk, and the region's pixels encoded as a PNG image,
+   /// whose size is the size of the region.
+   ///
+   /// This is sent instead of `Chunks` when only a small part of a chunk changed. It is only sent
+   /// to peers whose version is at least [`CHUNK_PATCH_VERSION`]; older clients get the full
+   /// chunk instead.
+   ChunkPatch((i32, i32), (u32, u32), Vec<u8>),
+
+   /* ---
+    * VERSION 0.4.4 (protocol 404)
+    * ---
+    */
+   /// The region of the canvas visible in the sender's viewport, as its left, top, right, and
+   /// bottom sides, in canvas pixels. `None` means the sender stopped sharing their viewport.
+   ///
+   /// Viewports are only shared by users who opt into it, and only sent to peers whose version is
+   /// at least [`VIEWPORT_VERSION`].
+   Viewport(Option<(i32, i32, i32, i32)>),
+
+   /* ---
+    * VERSION 0.4.5 (protocol 405)
+    * ---
+    */
+   /// Asks the host for the positions of the chunks modified within the given number of
+   /// milliseconds, which the host replies to with `ChunkPositions`.
+   ///
+   /// This is sent after rejoining the room, to only download the chunks that changed while the
+   /// connection to the relay was down. It is only sent to hosts whose version is at least
+   /// [`CHANGED_CHUNKS_VERSION`]; older hosts are asked for all of their chunks instead.
+   GetChangedChunks(u64),
 }
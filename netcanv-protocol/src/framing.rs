@@ -0,0 +1,306 @@
+//! Splitting messages larger than [`MAX_PACKET_SIZE`] across multiple WebSocket frames.
+//!
+//! Messages that fit in a single frame are sent as-is. Larger messages are split into fragments,
+//! each of which starts with a header:
+//!
+//! - [`FRAGMENT_MAGIC`], which is never a valid start of a serialized packet, as no packet enum
+//!   has that many variants,
+//! - the length of the whole message, as a little-endian `u32`,
+//! - the offset of the fragment's data within the message, as a little-endian `u32`.
+//!
+//! Fragments of a single message must be sent in order, without any other messages in between.
+//!
+//! [`MAX_PACKET_SIZE`]: crate::relay::MAX_PACKET_SIZE
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::relay::MAX_PACKET_SIZE;
+
+/// The bytes each fragment starts with.
+pub const FRAGMENT_MAGIC: [u8; 4] = u32::MAX.to_le_bytes();
+
+/// The length of a fragment's header.
+pub const FRAGMENT_HEADER_LEN: usize = 12;
+
+/// The maximum length of a message that's split into fragments.
+// 64 MiB, which leaves plenty of room for large canvas transfers, while preventing a peer from
+// making the other side buffer arbitrary amounts of memory.
+pub const MAX_MESSAGE_SIZE: u32 = 64 * 1024 * 1024;
+
+/// The maximum amount of message data carried by a single fragment.
+const MAX_FRAGMENT_DATA_LEN: usize = MAX_PACKET_SIZE as usize - FRAGMENT_HEADER_LEN;
+
+/// Splits a message into frames no larger than [`MAX_PACKET_SIZE`].
+///
+/// Returns `None` if the message is larger than [`MAX_MESSAGE_SIZE`].
+pub fn split(message: Vec<u8>) -> Option<Vec<Vec<u8>>> {
+   if message.len() <= MAX_PACKET_SIZE as usize {
+      return Some(vec![message]);
+   }
+   let total_len = u32::try_from(message.len()).ok().filter(|&len| len <= MAX_MESSAGE_SIZE)?;
+   let frames = message
+      .chunks(MAX_FRAGMENT_DATA_LEN)
+      .enumerate()
+      .map(|(index, data)| {
+         let offset = (index * MAX_FRAGMENT_DATA_LEN) as u32;
+         let mut frame = Vec::with_capacity(FRAGMENT_HEADER_LEN + data.len());
+         frame.extend_from_slice(&FRAGMENT_MAGIC);
+         frame.extend_from_slice(&total_len.to_le_bytes());
+         frame.extend_from_slice(&offset.to_le_bytes());
+         frame.extend_from_slice(data);
+         frame
+      })
+      .collect();
+   Some(frames)
+}
+
+/// Puts messages split with [`split`] back together.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+   /// The message being reassembled, along with its expected length.
+   partial: Option<(usize, Vec<u8>)>,
+}
+
+impl Reassembler {
+   /// Creates a new reassembler, with no message in progress.
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   /// Returns whether a message is partially reassembled, and is waiting for more fragments.
+   pub fn is_in_progress(&self) -> bool {
+      self.partial.is_some()
+   }
+
+   /// Processes a received frame. Returns the full message once all of its fragments have
+   /// arrived, or `None` if more fragments are needed.
+   ///
+   /// After an error, the message in progress is discarded.
+   pub fn push(&mut self, frame: Vec<u8>) -> Result<Option<Vec<u8>>, FramingError> {
+      let result = self.push_inner(frame);
+      if result.is_err() {
+         self.partial = None;
+      }
+      result
+   }
+
+   fn push_inner(&mut self, frame: Vec<u8>) -> Result<Option<Vec<u8>>, FramingError> {
+      if frame.len() > MAX_PACKET_SIZE as usize {
+         return Err(FramingError::FrameTooBig);
+      }
+      if !frame.starts_with(&FRAGMENT_MAGIC) {
+         if self.partial.is_some() {
+            return Err(FramingError::Interrupted);
+         }
+         return Ok(Some(frame));
+      }
+
+      if frame.len() <= FRAGMENT_HEADER_LEN {
+         return Err(FramingError::MalformedFragment);
+      }
+      let read_u32 = |at: usize| u32::from_le_bytes(frame[at..at + 4].try_into().unwrap());
+      let total_len = read_u32(4);
+      let offset = read_u32(8) as usize;
+      let data = &frame[FRAGMENT_HEADER_LEN..];
+      if total_len > MAX_MESSAGE_SIZE {
+         return Err(FramingError::MessageTooBig);
+      }
+      let total_len = total_len as usize;
+
+      if self.partial.is_none() {
+         if offset != 0 {
+            return Err(FramingError::OutOfOrder);
+         }
+         // Messages that fit in a single frame are never split, so a fragmented message must be
+         // longer than that.
+         if total_len <= MAX_PACKET_SIZE as usize {
+            return Err(FramingError::MalformedFragment);
+         }
+      }
+      let (expected_len, buffer) = self.partial.get_or_insert_with(|| (total_len, Vec::new()));
+      if *expected_len != total_len || offset != buffer.len() {
+         return Err(FramingError::OutOfOrder);
+      }
+      if buffer.len() + data.len() > total_len {
+         return Err(FramingError::MalformedFragment);
+      }
+      buffer.extend_from_slice(data);
+
+      if buffer.len() == total_len {
+         Ok(self.partial.take().map(|(_, message)| message))
+      } else {
+         Ok(None)
+      }
+   }
+}
+
+/// An error that occurred while reassembling a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramingError {
+   /// A frame was larger than [`MAX_PACKET_SIZE`].
+   FrameTooBig,
+   /// A fragmented message declared a length larger than [`MAX_MESSAGE_SIZE`].
+   MessageTooBig,
+   /// A fragment's header or length didn't make sense.
+   MalformedFragment,
+   /// A fragment arrived out of order, or belonged to a different message.
+   OutOfOrder,
+   /// A regular message arrived while a fragmented message was being reassembled.
+   Interrupted,
+}
+
+impl std::error::Error for FramingError {}
+
+impl Display for FramingError {
+   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+      match self {
+         Self::FrameTooBig => write!(f, "frame is bigger than {} bytes", MAX_PACKET_SIZE),
+         Self::MessageTooBig => write!(f, "message is bigger than {} bytes", MAX_MESSAGE_SIZE),
+         Self::MalformedFragment => write!(f, "malformed fragment"),
+         Self::OutOfOrder => write!(f, "fragment arrived out of order"),
+         Self::Interrupted => write!(f, "fragmented message was interrupted by another message"),
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   /// Returns a message of the given length, whose bytes differ from those at nearby offsets.
+   fn message(len: usize) -> Vec<u8> {
+      (0..len).map(|i| (i % 251) as u8).collect()
+   }
+
+   /// Builds a fragment by hand, without going through [`split`].
+   fn fragment(total_len: u32, offset: u32, data: &[u8]) -> Vec<u8> {
+      let mut frame = FRAGMENT_MAGIC.to_vec();
+      frame.extend_from_slice(&total_len.to_le_bytes());
+      frame.extend_from_slice(&offset.to_le_bytes());
+      frame.extend_from_slice(data);
+      frame
+   }
+
+   /// Pushes all frames into the reassembler, and returns the messages that came out of it.
+   fn reassemble(reassembler: &mut Reassembler, frames: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+      frames.into_iter().filter_map(|frame| reassembler.push(frame).unwrap()).collect()
+   }
+
+   #[test]
+   fn message_of_exactly_max_packet_size_is_not_split() {
+      let message = message(MAX_PACKET_SIZE as usize);
+      let frames = split(message.clone()).unwrap();
+      assert_eq!(frames, vec![message.clone()]);
+      assert_eq!(reassemble(&mut Reassembler::new(), frames), vec![message]);
+   }
+
+   #[test]
+   fn message_one_byte_over_max_packet_size_is_split() {
+      let message = message(MAX_PACKET_SIZE as usize + 1);
+      let frames = split(message.clone()).unwrap();
+      assert_eq!(frames.len(), 2);
+      for frame in &frames {
+         assert!(frame.len() <= MAX_PACKET_SIZE as usize);
+         assert!(frame.starts_with(&FRAGMENT_MAGIC));
+      }
+      assert_eq!(reassemble(&mut Reassembler::new(), frames), vec![message]);
+   }
+
+   #[test]
+   fn multi_fragment_message_round_trips() {
+      let message = message(3 * MAX_PACKET_SIZE as usize + 1234);
+      let frames = split(message.clone()).unwrap();
+      assert_eq!(frames.len(), 4);
+
+      let mut reassembler = Reassembler::new();
+      let last = frames.len() - 1;
+      for (index, frame) in frames.into_iter().enumerate() {
+         let result = reassembler.push(frame).unwrap();
+         if index == last {
+            assert_eq!(result, Some(message.clone()));
+         } else {
+            assert_eq!(result, None);
+            assert!(reassembler.is_in_progress());
+         }
+      }
+      assert!(!reassembler.is_in_progress());
+   }
+
+   #[test]
+   fn fragment_with_wrong_offset_is_out_of_order() {
+      let frames = split(message(2 * MAX_PACKET_SIZE as usize)).unwrap();
+      let mut reassembler = Reassembler::new();
+      assert_eq!(reassembler.push(frames[0].clone()), Ok(None));
+      // Send the first fragment again, whose offset is 0 rather than the length received so far.
+      assert_eq!(
+         reassembler.push(frames[0].clone()),
+         Err(FramingError::OutOfOrder)
+      );
+
+      // A message can't start with anything but the first fragment.
+      let mut reassembler = Reassembler::new();
+      assert_eq!(
+         reassembler.push(frames[1].clone()),
+         Err(FramingError::OutOfOrder)
+      );
+   }
+
+   #[test]
+   fn fragment_with_mismatched_total_len_is_out_of_order() {
+      let total_len = 2 * MAX_PACKET_SIZE;
+      let data = vec![0; MAX_FRAGMENT_DATA_LEN];
+      let mut reassembler = Reassembler::new();
+      assert_eq!(reassembler.push(fragment(total_len, 0, &data)), Ok(None));
+      assert_eq!(
+         reassembler.push(fragment(total_len + 1, MAX_FRAGMENT_DATA_LEN as u32, &data)),
+         Err(FramingError::OutOfOrder)
+      );
+   }
+
+   #[test]
+   fn regular_frame_in_the_middle_of_a_message_is_an_interruption() {
+      let frames = split(message(2 * MAX_PACKET_SIZE as usize)).unwrap();
+      let mut reassembler = Reassembler::new();
+      assert_eq!(reassembler.push(frames[0].clone()), Ok(None));
+      assert_eq!(
+         reassembler.push(vec![1, 2, 3]),
+         Err(FramingError::Interrupted)
+      );
+   }
+
+   #[test]
+   fn fragment_with_only_a_header_is_malformed() {
+      let frame = fragment(2 * MAX_PACKET_SIZE, 0, &[]);
+      assert_eq!(frame.len(), FRAGMENT_HEADER_LEN);
+      assert_eq!(
+         Reassembler::new().push(frame),
+         Err(FramingError::MalformedFragment)
+      );
+   }
+
+   #[test]
+   fn messages_over_max_message_size_are_rejected() {
+      assert_eq!(
+         Reassembler::new().push(fragment(MAX_MESSAGE_SIZE + 1, 0, &[0; 16])),
+         Err(FramingError::MessageTooBig)
+      );
+      assert_eq!(split(vec![0; MAX_MESSAGE_SIZE as usize + 1]), None);
+   }
+
+   #[test]
+   fn reassembler_resets_after_an_error() {
+      let frames = split(message(2 * MAX_PACKET_SIZE as usize)).unwrap();
+      let mut reassembler = Reassembler::new();
+      assert_eq!(reassembler.push(frames[0].clone()), Ok(None));
+      assert!(reassembler.push(vec![1, 2, 3]).is_err());
+      assert!(!reassembler.is_in_progress());
+
+      // Both regular messages and new fragmented messages go through after the error.
+      assert_eq!(reassembler.push(vec![1, 2, 3]), Ok(Some(vec![1, 2, 3])));
+      let message = message(MAX_PACKET_SIZE as usize + 1);
+      assert_eq!(
+         reassemble(&mut reassembler, split(message.clone()).unwrap()),
+         vec![message]
+      );
+   }
+}
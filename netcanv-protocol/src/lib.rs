@@ -1,2 +1,3 @@
 pub mod client;
+pub mod framing;
 pub mod relay;
@@ -19,8 +19,9 @@ pub const DEFAULT_PORT: u16 = 62137;
 // The version is incremented whenever breaking changes are introduced in the protocol.
 pub const PROTOCOL_VERSION: u32 = 1;
 
-/// The maximum length of a serialized packet. If a packet is larger than this amount, the
-/// connection shall be closed.
+/// The maximum length of a single WebSocket frame. Packets larger than this amount are split into
+/// multiple frames, as described in [`framing`][crate::framing]. If a frame is larger than this
+/// amount, the connection shall be closed.
 // 4 MiB for now, should be plenty. Chunk packets are never larger than 128 KiB, and clipboard
 // images are downscaled to max 1024x1024. A 1024x1024 PNG of RGB noise is about 2 MiB.
 pub const MAX_PACKET_SIZE: u32 = 4 * 1024 * 1024;
@@ -178,4 +179,6 @@ pub enum Error {
    RoomDoesNotExist,
    /// The peer with the given ID doesn't seem to be connected.
    NoSuchPeer { address: PeerId },
+   /// The room has reached the relay's limit of peers, so nobody else can join it.
+   RoomFull,
 }
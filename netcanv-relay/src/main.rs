@@ -4,17 +4,21 @@
 use std::collections::{HashMap, HashSet};
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
+use netcanv_protocol::framing::{self, Reassembler};
 use netcanv_protocol::relay::{self, Packet, PeerId, RoomId, DEFAULT_PORT};
 use structopt::StructOpt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{accept_async, tungstenite, WebSocketStream};
+use tracing::field::{display, Empty};
+use tracing::Instrument;
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
@@ -27,6 +31,28 @@ struct Options {
    /// The port to host the relay under.
    #[structopt(short)]
    port: Option<u16>,
+
+   /// The maximum number of peers in a single room, including the host. Unlimited if not set.
+   #[structopt(long)]
+   max_peers_per_room: Option<usize>,
+
+   /// The number of minutes after which rooms without any activity are removed.
+   #[structopt(long, default_value = "30")]
+   idle_room_timeout: u64,
+
+   /// The minimum level of messages to log: `error`, `warn`, `info`, `debug`, or `trace`.
+   ///
+   /// Relayed packets are logged at the `trace` level. The `NETCANV_LOG` environment variable can
+   /// be used for more fine-grained filtering, and takes precedence over this option.
+   #[structopt(long, default_value = "info")]
+   log_level: LevelFilter,
+
+   /// The port to serve metrics under, over plain HTTP. Metrics are not served if not set.
+   ///
+   /// `/metrics` reports the number of open rooms, connected peers, and relayed bytes in the
+   /// Prometheus text format, and `/health` simply responds with `OK`.
+   #[structopt(long)]
+   metrics_port: Option<u16>,
 }
 
 struct Rooms {
@@ -34,6 +60,10 @@ struct Rooms {
    client_rooms: HashMap<PeerId, RoomId>,
    room_clients: HashMap<RoomId, Vec<PeerId>>,
    room_hosts: HashMap<RoomId, PeerId>,
+   /// The last time anything happened in each room.
+   room_activity: HashMap<RoomId, Instant>,
+   /// The maximum number of peers in a single room, or `None` if there is no limit.
+   max_peers_per_room: Option<usize>,
 }
 
 impl Rooms {
@@ -48,12 +78,14 @@ impl Rooms {
    /// not handle Unicode characters for performance reasons.
    const ID_CHARSET: &'static [u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZ";
 
-   fn new() -> Self {
+   fn new(max_peers_per_room: Option<usize>) -> Self {
       Self {
          occupied_room_ids: HashSet::new(),
          client_rooms: HashMap::new(),
          room_clients: HashMap::new(),
          room_hosts: HashMap::new(),
+         room_activity: HashMap::new(),
+         max_peers_per_room,
       }
    }
 
@@ -73,6 +105,7 @@ impl Rooms {
          let id = self.generate_room_id();
          if self.occupied_room_ids.insert(id) {
             self.room_clients.insert(id, Vec::new());
+            self.room_activity.insert(id, Instant::now());
             return Some(id);
          }
       }
@@ -89,6 +122,14 @@ impl Rooms {
       if let Some(room_clients) = self.room_clients.get_mut(&room_id) {
          self.client_rooms.insert(peer_id, room_id);
          room_clients.push(peer_id);
+         self.mark_active(room_id);
+      }
+   }
+
+   /// Marks the room as active, such that it doesn't get removed for being idle.
+   fn mark_active(&mut self, room_id: RoomId) {
+      if let Some(last_activity) = self.room_activity.get_mut(&room_id) {
+         *last_activity = Instant::now();
       }
    }
 
@@ -97,6 +138,29 @@ impl Rooms {
       self.occupied_room_ids.remove(&room_id);
       self.room_clients.remove(&room_id);
       self.room_hosts.remove(&room_id);
+      self.room_activity.remove(&room_id);
+   }
+
+   /// Removes all rooms that haven't been active for longer than the given timeout, and returns
+   /// their IDs.
+   ///
+   /// This cleans up rooms whose peers' connections died without being torn down properly.
+   fn remove_idle_rooms(&mut self, timeout: Duration) -> Vec<RoomId> {
+      let idle_rooms: Vec<RoomId> = self
+         .room_activity
+         .iter()
+         .filter(|(_, last_activity)| last_activity.elapsed() > timeout)
+         .map(|(&room_id, _)| room_id)
+         .collect();
+      for &room_id in &idle_rooms {
+         if let Some(room_clients) = self.room_clients.get(&room_id) {
+            for peer_id in room_clients {
+               self.client_rooms.remove(peer_id);
+            }
+         }
+         self.remove_room(room_id);
+      }
+      idle_rooms
    }
 
    /// Makes the peer quit the room with the given ID. Returns the peer's room ID.
@@ -123,6 +187,14 @@ impl Rooms {
       self.room_hosts.get(&room_id).cloned()
    }
 
+   /// Returns whether the given room has reached the peer limit, such that nobody else can join it.
+   fn is_full(&self, room_id: RoomId) -> bool {
+      match (self.max_peers_per_room, self.room_clients.get(&room_id)) {
+         (Some(max_peers), Some(room_clients)) => room_clients.len() >= max_peers,
+         _ => false,
+      }
+   }
+
    /// Returns the ID of the given peer's room, or `None` if they haven't joined a room yet.
    fn room_id(&self, peer_id: PeerId) -> Option<RoomId> {
       self.client_rooms.get(&peer_id).cloned()
@@ -179,23 +251,61 @@ impl Peers {
 struct State {
    rooms: Rooms,
    peers: Peers,
+   /// The total number of payload bytes peers have sent to be relayed.
+   relayed_bytes: u64,
 }
 
 impl State {
-   fn new() -> Self {
+   fn new(max_peers_per_room: Option<usize>) -> Self {
       Self {
-         rooms: Rooms::new(),
+         rooms: Rooms::new(max_peers_per_room),
          peers: Peers::new(),
+         relayed_bytes: 0,
       }
    }
+
+   /// Renders the relay's metrics in the Prometheus text format.
+   fn metrics(&self) -> String {
+      let metrics = [
+         (
+            "netcanv_relay_rooms",
+            "gauge",
+            "The number of open rooms.",
+            self.rooms.occupied_room_ids.len() as u64,
+         ),
+         (
+            "netcanv_relay_peers",
+            "gauge",
+            "The number of peers in rooms.",
+            self.peers.occupied_peer_ids.len() as u64,
+         ),
+         (
+            "netcanv_relay_relayed_bytes_total",
+            "counter",
+            "The total number of payload bytes relayed between peers.",
+            self.relayed_bytes,
+         ),
+      ];
+      let mut output = String::new();
+      for (name, kind, help, value) in metrics {
+         output.push_str(&format!(
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+         ));
+      }
+      output
+   }
 }
 
 async fn send_packet(sink: &Mutex<Sink>, packet: Packet) -> anyhow::Result<()> {
    let encoded = bincode::serialize(&packet)?;
-   let mut sink = sink.lock().await;
-   u32::try_from(encoded.len()).context("packet is too big")?;
+   let frames = framing::split(encoded).context("packet is too big")?;
 
-   sink.send(Message::Binary(encoded)).await?;
+   // The sink is kept locked until all fragments are sent, so that they don't get interleaved
+   // with other packets.
+   let mut sink = sink.lock().await;
+   for frame in frames {
+      sink.send(Message::Binary(frame)).await?;
+   }
    Ok(())
 }
 
@@ -209,7 +319,7 @@ async fn broadcast_packet(
    packet: Packet,
 ) -> anyhow::Result<()> {
    let packet = bincode::serialize(&packet)?;
-   u32::try_from(packet.len()).context("packet is too big")?;
+   let frames = framing::split(packet).context("packet is too big")?;
 
    let peers_in_room = state.rooms.peers_in_room(room_id);
    let mut result = Ok(());
@@ -217,9 +327,12 @@ async fn broadcast_packet(
       for peer_id in iter {
          if peer_id != sender_id {
             if let Some(stream) = state.peers.peer_sinks.get(&peer_id) {
-               match stream.lock().await.send(Message::Binary(packet.to_owned())).await {
-                  Ok(()) => (),
-                  Err(error) => result = Err(error),
+               let mut stream = stream.lock().await;
+               for frame in &frames {
+                  if let Err(error) = stream.send(Message::Binary(frame.to_owned())).await {
+                     result = Err(error);
+                     break;
+                  }
                }
             }
          }
@@ -249,11 +362,21 @@ async fn host(
 
    state.rooms.make_host(room_id, peer_id);
    state.rooms.join_room(peer_id, room_id);
+   record_peer(peer_id, room_id);
+   tracing::info!("hosting a new room");
    send_packet(write, Packet::RoomCreated(room_id, peer_id)).await?;
 
    Ok(())
 }
 
+/// Records the peer's ID and room in the current connection's span, so that they're included in
+/// all messages logged from the connection.
+fn record_peer(peer_id: PeerId, room_id: RoomId) {
+   let span = tracing::Span::current();
+   span.record("peer", display(peer_id));
+   span.record("room", display(room_id));
+}
+
 async fn join(
    write: &Arc<Mutex<Sink>>,
    address: SocketAddr,
@@ -274,7 +397,14 @@ async fn join(
       anyhow::bail!("no room with the given ID");
    };
 
+   if state.rooms.is_full(room_id) {
+      send_packet(write, Packet::Error(relay::Error::RoomFull)).await?;
+      anyhow::bail!("the room is full");
+   }
+
    state.rooms.join_room(peer_id, room_id);
+   record_peer(peer_id, room_id);
+   tracing::info!(host = %host_id, "joined a room");
    send_packet(write, Packet::Joined { peer_id, host_id }).await?;
 
    Ok(())
@@ -293,6 +423,16 @@ async fn relay(
    let room_id =
       state.rooms.room_id(sender_id).ok_or_else(|| anyhow::anyhow!("peer is not in a room"))?;
 
+   state.rooms.mark_active(room_id);
+   state.relayed_bytes += data.len() as u64;
+   tracing::trace!(
+      room = %room_id,
+      from = %sender_id,
+      to = %target_id,
+      bytes = data.len(),
+      "relaying packet"
+   );
+
    let packet = Packet::Relayed(sender_id, data);
    if target_id.is_broadcast() {
       broadcast_packet(state, room_id, sender_id, packet).await?;
@@ -339,14 +479,14 @@ async fn read_packets(
    address: SocketAddr,
    state: &Mutex<State>,
 ) -> anyhow::Result<()> {
+   let mut reassembler = Reassembler::new();
    while let Some(message) = read.next().await {
       match message {
          Ok(Message::Binary(buffer)) => {
-            if buffer.len() > relay::MAX_PACKET_SIZE as usize {
-               anyhow::bail!("packet is too big");
+            if let Some(buffer) = reassembler.push(buffer)? {
+               let packet = bincode::deserialize(&buffer)?;
+               handle_packet(&write, address, state, packet).await?;
             }
-            let packet = bincode::deserialize(&buffer)?;
-            handle_packet(&write, address, state, packet).await?;
          }
          Ok(Message::Close(frame)) => {
             if let Some(frame) = frame {
@@ -354,7 +494,15 @@ async fn read_packets(
                return Ok(());
             }
          }
-         Ok(Message::Pong(_)) => {}
+         Ok(Message::Pong(_)) => {
+            // A peer answering pings is still connected, so their room must not be removed even
+            // if nobody's drawing anything.
+            let mut state = state.lock().await;
+            let room_id = state.peers.peer_id(address).and_then(|id| state.rooms.room_id(id));
+            if let Some(room_id) = room_id {
+               state.rooms.mark_active(room_id);
+            }
+         }
          Ok(_) => tracing::info!("got ignored message"),
          Err(e) => {
             use tungstenite::Error::*;
@@ -382,8 +530,12 @@ async fn read_packets(
 async fn transfer_host(state: &mut State, room_id: RoomId) -> anyhow::Result<()> {
    // If we get here, the room can't have been deleted, and because of that, there's at least
    // one person still in the room.
+   // Peers are stored in the order they joined, so the host role goes to the peer that's been in
+   // the room the longest, who is the most likely to have downloaded most of the canvas. The rest
+   // of the peers upload the chunks the new host is missing once they receive the transfer.
    let new_host_id = state.rooms.peers_in_room(room_id).unwrap().next().unwrap();
    state.rooms.make_host(room_id, new_host_id);
+   tracing::info!(room = %room_id, host = %new_host_id, "transferring the host role");
    broadcast_packet(
       state,
       room_id,
@@ -405,12 +557,96 @@ async fn ping_loop(write: Arc<Mutex<Sink>>) -> anyhow::Result<()> {
    }
 }
 
+/// Periodically removes rooms that have been idle for longer than the given timeout.
+async fn remove_idle_rooms_loop(state: Arc<Mutex<State>>, timeout: Duration) {
+   const CHECK_PERIOD: Duration = Duration::from_secs(60);
+   let mut interval = tokio::time::interval(CHECK_PERIOD);
+   loop {
+      interval.tick().await;
+      // Nobody is left in idle rooms to be notified, so the rooms are simply removed.
+      let removed = state.lock().await.rooms.remove_idle_rooms(timeout);
+      for room_id in removed {
+         tracing::info!(room = %room_id, "removed idle room");
+      }
+   }
+}
+
+/// Responds to a single HTTP request for the relay's metrics or health.
+///
+/// This is deliberately minimal: only the request line is looked at, and the connection is closed
+/// after the response is sent.
+async fn handle_metrics_request(mut stream: TcpStream, state: &Mutex<State>) -> anyhow::Result<()> {
+   const MAX_REQUEST_SIZE: usize = 8 * 1024;
+   const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+   let mut request = Vec::new();
+   tokio::time::timeout(READ_TIMEOUT, async {
+      let mut buffer = [0; 1024];
+      while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+         let count = stream.read(&mut buffer).await?;
+         if count == 0 {
+            anyhow::bail!("connection closed before the request was complete");
+         }
+         request.extend_from_slice(&buffer[..count]);
+         if request.len() > MAX_REQUEST_SIZE {
+            anyhow::bail!("request is too big");
+         }
+      }
+      Ok::<_, anyhow::Error>(())
+   })
+   .await
+   .context("timed out while reading the request")??;
+
+   let request = String::from_utf8_lossy(&request);
+   let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+   let (status, body) = match (request_line.next(), request_line.next()) {
+      (Some("GET"), Some("/metrics")) => ("200 OK", state.lock().await.metrics()),
+      (Some("GET"), Some("/health")) => ("200 OK", "OK\n".to_owned()),
+      (Some("GET"), _) => ("404 Not Found", "Not Found\n".to_owned()),
+      _ => ("405 Method Not Allowed", "Method Not Allowed\n".to_owned()),
+   };
+   let response = format!(
+      "HTTP/1.1 {status}\r\n\
+       Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+       Content-Length: {}\r\n\
+       Connection: close\r\n\
+       \r\n\
+       {body}",
+      body.len()
+   );
+   stream.write_all(response.as_bytes()).await?;
+   stream.shutdown().await?;
+   Ok(())
+}
+
+/// Serves the relay's metrics over HTTP.
+async fn serve_metrics(listener: TcpListener, state: Arc<Mutex<State>>) {
+   loop {
+      let (stream, address) = match listener.accept().await {
+         Ok(connection) => connection,
+         Err(error) => {
+            tracing::error!("metrics: cannot accept connection: {}", error);
+            continue;
+         }
+      };
+      let state = Arc::clone(&state);
+      tokio::spawn(
+         async move {
+            if let Err(error) = handle_metrics_request(stream, &state).await {
+               tracing::warn!("metrics request error: {}", error);
+            }
+         }
+         .instrument(tracing::info_span!("metrics", %address)),
+      );
+   }
+}
+
 async fn handle_connection(
    stream: TcpStream,
    address: SocketAddr,
    state: Arc<Mutex<State>>,
 ) -> anyhow::Result<()> {
-   tracing::info!("{} has connected", address);
+   tracing::info!("connected");
    stream.set_nodelay(true)?;
 
    let (mut write, read) = {
@@ -424,22 +660,25 @@ async fn handle_connection(
 
    let pinger = {
       let write = Arc::clone(&write);
-      tokio::spawn(async move {
-         if let Err(error) = ping_loop(write).await {
-            tracing::error!("[{}] ping loop: {}", address, error);
+      tokio::spawn(
+         async move {
+            if let Err(error) = ping_loop(write).await {
+               tracing::error!("ping loop: {}", error);
+            }
          }
-      })
+         .in_current_span(),
+      )
    };
 
    match read_packets(read, write, address, &state).await {
       Ok(()) => (),
-      Err(error) => tracing::error!("[{}] connection error: {}", address, error),
+      Err(error) => tracing::error!("connection error: {}", error),
    }
 
    // Abort the pinger if it hasn't already exited.
    pinger.abort();
 
-   tracing::info!("tearing down {}'s connection", address);
+   tracing::info!("tearing down the connection");
    {
       let mut state = state.lock().await;
       let peer_id =
@@ -466,24 +705,28 @@ async fn handle_connection(
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+   let options = Options::from_args();
+
    let subscriber = FmtSubscriber::builder()
       .with_env_filter(
          EnvFilter::builder()
-            .with_default_directive(LevelFilter::INFO.into())
+            .with_default_directive(options.log_level.into())
             .with_env_var("NETCANV_LOG")
             .from_env_lossy(),
       )
       .finish();
    tracing::subscriber::set_global_default(subscriber)?;
 
-   let options = Options::from_args();
-
    let listener = TcpListener::bind((
       Ipv4Addr::from([0, 0, 0, 0]),
       options.port.unwrap_or(DEFAULT_PORT),
    ))
    .await?;
-   let state = Arc::new(Mutex::new(State::new()));
+   let state = Arc::new(Mutex::new(State::new(options.max_peers_per_room)));
+   tokio::spawn(remove_idle_rooms_loop(
+      Arc::clone(&state),
+      Duration::from_secs(options.idle_room_timeout * 60),
+   ));
 
    tracing::info!(
       "NetCanv Relay server {} (protocol version {})",
@@ -492,9 +735,92 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
    );
    tracing::info!("listening on {}", listener.local_addr()?);
 
+   if let Some(metrics_port) = options.metrics_port {
+      let metrics_listener =
+         TcpListener::bind((Ipv4Addr::from([0, 0, 0, 0]), metrics_port)).await?;
+      tracing::info!("serving metrics on {}", metrics_listener.local_addr()?);
+      tokio::spawn(serve_metrics(metrics_listener, Arc::clone(&state)));
+   }
+
    loop {
       let (socket, address) = listener.accept().await?;
       let state = Arc::clone(&state);
-      tokio::spawn(async move { handle_connection(socket, address, state).await });
+      // The peer and room are filled in once the peer hosts or joins a room.
+      let span = tracing::info_span!("connection", %address, peer = Empty, room = Empty);
+      tokio::spawn(async move { handle_connection(socket, address, state).await }.instrument(span));
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn joining_a_full_room_is_refused() {
+      const MAX_PEERS: usize = 4;
+
+      let mut state = State::new(Some(MAX_PEERS));
+      let room_id = state.rooms.find_room_id().unwrap();
+      let host_id = PeerId(PeerId::FIRST_PEER);
+      state.rooms.make_host(room_id, host_id);
+      state.rooms.join_room(host_id, room_id);
+
+      // The host counts towards the limit, so only `MAX_PEERS - 1` other peers can join.
+      for i in 1..MAX_PEERS as u64 {
+         assert!(!state.rooms.is_full(room_id));
+         state.rooms.join_room(PeerId(PeerId::FIRST_PEER + i), room_id);
+      }
+      assert!(state.rooms.is_full(room_id));
+
+      // Once somebody leaves, there's room for another peer.
+      state.rooms.quit_room(host_id);
+      assert!(!state.rooms.is_full(room_id));
+   }
+
+   #[test]
+   fn only_idle_rooms_are_removed() {
+      const TIMEOUT: Duration = Duration::from_millis(50);
+
+      let mut state = State::new(None);
+      let idle_room_id = state.rooms.find_room_id().unwrap();
+      let active_room_id = state.rooms.find_room_id().unwrap();
+      let idle_peer_id = PeerId(PeerId::FIRST_PEER);
+      state.rooms.make_host(idle_room_id, idle_peer_id);
+      state.rooms.join_room(idle_peer_id, idle_room_id);
+
+      std::thread::sleep(TIMEOUT * 2);
+      state.rooms.mark_active(active_room_id);
+
+      assert_eq!(state.rooms.remove_idle_rooms(TIMEOUT), vec![idle_room_id]);
+      assert!(!state.rooms.occupied_room_ids.contains(&idle_room_id));
+      assert!(state.rooms.occupied_room_ids.contains(&active_room_id));
+      assert_eq!(state.rooms.room_id(idle_peer_id), None);
+      assert_eq!(state.rooms.host_id(idle_room_id), None);
+   }
+
+   #[test]
+   fn metrics_count_rooms_and_peers() {
+      let mut state = State::new(None);
+      let room_id = state.rooms.find_room_id().unwrap();
+      let peer_id = PeerId(PeerId::FIRST_PEER);
+      // Allocating a peer ID properly requires a WebSocket sink, so the ID is occupied by hand.
+      state.peers.occupied_peer_ids.insert(peer_id);
+      state.rooms.join_room(peer_id, room_id);
+      state.relayed_bytes = 1234;
+
+      let metrics = state.metrics();
+      assert!(metrics.contains("\nnetcanv_relay_rooms 1\n"));
+      assert!(metrics.contains("\nnetcanv_relay_peers 1\n"));
+      assert!(metrics.contains("\nnetcanv_relay_relayed_bytes_total 1234\n"));
+   }
+
+   #[test]
+   fn rooms_are_unlimited_by_default() {
+      let mut state = State::new(None);
+      let room_id = state.rooms.find_room_id().unwrap();
+      for i in 0..100 {
+         state.rooms.join_room(PeerId(PeerId::FIRST_PEER + i), room_id);
+      }
+      assert!(!state.rooms.is_full(room_id));
    }
 }
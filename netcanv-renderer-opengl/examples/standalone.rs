@@ -0,0 +1,77 @@
+//! A minimal application that uses the OpenGL backend on its own, without the rest of NetCanv.
+
+use netcanv_renderer::paws::{point, vector, AlignH, AlignV, Color, Layout, Rect, Renderer, Ui};
+use netcanv_renderer::{Framebuffer as _, RenderBackend};
+use netcanv_renderer_opengl::winit::dpi::PhysicalSize;
+use netcanv_renderer_opengl::winit::event::{Event, WindowEvent};
+use netcanv_renderer_opengl::winit::event_loop::{ControlFlow, EventLoop};
+use netcanv_renderer_opengl::winit::window::WindowBuilder;
+use netcanv_renderer_opengl::{OpenGlBackend, UiRenderFrame};
+
+fn main() -> anyhow::Result<()> {
+   let event_loop = EventLoop::new();
+   let window = WindowBuilder::new()
+      .with_title("netcanv-renderer")
+      .with_inner_size(PhysicalSize::new(640, 480))
+      .build(&event_loop)?;
+
+   let mut ui = Ui::new(OpenGlBackend::from_window(window, &event_loop)?);
+
+   // Resources are created through the RenderBackend trait.
+   let font = ui
+      .create_font_from_memory(
+         include_bytes!("../../src/assets/fonts/Barlow-Medium.ttf"),
+         24.0,
+      )
+      .expect("the bundled font should be valid");
+   let checkerboard: Vec<u8> = (0..16 * 16)
+      .flat_map(|i| {
+         let (x, y) = (i % 16, i / 16);
+         if (x + y) % 2 == 0 {
+            [255, 255, 255, 255]
+         } else {
+            [0, 0, 0, 255]
+         }
+      })
+      .collect();
+   let image = ui.create_image_from_rgba(16, 16, &checkerboard);
+   let framebuffer = ui.create_framebuffer(128, 128);
+   ui.draw_to(&framebuffer, |renderer| {
+      renderer.clear(Color::rgb(0x3366ff));
+      renderer.fill_circle(point(64.0, 64.0), 48.0, Color::WHITE);
+   });
+
+   event_loop.run(move |event, _, control_flow| {
+      *control_flow = ControlFlow::Poll;
+      match event {
+         Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+         } => *control_flow = ControlFlow::Exit,
+         Event::MainEventsCleared => {
+            let window_size = ui.window().inner_size();
+            let result = ui.render_frame(|ui| {
+               ui.root(
+                  vector(window_size.width as f32, window_size.height as f32),
+                  Layout::Freeform,
+               );
+               let renderer = ui.render();
+               renderer.clear(Color::rgb(0xeeeeee));
+               renderer.image(Rect::new(point(16.0, 16.0), vector(128.0, 128.0)), &image);
+               renderer.framebuffer(framebuffer.rect(point(160.0, 16.0)), &framebuffer);
+               renderer.text(
+                  Rect::new(point(16.0, 160.0), vector(272.0, 32.0)),
+                  &font,
+                  "Hello from netcanv-renderer!",
+                  Color::BLACK,
+                  (AlignH::Left, AlignV::Middle),
+               );
+            });
+            if let Err(error) = result {
+               eprintln!("render error: {error}");
+            }
+         }
+         _ => (),
+      }
+   });
+}
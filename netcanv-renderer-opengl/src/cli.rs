@@ -1,2 +1,38 @@
+use anyhow::bail;
+
 #[derive(clap::Args)]
-pub struct RendererCli {}
+pub struct RendererCli {
+   /// The number of samples per pixel used for antialiasing the interface. Available levels:
+   /// 0 (off), 2, 4, 8
+   ///
+   /// If the graphics driver does not support the requested level, the closest lower level is
+   /// used instead.
+   #[clap(
+      long,
+      default_value_t = crate::OpenGlBackend::DEFAULT_SAMPLE_COUNT,
+      value_parser = msaa_from_str
+   )]
+   pub msaa: u8,
+}
+
+fn msaa_from_str(s: &str) -> anyhow::Result<u8> {
+   match s {
+      "0" | "2" | "4" | "8" => Ok(s.parse()?),
+      _ => bail!("invalid MSAA level. available levels: 0, 2, 4, 8"),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn only_supported_msaa_levels_are_accepted() {
+      for level in [0, 2, 4, 8] {
+         assert_eq!(msaa_from_str(&level.to_string()).unwrap(), level);
+      }
+      for invalid in ["1", "3", "16", "-2", "", "four"] {
+         assert!(msaa_from_str(invalid).is_err(), "{invalid:?} was accepted");
+      }
+   }
+}
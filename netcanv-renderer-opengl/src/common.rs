@@ -1,6 +1,6 @@
 use glam::Vec2;
 use glow::HasContext;
-use netcanv_renderer::paws::{vector, Color, Rect, Vector};
+use netcanv_renderer::paws::{point, vector, Color, Rect, Vector};
 
 pub fn normalized_color(color: Color) -> (f32, f32, f32, f32) {
    (
@@ -38,12 +38,24 @@ impl VectorMath for Vector {
 
 pub trait RectMath {
    fn uv(self, texture_size: Vector) -> Self;
+   /// Returns the area covered by both rectangles. If they don't overlap, the resulting rectangle
+   /// is empty.
+   fn intersect(self, other: Self) -> Self;
 }
 
 impl RectMath for Rect {
    fn uv(self, texture_size: Vector) -> Self {
       Rect::new(self.position / texture_size, self.size / texture_size)
    }
+
+   fn intersect(self, other: Self) -> Self {
+      let (a, b) = (self.sort(), other.sort());
+      let left = a.left().max(b.left());
+      let top = a.top().max(b.top());
+      let right = a.right().min(b.right()).max(left);
+      let bottom = a.bottom().min(b.bottom()).max(top);
+      Rect::new(point(left, top), vector(right - left, bottom - top))
+   }
 }
 
 pub trait GlUtilities {
@@ -172,17 +172,18 @@ impl Drop for FontFace {
    }
 }
 
+/// A font, rendered by rasterizing glyphs into a texture atlas.
 pub struct Font {
    store: Rc<RefCell<FontFace>>,
    size: u32,
 }
 
 impl Font {
-   pub(crate) fn new(gl: Rc<glow::Context>, data: &[u8], default_size: f32) -> Self {
-      Self {
-         store: Rc::new(RefCell::new(FontFace::new(gl, data.into()).unwrap())),
+   pub(crate) fn new(gl: Rc<glow::Context>, data: &[u8], default_size: f32) -> Option<Self> {
+      Some(Self {
+         store: Rc::new(RefCell::new(FontFace::new(gl, data.into())?)),
          size: default_size as u32,
-      }
+      })
    }
 
    pub(crate) fn atlas(&self) -> glow::Texture {
@@ -1,12 +1,13 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use glow::{HasContext, PixelPackData, PixelUnpackData};
-use netcanv_renderer::ScalingFilter;
+use netcanv_renderer::{alpha, ScalingFilter};
 
 use crate::common::flip_vertically;
 use crate::rendering::GlState;
 
+/// An off-screen render target, backed by an OpenGL texture.
 pub struct Framebuffer {
    gl: Rc<glow::Context>,
    framebuffer: glow::Framebuffer,
@@ -14,6 +15,11 @@ pub struct Framebuffer {
    width: u32,
    height: u32,
    gl_state: Rc<RefCell<GlState>>,
+   filter: ScalingFilter,
+   mipmapping: bool,
+   // Set whenever the framebuffer's contents change, such that mipmaps are only regenerated when
+   // the framebuffer is actually drawn.
+   mipmaps_outdated: Cell<bool>,
 }
 
 impl Framebuffer {
@@ -83,6 +89,9 @@ impl Framebuffer {
          framebuffer,
          width,
          height,
+         filter: ScalingFilter::Nearest,
+         mipmapping: false,
+         mipmaps_outdated: Cell::new(false),
       }
    }
 
@@ -94,6 +103,54 @@ impl Framebuffer {
       self.texture
    }
 
+   /// Marks the framebuffer's contents as modified, such that its mipmaps get regenerated the next
+   /// time it's drawn.
+   pub(crate) fn mark_modified(&self) {
+      if self.mipmapping {
+         self.mipmaps_outdated.set(true);
+      }
+   }
+
+   /// Regenerates the framebuffer's mipmaps, if they're outdated.
+   ///
+   /// Note that this leaves the framebuffer's texture bound to the current texture unit.
+   pub(crate) fn update_mipmaps(&self) {
+      if self.mipmaps_outdated.replace(false) {
+         unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.generate_mipmap(glow::TEXTURE_2D);
+         }
+      }
+   }
+
+   /// Applies the scaling filter and mipmapping settings to the texture.
+   fn update_filters(&self) {
+      let mag_filter = match self.filter {
+         ScalingFilter::Nearest => glow::NEAREST,
+         ScalingFilter::Linear => glow::LINEAR,
+      };
+      // Regardless of the scaling filter, mipmapped framebuffers are always minified using
+      // trilinear filtering, as that's the whole point of mipmaps.
+      let min_filter = if self.mipmapping {
+         glow::LINEAR_MIPMAP_LINEAR
+      } else {
+         mag_filter
+      };
+      unsafe {
+         self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+         self.gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MIN_FILTER,
+            min_filter as i32,
+         );
+         self.gl.tex_parameter_i32(
+            glow::TEXTURE_2D,
+            glow::TEXTURE_MAG_FILTER,
+            mag_filter as i32,
+         );
+      }
+   }
+
    pub(crate) fn upload_rgba(
       &self,
       (x, mut y): (u32, u32),
@@ -102,6 +159,7 @@ impl Framebuffer {
    ) {
       let mut flipped = pixels.to_owned();
       flip_vertically(width as usize, height as usize, 4, &mut flipped);
+      alpha::premultiply(&mut flipped);
       y = self.height - y - height;
       unsafe {
          self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
@@ -117,6 +175,37 @@ impl Framebuffer {
             PixelUnpackData::Slice(&flipped),
          );
       }
+      self.mark_modified();
+   }
+
+   /// Copies a region of `source` into this framebuffer.
+   pub(crate) fn copy_from(
+      &self,
+      source: &Framebuffer,
+      (source_x, source_y): (u32, u32),
+      (width, height): (u32, u32),
+      (x, y): (u32, u32),
+   ) {
+      // Both textures are stored upside down, so the Y coordinates have to be flipped.
+      let source_y = source.height - source_y - height;
+      let y = self.height - y - height;
+      unsafe {
+         let mut gl_state = self.gl_state.borrow_mut();
+         let previous_framebuffer = gl_state.framebuffer(&self.gl, Some(source.framebuffer));
+         self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+         self.gl.copy_tex_sub_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            x as i32,
+            y as i32,
+            source_x as i32,
+            source_y as i32,
+            width as i32,
+            height as i32,
+         );
+         gl_state.framebuffer(&self.gl, previous_framebuffer);
+      }
+      self.mark_modified();
    }
 
    pub(crate) fn download_rgba(
@@ -146,6 +235,7 @@ impl Framebuffer {
       }
       // Fleeeeeeeeeeep them 'round.
       flip_vertically(width as usize, height as usize, 4, dest);
+      alpha::unpremultiply(dest);
    }
 
    pub(crate) fn download_rgba_scaled(
@@ -200,15 +290,14 @@ impl netcanv_renderer::Framebuffer for Framebuffer {
    }
 
    fn set_scaling_filter(&mut self, filter: ScalingFilter) {
-      unsafe {
-         self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
-         let filter = match filter {
-            ScalingFilter::Nearest => glow::NEAREST,
-            ScalingFilter::Linear => glow::LINEAR,
-         } as i32;
-         self.gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, filter);
-         self.gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, filter);
-      }
+      self.filter = filter;
+      self.update_filters();
+   }
+
+   fn set_mipmapping(&mut self, enabled: bool) {
+      self.mipmapping = enabled;
+      self.mipmaps_outdated.set(enabled);
+      self.update_filters();
    }
 }
 
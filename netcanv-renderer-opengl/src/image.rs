@@ -2,6 +2,7 @@ use std::rc::Rc;
 
 use glow::HasContext;
 use netcanv_renderer::paws::Color;
+use netcanv_renderer::ScalingFilter;
 
 pub(crate) struct TextureHandle {
    gl: Rc<glow::Context>,
@@ -16,11 +17,13 @@ impl Drop for TextureHandle {
    }
 }
 
+/// An image stored in an OpenGL texture.
 pub struct Image {
    pub(crate) texture: Rc<TextureHandle>,
    width: u32,
    height: u32,
    pub(crate) color: Option<Color>,
+   pub(crate) filter: ScalingFilter,
 }
 
 impl Image {
@@ -44,16 +47,6 @@ impl Image {
             glow::UNSIGNED_BYTE,
             Some(pixel_data),
          );
-         gl.tex_parameter_i32(
-            glow::TEXTURE_2D,
-            glow::TEXTURE_MIN_FILTER,
-            glow::LINEAR as i32,
-         );
-         gl.tex_parameter_i32(
-            glow::TEXTURE_2D,
-            glow::TEXTURE_MAG_FILTER,
-            glow::LINEAR as i32,
-         );
          gl.tex_parameter_i32(
             glow::TEXTURE_2D,
             glow::TEXTURE_WRAP_S,
@@ -70,6 +63,7 @@ impl Image {
             width,
             height,
             color: None,
+            filter: ScalingFilter::Linear,
          }
       }
    }
@@ -82,10 +76,15 @@ impl netcanv_renderer::Image for Image {
          width: self.width,
          height: self.height,
          color: Some(color),
+         filter: self.filter,
       }
    }
 
    fn size(&self) -> (u32, u32) {
       (self.width, self.height)
    }
+
+   fn set_scaling_filter(&mut self, filter: ScalingFilter) {
+      self.filter = filter;
+   }
 }
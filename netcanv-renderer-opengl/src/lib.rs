@@ -1,3 +1,11 @@
+//! An OpenGL render backend for `netcanv-renderer`.
+//!
+//! The backend renders onto a single [`winit`] window. It can either create the window itself,
+//! using [`OpenGlBackend::new`], or render onto an existing window, using
+//! [`OpenGlBackend::from_window`].
+//!
+//! See `examples/standalone.rs` for a minimal application using the backend outside of NetCanv.
+
 pub mod cli;
 mod common;
 mod font;
@@ -7,6 +15,7 @@ mod rect_packer;
 mod rendering;
 mod shape_buffer;
 
+use std::error::Error;
 use std::ffi::CString;
 use std::num::NonZeroU32;
 use std::rc::Rc;
@@ -24,7 +33,7 @@ use netcanv_renderer::paws::Ui;
 use raw_window_handle::HasRawWindowHandle;
 pub use winit;
 use winit::dpi::PhysicalSize;
-use winit::event_loop::EventLoop;
+use winit::event_loop::EventLoopWindowTarget;
 use winit::window::{Window, WindowBuilder};
 
 pub use crate::font::Font;
@@ -32,6 +41,10 @@ pub use crate::framebuffer::Framebuffer;
 pub use crate::image::Image;
 use rendering::RenderState;
 
+/// The OpenGL render backend.
+///
+/// Implements [`RenderBackend`][netcanv_renderer::RenderBackend], and is usually wrapped in a
+/// [`Ui`], whose frames are rendered using [`UiRenderFrame::render_frame`].
 pub struct OpenGlBackend {
    context: PossiblyCurrentContext,
    surface: Surface<WindowSurface>,
@@ -42,33 +55,65 @@ pub struct OpenGlBackend {
 }
 
 impl OpenGlBackend {
-   fn build_context(
-      window_builder: WindowBuilder,
-      event_loop: &EventLoop<()>,
-   ) -> anyhow::Result<(NotCurrentContext, Config, Window)> {
-      let template = ConfigTemplateBuilder::new().with_multisampling(8);
+   /// The number of samples per pixel requested for multisampling, unless specified otherwise.
+   pub const DEFAULT_SAMPLE_COUNT: u8 = 8;
 
-      // Passing window_builder is required by Windows.
-      // On Android, it should be passed later, but because we don't care about Android, we can take
-      // a shortcut.
-      let display_builder = DisplayBuilder::new().with_window_builder(Some(window_builder));
-
-      let (window, gl_config) = display_builder
-         .build(event_loop, template, |configs| {
-            configs
-               .reduce(|accum, config| {
-                  // Find the config with the maximum number of samples
-                  if config.num_samples() > accum.num_samples() {
-                     config
-                  } else {
-                     accum
-                  }
-               })
-               .unwrap()
+   /// Picks the config with the minimum number of samples.
+   ///
+   /// The config template already asks for at least the requested number of samples, so this
+   /// picks the config that's closest to the requested level.
+   fn pick_config(configs: Box<dyn Iterator<Item = Config> + '_>) -> Config {
+      configs
+         .reduce(|accum, config| {
+            if config.num_samples() < accum.num_samples() {
+               config
+            } else {
+               accum
+            }
          })
-         .map_err(|_| anyhow::anyhow!("Failed to create OpenGL window"))?;
+         .unwrap()
+   }
 
-      let raw_window_handle = window.as_ref().map(|window| window.raw_window_handle());
+   /// Builds a display and picks a config with the requested number of samples per pixel.
+   ///
+   /// If the requested level is not supported, lower levels are tried until one succeeds, and
+   /// a warning is logged. The error from the last attempt is returned if none do.
+   fn build_display<T>(
+      event_loop: &EventLoopWindowTarget<T>,
+      requested_sample_count: u8,
+      display_builder: impl Fn() -> DisplayBuilder,
+      template: impl Fn(ConfigTemplateBuilder) -> ConfigTemplateBuilder,
+   ) -> Result<(Option<Window>, Config), Box<dyn Error>> {
+      let mut sample_count = requested_sample_count;
+      loop {
+         let mut template_builder = ConfigTemplateBuilder::new();
+         if sample_count > 0 {
+            template_builder = template_builder.with_multisampling(sample_count);
+         }
+         match display_builder().build(event_loop, template(template_builder), Self::pick_config) {
+            Ok(result) => {
+               if sample_count != requested_sample_count {
+                  tracing::warn!(
+                     "{}x MSAA is not supported by the graphics driver, falling back to {}x",
+                     requested_sample_count,
+                     sample_count,
+                  );
+               }
+               return Ok(result);
+            }
+            Err(error) if sample_count == 0 => return Err(error),
+            Err(_) => sample_count /= 2,
+         }
+         // There's no such thing as 1x MSAA.
+         if sample_count == 1 {
+            sample_count = 0;
+         }
+      }
+   }
+
+   /// Creates an OpenGL context for the given window.
+   fn create_context(window: &Window, gl_config: &Config) -> anyhow::Result<NotCurrentContext> {
+      let raw_window_handle = Some(window.raw_window_handle());
 
       let gl_display = gl_config.display();
 
@@ -82,26 +127,45 @@ impl OpenGlBackend {
 
       let not_current_gl_context = unsafe {
          gl_display
-            .create_context(&gl_config, &context_attributes)
-            .or_else(|_| gl_display.create_context(&gl_config, &fallback_context_attributes))
+            .create_context(gl_config, &context_attributes)
+            .or_else(|_| gl_display.create_context(gl_config, &fallback_context_attributes))
       }?;
 
+      Ok(not_current_gl_context)
+   }
+
+   fn build_context<T>(
+      window_builder: WindowBuilder,
+      event_loop: &EventLoopWindowTarget<T>,
+      sample_count: u8,
+   ) -> anyhow::Result<(NotCurrentContext, Config, Window)> {
+      // Passing window_builder is required by Windows.
+      // On Android, it should be passed later, but because we don't care about Android, we can take
+      // a shortcut.
+      let (window, gl_config) = Self::build_display(
+         event_loop,
+         sample_count,
+         || DisplayBuilder::new().with_window_builder(Some(window_builder.clone())),
+         |template| template,
+      )
+      .map_err(|_| anyhow::anyhow!("Failed to create OpenGL window"))?;
+
       let window = window.ok_or(anyhow::anyhow!("Failed to create OpenGL window"))?;
+      let context = Self::create_context(&window, &gl_config)?;
 
-      Ok((not_current_gl_context, gl_config, window))
+      Ok((context, gl_config, window))
    }
 
-   /// Creates a new OpenGL renderer.
-   pub async fn new(
-      window_builder: WindowBuilder,
-      event_loop: &EventLoop<()>,
-      _: &RendererCli,
+   /// Makes the context current on a new surface for the window, and loads OpenGL functions.
+   fn from_context(
+      context: NotCurrentContext,
+      gl_config: &Config,
+      window: Window,
    ) -> anyhow::Result<Self> {
-      let (context, gl_config, window) = Self::build_context(window_builder, event_loop)?;
       let window_size = window.inner_size();
 
       let attributes = window.build_surface_attributes(<_>::default());
-      let surface = unsafe { gl_config.display().create_window_surface(&gl_config, &attributes) }?;
+      let surface = unsafe { gl_config.display().create_window_surface(gl_config, &attributes) }?;
 
       let context = context.make_current(&surface)?;
 
@@ -111,6 +175,16 @@ impl OpenGlBackend {
             gl_config.display().get_proc_address(&name) as *const _
          })
       };
+      unsafe {
+         use glow::HasContext;
+         tracing::info!(
+            "using OpenGL renderer: {} ({}), version {}",
+            gl.get_parameter_string(glow::RENDERER),
+            gl.get_parameter_string(glow::VENDOR),
+            gl.get_parameter_string(glow::VERSION),
+         );
+      }
+      tracing::info!("using {} sample(s) per pixel", gl_config.num_samples());
       let gl = Rc::new(gl);
       Ok(Self {
          context,
@@ -122,6 +196,37 @@ impl OpenGlBackend {
       })
    }
 
+   /// Creates a new OpenGL renderer, along with a window built from the given builder.
+   pub async fn new<T>(
+      window_builder: WindowBuilder,
+      event_loop: &EventLoopWindowTarget<T>,
+      cli: &RendererCli,
+   ) -> anyhow::Result<Self> {
+      let (context, gl_config, window) = Self::build_context(window_builder, event_loop, cli.msaa)?;
+      Self::from_context(context, &gl_config, window)
+   }
+
+   /// Creates a new OpenGL renderer that renders onto an existing window.
+   ///
+   /// The window must not be used with any other graphics API. Note that on some platforms
+   /// (notably Windows), an existing window may not support the pixel formats that
+   /// [`OpenGlBackend::new`] would pick, in which case a config compatible with the window is used.
+   /// [`OpenGlBackend::DEFAULT_SAMPLE_COUNT`] samples per pixel are requested for multisampling.
+   pub fn from_window<T>(
+      window: Window,
+      event_loop: &EventLoopWindowTarget<T>,
+   ) -> anyhow::Result<Self> {
+      let (_, gl_config) = Self::build_display(
+         event_loop,
+         Self::DEFAULT_SAMPLE_COUNT,
+         DisplayBuilder::new,
+         |template| template.compatible_with_native_window(window.raw_window_handle()),
+      )
+      .map_err(|_| anyhow::anyhow!("Failed to create OpenGL display"))?;
+      let context = Self::create_context(&window, &gl_config)?;
+      Self::from_context(context, &gl_config, window)
+   }
+
    /// Returns the window.
    pub fn window(&self) -> &Window {
       &self.window
@@ -145,6 +250,7 @@ impl OpenGlBackend {
    }
 }
 
+/// Rendering frames onto the backend's window.
 pub trait UiRenderFrame {
    /// Renders a single frame onto the window.
    fn render_frame(&mut self, callback: impl FnOnce(&mut Self)) -> anyhow::Result<()>;
@@ -14,10 +14,10 @@ use netcanv_renderer::paws::{
    point, vector, AlignH, AlignV, Alignment, Color, LineCap, Point, Rect, Renderer, Vector,
 };
 use netcanv_renderer::{
-   BlendMode, Font as FontTrait, Framebuffer as FramebufferTrait, RenderBackend,
+   BlendMode, Font as FontTrait, Framebuffer as FramebufferTrait, RenderBackend, ScalingFilter,
 };
 
-use crate::common::{normalized_color, to_vec2, GlUtilities, VectorMath};
+use crate::common::{normalized_color, to_vec2, GlUtilities, RectMath, VectorMath};
 use crate::font::Font;
 use crate::framebuffer::Framebuffer;
 use crate::image::Image;
@@ -41,14 +41,6 @@ impl Vertex {
       }
    }
 
-   fn textured(position: Point, uv: Point) -> Self {
-      Self {
-         position,
-         uv,
-         color: (1.0, 1.0, 1.0, 1.0),
-      }
-   }
-
    fn textured_colored(position: Point, uv: Point, color: Color) -> Self {
       Self {
          position,
@@ -61,7 +53,7 @@ impl Vertex {
 struct Uniforms {
    projection: glow::UniformLocation,
    the_texture: glow::UniformLocation,
-   premultiply_alpha: glow::UniformLocation,
+   texture_premultiplied: glow::UniformLocation,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -74,6 +66,8 @@ struct Transform {
 pub(crate) struct GlState {
    framebuffer: Option<glow::Framebuffer>,
    viewport: (u32, u32),
+   /// The factor everything drawn onto the current render target is scaled up by.
+   scale: f32,
 }
 
 impl GlState {
@@ -91,8 +85,15 @@ impl GlState {
       previous_framebuffer
    }
 
-   fn viewport(&mut self, gl: &glow::Context, uniforms: &Uniforms, width: u32, height: u32) {
-      let (fwidth, fheight) = (width as f32, height as f32);
+   fn viewport(
+      &mut self,
+      gl: &glow::Context,
+      uniforms: &Uniforms,
+      width: u32,
+      height: u32,
+      scale: f32,
+   ) {
+      let (fwidth, fheight) = (width as f32 / scale, height as f32 / scale);
       #[rustfmt::skip]
       let matrix: [f32; 3 * 3] = [
          2.0 / fwidth, 0.0,            -1.0,
@@ -105,6 +106,7 @@ impl GlState {
          gl.uniform_matrix_3_f32_slice(Some(&uniforms.projection), false, &matrix);
       }
       self.viewport = (width, height);
+      self.scale = scale;
    }
 }
 
@@ -121,6 +123,8 @@ pub(crate) struct RenderState {
    shape: ShapeBuffer,
    stack: Vec<Transform>,
    gl_state: Rc<RefCell<GlState>>,
+   /// The factor everything drawn onto the window is scaled up by.
+   screen_scale: f32,
 }
 
 impl RenderState {
@@ -215,16 +219,19 @@ impl RenderState {
          in vec4 vertex_color;
 
          uniform sampler2D the_texture;
-         uniform float premultiply_alpha;
+         uniform float texture_premultiplied;
 
          out vec4 fragment_color;
 
          void main(void)
          {
-            vec4 color = vertex_color * texture(the_texture, vertex_uv);
-            float alpha_factor = premultiply_alpha * color.a + (1.0 - premultiply_alpha);
-            color.rgb *= alpha_factor;
-            fragment_color = color;
+            // The output color is always premultiplied. Vertex colors and images use straight
+            // alpha, framebuffers are already premultiplied.
+            vec4 texel = texture(the_texture, vertex_uv);
+            texel.rgb *= mix(texel.a, 1.0, texture_premultiplied);
+            vec4 color = vertex_color;
+            color.rgb *= color.a;
+            fragment_color = color * texel;
          }
       "#;
       unsafe {
@@ -250,10 +257,12 @@ impl RenderState {
          let uniforms = Uniforms {
             projection: gl.get_uniform_location(program, "projection").unwrap(),
             the_texture: gl.get_uniform_location(program, "the_texture").unwrap(),
-            premultiply_alpha: gl.get_uniform_location(program, "premultiply_alpha").unwrap(),
+            texture_premultiplied: gl
+               .get_uniform_location(program, "texture_premultiplied")
+               .unwrap(),
          };
          gl.uniform_1_i32(Some(&uniforms.the_texture), 0);
-         gl.uniform_1_f32(Some(&uniforms.premultiply_alpha), 0.0);
+         gl.uniform_1_f32(Some(&uniforms.texture_premultiplied), 0.0);
 
          (program, uniforms)
       }
@@ -311,7 +320,9 @@ impl RenderState {
          gl_state: Rc::new(RefCell::new(GlState {
             framebuffer: None,
             viewport: (0, 0),
+            scale: 1.0,
          })),
+         screen_scale: 1.0,
       };
       state.apply_transform();
       state
@@ -363,7 +374,13 @@ impl RenderState {
    }
 
    pub(crate) fn viewport(&mut self, width: u32, height: u32) {
-      self.gl_state.borrow_mut().viewport(&self.gl, &self.uniforms, width, height);
+      self.gl_state.borrow_mut().viewport(
+         &self.gl,
+         &self.uniforms,
+         width,
+         height,
+         self.screen_scale,
+      );
    }
 
    fn transform(&self) -> &Transform {
@@ -376,7 +393,8 @@ impl RenderState {
 
    fn apply_transform(&mut self) {
       let transform = self.transform();
-      let mut premultiply_alpha = false;
+      // Colors coming out of the fragment shader are premultiplied, so the source factors don't
+      // include the source alpha.
       match transform.blend_mode {
          BlendMode::Replace => unsafe {
             self.gl.blend_equation(glow::FUNC_ADD);
@@ -384,16 +402,11 @@ impl RenderState {
          },
          BlendMode::Alpha => unsafe {
             self.gl.blend_equation(glow::FUNC_ADD);
-            self.gl.blend_func_separate(
-               glow::SRC_ALPHA,
-               glow::ONE_MINUS_SRC_ALPHA,
-               glow::ONE,
-               glow::ONE_MINUS_SRC_ALPHA,
-            );
+            self.gl.blend_func(glow::ONE, glow::ONE_MINUS_SRC_ALPHA);
          },
          BlendMode::Add => unsafe {
             self.gl.blend_equation(glow::FUNC_ADD);
-            self.gl.blend_func(glow::SRC_ALPHA, glow::ONE);
+            self.gl.blend_func(glow::ONE, glow::ONE);
          },
          BlendMode::Invert => unsafe {
             self.gl.blend_equation(glow::FUNC_ADD);
@@ -403,18 +416,36 @@ impl RenderState {
                glow::ZERO,
                glow::ONE,
             );
-            premultiply_alpha = true;
+         },
+         // The destination's alpha is kept as is, so multiplying onto transparent pixels doesn't
+         // leave black behind.
+         BlendMode::Multiply => unsafe {
+            self.gl.blend_equation(glow::FUNC_ADD);
+            self.gl.blend_func_separate(
+               glow::DST_COLOR,
+               glow::ONE_MINUS_SRC_ALPHA,
+               glow::ZERO,
+               glow::ONE,
+            );
+         },
+         BlendMode::Screen => unsafe {
+            self.gl.blend_equation(glow::FUNC_ADD);
+            self.gl.blend_func_separate(
+               glow::ONE,
+               glow::ONE_MINUS_SRC_COLOR,
+               glow::ONE,
+               glow::ONE_MINUS_SRC_ALPHA,
+            );
          },
       }
       unsafe {
-         self.gl.uniform_1_f32(
-            Some(&self.uniforms.premultiply_alpha),
-            premultiply_alpha as i32 as f32,
-         );
          if let Some(clip_rect) = &transform.clip {
-            let viewport = self.gl_state.borrow().viewport;
-            let top_left = clip_rect.top_left();
-            let bottom_right = clip_rect.bottom_right();
+            let (viewport, scale) = {
+               let gl_state = self.gl_state.borrow();
+               (gl_state.viewport, gl_state.scale)
+            };
+            let top_left = clip_rect.top_left() * scale;
+            let bottom_right = clip_rect.bottom_right() * scale;
             let (width, height) = (bottom_right.x - top_left.x, bottom_right.y - top_left.y);
             let y = viewport.1 as f32 - top_left.y - height;
             self.gl.enable(glow::SCISSOR_TEST);
@@ -496,7 +527,12 @@ impl Renderer for OpenGlBackend {
    }
 
    fn clip(&mut self, rect: Rect) {
-      self.state.transform_mut().clip = Some(rect);
+      // Nested clips can only ever shrink the clipped area.
+      let transform = self.state.transform_mut();
+      transform.clip = Some(match transform.clip {
+         Some(parent) => parent.intersect(rect),
+         None => rect.sort(),
+      });
       self.state.apply_transform();
    }
 
@@ -778,9 +814,15 @@ impl Renderer for OpenGlBackend {
       color: Color,
       alignment: Alignment,
    ) -> f32 {
+      // When the render target is scaled up, glyphs are rasterized at the scaled up size and drawn
+      // scaled down, so that they stay sharp.
+      let scale = self.state.gl_state.borrow().scale;
+      let scaled_font = (scale != 1.0).then(|| font.with_size(font.size() * scale));
+      let raster_font = scaled_font.as_ref().unwrap_or(font);
+
       // Set up textures.
       unsafe {
-         let atlas = font.atlas();
+         let atlas = raster_font.atlas();
          self.gl.active_texture(glow::TEXTURE0);
          self.gl.bind_texture(glow::TEXTURE_2D, Some(atlas));
       }
@@ -788,7 +830,8 @@ impl Renderer for OpenGlBackend {
       // Buffer up the glyphs.
       let origin = text_origin(&rect, font, text, alignment);
       self.start();
-      for (mut position, uv) in font.typeset(text) {
+      for (mut position, uv) in raster_font.typeset(text) {
+         position = Rect::new(position.position / scale, position.size / scale);
          position.position += origin;
          self.shape().rect(
             Vertex::textured_colored(position.top_left(), uv.top_left(), color),
@@ -811,7 +854,7 @@ impl RenderBackend for OpenGlBackend {
       Image::from_rgba(Rc::clone(&self.gl), width, height, pixel_data)
    }
 
-   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Self::Font {
+   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Option<Self::Font> {
       Font::new(Rc::clone(&self.gl), data, default_size)
    }
 
@@ -830,19 +873,21 @@ impl RenderBackend for OpenGlBackend {
       {
          let mut gl_state = self.state.gl_state.borrow_mut();
          previous_framebuffer = gl_state.framebuffer(&self.gl, Some(framebuffer.framebuffer()));
-         previous_viewport = gl_state.viewport;
+         previous_viewport = (gl_state.viewport, gl_state.scale);
          gl_state.viewport(
             &self.gl,
             &self.state.uniforms,
             framebuffer.width(),
             framebuffer.height(),
+            1.0,
          );
       }
       f(self);
+      framebuffer.mark_modified();
       let mut gl_state = self.state.gl_state.borrow_mut();
       gl_state.framebuffer(&self.gl, previous_framebuffer);
-      let (width, height) = previous_viewport;
-      gl_state.viewport(&self.gl, &self.state.uniforms, width, height);
+      let ((width, height), scale) = previous_viewport;
+      gl_state.viewport(&self.gl, &self.state.uniforms, width, height, scale);
    }
 
    fn clear(&mut self, color: Color) {
@@ -869,25 +914,42 @@ impl RenderBackend for OpenGlBackend {
             [glow::RED, glow::GREEN, glow::BLUE, glow::ALPHA]
          };
          self.gl.texture_swizzle_mask(glow::TEXTURE_2D, &swizzle_mask);
+         // Colorized images share their texture with the original, so the filter has to be set
+         // every time the image is drawn, just like the swizzle mask.
+         let filter = match image.filter {
+            ScalingFilter::Nearest => glow::NEAREST,
+            ScalingFilter::Linear => glow::LINEAR,
+         };
+         self.gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, filter as i32);
+         self.gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, filter as i32);
          self.state.draw();
       }
    }
 
    fn framebuffer(&mut self, rect: Rect, framebuffer: &Framebuffer) {
+      self.framebuffer_with_opacity(rect, framebuffer, 1.0);
+   }
+
+   fn framebuffer_with_opacity(&mut self, rect: Rect, framebuffer: &Framebuffer, opacity: f32) {
       assert!(
          self.state.gl_state.borrow().framebuffer != Some(framebuffer.framebuffer()),
          "cannot render a framebuffer to itself"
       );
+      // The vertex color's alpha is multiplied into the whole premultiplied texel, fading it out.
+      let color = Color::WHITE.with_alpha((opacity.clamp(0.0, 1.0) * 255.0).round() as u8);
       self.start();
       self.shape().rect(
-         Vertex::textured(rect.top_left(), point(0.0, 1.0)),
-         Vertex::textured(rect.bottom_right(), point(1.0, 0.0)),
+         Vertex::textured_colored(rect.top_left(), point(0.0, 1.0), color),
+         Vertex::textured_colored(rect.bottom_right(), point(1.0, 0.0), color),
       );
       let texture = framebuffer.texture();
       unsafe {
          self.gl.active_texture(glow::TEXTURE0);
+         framebuffer.update_mipmaps();
          self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+         self.gl.uniform_1_f32(Some(&self.state.uniforms.texture_premultiplied), 1.0);
          self.state.draw();
+         self.gl.uniform_1_f32(Some(&self.state.uniforms.texture_premultiplied), 0.0);
       }
    }
 
@@ -901,6 +963,21 @@ impl RenderBackend for OpenGlBackend {
       framebuffer.upload_rgba(position, size, pixels);
    }
 
+   fn copy_framebuffer(
+      &mut self,
+      source: &Self::Framebuffer,
+      source_position: (u32, u32),
+      size: (u32, u32),
+      destination: &Self::Framebuffer,
+      destination_position: (u32, u32),
+   ) {
+      assert!(
+         source.framebuffer() != destination.framebuffer(),
+         "cannot copy a framebuffer onto itself"
+      );
+      destination.copy_from(source, source_position, size, destination_position);
+   }
+
    fn download_framebuffer(
       &mut self,
       framebuffer: &Self::Framebuffer,
@@ -925,6 +1002,14 @@ impl RenderBackend for OpenGlBackend {
       self.state.transform_mut().matrix *= Mat3A::from_scale(to_vec2(scale));
    }
 
+   fn set_screen_scale(&mut self, scale: f32) {
+      self.state.screen_scale = scale;
+   }
+
+   fn rotate(&mut self, angle: f32) {
+      self.state.transform_mut().matrix *= Mat3A::from_angle(angle);
+   }
+
    fn set_blend_mode(&mut self, new_blend_mode: BlendMode) {
       self.state.transform_mut().blend_mode = new_blend_mode;
       self.state.apply_transform();
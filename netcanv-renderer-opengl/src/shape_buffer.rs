@@ -100,10 +100,33 @@ impl ShapeBuffer {
       self.quad(top_left, top_right, bottom_right, bottom_left)
    }
 
+   /// The minimum number of vertices in an arc.
+   const MIN_ARC_VERTEX_COUNT: usize = 6;
+   /// The maximum number of vertices in an arc, no matter how large it is on the screen.
+   const MAX_ARC_VERTEX_COUNT: usize = 512;
+   /// How far the edges of an arc may stray from the actual curve, in screen pixels.
+   const ARC_TOLERANCE: f32 = 0.25;
+
+   /// Returns how much the current transform scales shapes up.
+   fn transform_scale(&self) -> f32 {
+      let x_scale = self.transform.x_axis.truncate().length();
+      let y_scale = self.transform.y_axis.truncate().length();
+      x_scale.max(y_scale)
+   }
+
    /// Returns the number of vertices an arc with the given radius, start, and end angles should
    /// have to look smooth.
-   fn arc_vertex_count(radius: f32, start_angle: f32, end_angle: f32) -> usize {
-      ((end_angle - start_angle).abs() * radius).max(6.0) as usize
+   ///
+   /// The count depends on the arc's radius on the screen, such that no edge strays from the curve
+   /// by more than [`ShapeBuffer::ARC_TOLERANCE`].
+   fn arc_vertex_count(&self, radius: f32, start_angle: f32, end_angle: f32) -> usize {
+      let screen_radius = radius.abs() * self.transform_scale();
+      // The angle spanned by a single edge whose midpoint lies ARC_TOLERANCE away from the curve.
+      let edge_angle = 2.0 * (1.0 - Self::ARC_TOLERANCE / screen_radius).max(-1.0).acos();
+      let edge_count = ((end_angle - start_angle).abs() / edge_angle).ceil();
+      (edge_count as usize)
+         .saturating_add(1)
+         .clamp(Self::MIN_ARC_VERTEX_COUNT, Self::MAX_ARC_VERTEX_COUNT)
    }
 
    /// Pushes a filled arc into the shape buffer.
@@ -120,7 +143,7 @@ impl ShapeBuffer {
       end_angle: f32,
    ) {
       let Vertex { color, uv, .. } = self.vertices[center_index as usize];
-      let vertex_count = Self::arc_vertex_count(radius, start_angle, end_angle);
+      let vertex_count = self.arc_vertex_count(radius, start_angle, end_angle);
       let mut perimeter_indices = SmallVec::<[u32; 32]>::new();
       for angle_vector in Rotate::new(start_angle, end_angle, vertex_count) {
          perimeter_indices.push(self.push_vertex(Vertex {
@@ -144,7 +167,7 @@ impl ShapeBuffer {
       end_angle: f32,
    ) {
       let Vertex { color, uv, .. } = vertex_template;
-      let vertex_count = Self::arc_vertex_count(radius, start_angle, end_angle);
+      let vertex_count = self.arc_vertex_count(radius, start_angle, end_angle);
       let inner_radius = radius - thickness / 2.0;
       let mut perimeter_positions = SmallVec::<[Point; 32]>::new();
       let mut perimeter_indices = SmallVec::<[u32; 32]>::new();
@@ -227,3 +250,56 @@ impl Iterator for Rotate {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use std::f32::consts::TAU;
+
+   use super::*;
+
+   fn scaled(scale: f32) -> ShapeBuffer {
+      let mut buffer = ShapeBuffer::new();
+      buffer.start(Mat3A::from_scale(glam::Vec2::splat(scale)));
+      buffer
+   }
+
+   #[test]
+   fn arc_edges_stay_close_to_the_curve() {
+      for scale in [0.5, 1.0, 4.0] {
+         let buffer = scaled(scale);
+         for radius in [4.0, 32.0, 200.0] {
+            let vertex_count = buffer.arc_vertex_count(radius, 0.0, TAU);
+            let edge_angle = TAU / (vertex_count - 1) as f32;
+            let error = radius * scale * (1.0 - (edge_angle / 2.0).cos());
+            assert!(
+               error <= ShapeBuffer::ARC_TOLERANCE,
+               "radius {radius} at scale {scale}: {vertex_count} vertices stray {error} px away"
+            );
+         }
+      }
+   }
+
+   #[test]
+   fn zooming_in_adds_vertices() {
+      let at_1x = scaled(1.0).arc_vertex_count(32.0, 0.0, TAU);
+      let at_4x = scaled(4.0).arc_vertex_count(32.0, 0.0, TAU);
+      assert!(at_4x > at_1x, "{at_4x} vertices at 4x, {at_1x} at 1x");
+   }
+
+   #[test]
+   fn arc_vertex_count_is_clamped() {
+      let buffer = scaled(1.0);
+      assert_eq!(
+         buffer.arc_vertex_count(0.1, 0.0, TAU),
+         ShapeBuffer::MIN_ARC_VERTEX_COUNT
+      );
+      assert_eq!(
+         buffer.arc_vertex_count(0.0, 0.0, TAU),
+         ShapeBuffer::MIN_ARC_VERTEX_COUNT
+      );
+      assert_eq!(
+         buffer.arc_vertex_count(1_000_000.0, 0.0, TAU),
+         ShapeBuffer::MAX_ARC_VERTEX_COUNT
+      );
+   }
+}
@@ -0,0 +1,137 @@
+//! Renders a scene of translucent shapes and composited framebuffers, and checks that the
+//! resulting pixels match what straight "source over" compositing would produce, as well as what
+//! the other blend modes would produce.
+//!
+//! Other backends are expected to produce the same pixels for the same draw calls.
+
+use netcanv_renderer::paws::{point, vector, Color, Rect, Renderer};
+use netcanv_renderer::{BlendMode, RenderBackend};
+use netcanv_renderer_opengl::{Framebuffer, OpenGlBackend};
+
+mod common;
+
+/// The maximum difference between expected and actual channel values, to account for rounding.
+const TOLERANCE: u8 = 2;
+
+/// A pixel to check: its name, the framebuffer it's in, its position, and its expected color.
+type Probe<'a> = (&'a str, &'a Framebuffer, (u32, u32), [u8; 4]);
+
+/// Composites `top` over `bottom`, using straight alpha.
+fn over(top: [u8; 4], bottom: [u8; 4]) -> [u8; 4] {
+   let top_alpha = top[3] as f32 / 255.0;
+   let bottom_alpha = bottom[3] as f32 / 255.0;
+   let alpha = top_alpha + bottom_alpha * (1.0 - top_alpha);
+   let mut result = [0, 0, 0, (alpha * 255.0).round() as u8];
+   if alpha > 0.0 {
+      for i in 0..3 {
+         let color =
+            top[i] as f32 * top_alpha + bottom[i] as f32 * bottom_alpha * (1.0 - top_alpha);
+         result[i] = (color / alpha).round() as u8;
+      }
+   }
+   result
+}
+
+#[test]
+fn translucent_shapes_blend_with_source_over() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+
+   let red = Color::rgb(0xff0000).with_alpha(128);
+   let blue = Color::rgb(0x0000ff).with_alpha(128);
+   let to_rgba = |color: Color| [color.r, color.g, color.b, color.a];
+
+   // Overlapping translucent rectangles, drawn into a transparent framebuffer.
+   let shapes = renderer.create_framebuffer(64, 64);
+   renderer.draw_to(&shapes, |renderer| {
+      renderer.fill(Rect::new(point(0.0, 0.0), vector(64.0, 64.0)), red, 0.0);
+      renderer.fill(Rect::new(point(32.0, 0.0), vector(32.0, 64.0)), blue, 0.0);
+   });
+   // The same framebuffer, composited onto another transparent framebuffer. This must not change
+   // the pixels in any way.
+   let composited = renderer.create_framebuffer(64, 64);
+   renderer.draw_to(&composited, |renderer| {
+      renderer.framebuffer(Rect::new(point(0.0, 0.0), vector(64.0, 64.0)), &shapes);
+   });
+
+   let only_red = over(to_rgba(red), [0, 0, 0, 0]);
+   let red_and_blue = over(to_rgba(blue), only_red);
+   let probes = [
+      ("shapes, red", &shapes, (16, 32), only_red),
+      ("shapes, red and blue", &shapes, (48, 32), red_and_blue),
+      ("composited, red", &composited, (16, 32), only_red),
+      (
+         "composited, red and blue",
+         &composited,
+         (48, 32),
+         red_and_blue,
+      ),
+   ];
+
+   check_probes(&mut renderer, &probes);
+}
+
+#[test]
+fn multiply_and_screen_blend_with_the_destination() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+
+   let gray = Color::rgb(0x808080);
+   let red = Color::rgb(0xff0000);
+   let multiplied = draw_blended(&mut renderer, BlendMode::Multiply, red, gray);
+   let screened = draw_blended(&mut renderer, BlendMode::Screen, gray, gray);
+
+   let probes = [
+      ("multiply", &multiplied, (16, 32), [128, 0, 0, 255]),
+      ("multiply, transparent", &multiplied, (48, 32), [0, 0, 0, 0]),
+      ("screen", &screened, (16, 32), [192, 192, 192, 255]),
+      (
+         "screen, transparent",
+         &screened,
+         (48, 32),
+         [128, 128, 128, 255],
+      ),
+   ];
+   check_probes(&mut renderer, &probes);
+}
+
+/// Fills a framebuffer whose left half is `background` and whose right half is transparent with
+/// `color`, using the given blend mode.
+fn draw_blended(
+   renderer: &mut OpenGlBackend,
+   blend_mode: BlendMode,
+   color: Color,
+   background: Color,
+) -> Framebuffer {
+   let framebuffer = renderer.create_framebuffer(64, 64);
+   renderer.draw_to(&framebuffer, |renderer| {
+      renderer.fill(
+         Rect::new(point(0.0, 0.0), vector(32.0, 64.0)),
+         background,
+         0.0,
+      );
+      renderer.push();
+      renderer.set_blend_mode(blend_mode);
+      renderer.fill(Rect::new(point(0.0, 0.0), vector(64.0, 64.0)), color, 0.0);
+      renderer.pop();
+   });
+   framebuffer
+}
+
+/// Downloads the pixels at the probes' positions and checks them against the expected colors.
+fn check_probes(renderer: &mut OpenGlBackend, probes: &[Probe]) {
+   let mut failed = false;
+   for &(name, framebuffer, position, expected) in probes {
+      let mut actual = [0; 4];
+      renderer.download_framebuffer(framebuffer, position, (1, 1), &mut actual);
+      let matches = actual.iter().zip(expected).all(|(&a, e)| a.abs_diff(e) <= TOLERANCE);
+      println!(
+         "{} {name}: expected {expected:?}, got {actual:?}",
+         if matches { "ok  " } else { "FAIL" }
+      );
+      failed |= !matches;
+   }
+   assert!(!failed, "rendered pixels do not match the expected values");
+}
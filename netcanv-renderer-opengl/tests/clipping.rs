@@ -0,0 +1,59 @@
+//! Renders a rectangle through two nested clipping rectangles, and checks that only pixels inside
+//! their intersection are drawn.
+
+use netcanv_renderer::paws::{point, vector, Color, Rect, Renderer};
+use netcanv_renderer::RenderBackend;
+
+mod common;
+
+#[test]
+fn nested_clips_intersect() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+
+   let framebuffer = renderer.create_framebuffer(64, 64);
+   renderer.draw_to(&framebuffer, |renderer| {
+      renderer.push();
+      renderer.clip(Rect::new(point(0.0, 0.0), vector(32.0, 32.0)));
+      renderer.push();
+      renderer.clip(Rect::new(point(16.0, 16.0), vector(32.0, 32.0)));
+      renderer.fill(
+         Rect::new(point(0.0, 0.0), vector(64.0, 64.0)),
+         Color::WHITE,
+         0.0,
+      );
+      renderer.pop();
+      renderer.pop();
+   });
+
+   // The intersection of both clips spans from (16, 16) to (32, 32).
+   let probes = [
+      ("inside both clips", (24, 24), true),
+      ("inside the outer clip only", (8, 8), false),
+      ("inside the inner clip only", (40, 40), false),
+      ("outside both clips", (56, 8), false),
+   ];
+
+   let mut failed = false;
+   for (name, position, should_be_drawn) in probes {
+      let mut pixel = [0; 4];
+      renderer.download_framebuffer(&framebuffer, position, (1, 1), &mut pixel);
+      let drawn = pixel[3] != 0;
+      println!(
+         "{} {name}: expected {}, got {pixel:?}",
+         if drawn == should_be_drawn {
+            "ok  "
+         } else {
+            "FAIL"
+         },
+         if should_be_drawn {
+            "drawn"
+         } else {
+            "not drawn"
+         },
+      );
+      failed |= drawn != should_be_drawn;
+   }
+   assert!(!failed, "clipping does not match the expected area");
+}
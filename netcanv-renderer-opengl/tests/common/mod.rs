@@ -0,0 +1,48 @@
+//! Setup shared by the rendering tests.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use netcanv_renderer_opengl::winit::event_loop::{EventLoop, EventLoopBuilder};
+#[cfg(windows)]
+use netcanv_renderer_opengl::winit::platform::windows::EventLoopBuilderExtWindows;
+#[cfg(all(
+   unix,
+   not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+use netcanv_renderer_opengl::winit::platform::x11::EventLoopBuilderExtX11;
+use netcanv_renderer_opengl::winit::window::WindowBuilder;
+use netcanv_renderer_opengl::OpenGlBackend;
+
+/// Creates a renderer onto a hidden window. The event loop has to outlive the renderer.
+///
+/// Returns `None` if there's no display or OpenGL driver to render with, such as on a headless CI
+/// machine, in which case the test should be skipped.
+pub fn renderer() -> Option<(EventLoop<()>, OpenGlBackend)> {
+   // Tests don't run on the main thread, and winit panics instead of returning an error when there's
+   // no display to connect to.
+   let event_loop = panic::catch_unwind(AssertUnwindSafe(|| {
+      let mut builder = EventLoopBuilder::new();
+      #[cfg(any(
+         windows,
+         all(
+            unix,
+            not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+         )
+      ))]
+      builder.with_any_thread(true);
+      builder.build()
+   }));
+   let result =
+      event_loop.map_err(|_| anyhow::anyhow!("no display is available")).and_then(|event_loop| {
+         let window = WindowBuilder::new().with_visible(false).build(&event_loop)?;
+         let renderer = OpenGlBackend::from_window(window, &event_loop)?;
+         Ok((event_loop, renderer))
+      });
+   match result {
+      Ok(result) => Some(result),
+      Err(error) => {
+         eprintln!("skipping: cannot create an OpenGL renderer: {error}");
+         None
+      }
+   }
+}
@@ -0,0 +1,26 @@
+//! Loads fonts from memory, and checks that invalid font data is rejected instead of crashing the
+//! app.
+
+use netcanv_renderer::{Font as FontTrait, RenderBackend};
+
+mod common;
+
+#[test]
+fn valid_fonts_are_loaded() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+   let data = include_bytes!("../../src/assets/fonts/Barlow-Medium.ttf");
+   let font = renderer.create_font_from_memory(data, 14.0).expect("the bundled font must load");
+   assert_eq!(font.size(), 14.0);
+   assert!(font.text_width("NetCanv") > 0.0);
+}
+
+#[test]
+fn invalid_fonts_are_rejected() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+   assert!(renderer.create_font_from_memory(b"", 14.0).is_none());
+   assert!(renderer.create_font_from_memory(b"definitely not a font file", 14.0).is_none());
+}
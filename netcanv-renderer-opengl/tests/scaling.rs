@@ -0,0 +1,54 @@
+//! Stretches a tiny image with each of the scaling filters, and checks that nearest neighbor
+//! filtering keeps hard edges, while bilinear filtering blends neighboring pixels together.
+
+use netcanv_renderer::paws::{point, vector, Rect};
+use netcanv_renderer::{Image as ImageTrait, RenderBackend, ScalingFilter};
+use netcanv_renderer_opengl::OpenGlBackend;
+
+mod common;
+
+/// Stretches a 2x1 image, black on the left and white on the right, over a 64x1 framebuffer. The
+/// image is drawn with the given filter, or the default one if it's `None`.
+///
+/// Returns the red channel of the pixels at 3/8 and 5/8 of the framebuffer's width, on either
+/// side of the edge between the two colors.
+fn stretch(renderer: &mut OpenGlBackend, filter: Option<ScalingFilter>) -> [u8; 2] {
+   let mut image = renderer.create_image_from_rgba(2, 1, &[0, 0, 0, 255, 255, 255, 255, 255]);
+   if let Some(filter) = filter {
+      image.set_scaling_filter(filter);
+   }
+   let framebuffer = renderer.create_framebuffer(64, 1);
+   renderer.draw_to(&framebuffer, |renderer| {
+      renderer.image(Rect::new(point(0.0, 0.0), vector(64.0, 1.0)), &image);
+   });
+   [24, 40].map(|x| {
+      let mut pixel = [0; 4];
+      renderer.download_framebuffer(&framebuffer, (x, 0), (1, 1), &mut pixel);
+      pixel[0]
+   })
+}
+
+#[test]
+fn nearest_filter_keeps_hard_edges() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+   assert_eq!(
+      stretch(&mut renderer, Some(ScalingFilter::Nearest)),
+      [0, 255]
+   );
+}
+
+#[test]
+fn images_are_filtered_linearly_by_default() {
+   let Some((_event_loop, mut renderer)) = common::renderer() else {
+      return;
+   };
+   let linear = stretch(&mut renderer, Some(ScalingFilter::Linear));
+   let [left, right] = linear;
+   assert!(
+      left > 16 && left < right && right < 240,
+      "linear filtering left hard edges: {linear:?}"
+   );
+   assert_eq!(stretch(&mut renderer, None), linear);
+}
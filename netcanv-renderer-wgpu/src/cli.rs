@@ -9,6 +9,14 @@ pub struct RendererCli {
    /// the app will fail to open.
    #[clap(long, value_parser = backend_from_str)]
    pub wgpu_backend: Option<Backend>,
+
+   /// The number of samples per pixel used for antialiasing the interface. Available levels:
+   /// 0 (off), 2, 4, 8
+   ///
+   /// If the graphics adapter does not support the requested level, the closest lower level is
+   /// used instead.
+   #[clap(long, default_value_t = 0, value_parser = msaa_from_str)]
+   pub msaa: u8,
 }
 
 fn backend_from_str(s: &str) -> anyhow::Result<Backend> {
@@ -24,3 +32,25 @@ fn backend_from_str(s: &str) -> anyhow::Result<Backend> {
       }
    })
 }
+
+fn msaa_from_str(s: &str) -> anyhow::Result<u8> {
+   match s {
+      "0" | "2" | "4" | "8" => Ok(s.parse()?),
+      _ => bail!("invalid MSAA level. available levels: 0, 2, 4, 8"),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn only_supported_msaa_levels_are_accepted() {
+      for level in [0, 2, 4, 8] {
+         assert_eq!(msaa_from_str(&level.to_string()).unwrap(), level);
+      }
+      for invalid in ["1", "3", "16", "-2", "", "four"] {
+         assert!(msaa_from_str(invalid).is_err(), "{invalid:?} was accepted");
+      }
+   }
+}
@@ -1,5 +1,5 @@
 use glam::{vec2, Vec2};
-use netcanv_renderer::paws::{Color, Vector};
+use netcanv_renderer::paws::{point, vector, Color, Rect, Vector};
 
 pub fn paws_color_to_wgpu(color: Color) -> wgpu::Color {
    wgpu::Color {
@@ -13,3 +13,14 @@ pub fn paws_color_to_wgpu(color: Color) -> wgpu::Color {
 pub fn vector_to_vec2(vector: Vector) -> Vec2 {
    vec2(vector.x, vector.y)
 }
+
+/// Returns the area covered by both rectangles. If they don't overlap, the resulting rectangle is
+/// empty.
+pub fn intersect_rects(a: Rect, b: Rect) -> Rect {
+   let (a, b) = (a.sort(), b.sort());
+   let left = a.left().max(b.left());
+   let top = a.top().max(b.top());
+   let right = a.right().min(b.right()).max(left);
+   let bottom = a.bottom().min(b.bottom()).max(top);
+   Rect::new(point(left, top), vector(right - left, bottom - top))
+}
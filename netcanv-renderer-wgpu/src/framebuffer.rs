@@ -1,7 +1,7 @@
 use std::cell::Cell;
 use std::sync::{Arc, OnceLock};
 
-use netcanv_renderer::ScalingFilter;
+use netcanv_renderer::{alpha, ScalingFilter};
 use tracing::warn;
 
 use crate::gpu::Gpu;
@@ -65,6 +65,8 @@ impl Framebuffer {
    pub(crate) fn upload(&self, gpu: &Gpu, position: (u32, u32), size: (u32, u32), pixels: &[u8]) {
       let (x, y) = position;
       let (width, height) = size;
+      let mut pixels = pixels.to_owned();
+      alpha::premultiply(&mut pixels);
       gpu.queue.write_texture(
          wgpu::ImageCopyTextureBase {
             texture: &self.texture,
@@ -72,7 +74,7 @@ impl Framebuffer {
             origin: wgpu::Origin3d { x, y, z: 0 },
             aspect: wgpu::TextureAspect::All,
          },
-         pixels,
+         &pixels,
          wgpu::ImageDataLayout {
             offset: 0,
             bytes_per_row: Some(width * 4),
@@ -86,6 +88,39 @@ impl Framebuffer {
       )
    }
 
+   pub(crate) fn copy_to(
+      &self,
+      encoder: &mut wgpu::CommandEncoder,
+      (source_x, source_y): (u32, u32),
+      (width, height): (u32, u32),
+      destination: &Framebuffer,
+      (x, y): (u32, u32),
+   ) {
+      encoder.copy_texture_to_texture(
+         wgpu::ImageCopyTexture {
+            texture: &self.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+               x: source_x,
+               y: source_y,
+               z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+         },
+         wgpu::ImageCopyTexture {
+            texture: &destination.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+         },
+         wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+         },
+      );
+   }
+
    pub(crate) fn sync_download(
       &self,
       gpu: &Gpu,
@@ -169,6 +204,7 @@ impl Framebuffer {
          None => warn!("could not copy pixels from the CPU to the GPU: did not receive a signal that the pixels are ready"),
       }
       download_buffer.destroy();
+      alpha::unpremultiply(out_pixels);
    }
 }
 
@@ -180,4 +216,11 @@ impl netcanv_renderer::Framebuffer for Framebuffer {
    fn set_scaling_filter(&mut self, filter: ScalingFilter) {
       self.scaling_filter = filter;
    }
+
+   /// Mipmapping is not supported by this backend, so this does nothing. Framebuffers are always
+   /// minified using their scaling filter.
+   ///
+   /// wgpu has no built-in way of generating mip levels; that would need a separate blit
+   /// pipeline rendering each level from the previous one.
+   fn set_mipmapping(&mut self, _enabled: bool) {}
 }
@@ -18,11 +18,21 @@ pub struct Gpu {
    pub nearest_sampler: wgpu::Sampler,
    pub scene_uniform_bind_group_layout: wgpu::BindGroupLayout,
 
+   /// The number of samples used when rendering to the screen. Framebuffers are never
+   /// multisampled.
+   pub sample_count: u32,
+
    pub screen_texture: wgpu::Texture,
    pub screen_texture_bind_group_layout: wgpu::BindGroupLayout,
    pub screen_texture_bind_group: wgpu::BindGroup,
    pub current_render_target: Option<wgpu::TextureView>,
    pub current_render_target_size: (u32, u32),
+   /// The factor everything drawn onto the current render target is scaled up by.
+   pub current_render_target_scale: f32,
+   /// The multisampled texture rendered to in place of the current render target, which it is
+   /// resolved into at the end of every render pass. This is only present while rendering to the
+   /// screen with multisampling enabled.
+   pub current_msaa_target: Option<wgpu::TextureView>,
 }
 
 impl Gpu {
@@ -60,6 +70,34 @@ impl Gpu {
       (texture, texture_view, bind_group)
    }
 
+   pub fn create_msaa_texture_view(
+      device: &wgpu::Device,
+      window_size: PhysicalSize<u32>,
+      sample_count: u32,
+   ) -> Option<wgpu::TextureView> {
+      if sample_count <= 1 {
+         return None;
+      }
+      let texture = device.create_texture(&wgpu::TextureDescriptor {
+         label: Some("Screen MSAA Texture"),
+         size: wgpu::Extent3d {
+            width: window_size.width,
+            height: window_size.height,
+            depth_or_array_layers: 1,
+         },
+         mip_level_count: 1,
+         sample_count,
+         dimension: wgpu::TextureDimension::D2,
+         format: wgpu::TextureFormat::Rgba8Unorm,
+         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+         view_formats: &[wgpu::TextureFormat::Rgba8Unorm],
+      });
+      Some(texture.create_view(&wgpu::TextureViewDescriptor {
+         label: Some("Screen MSAA Texture View"),
+         ..Default::default()
+      }))
+   }
+
    pub fn handle_resize(&mut self, window_size: PhysicalSize<u32>) {
       profiling::scope!("Gpu::handle_resize");
 
@@ -76,6 +114,8 @@ impl Gpu {
       self.screen_texture_bind_group = screen_texture_bind_group;
       self.current_render_target = Some(screen_texture_view);
       self.current_render_target_size = (window_size.width, window_size.height);
+      self.current_msaa_target =
+         Self::create_msaa_texture_view(&self.device, window_size, self.sample_count);
    }
 
    pub fn screen_format(&self) -> wgpu::TextureFormat {
@@ -116,20 +156,30 @@ impl Gpu {
       self.current_render_target.as_ref().expect("attempt to render outside of render_frame")
    }
 
+   /// Returns the number of samples the current render target is rendered with.
+   pub fn current_sample_count(&self) -> u32 {
+      if self.current_msaa_target.is_some() {
+         self.sample_count
+      } else {
+         1
+      }
+   }
+
    pub fn color_target_state(&self, blend_mode: BlendMode) -> wgpu::ColorTargetState {
       wgpu::ColorTargetState {
          format: self.screen_format(),
          blend: Some(match blend_mode {
             BlendMode::Replace => wgpu::BlendState::REPLACE,
-            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            // Shaders output premultiplied colors, so the source alpha must not be applied again.
+            BlendMode::Alpha => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
             BlendMode::Add => wgpu::BlendState {
                color: wgpu::BlendComponent {
-                  src_factor: wgpu::BlendFactor::SrcAlpha,
+                  src_factor: wgpu::BlendFactor::One,
                   operation: wgpu::BlendOperation::Add,
                   dst_factor: wgpu::BlendFactor::One,
                },
                alpha: wgpu::BlendComponent {
-                  src_factor: wgpu::BlendFactor::SrcAlpha,
+                  src_factor: wgpu::BlendFactor::One,
                   operation: wgpu::BlendOperation::Add,
                   dst_factor: wgpu::BlendFactor::One,
                },
@@ -146,6 +196,32 @@ impl Gpu {
                   dst_factor: wgpu::BlendFactor::One,
                },
             },
+            // The destination's alpha is kept as is, so multiplying onto transparent pixels
+            // doesn't leave black behind.
+            BlendMode::Multiply => wgpu::BlendState {
+               color: wgpu::BlendComponent {
+                  src_factor: wgpu::BlendFactor::Dst,
+                  operation: wgpu::BlendOperation::Add,
+                  dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+               },
+               alpha: wgpu::BlendComponent {
+                  src_factor: wgpu::BlendFactor::Zero,
+                  operation: wgpu::BlendOperation::Add,
+                  dst_factor: wgpu::BlendFactor::One,
+               },
+            },
+            BlendMode::Screen => wgpu::BlendState {
+               color: wgpu::BlendComponent {
+                  src_factor: wgpu::BlendFactor::One,
+                  operation: wgpu::BlendOperation::Add,
+                  dst_factor: wgpu::BlendFactor::OneMinusSrc,
+               },
+               alpha: wgpu::BlendComponent {
+                  src_factor: wgpu::BlendFactor::One,
+                  operation: wgpu::BlendOperation::Add,
+                  dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+               },
+            },
          }),
          write_mask: wgpu::ColorWrites::ALL,
       }
@@ -19,6 +19,8 @@ unsafe impl Pod for SceneUniformData {}
 struct CacheKey {
    viewport_width: u32,
    viewport_height: u32,
+   /// The bits of the `f32` scale factor, as floats can't be hashed.
+   scale_bits: u32,
 }
 
 struct CacheEntry {
@@ -40,31 +42,43 @@ impl SceneUniformCache {
       }
    }
 
-   pub fn bind_group(&mut self, gpu: &Gpu, viewport_size: (u32, u32)) -> &wgpu::BindGroup {
+   /// Returns the bind group for a viewport of the given size in physical pixels, whose contents are
+   /// scaled up by `scale`.
+   pub fn bind_group(
+      &mut self,
+      gpu: &Gpu,
+      viewport_size: (u32, u32),
+      scale: f32,
+   ) -> &wgpu::BindGroup {
       let (viewport_width, viewport_height) = viewport_size;
+      let (logical_width, logical_height) = (
+         viewport_width as f32 / scale,
+         viewport_height as f32 / scale,
+      );
       &self
          .cache
          .entry(CacheKey {
             viewport_width,
             viewport_height,
+            scale_bits: scale.to_bits(),
          })
          .or_insert_with(|| {
             let buffer = gpu.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                label: Some(&format!(
-                  "Scene Uniform Buffer ({viewport_width}x{viewport_height} viewport)"
+                  "Scene Uniform Buffer ({viewport_width}x{viewport_height} viewport @ {scale}x)"
                )),
                usage: wgpu::BufferUsages::UNIFORM,
                contents: bytemuck::bytes_of(&SceneUniformData {
                   transform: Mat3A::from_cols(
-                     vec3a(2.0 / viewport_width as f32, 0.0, 0.0),
-                     vec3a(0.0, -2.0 / viewport_height as f32, 0.0),
+                     vec3a(2.0 / logical_width, 0.0, 0.0),
+                     vec3a(0.0, -2.0 / logical_height, 0.0),
                      vec3a(-1.0, 1.0, 0.0),
                   ),
                }),
             });
             let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
                label: Some(&format!(
-                  "Scene Uniform Bind Group ({viewport_width}x{viewport_height} viewport)"
+                  "Scene Uniform Bind Group ({viewport_width}x{viewport_height} viewport @ {scale}x)"
                )),
                layout: &gpu.scene_uniform_bind_group_layout,
                entries: &[
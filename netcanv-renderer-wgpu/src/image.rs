@@ -1,4 +1,5 @@
 use netcanv_renderer::paws::Color;
+use netcanv_renderer::ScalingFilter;
 use wgpu::util::DeviceExt;
 
 use crate::gpu::Gpu;
@@ -10,6 +11,7 @@ pub struct Image {
    height: u32,
    pub(crate) index: u32,
    pub(crate) color: Option<Color>,
+   pub(crate) scaling_filter: ScalingFilter,
 }
 
 impl netcanv_renderer::Image for Image {
@@ -23,6 +25,10 @@ impl netcanv_renderer::Image for Image {
    fn size(&self) -> (u32, u32) {
       (self.width, self.height)
    }
+
+   fn set_scaling_filter(&mut self, filter: ScalingFilter) {
+      self.scaling_filter = filter;
+   }
 }
 
 // NOTE(liquidev): Right now the implementation of images is quite rudimentary, having one texture
@@ -128,6 +134,7 @@ impl WgpuBackend {
          height,
          index,
          color: None,
+         scaling_filter: ScalingFilter::Linear,
       }
    }
 }
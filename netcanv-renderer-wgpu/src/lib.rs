@@ -8,11 +8,11 @@ use image::ImageStorage;
 use netcanv_renderer::paws::{Color, Ui};
 use rendering::Pass;
 use text::TextRenderer;
-use tracing::info;
+use tracing::{info, warn};
 use transform::TransformState;
 use wgpu::util::DeviceExt;
 use winit::dpi::PhysicalSize;
-use winit::event_loop::EventLoop;
+use winit::event_loop::EventLoopWindowTarget;
 use winit::window::{Window, WindowBuilder};
 
 pub use winit;
@@ -52,6 +52,8 @@ pub struct WgpuBackend {
 
    clear: Option<Color>,
    last_pass: Option<Pass>,
+   /// The factor everything drawn onto the window is scaled up by.
+   screen_scale: f32,
 
    rounded_rects: pass::RoundedRects,
    lines: pass::Lines,
@@ -66,9 +68,9 @@ pub struct WgpuBackend {
 }
 
 impl WgpuBackend {
-   pub async fn new(
+   pub async fn new<T>(
       window_builder: WindowBuilder,
-      event_loop: &EventLoop<()>,
+      event_loop: &EventLoopWindowTarget<T>,
       cli: &RendererCli,
    ) -> anyhow::Result<Self> {
       let window = window_builder.build(event_loop).context("Failed to create window")?;
@@ -94,16 +96,25 @@ impl WgpuBackend {
             "Failed to find a graphics adapter. Please make sure your drivers are up to date",
          )?;
 
+      info!("using graphics adapter: {:?}", adapter.get_info());
       let capabilities = surface.get_capabilities(&adapter);
       info!("adapter capabilities: {capabilities:#?}");
       info!("adapter limits: {:#?}", adapter.limits());
       let limits = wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
       info!("using compatible set of limits: {limits:#?}");
+      let sample_count = Self::pick_sample_count(&adapter, cli.msaa);
+      info!("using {sample_count} sample(s) per pixel");
 
       let (device, queue) = adapter.request_device(
          &wgpu::DeviceDescriptor {
             label: None,
-            features: wgpu::Features::empty(),
+            // Only 4x multisampling is guaranteed to be supported without adapter-specific
+            // format features.
+            features: if matches!(sample_count, 1 | 4) {
+               wgpu::Features::empty()
+            } else {
+               wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            },
             limits,
          },
          None,
@@ -220,8 +231,12 @@ impl WgpuBackend {
          nearest_sampler,
          scene_uniform_bind_group_layout,
 
+         sample_count,
+
          current_render_target: Some(screen_texture_view),
          current_render_target_size: (screen_texture.width(), screen_texture.height()),
+         current_render_target_scale: 1.0,
+         current_msaa_target: None,
          screen_texture,
          screen_texture_bind_group_layout,
          screen_texture_bind_group,
@@ -260,6 +275,7 @@ impl WgpuBackend {
 
          clear: None,
          last_pass: None,
+         screen_scale: 1.0,
          context_size,
          command_buffers: vec![],
 
@@ -267,6 +283,32 @@ impl WgpuBackend {
       })
    }
 
+   /// Returns the highest number of samples per pixel supported by the adapter that does not
+   /// exceed the requested MSAA level. A warning is logged if the requested level is unsupported.
+   fn pick_sample_count(adapter: &wgpu::Adapter, requested: u8) -> u32 {
+      let requested = u32::from(requested).max(1);
+      let flags = adapter.get_texture_format_features(wgpu::TextureFormat::Rgba8Unorm).flags;
+      let adapter_specific =
+         adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+      let is_supported = |count: u32| {
+         count == 1
+            || flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+               && flags.sample_count_supported(count)
+               && (count == 4 || adapter_specific)
+      };
+      let sample_count = [8, 4, 2, 1]
+         .into_iter()
+         .find(|&count| count <= requested && is_supported(count))
+         .unwrap_or(1);
+      if sample_count != requested {
+         warn!(
+            "{requested}x MSAA is not supported by the graphics adapter, \
+             falling back to {sample_count}x"
+         );
+      }
+      sample_count
+   }
+
    pub fn window(&self) -> &Window {
       &self.window
    }
@@ -295,6 +337,7 @@ impl UiRenderFrame for Ui<WgpuBackend> {
       });
 
       self.rewind();
+      self.gpu.current_render_target_scale = self.screen_scale;
       {
          profiling::scope!("main_render_pass");
          f(self);
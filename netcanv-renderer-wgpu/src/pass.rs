@@ -21,19 +21,40 @@ pub(crate) struct PassCreationContext<'a> {
 }
 
 pub(crate) struct RenderPipelinePermutations {
-   permutations: [wgpu::RenderPipeline; BlendMode::VARIANTS.len()],
+   single_sampled: [wgpu::RenderPipeline; BlendMode::VARIANTS.len()],
+   /// Used when rendering to the screen. Only present if multisampling is enabled.
+   multisampled: Option<[wgpu::RenderPipeline; BlendMode::VARIANTS.len()]>,
 }
 
 impl RenderPipelinePermutations {
-   pub fn new(make_permutation: impl Fn(&str, BlendMode) -> wgpu::RenderPipeline) -> Self {
+   pub fn new(
+      gpu: &Gpu,
+      make_permutation: impl Fn(&str, BlendMode, wgpu::MultisampleState) -> wgpu::RenderPipeline,
+   ) -> Self {
+      let make_permutations = |sample_count: u32| {
+         BlendMode::VARIANTS.map(|blend_mode| {
+            make_permutation(
+               &format!("(blend_mode={blend_mode:?}, sample_count={sample_count})"),
+               blend_mode,
+               wgpu::MultisampleState {
+                  count: sample_count,
+                  ..Default::default()
+               },
+            )
+         })
+      };
       Self {
-         permutations: BlendMode::VARIANTS.map(|blend_mode| {
-            make_permutation(&format!("(blend_mode={blend_mode:?})"), blend_mode)
-         }),
+         single_sampled: make_permutations(1),
+         multisampled: (gpu.sample_count > 1).then(|| make_permutations(gpu.sample_count)),
       }
    }
 
-   pub fn get(&self, blend_mode: BlendMode) -> &wgpu::RenderPipeline {
-      &self.permutations[blend_mode as usize]
+   pub fn get(&self, blend_mode: BlendMode, sample_count: u32) -> &wgpu::RenderPipeline {
+      let permutations = if sample_count > 1 {
+         self.multisampled.as_ref().expect("multisampled pipelines were not created")
+      } else {
+         &self.single_sampled
+      };
+      &permutations[blend_mode as usize]
    }
 }
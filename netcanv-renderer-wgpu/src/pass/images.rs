@@ -70,26 +70,27 @@ impl Images {
             ],
             push_constant_ranges: &[],
          });
-      let render_pipelines = RenderPipelinePermutations::new(|label, blend_mode| {
-         context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(&format!("Images: Render Pipeline {label}")),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-               module: &shader,
-               entry_point: "main_vs",
-               buffers: &[Vertex::LAYOUT],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-               module: &shader,
-               entry_point: "main_fs",
-               targets: &[Some(context.gpu.color_target_state(blend_mode))],
-            }),
-            multiview: None,
-         })
-      });
+      let render_pipelines =
+         RenderPipelinePermutations::new(context.gpu, |label, blend_mode, multisample| {
+            context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+               label: Some(&format!("Images: Render Pipeline {label}")),
+               layout: Some(&pipeline_layout),
+               vertex: wgpu::VertexState {
+                  module: &shader,
+                  entry_point: "main_vs",
+                  buffers: &[Vertex::LAYOUT],
+               },
+               primitive: wgpu::PrimitiveState::default(),
+               depth_stencil: None,
+               multisample,
+               fragment: Some(wgpu::FragmentState {
+                  module: &shader,
+                  entry_point: "main_fs",
+                  targets: &[Some(context.gpu.color_target_state(blend_mode))],
+               }),
+               multiview: None,
+            })
+         });
 
       Self {
          vertex_buffer,
@@ -105,7 +106,18 @@ impl Images {
       }
    }
 
-   pub fn add(&mut self, rect: Rect, color: Option<Color>, binding: u32, filter: ScalingFilter) {
+   /// Adds an image to the batch. `premultiplied` must be set for textures that store premultiplied
+   /// colors, such as framebuffers. The image is faded out according to `opacity`, which ranges
+   /// from 0 to 1.
+   pub fn add(
+      &mut self,
+      rect: Rect,
+      color: Option<Color>,
+      binding: u32,
+      filter: ScalingFilter,
+      premultiplied: bool,
+      opacity: f32,
+   ) {
       assert!(
          self.image_rect_data.len() < self.image_rect_data.capacity(),
          "too many images without flushing"
@@ -114,11 +126,13 @@ impl Images {
       let mut rendition = Rendition::empty();
       rendition.set(Rendition::COLORIZE, color.is_some());
       rendition.set(Rendition::NEAREST, filter == ScalingFilter::Nearest);
+      rendition.set(Rendition::PREMULTIPLIED, premultiplied);
 
       self.image_rect_data.push(ImageRectData {
          rect: vec4(rect.x(), rect.y(), rect.width(), rect.height()),
          color: color.unwrap_or(Color::TRANSPARENT),
          rendition,
+         opacity: opacity.clamp(0.0, 1.0),
       });
       self.image_bindings.push(binding);
    }
@@ -142,7 +156,7 @@ impl Images {
       context.gpu.queue.write_buffer(image_rect_data_buffer, 0, image_rect_data_bytes);
 
       render_pass.push_debug_group("Images");
-      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode));
+      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode, context.sample_count));
       render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
       render_pass.set_bind_group(1, bind_group, &[]);
       render_pass.set_bind_group(2, context.model_transform_bind_group, &[]);
@@ -177,6 +191,7 @@ struct ImageRectData {
    rect: Vec4,
    color: Color,
    rendition: Rendition,
+   opacity: f32,
 }
 
 bitflags! {
@@ -184,6 +199,7 @@ bitflags! {
    struct Rendition: u32 {
       const COLORIZE = 0x1;
       const NEAREST  = 0x2;
+      const PREMULTIPLIED = 0x4;
    }
 }
 
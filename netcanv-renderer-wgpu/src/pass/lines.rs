@@ -65,26 +65,27 @@ impl Lines {
             ],
             push_constant_ranges: &[],
          });
-      let render_pipelines = RenderPipelinePermutations::new(|label, blend_mode| {
-         context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(&format!("Lines: Render Pipeline {label}")),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-               module: &shader,
-               entry_point: "main_vs",
-               buffers: &[Vertex::LAYOUT],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-               module: &shader,
-               entry_point: "main_fs",
-               targets: &[Some(context.gpu.color_target_state(blend_mode))],
-            }),
-            multiview: None,
-         })
-      });
+      let render_pipelines =
+         RenderPipelinePermutations::new(context.gpu, |label, blend_mode, multisample| {
+            context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+               label: Some(&format!("Lines: Render Pipeline {label}")),
+               layout: Some(&pipeline_layout),
+               vertex: wgpu::VertexState {
+                  module: &shader,
+                  entry_point: "main_vs",
+                  buffers: &[Vertex::LAYOUT],
+               },
+               primitive: wgpu::PrimitiveState::default(),
+               depth_stencil: None,
+               multisample,
+               fragment: Some(wgpu::FragmentState {
+                  module: &shader,
+                  entry_point: "main_fs",
+                  targets: &[Some(context.gpu.color_target_state(blend_mode))],
+               }),
+               multiview: None,
+            })
+         });
 
       Self {
          vertex_buffer,
@@ -143,7 +144,7 @@ impl Lines {
       context.gpu.queue.write_buffer(line_data_buffer, 0, line_data_bytes);
 
       render_pass.push_debug_group("Lines");
-      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode));
+      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode, context.sample_count));
       render_pass.set_bind_group(0, bind_group, &[]);
       render_pass.set_bind_group(1, context.model_transform_bind_group, &[]);
       render_pass.set_bind_group(2, context.scene_uniform_bind_group, &[]);
@@ -11,10 +11,12 @@ struct ImageRect {
    rect: vec4f,
    color: u32,
    rendition: u32,
+   opacity: f32,
 }
 
 const rendition_colorize = 0x00000001u;
 const rendition_nearest  = 0x00000002u;
+const rendition_premultiplied = 0x00000004u;
 
 @group(0) @binding(0) var image_texture: texture_2d<f32>;
 @group(1) @binding(0) var<uniform> image_rect_data: array<ImageRect, max_image_count>;
@@ -52,5 +54,9 @@ fn main_fs(vertex: Vertex) -> @location(0) vec4f {
       let tint_color = unpack4x8unorm(data.color);
       color = vec4f(tint_color.r, tint_color.g, tint_color.b, tint_color.a * color.a);
    }
-   return color;
+   // Images store straight alpha, whereas the render target expects premultiplied colors.
+   if (data.rendition & rendition_premultiplied) == 0u {
+      color = vec4f(color.rgb * color.a, color.a);
+   }
+   return color * data.opacity;
 }
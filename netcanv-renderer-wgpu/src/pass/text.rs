@@ -76,26 +76,27 @@ impl Text {
             ],
             push_constant_ranges: &[],
          });
-      let render_pipelines = RenderPipelinePermutations::new(|label, blend_mode| {
-         context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(&format!("Text: Render Pipeline {label}")),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-               module: &shader,
-               entry_point: "main_vs",
-               buffers: &[Vertex::LAYOUT],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-               module: &shader,
-               entry_point: "main_fs",
-               targets: &[Some(context.gpu.color_target_state(blend_mode))],
-            }),
-            multiview: None,
-         })
-      });
+      let render_pipelines =
+         RenderPipelinePermutations::new(context.gpu, |label, blend_mode, multisample| {
+            context.gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+               label: Some(&format!("Text: Render Pipeline {label}")),
+               layout: Some(&pipeline_layout),
+               vertex: wgpu::VertexState {
+                  module: &shader,
+                  entry_point: "main_vs",
+                  buffers: &[Vertex::LAYOUT],
+               },
+               primitive: wgpu::PrimitiveState::default(),
+               depth_stencil: None,
+               multisample,
+               fragment: Some(wgpu::FragmentState {
+                  module: &shader,
+                  entry_point: "main_fs",
+                  targets: &[Some(context.gpu.color_target_state(blend_mode))],
+               }),
+               multiview: None,
+            })
+         });
 
       Self {
          vertex_buffer,
@@ -153,7 +154,7 @@ impl Text {
       }
 
       render_pass.push_debug_group("Text");
-      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode));
+      render_pass.set_pipeline(self.render_pipelines.get(context.blend_mode, context.sample_count));
       render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
       render_pass.set_bind_group(2, context.model_transform_bind_group, &[]);
       render_pass.set_bind_group(3, context.scene_uniform_bind_group, &[]);
@@ -5,9 +5,9 @@ use glam::{vec2, Vec2};
 use netcanv_renderer::paws::{
    vector, AlignH, AlignV, Alignment, Color, LineCap, Point, Rect, Renderer, Vector,
 };
-use netcanv_renderer::{BlendMode, Font as _, Framebuffer as _, RenderBackend, ScalingFilter};
+use netcanv_renderer::{BlendMode, Font as _, Framebuffer as _, RenderBackend};
 
-use crate::common::{paws_color_to_wgpu, vector_to_vec2};
+use crate::common::{intersect_rects, paws_color_to_wgpu, vector_to_vec2};
 use crate::gpu::Gpu;
 use crate::image::Image;
 use crate::transform::{Transform, TransformState};
@@ -44,7 +44,7 @@ bitflags! {
 
 impl Default for BlendFlags {
    fn default() -> Self {
-      Self::ANTIALIAS
+      Self::ANTIALIAS | Self::PREMULTIPLY_ALPHA
    }
 }
 
@@ -62,6 +62,7 @@ pub(crate) struct FlushContext<'flush> {
    pub model_transform_bind_group: &'flush wgpu::BindGroup,
    pub scene_uniform_bind_group: &'flush wgpu::BindGroup,
    pub blend_mode: BlendMode,
+   pub sample_count: u32,
 }
 
 impl WgpuBackend {
@@ -112,11 +113,15 @@ impl WgpuBackend {
       {
          profiling::scope!("WgpuBackend::flush::encode_render_pass");
 
+         let (view, resolve_target) = match &self.gpu.current_msaa_target {
+            Some(msaa_target) => (msaa_target, Some(self.gpu.render_target())),
+            None => (self.gpu.render_target(), None),
+         };
          let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some(&label),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-               view: self.gpu.render_target(),
-               resolve_target: None,
+               view,
+               resolve_target,
                ops: clear_ops.color,
             })],
             depth_stencil_attachment: None,
@@ -125,21 +130,25 @@ impl WgpuBackend {
          });
 
          if let Some(clip_rect) = transform_state.clip {
+            let scale = self.gpu.current_render_target_scale;
             render_pass.set_scissor_rect(
-               clip_rect.x() as u32,
-               clip_rect.y() as u32,
-               clip_rect.width() as u32,
-               clip_rect.height() as u32,
+               (clip_rect.x() * scale) as u32,
+               (clip_rect.y() * scale) as u32,
+               (clip_rect.width() * scale) as u32,
+               (clip_rect.height() * scale) as u32,
             );
          }
 
          let mut context = FlushContext {
             gpu: &self.gpu,
             model_transform_bind_group,
-            scene_uniform_bind_group: self
-               .scene_uniform_cache
-               .bind_group(&self.gpu, self.gpu.current_render_target_size),
+            scene_uniform_bind_group: self.scene_uniform_cache.bind_group(
+               &self.gpu,
+               self.gpu.current_render_target_size,
+               self.gpu.current_render_target_scale,
+            ),
             blend_mode: transform_state.blend_mode,
+            sample_count: self.gpu.current_sample_count(),
          };
 
          self.rounded_rects.flush(&mut context, &mut render_pass);
@@ -177,8 +186,9 @@ impl WgpuBackend {
 
    fn blend_flags(&self) -> BlendFlags {
       match self.current_transform().blend_mode {
-         BlendMode::Replace => BlendFlags::empty(),
-         BlendMode::Invert => BlendFlags::ANTIALIAS | BlendFlags::PREMULTIPLY_ALPHA,
+         // Render targets always hold premultiplied colors, so that has to be respected even when
+         // replacing pixels outright.
+         BlendMode::Replace => BlendFlags::PREMULTIPLY_ALPHA,
          _ => BlendFlags::default(),
       }
    }
@@ -226,12 +236,9 @@ impl Renderer for WgpuBackend {
    fn clip(&mut self, rect: Rect) {
       self.flush("clip");
       let rect = self.current_transform().transform.translate_rect(rect.sort());
+      // Nested clips can only ever shrink the clipped area.
       let clip = if let Some(existing_clip) = self.current_transform().clip {
-         let left = existing_clip.left().max(rect.left());
-         let top = existing_clip.top().max(rect.top());
-         let right = existing_clip.right().min(rect.right());
-         let bottom = existing_clip.bottom().min(rect.right());
-         Rect::new((left, top), (right - left, bottom - top))
+         intersect_rects(existing_clip, rect)
       } else {
          rect
       };
@@ -338,7 +345,7 @@ impl RenderBackend for WgpuBackend {
       self.create_image_from_rgba_impl(width, height, pixel_data)
    }
 
-   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Self::Font {
+   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Option<Self::Font> {
       Font::new(
          Rc::clone(&self.text_renderer.caches),
          data.to_owned(),
@@ -353,7 +360,9 @@ impl RenderBackend for WgpuBackend {
    fn draw_to(&mut self, framebuffer: &Self::Framebuffer, f: impl FnOnce(&mut Self)) {
       self.flush("before draw_to");
       let target = self.gpu.current_render_target.take();
+      let msaa_target = self.gpu.current_msaa_target.take();
       let previous_size = self.gpu.current_render_target_size;
+      let previous_scale = self.gpu.current_render_target_scale;
       self.gpu.current_render_target = Some(
          framebuffer
             .texture_view
@@ -361,11 +370,14 @@ impl RenderBackend for WgpuBackend {
             .expect("draw_to may not be called reentrantly on one framebuffer"),
       );
       self.gpu.current_render_target_size = framebuffer.size();
+      self.gpu.current_render_target_scale = 1.0;
       f(self);
       self.flush("after draw_to");
       framebuffer.texture_view.set(self.gpu.current_render_target.take());
       self.gpu.current_render_target = target;
+      self.gpu.current_msaa_target = msaa_target;
       self.gpu.current_render_target_size = previous_size;
+      self.gpu.current_render_target_scale = previous_scale;
    }
 
    fn clear(&mut self, color: Color) {
@@ -377,7 +389,14 @@ impl RenderBackend for WgpuBackend {
       {
          let rect = self.current_transform().transform.translate_rect(rect);
          self.switch_pass(Pass::Images);
-         self.images.add(rect, image.color, image.index, ScalingFilter::default());
+         self.images.add(
+            rect,
+            image.color,
+            image.index,
+            image.scaling_filter,
+            false,
+            1.0,
+         );
          if self.images.needs_flush() {
             self.flush("image");
          }
@@ -385,6 +404,15 @@ impl RenderBackend for WgpuBackend {
    }
 
    fn framebuffer(&mut self, rect: Rect, framebuffer: &Self::Framebuffer) {
+      self.framebuffer_with_opacity(rect, framebuffer, 1.0);
+   }
+
+   fn framebuffer_with_opacity(
+      &mut self,
+      rect: Rect,
+      framebuffer: &Self::Framebuffer,
+      opacity: f32,
+   ) {
       let rect = self.current_transform().transform.translate_rect(rect);
       self.switch_pass(Pass::Images);
       self.images.add(
@@ -392,6 +420,8 @@ impl RenderBackend for WgpuBackend {
          None,
          framebuffer.image_storage_index,
          framebuffer.scaling_filter,
+         true,
+         opacity,
       );
       if self.images.needs_flush() {
          self.flush("framebuffer");
@@ -408,6 +438,30 @@ impl RenderBackend for WgpuBackend {
       framebuffer.upload(&self.gpu, position, size, pixels);
    }
 
+   fn copy_framebuffer(
+      &mut self,
+      source: &Self::Framebuffer,
+      source_position: (u32, u32),
+      size: (u32, u32),
+      destination: &Self::Framebuffer,
+      destination_position: (u32, u32),
+   ) {
+      // Anything drawn so far may end up in either of the framebuffers, so it has to be submitted
+      // before the copy.
+      self.flush("copy_framebuffer");
+      let mut encoder = self.gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+         label: Some("Framebuffer Copy"),
+      });
+      source.copy_to(
+         &mut encoder,
+         source_position,
+         size,
+         destination,
+         destination_position,
+      );
+      self.command_buffers.push(encoder.finish());
+   }
+
    fn download_framebuffer(
       &mut self,
       framebuffer: &Self::Framebuffer,
@@ -426,6 +480,17 @@ impl RenderBackend for WgpuBackend {
       self.current_transform_mut().transform = state.transform.scale(vector_to_vec2(scale));
    }
 
+   fn set_screen_scale(&mut self, scale: f32) {
+      self.screen_scale = scale;
+   }
+
+   fn rotate(&mut self, angle: f32) {
+      // Same as with scaling, rotation always ends up producing a matrix.
+      self.flush("rotate");
+      let state = self.current_transform();
+      self.current_transform_mut().transform = state.transform.rotate(angle);
+   }
+
    fn set_blend_mode(&mut self, new_blend_mode: BlendMode) {
       if new_blend_mode != self.current_transform().blend_mode {
          self.flush("set_blend_mode");
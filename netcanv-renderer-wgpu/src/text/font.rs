@@ -13,13 +13,12 @@ pub struct Font {
 }
 
 impl Font {
-   pub(crate) fn new(caches: Rc<RefCell<Caches>>, data: Vec<u8>, size: f32) -> Self {
-      Self {
-         // TODO: Could probably use better error handling.
-         data: Rc::new(FontData::new(data).expect("failed to load font")),
+   pub(crate) fn new(caches: Rc<RefCell<Caches>>, data: Vec<u8>, size: f32) -> Option<Self> {
+      Some(Self {
+         data: Rc::new(FontData::new(data).ok()?),
          caches,
          size,
-      }
+      })
    }
 
    const SIZE_GRANULARITY: f32 = 0.5;
@@ -0,0 +1,30 @@
+//! Conversions between straight and premultiplied alpha.
+//!
+//! Backends store framebuffer contents with premultiplied alpha, such that translucent content
+//! blends and filters the same way regardless of the backend. Pixels passed to and from
+//! [`RenderBackend`][crate::RenderBackend] are always in straight alpha, and can be converted
+//! using these functions.
+
+/// Multiplies the color channels of RGBA pixels by their alpha channel.
+pub fn premultiply(pixels: &mut [u8]) {
+   for pixel in pixels.chunks_exact_mut(4) {
+      let alpha = pixel[3] as u32;
+      for channel in &mut pixel[..3] {
+         *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+      }
+   }
+}
+
+/// Divides the color channels of premultiplied RGBA pixels by their alpha channel.
+///
+/// Fully transparent pixels end up transparent black, as their color cannot be recovered.
+pub fn unpremultiply(pixels: &mut [u8]) {
+   for pixel in pixels.chunks_exact_mut(4) {
+      let alpha = pixel[3] as u32;
+      for channel in &mut pixel[..3] {
+         *channel = (*channel as u32 * 255 + alpha / 2)
+            .checked_div(alpha)
+            .map_or(0, |straight| straight.min(255) as u8);
+      }
+   }
+}
@@ -1,3 +1,15 @@
+//! The rendering abstraction used by NetCanv.
+//!
+//! This crate defines the traits a render backend has to implement: [`RenderBackend`] for the
+//! backend itself, and [`Image`], [`Framebuffer`], and [`Font`] for the resources it creates.
+//! Basic shapes and text are drawn using the [`Renderer`] trait from [`paws`], which is
+//! re-exported for convenience.
+//!
+//! The traits don't depend on NetCanv in any way, so a backend such as `netcanv-renderer-opengl`
+//! can be used on its own to render into any window.
+
+pub mod alpha;
+
 pub use paws;
 use paws::{vector, Color, Point, Rect, Renderer, Vector};
 
@@ -23,9 +35,9 @@ pub trait Font {
 /// Image and framebuffer scaling filters.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScalingFilter {
-   /// Nearest neighbor. The default filter.
+   /// Nearest neighbor. The default filter for framebuffers, such that canvas pixels stay sharp.
    Nearest,
-   /// Bilinear.
+   /// Bilinear. The default filter for images.
    Linear,
 }
 
@@ -49,6 +61,12 @@ pub trait Image {
    /// Returns the size of the image.
    fn size(&self) -> (u32, u32);
 
+   /// Sets the filter used for upscaling and downscaling the image. Images are scaled with
+   /// [`ScalingFilter::Linear`] unless specified otherwise.
+   ///
+   /// Colorized images are separate images, so each of them can have its own filter.
+   fn set_scaling_filter(&mut self, filter: ScalingFilter);
+
    /// Returns the width of the image.
    fn width(&self) -> u32 {
       self.size().0
@@ -87,9 +105,22 @@ pub trait Framebuffer {
 
    /// Sets the filter used for upscaling and downscaling the framebuffer.
    fn set_scaling_filter(&mut self, filter: ScalingFilter);
+
+   /// Enables or disables mipmapping, which makes the framebuffer look smoother when it's drawn
+   /// scaled down.
+   ///
+   /// Mipmaps are regenerated lazily, when the framebuffer is drawn after its contents change.
+   ///
+   /// Not every backend supports mipmapping. Those that don't ignore this setting, and minify the
+   /// framebuffer using its scaling filter instead. Currently only the OpenGL backend supports it.
+   fn set_mipmapping(&mut self, enabled: bool);
 }
 
 /// Blending modes.
+///
+/// All modes operate on premultiplied colors, so that drawing translucent content into a
+/// transparent framebuffer and then drawing that framebuffer gives the same result as drawing the
+/// content directly.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum BlendMode {
@@ -101,15 +132,22 @@ pub enum BlendMode {
    Add = 2,
    /// Inverts colors.
    Invert = 3,
+   /// Multiplies colors together, darkening the destination. Fully transparent parts of the
+   /// destination are left untouched.
+   Multiply = 4,
+   /// Multiplies the inverses of colors together, lightening the destination.
+   Screen = 5,
 }
 
 impl BlendMode {
    // NOTE: Indices here must match those of the enum.
-   pub const VARIANTS: [BlendMode; 4] = [
+   pub const VARIANTS: [BlendMode; 6] = [
       BlendMode::Replace,
       BlendMode::Alpha,
       BlendMode::Add,
       BlendMode::Invert,
+      BlendMode::Multiply,
+      BlendMode::Screen,
    ];
 }
 
@@ -122,7 +160,9 @@ pub trait RenderBackend: Renderer {
    fn create_image_from_rgba(&mut self, width: u32, height: u32, pixel_data: &[u8]) -> Self::Image;
 
    /// Creates a new font from the given in-memory TTF/OTF file, with a set default size.
-   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Self::Font;
+   ///
+   /// Returns `None` if the data does not contain a valid font.
+   fn create_font_from_memory(&mut self, data: &[u8], default_size: f32) -> Option<Self::Font>;
 
    /// Creates a new framebuffer of the given size.
    ///
@@ -137,13 +177,50 @@ pub trait RenderBackend: Renderer {
    fn clear(&mut self, color: Color);
 
    /// Draws an image such that it fills the given rectangle.
+   ///
+   /// The rectangle is subject to the full transform matrix, so images can be rotated and scaled
+   /// using [`RenderBackend::rotate`] and [`RenderBackend::scale`].
    fn image(&mut self, rect: Rect, image: &Self::Image);
 
    /// Draws a framebuffer such that it fills the given rectangle.
    ///
+   /// Like with [`RenderBackend::image`], the rectangle is subject to the full transform matrix.
+   ///
    /// Drawing the framebuffer that is currently being rendered to is undefined behavior.
    fn framebuffer(&mut self, rect: Rect, framebuffer: &Self::Framebuffer);
 
+   /// Draws a framebuffer such that it fills the given rectangle, with its opacity multiplied by
+   /// `opacity`, which ranges from 0 to 1.
+   ///
+   /// Like with [`RenderBackend::framebuffer`], drawing the framebuffer that is currently being
+   /// rendered to is undefined behavior.
+   fn framebuffer_with_opacity(
+      &mut self,
+      rect: Rect,
+      framebuffer: &Self::Framebuffer,
+      opacity: f32,
+   );
+
+   /// Draws an image such that it fills the given rectangle, rotated around the rectangle's center
+   /// by the given angle, in radians.
+   fn image_rotated(&mut self, rect: Rect, angle: f32, image: &Self::Image) {
+      self.push();
+      self.translate(rect.center());
+      self.rotate(angle);
+      self.image(Rect::new(-rect.size / 2.0, rect.size), image);
+      self.pop();
+   }
+
+   /// Draws a framebuffer such that it fills the given rectangle, rotated around the rectangle's
+   /// center by the given angle, in radians.
+   fn framebuffer_rotated(&mut self, rect: Rect, angle: f32, framebuffer: &Self::Framebuffer) {
+      self.push();
+      self.translate(rect.center());
+      self.rotate(angle);
+      self.framebuffer(Rect::new(-rect.size / 2.0, rect.size), framebuffer);
+      self.pop();
+   }
+
    /// Uploads RGBA pixels to the framebuffer.
    ///
    /// `pixels`'s length must be equal to `width * height * 4`.
@@ -155,6 +232,20 @@ pub trait RenderBackend: Renderer {
       pixels: &[u8],
    );
 
+   /// Copies a region of one framebuffer into another, without leaving the graphics card.
+   ///
+   /// The pixels are copied verbatim, without any blending or scaling. The region must lie within
+   /// the bounds of both framebuffers, and `source` and `destination` must not be the same
+   /// framebuffer.
+   fn copy_framebuffer(
+      &mut self,
+      source: &Self::Framebuffer,
+      source_position: (u32, u32),
+      size: (u32, u32),
+      destination: &Self::Framebuffer,
+      destination_position: (u32, u32),
+   );
+
    /// Downloads RGBA pixels from the framebuffer into a buffer.
    fn download_framebuffer(
       &mut self,
@@ -176,6 +267,17 @@ pub trait RenderBackend: Renderer {
    /// Scales the transform matrix by the given factor.
    fn scale(&mut self, scale: Vector);
 
+   /// Sets the factor everything drawn onto the window is scaled up by, so that the UI can be laid
+   /// out in logical pixels on high-density displays. Drawing to framebuffers is not affected.
+   ///
+   /// Takes effect starting with the next frame.
+   fn set_screen_scale(&mut self, scale: f32);
+
+   /// Rotates the transform matrix by the given angle, in radians.
+   ///
+   /// Positive angles rotate clockwise, as the Y axis points downwards.
+   fn rotate(&mut self, angle: f32);
+
    /// Sets the current blend mode.
    ///
    /// Blend modes are part of the transformation stack. If used inside `push()` and `pop()`,
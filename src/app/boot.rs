@@ -76,6 +76,10 @@ impl AppState for State {
       }
    }
 
+   fn needs_redraw(&self) -> bool {
+      self.peer.as_ref().map_or(false, |peer| peer.needs_redraw())
+   }
+
    fn next_state(self: Box<Self>, renderer: &mut Backend) -> Box<dyn AppState> {
       let mut connected = false;
       if let Some(peer) = &self.peer {
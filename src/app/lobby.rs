@@ -1,7 +1,7 @@
 // The lobby app state.
 
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use netcanv_i18n::translate_enum::TranslateEnum;
@@ -10,6 +10,7 @@ use netcanv_renderer::paws::{vector, AlignH, AlignV, Color, Layout, LineCap, Rec
 use netcanv_renderer::{Font, Image as ImageTrait, RenderBackend};
 use nysa::global as bus;
 use rfd::FileDialog;
+use web_time::{Duration, Instant};
 
 use crate::app::{paint, AppState, StateArgs};
 use crate::assets::{self, Assets, ColorScheme};
@@ -21,6 +22,7 @@ use crate::net::socket::SocketSystem;
 use crate::strings::Strings;
 use crate::ui::view::View;
 use crate::ui::*;
+use crate::wake;
 
 /// Colors used in the lobby screen.
 #[derive(Clone)]
@@ -53,6 +55,9 @@ pub struct State {
    nickname_field: TextField,
    relay_field: TextField,
    room_id_field: TextField,
+   join_button: FocusHandle,
+   host_button: FocusHandle,
+   host_from_file_button: FocusHandle,
 
    join_expand: Expand,
    host_expand: Expand,
@@ -60,6 +65,10 @@ pub struct State {
    main_view: View,
    panel_view: View,
    language_menu: ContextMenu,
+   font_menu: ContextMenu,
+   /// The font files available in the fonts directory. This is refreshed whenever the font menu
+   /// is opened, such that fonts can be added without restarting the app.
+   font_files: Vec<PathBuf>,
 
    // net
    status: Status,
@@ -69,6 +78,8 @@ pub struct State {
 
 impl State {
    const BANNER_HEIGHT: f32 = 128.0;
+   /// The time between two frames of the banner's animation, while nothing else is going on.
+   const BANNER_FRAME_TIME: Duration = Duration::from_millis(50);
    const MENU_HEIGHT: f32 = 294.0;
    const STATUS_HEIGHT: f32 = 8.0 + 48.0;
 
@@ -86,6 +97,9 @@ impl State {
          nickname_field,
          relay_field,
          room_id_field: TextField::new(None),
+         join_button: Default::default(),
+         host_button: Default::default(),
+         host_from_file_button: Default::default(),
 
          join_expand: Expand::new(true),
          host_expand: Expand::new(false),
@@ -94,9 +108,11 @@ impl State {
             Self::VIEW_BOX_WIDTH,
             Self::BANNER_HEIGHT + Self::VIEW_BOX_HEIGHT + Self::STATUS_HEIGHT,
          )),
-         panel_view: View::new((40.0, 4.0 + 3.0 * 36.0)),
-         // The size of the language menu is computed later.
+         panel_view: View::new((40.0, 4.0 + 4.0 * 36.0)),
+         // The sizes of the language and font menus are computed later.
          language_menu: ContextMenu::new((0.0, 0.0)),
+         font_menu: ContextMenu::new((0.0, 0.0)),
+         font_files: Vec::new(),
 
          assets,
 
@@ -105,6 +121,8 @@ impl State {
          image_file: None,
       };
       this.room_id_field.set_focus(true);
+      // Process the first frame right away, instead of waiting for input to arrive.
+      wake::wake();
       this
    }
 
@@ -162,6 +180,7 @@ impl State {
       }
 
       ui.image(image_rect, &self.assets.banner.base);
+      wake::at(Instant::now() + Self::BANNER_FRAME_TIME);
 
       ui.pop();
    }
@@ -274,7 +293,7 @@ impl State {
          if Button::with_text(
             ui,
             input,
-            &button,
+            &button.clone().focus(&self.join_button, self.assets.colors.focus_outline),
             &self.assets.sans,
             &self.assets.tr.lobby_join,
          )
@@ -347,7 +366,7 @@ impl State {
          if Button::with_text(
             ui,
             input,
-            &button,
+            &button.clone().focus(&self.host_button, self.assets.colors.focus_outline),
             &self.assets.sans,
             &self.assets.tr.lobby_host,
          )
@@ -359,7 +378,10 @@ impl State {
          if Button::with_text(
             ui,
             input,
-            &button,
+            &button.clone().focus(
+               &self.host_from_file_button,
+               self.assets.colors.focus_outline,
+            ),
             &self.assets.sans,
             &self.assets.tr.lobby_host_from_file,
          )
@@ -372,6 +394,7 @@ impl State {
                   &["png", "jpg", "jpeg", "jfif"],
                )
                .add_filter(&self.assets.tr.fd_netcanv_canvas, &["toml"])
+               .add_filter(&self.assets.tr.fd_ora_file, &["ora"])
                .pick_file()
             {
                Some(path) => {
@@ -389,14 +412,28 @@ impl State {
 
       ui.pop();
 
-      chain_focus(
-         input,
-         &mut [
-            &mut self.nickname_field,
-            &mut self.relay_field,
-            &mut self.room_id_field,
-         ],
-      );
+      // Only the elements inside of the expanded section can be focused.
+      if self.join_expand.is_expanded() {
+         chain_focus(
+            input,
+            &mut [
+               &mut self.nickname_field,
+               &mut self.relay_field,
+               &mut self.room_id_field,
+               &mut self.join_button,
+            ],
+         );
+      } else {
+         chain_focus(
+            input,
+            &mut [
+               &mut self.nickname_field,
+               &mut self.relay_field,
+               &mut self.host_button,
+               &mut self.host_from_file_button,
+            ],
+         );
+      }
 
       None
    }
@@ -452,12 +489,19 @@ impl State {
             &self.assets.sans,
             Tooltip::left(match config().ui.color_scheme {
                config::ColorScheme::Light => &self.assets.tr.switch_to_dark_mode,
-               config::ColorScheme::Dark => &self.assets.tr.switch_to_light_mode,
+               config::ColorScheme::Dark => &self.assets.tr.switch_to_high_contrast_mode,
+               config::ColorScheme::HighContrast | config::ColorScheme::Custom => {
+                  &self.assets.tr.switch_to_light_mode
+               }
             }),
          ),
          match config().ui.color_scheme {
-            config::ColorScheme::Dark => &self.assets.icons.lobby.light_mode,
-            config::ColorScheme::Light => &self.assets.icons.lobby.dark_mode,
+            config::ColorScheme::HighContrast | config::ColorScheme::Custom => {
+               &self.assets.icons.lobby.light_mode
+            }
+            config::ColorScheme::Light | config::ColorScheme::Dark => {
+               &self.assets.icons.lobby.dark_mode
+            }
          },
       )
       .clicked()
@@ -465,11 +509,14 @@ impl State {
          config::write(|config| {
             config.ui.color_scheme = match config.ui.color_scheme {
                config::ColorScheme::Light => config::ColorScheme::Dark,
-               config::ColorScheme::Dark => config::ColorScheme::Light,
+               config::ColorScheme::Dark => config::ColorScheme::HighContrast,
+               config::ColorScheme::HighContrast | config::ColorScheme::Custom => {
+                  config::ColorScheme::Light
+               }
             };
          });
          self.save_config();
-         self.assets.colors = ColorScheme::from(config().ui.color_scheme);
+         self.assets.colors = ColorScheme::load(config().ui.color_scheme);
       }
 
       ui.space(4.0);
@@ -500,6 +547,38 @@ impl State {
 
       ui.space(4.0);
 
+      let font_button = Button::with_icon(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &self.assets.colors.action_button).height(32.0).pill().tooltip(
+            &self.assets.sans,
+            Tooltip::left(&self.assets.tr.fonts.title),
+         ),
+         &self.assets.icons.lobby.font,
+      );
+      // Each of the two sections has a label, and a button for the bundled font along with one
+      // for each font file.
+      let n_buttons = 2.0 * (2.0 + self.font_files.len() as f32);
+      let font_menu_rect = TooltipPosition::Left.compute_rect(
+         ui,
+         font_button.group(),
+         vector(
+            192.0,
+            16.0 + n_buttons * 24.0 + (n_buttons - 1.0) * 4.0 + 8.0,
+         ),
+         TooltipLayout {
+            spacing: 24.0,
+            root_padding: 8.0,
+         },
+      );
+      view::layout::absolute(&mut self.font_menu.view, font_menu_rect);
+      if font_button.clicked() {
+         self.font_files = config::font_files();
+         self.font_menu.toggle();
+      }
+
+      ui.space(4.0);
+
       if assets::has_license_page()
          && Button::with_icon(
             ui,
@@ -559,6 +638,94 @@ impl State {
       }
    }
 
+   /// Processes a section of the font menu, with a label and a button for each font.
+   ///
+   /// Returns the font that was clicked, where `None` stands for the bundled font.
+   fn process_font_menu_section(
+      &self,
+      ui: &mut Ui,
+      input: &mut Input,
+      label: &str,
+      current: Option<&Path>,
+   ) -> Option<Option<PathBuf>> {
+      ui.push((ui.width(), 24.0), Layout::Freeform);
+      ui.text(
+         &self.assets.sans_bold,
+         label,
+         self.assets.colors.text,
+         (AlignH::Left, AlignV::Middle),
+      );
+      ui.pop();
+      ui.space(4.0);
+
+      let mut clicked = None;
+      let default = (self.assets.tr.fonts.default.as_str(), None);
+      let files =
+         self.font_files.iter().map(|file| (file.to_str().unwrap_or_default(), Some(file)));
+      for (name, file) in std::iter::once(default).chain(files) {
+         if Button::with_text_width(
+            ui,
+            input,
+            &ButtonArgs::new(ui, &self.assets.colors.action_button).height(24.0).pill(),
+            if file.map(PathBuf::as_path) == current {
+               &self.assets.sans_bold
+            } else {
+               &self.assets.sans
+            },
+            name,
+            ui.width(),
+         )
+         .clicked()
+         {
+            clicked = Some(file.cloned());
+         }
+         ui.space(4.0);
+      }
+      clicked
+   }
+
+   /// Processes the menu for picking the interface and text tool fonts.
+   fn process_font_menu(&mut self, ui: &mut Ui, input: &mut Input) {
+      if self
+         .font_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &self.assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(8.0);
+         let ui_font = self.process_font_menu_section(
+            ui,
+            input,
+            &self.assets.tr.fonts.interface,
+            config().fonts.ui.as_deref(),
+         );
+         ui.space(8.0);
+         let text_font = self.process_font_menu_section(
+            ui,
+            input,
+            &self.assets.tr.fonts.text_tool,
+            config().fonts.text.as_deref(),
+         );
+         if ui_font.is_some() || text_font.is_some() {
+            config::write(|config| {
+               if let Some(file) = ui_font {
+                  config.fonts.ui = file;
+               }
+               if let Some(file) = text_font {
+                  config.fonts.text = file;
+               }
+            });
+            self.assets.reload_fonts(ui.render());
+         }
+         self.font_menu.end(ui);
+      }
+   }
+
    /// Checks whether a nickname is valid.
    fn validate_nickname(tr: &Strings, nickname: &str) -> Result<(), Status> {
       const MAX_LEN: usize = 16;
@@ -674,9 +841,10 @@ impl AppState for State {
       self.process_icon_panel(ui, input);
       self.panel_view.end(ui);
 
-      // Language menu
+      // Language and font menus
 
       self.process_language_menu(ui, input);
+      self.process_font_menu(ui, input);
 
       for message in &bus::retrieve_all::<Error>() {
          let error = message.consume().0;
@@ -698,6 +866,11 @@ impl AppState for State {
       }
    }
 
+   /// Keeps redrawing while connecting to the relay.
+   fn needs_redraw(&self) -> bool {
+      self.peer.as_ref().map_or(false, |peer| peer.needs_redraw())
+   }
+
    fn next_state(self: Box<Self>, renderer: &mut Backend) -> Box<dyn AppState> {
       let mut connected = false;
       if let Some(peer) = &self.peer {
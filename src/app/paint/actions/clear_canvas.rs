@@ -0,0 +1,76 @@
+//! The `Clear canvas` action.
+
+use nysa::global as bus;
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+use crate::common::Log;
+
+use super::{Action, ActionArgs, ActionMessage};
+
+pub struct ClearCanvasAction {
+   icon: Image,
+}
+
+impl ClearCanvasAction {
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(
+            renderer,
+            include_bytes!("../../../assets/icons/delete-sweep.svg"),
+         ),
+      }
+   }
+
+   /// Asks the user whether they really want to erase everything on the canvas.
+   fn confirm(assets: &Assets) -> bool {
+      let strings = &assets.tr.clear_canvas;
+      let result = MessageDialog::new()
+         .set_title(&strings.title)
+         .set_description(&strings.description)
+         .set_level(MessageLevel::Warning)
+         .set_buttons(MessageButtons::OkCancelCustom(
+            strings.clear.clone(),
+            strings.cancel.clone(),
+         ))
+         .show();
+      match result {
+         MessageDialogResult::Custom(button) => button == strings.clear,
+         MessageDialogResult::Ok => true,
+         _ => false,
+      }
+   }
+}
+
+impl Action for ClearCanvasAction {
+   fn name(&self) -> &str {
+      "clear-canvas"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(
+      &mut self,
+      ActionArgs {
+         assets,
+         paint_canvas,
+         is_host,
+         ..
+      }: ActionArgs,
+   ) -> netcanv::Result<Option<ActionMessage>> {
+      if !is_host {
+         bus::push(Log(assets.tr.clear_canvas_host_only.clone()));
+         return Ok(None);
+      }
+      if !Self::confirm(assets) {
+         tracing::info!("not clearing the canvas");
+         return Ok(None);
+      }
+      tracing::info!("clearing the canvas");
+      paint_canvas.clear();
+      Ok(Some(ActionMessage::ClearCanvas))
+   }
+}
@@ -0,0 +1,36 @@
+//! The `Key bindings` action.
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+
+use super::{Action, ActionArgs, ActionMessage};
+
+/// Opens the window for rebinding keyboard shortcuts.
+pub struct EditKeymapAction {
+   icon: Image,
+}
+
+impl EditKeymapAction {
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(
+            renderer,
+            include_bytes!("../../../assets/icons/keyboard.svg"),
+         ),
+      }
+   }
+}
+
+impl Action for EditKeymapAction {
+   fn name(&self) -> &str {
+      "edit-keymap"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(&mut self, _args: ActionArgs) -> netcanv::Result<Option<ActionMessage>> {
+      Ok(Some(ActionMessage::EditKeymap))
+   }
+}
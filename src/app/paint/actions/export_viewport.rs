@@ -0,0 +1,166 @@
+//! The `Export visible area` action.
+
+use nysa::global as bus;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+use crate::common::Log;
+use crate::image_coder::{ExportFormat, ImageCoder};
+use crate::Error;
+
+use super::{Action, ActionArgs, ActionMessage, SaveToFileAction};
+
+pub struct ExportViewportAction {
+   icon: Image,
+}
+
+impl ExportViewportAction {
+   /// The quality of lossy images exported with the "high quality" option.
+   const HIGH_QUALITY: u8 = 95;
+   /// The quality of lossy images exported with the "smaller file" option.
+   const LOW_QUALITY: u8 = 75;
+
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/image.svg")),
+      }
+   }
+
+   /// Asks the user whether the visible area should be exported at the viewport's current zoom,
+   /// or at one image pixel per canvas pixel. Returns the scale to export at, or `None` if the
+   /// export was cancelled.
+   fn ask_for_scale(assets: &Assets, zoom: f32) -> Option<f32> {
+      let strings = &assets.tr.export_viewport;
+      let zoom_percent = format!("{:.0}", zoom * 100.0);
+      let result = MessageDialog::new()
+         .set_title(&strings.title)
+         .set_description(strings.description.format().with("zoom", zoom_percent.as_str()).done())
+         .set_level(MessageLevel::Info)
+         .set_buttons(MessageButtons::YesNoCancelCustom(
+            strings.current_zoom.clone(),
+            strings.actual_size.clone(),
+            strings.cancel.clone(),
+         ))
+         .show();
+      match result {
+         MessageDialogResult::Custom(button) if button == strings.current_zoom => Some(zoom),
+         MessageDialogResult::Custom(button) if button == strings.actual_size => Some(1.0),
+         MessageDialogResult::Yes => Some(zoom),
+         MessageDialogResult::No => Some(1.0),
+         _ => None,
+      }
+   }
+
+   /// Returns the name the exported file is given by default.
+   fn default_file_name() -> String {
+      let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+      format!("netcanv-{}.png", timestamp)
+   }
+
+   /// Asks the user about the quality a lossy image should be exported with. Returns `None` if
+   /// the export was cancelled.
+   fn ask_for_quality(assets: &Assets) -> Option<u8> {
+      let strings = &assets.tr.export_quality;
+      let result = MessageDialog::new()
+         .set_title(&strings.title)
+         .set_description(&strings.description)
+         .set_level(MessageLevel::Info)
+         .set_buttons(MessageButtons::YesNoCancelCustom(
+            strings.high_quality.clone(),
+            strings.smaller_file.clone(),
+            strings.cancel.clone(),
+         ))
+         .show();
+      match result {
+         MessageDialogResult::Custom(button) if button == strings.high_quality => {
+            Some(Self::HIGH_QUALITY)
+         }
+         MessageDialogResult::Custom(button) if button == strings.smaller_file => {
+            Some(Self::LOW_QUALITY)
+         }
+         MessageDialogResult::Yes => Some(Self::HIGH_QUALITY),
+         MessageDialogResult::No => Some(Self::LOW_QUALITY),
+         _ => None,
+      }
+   }
+}
+
+impl Action for ExportViewportAction {
+   fn name(&self) -> &str {
+      "export-viewport"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(
+      &mut self,
+      ActionArgs {
+         assets,
+         paint_canvas,
+         renderer,
+         viewport,
+         canvas_size,
+         ..
+      }: ActionArgs,
+   ) -> netcanv::Result<Option<ActionMessage>> {
+      let Some(scale) = Self::ask_for_scale(assets, viewport.zoom()) else {
+         return Ok(None);
+      };
+      let Some(path) = FileDialog::new()
+         .add_filter(&assets.tr.fd_png_file, &["png"])
+         .add_filter(&assets.tr.fd_jpeg_file, &["jpg", "jpeg"])
+         .add_filter(&assets.tr.fd_webp_file, &["webp"])
+         .set_file_name(Self::default_file_name())
+         .save_file()
+      else {
+         return Ok(None);
+      };
+      let format = ExportFormat::from_path(&path).ok_or(Error::UnsupportedExportFormat)?;
+      if path.exists() && !SaveToFileAction::confirm_overwrite(assets, &path) {
+         return Ok(None);
+      }
+      let quality = if format.is_lossy() {
+         let Some(quality) = Self::ask_for_quality(assets) else {
+            return Ok(None);
+         };
+         quality
+      } else {
+         Self::HIGH_QUALITY
+      };
+
+      // When the viewport is rotated, the whole bounding box of the visible area is exported,
+      // without the rotation.
+      let region = viewport.visible_rect(canvas_size);
+      tracing::info!("exporting {:?} at scale {} to {:?}", region, scale, path);
+      let image = paint_canvas.render_region(renderer, region, scale);
+      std::fs::write(&path, ImageCoder::encode_image(&image, format, quality)?)?;
+
+      let path = path.display().to_string();
+      bus::push(Log(
+         assets.tr.viewport_exported.format().with("path", path.as_str()).done(),
+      ));
+      Ok(None)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::path::Path;
+
+   use super::*;
+
+   #[test]
+   fn default_file_name_is_a_timestamped_png() {
+      let name = ExportViewportAction::default_file_name();
+      let timestamp = name.strip_prefix("netcanv-").and_then(|name| name.strip_suffix(".png"));
+      assert!(timestamp.unwrap().parse::<u64>().unwrap() > 0, "{name}");
+      assert!(matches!(
+         ExportFormat::from_path(Path::new(&name)),
+         Some(ExportFormat::Png)
+      ));
+   }
+}
@@ -1,18 +1,34 @@
 //! Overflow menu actions.
 
+mod clear_canvas;
+mod edit_keymap;
+mod export_viewport;
 mod leave_room;
+mod open_settings;
 mod save_to_file;
+mod share_viewport;
 
+pub use clear_canvas::*;
+pub use edit_keymap::*;
+pub use export_viewport::*;
 pub use leave_room::*;
+pub use open_settings::*;
 pub use save_to_file::*;
+pub use share_viewport::*;
+
+use netcanv_renderer::paws::Vector;
 
 use crate::assets::Assets;
 use crate::backend::{Backend, Image};
 use crate::paint_canvas::PaintCanvas;
 use crate::project_file::ProjectFile;
+use crate::viewport::Viewport;
 
 pub enum ActionMessage {
    LeaveTheRoom,
+   ClearCanvas,
+   EditKeymap,
+   OpenSettings,
 }
 
 pub trait Action {
@@ -37,6 +53,11 @@ pub struct ActionArgs<'a> {
    pub paint_canvas: &'a mut PaintCanvas,
    pub project_file: &'a mut ProjectFile,
    pub renderer: &'a mut Backend,
+   pub viewport: &'a Viewport,
+   /// The size of the canvas view, used for determining the area visible from the viewport.
+   pub canvas_size: Vector,
+   /// Whether this client is the host of the room.
+   pub is_host: bool,
 }
 
 fn _action_trait_must_be_object_safe(_action: Box<dyn Action>) {}
@@ -0,0 +1,36 @@
+//! The `Settings` action.
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+
+use super::{Action, ActionArgs, ActionMessage};
+
+/// Opens the settings window.
+pub struct OpenSettingsAction {
+   icon: Image,
+}
+
+impl OpenSettingsAction {
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(
+            renderer,
+            include_bytes!("../../../assets/icons/settings.svg"),
+         ),
+      }
+   }
+}
+
+impl Action for OpenSettingsAction {
+   fn name(&self) -> &str {
+      "open-settings"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(&mut self, _args: ActionArgs) -> netcanv::Result<Option<ActionMessage>> {
+      Ok(Some(ActionMessage::OpenSettings))
+   }
+}
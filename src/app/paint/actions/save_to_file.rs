@@ -1,41 +1,39 @@
 //! The `Save to file` action.
 
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use web_time::{Duration, Instant};
 
-use rfd::FileDialog;
+use nysa::global as bus;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 
 use crate::assets::Assets;
 use crate::backend::{Backend, Image};
+use crate::common::Log;
+use crate::config::config;
+use crate::wake;
 
 use super::{Action, ActionArgs, ActionMessage};
 
+/// Set when the user chooses not to be asked about overwriting files for the rest of the session.
+static SKIP_OVERWRITE_CONFIRMATION: AtomicBool = AtomicBool::new(false);
+
 pub struct SaveToFileAction {
    icon: Image,
    last_autosave: Instant,
 }
 
 impl SaveToFileAction {
-   const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
-
    pub fn new(renderer: &mut Backend) -> Self {
       Self {
          icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/save.svg")),
          last_autosave: Instant::now(),
       }
    }
-}
-
-impl Action for SaveToFileAction {
-   fn name(&self) -> &str {
-      "save-to-file"
-   }
-
-   fn icon(&self) -> &Image {
-      &self.icon
-   }
 
-   fn perform(
-      &mut self,
+   /// Asks the user where to save the canvas, and starts saving it there.
+   pub fn save_as(
       ActionArgs {
          assets,
          paint_canvas,
@@ -43,33 +41,157 @@ impl Action for SaveToFileAction {
          renderer,
          ..
       }: ActionArgs,
-   ) -> netcanv::Result<Option<ActionMessage>> {
+   ) -> netcanv::Result<()> {
       if let Some(path) = FileDialog::new()
          .add_filter(&assets.tr.fd_png_file, &["png"])
          .add_filter(&assets.tr.fd_netcanv_canvas, &["netcanv", "toml"])
+         .add_filter(&assets.tr.fd_ora_file, &["ora"])
          .save_file()
       {
-         project_file.save(renderer, Some(&path), paint_canvas)?
+         // Re-saving the current project is expected to replace it, so there's no need to ask.
+         let is_current_project = project_file.quick_save_path() == Some(path.as_path());
+         if path.exists() && !is_current_project && !Self::confirm_overwrite(assets, &path) {
+            return Ok(());
+         }
+         if path.extension().is_some_and(|extension| extension == "png") {
+            let Some(trim_borders) = Self::ask_for_trim_borders(assets) else {
+               return Ok(());
+            };
+            project_file.set_trim_borders(trim_borders);
+         }
+         project_file.save(renderer, Some(&path), paint_canvas)?;
+      }
+      Ok(())
+   }
+
+   /// Asks the user whether the existing file at `path` should be overwritten.
+   pub(super) fn confirm_overwrite(assets: &Assets, path: &Path) -> bool {
+      if SKIP_OVERWRITE_CONFIRMATION.load(Ordering::Relaxed) {
+         return true;
+      }
+      let strings = &assets.tr.overwrite_file;
+      let path_string = path.display().to_string();
+      let result = MessageDialog::new()
+         .set_title(&strings.title)
+         .set_description(strings.description.format().with("path", path_string.as_str()).done())
+         .set_level(MessageLevel::Warning)
+         .set_buttons(MessageButtons::YesNoCancelCustom(
+            strings.replace.clone(),
+            strings.replace_without_asking.clone(),
+            strings.cancel.clone(),
+         ))
+         .show();
+      match result {
+         MessageDialogResult::Custom(button) if button == strings.replace => true,
+         MessageDialogResult::Custom(button) if button == strings.replace_without_asking => {
+            SKIP_OVERWRITE_CONFIRMATION.store(true, Ordering::Relaxed);
+            true
+         }
+         MessageDialogResult::Yes => true,
+         _ => {
+            tracing::info!("not overwriting {:?}", path);
+            false
+         }
+      }
+   }
+
+   /// Asks the user whether the transparent borders around the drawing should be trimmed from a
+   /// PNG save. The option picked by `encoding.save_png_trim_borders` is listed first, as the
+   /// default. Returns `None` if the save was cancelled.
+   fn ask_for_trim_borders(assets: &Assets) -> Option<bool> {
+      let strings = &assets.tr.trim_borders;
+      let trim_by_default = config().encoding.save_png_trim_borders;
+      let (first, second) = if trim_by_default {
+         (&strings.trim, &strings.keep)
+      } else {
+         (&strings.keep, &strings.trim)
+      };
+      let result = MessageDialog::new()
+         .set_title(&strings.title)
+         .set_description(&strings.description)
+         .set_level(MessageLevel::Info)
+         .set_buttons(MessageButtons::YesNoCancelCustom(
+            first.clone(),
+            second.clone(),
+            strings.cancel.clone(),
+         ))
+         .show();
+      match result {
+         MessageDialogResult::Custom(button) if button == strings.trim => Some(true),
+         MessageDialogResult::Custom(button) if button == strings.keep => Some(false),
+         MessageDialogResult::Yes => Some(trim_by_default),
+         MessageDialogResult::No => Some(!trim_by_default),
+         _ => None,
       }
+   }
+
+   /// Starts saving the canvas to the path it was last saved to, without asking the user. If the
+   /// canvas hasn't been saved yet, falls back to [`SaveToFileAction::save_as`].
+   pub fn quick_save(args: ActionArgs) -> netcanv::Result<()> {
+      let Some(path) = args.project_file.quick_save_path().map(Path::to_path_buf) else {
+         return Self::save_as(args);
+      };
+      args.project_file.save(args.renderer, Some(&path), args.paint_canvas)?;
+      Ok(())
+   }
+
+   /// Lets the user know the canvas was saved successfully.
+   fn report_saved(assets: &Assets, path: &Path) {
+      tracing::info!("canvas saved to {:?}", path);
+      let path = path.display().to_string();
+      bus::push(Log(
+         assets.tr.canvas_saved.format().with("path", path.as_str()).done(),
+      ));
+   }
+}
+
+impl Action for SaveToFileAction {
+   fn name(&self) -> &str {
+      "save-to-file"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(&mut self, args: ActionArgs) -> netcanv::Result<Option<ActionMessage>> {
+      Self::save_as(args)?;
       Ok(None)
    }
 
    fn process(
       &mut self,
       ActionArgs {
+         assets,
          paint_canvas,
          project_file,
          renderer,
          ..
       }: ActionArgs,
    ) -> netcanv::Result<()> {
-      if project_file.filename().is_some() && self.last_autosave.elapsed() > Self::AUTOSAVE_INTERVAL
+      if let Some(result) = project_file.poll_save() {
+         let save = result?;
+         save.mark_chunks_saved(paint_canvas);
+         if save.is_explicit() {
+            Self::report_saved(assets, save.path());
+         } else {
+            tracing::info!("autosave complete");
+         }
+      }
+
+      let interval = Duration::from_secs(config().autosave.interval_secs);
+      if project_file.filename().is_some()
+         && !project_file.is_saving()
+         && !interval.is_zero()
+         && self.last_autosave.elapsed() > interval
       {
          tracing::info!("autosaving chunks");
          project_file.save(renderer, None, paint_canvas)?;
-         tracing::info!("autosave complete");
          self.last_autosave = Instant::now();
       }
+      if project_file.filename().is_some() && !interval.is_zero() {
+         wake::at(self.last_autosave + interval);
+      }
       Ok(())
    }
 }
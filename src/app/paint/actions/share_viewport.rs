@@ -0,0 +1,48 @@
+//! The `Share my view` action.
+
+use nysa::global as bus;
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+use crate::common::Log;
+use crate::config::{self, config};
+
+use super::{Action, ActionArgs, ActionMessage};
+
+/// Toggles whether the viewport is shared with other peers, so that they can follow it.
+pub struct ShareViewportAction {
+   icon: Image,
+}
+
+impl ShareViewportAction {
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/eye.svg")),
+      }
+   }
+}
+
+impl Action for ShareViewportAction {
+   fn name(&self) -> &str {
+      "share-viewport"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn perform(
+      &mut self,
+      ActionArgs { assets, .. }: ActionArgs,
+   ) -> netcanv::Result<Option<ActionMessage>> {
+      let share = !config().lobby.share_viewport;
+      tracing::info!("sharing viewport: {}", share);
+      config::write(|config| config.lobby.share_viewport = share);
+      let message = match share {
+         true => &assets.tr.viewport_shared,
+         false => &assets.tr.viewport_not_shared,
+      };
+      bus::push(Log(message.clone()));
+      Ok(None)
+   }
+}
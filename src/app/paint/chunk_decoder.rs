@@ -0,0 +1,94 @@
+//! A pool of worker threads for decoding chunks received over the network.
+
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+use image::RgbaImage;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::image_coder::ImageCoder;
+use crate::wake;
+
+/// Image data of a single chunk, waiting to be decoded.
+type DecodeJob = ((i32, i32), Vec<u8>);
+
+/// A decoded chunk image, or `None` if the chunk's image data could not be decoded.
+pub type DecodedChunk = ((i32, i32), Option<RgbaImage>);
+
+/// A bounded queue of chunks waiting to be decoded, backed by a fixed number of worker threads.
+///
+/// Decoded images are sent to the channel passed into [`ChunkDecoder::new`]. Chunks that fail to
+/// decode are sent as `None`, so that every queued chunk gets a reply.
+pub struct ChunkDecoder {
+   jobs: SyncSender<DecodeJob>,
+}
+
+impl ChunkDecoder {
+   /// The maximum number of worker threads.
+   const MAX_WORKERS: usize = 4;
+
+   /// The maximum number of chunks that may wait in the queue before new ones are rejected.
+   const QUEUE_CAPACITY: usize = 256;
+
+   /// Creates a new decoder and spawns its worker threads.
+   ///
+   /// The workers shut down once the decoder is dropped and the remaining jobs are processed.
+   pub fn new(decoded: UnboundedSender<DecodedChunk>) -> Self {
+      let (jobs, rx) = mpsc::sync_channel(Self::QUEUE_CAPACITY);
+      let rx = Arc::new(Mutex::new(rx));
+      let worker_count = std::thread::available_parallelism()
+         .map(|n| n.get())
+         .unwrap_or(1)
+         .clamp(1, Self::MAX_WORKERS);
+      for i in 0..worker_count {
+         let rx = Arc::clone(&rx);
+         let decoded = decoded.clone();
+         std::thread::Builder::new()
+            .name(format!("chunk decoder {i}"))
+            .spawn(move || Self::worker(&rx, &decoded))
+            .expect("cannot spawn chunk decoder thread");
+      }
+      Self { jobs }
+   }
+
+   /// Queues a chunk for decoding.
+   ///
+   /// If the queue is full, the chunk's image data is dropped and `false` is returned. In that
+   /// case the chunk should be downloaded again later.
+   pub fn decode(&self, chunk_position: (i32, i32), image_data: Vec<u8>) -> bool {
+      match self.jobs.try_send((chunk_position, image_data)) {
+         Ok(()) => true,
+         Err(TrySendError::Full(_)) => {
+            tracing::debug!("decode queue is full, dropping chunk {:?}", chunk_position);
+            false
+         }
+         Err(TrySendError::Disconnected(_)) => {
+            tracing::error!("all chunk decoder threads have exited");
+            false
+         }
+      }
+   }
+
+   /// The worker thread's main loop.
+   fn worker(rx: &Mutex<Receiver<DecodeJob>>, decoded: &UnboundedSender<DecodedChunk>) {
+      loop {
+         // The lock is released before decoding, so that other workers can pick up jobs meanwhile.
+         let job = rx.lock().expect("chunk decoder queue lock poisoned").recv();
+         let Ok((chunk_position, image_data)) = job else {
+            break;
+         };
+         let image = match ImageCoder::decode_network_data(&image_data) {
+            Ok(image) => Some(image),
+            Err(error) => {
+               tracing::error!("image decoding failed: {:?}", error);
+               None
+            }
+         };
+         if decoded.send((chunk_position, image)).is_err() {
+            // The paint state is gone, so there's nobody left to receive the images.
+            break;
+         }
+         wake::wake();
+      }
+   }
+}
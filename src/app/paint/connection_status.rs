@@ -0,0 +1,171 @@
+//! The connection status indicator in the bottom bar.
+
+use netcanv_renderer::paws::{vector, AlignH, Color};
+
+use crate::assets::Assets;
+use crate::net::peer::Peer;
+use crate::net::socket::{ConnectionHealth, ConnectionStatus};
+use crate::ui::view::View;
+use crate::ui::{Button, ButtonArgs, ContextMenu, ContextMenuArgs, Input, Tooltip, Ui, UiElements};
+
+/// The connection status indicator's color scheme.
+#[derive(Clone)]
+pub struct ConnectionStatusColors {
+   pub healthy: Color,
+   pub degraded: Color,
+   pub unhealthy: Color,
+}
+
+/// An icon showing how healthy the connection to the relay is. Clicking it opens a popup with
+/// details about the connection.
+pub struct ConnectionStatusIndicator {
+   details: ContextMenu,
+}
+
+impl ConnectionStatusIndicator {
+   /// The size of the details popup.
+   const DETAILS_SIZE: (f32, f32) = (256.0, 128.0);
+
+   /// Creates a new connection status indicator.
+   pub fn new() -> Self {
+      Self {
+         details: ContextMenu::new(Self::DETAILS_SIZE),
+      }
+   }
+
+   /// Returns the view of the details popup, so that it can be laid out.
+   pub fn details_view(&mut self) -> &mut View {
+      &mut self.details.view
+   }
+
+   /// Returns the color and description of the given connection status.
+   fn describe_status<'a>(assets: &'a Assets, status: ConnectionStatus) -> (Color, &'a str) {
+      let colors = &assets.colors.connection_status;
+      let strings = &assets.tr.connection_status;
+      match status {
+         ConnectionStatus::Healthy => (colors.healthy, &strings.healthy),
+         ConnectionStatus::Degraded => (colors.degraded, &strings.degraded),
+         ConnectionStatus::Unhealthy => (colors.unhealthy, &strings.unhealthy),
+      }
+   }
+
+   /// Processes the indicator's button. Clicking it toggles the details popup.
+   pub fn process_button(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      health: &ConnectionHealth,
+   ) {
+      let (color, description) = Self::describe_status(assets, health.status());
+      let height = ui.height();
+      if Button::process(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.action_button)
+            .tooltip(&assets.sans, Tooltip::top(description)),
+         Some(height),
+         |ui| {
+            ui.icon(
+               &assets.icons.status.connection,
+               color,
+               Some(vector(height, height)),
+            );
+         },
+      )
+      .clicked()
+      {
+         self.details.toggle();
+      }
+   }
+
+   /// Processes the indicator's button while the connection to the relay is being reestablished.
+   /// The tooltip shows which attempt at reconnecting is underway.
+   pub fn process_reconnecting(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      attempt: u32,
+   ) {
+      let description = assets
+         .tr
+         .connection_status
+         .reconnecting
+         .format()
+         .with("attempt", attempt)
+         .with("attempts", Peer::RECONNECT_ATTEMPTS)
+         .done();
+      let height = ui.height();
+      Button::process(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.action_button)
+            .tooltip(&assets.sans, Tooltip::top(&description)),
+         Some(height),
+         |ui| {
+            ui.icon(
+               &assets.icons.status.connection,
+               assets.colors.connection_status.unhealthy,
+               Some(vector(height, height)),
+            );
+         },
+      );
+   }
+
+   /// Processes the details popup, if it's open.
+   pub fn process_details(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      health: &ConnectionHealth,
+   ) {
+      if self
+         .details
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         let strings = &assets.tr.connection_status;
+         let (color, description) = Self::describe_status(assets, health.status());
+
+         ui.pad(16.0);
+         ui.vertical_label(
+            &assets.sans_bold,
+            &strings.title,
+            assets.colors.text,
+            AlignH::Left,
+         );
+         ui.space(8.0);
+         ui.vertical_label(&assets.sans, description, color, AlignH::Left);
+         ui.space(4.0);
+
+         let round_trip_time = match health.round_trip_time {
+            Some(round_trip_time) => strings
+               .round_trip_time
+               .format()
+               .with("milliseconds", round_trip_time.as_millis() as u64)
+               .done(),
+            None => strings.round_trip_time_unknown.clone(),
+         };
+         let last_received = strings
+            .last_received
+            .format()
+            .with("seconds", health.last_received.elapsed().as_secs())
+            .done();
+         let missed_pings = strings.missed_pings.format().with("count", health.missed_pings).done();
+         for line in [round_trip_time, last_received, missed_pings] {
+            ui.vertical_label(&assets.sans, &line, assets.colors.text, AlignH::Left);
+            ui.space(4.0);
+         }
+
+         self.details.end(ui);
+      }
+   }
+}
@@ -0,0 +1,140 @@
+//! The popup for jumping to a point on the canvas by typing in its coordinates.
+
+use netcanv_renderer::paws::{point, AlignH, Layout, Point};
+
+use crate::assets::Assets;
+use crate::ui::view::View;
+use crate::ui::{
+   chain_focus, Button, ButtonArgs, ContextMenu, ContextMenuArgs, Focus, Input, TextField,
+   TextFieldArgs, Ui, UiElements,
+};
+
+/// A popup with fields for the X and Y coordinates of the point to center the viewport on.
+pub struct GoToCoordinates {
+   popup: ContextMenu,
+   x_field: TextField,
+   y_field: TextField,
+   /// Whether the coordinates entered last time could not be parsed.
+   invalid: bool,
+}
+
+impl GoToCoordinates {
+   /// The size of the popup.
+   const SIZE: (f32, f32) = (256.0, 176.0);
+
+   /// Creates a new, closed popup.
+   pub fn new() -> Self {
+      Self {
+         popup: ContextMenu::new(Self::SIZE),
+         x_field: TextField::new(None),
+         y_field: TextField::new(None),
+         invalid: false,
+      }
+   }
+
+   /// Returns the view of the popup, so that it can be laid out.
+   pub fn view(&mut self) -> &mut View {
+      &mut self.popup.view
+   }
+
+   /// Returns whether the popup is open.
+   pub fn is_open(&self) -> bool {
+      self.popup.is_open()
+   }
+
+   /// Opens the popup, with the fields filled in with the given point, which should be the
+   /// viewport's current center.
+   pub fn open(&mut self, center: Point) {
+      self.x_field.set_text(format!("{:.0}", center.x));
+      self.y_field.set_text(format!("{:.0}", center.y));
+      self.x_field.set_focus(true);
+      self.y_field.set_focus(false);
+      self.invalid = false;
+      self.popup.open();
+   }
+
+   /// Parses the coordinates typed into the fields.
+   fn parse(&self) -> Option<Point> {
+      let x: f32 = self.x_field.text().trim().parse().ok()?;
+      let y: f32 = self.y_field.text().trim().parse().ok()?;
+      (x.is_finite() && y.is_finite()).then(|| point(x, y))
+   }
+
+   /// Processes the popup, if it's open. Returns the point to go to once the user confirms it.
+   pub fn process(&mut self, ui: &mut Ui, input: &mut Input, assets: &Assets) -> Option<Point> {
+      let mut target = None;
+      if self
+         .popup
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         let strings = &assets.tr.go_to_coordinates;
+
+         ui.pad(16.0);
+         ui.vertical_label(
+            &assets.sans_bold,
+            &strings.title,
+            assets.colors.text,
+            AlignH::Left,
+         );
+         ui.space(8.0);
+
+         let textfield = TextFieldArgs {
+            font: &assets.sans,
+            width: (ui.width() - 8.0) / 2.0,
+            colors: &assets.colors.text_field,
+            hint: None,
+         };
+         ui.push(
+            (ui.width(), TextField::labelled_height(textfield.font)),
+            Layout::Horizontal,
+         );
+         let x_field = self.x_field.with_label(ui, input, &assets.sans, &strings.x, textfield);
+         ui.space(8.0);
+         let y_field = self.y_field.with_label(ui, input, &assets.sans, &strings.y, textfield);
+         ui.pop();
+         ui.space(8.0);
+
+         ui.push((ui.width(), 32.0), Layout::HorizontalRev);
+         let go = Button::with_text(
+            ui,
+            input,
+            &ButtonArgs::new(ui, &assets.colors.button).height(32.0).pill(),
+            &assets.sans,
+            &strings.go,
+         )
+         .clicked();
+         ui.pop();
+         if self.invalid {
+            ui.space(8.0);
+            ui.vertical_label(
+               &assets.sans,
+               &strings.invalid,
+               assets.colors.error,
+               AlignH::Left,
+            );
+         }
+
+         chain_focus(input, &mut [&mut self.x_field, &mut self.y_field]);
+
+         if go || x_field.done() || y_field.done() {
+            match self.parse() {
+               Some(coordinates) => {
+                  target = Some(coordinates);
+                  self.popup.close();
+               }
+               None => self.invalid = true,
+            }
+         }
+
+         self.popup.end(ui);
+      }
+      target
+   }
+}
@@ -0,0 +1,113 @@
+//! The grid drawn over the canvas, and snapping points to it.
+
+use netcanv_renderer::paws::{point, Color, LineCap, Point, Renderer, Vector};
+
+use crate::backend::Backend;
+use crate::config::{config, GridConfig};
+use crate::paint_canvas::chunk::Chunk;
+use crate::viewport::Viewport;
+
+/// The minimum distance between grid lines on the screen. When zoomed out far enough for lines
+/// to get any closer than this, only every other line is drawn.
+const MIN_SCREEN_SPACING: f32 = 8.0;
+
+/// Returns the distance between grid lines, rounded to the nearest divisor of the chunk size.
+fn spacing(grid: &GridConfig) -> f32 {
+   // The chunk size is a power of two, so its divisors are powers of two, too.
+   let spacing = grid.spacing.clamp(1, Chunk::SIZE.0) as f32;
+   spacing.log2().round().exp2()
+}
+
+/// Snaps the point to the nearest intersection of grid lines, if the grid is shown and snapping
+/// is enabled. Otherwise, the point is returned unchanged.
+pub fn snap(position: Point) -> Point {
+   let grid = &config().grid;
+   if !(grid.show && grid.snap) {
+      return position;
+   }
+   snap_to_spacing(position, spacing(grid))
+}
+
+/// Snaps the point to the nearest intersection of grid lines with the given spacing.
+fn snap_to_spacing(position: Point, spacing: f32) -> Point {
+   point(
+      (position.x / spacing).round() * spacing,
+      (position.y / spacing).round() * spacing,
+   )
+}
+
+/// Draws the grid over the visible part of the canvas.
+///
+/// The viewport's transform must already be applied to the renderer. Lines are drawn 1 pixel
+/// wide on the screen, no matter the zoom level.
+pub fn draw(renderer: &mut Backend, viewport: &Viewport, canvas_size: Vector) {
+   let grid = &config().grid;
+   let mut spacing = spacing(grid);
+   while viewport.to_screen_length(spacing) < MIN_SCREEN_SPACING {
+      spacing *= 2.0;
+   }
+   let (r, g, b, a) = grid.color;
+   let color = Color::new(r, g, b, a);
+   let thickness = viewport.to_viewport_length(1.0);
+
+   let visible_rect = viewport.visible_rect(canvas_size);
+   let mut x = (visible_rect.left() / spacing).floor() * spacing;
+   while x <= visible_rect.right() {
+      renderer.line(
+         point(x, visible_rect.top()),
+         point(x, visible_rect.bottom()),
+         color,
+         LineCap::Butt,
+         thickness,
+      );
+      x += spacing;
+   }
+   let mut y = (visible_rect.top() / spacing).floor() * spacing;
+   while y <= visible_rect.bottom() {
+      renderer.line(
+         point(visible_rect.left(), y),
+         point(visible_rect.right(), y),
+         color,
+         LineCap::Butt,
+         thickness,
+      );
+      y += spacing;
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn grid(spacing: u32) -> GridConfig {
+      GridConfig {
+         spacing,
+         ..Default::default()
+      }
+   }
+
+   #[test]
+   fn spacing_divides_the_chunk_size() {
+      assert_eq!(spacing(&grid(32)), 32.0);
+      assert_eq!(spacing(&grid(40)), 32.0);
+      assert_eq!(spacing(&grid(50)), 64.0);
+      assert_eq!(spacing(&grid(0)), 1.0);
+      assert_eq!(spacing(&grid(1000)), Chunk::SIZE.0 as f32);
+      for requested in 0..=300 {
+         assert_eq!(Chunk::SIZE.0 as f32 % spacing(&grid(requested)), 0.0);
+      }
+   }
+
+   #[test]
+   fn points_snap_to_the_nearest_intersection() {
+      assert_eq!(snap_to_spacing(point(15.0, 17.0), 32.0), point(0.0, 32.0));
+      assert_eq!(
+         snap_to_spacing(point(-17.0, 40.0), 32.0),
+         point(-32.0, 32.0)
+      );
+      assert_eq!(
+         snap_to_spacing(point(64.0, -64.0), 32.0),
+         point(64.0, -64.0)
+      );
+   }
+}
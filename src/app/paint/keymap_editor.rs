@@ -0,0 +1,383 @@
+//! The window for rebinding keyboard shortcuts.
+
+use netcanv_renderer::paws::{vector, AlignH, AlignV, Layout, Padding};
+
+use crate::assets::Assets;
+use crate::backend::winit::event::VirtualKeyCode;
+use crate::config::{self, config};
+use crate::keymap::{describe_binding, KeyBinding, Keymap};
+use crate::ui::view::{Dimension, Dimensions, View};
+use crate::ui::wm::windows::WindowButtonStyle;
+use crate::ui::wm::{
+   HitTest, WindowContent, WindowContentArgs, WindowContentWrappers, WindowId, WindowManager,
+};
+use crate::ui::{Button, ButtonArgs, ButtonColors, Input, Modifier, Ui, UiElements, UiInput};
+
+/// What the user chose to do with the edited key bindings.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+   Save,
+   Cancel,
+}
+
+/// The data shared between the key bindings window and its [`KeymapEditor`].
+pub struct KeymapEditorData {
+   /// The key bindings being edited. These only make it into the config once they're saved.
+   keymap: Keymap,
+   outcome: Option<Outcome>,
+}
+
+/// Opens and closes the key bindings window, and saves the bindings edited in it.
+pub struct KeymapEditor {
+   window: Option<WindowId<KeymapEditorData>>,
+}
+
+impl KeymapEditor {
+   /// Creates a new editor, with its window closed.
+   pub fn new() -> Self {
+      Self { window: None }
+   }
+
+   /// Returns a view for the window. This view should be laid out and then passed to
+   /// [`KeymapEditor::open`].
+   pub fn view() -> View {
+      View::new(KeymapEditorWindow::DIMENSIONS)
+   }
+
+   /// Opens the window with the key bindings currently in the config. Does nothing if the window
+   /// is already open.
+   pub fn open(&mut self, wm: &mut WindowManager, view: View) {
+      if self.window.is_some() {
+         return;
+      }
+      let data = KeymapEditorData {
+         keymap: config().keymap.clone(),
+         outcome: None,
+      };
+      let content = KeymapEditorWindow::new().background().buttons(WindowButtonStyle {
+         padding: Padding::even(12.0),
+      });
+      // The window is pinned, so that edits aren't lost by clicking on the canvas.
+      self.window = Some(wm.open_window(view, content, data).set_pinned(true).finish());
+   }
+
+   /// Closes the window once the user saves, cancels, or closes it. Returns whether the edited key
+   /// bindings were saved to the config.
+   pub fn process(&mut self, wm: &mut WindowManager) -> bool {
+      let Some(window_id) = &self.window else {
+         return false;
+      };
+      if wm.window_data(window_id).outcome.is_none() && !wm.should_close(window_id) {
+         return false;
+      }
+      let KeymapEditorData { keymap, outcome } = wm.close_window(self.window.take().unwrap());
+      if outcome == Some(Outcome::Save) {
+         tracing::info!("saving key bindings");
+         config::write(|config| config.keymap = keymap);
+         true
+      } else {
+         false
+      }
+   }
+}
+
+/// The key bindings window's content.
+struct KeymapEditorWindow {
+   /// The section whose key bindings are listed.
+   section: &'static str,
+   /// The action whose new key binding is being waited for.
+   capturing: Option<&'static str>,
+}
+
+impl KeymapEditorWindow {
+   /// The dimensions of the window.
+   const DIMENSIONS: Dimensions = Dimensions {
+      horizontal: Dimension::Constant(512.0),
+      vertical: Dimension::Constant(Self::HEADER_HEIGHT + 360.0 + Self::FOOTER_HEIGHT),
+   };
+
+   /// The height of the header bar, which holds the title.
+   const HEADER_HEIGHT: f32 = 48.0;
+   /// The height of the footer, which holds the buttons for saving and discarding changes.
+   const FOOTER_HEIGHT: f32 = 36.0;
+   /// The width of the list of sections.
+   const SIDEBAR_WIDTH: f32 = 128.0;
+   /// The width of the buttons showing key bindings.
+   const BINDING_WIDTH: f32 = 144.0;
+
+   fn new() -> Self {
+      Self {
+         section: "edit",
+         capturing: None,
+      }
+   }
+
+   /// Returns the key the config name of an action or section is translated under, eg.
+   /// `viewport-pan-left` for `viewport.pan_left`.
+   fn translation_key(name: &str) -> String {
+      name.replace(['.', '_'], "-")
+   }
+
+   /// Returns whether the key is a modifier, which can't be bound on its own.
+   fn is_modifier(key: VirtualKeyCode) -> bool {
+      matches!(
+         key,
+         VirtualKeyCode::LShift
+            | VirtualKeyCode::RShift
+            | VirtualKeyCode::LControl
+            | VirtualKeyCode::RControl
+            | VirtualKeyCode::LAlt
+            | VirtualKeyCode::RAlt
+            | VirtualKeyCode::LWin
+            | VirtualKeyCode::RWin
+      )
+   }
+
+   /// Binds the action being captured to the key typed during this frame, if any. Escape cancels
+   /// capturing and leaves the old binding in place.
+   fn capture_binding(&mut self, input: &Input, data: &mut KeymapEditorData) {
+      let Some(action) = self.capturing else {
+         return;
+      };
+      match input.last_key_typed() {
+         Some(VirtualKeyCode::Escape) => self.capturing = None,
+         Some(key) if !Self::is_modifier(key) => {
+            if let Some(binding) = data.keymap.binding_mut(action) {
+               *binding = (Modifier::from_input(input), key);
+            }
+            self.capturing = None;
+         }
+         _ => (),
+      }
+   }
+
+   /// Processes the header bar, which holds the title and can be used to drag the window around.
+   fn process_header_bar(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      hit_test: &mut HitTest,
+   ) {
+      ui.push((ui.width(), Self::HEADER_HEIGHT), Layout::Freeform);
+      if ui.hover(input) {
+         *hit_test = HitTest::Draggable;
+      }
+      ui.pad((16.0, 0.0));
+      ui.text(
+         &assets.sans_bold,
+         &assets.tr.keymap_editor.title,
+         assets.colors.text,
+         (AlignH::Left, AlignV::Middle),
+      );
+      ui.pop();
+   }
+
+   /// Processes the list of sections. Sections containing conflicting key bindings are
+   /// highlighted.
+   fn process_sections(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      keymap: &Keymap,
+      conflicting: &[KeyBinding],
+   ) {
+      ui.push((Self::SIDEBAR_WIDTH, ui.height()), Layout::Vertical);
+      for section in keymap.sections() {
+         let colors = ButtonColors::toggle(
+            section == self.section,
+            &assets.colors.action_button,
+            &assets.colors.selected_toolbar_button,
+         );
+         let has_conflicts = keymap.bindings().iter().any(|(action, binding)| {
+            Keymap::section(action) == section && conflicting.contains(binding)
+         });
+         let text_color = if has_conflicts {
+            assets.colors.error
+         } else {
+            colors.text
+         };
+         let name = assets.tr.keymap_section.get(&Self::translation_key(section));
+         let button = Button::process(
+            ui,
+            input,
+            &ButtonArgs::new(ui, colors).height(28.0).corner_radius(4.0),
+            Some(ui.width()),
+            |ui| {
+               ui.push(ui.size(), Layout::Freeform);
+               ui.pad((8.0, 0.0));
+               ui.text(
+                  &assets.sans,
+                  &name,
+                  text_color,
+                  (AlignH::Left, AlignV::Middle),
+               );
+               ui.pop();
+            },
+         );
+         if button.clicked() {
+            self.section = section;
+            self.capturing = None;
+         }
+         ui.space(4.0);
+      }
+      ui.pop();
+   }
+
+   /// Processes the key bindings in the selected section. Clicking a binding starts capturing the
+   /// keys for it.
+   fn process_bindings(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      keymap: &Keymap,
+      conflicting: &[KeyBinding],
+   ) {
+      let strings = &assets.tr.keymap_editor;
+
+      ui.push(ui.remaining_size(), Layout::Vertical);
+      if conflicting.is_empty() {
+         ui.vertical_label(
+            &assets.sans,
+            &strings.hint,
+            assets.colors.text,
+            AlignH::Left,
+         );
+      } else {
+         ui.vertical_label(
+            &assets.sans,
+            &strings.conflict,
+            assets.colors.error,
+            AlignH::Left,
+         );
+      }
+      ui.space(12.0);
+
+      for (action, binding) in keymap.bindings() {
+         if Keymap::section(action) != self.section {
+            continue;
+         }
+         let capturing = self.capturing == Some(action);
+
+         ui.push((ui.width(), 24.0), Layout::Horizontal);
+         ui.horizontal_label(
+            &assets.sans,
+            &assets.tr.keymap_action.get(&Self::translation_key(action)),
+            assets.colors.text,
+            Some((ui.width() - Self::BINDING_WIDTH, AlignH::Left)),
+         );
+         let colors = ButtonColors::toggle(
+            capturing,
+            &assets.colors.radio_button.normal,
+            &assets.colors.radio_button.selected,
+         );
+         let (text, text_color) = if capturing {
+            (strings.press_keys.clone(), colors.text)
+         } else if conflicting.contains(&binding) {
+            (describe_binding(binding), assets.colors.error)
+         } else {
+            (describe_binding(binding), colors.text)
+         };
+         let button = Button::process(
+            ui,
+            input,
+            &ButtonArgs::new(ui, colors).height(24.0).pill(),
+            Some(Self::BINDING_WIDTH),
+            |ui| {
+               ui.push((Self::BINDING_WIDTH, ui.height()), Layout::Freeform);
+               ui.text(
+                  &assets.sans,
+                  &text,
+                  text_color,
+                  (AlignH::Center, AlignV::Middle),
+               );
+               ui.pop();
+            },
+         );
+         if button.clicked() {
+            self.capturing = if capturing { None } else { Some(action) };
+         }
+         ui.pop();
+         ui.space(4.0);
+      }
+      ui.pop();
+   }
+
+   /// Processes the footer, which holds the buttons for resetting, discarding, and saving the
+   /// key bindings. Key bindings can't be saved while they conflict with each other.
+   fn process_footer(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      data: &mut KeymapEditorData,
+      has_conflicts: bool,
+   ) {
+      let strings = &assets.tr.keymap_editor;
+
+      ui.push((ui.width(), Self::FOOTER_HEIGHT), Layout::HorizontalRev);
+      ui.pad(Padding {
+         top: 0.0,
+         ..Padding::even(12.0)
+      });
+      let button = ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill();
+      if Button::with_text(ui, input, &button, &assets.sans, &strings.save).clicked()
+         && !has_conflicts
+      {
+         data.outcome = Some(Outcome::Save);
+      }
+      ui.space(8.0);
+      if Button::with_text(ui, input, &button, &assets.sans, &strings.cancel).clicked() {
+         data.outcome = Some(Outcome::Cancel);
+      }
+      ui.space(8.0);
+      if Button::with_text(ui, input, &button, &assets.sans, &strings.reset).clicked() {
+         data.keymap = Keymap::default();
+         self.capturing = None;
+      }
+      ui.pop();
+   }
+}
+
+impl WindowContent for KeymapEditorWindow {
+   type Data = KeymapEditorData;
+
+   fn process(
+      &mut self,
+      WindowContentArgs {
+         ui,
+         input,
+         assets,
+         hit_test,
+         ..
+      }: &mut WindowContentArgs,
+      data: &mut Self::Data,
+   ) {
+      self.capture_binding(input, data);
+      let conflicting: Vec<KeyBinding> =
+         data.keymap.conflicts().into_iter().map(|(binding, _)| binding).collect();
+
+      ui.push(ui.size(), Layout::Vertical);
+
+      self.process_header_bar(ui, input, assets, hit_test);
+
+      ui.push(
+         ui.remaining_size() - vector(0.0, Self::FOOTER_HEIGHT),
+         Layout::Horizontal,
+      );
+      ui.pad(Padding {
+         top: 0.0,
+         ..Padding::even(12.0)
+      });
+      self.process_sections(ui, input, assets, &data.keymap, &conflicting);
+      ui.space(12.0);
+      self.process_bindings(ui, input, assets, &data.keymap, &conflicting);
+      ui.pop();
+
+      self.process_footer(ui, input, assets, data, !conflicting.is_empty());
+
+      ui.pop();
+   }
+}
@@ -0,0 +1,132 @@
+//! The minimap, showing where the painted parts of the canvas lie relative to the viewport.
+
+use netcanv_renderer::paws::{point, Color, Layout, Point, Rect, Renderer, Vector};
+
+use crate::backend::winit::event::MouseButton;
+use crate::paint_canvas::DrawingBounds;
+use crate::ui::view::View;
+use crate::ui::{Input, Ui, UiInput};
+use crate::viewport::Viewport;
+
+/// A small map of the canvas, drawn in a corner of the canvas view.
+///
+/// The map shows the region covered by chunks, along with the area visible in the viewport. Only
+/// the positions of chunks are looked at, so keeping the map up to date is cheap. Clicking or
+/// dragging across the map centers the viewport on the point under the mouse.
+pub struct Minimap {
+   pub view: View,
+   /// The region of the canvas shown while the user is dragging across the map. The region stays
+   /// the same for the duration of the drag, as it would otherwise shift around whenever the
+   /// viewport moves.
+   dragged_region: Option<Rect>,
+}
+
+impl Minimap {
+   /// The size of the minimap.
+   const SIZE: (f32, f32) = (192.0, 144.0);
+
+   /// The space left between the minimap's edges and the region shown on it.
+   const PADDING: f32 = 8.0;
+
+   /// Creates a new minimap.
+   pub fn new() -> Self {
+      Self {
+         view: View::new(Self::SIZE),
+         dragged_region: None,
+      }
+   }
+
+   /// Returns the smallest rectangle containing both of the given rectangles.
+   fn union(a: Rect, b: Rect) -> Rect {
+      let top_left = point(a.left().min(b.left()), a.top().min(b.top()));
+      let bottom_right = point(a.right().max(b.right()), a.bottom().max(b.bottom()));
+      Rect::new(top_left, bottom_right - top_left)
+   }
+
+   /// Processes the minimap.
+   ///
+   /// `chunk_positions` are the positions of all the chunks on the canvas, and may contain
+   /// duplicates. If there aren't any chunks, the minimap is hidden, as there's nothing to show.
+   pub fn process(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      viewport: &mut Viewport,
+      canvas_size: Vector,
+      chunk_positions: impl Iterator<Item = (i32, i32)>,
+   ) {
+      let Some(extent) = DrawingBounds::around_chunks(chunk_positions).map(|bounds| bounds.rect())
+      else {
+         self.dragged_region = None;
+         return;
+      };
+      let visible_rect = viewport.visible_rect(canvas_size);
+      let region = self.dragged_region.unwrap_or_else(|| Self::union(extent, visible_rect));
+
+      self.view.begin(ui, input, Layout::Freeform);
+      ui.fill_rounded(Color::BLACK.with_alpha(160), 8.0);
+
+      // The region is scaled uniformly to fit within the minimap, and centered on it.
+      let map_center = point(ui.width(), ui.height()) / 2.0;
+      let scale = f32::min(
+         (ui.width() - Self::PADDING * 2.0) / region.width().max(1.0),
+         (ui.height() - Self::PADDING * 2.0) / region.height().max(1.0),
+      );
+      let to_map = |rect: Rect| -> Rect {
+         Rect::new(
+            map_center + (rect.position - region.center()) * scale,
+            rect.size * scale,
+         )
+      };
+      let to_canvas = |point: Point| -> Point { region.center() + (point - map_center) / scale };
+
+      if ui.hover(input) && input.mouse_button_just_pressed(MouseButton::Left) {
+         self.dragged_region = Some(region);
+      }
+      if !input.global_mouse_button_is_down(MouseButton::Left) {
+         self.dragged_region = None;
+      }
+      if self.dragged_region.is_some() {
+         viewport.center_on(to_canvas(ui.mouse_position(input)));
+      }
+
+      // While dragging, the viewport can end up outside of the region shown.
+      ui.render().push();
+      ui.clip();
+      ui.draw(|ui| {
+         let renderer = ui.render();
+         renderer.fill(to_map(extent), Color::WHITE.with_alpha(96), 2.0);
+         // The viewport is drawn after moving it, so that it doesn't lag behind the mouse.
+         let visible_rect = to_map(viewport.visible_rect(canvas_size));
+         renderer.outline(visible_rect, Color::WHITE, 2.0, 1.0);
+      });
+      ui.render().pop();
+
+      self.view.end(ui);
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn union_covers_both_rects() {
+      let a = Rect::new((0.0, 0.0), (10.0, 10.0));
+      let b = Rect::new((20.0, -5.0), (5.0, 5.0));
+      assert_eq!(Minimap::union(a, b), Rect::new((0.0, -5.0), (25.0, 15.0)));
+      assert_eq!(Minimap::union(b, a), Minimap::union(a, b));
+
+      let inner = Rect::new((2.0, 2.0), (4.0, 4.0));
+      assert_eq!(Minimap::union(a, inner), a);
+   }
+
+   #[test]
+   fn extent_covers_all_chunks() {
+      assert!(DrawingBounds::around_chunks(std::iter::empty()).is_none());
+
+      let chunks = [(0, 0), (-1, 2), (0, 0)];
+      let extent = DrawingBounds::around_chunks(chunks.into_iter()).unwrap().rect();
+      assert_eq!(extent, Rect::new((-256.0, 0.0), (512.0, 768.0)));
+   }
+}
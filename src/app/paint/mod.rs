@@ -1,19 +1,38 @@
 //! The paint state. This is the screen where you paint on the canvas with other people.
 
 mod actions;
+mod chunk_decoder;
+pub mod connection_status;
+mod go_to_coordinates;
+mod grid;
+mod keymap_editor;
+mod minimap;
+mod settings;
 pub mod tool_bar;
 mod tools;
 
-use actions::{ActionMessage, LeaveTheRoomAction};
+use actions::{
+   ActionMessage, ClearCanvasAction, EditKeymapAction, ExportViewportAction, LeaveTheRoomAction,
+   OpenSettingsAction, ShareViewportAction,
+};
 use image::RgbaImage;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use web_time::{Duration, Instant};
 
 use self::actions::SaveToFileAction;
+use self::chunk_decoder::{ChunkDecoder, DecodedChunk};
+use self::connection_status::ConnectionStatusIndicator;
+use self::go_to_coordinates::GoToCoordinates;
+use self::keymap_editor::KeymapEditor;
+use self::minimap::Minimap;
+use self::settings::Settings;
 use self::tool_bar::{ToolId, Toolbar};
-use self::tools::{BrushTool, EyedropperTool, Net, SelectionTool, ToolArgs};
+use self::tools::{
+   BrushTool, BucketTool, EyedropperTool, HandTool, LineTool, Net, SelectionTool, TextTool,
+   ToolArgs,
+};
 use crate::app::paint::actions::ActionArgs;
 use crate::app::paint::tool_bar::ToolbarArgs;
 use crate::app::paint::tools::KeyShortcutAction;
@@ -23,24 +42,28 @@ use crate::backend::Backend;
 use crate::clipboard;
 use crate::common;
 use crate::common::*;
+use crate::config::{self, config, PeerMotionEasing, ToolsConfig};
 use crate::image_coder::ImageCoder;
 use crate::net::peer::{self, Peer};
 use crate::net::socket::SocketSystem;
 use crate::net::timer::Timer;
 use crate::paint_canvas::cache_layer::{CacheLayer, CachedChunk};
 use crate::paint_canvas::chunk::Chunk;
+use crate::paint_canvas::history::{HistoryOutcome, RestoredChunk};
+use crate::paint_canvas::layer::LayerId;
 use crate::paint_canvas::*;
-use crate::project_file::ProjectFile;
+use crate::project_file::{LoadStatus, ProjectFile, ProjectLoad};
 use crate::ui::view::layout::DirectionV;
 use crate::ui::view::{Dimension, View};
 use crate::ui::wm::WindowManager;
 use crate::ui::*;
 use crate::viewport::Viewport;
+use crate::wake;
 use netcanv::cli::cli_args;
 use netcanv_i18n::translate_enum::TranslateEnum;
 use netcanv_protocol::relay::PeerId;
 use netcanv_renderer::paws::{
-   point, vector, AlignH, AlignV, Color, Layout, Rect, Renderer, Vector,
+   point, vector, AlignH, AlignV, Color, Layout, LineCap, Point, Rect, Renderer, Vector,
 };
 use netcanv_renderer::{BlendMode, Font, RenderBackend};
 use nysa::global as bus;
@@ -60,18 +83,40 @@ struct Tip {
    visible_duration: Duration,
 }
 
+/// An animated pan of the viewport, used when jumping to another peer.
+struct PanAnimation {
+   from: Point,
+   to: Point,
+   started: Instant,
+}
+
+impl PanAnimation {
+   /// Returns the point the viewport should be centered on once `elapsed` has passed since the
+   /// animation started, along with whether the animation is over.
+   fn position(&self, elapsed: Duration) -> (Point, bool) {
+      let t = (elapsed.as_secs_f32() / State::PAN_ANIMATION_DURATION.as_secs_f32()).min(1.0);
+      let eased = PeerMotionEasing::EaseInOut.apply(t);
+      (common::lerp_point(self.from, self.to, eased), t >= 1.0)
+   }
+}
+
 /// The state of a chunk download.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum ChunkDownload {
    NotDownloaded,
    Queued,
-   Requested,
+   /// The chunk was requested at the given time, and is yet to arrive.
+   Requested(Instant),
    Downloaded,
 }
 
 /// A bus message requesting a chunk download.
 struct RequestChunkDownload((i32, i32));
 
+/// A bus message asking for a tip to be shown in the upper left corner. Used by tools to show the
+/// values of settings changed with shortcuts.
+struct ShowTip(String);
+
 /// Controls shared between tools, such as the color palette.
 pub struct GlobalControls {
    pub color_picker: ColorPicker,
@@ -82,9 +127,51 @@ struct EncodeChannels {
    rx: mpsc::UnboundedReceiver<((i32, i32), CachedChunk)>,
 }
 
+/// The result of encoding a chunk for sending over the network.
+struct EncodedChunk {
+   position: (i32, i32),
+   /// The revision of the chunk the image was downloaded from.
+   revision: u64,
+   /// The encoded images, or `None` if encoding failed.
+   images: Option<CachedChunk>,
+}
+
+struct EncodeResults {
+   tx: mpsc::UnboundedSender<EncodedChunk>,
+   rx: mpsc::UnboundedReceiver<EncodedChunk>,
+}
+
+/// A chunk restored from the undo history, encoded for sending to other peers.
+enum EncodedRestoredChunk {
+   /// The full chunk, which is sent to the given peers.
+   Full {
+      position: (i32, i32),
+      peers: Vec<PeerId>,
+      data: Vec<u8>,
+   },
+   /// Only the region of the chunk that changed, which is sent to everyone. The full image is kept
+   /// around for peers that don't understand chunk patches.
+   Patch {
+      position: (i32, i32),
+      offset: (u32, u32),
+      data: Vec<u8>,
+      image: RgbaImage,
+   },
+}
+
+struct RestoredChunkChannels {
+   tx: mpsc::UnboundedSender<EncodedRestoredChunk>,
+   rx: mpsc::UnboundedReceiver<EncodedRestoredChunk>,
+}
+
 struct DecodeChannels {
-   tx: mpsc::UnboundedSender<((i32, i32), RgbaImage)>,
-   rx: mpsc::UnboundedReceiver<((i32, i32), RgbaImage)>,
+   decoder: ChunkDecoder,
+   rx: mpsc::UnboundedReceiver<DecodedChunk>,
+   /// The number of chunk images waiting to be decoded, per chunk.
+   pending: HashMap<(i32, i32), usize>,
+   /// Patches received for chunks with images waiting to be decoded. These are applied once the
+   /// images are decoded, so that they don't get overwritten by older data.
+   deferred_patches: HashMap<(i32, i32), Vec<((u32, u32), RgbaImage)>>,
 }
 
 /// The paint app state.
@@ -92,6 +179,7 @@ pub struct State {
    assets: Box<Assets>,
    socket_system: Arc<SocketSystem>,
    project_file: ProjectFile,
+   project_load: Option<ProjectLoad>,
 
    paint_canvas: PaintCanvas,
    cache_layer: CacheLayer,
@@ -101,8 +189,14 @@ pub struct State {
    peer: Peer,
    update_timer: Timer,
    chunk_downloads: HashMap<(i32, i32), ChunkDownload>,
+   /// How long the peer was away for after reconnecting to the relay, until the host sends its
+   /// chunk positions. Only the chunks that changed in the meantime are downloaded again.
+   away_after_reconnect: Option<Duration>,
    encoded_chunks: HashMap<PeerId, EncodeChannels>,
-   encode_channels: EncodeChannels,
+   encode_channels: EncodeResults,
+   /// Peers waiting for a chunk revision that's currently being encoded.
+   pending_encodes: HashMap<((i32, i32), u64), Vec<PeerId>>,
+   restored_chunks: RestoredChunkChannels,
    decode_channels: DecodeChannels,
 
    fatal_error: bool,
@@ -111,13 +205,31 @@ pub struct State {
    tip: Tip,
 
    panning: bool,
+   pan_animation: Option<PanAnimation>,
+   /// When the viewport was last panned with the keyboard, while the panning keys are held down.
+   last_keyboard_pan: Option<Instant>,
+   /// The peer whose viewport is being followed.
+   following: Option<PeerId>,
+   /// The viewport last sent to other peers, or `None` if it isn't shared.
+   shared_viewport: Option<(i32, i32, i32, i32)>,
+   /// When the viewport was last sent to other peers.
+   viewport_shared_at: Instant,
    viewport: Viewport,
 
    canvas_view: View,
    bottom_bar_view: View,
 
    overflow_menu: ContextMenu,
+   /// The height of the overflow menu without the peer list.
+   overflow_menu_height: f32,
+   connection_status: ConnectionStatusIndicator,
+   go_to_coordinates: GoToCoordinates,
+   minimap: Minimap,
+   keymap_editor: KeymapEditor,
+   settings: Settings,
    toolbar: Toolbar,
+   /// The tool to switch back to once the key for temporarily using the hand tool is released.
+   tool_before_hold: Option<ToolId>,
    wm: WindowManager,
    global_controls: GlobalControls,
 }
@@ -146,12 +258,53 @@ impl State {
    /// The network communication tick interval.
    pub const TIME_PER_UPDATE: Duration = Duration::from_millis(50);
 
+   /// Returns the coefficient for interpolating a peer's cursor or selection between its previous
+   /// and its most recent state, given the time the most recent state arrived.
+   ///
+   /// The interpolation lasts for one network tick, and is eased using the curve from the user's
+   /// config.
+   pub fn peer_interpolation_factor(last_update: Instant) -> f32 {
+      let elapsed = last_update.elapsed().as_secs_f32();
+      let t = (elapsed / Self::TIME_PER_UPDATE.as_secs_f32()).clamp(0.0, 1.0);
+      config().ui.peer_motion_easing.apply(t)
+   }
+
    /// The height of the bottom bar.
    const BOTTOM_BAR_SIZE: f32 = 32.0;
 
    /// The amount of padding applied around the canvas area, when laying out elements on top of it.
    const CANVAS_INNER_PADDING: f32 = 8.0;
 
+   /// The largest area of a chunk restored from the undo history that's sent as a patch. Larger
+   /// changes are sent as full chunks, as a patch wouldn't save much.
+   const MAX_PATCH_AREA: u32 = Chunk::SIZE.0 * Chunk::SIZE.1 / 2;
+
+   /// The height of a single peer's row in the overflow menu.
+   const PEER_ROW_HEIGHT: f32 = 32.0;
+
+   /// How long it takes to pan the viewport over to another peer.
+   const PAN_ANIMATION_DURATION: Duration = Duration::from_millis(300);
+
+   /// The shortest time between sending two changes of the viewport to other peers.
+   const VIEWPORT_SHARE_INTERVAL: Duration = Duration::from_millis(250);
+
+   /// How often the shared viewport is sent again even if it didn't change, such that peers who
+   /// joined in the meantime learn about it.
+   const VIEWPORT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+   /// How long messages stay in the log.
+   const LOG_ENTRY_LIFETIME: Duration = Duration::from_secs(5);
+
+   /// How long a requested chunk may take to arrive before it's requested again. The host doesn't
+   /// answer requests for chunks it doesn't have, which happens right after a host transfer.
+   const CHUNK_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+   /// The angle the viewport is rotated by with a single key press or scroll step.
+   const ROTATION_STEP: f32 = std::f32::consts::PI / 12.0;
+
+   /// How many times faster the viewport is panned with the keyboard while Shift is held.
+   const FAST_KEYBOARD_PAN_FACTOR: f32 = 4.0;
+
    /// Creates a new paint state.
    pub fn new(
       assets: Box<Assets>,
@@ -162,9 +315,11 @@ impl State {
    ) -> Result<Self, (netcanv::Error, Box<Assets>)> {
       let (encoded_tx, encoded_rx) = mpsc::unbounded_channel();
       let (decoded_tx, decoded_rx) = mpsc::unbounded_channel();
+      let (restored_tx, restored_rx) = mpsc::unbounded_channel();
       let zoom_level = cli_args().zoom_level.map(|x| x.into()).unwrap_or(0.0);
 
       let mut wm = WindowManager::new();
+      wm.restore_layout(config().ui.window_layout.clone());
       let mut this = Self {
          assets,
          socket_system,
@@ -172,20 +327,29 @@ impl State {
          paint_canvas: PaintCanvas::new(),
          cache_layer: CacheLayer::new(),
          project_file: ProjectFile::new(),
+         project_load: None,
 
          actions: Vec::new(),
 
          peer,
          update_timer: Timer::new(Self::TIME_PER_UPDATE),
          chunk_downloads: HashMap::new(),
+         away_after_reconnect: None,
          encoded_chunks: HashMap::new(),
-         encode_channels: EncodeChannels {
+         encode_channels: EncodeResults {
             tx: encoded_tx,
             rx: encoded_rx,
          },
+         pending_encodes: HashMap::new(),
+         restored_chunks: RestoredChunkChannels {
+            tx: restored_tx,
+            rx: restored_rx,
+         },
          decode_channels: DecodeChannels {
-            tx: decoded_tx,
+            decoder: ChunkDecoder::new(decoded_tx),
             rx: decoded_rx,
+            pending: HashMap::new(),
+            deferred_patches: HashMap::new(),
          },
 
          fatal_error: false,
@@ -198,13 +362,25 @@ impl State {
          },
 
          panning: false,
+         pan_animation: None,
+         last_keyboard_pan: None,
+         following: None,
+         shared_viewport: None,
+         viewport_shared_at: Instant::now(),
          viewport: Viewport::with_zoom_level(zoom_level),
 
          canvas_view: View::new((Dimension::Percentage(1.0), Dimension::Rest(1.0))),
          bottom_bar_view: View::new((Dimension::Percentage(1.0), Self::BOTTOM_BAR_SIZE)),
 
          overflow_menu: ContextMenu::new((256.0, 0.0)), // Vertical is filled in later
+         overflow_menu_height: 0.0,
+         connection_status: ConnectionStatusIndicator::new(),
+         go_to_coordinates: GoToCoordinates::new(),
+         minimap: Minimap::new(),
+         keymap_editor: KeymapEditor::new(),
+         settings: Settings::new(),
          toolbar: Toolbar::new(&mut wm),
+         tool_before_hold: None,
          wm,
 
          global_controls: GlobalControls {
@@ -215,18 +391,22 @@ impl State {
       this.register_actions(renderer);
 
       if let Some(path) = image_path {
-         if let Err(error) = this.project_file.load(renderer, &path, &mut this.paint_canvas) {
-            return Err((error, this.assets));
+         match this.project_file.load(&path) {
+            Ok(load) => this.project_load = Some(load),
+            Err(error) => return Err((error, this.assets)),
          }
       }
 
       if this.peer.is_host() {
+         this.paint_canvas.set_bounds(config().lobby.drawing_bounds);
          for line in this.assets.tr.paint_welcome_host.split('\n') {
             log!(this.log, "{}", line);
          }
          this.overflow_menu.open();
       }
 
+      // Process the first frame right away, instead of waiting for input to arrive.
+      wake::wake();
       Ok(this)
    }
 
@@ -235,14 +415,64 @@ impl State {
       let _selection = self.toolbar.add_tool(SelectionTool::new(renderer));
       let brush = self.toolbar.add_tool(BrushTool::new(renderer));
       let _eyedropper = self.toolbar.add_tool(EyedropperTool::new(renderer));
+      let _hand = self.toolbar.add_tool(HandTool::new(renderer));
+      let _bucket = self.toolbar.add_tool(BucketTool::new(renderer));
+      let _text = self.toolbar.add_tool(TextTool::new(renderer));
+      let _line = self.toolbar.add_tool(LineTool::new(renderer));
+
+      // Restore the tools' settings from the previous session.
+      let mut settings = config().tools.settings.clone();
+      self.toolbar.with_each_tool(|_, tool| {
+         if let Some(settings) = settings.remove(tool.name()) {
+            tool.load_settings(settings);
+         }
+         ControlFlow::<()>::Continue
+      });
+
+      // Restore the last used tool, falling back to the brush.
+      let current_tool = config().tools.current.as_deref().and_then(|name| {
+         let tool = self.toolbar.tool_by_name(name);
+         if tool.is_none() {
+            tracing::warn!("last used tool {:?} does not exist", name);
+         }
+         tool
+      });
+      self.toolbar.set_current_tool(current_tool.unwrap_or(brush));
+   }
 
-      // Set the default tool to the brush.
-      self.toolbar.set_current_tool(brush);
+   /// Saves the current tool and the tools' settings to the user config.
+   fn save_tools(&mut self) {
+      let current = self.tool_before_hold.unwrap_or_else(|| self.toolbar.current_tool());
+      let current = self.toolbar.clone_tool_name(current);
+      let mut settings = HashMap::new();
+      self.toolbar.with_each_tool(|_, tool| {
+         if let Some(tool_settings) = tool.save_settings() {
+            settings.insert(tool.name().to_owned(), tool_settings);
+         }
+         ControlFlow::<()>::Continue
+      });
+      config::write(|config| {
+         config.tools = ToolsConfig {
+            current: Some(current),
+            settings,
+         };
+      });
+   }
+
+   /// Saves the positions of pinned windows to the user config.
+   fn save_window_layout(&self) {
+      let window_layout = self.wm.save_layout();
+      config::write(|config| config.ui.window_layout = window_layout);
    }
 
    /// Registers all the actions and calculates the layout height of the overflow menu.
    fn register_actions(&mut self, renderer: &mut Backend) {
       self.actions.push(Box::new(SaveToFileAction::new(renderer)));
+      self.actions.push(Box::new(ExportViewportAction::new(renderer)));
+      self.actions.push(Box::new(ShareViewportAction::new(renderer)));
+      self.actions.push(Box::new(EditKeymapAction::new(renderer)));
+      self.actions.push(Box::new(OpenSettingsAction::new(renderer)));
+      self.actions.push(Box::new(ClearCanvasAction::new(renderer)));
       self.actions.push(Box::new(LeaveTheRoomAction::new(renderer)));
 
       let room_id_height = 108.0;
@@ -252,8 +482,7 @@ impl State {
       let actions_height = action_height * self.actions.len() as f32
          + action_margin * (self.actions.len() - 1) as f32
          + 4.0;
-      self.overflow_menu.view.dimensions.vertical =
-         Dimension::Constant(room_id_height + separator_height + actions_height);
+      self.overflow_menu_height = room_id_height + separator_height + actions_height;
    }
 
    fn tool_switch_events(
@@ -290,25 +519,188 @@ impl State {
          created: Instant::now(),
          visible_duration: duration,
       };
+      wake::at(self.tip.created + duration);
    }
 
-   /// Decodes canvas data to the given chunk.
-   fn decode_canvas_data(&mut self, chunk_position: (i32, i32), image_data: Vec<u8>) {
-      let tx = self.decode_channels.tx.clone();
-      tokio::task::spawn_blocking(move || {
-         match ImageCoder::decode_network_data(&image_data) {
-            Ok(image) => {
-               // Doesn't matter if the receiving half is closed.
-               tx.send((chunk_position, image)).expect("Unbounded send failed");
+   /// Shows the current zoom factor as a tip.
+   fn show_zoom_tip(&mut self) {
+      self.show_tip(
+         &format!("{:.0}%", self.viewport.target_zoom() * 100.0),
+         Duration::from_secs(3),
+      );
+   }
+
+   /// Zooms the viewport such that all chunks are visible. If there aren't any, the viewport
+   /// goes back to the origin at 100% zoom instead.
+   fn zoom_to_fit(&mut self) {
+      // Chunks that weren't downloaded yet count too, as they're known to be painted on.
+      let chunk_positions =
+         self.paint_canvas.all_chunk_positions().chain(self.chunk_downloads.keys().copied());
+      match DrawingBounds::around_chunks(chunk_positions) {
+         Some(bounds) => self.viewport.zoom_to_fit(bounds.rect(), self.canvas_view.size()),
+         None => self.viewport.reset_pan_and_zoom(),
+      }
+      self.pan_animation = None;
+      self.show_zoom_tip();
+   }
+
+   /// Shows the current viewport rotation as a tip.
+   fn show_rotation_tip(&mut self) {
+      self.show_tip(
+         &format!("{:.0}°", self.viewport.rotation().to_degrees()),
+         Duration::from_secs(3),
+      );
+   }
+
+   /// Uploads chunks of a canvas that's being loaded in the background.
+   fn process_project_load(&mut self, renderer: &mut Backend) {
+      if let Some(load) = &mut self.project_load {
+         match load.upload_chunks(renderer, &mut self.project_file, &mut self.paint_canvas) {
+            Ok(LoadStatus::Loading) => (),
+            Ok(LoadStatus::Done) => {
+               let skipped_chunks = load.skipped_chunks().len();
+               if skipped_chunks > 0 {
+                  log!(
+                     self.log,
+                     "{}",
+                     self
+                        .assets
+                        .tr
+                        .canvas_chunks_skipped
+                        .format()
+                        .with("count", skipped_chunks)
+                        .done()
+                  );
+               }
+               self.project_load = None;
+            }
+            Err(error) => {
+               // Don't leave a partially loaded canvas behind, but keep what was there before.
+               for chunk_position in load.remove_created_chunks(&mut self.paint_canvas) {
+                  self.cache_layer.invalidate_chunk(chunk_position);
+               }
+               self.project_load = None;
+               log!(
+                  self.log,
+                  "{}",
+                  self
+                     .assets
+                     .tr
+                     .error
+                     .format()
+                     .with("error", error.translate(&self.assets.language))
+                     .done()
+               );
             }
-            Err(error) => tracing::error!("image decoding failed: {:?}", error),
          }
-      });
+      }
+   }
+
+   /// Draws the progress of loading or saving a canvas.
+   fn process_project_file_status(&mut self, ui: &mut Ui) {
+      let (text, progress) = if let Some(load) = &self.project_load {
+         let progress = load.progress();
+         let text = self
+            .assets
+            .tr
+            .loading_canvas
+            .format()
+            .with("progress", (progress * 100.0).round() as u32)
+            .done();
+         (text, Some(progress))
+      } else if self.project_file.is_saving() {
+         (self.assets.tr.saving_canvas.clone(), None)
+      } else {
+         return;
+      };
+      ui.push(ui.size(), Layout::Freeform);
+      ui.pad((16.0, 16.0));
+      ui.push((224.0, 32.0), Layout::Freeform);
+      ui.align((AlignH::Center, AlignV::Top));
+      ui.fill(Color::BLACK.with_alpha(192));
+      if let Some(progress) = progress {
+         ui.draw(|ui| {
+            let bar = Rect::new(
+               point(0.0, ui.height() - 2.0),
+               vector(ui.width() * progress, 2.0),
+            );
+            ui.render().fill(bar, Color::WHITE, 0.0);
+         });
+      }
+      ui.text(
+         &self.assets.sans,
+         &text,
+         Color::WHITE,
+         (AlignH::Center, AlignV::Middle),
+      );
+      ui.pop();
+      ui.pop();
+   }
+
+   /// Queues canvas data for decoding to the given chunk.
+   ///
+   /// If the decoder is too busy to accept the data, the chunk is marked as not downloaded, such
+   /// that it's requested again once it's visible.
+   fn decode_canvas_data(&mut self, chunk_position: (i32, i32), image_data: Vec<u8>) {
+      let state = if self.decode_channels.decoder.decode(chunk_position, image_data) {
+         *self.decode_channels.pending.entry(chunk_position).or_default() += 1;
+         ChunkDownload::Downloaded
+      } else {
+         ChunkDownload::NotDownloaded
+      };
+      self.chunk_downloads.insert(chunk_position, state);
+   }
+
+   /// Marks one of the chunk's queued images as decoded. Once none are left, the patches received
+   /// for the chunk in the meantime are applied.
+   fn chunk_decoded(&mut self, renderer: &mut Backend, chunk_position: (i32, i32)) {
+      let DecodeChannels {
+         pending,
+         deferred_patches,
+         ..
+      } = &mut self.decode_channels;
+      let Some(count) = pending.get_mut(&chunk_position) else {
+         return;
+      };
+      *count -= 1;
+      if *count > 0 {
+         return;
+      }
+      pending.remove(&chunk_position);
+      for (offset, image) in deferred_patches.remove(&chunk_position).unwrap_or_default() {
+         self.paint_canvas.patch_chunk(renderer, chunk_position, offset, &image);
+      }
+   }
+
+   /// Applies a patch replacing a region of a chunk. If the chunk has images waiting to be
+   /// decoded, the patch is applied after them.
+   fn apply_chunk_patch(
+      &mut self,
+      renderer: &mut Backend,
+      chunk_position: (i32, i32),
+      offset: (u32, u32),
+      image_data: &[u8],
+   ) -> netcanv::Result<()> {
+      let image = ImageCoder::decode_network_patch(image_data, offset)?;
+      if self.decode_channels.pending.contains_key(&chunk_position) {
+         self
+            .decode_channels
+            .deferred_patches
+            .entry(chunk_position)
+            .or_default()
+            .push((offset, image));
+      } else {
+         self.paint_canvas.patch_chunk(renderer, chunk_position, offset, &image);
+      }
+      Ok(())
    }
 
    /// Processes the message log.
    fn process_log(&mut self, ui: &mut Ui) {
-      self.log.retain(|(_, time_created)| time_created.elapsed() < Duration::from_secs(5));
+      self.log.retain(|(_, time_created)| time_created.elapsed() < Self::LOG_ENTRY_LIFETIME);
+      if let Some((_, oldest)) = self.log.first() {
+         wake::at(*oldest + Self::LOG_ENTRY_LIFETIME);
+      }
       ui.draw(|ui| {
          let mut y = ui.height() - (self.log.len() as f32 - 1.0) * 16.0 - 8.0;
          let renderer = ui.render();
@@ -328,9 +720,322 @@ impl State {
       });
    }
 
+   /// Reports the outcome of undoing or redoing an action, and sends the restored chunks to the
+   /// other peers.
+   fn process_history_outcome(&mut self, outcome: HistoryOutcome) {
+      match outcome {
+         HistoryOutcome::Empty => (),
+         HistoryOutcome::Conflict => {
+            tracing::info!("not restoring chunks from the undo history, as they were painted over");
+            log!(self.log, "{}", self.assets.tr.undo_conflict);
+         }
+         HistoryOutcome::Restored(chunks) => {
+            tracing::info!("restoring {} chunks from the undo history", chunks.len());
+            // Only the base layer is shared with other peers.
+            let base_layer_chunks = chunks.into_iter().filter(|chunk| chunk.layer == LayerId::BASE);
+            for RestoredChunk {
+               position,
+               image,
+               changed,
+               ..
+            } in base_layer_chunks
+            {
+               let Some((x, y, width, height)) = changed else {
+                  continue;
+               };
+               let patch =
+                  (width * height <= Self::MAX_PATCH_AREA).then_some((x, y, width, height));
+               self.encode_restored_chunk(position, image, patch, vec![PeerId::BROADCAST]);
+            }
+         }
+      }
+   }
+
+   /// Encodes a chunk restored from the undo history on a separate thread, so that large undos
+   /// don't stall the frame. Once done, the chunk is sent by [`State::send_restored_chunk`].
+   ///
+   /// If a `patch` region is given, only that region is encoded and sent to everyone. Otherwise, the
+   /// full chunk is encoded and sent to the given peers.
+   fn encode_restored_chunk(
+      &self,
+      position: (i32, i32),
+      image: RgbaImage,
+      patch: Option<(u32, u32, u32, u32)>,
+      peers: Vec<PeerId>,
+   ) {
+      let tx = self.restored_chunks.tx.clone();
+      tokio::task::spawn_blocking(move || {
+         let encoded = match patch {
+            Some((x, y, width, height)) => {
+               let region = image::imageops::crop_imm(&image, x, y, width, height).to_image();
+               ImageCoder::encode_network_patch(&region).map(|data| EncodedRestoredChunk::Patch {
+                  position,
+                  offset: (x, y),
+                  data,
+                  image,
+               })
+            }
+            None => {
+               ImageCoder::encode_png_data_sync(image).map(|data| EncodedRestoredChunk::Full {
+                  position,
+                  peers,
+                  data,
+               })
+            }
+         };
+         match encoded {
+            Ok(encoded) => {
+               let _ = tx.send(encoded);
+               wake::wake();
+            }
+            Err(error) => {
+               tracing::error!(
+                  "error while encoding restored chunk {:?}: {:?}",
+                  position,
+                  error
+               );
+            }
+         }
+      });
+   }
+
+   /// Sends an encoded chunk restored from the undo history to the other peers.
+   ///
+   /// Peers that don't understand chunk patches get the full chunk instead, which is encoded
+   /// separately.
+   fn send_restored_chunk(&self, chunk: EncodedRestoredChunk) -> netcanv::Result<()> {
+      match chunk {
+         EncodedRestoredChunk::Full {
+            position,
+            peers,
+            data,
+         } => {
+            for peer_id in peers {
+               self.peer.send_chunks(peer_id, vec![(position, data.clone())])?;
+            }
+         }
+         EncodedRestoredChunk::Patch {
+            position,
+            offset,
+            data,
+            image,
+         } => {
+            let outdated = self.peer.send_chunk_patch(position, offset, data)?;
+            if !outdated.is_empty() {
+               self.encode_restored_chunk(position, image, None, outdated);
+            }
+         }
+      }
+      Ok(())
+   }
+
+   /// Processes the key shortcuts for adding and switching between layers. Returns whether any of
+   /// them were triggered.
+   #[cfg(feature = "layers")]
+   fn process_layer_key_shortcuts(&mut self, input: &Input) -> bool {
+      let keymap = config().keymap.layers.clone();
+      let layers = self.paint_canvas.layers();
+      let active_index = layers
+         .iter()
+         .position(|layer| layer.id() == self.paint_canvas.active_layer())
+         .unwrap_or(0);
+      let layer = if input.action(keymap.new_layer) == (true, true) {
+         Some(self.paint_canvas.add_layer())
+      } else if input.action(keymap.select_layer_above) == (true, true) {
+         layers.get(active_index + 1).map(|layer| layer.id())
+      } else if input.action(keymap.select_layer_below) == (true, true) {
+         active_index.checked_sub(1).and_then(|index| layers.get(index)).map(|layer| layer.id())
+      } else {
+         return false;
+      };
+
+      if let Some(layer) = layer {
+         self.paint_canvas.set_active_layer(layer);
+         let layers = self.paint_canvas.layers();
+         let number = layers.iter().position(|l| l.id() == layer).unwrap_or(0) + 1;
+         log!(
+            self.log,
+            "{}",
+            self
+               .assets
+               .tr
+               .layer_selected
+               .format()
+               .with("layer", number)
+               .with("count", layers.len())
+               .done()
+         );
+      }
+      true
+   }
+
+   /// Returns whether the keyboard is being used for something other than shortcuts: any of the
+   /// WM's windows are focused, or coordinates or text are being typed in.
+   fn keyboard_is_captured(&mut self) -> bool {
+      self.wm.has_focus()
+         || self.go_to_coordinates.is_open()
+         || self.toolbar.with_current_tool(|tool| tool.captures_keyboard())
+   }
+
+   /// Pans the viewport for as long as any of the panning keys are held down.
+   fn process_keyboard_panning(&mut self, input: &Input) {
+      let (keymap, speed) = {
+         let config = config();
+         (config.keymap.viewport.clone(), config.ui.keyboard_pan_speed)
+      };
+      let direction = [
+         (keymap.pan_left, vector(-1.0, 0.0)),
+         (keymap.pan_right, vector(1.0, 0.0)),
+         (keymap.pan_up, vector(0.0, -1.0)),
+         (keymap.pan_down, vector(0.0, 1.0)),
+      ]
+      .into_iter()
+      .filter(|((_, key), _)| input.key_is_down(*key))
+      .fold(vector(0.0, 0.0), |sum, (_, direction)| sum + direction);
+      if direction == vector(0.0, 0.0) || input.ctrl_is_down() || self.keyboard_is_captured() {
+         self.last_keyboard_pan = None;
+         return;
+      }
+
+      let now = Instant::now();
+      let elapsed = self.last_keyboard_pan.map(|last| (now - last).as_secs_f32());
+      self.last_keyboard_pan = Some(now);
+      self.viewport.pan_around(Self::keyboard_pan_delta(
+         direction,
+         speed,
+         input.shift_is_down(),
+         elapsed,
+      ));
+      self.pan_animation = None;
+   }
+
+   /// Returns how far the viewport is panned with the keyboard in a single frame, in screen
+   /// pixels. `elapsed` is the time since the previous frame of panning, in seconds, or `None` if
+   /// panning just started.
+   fn keyboard_pan_delta(
+      direction: Vector,
+      speed: f32,
+      fast: bool,
+      elapsed: Option<f32>,
+   ) -> Vector {
+      // Frames aren't rendered at a steady rate, so the distance depends on the time that passed
+      // since the last frame. The first frame of a key press pans by a single 60 FPS frame's
+      // worth, and long hitches are capped so that the viewport doesn't jump.
+      let elapsed = elapsed.unwrap_or(1.0 / 60.0).min(1.0 / 15.0);
+      let speed = match fast {
+         true => speed * Self::FAST_KEYBOARD_PAN_FACTOR,
+         false => speed,
+      };
+      direction * speed * elapsed
+   }
+
    fn process_tool_key_shortcuts(&mut self, ui: &mut Ui, input: &mut Input) {
-      // If any of the WM's windows are focused, skip keyboard shortcuts.
-      if self.wm.has_focus() {
+      // Switch back from the hand tool once its hold key is released. This is done before
+      // checking for focus, so that the tool isn't left switched if a window got focused while
+      // the key was being held.
+      if let Some(previous_tool) = self.tool_before_hold {
+         if !input.key_is_down(config().keymap.tools.hold_hand.1) {
+            self.tool_before_hold = None;
+            if self.toolbar.tool_by_name("hand") == Some(self.toolbar.current_tool()) {
+               self.set_current_tool(ui, previous_tool);
+            }
+         }
+      }
+
+      if self.keyboard_is_captured() {
+         return;
+      }
+
+      if self.tool_before_hold.is_none()
+         && input.action(config().keymap.tools.hold_hand) == (true, true)
+      {
+         if let Some(hand) = self.toolbar.tool_by_name("hand") {
+            let current_tool = self.toolbar.current_tool();
+            if current_tool != hand {
+               self.tool_before_hold = Some(current_tool);
+               self.set_current_tool(ui, hand);
+            }
+         }
+         return;
+      }
+
+      if input.action(config().keymap.color_picker.swap_colors) == (true, true) {
+         self.global_controls.color_picker.swap_colors();
+      }
+
+      let keymap = config().keymap.viewport.clone();
+      if input.action(keymap.rotate_counterclockwise) == (true, true) {
+         self.viewport.rotate(-Self::ROTATION_STEP);
+         self.show_rotation_tip();
+      }
+      if input.action(keymap.rotate_clockwise) == (true, true) {
+         self.viewport.rotate(Self::ROTATION_STEP);
+         self.show_rotation_tip();
+      }
+      if input.action(keymap.reset_rotation) == (true, true) {
+         self.viewport.reset_rotation();
+         self.show_rotation_tip();
+      }
+      if input.action(keymap.go_to_coordinates) == (true, true) {
+         let center = self.viewport.pan();
+         self.go_to_coordinates.open(point(center.x, center.y));
+      }
+      if input.action(keymap.toggle_minimap) == (true, true) {
+         config::write(|config| config.ui.show_minimap = !config.ui.show_minimap);
+      }
+      if input.action(keymap.toggle_grid) == (true, true) {
+         config::write(|config| config.grid.show = !config.grid.show);
+      }
+      if input.action(keymap.zoom_to_fit) == (true, true) {
+         self.zoom_to_fit();
+      }
+      if input.action(keymap.reset_zoom) == (true, true) {
+         self.viewport.reset_zoom();
+         self.show_zoom_tip();
+      }
+
+      if input.action(config().keymap.file.save) == (true, true) {
+         if let Err(error) = SaveToFileAction::quick_save(ActionArgs {
+            assets: &self.assets,
+            paint_canvas: &mut self.paint_canvas,
+            project_file: &mut self.project_file,
+            renderer: ui,
+            viewport: &self.viewport,
+            canvas_size: self.canvas_view.size(),
+            is_host: self.peer.is_host(),
+         }) {
+            log!(
+               self.log,
+               "{}",
+               self
+                  .assets
+                  .tr
+                  .error_while_performing_action
+                  .format()
+                  .with("error", error.translate(&self.assets.language))
+                  .done()
+            );
+         }
+      }
+
+      // Actions can't be undone or redone while another one is still in progress, such as while a
+      // stroke is being painted, or a selection is being moved around.
+      if !self.paint_canvas.is_recording_history() {
+         let outcome = if input.action(config().keymap.edit.undo) == (true, true) {
+            Some(self.paint_canvas.undo(ui))
+         } else if input.action(config().keymap.edit.redo) == (true, true) {
+            Some(self.paint_canvas.redo(ui))
+         } else {
+            None
+         };
+         if let Some(outcome) = outcome {
+            self.process_history_outcome(outcome);
+            return;
+         }
+      }
+
+      #[cfg(feature = "layers")]
+      if self.process_layer_key_shortcuts(input) {
          return;
       }
 
@@ -385,12 +1090,27 @@ impl State {
 
       // Panning and zooming
 
-      match input.action(MouseButton::Middle) {
+      let pan_button = config().keymap.mouse.pan;
+      match input.action(pan_button) {
          (true, ButtonState::Pressed) if ui.hover(input) => self.panning = true,
          (_, ButtonState::Released) => self.panning = false,
          _ => (),
       }
 
+      // Panning by hand takes over from an animated pan, and stops following another peer.
+      if self.panning {
+         self.pan_animation = None;
+         self.following = None;
+      }
+      self.follow_peer_viewport(canvas_size);
+      if let Some(animation) = &self.pan_animation {
+         let (position, finished) = animation.position(animation.started.elapsed());
+         self.viewport.center_on(position);
+         if finished {
+            self.pan_animation = None;
+         }
+      }
+
       if self.panning {
          let delta_pan = input.previous_mouse_position() - input.mouse_position();
          self.viewport.pan_around(delta_pan);
@@ -400,16 +1120,25 @@ impl State {
       }
       if let (true, Some(scroll)) = input.action(MouseScroll) {
          self.viewport.zoom_in(scroll.y);
-         self.show_tip(
-            &format!("{:.0}%", self.viewport.zoom() * 100.0),
-            Duration::from_secs(3),
-         );
+         self.show_zoom_tip();
+      }
+      self.process_keyboard_panning(input);
+      self.viewport.animate(config().ui.animate_viewport);
+
+      // Rotating
+
+      if let (true, Some(scroll)) = input.action((Modifier::SHIFT, MouseScroll)) {
+         self.viewport.rotate(scroll.y * Self::ROTATION_STEP);
+         self.show_rotation_tip();
       }
 
       // Drawing & key shortcuts
 
       self.toolbar.with_each_tool::<(), _>(|_, tool| {
-         tool.process_background_jobs(tool_args!(ui, input, self), &mut self.paint_canvas);
+         // Background jobs place other peers' drawings, which belong on the base layer.
+         self.paint_canvas.with_base_layer_active(|paint_canvas| {
+            tool.process_background_jobs(tool_args!(ui, input, self), paint_canvas);
+         });
          ControlFlow::Continue
       });
 
@@ -419,7 +1148,7 @@ impl State {
          tool.process_paint_canvas_input(
             tool_args!(ui, input, self),
             &mut self.paint_canvas,
-            &self.viewport,
+            &mut self.viewport,
          )
       });
 
@@ -427,25 +1156,54 @@ impl State {
       // Rendering
       //
 
+      self.process_project_load(ui);
       while let Ok((chunk_position, image)) = self.decode_channels.rx.try_recv() {
-         self.paint_canvas.set_chunk(ui, chunk_position, image);
+         if let Some(image) = image {
+            self.paint_canvas.set_chunk(ui, chunk_position, image);
+         }
+         self.chunk_decoded(ui, chunk_position);
       }
-      while let Ok((chunk_position, image)) = self.encode_channels.rx.try_recv() {
-         let _ = self.paint_canvas.ensure_chunk(ui, chunk_position);
-         self.cache_layer.set_chunk(chunk_position, image);
+      while let Ok(encoded) = self.encode_channels.rx.try_recv() {
+         let key = (encoded.position, encoded.revision);
+         let requesters = self.pending_encodes.remove(&key).unwrap_or_default();
+         let Some(images) = encoded.images else {
+            continue;
+         };
+         for requester in requesters {
+            if let Some(EncodeChannels { tx, .. }) = self.encoded_chunks.get(&requester) {
+               let _ = tx.send((encoded.position, images.clone()));
+            }
+         }
+         // If the chunk was modified while it was being encoded, the images are already stale
+         // and shouldn't be cached.
+         let current_revision =
+            self.paint_canvas.chunk(encoded.position).map(|chunk| chunk.revision());
+         if current_revision == Some(encoded.revision) {
+            self.cache_layer.set_chunk(encoded.position, encoded.revision, images);
+         }
+      }
+      while let Ok(chunk) = self.restored_chunks.rx.try_recv() {
+         catch!(self.send_restored_chunk(chunk));
+      }
+      for chunk_position in self.paint_canvas.take_modified_chunks() {
+         self.cache_layer.invalidate_chunk(chunk_position);
       }
       self.cache_layer.update_timers();
 
       ui.draw(|ui| {
          ui.render().push();
-         let Vector {
-            x: width,
-            y: height,
-         } = ui.size();
-         ui.render().translate(vector(width / 2.0, height / 2.0));
-         ui.render().scale(vector(self.viewport.zoom(), self.viewport.zoom()));
-         ui.render().translate(-self.viewport.pan());
+         self.viewport.apply_transform(ui.render(), ui.size());
          self.paint_canvas.draw_to(ui.render(), &self.viewport, canvas_size);
+         if config().grid.show {
+            grid::draw(ui.render(), &self.viewport, canvas_size);
+         }
+         if let Some(bounds) = self.paint_canvas.bounds() {
+            let thickness = 2.0 / self.viewport.zoom();
+            ui.render().outline(bounds.rect(), self.assets.colors.separator, 0.0, thickness);
+         }
+         if config().ui.show_origin_marker {
+            self.draw_origin_marker(ui.render());
+         }
          ui.render().pop();
 
          ui.render().push();
@@ -483,6 +1241,7 @@ impl State {
          ui.pop();
       }
 
+      self.process_project_file_status(ui);
       self.process_log(ui);
 
       self.canvas_view.end(ui);
@@ -492,7 +1251,17 @@ impl State {
       //
 
       self.update_timer.tick();
+      let mut updated = false;
       while self.update_timer.update() {
+         updated = true;
+
+         // Chunks can't be downloaded while the connection to the relay is down, and tools keep
+         // their packets queued up until it's back. Everything else sent in the meantime is
+         // queued up by the peer.
+         if self.peer.is_reconnecting() {
+            continue;
+         }
+
          // Tool updates
          self.toolbar.with_current_tool(|tool| {
             catch!(tool.network_send(
@@ -503,13 +1272,40 @@ impl State {
             ))
          });
 
-         for chunk_position in self.viewport.visible_tiles(Chunk::SIZE, canvas_size) {
-            if let Some(state) = self.chunk_downloads.get_mut(&chunk_position) {
-               if *state == ChunkDownload::NotDownloaded {
-                  Self::queue_chunk_download(chunk_position);
-                  *state = ChunkDownload::Queued;
+         // Viewport sharing
+         catch!(self.share_viewport(canvas_size));
+
+         // The host has no one to download chunks from, so the chunks it's missing can only be
+         // resynchronized from other peers after a host transfer.
+         if !self.peer.is_host() {
+            let visible_chunks: HashSet<_> =
+               self.viewport.visible_tiles(Chunk::SIZE, canvas_size).collect();
+            for &chunk_position in &visible_chunks {
+               if let Some(state) = self.chunk_downloads.get_mut(&chunk_position) {
+                  let timed_out = match *state {
+                     ChunkDownload::Requested(requested) => {
+                        requested.elapsed() > Self::CHUNK_DOWNLOAD_TIMEOUT
+                     }
+                     _ => false,
+                  };
+                  if *state == ChunkDownload::NotDownloaded || timed_out {
+                     Self::queue_chunk_download(chunk_position);
+                     *state = ChunkDownload::Queued;
+                  }
                }
             }
+
+            // For the same reason, only non-hosts can get rid of chunks once there are too many
+            // of them. The evicted chunks are downloaded again when they come back into view.
+            self.paint_canvas.mark_visible(visible_chunks.iter().copied());
+            let max_resident_chunks = config().canvas.max_resident_chunks;
+            let evicted = self.paint_canvas.evict_chunks(max_resident_chunks, |position| {
+               visible_chunks.contains(&position)
+            });
+            for chunk_position in evicted {
+               self.cache_layer.invalidate_chunk(chunk_position);
+               self.chunk_downloads.insert(chunk_position, ChunkDownload::NotDownloaded);
+            }
          }
 
          // Chunk sending
@@ -519,14 +1315,7 @@ impl State {
 
             let mut bytes_in_packet = 0;
             let mut packet = Vec::new();
-            while let Ok((chunk_position, images)) = rx.try_recv() {
-               let image_data = match images {
-                  CachedChunk {
-                     png: _,
-                     webp: Some(webp),
-                  } => webp,
-                  CachedChunk { png, webp: None } => png,
-               };
+            while let Ok((chunk_position, CachedChunk { data: image_data })) = rx.try_recv() {
                if bytes_in_packet + image_data.len() > MAX_BYTES_PER_PACKET {
                   catch!(self.peer.send_chunks(peer_id, std::mem::take(&mut packet)));
                   bytes_in_packet = 0;
@@ -539,6 +1328,26 @@ impl State {
             }
          }
       }
+      // Changes made during this frame are only sent out with the next update, so it has to
+      // happen even if nothing else wakes the app up until then.
+      if !updated {
+         wake::at(self.update_timer.next_update());
+      }
+   }
+
+   /// Draws a small crosshair at the canvas's origin, so that it can be found again after
+   /// wandering off far. The viewport's transform must already be applied.
+   fn draw_origin_marker(&self, renderer: &mut Backend) {
+      const ARM_LENGTH: f32 = 12.0;
+
+      let arm = ARM_LENGTH / self.viewport.zoom();
+      let thickness = 1.0 / self.viewport.zoom();
+      let color = self.assets.colors.separator;
+      let horizontal = (point(-arm, 0.0), point(arm, 0.0));
+      let vertical = (point(0.0, -arm), point(0.0, arm));
+      for (a, b) in [horizontal, vertical] {
+         renderer.line(a, b, color, LineCap::Butt, thickness);
+      }
    }
 
    /// Processes the bottom bar.
@@ -571,6 +1380,11 @@ impl State {
       {
          self.overflow_menu.toggle();
       }
+      if let Some(attempt) = self.peer.reconnect_attempt() {
+         self.connection_status.process_reconnecting(ui, input, &self.assets, attempt);
+      } else if let Some(health) = self.peer.connection_health() {
+         self.connection_status.process_button(ui, input, &self.assets, &health);
+      }
 
       ui.pop();
 
@@ -691,6 +1505,17 @@ impl State {
          ui.pop();
          ui.space(8.0);
 
+         // Peer list
+
+         if !self.peer.mates().is_empty() {
+            self.process_peer_list(ui, input);
+            ui.space(8.0);
+            ui.push((ui.width(), 0.0), Layout::Freeform);
+            ui.border_top(self.assets.colors.separator, 1.0);
+            ui.pop();
+            ui.space(8.0);
+         }
+
          for action in &mut self.actions {
             let action_button = Button::process(
                ui,
@@ -722,10 +1547,35 @@ impl State {
                   paint_canvas: &mut self.paint_canvas,
                   project_file: &mut self.project_file,
                   renderer: ui,
+                  viewport: &self.viewport,
+                  canvas_size: self.canvas_view.size(),
+                  is_host: self.peer.is_host(),
                }) {
                   Ok(Some(ActionMessage::LeaveTheRoom)) => {
                      self.leave_the_room = true;
                   }
+                  Ok(Some(ActionMessage::ClearCanvas)) => {
+                     self.chunk_downloads.clear();
+                     catch!(self.peer.send_clear_canvas());
+                  }
+                  Ok(Some(ActionMessage::EditKeymap)) => {
+                     let mut view = KeymapEditor::view();
+                     view::layout::align(
+                        &self.canvas_view,
+                        &mut view,
+                        (AlignH::Center, AlignV::Middle),
+                     );
+                     self.keymap_editor.open(&mut self.wm, view);
+                  }
+                  Ok(Some(ActionMessage::OpenSettings)) => {
+                     let mut view = Settings::view();
+                     view::layout::align(
+                        &self.canvas_view,
+                        &mut view,
+                        (AlignH::Center, AlignV::Middle),
+                     );
+                     self.settings.open(&mut self.wm, view);
+                  }
                   Err(error) => {
                      log!(
                         self.log,
@@ -749,6 +1599,223 @@ impl State {
       }
    }
 
+   /// Processes the list of peers in the overflow menu, which lets the user copy another peer's
+   /// tool settings.
+   fn process_peer_list(&mut self, ui: &mut Ui, input: &mut Input) {
+      let mut mates: Vec<_> = self
+         .peer
+         .mates()
+         .iter()
+         .map(|(&peer_id, mate)| (peer_id, mate.nickname.clone(), mate.tool.clone()))
+         .collect();
+      mates.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+
+      for (peer_id, nickname, tool_name) in mates {
+         let color = self.peer.mates()[&peer_id].color;
+         ui.push((ui.width(), Self::PEER_ROW_HEIGHT), Layout::HorizontalRev);
+         let copy_button = ButtonArgs::new(ui, &self.assets.colors.button).height(28.0).pill();
+         let copy_clicked = tool_name.is_some()
+            && Button::with_text(
+               ui,
+               input,
+               &copy_button,
+               &self.assets.sans,
+               &self.assets.tr.copy_peer_tool,
+            )
+            .clicked();
+         ui.space(8.0);
+         let go_to_clicked = Button::with_text(
+            ui,
+            input,
+            &copy_button,
+            &self.assets.sans,
+            &self.assets.tr.go_to_peer,
+         )
+         .clicked();
+         ui.space(8.0);
+         // Only peers sharing their viewport can be followed.
+         let is_followed = self.following == Some(peer_id);
+         let can_follow = is_followed || self.peer.mates()[&peer_id].viewport.is_some();
+         let follow_clicked = can_follow
+            && Button::with_text(
+               ui,
+               input,
+               &copy_button,
+               &self.assets.sans,
+               if is_followed {
+                  &self.assets.tr.stop_following_peer
+               } else {
+                  &self.assets.tr.follow_peer
+               },
+            )
+            .clicked();
+         if can_follow {
+            ui.space(8.0);
+         }
+         if let Some(latency) = self.peer.latency(peer_id) {
+            let latency = self
+               .assets
+               .tr
+               .peer_latency
+               .format()
+               .with("milliseconds", latency.as_millis() as u64)
+               .done();
+            let width = self.assets.sans.text_width(&latency);
+            ui.horizontal_label(
+               &self.assets.sans,
+               &latency,
+               self.assets.colors.text,
+               Some((width, AlignH::Right)),
+            );
+            ui.space(8.0);
+         }
+         ui.push((ui.remaining_width(), ui.height()), Layout::Horizontal);
+         ui.pad((8.0, 0.0));
+         // The peer's color, same as the one used for their cursor and selection.
+         ui.push((12.0, ui.height()), Layout::Freeform);
+         ui.draw(|ui| {
+            let center = point(ui.width() / 2.0, ui.height() / 2.0);
+            ui.render().fill_circle(center, 5.0, color);
+         });
+         ui.pop();
+         ui.space(4.0);
+         let name = truncate_text(&self.assets.sans, ui.remaining_width(), &nickname);
+         ui.horizontal_label(&self.assets.sans, &name, self.assets.colors.text, None);
+         ui.pop();
+         ui.pop();
+
+         if copy_clicked {
+            self.copy_peer_tool(ui, peer_id, &nickname, tool_name.as_deref().unwrap());
+         }
+         if go_to_clicked {
+            self.go_to_peer(peer_id, &nickname, tool_name.as_deref());
+         }
+         if follow_clicked {
+            self.toggle_following(peer_id, &nickname);
+         }
+      }
+   }
+
+   /// Switches to the tool the given peer is using, and copies its settings.
+   fn copy_peer_tool(&mut self, ui: &mut Ui, peer_id: PeerId, nickname: &str, tool_name: &str) {
+      let Some(tool) = self.toolbar.tool_by_name(tool_name) else {
+         tracing::warn!("peer {:?} is using unknown tool {:?}", peer_id, tool_name);
+         return;
+      };
+      self.set_current_tool(ui, tool);
+      let copied = self.toolbar.with_tool(tool, |tool| {
+         tool.copy_peer_settings(peer_id, &mut self.global_controls)
+      });
+      if copied {
+         log!(
+            self.log,
+            "{}",
+            self.assets.tr.peer_tool_copied.format().with("nickname", nickname).done()
+         );
+      }
+   }
+
+   /// Starts panning the viewport over to the given peer's last known position, which is taken
+   /// from the tool they're using.
+   fn go_to_peer(&mut self, peer_id: PeerId, nickname: &str, tool_name: Option<&str>) {
+      let position = tool_name
+         .and_then(|tool_name| self.toolbar.tool_by_name(tool_name))
+         .and_then(|tool| self.toolbar.with_tool(tool, |tool| tool.peer_position(peer_id)));
+      match position {
+         Some(position) => {
+            self.following = None;
+            let pan = self.viewport.pan();
+            self.pan_animation = Some(PanAnimation {
+               from: point(pan.x, pan.y),
+               to: position,
+               started: Instant::now(),
+            });
+         }
+         None => log!(
+            self.log,
+            "{}",
+            self.assets.tr.peer_position_unknown.format().with("nickname", nickname).done()
+         ),
+      }
+   }
+
+   /// Starts or stops following the given peer's viewport.
+   fn toggle_following(&mut self, peer_id: PeerId, nickname: &str) {
+      if self.following == Some(peer_id) {
+         self.following = None;
+      } else {
+         self.following = Some(peer_id);
+         self.pan_animation = None;
+         log!(
+            self.log,
+            "{}",
+            self.assets.tr.following_peer.format().with("nickname", nickname).done()
+         );
+      }
+   }
+
+   /// Pans and zooms the viewport towards the viewport of the followed peer, if any. Stops
+   /// following once the peer stops sharing their viewport.
+   fn follow_peer_viewport(&mut self, canvas_size: Vector) {
+      let Some(peer_id) = self.following else {
+         return;
+      };
+      let Some(mate) = self.peer.mates().get(&peer_id) else {
+         return;
+      };
+      match mate.viewport {
+         Some((left, top, right, bottom)) => {
+            let rect = Rect::new(
+               point(left as f32, top as f32),
+               vector((right - left) as f32, (bottom - top) as f32),
+            );
+            self.viewport.zoom_to_fit(rect, canvas_size);
+         }
+         None => {
+            log!(
+               self.log,
+               "{}",
+               self
+                  .assets
+                  .tr
+                  .followed_peer_stopped_sharing
+                  .format()
+                  .with("nickname", mate.nickname.as_str())
+                  .done()
+            );
+            self.following = None;
+         }
+      }
+   }
+
+   /// Sends the viewport to other peers if the user chose to share it, and it changed since it
+   /// was last sent. Once the user stops sharing it, the other peers are told about that.
+   fn share_viewport(&mut self, canvas_size: Vector) -> netcanv::Result<()> {
+      let viewport = config().lobby.share_viewport.then(|| {
+         let rect = self.viewport.visible_rect(canvas_size);
+         (
+            rect.left().floor() as i32,
+            rect.top().floor() as i32,
+            rect.right().ceil() as i32,
+            rect.bottom().ceil() as i32,
+         )
+      });
+      let elapsed = self.viewport_shared_at.elapsed();
+      let changed = viewport != self.shared_viewport && elapsed >= Self::VIEWPORT_SHARE_INTERVAL;
+      let refresh = viewport.is_some() && elapsed >= Self::VIEWPORT_REFRESH_INTERVAL;
+      if changed || refresh {
+         self.peer.send_viewport(viewport)?;
+         self.shared_viewport = viewport;
+         self.viewport_shared_at = Instant::now();
+      }
+      if viewport != self.shared_viewport {
+         wake::at(self.viewport_shared_at + Self::VIEWPORT_SHARE_INTERVAL);
+      } else if viewport.is_some() {
+         wake::at(self.viewport_shared_at + Self::VIEWPORT_REFRESH_INTERVAL);
+      }
+      Ok(())
+   }
+
    fn process_peer_message(&mut self, ui: &mut Ui, message: peer::Message) -> netcanv::Result<()> {
       use peer::MessageKind;
 
@@ -766,7 +1833,13 @@ impl State {
                   .done()
             );
             if self.peer.is_host() {
-               let positions = self.paint_canvas.chunk_positions();
+               // The bounds must arrive before the chunk positions, so that the peer doesn't
+               // start out with an unbounded canvas.
+               if let Some(bounds) = self.paint_canvas.bounds() {
+                  self.peer.send_drawing_bounds(peer_id, bounds.to_tuple())?;
+               }
+               let mut positions = self.paint_canvas.chunk_positions();
+               positions.retain(|&position| self.paint_canvas.chunk_is_in_bounds(position));
                self.peer.send_chunk_positions(peer_id, positions)?;
             }
             // Order matters here! The tool selection packet must arrive before the packets sent
@@ -783,48 +1856,163 @@ impl State {
             nickname,
             last_tool,
          } => {
-            log!(
-               self.log,
-               "{}",
-               self
-                  .assets
-                  .tr
-                  .someone_left_the_room
-                  .format()
-                  .with("nickname", nickname.as_str())
-                  .done()
-            );
+            // Everyone is forgotten when the connection to the relay is lost, but they haven't
+            // really left the room.
+            if !self.peer.is_reconnecting() {
+               log!(
+                  self.log,
+                  "{}",
+                  self
+                     .assets
+                     .tr
+                     .someone_left_the_room
+                     .format()
+                     .with("nickname", nickname.as_str())
+                     .done()
+               );
+            }
+            if self.following == Some(peer_id) {
+               self.following = None;
+               if !self.peer.is_reconnecting() {
+                  log!(
+                     self.log,
+                     "{}",
+                     self
+                        .assets
+                        .tr
+                        .followed_peer_left
+                        .format()
+                        .with("nickname", nickname.as_str())
+                        .done()
+                  );
+               }
+            }
             // Make sure the tool they were last using is properly deinitialized.
             if let Some(tool) = last_tool {
                if let Some(tool_id) = self.toolbar.tool_by_name(&tool) {
                   self.toolbar.with_tool(tool_id, |tool| {
-                     tool.network_peer_deactivate(
-                        ui,
-                        Net::new(&self.peer),
-                        &mut self.paint_canvas,
-                        peer_id,
-                     )
+                     self.paint_canvas.with_base_layer_active(|paint_canvas| {
+                        tool.network_peer_deactivate(
+                           ui,
+                           Net::new(&self.peer),
+                           paint_canvas,
+                           peer_id,
+                        )
+                     })
                   })?
                }
             }
          }
-         MessageKind::NewHost(nickname) => log!(
-            self.log,
-            "{}",
-            self
-               .assets
-               .tr
-               .someone_is_now_hosting_the_room
-               .format()
-               .with("nickname", nickname.as_str())
-               .done()
-         ),
+         MessageKind::NewHost { peer_id, nickname } => {
+            if let Some(nickname) = nickname {
+               log!(
+                  self.log,
+                  "{}",
+                  self
+                     .assets
+                     .tr
+                     .someone_is_now_hosting_the_room
+                     .format()
+                     .with("nickname", nickname.as_str())
+                     .done()
+               );
+            }
+            // The new host may have only downloaded the chunks they've seen, so send them the
+            // positions of all the chunks we have, so that they can download the ones they're
+            // missing before the rest of the canvas is lost.
+            self.peer.send_chunk_positions(peer_id, self.paint_canvas.chunk_positions())?;
+            // Downloads requested from the previous host will never arrive, so request them
+            // again from the new host once they become visible.
+            for state in self.chunk_downloads.values_mut() {
+               if matches!(state, ChunkDownload::Queued | ChunkDownload::Requested(_)) {
+                  *state = ChunkDownload::NotDownloaded;
+               }
+            }
+         }
          MessageKind::NowHosting => {
             log!(self.log, "{}", self.assets.tr.you_are_now_hosting_the_room);
+            // Chunks that weren't downloaded yet will be resynchronized from the other peers, who
+            // send us the positions of the chunks they have.
+            self.chunk_downloads.retain(|_, state| *state == ChunkDownload::Downloaded);
+         }
+         MessageKind::Reconnecting => {
+            log!(self.log, "{}", self.assets.tr.connection_lost);
+            // Downloads that were underway will never arrive, so they're requested again after
+            // reconnecting.
+            for state in self.chunk_downloads.values_mut() {
+               if matches!(state, ChunkDownload::Queued | ChunkDownload::Requested(_)) {
+                  *state = ChunkDownload::NotDownloaded;
+               }
+            }
+         }
+         MessageKind::Reconnected { away } => {
+            if let Some(host) = self.peer.host() {
+               log!(self.log, "{}", self.assets.tr.reconnected);
+               // The host sends us the positions of its chunks, and the ones that changed while we
+               // were away are then downloaded again. In return, the host gets to download the
+               // chunks it's missing, in case we were the host before losing the connection.
+               self.away_after_reconnect = Some(away);
+               self.peer.send_chunk_positions(host, self.paint_canvas.chunk_positions())?;
+            } else {
+               // Nobody's left to download the missing chunks from.
+               self.chunk_downloads.retain(|_, state| *state == ChunkDownload::Downloaded);
+               let room_id = self.peer.room_id().unwrap().to_string();
+               log!(
+                  self.log,
+                  "{}",
+                  self
+                     .assets
+                     .tr
+                     .reconnected_to_new_room
+                     .format()
+                     .with("id", room_id.as_str())
+                     .done()
+               );
+            }
+         }
+         MessageKind::DrawingBounds(bounds) => {
+            let bounds = DrawingBounds::from_tuple(bounds);
+            tracing::debug!("received drawing bounds {:?}", bounds);
+            self.paint_canvas.set_bounds(Some(bounds));
+         }
+         MessageKind::ClearCanvas => {
+            tracing::info!("the host cleared the canvas");
+            self.paint_canvas.clear();
             self.chunk_downloads.clear();
+            log!(self.log, "{}", self.assets.tr.canvas_cleared);
          }
-         MessageKind::ChunkPositions(positions) => {
+         MessageKind::ChunkPositions(sender, mut positions) if self.peer.is_host() => {
+            Self::reject_chunks_outside_of_limits(sender, &mut positions);
+            // After a host transfer, download the chunks we're missing from the peer that has
+            // them. Chunks that were already requested from another peer are skipped.
+            let missing: Vec<_> = positions
+               .into_iter()
+               .filter(|&position| {
+                  self.paint_canvas.chunk(position).is_none()
+                     && self.paint_canvas.chunk_is_in_bounds(position)
+                     && !self.chunk_downloads.contains_key(&position)
+               })
+               .collect();
+            tracing::debug!("resyncing {} chunks from {:?}", missing.len(), sender);
+            if !missing.is_empty() {
+               let now = Instant::now();
+               for &chunk_position in &missing {
+                  self.chunk_downloads.insert(chunk_position, ChunkDownload::Requested(now));
+               }
+               wake::at(now + Self::CHUNK_DOWNLOAD_TIMEOUT);
+               self.peer.request_chunks(sender, missing)?;
+            }
+         }
+         MessageKind::ChunkPositions(sender, mut positions) => {
+            Self::reject_chunks_outside_of_limits(sender, &mut positions);
             tracing::debug!("received {} chunk positions", positions.len());
+            // After reconnecting, the chunks we already have are only downloaded again if they
+            // changed while we were away. The host replies to the request with their positions.
+            if let Some(away) = self.away_after_reconnect.take() {
+               if self.peer.request_changed_chunks(sender, away)? {
+                  positions.retain(|&position| self.paint_canvas.chunk(position).is_none());
+               }
+            }
             for chunk_position in positions {
                self.chunk_downloads.insert(chunk_position, ChunkDownload::NotDownloaded);
             }
@@ -838,23 +2026,46 @@ impl State {
          MessageKind::Chunks(chunks) => {
             tracing::debug!("received {} chunks", chunks.len());
             for (chunk_position, image_data) in chunks {
+               if !PaintCanvas::chunk_is_within_limits(chunk_position) {
+                  tracing::warn!("rejecting chunk {:?} outside of the limits", chunk_position);
+                  continue;
+               }
                self.decode_canvas_data(chunk_position, image_data);
-               self.chunk_downloads.insert(chunk_position, ChunkDownload::Downloaded);
             }
          }
-         MessageKind::GetChunks(requester, positions) => {
+         MessageKind::ChunkPatch(chunk_position, offset, image_data) => {
+            if PaintCanvas::chunk_is_within_limits(chunk_position) {
+               catch!(self.apply_chunk_patch(ui, chunk_position, offset, &image_data));
+            } else {
+               tracing::warn!(
+                  "rejecting patch of chunk {:?} outside of the limits",
+                  chunk_position
+               );
+            }
+         }
+         MessageKind::GetChunks(requester, mut positions) => {
+            positions.retain(|&position| self.paint_canvas.chunk_is_in_bounds(position));
             self.encode_chunks(ui, requester, &positions);
          }
+         MessageKind::GetChangedChunks(requester, within) => {
+            let mut positions = self.paint_canvas.chunks_modified_within(within);
+            positions.retain(|&position| self.paint_canvas.chunk_is_in_bounds(position));
+            tracing::debug!("{} chunks changed within {:?}", positions.len(), within);
+            self.peer.send_chunk_positions(requester, positions)?;
+         }
          MessageKind::Tool(sender, name, payload) => {
             if let Some(tool_id) = self.toolbar.tool_by_name(&name) {
+               // Other peers only know about the base layer, so that's where their drawings go.
                self.toolbar.with_tool(tool_id, |tool| {
-                  tool.network_receive(
-                     ui,
-                     Net::new(&self.peer),
-                     &mut self.paint_canvas,
-                     sender,
-                     payload.clone(),
-                  )
+                  self.paint_canvas.with_base_layer_active(|paint_canvas| {
+                     tool.network_receive(
+                        ui,
+                        Net::new(&self.peer),
+                        paint_canvas,
+                        sender,
+                        payload.clone(),
+                     )
+                  })
                })?;
             }
          }
@@ -869,12 +2080,14 @@ impl State {
                if let Some(tool_id) = self.toolbar.tool_by_name(&tool) {
                   // ↑ still waiting for if_let_chains to get stabilized.
                   self.toolbar.with_tool(tool_id, |tool| {
-                     tool.network_peer_deactivate(
-                        ui,
-                        Net::new(&self.peer),
-                        &mut self.paint_canvas,
-                        address,
-                     )
+                     self.paint_canvas.with_base_layer_active(|paint_canvas| {
+                        tool.network_peer_deactivate(
+                           ui,
+                           Net::new(&self.peer),
+                           paint_canvas,
+                           address,
+                        )
+                     })
                   })?;
                }
             }
@@ -890,6 +2103,20 @@ impl State {
       Ok(())
    }
 
+   /// Removes the positions of chunks that lie outside of the canvas's limits from a list of
+   /// positions sent by another peer, as allocating them would only waste memory.
+   fn reject_chunks_outside_of_limits(sender: PeerId, positions: &mut Vec<(i32, i32)>) {
+      let count = positions.len();
+      positions.retain(|&position| PaintCanvas::chunk_is_within_limits(position));
+      if positions.len() < count {
+         tracing::warn!(
+            "rejecting {} chunk positions outside of the limits from {:?}",
+            count - positions.len(),
+            sender
+         );
+      }
+   }
+
    fn encode_chunks(
       &mut self,
       renderer: &mut Backend,
@@ -909,41 +2136,55 @@ impl State {
             "fetching data for networking transmission of chunk {:?}",
             chunk_position
          );
-         // If there is a cached image already, there's no point in encoding it all over again.
-         if let Some(chunk) = self.cache_layer.chunk(chunk_position) {
+         let Some(chunk) = self.paint_canvas.chunk(chunk_position) else {
+            continue;
+         };
+         let revision = chunk.revision();
+         // If there is a cached image of this revision already, there's no point in encoding it
+         // all over again.
+         if let Some(images) = self.cache_layer.chunk(chunk_position, revision) {
             tracing::debug!("reusing {:?}", chunk_position);
-            let _ = self.encode_channels.tx.send((chunk_position, chunk.to_owned()));
-            let _ = tx.send((chunk_position, chunk.to_owned()));
-         } else if let Some(chunk) = self.paint_canvas.chunk(chunk_position) {
-            // If the chunk's image is empty, there's no point in sending it.
-            let image = chunk.download_image(renderer);
-            if Chunk::image_is_empty(&image) {
-               continue;
-            }
-            // Otherwise, we can start encoding the chunk image.
-            let encoded_chunks_tx = self.encode_channels.tx.clone();
-            let tx = tx.clone();
-
-            tokio::spawn(async move {
-               tracing::debug!("encoding image data for chunk {:?}", chunk_position);
-               let image_data = ImageCoder::encode_network_data(image).await;
-               tracing::debug!("encoding done for chunk {:?}", chunk_position);
-               match image_data {
-                  Ok(data) => {
-                     tracing::debug!("sending image data back to main thread");
-                     let _ = encoded_chunks_tx.send((chunk_position, data.clone()));
-                     let _ = tx.send((chunk_position, data));
-                  }
-                  Err(error) => {
-                     tracing::error!(
-                        "error while encoding image for chunk {:?}: {:?}",
-                        chunk_position,
-                        error
-                     );
-                  }
+            let _ = tx.send((chunk_position, images.to_owned()));
+            continue;
+         }
+         // The same goes for when the chunk is already being encoded for another peer; the result
+         // will be sent to everyone who asked for it.
+         if let Some(requesters) = self.pending_encodes.get_mut(&(chunk_position, revision)) {
+            tracing::debug!("waiting for pending encode of {:?}", chunk_position);
+            requesters.push(requester);
+            continue;
+         }
+         // If the chunk's image is empty, there's no point in sending it.
+         let image = chunk.download_image(renderer);
+         if Chunk::image_is_empty(&image) {
+            continue;
+         }
+         // Otherwise, we can start encoding the chunk image.
+         self.pending_encodes.insert((chunk_position, revision), vec![requester]);
+         let encoded_chunks_tx = self.encode_channels.tx.clone();
+         tokio::spawn(async move {
+            tracing::debug!("encoding image data for chunk {:?}", chunk_position);
+            let image_data = ImageCoder::encode_network_data(image).await;
+            tracing::debug!("encoding done for chunk {:?}", chunk_position);
+            let images = match image_data {
+               Ok(data) => Some(data),
+               Err(error) => {
+                  tracing::error!(
+                     "error while encoding image for chunk {:?}: {:?}",
+                     chunk_position,
+                     error
+                  );
+                  None
                }
+            };
+            tracing::debug!("sending image data back to main thread");
+            let _ = encoded_chunks_tx.send(EncodedChunk {
+               position: chunk_position,
+               revision,
+               images,
             });
-         }
+            wake::wake();
+         });
       }
    }
 
@@ -955,13 +2196,39 @@ impl State {
          DirectionV::BottomToTop,
       );
       let padded_canvas = view::layout::padded(&self.canvas_view, Self::CANVAS_INNER_PADDING);
-
-      // The overflow menu.
+      // Popups anchored to the canvas's edges shouldn't cover the toolbar.
+      let popup_area =
+         view::layout::padded(&padded_canvas, Toolbar::padding(Self::CANVAS_INNER_PADDING));
+
+      // The overflow menu. Its height depends on how many peers are in the room.
+      let peer_count = self.peer.mates().len();
+      let peers_height = if peer_count > 0 {
+         Self::PEER_ROW_HEIGHT * peer_count as f32 + 8.0 * 2.0
+      } else {
+         0.0
+      };
+      self.overflow_menu.view.dimensions.vertical =
+         Dimension::Constant(self.overflow_menu_height + peers_height);
       view::layout::align(
-         &padded_canvas,
+         &popup_area,
          &mut self.overflow_menu.view,
          (AlignH::Right, AlignV::Bottom),
       );
+      view::layout::align(
+         &popup_area,
+         self.connection_status.details_view(),
+         (AlignH::Right, AlignV::Bottom),
+      );
+      view::layout::align(
+         &popup_area,
+         self.go_to_coordinates.view(),
+         (AlignH::Center, AlignV::Top),
+      );
+      view::layout::align(
+         &popup_area,
+         &mut self.minimap.view,
+         (AlignH::Right, AlignV::Top),
+      );
    }
 }
 
@@ -984,6 +2251,9 @@ impl AppState for State {
             paint_canvas: &mut self.paint_canvas,
             project_file: &mut self.project_file,
             renderer: ui,
+            viewport: &self.viewport,
+            canvas_size: self.canvas_view.size(),
+            is_host: self.peer.is_host(),
          }) {
             Ok(()) => (),
             Err(error) => log!(
@@ -1014,12 +2284,21 @@ impl AppState for State {
          .map(|message| message.consume().0)
          .collect();
       if !needed_chunks.is_empty() {
+         let now = Instant::now();
          for &chunk_position in &needed_chunks {
-            self.chunk_downloads.insert(chunk_position, ChunkDownload::Requested);
+            self.chunk_downloads.insert(chunk_position, ChunkDownload::Requested(now));
          }
+         wake::at(now + Self::CHUNK_DOWNLOAD_TIMEOUT);
          catch!(self.peer.download_chunks(needed_chunks));
       }
 
+      // Tips
+
+      for message in &bus::retrieve_all::<ShowTip>() {
+         let ShowTip(tip) = message.consume();
+         self.show_tip(&tip, Duration::from_secs(3));
+      }
+
       // Error checking
 
       for message in &bus::retrieve_all::<common::Log>() {
@@ -1049,6 +2328,18 @@ impl AppState for State {
 
       // Paint canvas
       self.process_canvas(ui, input);
+      if config().ui.show_minimap {
+         // Chunks that weren't downloaded yet count too, as they're known to be painted on.
+         let chunk_positions =
+            self.paint_canvas.all_chunk_positions().chain(self.chunk_downloads.keys().copied());
+         self.minimap.process(
+            ui,
+            input,
+            &mut self.viewport,
+            self.canvas_view.size(),
+            chunk_positions,
+         );
+      }
 
       // Bars
       let toolbar_process = self.toolbar.process(
@@ -1065,11 +2356,45 @@ impl AppState for State {
       }
       // Draw windows over the toolbar, but below the bottom bar.
       self.wm.process(ui, input, &self.assets);
+      if self.keymap_editor.process(&mut self.wm) {
+         log!(self.log, "{}", self.assets.tr.keymap_editor.saved);
+      }
+      if let Some(color_scheme) = self.settings.process(&mut self.wm) {
+         config::write(|config| config.ui.color_scheme = color_scheme);
+         self.assets.colors = ColorScheme::load(color_scheme);
+      }
       self.process_bar(ui, input);
       self.process_overflow_menu(ui, input);
+      if let Some(health) = self.peer.connection_health() {
+         self.connection_status.process_details(ui, input, &self.assets, &health);
+      }
+      if let Some(coordinates) = self.go_to_coordinates.process(ui, input, &self.assets) {
+         self.viewport.center_on(coordinates);
+      }
    }
 
-   fn next_state(self: Box<Self>, _renderer: &mut Backend) -> Box<dyn AppState> {
+   /// Keeps redrawing while tools are animating, while connecting, while a project is being
+   /// loaded, while the viewport is being panned or animating a zoom, and while the
+   /// go-to-coordinates popup's caret is blinking. Incoming network data is
+   /// picked up by the periodic wake-ups while idle.
+   fn needs_redraw(&self) -> bool {
+      self.toolbar.needs_redraw()
+         || self.peer.needs_redraw()
+         || self.project_load.is_some()
+         || self.project_file.is_saving()
+         || self.panning
+         || self.last_keyboard_pan.is_some()
+         || self.pan_animation.is_some()
+         || self.viewport.is_animating()
+         || self.go_to_coordinates.is_open()
+   }
+
+   fn next_state(mut self: Box<Self>, _renderer: &mut Backend) -> Box<dyn AppState> {
+      if self.fatal_error || self.leave_the_room {
+         self.global_controls.color_picker.save_colors();
+         self.save_tools();
+         self.save_window_layout();
+      }
       if self.fatal_error {
          Box::new(lobby::State::new(self.assets, self.socket_system))
       } else if self.leave_the_room {
@@ -1082,5 +2407,63 @@ impl AppState for State {
       }
    }
 
-   fn exit(self: Box<Self>) {}
+   fn exit(mut self: Box<Self>) {
+      self.global_controls.color_picker.save_colors();
+      self.save_tools();
+      self.save_window_layout();
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn pan_animation_ends_at_the_peer() {
+      let animation = PanAnimation {
+         from: point(0.0, 0.0),
+         to: point(100.0, -50.0),
+         started: Instant::now(),
+      };
+      assert_eq!(animation.position(Duration::ZERO), (point(0.0, 0.0), false));
+      assert_eq!(
+         animation.position(State::PAN_ANIMATION_DURATION / 2),
+         (point(50.0, -25.0), false)
+      );
+      assert_eq!(
+         animation.position(State::PAN_ANIMATION_DURATION),
+         (point(100.0, -50.0), true)
+      );
+      // Frames may come late, but the viewport never overshoots.
+      assert_eq!(
+         animation.position(State::PAN_ANIMATION_DURATION * 3),
+         (point(100.0, -50.0), true)
+      );
+   }
+
+   #[test]
+   fn keyboard_panning_depends_on_the_frame_time() {
+      let right = vector(1.0, 0.0);
+      assert_eq!(
+         State::keyboard_pan_delta(right, 480.0, false, Some(1.0 / 32.0)),
+         vector(15.0, 0.0)
+      );
+      // Panning starts off with a single frame's worth, and hitches don't make it jump.
+      assert_eq!(
+         State::keyboard_pan_delta(right, 480.0, false, None),
+         vector(8.0, 0.0)
+      );
+      assert_eq!(
+         State::keyboard_pan_delta(right, 480.0, false, Some(3.0)),
+         vector(32.0, 0.0)
+      );
+   }
+
+   #[test]
+   fn shift_pans_faster() {
+      assert_eq!(
+         State::keyboard_pan_delta(vector(-1.0, -1.0), 480.0, true, None),
+         vector(-32.0, -32.0)
+      );
+   }
 }
@@ -0,0 +1,273 @@
+//! The settings window, for changing preferences while in a room.
+
+use netcanv_renderer::paws::{point, AlignH, AlignV, Layout, Padding, Rect};
+
+use crate::assets::Assets;
+use crate::config::{self, config};
+use crate::ui::view::{Dimension, Dimensions, View};
+use crate::ui::wm::windows::WindowButtonStyle;
+use crate::ui::wm::{
+   HitTest, WindowContent, WindowContentArgs, WindowContentWrappers, WindowId, WindowManager,
+};
+use crate::ui::{
+   Button, ButtonArgs, ButtonColors, ContextMenu, ContextMenuArgs, Input, Ui, UiElements,
+};
+
+/// The data shared between the settings window and its [`Settings`].
+pub struct SettingsData {
+   /// The color scheme currently in use.
+   color_scheme: config::ColorScheme,
+   /// The color scheme picked in the window, which is yet to be applied.
+   picked_color_scheme: Option<config::ColorScheme>,
+}
+
+/// Opens and closes the settings window, and reports the changes made in it.
+pub struct Settings {
+   window: Option<WindowId<SettingsData>>,
+}
+
+impl Settings {
+   /// Creates a new settings window owner, with the window closed.
+   pub fn new() -> Self {
+      Self { window: None }
+   }
+
+   /// Returns a view for the window. This view should be laid out and then passed to
+   /// [`Settings::open`].
+   pub fn view() -> View {
+      View::new(SettingsWindow::DIMENSIONS)
+   }
+
+   /// Opens the window. Does nothing if the window is already open.
+   pub fn open(&mut self, wm: &mut WindowManager, view: View) {
+      if self.window.is_some() {
+         return;
+      }
+      let data = SettingsData {
+         color_scheme: config().ui.color_scheme,
+         picked_color_scheme: None,
+      };
+      let content = SettingsWindow::new().background().buttons(WindowButtonStyle {
+         padding: Padding::even(12.0),
+      });
+      // The window is pinned, so that it stays open while trying out themes on the canvas.
+      self.window = Some(wm.open_window(view, content, data).set_pinned(true).finish());
+   }
+
+   /// Closes the window once the user closes it. Returns the color scheme picked in the window
+   /// during this frame, if any.
+   pub fn process(&mut self, wm: &mut WindowManager) -> Option<config::ColorScheme> {
+      let window_id = self.window.as_ref()?;
+      if wm.should_close(window_id) {
+         wm.close_window(self.window.take().unwrap());
+         return None;
+      }
+      let data = wm.window_data_mut(window_id);
+      let picked = data.picked_color_scheme.take()?;
+      data.color_scheme = picked;
+      Some(picked)
+   }
+}
+
+/// The settings window's content.
+struct SettingsWindow {
+   /// The dropdown menu listing the color schemes.
+   theme_menu: ContextMenu,
+}
+
+impl SettingsWindow {
+   /// The dimensions of the window.
+   const DIMENSIONS: Dimensions = Dimensions {
+      horizontal: Dimension::Constant(448.0),
+      vertical: Dimension::Constant(Self::HEADER_HEIGHT + 196.0),
+   };
+
+   /// The height of the header bar, which holds the title.
+   const HEADER_HEIGHT: f32 = 48.0;
+   /// The width of the button that opens the theme menu.
+   const THEME_BUTTON_WIDTH: f32 = 160.0;
+   /// The color schemes listed in the theme menu, in order.
+   const COLOR_SCHEMES: [config::ColorScheme; 4] = [
+      config::ColorScheme::Light,
+      config::ColorScheme::Dark,
+      config::ColorScheme::HighContrast,
+      config::ColorScheme::Custom,
+   ];
+
+   fn new() -> Self {
+      Self {
+         theme_menu: ContextMenu::new((0.0, 0.0)),
+      }
+   }
+
+   /// Returns the translated name of the color scheme.
+   fn color_scheme_name(assets: &Assets, scheme: config::ColorScheme) -> &str {
+      let strings = &assets.tr.settings;
+      match scheme {
+         config::ColorScheme::Light => &strings.theme_light,
+         config::ColorScheme::Dark => &strings.theme_dark,
+         config::ColorScheme::HighContrast => &strings.theme_high_contrast,
+         config::ColorScheme::Custom => &strings.theme_custom,
+      }
+   }
+
+   /// Processes the header bar, which holds the title and can be used to drag the window around.
+   fn process_header_bar(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      hit_test: &mut HitTest,
+   ) {
+      ui.push((ui.width(), Self::HEADER_HEIGHT), Layout::Freeform);
+      if ui.hover(input) {
+         *hit_test = HitTest::Draggable;
+      }
+      ui.pad((16.0, 0.0));
+      ui.text(
+         &assets.sans_bold,
+         &assets.tr.settings.title,
+         assets.colors.text,
+         (AlignH::Left, AlignV::Middle),
+      );
+      ui.pop();
+   }
+
+   /// Processes the theme setting, whose button opens the theme menu.
+   fn process_theme(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      data: &SettingsData,
+   ) {
+      let strings = &assets.tr.settings;
+
+      ui.push((ui.width(), 24.0), Layout::Horizontal);
+      ui.horizontal_label(
+         &assets.sans,
+         &strings.theme,
+         assets.colors.text,
+         Some((ui.width() - Self::THEME_BUTTON_WIDTH, AlignH::Left)),
+      );
+      let button = Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+         &assets.sans,
+         Self::color_scheme_name(assets, data.color_scheme),
+      );
+      let button_rect = button.group();
+      if button.clicked() {
+         if self.theme_menu.is_open() {
+            self.theme_menu.close();
+         } else {
+            self.open_theme_menu(button_rect);
+         }
+      }
+      ui.pop();
+
+      ui.space(12.0);
+      ui.vertical_label(
+         &assets.sans,
+         &strings.custom_theme_hint,
+         assets.colors.text,
+         AlignH::Left,
+      );
+      ui.vertical_label(
+         &assets.sans,
+         &strings.reload_theme_hint,
+         assets.colors.text,
+         AlignH::Left,
+      );
+   }
+
+   /// Opens the theme menu below the given button.
+   fn open_theme_menu(&mut self, button_rect: Rect) {
+      let item_count = Self::COLOR_SCHEMES.len();
+      let height = 16.0 + item_count as f32 * 24.0 + (item_count - 1) as f32 * 4.0;
+      self.theme_menu = ContextMenu::new((Self::THEME_BUTTON_WIDTH, height));
+      self.theme_menu.view.position = point(button_rect.left(), button_rect.bottom() + 4.0);
+      self.theme_menu.open();
+   }
+
+   /// Processes the theme menu, if it's open. Picking the custom theme again reloads it, so that
+   /// changes made to the theme file can be previewed.
+   fn process_theme_menu(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      data: &mut SettingsData,
+   ) {
+      if self
+         .theme_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(8.0);
+         for scheme in Self::COLOR_SCHEMES {
+            ui.push((ui.width(), 24.0), Layout::Horizontal);
+            let colors = ButtonColors::toggle(
+               scheme == data.color_scheme,
+               &assets.colors.radio_button.normal,
+               &assets.colors.radio_button.selected,
+            );
+            if Button::with_text(
+               ui,
+               input,
+               &ButtonArgs::new(ui, colors).height(24.0).pill(),
+               &assets.sans,
+               Self::color_scheme_name(assets, scheme),
+            )
+            .clicked()
+            {
+               data.picked_color_scheme = Some(scheme);
+               self.theme_menu.close();
+            }
+            ui.pop();
+            ui.space(4.0);
+         }
+         self.theme_menu.end(ui);
+      }
+   }
+}
+
+impl WindowContent for SettingsWindow {
+   type Data = SettingsData;
+
+   fn process(
+      &mut self,
+      WindowContentArgs {
+         ui,
+         input,
+         assets,
+         hit_test,
+         ..
+      }: &mut WindowContentArgs,
+      data: &mut Self::Data,
+   ) {
+      ui.push(ui.size(), Layout::Vertical);
+
+      self.process_header_bar(ui, input, assets, hit_test);
+
+      ui.push(ui.remaining_size(), Layout::Vertical);
+      ui.pad(Padding {
+         top: 0.0,
+         ..Padding::even(16.0)
+      });
+      self.process_theme(ui, input, assets, data);
+      ui.pop();
+
+      ui.pop();
+
+      // The theme menu is processed last, such that it's drawn over everything else.
+      self.process_theme_menu(ui, input, assets, data);
+   }
+}
@@ -6,10 +6,10 @@ use std::rc::Rc;
 
 use crate::backend::winit::event::MouseButton;
 use netcanv_renderer::paws::{
-   point, vector, AlignH, AlignV, Alignment, Color, Layout, LineCap, Point, Rect, Renderer,
+   point, AlignH, AlignV, Alignment, Color, Layout, LineCap, Padding, Point, Renderer,
 };
 
-use crate::common::{ControlFlow, RectMath};
+use crate::common::ControlFlow;
 use crate::config::{self, config, ToolbarPosition};
 use crate::ui::view::{self, Dimensions, View};
 use crate::ui::wm::{HitTest, WindowContent, WindowContentArgs, WindowId, WindowManager};
@@ -47,9 +47,12 @@ pub struct Toolbar {
 }
 
 impl Toolbar {
+   /// The distance from the canvas's edges, past which the toolbar is detached when dropped.
+   const DETACH_DISTANCE: f32 = 96.0;
+
    /// Creates a new, empty toolbar.
    pub fn new(wm: &mut WindowManager) -> Self {
-      let view = View::new(ToolbarWindow::dimensions(0));
+      let view = View::new(ToolbarWindow::dimensions(0, Self::position()));
       let content = ToolbarWindow::new();
       let tools = Rc::new(RefCell::new(Vec::new()));
       let data = ToolbarData::new(Rc::clone(&tools));
@@ -68,6 +71,26 @@ impl Toolbar {
       config().ui.toolbar_position
    }
 
+   /// Returns padding that keeps elements aligned to the canvas's edges from overlapping the
+   /// toolbar, with the given amount of spacing between them and the toolbar.
+   pub fn padding(spacing: f32) -> Padding {
+      let size = ToolbarWindow::TOOLBAR_SIZE + spacing;
+      let (mut left, mut top, mut right, mut bottom) = (0.0, 0.0, 0.0, 0.0);
+      match Self::position() {
+         ToolbarPosition::Left => left = size,
+         ToolbarPosition::Right => right = size,
+         ToolbarPosition::Top => top = size,
+         ToolbarPosition::Bottom => bottom = size,
+         ToolbarPosition::Floating => (),
+      }
+      Padding {
+         left,
+         top,
+         right,
+         bottom,
+      }
+   }
+
    /// Adds a tool into the toolbar.
    pub fn add_tool(&mut self, tool: impl Tool + 'static) -> ToolId {
       let boxed = Box::new(tool);
@@ -116,6 +139,11 @@ impl Toolbar {
       self.with_tool(self.current_tool, f)
    }
 
+   /// Returns whether any of the tools needs another frame to be processed.
+   pub fn needs_redraw(&self) -> bool {
+      self.tools.borrow().iter().any(|tool| tool.needs_redraw())
+   }
+
    /// Borrows each tool mutably to the given closure.
    pub fn with_each_tool<B, F>(&mut self, mut f: F) -> Option<B>
    where
@@ -135,36 +163,60 @@ impl Toolbar {
       value
    }
 
-   /// Returns the alignment of the toolbar window, according to a toolbar position.
-   fn view_alignment(position: ToolbarPosition) -> Alignment {
+   /// Returns the alignment of the toolbar window, according to a toolbar position. Returns `None`
+   /// for floating toolbars, as they aren't aligned to anything.
+   fn view_alignment(position: ToolbarPosition) -> Option<Alignment> {
       match position {
-         ToolbarPosition::Left => (AlignH::Left, AlignV::Middle),
-         ToolbarPosition::Right => (AlignH::Right, AlignV::Middle),
+         ToolbarPosition::Left => Some((AlignH::Left, AlignV::Middle)),
+         ToolbarPosition::Right => Some((AlignH::Right, AlignV::Middle)),
+         ToolbarPosition::Top => Some((AlignH::Center, AlignV::Top)),
+         ToolbarPosition::Bottom => Some((AlignH::Center, AlignV::Bottom)),
+         ToolbarPosition::Floating => None,
       }
    }
 
    /// Finds out the toolbar's snapped position, using the given "precise" position.
+   ///
+   /// The parent view is split into four triangles by its diagonals, and the toolbar snaps to the
+   /// edge of the triangle the position lies in. If the position is too far away from that edge,
+   /// the toolbar is detached instead.
    fn snap_position(parent_view: &View, position: Point) -> Option<ToolbarPosition> {
       let rect = parent_view.rect();
-      let left = Rect {
-         size: vector(rect.width() / 2.0, rect.height()),
-         ..rect
-      };
-      let right = Rect {
-         position: left.position + vector(left.size.x, 0.0),
-         ..left
-      };
-      if left.contains(position) {
-         Some(ToolbarPosition::Left)
-      } else if right.contains(position) {
-         Some(ToolbarPosition::Right)
+      if !rect.contains(position) {
+         return None;
+      }
+      // Normalizing the position makes the diagonals lie at x = y and x = 1 - y.
+      let x = (position.x - rect.left()) / rect.width();
+      let y = (position.y - rect.top()) / rect.height();
+      let (edge, distance) = [
+         (x, ToolbarPosition::Left, position.x - rect.left()),
+         (1.0 - x, ToolbarPosition::Right, rect.right() - position.x),
+         (y, ToolbarPosition::Top, position.y - rect.top()),
+         (1.0 - y, ToolbarPosition::Bottom, rect.bottom() - position.y),
+      ]
+      .into_iter()
+      .min_by(|(a, ..), (b, ..)| a.total_cmp(b))
+      .map(|(_, edge, distance)| (edge, distance))?;
+      if distance > Self::DETACH_DISTANCE {
+         Some(ToolbarPosition::Floating)
       } else {
-         None
+         Some(edge)
       }
    }
 
    fn position_view(parent_view: &View, view: &mut View, position: ToolbarPosition) {
-      view::layout::align(parent_view, view, Self::view_alignment(position));
+      match Self::view_alignment(position) {
+         Some(alignment) => view::layout::align(parent_view, view, alignment),
+         None => {
+            view::layout::align(parent_view, view, (AlignH::Left, AlignV::Top));
+            // Keep the floating toolbar within the parent view, such that it doesn't get lost
+            // after the window is resized.
+            let (x, y) = config().ui.floating_toolbar_position;
+            let x = x.min(parent_view.width() - view.width()).max(0.0);
+            let y = y.min(parent_view.height() - view.height()).max(0.0);
+            view.position += point(x, y);
+         }
+      }
    }
 
    /// Processes the toolbar.
@@ -181,43 +233,52 @@ impl Toolbar {
       let position = Self::position();
 
       // Update the view's size and lay it out in the parent view.
-      wm.view_mut(&self.window).dimensions = ToolbarWindow::dimensions(self.tool_count());
+      wm.view_mut(&self.window).dimensions = ToolbarWindow::dimensions(self.tool_count(), position);
       if wm.dragging(&self.window) {
          let window_view = wm.view(&self.window).clone();
          let new_position =
-            Self::snap_position(parent_view, window_view.rect().top_center()).unwrap_or(position);
-
-         // Draw a preview for where the new position is going to be while dragging.
-         let mut preview = View::new(window_view.dimensions);
-         Self::position_view(parent_view, &mut preview, new_position);
-         let rect = preview.rect();
-         ui.render().outline(rect, colors.position_highlight, rect.width() / 2.0, 1.0);
-         ui.render().fill(
-            rect,
-            colors.position_highlight.with_alpha(127),
-            rect.width() / 2.0,
-         );
-
-         // Draw a guide line for where the boundary between the left and right side is.
+            Self::snap_position(parent_view, window_view.rect().center()).unwrap_or(position);
+
+         // Draw a preview for where the new position is going to be while dragging. A floating
+         // toolbar stays where it's dropped, so the window itself serves as the preview.
+         if new_position != ToolbarPosition::Floating {
+            let mut preview = View::new(ToolbarWindow::dimensions(self.tool_count(), new_position));
+            Self::position_view(parent_view, &mut preview, new_position);
+            let rect = preview.rect();
+            let corner_radius = rect.width().min(rect.height()) / 2.0;
+            ui.render().outline(rect, colors.position_highlight, corner_radius, 1.0);
+            ui.render().fill(
+               rect,
+               colors.position_highlight.with_alpha(127),
+               corner_radius,
+            );
+         }
+
+         // Draw guide lines for where the boundaries between the edges are, and for the area
+         // in which the toolbar is detached.
          let parent_rect = parent_view.rect();
-         let center_x = parent_rect.center_x();
-         let top = parent_rect.top();
-         let bottom = parent_rect.bottom();
-         ui.render().line(
-            point(center_x, top),
-            point(center_x, bottom),
-            colors.position_highlight,
-            LineCap::Butt,
-            1.0,
-         );
+         for (start, end) in [
+            (parent_rect.top_left(), parent_rect.bottom_right()),
+            (parent_rect.top_right(), parent_rect.bottom_left()),
+         ] {
+            ui.render().line(start, end, colors.position_highlight, LineCap::Butt, 1.0);
+         }
+         let detach_area = view::layout::padded(parent_view, Self::DETACH_DISTANCE).rect();
+         ui.render().outline(detach_area, colors.position_highlight, 0.0, 1.0);
 
          // Snap to the correct position if the mouse was released.
-         if input.action(MouseButton::Left) == (true, ButtonState::Released)
-            && new_position != position
-         {
-            config::write(|config| {
-               config.ui.toolbar_position = new_position;
-            })
+         if input.action(MouseButton::Left) == (true, ButtonState::Released) {
+            if new_position == ToolbarPosition::Floating {
+               let offset = window_view.position - parent_view.position;
+               config::write(|config| {
+                  config.ui.toolbar_position = new_position;
+                  config.ui.floating_toolbar_position = (offset.x, offset.y);
+               })
+            } else if new_position != position {
+               config::write(|config| {
+                  config.ui.toolbar_position = new_position;
+               })
+            }
          }
       } else {
          Self::position_view(parent_view, wm.view_mut(&self.window), position);
@@ -274,10 +335,14 @@ impl ToolbarWindow {
       Self {}
    }
 
-   fn dimensions(n_tools: usize) -> Dimensions {
+   fn dimensions(n_tools: usize, position: ToolbarPosition) -> Dimensions {
       let padding = 4.0;
       let length = padding + Self::DRAG_HANDLE_SIZE + n_tools as f32 * (Self::TOOL_SIZE + padding);
-      Dimensions::from((Self::TOOLBAR_SIZE, length))
+      if position.is_horizontal() {
+         Dimensions::from((length, Self::TOOLBAR_SIZE))
+      } else {
+         Dimensions::from((Self::TOOLBAR_SIZE, length))
+      }
    }
 }
 
@@ -295,18 +360,34 @@ impl WindowContent for ToolbarWindow {
       }: &mut WindowContentArgs,
       data: &mut Self::Data,
    ) {
-      ui.push(ui.size(), Layout::Vertical);
+      let position = config().ui.toolbar_position;
+      let horizontal = position.is_horizontal();
+      ui.push(
+         ui.size(),
+         if horizontal {
+            Layout::Horizontal
+         } else {
+            Layout::Vertical
+         },
+      );
 
       ui.fill_rounded(assets.colors.panel, ui.width().min(ui.height()) / 2.0);
       ui.pad(4.0);
 
       // The dragging handle.
-      ui.push((ui.width(), Self::DRAG_HANDLE_SIZE), Layout::Freeform);
-      ui.icon(
-         &assets.icons.navigation.drag_horizontal,
-         assets.colors.drag_handle,
-         Some(ui.size()),
-      );
+      let (handle_size, handle_icon) = if horizontal {
+         (
+            (Self::DRAG_HANDLE_SIZE, ui.height()),
+            &assets.icons.navigation.drag_vertical,
+         )
+      } else {
+         (
+            (ui.width(), Self::DRAG_HANDLE_SIZE),
+            &assets.icons.navigation.drag_horizontal,
+         )
+      };
+      ui.push(handle_size, Layout::Freeform);
+      ui.icon(handle_icon, assets.colors.drag_handle, Some(ui.size()));
       if ui.hover(input) {
          **hit_test = HitTest::Draggable;
       }
@@ -314,9 +395,11 @@ impl WindowContent for ToolbarWindow {
 
       // The tools.
 
-      let tooltip_position = match config().ui.toolbar_position {
-         ToolbarPosition::Left => TooltipPosition::Right,
+      let tooltip_position = match position {
+         ToolbarPosition::Left | ToolbarPosition::Floating => TooltipPosition::Right,
          ToolbarPosition::Right => TooltipPosition::Left,
+         ToolbarPosition::Top => TooltipPosition::Bottom,
+         ToolbarPosition::Bottom => TooltipPosition::Top,
       };
 
       let tools = data.tools.borrow_mut();
@@ -1,9 +1,44 @@
 //! The Brush tool. Allows for painting, as well as erasing pixels from the canvas.
+//!
+//! Dragging with the left mouse button paints with the primary color. Dragging with the right
+//! mouse button erases, regardless of the color picked. A right click that doesn't move the mouse
+//! past [`BrushTool::RIGHT_DRAG_THRESHOLD`] does not paint anything, so that it can be used for
+//! other things, such as opening a context menu.
+//!
+//! The eraser toggle on the bottom bar makes the left mouse button erase as well, without having
+//! to pick the eraser in the color picker. Erasing strokes are sent to peers with a fully
+//! transparent color, which always replaces the pixels underneath.
+//!
+//! Shift-clicking with the left mouse button draws a straight line from the point where the previous
+//! stroke ended.
+//!
+//! Besides the slider, the thickness can be changed by scrolling with Ctrl held, or with the keys
+//! bound to `brush.decrease_thickness` and `brush.increase_thickness` (`[` and `]` by default).
+//! The new thickness is shown as a tip.
+//!
+//! When drawing with a pen that reports pressure, the thickness of strokes is scaled by the
+//! pressure, mapped through the curve set in the config.
+//!
+//! The stabilizer smooths out jittery input by making strokes trail behind the cursor, with an
+//! exponential moving average of the mouse positions. Strokes still start exactly under the
+//! cursor, so clicks leave dots where they're expected.
+//!
+//! Besides replacing the pixels underneath, strokes can be blended with them using the Multiply or
+//! Screen blend modes, for shading and highlighting respectively. The eraser always replaces.
+//!
+//! Strokes end in round caps by default. Square and flat caps can be picked instead, which is
+//! handy for technical drawing; the guide shows a square outline for these.
+//!
+//! The brush's dynamics (scatter, size jitter, and the tip's shape) turn strokes into a series of
+//! individual dabs. Their randomness is derived from a seed sent along with each stroke, so that
+//! peers paint exactly the same dabs.
+//!
+//! Each stroke is recorded in the paint canvas's undo history as a single step.
 
 use std::collections::HashMap;
-use web_time::Instant;
+use std::f32::consts::{SQRT_2, TAU};
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
 
-use crate::backend::winit::event::MouseButton;
 use crate::config::config;
 use crate::keymap::KeyBinding;
 use crate::Error;
@@ -12,19 +47,23 @@ use netcanv_renderer::paws::{
    point, vector, AlignH, AlignV, Color, Layout, LineCap, Point, Rect, Renderer,
 };
 use netcanv_renderer::{BlendMode, Font, RenderBackend};
+use nysa::global as bus;
 use serde::{Deserialize, Serialize};
 
 use crate::app::paint::{self, GlobalControls};
 use crate::assets::Assets;
 use crate::backend::{Backend, Image};
-use crate::common::{deserialize_bincode, lerp_point, ColorMath};
+use crate::common::{deserialize_bincode, lerp_point, ColorMath, VectorMath};
 use crate::paint_canvas::PaintCanvas;
+use crate::strings::BrushBlendingStrings;
 use crate::ui::{
-   view, ButtonState, ColorPicker, ColorPickerArgs, Modifier, MouseScroll, Slider, SliderArgs,
-   SliderStep, UiElements, UiInput,
+   view, Button, ButtonArgs, ButtonColors, ButtonState, ColorPicker, ColorPickerArgs, ContextMenu,
+   ContextMenuArgs, Input, Modifier, MouseScroll, Slider, SliderArgs, SliderStep, Ui, UiElements,
+   UiInput,
 };
 use crate::viewport::Viewport;
 
+use super::line::Cap;
 use super::{Net, Tool, ToolArgs};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,10 +72,147 @@ enum BrushType {
    Eraser,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How the brush's strokes are blended with what's already on the canvas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum BrushBlending {
+   /// Strokes replace the pixels underneath.
+   #[default]
+   Normal,
+   /// Strokes darken the pixels underneath, which is useful for shading.
+   Multiply,
+   /// Strokes lighten the pixels underneath, which is useful for highlights.
+   Screen,
+}
+
+impl BrushBlending {
+   const ALL: [BrushBlending; 3] = [Self::Normal, Self::Multiply, Self::Screen];
+
+   /// Returns the blend mode a stroke of the given color is painted with.
+   ///
+   /// Fully transparent strokes always replace the pixels underneath, as that's how the eraser
+   /// erases.
+   fn blend_mode(self, color: Color) -> BlendMode {
+      match self {
+         _ if color.a == 0 => BlendMode::Replace,
+         Self::Normal => BlendMode::Replace,
+         Self::Multiply => BlendMode::Multiply,
+         Self::Screen => BlendMode::Screen,
+      }
+   }
+
+   fn name(self, strings: &BrushBlendingStrings) -> &str {
+      match self {
+         Self::Normal => &strings.normal,
+         Self::Multiply => &strings.multiply,
+         Self::Screen => &strings.screen,
+      }
+   }
+}
+
+/// The brush's settings, saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct BrushSettings {
+   brush_thickness: f32,
+   eraser_thickness: f32,
+   dynamics: BrushDynamics,
+   /// The strength of the stabilizer, from 0 (off) to 1.
+   stabilizer: f32,
+   blending: BrushBlending,
+   cap: Cap,
+   /// Whether the eraser toggle is on.
+   eraser: bool,
+}
+
+impl Default for BrushSettings {
+   fn default() -> Self {
+      Self {
+         brush_thickness: BrushTool::DEFAULT_THICKNESS,
+         eraser_thickness: BrushTool::DEFAULT_THICKNESS,
+         dynamics: BrushDynamics::default(),
+         stabilizer: 0.0,
+         blending: BrushBlending::Normal,
+         cap: Cap::Round,
+         eraser: false,
+      }
+   }
+}
+
+/// Parameters that make brush strokes less uniform.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct BrushDynamics {
+   /// How far dabs can be scattered away from the stroke, relative to the thickness.
+   position_jitter: f32,
+   /// How much smaller than the thickness dabs can randomly get, relative to the thickness.
+   size_jitter: f32,
+   /// The angle of the brush tip, in degrees.
+   tip_angle: f32,
+   /// The width of the brush tip relative to its length. 1 is a round tip.
+   tip_roundness: f32,
+}
+
+impl BrushDynamics {
+   /// Returns whether the dynamics don't change the stroke in any way, in which case it can be
+   /// painted as a plain line.
+   fn is_uniform(&self) -> bool {
+      self.position_jitter == 0.0 && self.size_jitter == 0.0 && self.tip_roundness >= 1.0
+   }
+
+   /// Returns whether the dynamics received from a peer are within the allowed ranges.
+   fn is_valid(&self) -> bool {
+      (0.0..=1.0).contains(&self.position_jitter)
+         && (0.0..=1.0).contains(&self.size_jitter)
+         && (0.0..=180.0).contains(&self.tip_angle)
+         && (BrushTool::MIN_TIP_ROUNDNESS..=1.0).contains(&self.tip_roundness)
+   }
+}
+
+impl Default for BrushDynamics {
+   fn default() -> Self {
+      Self {
+         position_jitter: 0.0,
+         size_jitter: 0.0,
+         tip_angle: 0.0,
+         tip_roundness: 1.0,
+      }
+   }
+}
+
+/// A small deterministic random number generator (xorshift32), so that the dabs of a stroke can
+/// be reproduced by peers from its seed.
+struct DabRng(u32);
+
+impl DabRng {
+   fn new(seed: u32) -> Self {
+      // Xorshift gets stuck at zero.
+      Self(seed.max(1))
+   }
+
+   fn next_u32(&mut self) -> u32 {
+      let mut x = self.0;
+      x ^= x << 13;
+      x ^= x >> 17;
+      x ^= x << 5;
+      self.0 = x;
+      x
+   }
+
+   /// Returns a random number in the range `[0, 1)`.
+   fn next_f32(&mut self) -> f32 {
+      (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+   }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum BrushState {
    Idle,
+   /// Drawing with the primary color (left mouse button).
    Drawing,
+   /// The right mouse button was pressed at the given point, but it's not yet known whether this
+   /// is a click or a drag.
+   ErasePending(Point),
+   /// Erasing pixels (right mouse button drag).
    Erasing,
 }
 
@@ -45,12 +221,42 @@ pub struct BrushTool {
 
    state: BrushState,
    tool: BrushType,
+   /// Whether the eraser toggle on the bottom bar is on. While it is, the brush erases no matter
+   /// which color is picked.
+   eraser_toggled: bool,
    brush_thickness_slider: Slider,
    eraser_thickness_slider: Slider,
+   stabilizer_slider: Slider,
+
+   blending: BrushBlending,
+   /// Set when the blending changes, such that peers can be told about it.
+   blending_changed: bool,
+   blending_menu: ContextMenu,
+
+   cap: Cap,
+   /// Set when the cap changes, such that peers can be told about it.
+   cap_changed: bool,
+   cap_menu: ContextMenu,
+
+   dynamics_menu: ContextMenu,
+   position_jitter_slider: Slider,
+   size_jitter_slider: Slider,
+   tip_angle_slider: Slider,
+   tip_roundness_slider: Slider,
+   seed_rng: DabRng,
 
    mouse_position: Point,
    previous_mouse_position: Point,
-   stroke_points: Vec<Stroke>,
+   /// The point where the last stroke ended, in viewport space. Shift-clicking draws a straight
+   /// line from here.
+   last_stroke_end: Option<Point>,
+   /// The point the current stroke has reached, in viewport space. This trails behind the mouse
+   /// position when the stabilizer is on.
+   stroke_position: Option<Point>,
+   /// The strokes painted since they were last sent to peers, in the order they were painted.
+   pending_strokes: Vec<PendingStroke>,
+   /// The thickness and color sent to peers in the last cursor packet.
+   sent_cursor_style: Option<(u8, Color)>,
 
    peers: HashMap<PeerId, PeerBrush>,
 }
@@ -58,6 +264,16 @@ pub struct BrushTool {
 impl BrushTool {
    const MAX_THICKNESS: f32 = 64.0;
    const DEFAULT_THICKNESS: f32 = 4.0;
+   /// The distance (in screen pixels) the mouse has to travel with the right mouse button held
+   /// for the press to be treated as an erasing drag rather than a click.
+   const RIGHT_DRAG_THRESHOLD: f32 = 4.0;
+   /// The distance between dabs, relative to the thickness.
+   const DAB_SPACING: f32 = 0.25;
+   /// The lowest allowed tip roundness. Anything thinner would barely leave a mark.
+   const MIN_TIP_ROUNDNESS: f32 = 0.1;
+   /// How much of the distance to the cursor the stroke lags behind per frame, at the
+   /// stabilizer's full strength. This is below 1, so that the stroke never stops entirely.
+   const MAX_STABILIZATION: f32 = 0.9;
 
    /// Creates an instance of the brush tool.
    pub fn new(renderer: &mut Backend) -> Self {
@@ -65,6 +281,7 @@ impl BrushTool {
          icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/brush.svg")),
          state: BrushState::Idle,
          tool: BrushType::Brush,
+         eraser_toggled: false,
          brush_thickness_slider: Slider::new(
             Self::DEFAULT_THICKNESS,
             1.0,
@@ -77,9 +294,32 @@ impl BrushTool {
             Self::MAX_THICKNESS,
             SliderStep::Discrete(1.0),
          ),
+         stabilizer_slider: Slider::new(0.0, 0.0, 100.0, SliderStep::Discrete(1.0)),
+         blending: BrushBlending::Normal,
+         blending_changed: false,
+         blending_menu: ContextMenu::new((160.0, 104.0)),
+         cap: Cap::Round,
+         cap_changed: false,
+         cap_menu: ContextMenu::new((160.0, 104.0)),
+         dynamics_menu: ContextMenu::new((320.0, 144.0)),
+         position_jitter_slider: Slider::new(0.0, 0.0, 100.0, SliderStep::Discrete(1.0)),
+         size_jitter_slider: Slider::new(0.0, 0.0, 100.0, SliderStep::Discrete(1.0)),
+         tip_angle_slider: Slider::new(0.0, 0.0, 180.0, SliderStep::Discrete(1.0)),
+         tip_roundness_slider: Slider::new(
+            100.0,
+            Self::MIN_TIP_ROUNDNESS * 100.0,
+            100.0,
+            SliderStep::Discrete(1.0),
+         ),
+         seed_rng: DabRng::new(
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos(),
+         ),
          mouse_position: point(0.0, 0.0),
          previous_mouse_position: point(0.0, 0.0),
-         stroke_points: Vec::new(),
+         last_stroke_end: None,
+         stroke_position: None,
+         pending_strokes: Vec::new(),
+         sent_cursor_style: None,
          peers: HashMap::new(),
       }
    }
@@ -106,6 +346,48 @@ impl BrushTool {
       }
    }
 
+   /// Returns the thickness of the stroke being painted, which is scaled by the pen's pressure if
+   /// there is one.
+   fn stroke_thickness(&self, input: &Input) -> f32 {
+      match input.pen_pressure() {
+         Some(pressure) => {
+            let pressure = config().brush.pressure_curve.apply(pressure);
+            (self.thickness() * pressure).round().max(1.0)
+         }
+         None => self.thickness(),
+      }
+   }
+
+   /// Returns the strength of the stabilizer, from 0 (off) to 1.
+   fn stabilizer(&self) -> f32 {
+      self.stabilizer_slider.value() / 100.0
+   }
+
+   /// Returns the dynamics set with the sliders.
+   fn brush_dynamics(&self) -> BrushDynamics {
+      BrushDynamics {
+         position_jitter: self.position_jitter_slider.value() / 100.0,
+         size_jitter: self.size_jitter_slider.value() / 100.0,
+         tip_angle: self.tip_angle_slider.value(),
+         tip_roundness: self.tip_roundness_slider.value() / 100.0,
+      }
+   }
+
+   /// Returns the dynamics of the current tool. The eraser always erases uniformly.
+   fn dynamics(&self) -> BrushDynamics {
+      match self.tool {
+         BrushType::Brush => self.brush_dynamics(),
+         BrushType::Eraser => BrushDynamics::default(),
+      }
+   }
+
+   fn set_dynamics(&mut self, dynamics: BrushDynamics) {
+      self.position_jitter_slider.set_value(dynamics.position_jitter * 100.0);
+      self.size_jitter_slider.set_value(dynamics.size_jitter * 100.0);
+      self.tip_angle_slider.set_value(dynamics.tip_angle);
+      self.tip_roundness_slider.set_value(dynamics.tip_roundness * 100.0);
+   }
+
    /// Returns the coverage rectangle for the provided point.
    fn point_coverage(p: Point, thickness: f32) -> Rect {
       let half_thickness = thickness / 2.0;
@@ -126,6 +408,16 @@ impl BrushTool {
       Rect::new(point(left, top), vector(right - left, bottom - top))
    }
 
+   /// Returns the coverage rectangle for a uniform stroke between the two points.
+   fn stroke_coverage(a: Point, b: Point, thickness: f32, cap: Cap) -> Rect {
+      match cap {
+         // Square caps reach out further than half the thickness along the diagonal.
+         Cap::Square => Self::coverage(a, b, thickness * SQRT_2),
+         Cap::Round | Cap::Butt => Self::coverage(a, b, thickness),
+      }
+   }
+
+   #[allow(clippy::too_many_arguments)]
    fn stroke(
       &self,
       renderer: &mut Backend,
@@ -134,29 +426,334 @@ impl BrushTool {
       b: Point,
       color: Color,
       thickness: f32,
+      blending: BrushBlending,
+      cap: Cap,
    ) {
-      let coverage = Self::coverage(a, b, thickness);
+      let coverage = Self::stroke_coverage(a, b, thickness, cap);
       renderer.push();
-      renderer.set_blend_mode(BlendMode::Replace);
+      renderer.set_blend_mode(blending.blend_mode(color));
       paint_canvas.draw(renderer, coverage, |renderer| {
-         renderer.line(a, b, color, LineCap::Round, thickness);
+         renderer.line(a, b, color, cap.line_cap(), thickness);
       });
       renderer.pop();
    }
 
+   /// Paints a stroke as a series of dabs, with the given dynamics applied to each one.
+   ///
+   /// The dabs are derived from `seed` alone, so painting the same stroke with the same seed always
+   /// gives the same result.
+   #[allow(clippy::too_many_arguments)]
+   fn dab_stroke(
+      &self,
+      renderer: &mut Backend,
+      paint_canvas: &mut PaintCanvas,
+      a: Point,
+      b: Point,
+      color: Color,
+      thickness: f32,
+      blending: BrushBlending,
+      dynamics: &BrushDynamics,
+      seed: u32,
+   ) {
+      let coverage = Self::coverage(a, b, thickness * (1.0 + 2.0 * dynamics.position_jitter));
+      let spacing = (thickness * Self::DAB_SPACING).max(1.0);
+      let distance = (b.x - a.x).hypot(b.y - a.y);
+      let dab_count = (distance / spacing) as usize + 1;
+      let (tip_sin, tip_cos) = dynamics.tip_angle.to_radians().sin_cos();
+      let mut rng = DabRng::new(seed);
+
+      renderer.push();
+      renderer.set_blend_mode(blending.blend_mode(color));
+      paint_canvas.draw(renderer, coverage, |renderer| {
+         for i in 0..dab_count {
+            let t = if distance > 0.0 {
+               i as f32 * spacing / distance
+            } else {
+               0.0
+            };
+            // The same amount of random numbers is drawn for every dab, no matter the dynamics.
+            let size = thickness * (1.0 - dynamics.size_jitter * rng.next_f32());
+            let (scatter_sin, scatter_cos) = (rng.next_f32() * TAU).sin_cos();
+            let scatter = rng.next_f32() * dynamics.position_jitter * thickness;
+            let center = lerp_point(a, b, t) + vector(scatter_cos, scatter_sin) * scatter;
+
+            let width = size * dynamics.tip_roundness;
+            let half_length = (size - width) / 2.0;
+            let tip = vector(tip_cos, tip_sin) * half_length;
+            renderer.line(center - tip, center + tip, color, LineCap::Round, width);
+         }
+      });
+      renderer.pop();
+   }
+
+   /// Draws the outline of a guide with the given radius, centered on `position`. The outline is
+   /// a circle for round caps, and a square otherwise.
+   fn outline_guide(
+      renderer: &mut Backend,
+      position: Point,
+      radius: f32,
+      cap: Cap,
+      color: Color,
+      thickness: f32,
+   ) {
+      let half_size = vector(radius, radius);
+      match cap {
+         Cap::Round => renderer.outline_circle(position, radius, color, thickness),
+         Cap::Square | Cap::Butt => {
+            let rect = Rect::new(position - half_size, half_size * 2.0);
+            renderer.outline(rect, color, 0.0, thickness);
+         }
+      }
+   }
+
+   fn set_blending(&mut self, blending: BrushBlending) {
+      self.blending_changed |= self.blending != blending;
+      self.blending = blending;
+   }
+
+   /// Processes the dropdown for picking how strokes are blended with the canvas.
+   fn process_blending(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      canvas_view: &view::View,
+   ) {
+      let strings = &assets.tr.brush_blending;
+      let button = Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+         &assets.sans,
+         self.blending.name(strings),
+      );
+      let button_rect = button.group();
+      if button.clicked() {
+         self.blending_menu.toggle();
+      }
+
+      view::layout::align(
+         &view::layout::padded(canvas_view, 8.0),
+         &mut self.blending_menu.view,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      self.blending_menu.view.position.x = button_rect.left();
+      if self
+         .blending_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(8.0);
+         for (i, blending) in BrushBlending::ALL.into_iter().enumerate() {
+            if i > 0 {
+               ui.space(4.0);
+            }
+            ui.push((ui.width(), 24.0), Layout::Horizontal);
+            let colors = ButtonColors::toggle(
+               self.blending == blending,
+               &assets.colors.radio_button.normal,
+               &assets.colors.radio_button.selected,
+            );
+            if Button::with_text(
+               ui,
+               input,
+               &ButtonArgs::new(ui, colors).height(24.0).pill(),
+               &assets.sans,
+               blending.name(strings),
+            )
+            .clicked()
+            {
+               self.set_blending(blending);
+               self.blending_menu.close();
+            }
+            ui.pop();
+         }
+         self.blending_menu.end(ui);
+      }
+   }
+
+   fn set_cap(&mut self, cap: Cap) {
+      self.cap_changed |= self.cap != cap;
+      self.cap = cap;
+   }
+
+   /// Processes the dropdown for picking the shape of the strokes' ends.
+   fn process_cap(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      canvas_view: &view::View,
+   ) {
+      let strings = &assets.tr.line_cap;
+      let button = Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+         &assets.sans,
+         self.cap.name(strings),
+      );
+      let button_rect = button.group();
+      if button.clicked() {
+         self.cap_menu.toggle();
+      }
+
+      view::layout::align(
+         &view::layout::padded(canvas_view, 8.0),
+         &mut self.cap_menu.view,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      self.cap_menu.view.position.x = button_rect.left();
+      if self
+         .cap_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(8.0);
+         for (i, cap) in Cap::ALL.into_iter().enumerate() {
+            if i > 0 {
+               ui.space(4.0);
+            }
+            ui.push((ui.width(), 24.0), Layout::Horizontal);
+            let colors = ButtonColors::toggle(
+               self.cap == cap,
+               &assets.colors.radio_button.normal,
+               &assets.colors.radio_button.selected,
+            );
+            if Button::with_text(
+               ui,
+               input,
+               &ButtonArgs::new(ui, colors).height(24.0).pill(),
+               &assets.sans,
+               cap.name(strings),
+            )
+            .clicked()
+            {
+               self.set_cap(cap);
+               self.cap_menu.close();
+            }
+            ui.pop();
+         }
+         self.cap_menu.end(ui);
+      }
+   }
+
+   /// Processes the button that opens the dynamics menu, as well as the menu itself.
+   fn process_dynamics(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      canvas_view: &view::View,
+   ) {
+      let strings = &assets.tr.brush_dynamics;
+      let button = Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+         &assets.sans,
+         &strings.title,
+      );
+      // The button is processed before the menu, such that clicking it while the menu is open
+      // closes the menu rather than reopening it.
+      let button_rect = button.group();
+      if button.clicked() {
+         self.dynamics_menu.toggle();
+      }
+
+      view::layout::align(
+         &view::layout::padded(canvas_view, 8.0),
+         &mut self.dynamics_menu.view,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      self.dynamics_menu.view.position.x = button_rect.left();
+      if self
+         .dynamics_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(12.0);
+         let sliders = [
+            (
+               &strings.position_jitter,
+               &mut self.position_jitter_slider,
+               "%",
+            ),
+            (&strings.size_jitter, &mut self.size_jitter_slider, "%"),
+            (&strings.tip_angle, &mut self.tip_angle_slider, "°"),
+            (&strings.tip_roundness, &mut self.tip_roundness_slider, "%"),
+         ];
+         for (i, (label, slider, unit)) in sliders.into_iter().enumerate() {
+            if i > 0 {
+               ui.space(8.0);
+            }
+            ui.push((ui.width(), 24.0), Layout::Horizontal);
+            ui.horizontal_label(
+               &assets.sans,
+               label,
+               assets.colors.text,
+               Some((112.0, AlignH::Left)),
+            );
+            slider.process(
+               ui,
+               input,
+               SliderArgs {
+                  width: ui.remaining_width() - 48.0,
+                  color: assets.colors.slider,
+                  focus_outline: assets.colors.focus_outline,
+               },
+            );
+            ui.horizontal_label(
+               &assets.sans_bold,
+               &format!("{}{}", slider.value(), unit),
+               assets.colors.text,
+               Some((48.0, AlignH::Right)),
+            );
+            ui.pop();
+         }
+         self.dynamics_menu.end(ui);
+      }
+   }
+
    fn ensure_peer(&mut self, peer_id: PeerId) -> &mut PeerBrush {
       self.peers.entry(peer_id).or_insert(PeerBrush {
          mouse_position: point(0.0, 0.0),
          previous_mouse_position: point(0.0, 0.0),
          last_cursor_packet: Instant::now(),
+         cursor_received: false,
          thickness: 4.0,
          color: Color::BLACK,
+         blending: BrushBlending::Normal,
+         cap: Cap::Round,
       })
    }
 
-   /// Returns the color currently selected in the color picker.
-   fn color(global_controls: &GlobalControls) -> Color {
-      global_controls.color_picker.color()
+   /// Returns the color strokes are painted with: the color selected in the color picker, or
+   /// transparency when erasing.
+   fn color(&self, global_controls: &GlobalControls) -> Color {
+      if self.eraser_toggled {
+         Color::TRANSPARENT
+      } else {
+         global_controls.color_picker.color()
+      }
    }
 }
 
@@ -173,7 +770,82 @@ impl Tool for BrushTool {
       config().keymap.tools.brush
    }
 
+   fn copy_peer_settings(&mut self, peer_id: PeerId, global_controls: &mut GlobalControls) -> bool {
+      if let Some(peer) = self.peers.get(&peer_id) {
+         let (color, thickness, blending, cap) =
+            (peer.color, peer.thickness, peer.blending, peer.cap);
+         // The peer's eraser is mirrored by the toggle, leaving the color picker as it is.
+         self.eraser_toggled = color.a == 0;
+         if !self.eraser_toggled {
+            global_controls.color_picker.set_color(color);
+         }
+         self.tool = match self.eraser_toggled || global_controls.color_picker.eraser {
+            true => BrushType::Eraser,
+            false => BrushType::Brush,
+         };
+         self.set_thickness(thickness);
+         self.set_blending(blending);
+         self.set_cap(cap);
+         true
+      } else {
+         false
+      }
+   }
+
+   fn peer_position(&self, peer_id: PeerId) -> Option<Point> {
+      self
+         .peers
+         .get(&peer_id)
+         .filter(|peer| peer.cursor_received)
+         .map(|peer| peer.lerp_mouse_position())
+   }
+
+   /// Lets peers know about the blending and cap when the tool is selected, as peers that joined
+   /// while another tool was active haven't been told about them.
+   fn activate(&mut self) {
+      self.blending_changed |= self.blending != BrushBlending::Normal;
+      self.cap_changed |= self.cap != Cap::Round;
+   }
+
+   fn save_settings(&self) -> Option<toml::Value> {
+      let settings = BrushSettings {
+         brush_thickness: self.brush_thickness_slider.value(),
+         eraser_thickness: self.eraser_thickness_slider.value(),
+         dynamics: self.brush_dynamics(),
+         stabilizer: self.stabilizer(),
+         blending: self.blending,
+         cap: self.cap,
+         eraser: self.eraser_toggled,
+      };
+      toml::Value::try_from(settings).ok()
+   }
+
+   fn load_settings(&mut self, settings: toml::Value) {
+      match settings.try_into::<BrushSettings>() {
+         Ok(settings) => {
+            self.brush_thickness_slider.set_value(settings.brush_thickness);
+            self.eraser_thickness_slider.set_value(settings.eraser_thickness);
+            self.set_dynamics(settings.dynamics);
+            self.stabilizer_slider.set_value(settings.stabilizer * 100.0);
+            self.set_blending(settings.blending);
+            self.set_cap(settings.cap);
+            self.eraser_toggled = settings.eraser;
+         }
+         Err(error) => tracing::warn!("ignoring invalid brush settings: {}", error),
+      }
+   }
+
+   /// Keeps redrawing while any of the peers' cursors is still moving, and while a stabilized
+   /// stroke is still catching up with the cursor.
+   fn needs_redraw(&self) -> bool {
+      let stroke_is_catching_up = self
+         .stroke_position
+         .is_some_and(|position| !position.is_in_circle(self.mouse_position, 0.5));
+      stroke_is_catching_up || self.peers.values().any(|peer| peer.is_moving())
+   }
+
    /// Handles input and drawing to the paint canvas with the brush.
+
    fn process_paint_canvas_input(
       &mut self,
       ToolArgs {
@@ -183,14 +855,13 @@ impl Tool for BrushTool {
          ..
       }: ToolArgs,
       paint_canvas: &mut PaintCanvas,
-      viewport: &Viewport,
+      viewport: &mut Viewport,
    ) {
-      // Take color picker's eraser status into consideration only when user isn't drawing.
-      // If user is pressing mouse right button, then the tool is set to Eraser,
-      // but color picker's eraser status might be false, and overwrite tool
-      // to Brush.
+      // Take the eraser toggle and color picker's eraser status into consideration only when
+      // the user isn't drawing. This way the thickness doesn't change mid-stroke, if the primary
+      // color is changed while drawing.
       if self.state == BrushState::Idle {
-         match global_controls.color_picker.eraser {
+         match self.eraser_toggled || global_controls.color_picker.eraser {
             true => self.tool = BrushType::Eraser,
             false => self.tool = BrushType::Brush,
          }
@@ -198,11 +869,40 @@ impl Tool for BrushTool {
 
       // Read input.
 
-      match input.action([MouseButton::Left, MouseButton::Right]) {
-         (true, [ButtonState::Pressed, _]) => self.state = BrushState::Drawing,
-         (true, [_, ButtonState::Pressed]) => self.state = BrushState::Erasing,
+      let mouse = config().keymap.mouse.clone();
+      // Shift-clicking starts the stroke with a straight line from where the last stroke ended.
+      let straight_line_start = match input.action((Modifier::SHIFT, mouse.draw)) {
+         (true, ButtonState::Pressed) => self.last_stroke_end,
+         _ => None,
+      };
+
+      match input.action([mouse.draw, mouse.alternate]) {
+         (no_modifiers, [ButtonState::Pressed, _])
+            if no_modifiers || straight_line_start.is_some() =>
+         {
+            // Strokes can only be started within the room's drawing bounds; strokes that leave
+            // the bounds are clipped by the paint canvas.
+            let position = viewport.to_viewport_space(ui.mouse_position(input), ui.size());
+            if paint_canvas.point_is_in_bounds(position) {
+               self.state = BrushState::Drawing;
+               paint_canvas.begin_history_step();
+               self.stroke_position = Some(position);
+            }
+         }
+         (true, [_, ButtonState::Pressed]) => {
+            self.state = BrushState::ErasePending(ui.mouse_position(input))
+         }
          (_, [ButtonState::Released, _]) | (_, [_, ButtonState::Released]) => {
-            self.state = BrushState::Idle
+            if let Some(stroke_end) = self.stroke_position.take() {
+               self.last_stroke_end = Some(stroke_end);
+            }
+            if matches!(self.state, BrushState::Drawing | BrushState::Erasing) {
+               paint_canvas.finish_history_step(ui);
+            }
+            if self.state == BrushState::Drawing && matches!(self.tool, BrushType::Brush) {
+               global_controls.color_picker.remember_color();
+            }
+            self.state = BrushState::Idle;
          }
          _ => (),
       }
@@ -222,44 +922,120 @@ impl Tool for BrushTool {
          thickness_change += 2.0;
       }
 
-      self.set_thickness(self.thickness() + thickness_change);
+      if thickness_change != 0.0 {
+         self.set_thickness(self.thickness() + thickness_change);
+         bus::push(paint::ShowTip(format!("{} px", self.thickness())));
+      }
 
       // Draw to the paint canvas.
-      let a = ui.previous_mouse_position(input);
+      let mut a = ui.previous_mouse_position(input);
       let b = ui.mouse_position(input);
+      // Only start erasing once the mouse has been dragged far enough; the stroke starts where
+      // the button was pressed so that no pixels are skipped.
+      if let BrushState::ErasePending(origin) = self.state {
+         if !b.is_in_circle(origin, Self::RIGHT_DRAG_THRESHOLD) {
+            self.state = BrushState::Erasing;
+            paint_canvas.begin_history_step();
+            a = origin;
+         }
+      }
       let (a, b) = (
-         viewport.to_viewport_space(a, ui.size()),
+         straight_line_start.unwrap_or_else(|| viewport.to_viewport_space(a, ui.size())),
          viewport.to_viewport_space(b, ui.size()),
       );
-      if self.state != BrushState::Idle {
-         let color = Self::color(global_controls);
-         self.stroke(
-            ui,
-            paint_canvas,
-            a,
-            b,
-            match self.state {
-               BrushState::Drawing => color,
-               BrushState::Erasing => Color::TRANSPARENT,
-               _ => unreachable!(),
-            },
-            self.thickness(),
-         );
-         self.stroke_points.push(Stroke {
-            color: match self.state {
-               BrushState::Drawing => (color.r, color.g, color.b, color.a),
-               BrushState::Erasing => (0, 0, 0, 0),
-               _ => unreachable!(),
-            },
-            thickness: self.thickness() as u8,
+      let color = match self.state {
+         BrushState::Drawing => Some(self.color(global_controls)),
+         BrushState::Erasing => Some(Color::TRANSPARENT),
+         BrushState::Idle | BrushState::ErasePending(_) => None,
+      };
+      if let Some(color) = color {
+         // With the stabilizer on, the stroke only moves part of the way towards the cursor.
+         // The cursor itself still follows the mouse exactly.
+         let stabilizer = self.stabilizer();
+         let (a, b) = match self.stroke_position {
+            Some(previous) if stabilizer > 0.0 && straight_line_start.is_none() => (
+               previous,
+               lerp_point(previous, b, 1.0 - stabilizer * Self::MAX_STABILIZATION),
+            ),
+            _ => (a, b),
+         };
+         self.stroke_position = Some(b);
+
+         let thickness = self.stroke_thickness(input);
+         let stroke = Stroke {
+            color: (color.r, color.g, color.b, color.a),
+            thickness: thickness as u8,
             a: (a.x, a.y),
             b: (b.x, b.y),
-         });
+         };
+         let dynamics = self.dynamics();
+         let coverage = match dynamics.is_uniform() {
+            true => Self::stroke_coverage(a, b, thickness, self.cap),
+            false => Self::coverage(a, b, thickness * (1.0 + 2.0 * dynamics.position_jitter)),
+         };
+         paint_canvas.record_history(ui, coverage);
+         if dynamics.is_uniform() {
+            let (blending, cap) = (self.blending, self.cap);
+            self.stroke(ui, paint_canvas, a, b, color, thickness, blending, cap);
+            self.pending_strokes.push(PendingStroke::Uniform(stroke));
+         } else {
+            let seed = self.seed_rng.next_u32();
+            let blending = self.blending;
+            self.dab_stroke(
+               ui,
+               paint_canvas,
+               a,
+               b,
+               color,
+               thickness,
+               blending,
+               &dynamics,
+               seed,
+            );
+            self.pending_strokes.push(PendingStroke::Dab(DabStroke {
+               stroke,
+               dynamics,
+               seed,
+            }));
+         }
       }
       self.previous_mouse_position = self.mouse_position;
       self.mouse_position = b;
    }
 
+   /// Sends the strokes painted since the last call to peers. Strokes with and without dynamics
+   /// go in separate packets, which are sent in the order the strokes were painted in, such that
+   /// overlapping strokes end up stacked the same way for everyone.
+   fn send_pending_strokes(&mut self, net: &Net) -> netcanv::Result<()> {
+      let mut strokes = Vec::new();
+      let mut dab_strokes = Vec::new();
+      for stroke in std::mem::take(&mut self.pending_strokes) {
+         match stroke {
+            PendingStroke::Uniform(stroke) => {
+               if !dab_strokes.is_empty() {
+                  let packet = Packet::DabStroke(std::mem::take(&mut dab_strokes));
+                  net.send(self, PeerId::BROADCAST, packet)?;
+               }
+               strokes.push(stroke);
+            }
+            PendingStroke::Dab(stroke) => {
+               if !strokes.is_empty() {
+                  let packet = Packet::Stroke(std::mem::take(&mut strokes));
+                  net.send(self, PeerId::BROADCAST, packet)?;
+               }
+               dab_strokes.push(stroke);
+            }
+         }
+      }
+      if !strokes.is_empty() {
+         net.send(self, PeerId::BROADCAST, Packet::Stroke(strokes))?;
+      }
+      if !dab_strokes.is_empty() {
+         net.send(self, PeerId::BROADCAST, Packet::DabStroke(dab_strokes))?;
+      }
+      Ok(())
+   }
+
    /// Draws the guide circle of the brush.
    fn process_paint_canvas_overlays(
       &mut self,
@@ -275,9 +1051,16 @@ impl Tool for BrushTool {
          // (well, most) backgrounds.
          // This doesn't work on 50% gray but this is the best we can do.
          renderer.set_blend_mode(BlendMode::Invert);
-         renderer.outline_circle(
+         // Dabs aren't affected by the cap, so their guide stays round.
+         let cap = match self.dynamics().is_uniform() {
+            true => self.cap,
+            false => Cap::Round,
+         };
+         Self::outline_guide(
+            renderer,
             position,
-            self.thickness() / 2.0 * viewport.zoom(),
+            viewport.to_screen_length(self.thickness() / 2.0),
+            cap,
             Color::WHITE.with_alpha(240),
             1.0,
          );
@@ -294,22 +1077,16 @@ impl Tool for BrushTool {
       viewport: &Viewport,
       peer_id: PeerId,
    ) {
-      if let Some(peer) = self.peers.get(&peer_id) {
+      // Peers that haven't sent their cursor yet would otherwise show up at the origin.
+      if let Some(peer) = self.peers.get(&peer_id).filter(|peer| peer.cursor_received) {
          let position = viewport.to_screen_space(peer.lerp_mouse_position(), ui.size());
-         let radius = peer.thickness / 2.0 * viewport.zoom();
+         let radius = viewport.to_screen_length(peer.thickness / 2.0);
+         let peer_color = net.peer_color(peer_id).unwrap();
          let renderer = ui.render();
          // Render their guide circle.
-         renderer.push();
-         renderer.set_blend_mode(BlendMode::Invert);
-         renderer.outline_circle(position, radius, Color::WHITE.with_alpha(240), 1.0);
-         renderer.pop();
+         Self::outline_guide(renderer, position, radius, peer.cap, peer_color, 1.5);
          // Render their nickname.
          let nickname = net.peer_name(peer_id).unwrap();
-         let text_color = if peer.color.brightness() < 0.5 || peer.color.a == 0 {
-            Color::WHITE
-         } else {
-            Color::BLACK
-         };
          let thickness = vector(radius, radius);
          let text_rect = Rect::new(
             position + thickness,
@@ -317,21 +1094,14 @@ impl Tool for BrushTool {
          );
          let padding = vector(4.0, 4.0);
          let text_rect = Rect::new(text_rect.position, text_rect.size + padding * 2.0);
-         renderer.push();
-         if peer.color.a == 0 {
-            renderer.set_blend_mode(BlendMode::Invert);
-            renderer.outline(text_rect, Color::WHITE, 2.0, 2.0);
-         } else {
-            renderer.fill(text_rect, peer.color, 2.0);
-         }
+         renderer.fill(text_rect, peer_color, 2.0);
          renderer.text(
             text_rect,
             &assets.sans,
             nickname,
-            text_color,
+            Color::WHITE,
             (AlignH::Center, AlignV::Middle),
          );
-         renderer.pop();
       }
    }
 
@@ -367,6 +1137,25 @@ impl Tool for BrushTool {
       );
       ui.space(16.0);
 
+      // Draw the eraser toggle.
+      let eraser_colors = ButtonColors::toggle(
+         self.eraser_toggled,
+         &assets.colors.radio_button.normal,
+         &assets.colors.radio_button.selected,
+      );
+      if Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, eraser_colors).height(24.0).pill(),
+         &assets.sans,
+         &assets.tr.eraser,
+      )
+      .clicked()
+      {
+         self.eraser_toggled = !self.eraser_toggled;
+      }
+      ui.space(16.0);
+
       // Draw the thickness: its slider and value display.
       ui.horizontal_label(
          &assets.sans,
@@ -383,6 +1172,7 @@ impl Tool for BrushTool {
          SliderArgs {
             width: ui.width(),
             color: assets.colors.slider,
+            focus_outline: assets.colors.focus_outline,
          },
       );
 
@@ -413,25 +1203,76 @@ impl Tool for BrushTool {
          assets.colors.text,
          Some((ui.height(), AlignH::Center)),
       );
+
+      // Draw the stabilizer's strength.
+      ui.space(16.0);
+      ui.horizontal_label(
+         &assets.sans,
+         &assets.tr.brush_stabilizer,
+         assets.colors.text,
+         None,
+      );
+      ui.space(16.0);
+      ui.push((96.0, ui.height()), Layout::Freeform);
+      self.stabilizer_slider.process(
+         ui,
+         input,
+         SliderArgs {
+            width: ui.width(),
+            color: assets.colors.slider,
+            focus_outline: assets.colors.focus_outline,
+         },
+      );
+      ui.pop();
+      ui.space(8.0);
+      ui.horizontal_label(
+         &assets.sans_bold,
+         &format!("{}%", self.stabilizer_slider.value()),
+         assets.colors.text,
+         Some((40.0, AlignH::Center)),
+      );
+
+      ui.space(16.0);
+      self.process_cap(ui, input, assets, canvas_view);
+
+      // The eraser always erases uniformly, so there's no blending or dynamics to adjust.
+      if self.tool == BrushType::Brush {
+         ui.space(16.0);
+         self.process_blending(ui, input, assets, canvas_view);
+         ui.space(8.0);
+         self.process_dynamics(ui, input, assets, canvas_view);
+      }
    }
 
    fn network_send(&mut self, net: Net, global_controls: &GlobalControls) -> netcanv::Result<()> {
-      if !self.stroke_points.is_empty() {
-         let packet = Packet::Stroke(self.stroke_points.drain(..).collect());
-         net.send(self, PeerId::BROADCAST, packet)?;
+      // The blending and cap have to arrive before any strokes painted with them.
+      if self.blending_changed {
+         net.send(self, PeerId::BROADCAST, Packet::Blending(self.blending))?;
+         self.blending_changed = false;
       }
-      if self.mouse_position != self.previous_mouse_position {
+      if self.cap_changed {
+         net.send(self, PeerId::BROADCAST, Packet::Cap(self.cap))?;
+         self.cap_changed = false;
+      }
+      self.send_pending_strokes(&net)?;
+      // Peers' guide circles have to follow changes in thickness and color, even while the mouse
+      // stays in place.
+      let style = (self.thickness() as u8, self.color(global_controls));
+      if self.mouse_position != self.previous_mouse_position
+         || self.sent_cursor_style != Some(style)
+      {
          let Point { x, y } = self.mouse_position;
-         let Color { r, g, b, a } = Self::color(global_controls);
+         let (thickness, Color { r, g, b, a }) = style;
          net.send(
             self,
             PeerId::BROADCAST,
             Packet::Cursor {
                position: (x, y),
-               thickness: self.thickness() as u8,
+               thickness,
                color: (r, g, b, a),
             },
          )?;
+         self.sent_cursor_style = Some(style);
       }
       Ok(())
    }
@@ -455,9 +1296,12 @@ impl Tool for BrushTool {
             peer.previous_mouse_position = peer.mouse_position;
             peer.mouse_position = point(x, y);
             peer.last_cursor_packet = Instant::now();
+            peer.cursor_received = true;
             peer.thickness = thickness as f32;
             peer.color = Color::new(r, g, b, a);
          }
+         Packet::Blending(blending) => self.ensure_peer(sender).blending = blending,
+         Packet::Cap(cap) => self.ensure_peer(sender).cap = cap,
          Packet::Stroke(points) => {
             for Stroke {
                color,
@@ -487,7 +1331,56 @@ impl Tool for BrushTool {
                   let (r, g, b, a) = color;
                   Color::new(r, g, b, a)
                };
-               self.stroke(renderer, paint_canvas, a, b, color, thickness);
+               let peer = self.ensure_peer(sender);
+               let (blending, cap) = (peer.blending, peer.cap);
+               self.stroke(
+                  renderer,
+                  paint_canvas,
+                  a,
+                  b,
+                  color,
+                  thickness,
+                  blending,
+                  cap,
+               );
+            }
+         }
+         Packet::DabStroke(points) => {
+            for DabStroke {
+               stroke,
+               dynamics,
+               seed,
+            } in points
+            {
+               let thickness = stroke.thickness as f32;
+               ensure!(
+                  thickness <= Self::MAX_THICKNESS + 0.1 && dynamics.is_valid(),
+                  Error::InvalidToolPacket
+               );
+               let a = {
+                  let (ax, ay) = stroke.a;
+                  point(ax, ay)
+               };
+               let b = {
+                  let (bx, by) = stroke.b;
+                  point(bx, by)
+               };
+               let color = {
+                  let (r, g, b, a) = stroke.color;
+                  Color::new(r, g, b, a)
+               };
+               let blending = self.ensure_peer(sender).blending;
+               self.dab_stroke(
+                  renderer,
+                  paint_canvas,
+                  a,
+                  b,
+                  color,
+                  thickness,
+                  blending,
+                  &dynamics,
+                  seed,
+               );
             }
          }
       }
@@ -503,7 +1396,7 @@ impl Tool for BrushTool {
    ) -> netcanv::Result<()> {
       // Send to newly joined peer where and what color we are.
       let Point { x, y } = self.mouse_position;
-      let Color { r, g, b, a } = Self::color(global_controls);
+      let Color { r, g, b, a } = self.color(global_controls);
       net.send(
          self,
          peer_id,
@@ -513,6 +1406,13 @@ impl Tool for BrushTool {
             color: (r, g, b, a),
          },
       )?;
+      // Peers assume normal blending and round caps until told otherwise.
+      if self.blending != BrushBlending::Normal {
+         net.send(self, peer_id, Packet::Blending(self.blending))?;
+      }
+      if self.cap != Cap::Round {
+         net.send(self, peer_id, Packet::Cap(self.cap))?;
+      }
       Ok(())
    }
 
@@ -530,6 +1430,21 @@ struct Stroke {
    b: (f32, f32),
 }
 
+/// A stroke painted with brush dynamics.
+#[derive(Serialize, Deserialize)]
+struct DabStroke {
+   stroke: Stroke,
+   dynamics: BrushDynamics,
+   /// The seed the stroke's dabs are randomized with.
+   seed: u32,
+}
+
+/// A stroke waiting to be sent to peers.
+enum PendingStroke {
+   Uniform(Stroke),
+   Dab(DabStroke),
+}
+
 /// A brush packet.
 #[derive(Serialize, Deserialize)]
 enum Packet {
@@ -539,20 +1454,106 @@ enum Packet {
       color: (u8, u8, u8, u8),
    },
    Stroke(Vec<Stroke>),
+   DabStroke(Vec<DabStroke>),
+   /// Sets how the sender's strokes are blended with the canvas, until the next such packet.
+   Blending(BrushBlending),
+   /// Sets the shape of the ends of the sender's strokes, until the next such packet. Strokes
+   /// painted with dynamics are made of dabs, and aren't affected by this.
+   Cap(Cap),
 }
 
 struct PeerBrush {
    mouse_position: Point,
    previous_mouse_position: Point,
    last_cursor_packet: Instant,
+   /// Whether a cursor packet has arrived from the peer. Until then, the mouse position is only a
+   /// placeholder.
+   cursor_received: bool,
    thickness: f32,
    color: Color,
+   blending: BrushBlending,
+   cap: Cap,
 }
 
 impl PeerBrush {
    fn lerp_mouse_position(&self) -> Point {
-      let elapsed_ms = self.last_cursor_packet.elapsed().as_millis() as f32;
-      let t = (elapsed_ms / paint::State::TIME_PER_UPDATE.as_millis() as f32).min(1.0);
+      let t = paint::State::peer_interpolation_factor(self.last_cursor_packet);
       lerp_point(self.previous_mouse_position, self.mouse_position, t)
    }
+
+   /// Returns whether the cursor is still being interpolated towards its most recent position.
+   fn is_moving(&self) -> bool {
+      self.previous_mouse_position != self.mouse_position
+         && self.last_cursor_packet.elapsed() < paint::State::TIME_PER_UPDATE
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn erasing_always_replaces_pixels() {
+      for blending in BrushBlending::ALL {
+         assert_eq!(blending.blend_mode(Color::TRANSPARENT), BlendMode::Replace);
+      }
+      assert_eq!(
+         BrushBlending::Multiply.blend_mode(Color::BLACK),
+         BlendMode::Multiply
+      );
+   }
+
+   #[test]
+   fn eraser_toggle_is_saved_and_off_by_default() {
+      // Settings saved before the toggle existed don't mention it.
+      let settings: BrushSettings = toml::from_str("brush_thickness = 8.0").unwrap();
+      assert!(!settings.eraser);
+
+      let saved = toml::Value::try_from(BrushSettings {
+         eraser: true,
+         ..Default::default()
+      })
+      .unwrap();
+      let loaded: BrushSettings = saved.try_into().unwrap();
+      assert!(loaded.eraser);
+   }
+
+   #[test]
+   fn stroke_coverage_includes_square_caps() {
+      let (a, b, thickness) = (point(0.0, 0.0), point(30.0, 10.0), 10.0);
+      let coverage = BrushTool::stroke_coverage(a, b, thickness, Cap::Square);
+
+      // The corners of the square caps stick out by half the thickness, both along the stroke
+      // and across it.
+      let delta = b - a;
+      let along = delta / delta.x.hypot(delta.y) * (thickness / 2.0);
+      let across = vector(-along.y, along.x);
+      for corner in [
+         a - along - across,
+         a - along + across,
+         b + along - across,
+         b + along + across,
+      ] {
+         assert!(
+            coverage.left() <= corner.x
+               && corner.x <= coverage.right()
+               && coverage.top() <= corner.y
+               && corner.y <= coverage.bottom(),
+            "{corner:?} lies outside of {coverage:?}"
+         );
+      }
+
+      for cap in [Cap::Round, Cap::Butt] {
+         assert_eq!(
+            BrushTool::stroke_coverage(a, b, thickness, cap),
+            BrushTool::coverage(a, b, thickness)
+         );
+      }
+   }
+
+   #[test]
+   fn strokes_have_round_caps_by_default() {
+      let settings: BrushSettings = toml::from_str("brush_thickness = 8.0").unwrap();
+      assert_eq!(settings.cap, Cap::Round);
+   }
 }
@@ -0,0 +1,462 @@
+//! The Bucket tool. Fills areas of similar color.
+//!
+//! Clicking with the left mouse button fills the area around the cursor with the primary color.
+//! Pixels are considered part of the area if none of their channels differ from the clicked pixel
+//! by more than the tolerance. The canvas is infinite, so the fill never spreads further than
+//! [`BucketConfig::max_fill_size`] pixels around the clicked point.
+//!
+//! [`BucketConfig::max_fill_size`]: crate::config::BucketConfig::max_fill_size
+
+use image::{Rgba, RgbaImage};
+use netcanv_protocol::relay::PeerId;
+use netcanv_renderer::paws::{point, vector, AlignH, AlignV, Color, Layout, Point, Rect};
+use netcanv_renderer::{BlendMode, RenderBackend};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::assets::Assets;
+use crate::backend::winit::window::CursorIcon;
+use crate::backend::{Backend, Image};
+use crate::common::deserialize_bincode;
+use crate::config::config;
+use crate::keymap::KeyBinding;
+use crate::paint_canvas::PaintCanvas;
+use crate::ui::{
+   view, ButtonState, ColorPicker, ColorPickerArgs, Slider, SliderArgs, SliderStep, UiElements,
+};
+use crate::viewport::Viewport;
+
+use super::{Net, SelectionTool, Tool, ToolArgs};
+
+/// The bucket's settings, saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct BucketSettings {
+   /// The tolerance, in percent.
+   tolerance: f32,
+}
+
+impl Default for BucketSettings {
+   fn default() -> Self {
+      Self { tolerance: 10.0 }
+   }
+}
+
+/// A filled area, placed onto the canvas with its top-left corner at the given position.
+struct Fill {
+   position: (i32, i32),
+   image: RgbaImage,
+}
+
+pub struct BucketTool {
+   icon: Image,
+   tolerance_slider: Slider,
+
+   /// Fills made by the user, encoded and ready to be sent to other peers.
+   encoded_fills_tx: mpsc::UnboundedSender<((i32, i32), Vec<u8>)>,
+   encoded_fills_rx: mpsc::UnboundedReceiver<((i32, i32), Vec<u8>)>,
+   /// Fills received from other peers, decoded and ready to be placed onto the canvas.
+   peer_fills_tx: mpsc::UnboundedSender<Fill>,
+   peer_fills_rx: mpsc::UnboundedReceiver<Fill>,
+   /// The number of fills still being encoded or decoded.
+   ongoing_jobs: usize,
+}
+
+impl BucketTool {
+   /// Creates an instance of the bucket tool.
+   pub fn new(renderer: &mut Backend) -> Self {
+      let (encoded_fills_tx, encoded_fills_rx) = mpsc::unbounded_channel();
+      let (peer_fills_tx, peer_fills_rx) = mpsc::unbounded_channel();
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/bucket.svg")),
+         tolerance_slider: Slider::new(
+            BucketSettings::default().tolerance,
+            0.0,
+            100.0,
+            SliderStep::Discrete(1.0),
+         ),
+         encoded_fills_tx,
+         encoded_fills_rx,
+         peer_fills_tx,
+         peer_fills_rx,
+         ongoing_jobs: 0,
+      }
+   }
+
+   /// Returns the largest difference between the channels of two colors.
+   fn color_distance(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+      a.0.iter().zip(b.0.iter()).map(|(&a, &b)| a.abs_diff(b)).max().unwrap_or(0)
+   }
+
+   /// Performs a scanline flood fill of the area around `start` whose colors are within
+   /// `tolerance` of the starting pixel's color.
+   ///
+   /// Returns the filled pixels as a row-major mask over the whole image, along with the
+   /// left, top, right, and bottom edges of the filled area (inclusive).
+   fn flood_fill(
+      image: &RgbaImage,
+      (start_x, start_y): (u32, u32),
+      tolerance: u8,
+   ) -> (Vec<bool>, (u32, u32, u32, u32)) {
+      let (width, height) = image.dimensions();
+      let target = *image.get_pixel(start_x, start_y);
+      let index = |x: u32, y: u32| (y * width + x) as usize;
+      let mut filled = vec![false; (width * height) as usize];
+      let fillable = |filled: &[bool], x: u32, y: u32| {
+         !filled[index(x, y)] && Self::color_distance(image.get_pixel(x, y), &target) <= tolerance
+      };
+
+      let mut bounds = (start_x, start_y, start_x, start_y);
+      let mut seeds = vec![(start_x, start_y)];
+      while let Some((x, y)) = seeds.pop() {
+         if !fillable(&filled, x, y) {
+            continue;
+         }
+         // Extend the seed into the longest horizontal span of fillable pixels.
+         let mut left = x;
+         while left > 0 && fillable(&filled, left - 1, y) {
+            left -= 1;
+         }
+         let mut right = x;
+         while right + 1 < width && fillable(&filled, right + 1, y) {
+            right += 1;
+         }
+         for span_x in left..=right {
+            filled[index(span_x, y)] = true;
+         }
+         bounds = (
+            bounds.0.min(left),
+            bounds.1.min(y),
+            bounds.2.max(right),
+            bounds.3.max(y),
+         );
+
+         // Seed the spans of fillable pixels directly above and below.
+         let neighbors = [y.checked_sub(1), (y + 1 < height).then_some(y + 1)];
+         for neighbor_y in neighbors.into_iter().flatten() {
+            let mut in_span = false;
+            for span_x in left..=right {
+               let is_fillable = fillable(&filled, span_x, neighbor_y);
+               if is_fillable && !in_span {
+                  seeds.push((span_x, neighbor_y));
+               }
+               in_span = is_fillable;
+            }
+         }
+      }
+
+      (filled, bounds)
+   }
+
+   /// Fills the area around the given point on the canvas with a color. Returns the filled area,
+   /// or `None` if there was nothing to fill.
+   fn fill(
+      &self,
+      renderer: &mut Backend,
+      paint_canvas: &PaintCanvas,
+      at: Point,
+      color: Color,
+   ) -> Option<Fill> {
+      let size = config().bucket.max_fill_size.max(1);
+      let half_size = (size / 2) as i32;
+      let (x, y) = (at.x.floor() as i32, at.y.floor() as i32);
+      let (left, top) = (x - half_size, y - half_size);
+      let region = Rect::new(
+         point(left as f32, top as f32),
+         vector(size as f32, size as f32),
+      );
+      let image = paint_canvas.render_region(renderer, region, 1.0);
+
+      let start = (half_size as u32, half_size as u32);
+      let color = Rgba([color.r, color.g, color.b, color.a]);
+      if *image.get_pixel(start.0, start.1) == color {
+         return None;
+      }
+      let tolerance = (self.tolerance_slider.value() / 100.0 * 255.0).round() as u8;
+      let (filled, (fill_left, fill_top, fill_right, fill_bottom)) =
+         Self::flood_fill(&image, start, tolerance);
+
+      // The pixels around the filled area are kept, such that the whole image can simply
+      // replace what's on the canvas.
+      let mut patch = image::imageops::crop_imm(
+         &image,
+         fill_left,
+         fill_top,
+         fill_right - fill_left + 1,
+         fill_bottom - fill_top + 1,
+      )
+      .to_image();
+      for (patch_x, patch_y, pixel) in patch.enumerate_pixels_mut() {
+         let index = (fill_top + patch_y) * image.width() + fill_left + patch_x;
+         if filled[index as usize] {
+            *pixel = color;
+         }
+      }
+      Some(Fill {
+         position: (left + fill_left as i32, top + fill_top as i32),
+         image: patch,
+      })
+   }
+
+   /// Draws a filled area onto the paint canvas.
+   fn place(renderer: &mut Backend, paint_canvas: &mut PaintCanvas, fill: &Fill) {
+      let (width, height) = fill.image.dimensions();
+      let framebuffer = renderer.create_framebuffer(width, height);
+      renderer.upload_framebuffer(&framebuffer, (0, 0), (width, height), &fill.image);
+      let rect = Rect::new(
+         point(fill.position.0 as f32, fill.position.1 as f32),
+         vector(width as f32, height as f32),
+      );
+      paint_canvas.draw(renderer, rect, |renderer| {
+         renderer.set_blend_mode(BlendMode::Replace);
+         renderer.framebuffer(rect, &framebuffer);
+      });
+   }
+}
+
+impl Tool for BucketTool {
+   fn name(&self) -> &'static str {
+      "bucket"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn key_shortcut(&self) -> KeyBinding {
+      config().keymap.tools.bucket
+   }
+
+   fn save_settings(&self) -> Option<toml::Value> {
+      let settings = BucketSettings {
+         tolerance: self.tolerance_slider.value(),
+      };
+      toml::Value::try_from(settings).ok()
+   }
+
+   fn load_settings(&mut self, settings: toml::Value) {
+      match settings.try_into::<BucketSettings>() {
+         Ok(settings) => self.tolerance_slider.set_value(settings.tolerance),
+         Err(error) => tracing::warn!("ignoring invalid bucket settings: {}", error),
+      }
+   }
+
+   /// Sends out the user's encoded fills, and places the fills received from peers.
+   fn process_background_jobs(
+      &mut self,
+      ToolArgs { ui, net, .. }: ToolArgs,
+      paint_canvas: &mut PaintCanvas,
+   ) {
+      while let Ok((position, data)) = self.encoded_fills_rx.try_recv() {
+         self.ongoing_jobs -= 1;
+         catch!(net.send(self, PeerId::BROADCAST, Packet::Fill(position, data)));
+      }
+      while let Ok(fill) = self.peer_fills_rx.try_recv() {
+         self.ongoing_jobs -= 1;
+         Self::place(ui.render(), paint_canvas, &fill);
+      }
+   }
+
+   /// Keeps redrawing while fills are being encoded or decoded.
+   fn needs_redraw(&self) -> bool {
+      self.ongoing_jobs > 0
+   }
+
+   /// Fills the clicked area.
+   fn process_paint_canvas_input(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         global_controls,
+         ..
+      }: ToolArgs,
+      paint_canvas: &mut PaintCanvas,
+      viewport: &mut Viewport,
+   ) {
+      if !input.mouse_active() {
+         return;
+      }
+      input.set_cursor(CursorIcon::Crosshair);
+
+      if input.action(config().keymap.mouse.draw) != (true, ButtonState::Pressed) {
+         return;
+      }
+      let position = viewport.to_viewport_space(input.mouse_position(), ui.size());
+      if !paint_canvas.point_is_in_bounds(position) {
+         return;
+      }
+      let color = global_controls.color_picker.color();
+      let Some(fill) = self.fill(ui.render(), paint_canvas, position, color) else {
+         return;
+      };
+
+      let (width, height) = fill.image.dimensions();
+      paint_canvas.begin_history_step();
+      paint_canvas.record_history(
+         ui.render(),
+         Rect::new(
+            point(fill.position.0 as f32, fill.position.1 as f32),
+            vector(width as f32, height as f32),
+         ),
+      );
+      Self::place(ui.render(), paint_canvas, &fill);
+      paint_canvas.finish_history_step(ui.render());
+      global_controls.color_picker.remember_color();
+
+      let tx = self.encoded_fills_tx.clone();
+      self.ongoing_jobs += 1;
+      tokio::task::spawn_blocking(move || {
+         let data = catch!(SelectionTool::encode_image(&fill.image));
+         let _ = tx.send((fill.position, data));
+      });
+   }
+
+   /// Shows the color palette and the tolerance slider.
+   fn process_bottom_bar(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         assets,
+         wm,
+         canvas_view,
+         global_controls,
+         ..
+      }: ToolArgs,
+   ) {
+      let mut picker_window = ColorPicker::picker_window_view();
+      view::layout::align(
+         &view::layout::padded(canvas_view, 16.0),
+         &mut picker_window,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      global_controls.color_picker.process(
+         ui,
+         input,
+         ColorPickerArgs {
+            assets,
+            wm,
+            window_view: picker_window,
+            show_eraser: true,
+         },
+      );
+      ui.space(16.0);
+
+      ui.horizontal_label(
+         &assets.sans,
+         &assets.tr.bucket_tolerance,
+         assets.colors.text,
+         None,
+      );
+      ui.space(16.0);
+      ui.push((192.0, ui.height()), Layout::Freeform);
+      self.tolerance_slider.process(
+         ui,
+         input,
+         SliderArgs {
+            width: ui.width(),
+            color: assets.colors.slider,
+            focus_outline: assets.colors.focus_outline,
+         },
+      );
+      ui.pop();
+      ui.space(8.0);
+      ui.horizontal_label(
+         &assets.sans_bold,
+         &format!("{}%", self.tolerance_slider.value()),
+         assets.colors.text,
+         Some((ui.height(), AlignH::Center)),
+      );
+   }
+
+   /// Decodes fills made by other peers.
+   fn network_receive(
+      &mut self,
+      _renderer: &mut Backend,
+      _net: Net,
+      _paint_canvas: &mut PaintCanvas,
+      sender: PeerId,
+      payload: Vec<u8>,
+   ) -> netcanv::Result<()> {
+      let packet = deserialize_bincode(&payload)?;
+      match packet {
+         Packet::Fill(position, data) => {
+            tracing::debug!("{} filled an area ({} bytes of data)", sender, data.len());
+            let tx = self.peer_fills_tx.clone();
+            self.ongoing_jobs += 1;
+            tokio::task::spawn_blocking(move || match SelectionTool::decode_image(&data) {
+               Ok(image) => {
+                  let _ = tx.send(Fill { position, image });
+               }
+               Err(error) => tracing::error!("could not decode filled area: {:?}", error),
+            });
+         }
+      }
+      Ok(())
+   }
+}
+
+/// A network packet for the bucket tool.
+#[derive(Serialize, Deserialize)]
+enum Packet {
+   /// An area was filled. Contains the position of the filled area's top-left corner, and its
+   /// image encoded as PNG.
+   Fill((i32, i32), Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+   const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+   /// Renders a fill mask as rows of `#` for filled pixels and `.` for the rest.
+   fn mask_rows(mask: &[bool], width: usize) -> Vec<String> {
+      mask
+         .chunks(width)
+         .map(|row| row.iter().map(|&filled| if filled { '#' } else { '.' }).collect())
+         .collect()
+   }
+
+   #[test]
+   fn fill_stops_at_different_colors() {
+      let image = RgbaImage::from_fn(5, 3, |x, _| if x == 2 { BLACK } else { WHITE });
+      let (mask, bounds) = BucketTool::flood_fill(&image, (0, 1), 0);
+      assert_eq!(mask_rows(&mask, 5), ["##...", "##...", "##..."]);
+      assert_eq!(bounds, (0, 0, 1, 2));
+   }
+
+   #[test]
+   fn fill_spreads_around_walls() {
+      // The walls force the fill to snake through the image from one side to the other.
+      let image = RgbaImage::from_fn(5, 5, |x, y| {
+         if (y == 1 && x < 4) || (y == 3 && x > 0) {
+            BLACK
+         } else {
+            WHITE
+         }
+      });
+      let (mask, bounds) = BucketTool::flood_fill(&image, (0, 0), 0);
+      assert_eq!(
+         mask_rows(&mask, 5),
+         ["#####", "....#", "#####", "#....", "#####"]
+      );
+      assert_eq!(bounds, (0, 0, 4, 4));
+   }
+
+   #[test]
+   fn tolerance_includes_similar_colors() {
+      let gray = Rgba([250, 255, 255, 255]);
+      let image = RgbaImage::from_fn(3, 1, |x, _| if x == 1 { gray } else { WHITE });
+      assert_eq!(BucketTool::color_distance(&gray, &WHITE), 5);
+
+      let (mask, bounds) = BucketTool::flood_fill(&image, (0, 0), 4);
+      assert_eq!(mask, [true, false, false]);
+      assert_eq!(bounds, (0, 0, 0, 0));
+      let (mask, bounds) = BucketTool::flood_fill(&image, (0, 0), 5);
+      assert_eq!(mask, [true, true, true]);
+      assert_eq!(bounds, (0, 0, 2, 0));
+   }
+}
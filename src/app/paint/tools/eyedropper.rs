@@ -1,4 +1,8 @@
-use crate::backend::winit::event::MouseButton;
+//! The Eyedropper tool. Picks colors from the canvas.
+//!
+//! Clicking with the left mouse button picks the primary color, and clicking with the right mouse
+//! button picks the secondary color.
+
 use netcanv_renderer::paws::{AlignH, AlignV, Color, Layout, Point};
 
 use crate::assets::Assets;
@@ -52,18 +56,17 @@ impl Tool for EyedropperTool {
          ..
       }: ToolArgs,
       paint_canvas: &mut PaintCanvas,
-      viewport: &Viewport,
+      viewport: &mut Viewport,
    ) {
       if input.mouse_active() {
          let Point { x, y } = viewport.to_viewport_space(input.mouse_position(), ui.size());
          self.color = paint_canvas.get_pixel(ui, (x as i64, y as i64));
 
-         if input.mouse_button_is_down(MouseButton::Left) {
-            if self.color.a == 0 {
-               global_controls.color_picker.set_eraser(true);
-            } else {
-               global_controls.color_picker.set_color(self.color);
-            }
+         let mouse = config().keymap.mouse.clone();
+         if input.mouse_button_is_down(mouse.draw) {
+            global_controls.color_picker.set_color_or_eraser(self.color);
+         } else if input.mouse_button_is_down(mouse.alternate) {
+            global_controls.color_picker.set_secondary_color(self.color);
          }
       }
    }
@@ -0,0 +1,119 @@
+//! The Hand tool. Navigates around the canvas without drawing anything.
+//!
+//! Dragging with the left mouse button pans the canvas around, same as dragging with the middle
+//! mouse button does with any other tool. Zooming with the mouse wheel works regardless of the
+//! current tool, so this tool doesn't need to handle it itself.
+
+use crate::backend::winit::window::CursorIcon;
+use netcanv_renderer::paws::{point, vector, AlignH, Point};
+use netcanv_renderer::Font as FontTrait;
+
+use crate::assets::Assets;
+use crate::backend::{Backend, Image};
+use crate::config::config;
+use crate::keymap::KeyBinding;
+use crate::paint_canvas::PaintCanvas;
+use crate::ui::{ButtonState, UiElements};
+use crate::viewport::Viewport;
+
+use super::{Tool, ToolArgs};
+
+pub struct HandTool {
+   icon: Image,
+   position_icon: Image,
+   panning: bool,
+   /// The point at the center of the screen, in viewport space. Displayed on the bottom bar.
+   center: Point,
+   /// The zoom level, displayed on the bottom bar.
+   zoom: f32,
+}
+
+impl HandTool {
+   /// Creates an instance of the hand tool.
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/hand.svg")),
+         position_icon: Assets::load_svg(
+            renderer,
+            include_bytes!("../../../assets/icons/position.svg"),
+         ),
+         panning: false,
+         center: point(0.0, 0.0),
+         zoom: 1.0,
+      }
+   }
+}
+
+impl Tool for HandTool {
+   fn name(&self) -> &'static str {
+      "hand"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn key_shortcut(&self) -> KeyBinding {
+      config().keymap.tools.hand
+   }
+
+   /// Stops panning, such that releasing the temporary activation key in the middle of a drag
+   /// doesn't leave the tool stuck panning the next time it's selected.
+   fn deactivate(&mut self, _renderer: &mut Backend, _paint_canvas: &mut PaintCanvas) {
+      self.panning = false;
+   }
+
+   /// Pans the viewport around when dragging with the draw button.
+   fn process_paint_canvas_input(
+      &mut self,
+      ToolArgs { ui, input, .. }: ToolArgs,
+      _paint_canvas: &mut PaintCanvas,
+      viewport: &mut Viewport,
+   ) {
+      let draw_button = config().keymap.mouse.draw;
+      match input.action(draw_button) {
+         (true, ButtonState::Pressed) if input.mouse_active() => self.panning = true,
+         (_, ButtonState::Released) => self.panning = false,
+         _ => (),
+      }
+
+      if self.panning {
+         let delta_pan = input.previous_mouse_position() - input.mouse_position();
+         viewport.pan_around(delta_pan);
+      }
+
+      if input.mouse_active() || self.panning {
+         input.set_cursor(if self.panning {
+            CursorIcon::Grabbing
+         } else {
+            CursorIcon::Grab
+         });
+      }
+
+      self.center =
+         viewport.to_viewport_space(point(ui.width() / 2.0, ui.height() / 2.0), ui.size());
+      self.zoom = viewport.zoom();
+   }
+
+   /// Shows the current position and zoom level on the bottom bar.
+   fn process_bottom_bar(&mut self, ToolArgs { ui, assets, .. }: ToolArgs) {
+      let icon_size = vector(ui.height(), ui.height());
+
+      let center = format!("{:.0}, {:.0}", self.center.x, self.center.y);
+      ui.icon(&self.position_icon, assets.colors.text, Some(icon_size));
+      ui.horizontal_label(
+         &assets.sans,
+         &center,
+         assets.colors.text,
+         Some((assets.sans.text_width(&center).max(96.0), AlignH::Center)),
+      );
+
+      let zoom = format!("{:.0}%", self.zoom * 100.0);
+      ui.horizontal_label(
+         &assets.sans,
+         &zoom,
+         assets.colors.text,
+         Some((assets.sans.text_width(&zoom).max(64.0), AlignH::Center)),
+      );
+   }
+}
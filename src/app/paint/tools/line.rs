@@ -0,0 +1,467 @@
+//! The Line tool. Draws straight lines.
+//!
+//! Dragging with the left mouse button previews a line from the point where the button was pressed
+//! to the cursor, and releasing the button places it onto the canvas. Holding Shift snaps the
+//! line's angle to multiples of [`LineTool::ANGLE_STEP`] degrees. Pressing Escape or the right
+//! mouse button while dragging cancels the line.
+//!
+//! Lines are sent to peers as their endpoints, along with the color, thickness, and cap they were
+//! drawn with, such that peers can draw the exact same line.
+
+use netcanv_protocol::relay::PeerId;
+use netcanv_renderer::paws::{point, vector, AlignH, AlignV, Color, Layout, LineCap, Point, Rect};
+use netcanv_renderer::{BlendMode, RenderBackend};
+use serde::{Deserialize, Serialize};
+
+use crate::app::paint::{grid, GlobalControls};
+use crate::assets::Assets;
+use crate::backend::winit::window::CursorIcon;
+use crate::backend::{Backend, Image};
+use crate::common::deserialize_bincode;
+use crate::config::config;
+use crate::keymap::KeyBinding;
+use crate::paint_canvas::PaintCanvas;
+use crate::strings::LineCapStrings;
+use crate::ui::{
+   view, Button, ButtonArgs, ButtonColors, ButtonState, ColorPicker, ColorPickerArgs, Slider,
+   SliderArgs, SliderStep, UiElements, VirtualKeyCode,
+};
+use crate::viewport::Viewport;
+use crate::Error;
+
+use super::{Net, Tool, ToolArgs};
+
+/// The shape of a line's ends. This is also used by the brush.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum Cap {
+   #[default]
+   Round,
+   Square,
+   Butt,
+}
+
+impl Cap {
+   pub(super) const ALL: [Cap; 3] = [Self::Round, Self::Square, Self::Butt];
+
+   pub(super) fn line_cap(self) -> LineCap {
+      match self {
+         Self::Round => LineCap::Round,
+         Self::Square => LineCap::Square,
+         Self::Butt => LineCap::Butt,
+      }
+   }
+
+   pub(super) fn name(self, strings: &LineCapStrings) -> &str {
+      match self {
+         Self::Round => &strings.round,
+         Self::Square => &strings.square,
+         Self::Butt => &strings.butt,
+      }
+   }
+}
+
+/// The line tool's settings, saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct LineSettings {
+   thickness: f32,
+   cap: Cap,
+}
+
+impl Default for LineSettings {
+   fn default() -> Self {
+      Self {
+         thickness: LineTool::DEFAULT_THICKNESS,
+         cap: Cap::Round,
+      }
+   }
+}
+
+/// A straight line, in viewport space.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Line {
+   a: (f32, f32),
+   b: (f32, f32),
+   color: (u8, u8, u8, u8),
+   thickness: f32,
+   cap: Cap,
+}
+
+impl Line {
+   /// Returns the rectangle covered by the line, including its caps.
+   fn coverage(&self) -> Rect {
+      // Square caps stick out the furthest, by half of the diagonal of a square as wide as the
+      // line; the full thickness is a bit more than that.
+      let reach = self.thickness;
+      let left = self.a.0.min(self.b.0) - reach;
+      let top = self.a.1.min(self.b.1) - reach;
+      let right = self.a.0.max(self.b.0) + reach;
+      let bottom = self.a.1.max(self.b.1) + reach;
+      Rect::new(point(left, top), vector(right - left, bottom - top))
+   }
+}
+
+pub struct LineTool {
+   icon: Image,
+   thickness_slider: Slider,
+   cap: Cap,
+
+   /// The point where the line being dragged out starts, in viewport space.
+   start: Option<Point>,
+   /// The point where the line being dragged out ends, in viewport space.
+   end: Point,
+   /// Lines placed by the user, waiting to be sent to other peers.
+   placed_lines: Vec<Line>,
+}
+
+impl LineTool {
+   const MAX_THICKNESS: f32 = 64.0;
+   const DEFAULT_THICKNESS: f32 = 4.0;
+
+   /// The step the angle of lines is snapped to while Shift is held, in degrees.
+   const ANGLE_STEP: f32 = 15.0;
+
+   /// Creates an instance of the line tool.
+   pub fn new(renderer: &mut Backend) -> Self {
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/line.svg")),
+         thickness_slider: Slider::new(
+            Self::DEFAULT_THICKNESS,
+            1.0,
+            Self::MAX_THICKNESS,
+            SliderStep::Discrete(1.0),
+         ),
+         cap: Cap::Round,
+         start: None,
+         end: point(0.0, 0.0),
+         placed_lines: Vec::new(),
+      }
+   }
+
+   /// Snaps the angle of the line from `start` to `end` to the nearest multiple of
+   /// [`Self::ANGLE_STEP`], keeping its length. Returns the new end point.
+   fn snap_angle(start: Point, end: Point) -> Point {
+      let delta = end - start;
+      let length = delta.x.hypot(delta.y);
+      let step = Self::ANGLE_STEP.to_radians();
+      let angle = (delta.y.atan2(delta.x) / step).round() * step;
+      start + vector(angle.cos(), angle.sin()) * length
+   }
+
+   /// Returns the line that's being dragged out, if any.
+   fn pending_line(&self, color: Color) -> Option<Line> {
+      self.start.map(|start| Line {
+         a: (start.x, start.y),
+         b: (self.end.x, self.end.y),
+         color: (color.r, color.g, color.b, color.a),
+         thickness: self.thickness_slider.value(),
+         cap: self.cap,
+      })
+   }
+
+   /// Draws a line onto the paint canvas.
+   fn draw(renderer: &mut Backend, paint_canvas: &mut PaintCanvas, line: &Line) {
+      let (r, g, b, a) = line.color;
+      paint_canvas.draw(renderer, line.coverage(), |renderer| {
+         renderer.set_blend_mode(BlendMode::Replace);
+         renderer.line(
+            point(line.a.0, line.a.1),
+            point(line.b.0, line.b.1),
+            Color::new(r, g, b, a),
+            line.cap.line_cap(),
+            line.thickness,
+         );
+      });
+   }
+}
+
+impl Tool for LineTool {
+   fn name(&self) -> &'static str {
+      "line"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn key_shortcut(&self) -> KeyBinding {
+      config().keymap.tools.line
+   }
+
+   fn save_settings(&self) -> Option<toml::Value> {
+      let settings = LineSettings {
+         thickness: self.thickness_slider.value(),
+         cap: self.cap,
+      };
+      toml::Value::try_from(settings).ok()
+   }
+
+   fn load_settings(&mut self, settings: toml::Value) {
+      match settings.try_into::<LineSettings>() {
+         Ok(settings) => {
+            self.thickness_slider.set_value(settings.thickness);
+            self.cap = settings.cap;
+         }
+         Err(error) => tracing::warn!("ignoring invalid line settings: {}", error),
+      }
+   }
+
+   /// Cancels the line that's being dragged out.
+   fn deactivate(&mut self, _renderer: &mut Backend, _paint_canvas: &mut PaintCanvas) {
+      self.start = None;
+   }
+
+   /// Drags out lines and places them onto the canvas.
+   fn process_paint_canvas_input(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         global_controls,
+         ..
+      }: ToolArgs,
+      paint_canvas: &mut PaintCanvas,
+      viewport: &mut Viewport,
+   ) {
+      let mouse = config().keymap.mouse.clone();
+      if self.start.is_some()
+         && (input.key_just_typed(VirtualKeyCode::Escape)
+            || input.mouse_button_just_pressed(mouse.alternate))
+      {
+         self.start = None;
+      }
+      if !input.mouse_active() {
+         return;
+      }
+      input.set_cursor(CursorIcon::Crosshair);
+
+      let mouse_position = viewport.to_viewport_space(input.mouse_position(), ui.size());
+      self.end = match self.start {
+         Some(start) if input.shift_is_down() => Self::snap_angle(start, mouse_position),
+         _ => grid::snap(mouse_position),
+      };
+
+      match input.action(mouse.draw) {
+         (true, ButtonState::Pressed) => {
+            if paint_canvas.point_is_in_bounds(mouse_position) {
+               self.start = Some(self.end);
+            }
+         }
+         (_, ButtonState::Released) => {
+            let color = global_controls.color_picker.color();
+            if let Some(line) = self.pending_line(color) {
+               paint_canvas.begin_history_step();
+               paint_canvas.record_history(ui.render(), line.coverage());
+               Self::draw(ui.render(), paint_canvas, &line);
+               paint_canvas.finish_history_step(ui.render());
+               global_controls.color_picker.remember_color();
+               self.placed_lines.push(line);
+            }
+            self.start = None;
+         }
+         _ => (),
+      }
+   }
+
+   /// Previews the line that's being dragged out.
+   fn process_paint_canvas_overlays(
+      &mut self,
+      ToolArgs {
+         ui,
+         global_controls,
+         ..
+      }: ToolArgs,
+      viewport: &Viewport,
+   ) {
+      let Some(line) = self.pending_line(global_controls.color_picker.color()) else {
+         return;
+      };
+      let window_size = ui.size();
+      let (r, g, b, a) = line.color;
+      ui.render().line(
+         viewport.to_screen_space(point(line.a.0, line.a.1), window_size),
+         viewport.to_screen_space(point(line.b.0, line.b.1), window_size),
+         Color::new(r, g, b, a),
+         line.cap.line_cap(),
+         viewport.to_screen_length(line.thickness),
+      );
+   }
+
+   /// Shows the color palette, the thickness slider, and the cap buttons.
+   fn process_bottom_bar(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         assets,
+         wm,
+         canvas_view,
+         global_controls,
+         ..
+      }: ToolArgs,
+   ) {
+      let mut picker_window = ColorPicker::picker_window_view();
+      view::layout::align(
+         &view::layout::padded(canvas_view, 16.0),
+         &mut picker_window,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      global_controls.color_picker.process(
+         ui,
+         input,
+         ColorPickerArgs {
+            assets,
+            wm,
+            window_view: picker_window,
+            show_eraser: false,
+         },
+      );
+      ui.space(16.0);
+
+      ui.horizontal_label(
+         &assets.sans,
+         &assets.tr.brush_thickness,
+         assets.colors.text,
+         None,
+      );
+      ui.space(16.0);
+      ui.push((192.0, ui.height()), Layout::Freeform);
+      self.thickness_slider.process(
+         ui,
+         input,
+         SliderArgs {
+            width: ui.width(),
+            color: assets.colors.slider,
+            focus_outline: assets.colors.focus_outline,
+         },
+      );
+      ui.pop();
+      ui.space(8.0);
+      ui.horizontal_label(
+         &assets.sans_bold,
+         &self.thickness_slider.value().to_string(),
+         assets.colors.text,
+         Some((40.0, AlignH::Center)),
+      );
+      ui.space(16.0);
+
+      let strings = &assets.tr.line_cap;
+      for (i, cap) in Cap::ALL.into_iter().enumerate() {
+         if i > 0 {
+            ui.space(4.0);
+         }
+         let colors = ButtonColors::toggle(
+            self.cap == cap,
+            &assets.colors.radio_button.normal,
+            &assets.colors.radio_button.selected,
+         );
+         if Button::with_text(
+            ui,
+            input,
+            &ButtonArgs::new(ui, colors).height(24.0).pill(),
+            &assets.sans,
+            cap.name(strings),
+         )
+         .clicked()
+         {
+            self.cap = cap;
+         }
+      }
+   }
+
+   /// Sends out the lines placed by the user.
+   fn network_send(&mut self, net: Net, _global_controls: &GlobalControls) -> netcanv::Result<()> {
+      if !self.placed_lines.is_empty() {
+         let packet = Packet::Lines(self.placed_lines.drain(..).collect());
+         net.send(self, PeerId::BROADCAST, packet)?;
+      }
+      Ok(())
+   }
+
+   /// Draws lines placed by other peers.
+   fn network_receive(
+      &mut self,
+      renderer: &mut Backend,
+      _net: Net,
+      paint_canvas: &mut PaintCanvas,
+      sender: PeerId,
+      payload: Vec<u8>,
+   ) -> netcanv::Result<()> {
+      let packet = deserialize_bincode(&payload)?;
+      match packet {
+         Packet::Lines(lines) => {
+            tracing::debug!("{} placed {} lines", sender, lines.len());
+            for line in &lines {
+               ensure!(
+                  line.thickness.is_finite()
+                     && line.thickness <= Self::MAX_THICKNESS + 0.1
+                     && [line.a.0, line.a.1, line.b.0, line.b.1].iter().all(|x| x.is_finite()),
+                  Error::InvalidToolPacket
+               );
+               Self::draw(renderer, paint_canvas, line);
+            }
+         }
+      }
+      Ok(())
+   }
+}
+
+/// A network packet for the line tool.
+#[derive(Serialize, Deserialize)]
+enum Packet {
+   /// Lines were placed onto the canvas.
+   Lines(Vec<Line>),
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn assert_close(a: Point, b: Point) {
+      let d = a - b;
+      assert!(d.x.hypot(d.y) < 1e-3, "{a:?} is not close to {b:?}");
+   }
+
+   fn line(a: (f32, f32), b: (f32, f32), thickness: f32) -> Line {
+      Line {
+         a,
+         b,
+         color: (0, 0, 0, 255),
+         thickness,
+         cap: Cap::Square,
+      }
+   }
+
+   #[test]
+   fn snapping_keeps_the_length_of_lines() {
+      let start = point(10.0, 10.0);
+      let snapped = LineTool::snap_angle(start, point(20.0, 11.0));
+      assert_close(snapped, point(10.0 + 101f32.sqrt(), 10.0));
+
+      let snapped = LineTool::snap_angle(start, point(0.0, 21.0));
+      let (length, diagonal) = (221f32.sqrt(), std::f32::consts::FRAC_1_SQRT_2);
+      assert_close(snapped, start + vector(-diagonal, diagonal) * length);
+
+      // Lines that already lie at a multiple of the step are left alone.
+      let end = start + vector(30f32.to_radians().cos(), 30f32.to_radians().sin()) * 5.0;
+      assert_close(LineTool::snap_angle(start, end), end);
+   }
+
+   #[test]
+   fn coverage_includes_the_caps() {
+      let expected = Rect::new(point(-4.0, 6.0), vector(18.0, 28.0));
+      assert_eq!(line((0.0, 10.0), (10.0, 30.0), 4.0).coverage(), expected);
+      assert_eq!(line((10.0, 30.0), (0.0, 10.0), 4.0).coverage(), expected);
+   }
+
+   #[test]
+   fn missing_settings_are_defaulted() {
+      let settings: LineSettings = toml::from_str("thickness = 8.0").unwrap();
+      assert_eq!(settings.thickness, 8.0);
+      assert_eq!(settings.cap, Cap::Round);
+
+      let settings: LineSettings = toml::from_str(r#"cap = "Butt""#).unwrap();
+      assert_eq!(settings.thickness, LineTool::DEFAULT_THICKNESS);
+      assert_eq!(settings.cap, Cap::Butt);
+   }
+}
@@ -14,14 +14,23 @@ use crate::ui::{Input, Ui};
 use crate::viewport::Viewport;
 
 mod brush;
+mod bucket;
 mod eyedropper;
+mod hand;
+mod line;
 mod selection;
+mod text;
 
 pub use brush::*;
+pub use bucket::*;
 pub use eyedropper::*;
+pub use hand::*;
+pub use line::*;
 pub use selection::*;
+pub use text::*;
 
 use netcanv_protocol::relay::PeerId;
+use netcanv_renderer::paws::{Color, Point};
 use serde::Serialize;
 
 use super::GlobalControls;
@@ -39,6 +48,37 @@ pub trait Tool {
    /// Returns the key shortcut for this tool.
    fn key_shortcut(&self) -> KeyBinding;
 
+   /// Returns the tool's settings, to be saved in the user config when the app exits.
+   ///
+   /// Tools that don't have any settings worth remembering return `None`.
+   fn save_settings(&self) -> Option<toml::Value> {
+      None
+   }
+
+   /// Restores the tool's settings previously returned by `save_settings`.
+   ///
+   /// The settings may come from an older version of the tool, or be edited by hand, so invalid
+   /// settings should be ignored rather than treated as an error.
+   fn load_settings(&mut self, _settings: toml::Value) {}
+
+   /// Copies the last known settings of the given peer's instance of this tool into this one.
+   ///
+   /// This is a one-time copy; later changes the peer makes to their settings are not mirrored.
+   /// Returns whether any settings were copied.
+   fn copy_peer_settings(
+      &mut self,
+      _peer_id: PeerId,
+      _global_controls: &mut GlobalControls,
+   ) -> bool {
+      false
+   }
+
+   /// Returns the last known position of the given peer's cursor on the canvas, or `None` if the
+   /// tool doesn't know where the peer is.
+   fn peer_position(&self, _peer_id: PeerId) -> Option<Point> {
+      None
+   }
+
    /// Called when the tool is selected.
    fn activate(&mut self) {}
 
@@ -48,6 +88,12 @@ pub trait Tool {
    /// deselected.
    fn deactivate(&mut self, _renderer: &mut Backend, _paint_canvas: &mut PaintCanvas) {}
 
+   /// Returns whether the tool is using the keyboard for something else, such as typing in text.
+   /// Keyboard shortcuts are not processed while this is the case.
+   fn captures_keyboard(&self) -> bool {
+      false
+   }
+
    /// Called each frame when this tool is active, to poll for keyboard shortcuts.
    ///
    /// The returned value signifies what action should be taken after the function is done running.
@@ -73,18 +119,37 @@ pub trait Tool {
    }
 
    /// Processes background jobs like image decoding.
+   ///
+   /// Anything drawn to the paint canvas here ends up on its base layer, as background jobs are
+   /// used for placing other peers' drawings.
    fn process_background_jobs(&mut self, _args: ToolArgs, _paint_canvas: &mut PaintCanvas) {}
 
+   /// Returns whether the tool needs another frame to be processed, even if no input arrives.
+   ///
+   /// This should be the case while something is being animated, such as a peer's cursor moving
+   /// towards its most recent position, or while background jobs are still running.
+   fn needs_redraw(&self) -> bool {
+      false
+   }
+
    /// Called before the paint canvas is rendered to the screen. Primarily used for drawing to the
    /// paint canvas, or updating related state.
    ///
    /// Should not be used for drawing to the screen, as all effects of drawing would be overwritten
    /// by the canvas itself.
+   ///
+   /// The left mouse button triggers the tool's primary action. Tools may also assign a secondary
+   /// action to the right mouse button, which should be documented in the tool's module docs.
+   /// Right-button actions that modify the canvas should only trigger on drags, so that plain
+   /// right clicks remain free for opening context menus.
+   ///
+   /// The viewport is mutable so that navigation tools can pan it around; drawing tools should
+   /// leave it as is.
    fn process_paint_canvas_input(
       &mut self,
       _args: ToolArgs,
       _paint_canvas: &mut PaintCanvas,
-      _viewport: &Viewport,
+      _viewport: &mut Viewport,
    ) {
    }
 
@@ -130,7 +195,8 @@ pub trait Tool {
       Ok(())
    }
 
-   /// Called for each incoming packet from a specific `sender`.
+   /// Called for each incoming packet from a specific `sender`. Anything drawn to the paint canvas
+   /// here ends up on its base layer.
    fn network_receive(
       &mut self,
       _renderer: &mut Backend,
@@ -204,6 +270,11 @@ impl<'peer> Net<'peer> {
    pub fn peer_name(&self, peer_id: PeerId) -> Option<&str> {
       self.peer.mates().get(&peer_id).map(|mate| mate.nickname.deref())
    }
+
+   /// Returns the color assigned to the given peer, if the peer is present.
+   pub fn peer_color(&self, peer_id: PeerId) -> Option<Color> {
+      self.peer.mates().get(&peer_id).map(|mate| mate.color)
+   }
 }
 
 #[non_exhaustive]
@@ -2,24 +2,26 @@ use image::imageops::FilterType;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use tokio::sync::{mpsc, oneshot};
 use web_time::Instant;
 
-use crate::backend::winit::event::MouseButton;
 use crate::backend::winit::window::CursorIcon;
-use crate::config::config;
+use crate::config::{config, PastePosition, PasteScaling, SelectionCapture};
 use crate::keymap::KeyBinding;
 use image::codecs::png::PngEncoder;
 use image::io::Reader;
-use image::{ColorType, ImageEncoder, ImageFormat, RgbaImage};
+use image::{ColorType, ImageEncoder, ImageError, ImageFormat, RgbaImage};
 use netcanv_protocol::relay::PeerId;
-use netcanv_renderer::paws::{point, vector, AlignH, AlignV, Color, Point, Rect, Renderer, Vector};
+use netcanv_renderer::paws::{
+   point, vector, AlignH, AlignV, Color, LineCap, Point, Rect, Renderer, Vector,
+};
 use netcanv_renderer::{
    BlendMode, Font as FontTrait, Framebuffer as FramebufferTrait, RenderBackend,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::app::paint::{self, GlobalControls};
+use crate::app::paint::{self, grid, GlobalControls};
 use crate::assets::Assets;
 use crate::backend::{Backend, Font, Framebuffer, Image};
 use crate::clipboard;
@@ -27,6 +29,8 @@ use crate::common::{deserialize_bincode, lerp_point, RectMath, VectorMath};
 use crate::paint_canvas::PaintCanvas;
 use crate::ui::{ButtonState, UiElements, UiInput};
 use crate::viewport::Viewport;
+use crate::wake;
+use crate::Error;
 
 use super::{KeyShortcutAction, Net, Tool, ToolArgs};
 
@@ -58,6 +62,7 @@ enum Action {
    Selecting,
    DraggingHandle(Handle),
    DraggingWhole,
+   Rotating,
 }
 
 impl Action {
@@ -78,26 +83,56 @@ pub struct SelectionTool {
    potential_action: Action,
    action: Action,
    selection: Selection,
+   /// The point where marking out the current selection started.
+   anchor: Point,
+   /// The selection angle peers were last told about.
+   sent_angle: f32,
    peer_selections: HashMap<PeerId, PeerSelection>,
 
    paste: Option<(
-      Point,
-      oneshot::Receiver<RgbaImage>,
-      oneshot::Receiver<Vec<u8>>,
+      oneshot::Receiver<Vec<PasteTile>>,
+      oneshot::Receiver<Vec<(Rect, Vec<u8>)>>,
    )>,
    peer_pastes_tx: mpsc::UnboundedSender<(PeerId, Option<RgbaImage>)>,
    peer_pastes_rx: mpsc::UnboundedReceiver<(PeerId, Option<RgbaImage>)>,
    ongoing_paste_jobs: HashSet<PeerId>,
+   peer_placements_tx: mpsc::UnboundedSender<(Point, RgbaImage)>,
+   peer_placements_rx: mpsc::UnboundedReceiver<(Point, RgbaImage)>,
+}
+
+/// A piece of an image pasted from the clipboard.
+///
+/// Images that fit in a single selection are pasted as one tile.
+#[derive(Clone)]
+struct PasteTile {
+   /// The position of the tile's top-left corner on the canvas.
+   position: Point,
+   image: RgbaImage,
+}
+
+impl PasteTile {
+   /// Returns the rectangle the tile covers on the canvas.
+   fn rect(&self) -> Rect {
+      Rect::new(
+         self.position,
+         vector(self.image.width() as f32, self.image.height() as f32),
+      )
+   }
 }
 
 impl SelectionTool {
-   /// The color of the selection.
+   /// The color of the selection. Other peers' selections use the colors assigned to them.
    const COLOR: Color = Color::rgb(0x0397fb);
    /// The radius of handles for resizing the selection contents.
    const HANDLE_RADIUS: f32 = 4.0;
+   /// The distance between the top edge of the selection and the rotation handle, in pixels.
+   const ROTATION_HANDLE_DISTANCE: f32 = 24.0;
+   /// The angle the rotation snaps to while Shift is held.
+   const ROTATION_SNAP: f32 = std::f32::consts::PI / 12.0;
 
    pub fn new(renderer: &mut Backend) -> Self {
       let (peer_pastes_tx, peer_pastes_rx) = mpsc::unbounded_channel();
+      let (peer_placements_tx, peer_placements_rx) = mpsc::unbounded_channel();
       Self {
          icons: Icons {
             tool: Assets::load_svg(
@@ -121,12 +156,16 @@ impl SelectionTool {
          potential_action: Action::None,
          action: Action::None,
          selection: Selection::new(),
+         anchor: point(0.0, 0.0),
+         sent_angle: 0.0,
          peer_selections: HashMap::new(),
 
          paste: None,
          peer_pastes_tx,
          peer_pastes_rx,
          ongoing_paste_jobs: HashSet::new(),
+         peer_placements_tx,
+         peer_placements_rx,
       }
    }
 
@@ -153,9 +192,19 @@ impl SelectionTool {
       }
    }
 
+   /// Returns the position of the rotation handle, which sits above the top edge of the rectangle.
+   fn rotation_handle(rect: Rect, distance: f32) -> Point {
+      rect.sort().top_center() - vector(0.0, distance)
+   }
+
    /// Draws a resize handle.
    fn draw_handle(&self, renderer: &mut Backend, position: Point, handle: Handle) {
-      let radius = if self.potential_action == Action::DraggingHandle(handle) {
+      self.draw_handle_for(renderer, position, Action::DraggingHandle(handle));
+   }
+
+   /// Draws a handle that triggers the given action when dragged.
+   fn draw_handle_for(&self, renderer: &mut Backend, position: Point, action: Action) {
+      let radius = if self.potential_action == action {
          Self::HANDLE_RADIUS * 2.0
       } else {
          Self::HANDLE_RADIUS
@@ -164,6 +213,67 @@ impl SelectionTool {
       renderer.fill_circle(position, radius, Self::COLOR);
    }
 
+   /// Rotates the renderer's transform by the given angle around the center of the rectangle.
+   fn rotate_around_center(renderer: &mut Backend, rect: Rect, angle: f32) {
+      let center = rect.center();
+      renderer.translate(center);
+      renderer.rotate(angle);
+      renderer.translate(-center);
+   }
+
+   /// Draws other peers' selections overlapping the local selection into its capture. Returns
+   /// whether any selections were drawn.
+   fn merge_peer_selections(&self, renderer: &mut Backend) -> bool {
+      let (Some(rect), Some(capture)) = (
+         self.selection.normalized_rect(),
+         self.selection.capture.as_ref(),
+      ) else {
+         return false;
+      };
+      let rect = rect.sort();
+      let mut merged = false;
+      renderer.draw_to(capture, |renderer| {
+         for peer in self.peer_selections.values() {
+            let (Some(peer_rect), Some(peer_capture)) = (
+               peer.selection.normalized_rect(),
+               peer.selection.capture.as_ref(),
+            ) else {
+               continue;
+            };
+            let peer_rect = peer_rect.sort();
+            let overlaps = peer_rect.left() < rect.right()
+               && peer_rect.right() > rect.left()
+               && peer_rect.top() < rect.bottom()
+               && peer_rect.bottom() > rect.top();
+            if overlaps {
+               renderer.framebuffer(
+                  Rect::new(peer_rect.position - rect.position, peer_rect.size),
+                  peer_capture,
+               );
+               merged = true;
+            }
+         }
+      });
+      merged
+   }
+
+   /// Returns the rectangle marked out by dragging the mouse from the anchor to the given point.
+   ///
+   /// With `square`, both sides are as long as the longer one. With `from_center`, the anchor is
+   /// the center of the rectangle rather than one of its corners.
+   fn marked_rect(anchor: Point, mouse_position: Point, square: bool, from_center: bool) -> Rect {
+      let mut size = mouse_position - anchor;
+      if square {
+         let side = size.x.abs().max(size.y.abs());
+         size = vector(side * size.x.signum(), side * size.y.signum());
+      }
+      if from_center {
+         Rect::new(anchor - size, size * 2.0)
+      } else {
+         Rect::new(anchor, size)
+      }
+   }
+
    /// Returns whether a rect is smaller than a pixel.
    fn rect_is_smaller_than_a_pixel(rect: Rect) -> bool {
       rect.width().trunc().abs() < 1.0 || rect.height().trunc().abs() < 1.0
@@ -179,7 +289,8 @@ impl SelectionTool {
       })
    }
 
-   /// Sends a `Rect` packet containing the current selection rectangle.
+   /// Sends a `Rect` packet containing the current selection rectangle, followed by a `Rotation`
+   /// packet if the selection is rotated.
    /// This is sometimes needed before important actions, where the rectangle may not have been
    /// synchronized yet due to the lower network tick rate.
    fn send_rect_packet(&self, net: &Net) -> netcanv::Result<()> {
@@ -192,6 +303,30 @@ impl SelectionTool {
                size: (rect.width(), rect.height()),
             },
          )?;
+         if self.selection.angle != 0.0 {
+            net.send(
+               self,
+               PeerId::BROADCAST,
+               Packet::Rotation(self.selection.angle),
+            )?;
+         }
+      }
+      Ok(())
+   }
+
+   /// Sends a `Rotation` packet if the selection's angle changed since peers were last told about
+   /// it.
+   ///
+   /// Unrotated selections don't need the packet, which keeps them compatible with clients that
+   /// don't support rotation.
+   fn send_rotation_packet(&mut self, net: &Net) -> netcanv::Result<()> {
+      if self.selection.angle != self.sent_angle {
+         net.send(
+            self,
+            PeerId::BROADCAST,
+            Packet::Rotation(self.selection.angle),
+         )?;
+         self.sent_angle = self.selection.angle;
       }
       Ok(())
    }
@@ -203,45 +338,159 @@ impl SelectionTool {
       }
    }
 
-   /// Pastes the clipboard image into a new selection.
-   fn enqueue_paste_from_clipboard(&mut self, position: Point) {
-      let (image_tx, image_rx) = oneshot::channel();
-      let (bytes_tx, bytes_rx) = oneshot::channel();
-      self.paste = Some((position, image_rx, bytes_rx));
-      tokio::task::spawn_blocking(|| {
+   /// Pastes the clipboard image into a new selection, centered on the given position.
+   fn enqueue_paste_from_clipboard(&mut self, center: Point) {
+      self.enqueue_paste(center, || {
          tracing::debug!("reading image from clipboard");
-         let image = catch!(clipboard::paste_image());
-         let image = if image.width() > Selection::MAX_SIZE || image.height() > Selection::MAX_SIZE
-         {
-            tracing::debug!("image is too big! scaling down");
-            let scale = Selection::MAX_SIZE as f32 / image.width().max(image.height()) as f32;
-            let new_width = (image.width() as f32 * scale) as u32;
-            let new_height = (image.height() as f32 * scale) as u32;
-            image::imageops::resize(&image, new_width, new_height, FilterType::Triangle)
-         } else {
-            image
+         clipboard::paste_image()
+      });
+   }
+
+   /// Pastes an image file into a new selection, centered on the given position.
+   fn enqueue_paste_from_file(&mut self, center: Point, path: PathBuf) {
+      self.enqueue_paste(center, move || {
+         tracing::debug!("reading image from {:?}", path);
+         Self::load_image_file(&path)
+      });
+   }
+
+   /// Loads the image pasted from a file.
+   fn load_image_file(path: &Path) -> netcanv::Result<RgbaImage> {
+      let image = Reader::open(path)?.with_guessed_format()?.decode();
+      let image = image.map_err(|error| match error {
+         ImageError::Unsupported(_) => Error::UnsupportedImageFile {
+            file: path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned(),
+         },
+         error => error.into(),
+      })?;
+      Ok(image.into_rgba8())
+   }
+
+   /// Pastes the image returned by `load` into a new selection, centered on the given position.
+   ///
+   /// The image is loaded, split into tiles, and encoded on a separate thread, and the paste is
+   /// finished by [`SelectionTool::poll_paste`].
+   fn enqueue_paste(
+      &mut self,
+      center: Point,
+      load: impl FnOnce() -> netcanv::Result<RgbaImage> + Send + 'static,
+   ) {
+      let (tiles_tx, tiles_rx) = oneshot::channel();
+      let (bytes_tx, bytes_rx) = oneshot::channel();
+      self.paste = Some((tiles_rx, bytes_rx));
+      let scaling = config().clipboard.paste_scaling;
+      tokio::task::spawn_blocking(move || {
+         let image = catch!(load());
+         let image = match scaling {
+            PasteScaling::FitToMaxSize => Self::fit_to_max_size(image),
+            PasteScaling::OriginalSize => image,
          };
+         let top_left = center - vector(image.width() as f32, image.height() as f32) / 2.0;
+         let tiles = Self::split_into_tiles(&image, point(top_left.x.floor(), top_left.y.floor()));
+         tracing::debug!("pasting image as {} tile(s)", tiles.len());
          // The result here doesn't matter. If the image doesn't arrive, we're out of the
          // paint state.
-         let _ = image_tx.send(image.clone());
+         let _ = tiles_tx.send(tiles.clone());
          tracing::debug!("encoding image for transmission");
-         let bytes = catch!(Self::encode_image(&image));
-         tracing::debug!("paste job done; encoded {} bytes", bytes.len());
+         let bytes: Vec<_> = catch!(tiles
+            .iter()
+            .map(|tile| Ok((tile.rect(), Self::encode_image(&tile.image)?)))
+            .collect::<netcanv::Result<_>>());
+         tracing::debug!(
+            "paste job done; encoded {} bytes",
+            bytes.iter().map(|(_, bytes)| bytes.len()).sum::<usize>()
+         );
          let _ = bytes_tx.send(bytes);
       });
    }
 
+   /// Scales the image down such that it fits in a single selection.
+   fn fit_to_max_size(image: RgbaImage) -> RgbaImage {
+      if image.width() > Selection::MAX_SIZE || image.height() > Selection::MAX_SIZE {
+         tracing::debug!("image is too big! scaling down");
+         let scale = Selection::MAX_SIZE as f32 / image.width().max(image.height()) as f32;
+         let new_width = (image.width() as f32 * scale) as u32;
+         let new_height = (image.height() as f32 * scale) as u32;
+         image::imageops::resize(&image, new_width, new_height, FilterType::Triangle)
+      } else {
+         image
+      }
+   }
+
+   /// Splits the image into tiles no larger than a single selection, with the image's top-left
+   /// corner placed at the given position.
+   fn split_into_tiles(image: &RgbaImage, top_left: Point) -> Vec<PasteTile> {
+      let mut tiles = Vec::new();
+      for y in (0..image.height()).step_by(Selection::MAX_SIZE as usize) {
+         for x in (0..image.width()).step_by(Selection::MAX_SIZE as usize) {
+            let width = (image.width() - x).min(Selection::MAX_SIZE);
+            let height = (image.height() - y).min(Selection::MAX_SIZE);
+            tiles.push(PasteTile {
+               position: top_left + vector(x as f32, y as f32),
+               image: image::imageops::crop_imm(image, x, y, width, height).to_image(),
+            });
+         }
+      }
+      tiles
+   }
+
+   /// Captures the user's own selection, recording the area it erases in the undo history.
+   ///
+   /// The history step is left open until the selection is placed back onto the canvas or
+   /// discarded, such that moving a selection around is undone in one go.
+   fn capture_own(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      if let Some(rect) = self.selection.rect {
+         paint_canvas.begin_history_step();
+         paint_canvas.record_history(renderer, rect);
+      }
+      self.selection.capture(renderer, paint_canvas);
+   }
+
+   /// Places the user's own selection onto the paint canvas, finishing its history step.
+   fn deselect_own(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      if let (Some(bounds), Some(_)) = (self.selection.bounds(), &self.selection.capture) {
+         paint_canvas.begin_history_step();
+         paint_canvas.record_history(renderer, bounds);
+      }
+      self.selection.deselect(renderer, paint_canvas);
+      // Peers reset the angle of the selection when it's deselected, too.
+      self.sent_angle = 0.0;
+      paint_canvas.finish_history_step(renderer);
+   }
+
+   /// Discards the user's own selection, finishing its history step.
+   fn cancel_own(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      self.selection.cancel();
+      self.sent_angle = 0.0;
+      paint_canvas.finish_history_step(renderer);
+   }
+
+   /// Draws an image onto the paint canvas, without selecting it.
+   fn place(
+      renderer: &mut Backend,
+      paint_canvas: &mut PaintCanvas,
+      position: Point,
+      image: &RgbaImage,
+   ) {
+      let mut selection = Selection::new();
+      selection.paste(renderer, Some(position), image);
+      selection.deselect(renderer, paint_canvas);
+   }
+
    /// Polls whether the paste operation is complete. Returns `true` when the tool should be
    /// switched to the selection tool.
-   fn poll_paste_from_clipboard(
+   fn poll_paste(
       &mut self,
       renderer: &mut Backend,
       paint_canvas: &mut PaintCanvas,
       net: &Net,
    ) -> bool {
-      if let Some((position, image, bytes)) = self.paste.as_mut() {
-         if let Ok(image) = image.try_recv() {
-            let position = *position;
+      if let Some((tiles, bytes)) = self.paste.as_mut() {
+         if let Ok(tiles) = tiles.try_recv() {
+            let Some((last, rest)) = tiles.split_last() else {
+               self.paste = None;
+               return false;
+            };
             // Tell peers to deselect the selection now, to avoid a race condition where the
             // position will get updated before deselecting, thus placing it at wrong position.
             catch!(self.send_rect_packet(net), return false);
@@ -249,14 +498,35 @@ impl SelectionTool {
                net.send(self, PeerId::BROADCAST, Packet::Deselect),
                return false
             );
-            self.selection.deselect(renderer, paint_canvas);
-            self.selection.paste(renderer, Some(position), &image);
+            self.deselect_own(renderer, paint_canvas);
+            // The placed tiles and the final position of the selection are undone together.
+            paint_canvas.begin_history_step();
+            for tile in rest {
+               paint_canvas.record_history(renderer, tile.rect());
+               Self::place(renderer, paint_canvas, tile.position, &tile.image);
+            }
+            self.selection.paste(renderer, Some(last.position), &last.image);
             return true;
          }
-         if let Ok(bytes) = bytes.try_recv() {
-            let Point { x, y } = *position;
+         if let Ok(mut bytes) = bytes.try_recv() {
+            // All tiles but the last one were placed onto the canvas right away, so peers are
+            // told to do the same. The last one ends up in the selection.
+            let Some((last_rect, last)) = bytes.pop() else {
+               self.paste = None;
+               return false;
+            };
+            for (rect, data) in bytes {
+               let Point { x, y } = rect.position;
+               catch!(
+                  net.send(self, PeerId::BROADCAST, Packet::Place((x, y), data)),
+                  return false
+               );
+            }
+            // Unlike placed tiles, pasted images are positioned by their center, as they always
+            // have been.
+            let Point { x, y } = last_rect.center();
             catch!(
-               net.send(self, PeerId::BROADCAST, Packet::Paste((x, y), bytes)),
+               net.send(self, PeerId::BROADCAST, Packet::Paste((x, y), last)),
                return false
             );
             catch!(self.send_rect_packet(net), return false);
@@ -291,8 +561,15 @@ impl SelectionTool {
       }
    }
 
+   /// Places images from peers' pastes that didn't fit in a single selection onto the canvas.
+   fn poll_peer_placements(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      while let Ok((position, image)) = self.peer_placements_rx.try_recv() {
+         Self::place(renderer, paint_canvas, position, &image);
+      }
+   }
+
    /// Encodes an image to PNG.
-   fn encode_image(image: &RgbaImage) -> netcanv::Result<Vec<u8>> {
+   pub(super) fn encode_image(image: &RgbaImage) -> netcanv::Result<Vec<u8>> {
       let mut bytes = Vec::new();
       PngEncoder::new(Cursor::new(&mut bytes)).write_image(
          image,
@@ -304,7 +581,7 @@ impl SelectionTool {
    }
 
    /// Decodes a PNG image.
-   fn decode_image(data: &[u8]) -> netcanv::Result<RgbaImage> {
+   pub(super) fn decode_image(data: &[u8]) -> netcanv::Result<RgbaImage> {
       Ok(Reader::with_format(Cursor::new(data), ImageFormat::Png).decode()?.to_rgba8())
    }
 }
@@ -322,21 +599,27 @@ impl Tool for SelectionTool {
       config().keymap.tools.selection
    }
 
+   /// Selections don't carry the peer's cursor, so the center of their selection is used instead.
+   fn peer_position(&self, peer_id: PeerId) -> Option<Point> {
+      let peer = self.peer_selections.get(&peer_id)?;
+      peer.lerp_normalized_rect().map(|rect| rect.center())
+   }
+
    /// When the tool is deactivated, the selection should be deselected.
    fn deactivate(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
-      self.selection.deselect(renderer, paint_canvas);
+      self.deselect_own(renderer, paint_canvas);
    }
 
    /// Processes key shortcuts when the selection is active.
    fn active_key_shortcuts(
       &mut self,
       ToolArgs { input, net, ui, .. }: ToolArgs,
-      _paint_canvas: &mut PaintCanvas,
+      paint_canvas: &mut PaintCanvas,
       _viewport: &Viewport,
    ) -> KeyShortcutAction {
       if input.action(config().keymap.edit.delete) == (true, true) {
          if self.selection.rect.is_some() {
-            self.selection.cancel();
+            self.cancel_own(ui, paint_canvas);
             catch!(
                net.send(self, PeerId::BROADCAST, Packet::Cancel),
                return KeyShortcutAction::None
@@ -352,7 +635,7 @@ impl Tool for SelectionTool {
 
       if input.action(config().keymap.edit.cut) == (true, true) {
          self.copy_to_clipboard(ui);
-         self.selection.cancel();
+         self.cancel_own(ui, paint_canvas);
          return KeyShortcutAction::Success;
       }
 
@@ -366,12 +649,24 @@ impl Tool for SelectionTool {
       paint_canvas: &mut PaintCanvas,
       viewport: &Viewport,
    ) -> KeyShortcutAction {
+      let paste_center = || match config().clipboard.paste_position {
+         PastePosition::Center => viewport.pan(),
+         PastePosition::Cursor => viewport.to_viewport_space(ui.mouse_position(input), ui.size()),
+      };
+
       if input.action(config().keymap.edit.paste) == (true, true) {
          tracing::info!("pasting image from clipboard");
-         self.enqueue_paste_from_clipboard(viewport.pan());
+         self.enqueue_paste_from_clipboard(paste_center());
       }
 
-      if self.poll_paste_from_clipboard(ui, paint_canvas, &net) {
+      // Only one image can be pasted at a time, so when multiple files are dropped, only the
+      // first one is pasted.
+      if let Some(path) = input.dropped_files().first() {
+         tracing::info!("pasting image from {:?}", path);
+         self.enqueue_paste_from_file(paste_center(), path.clone());
+      }
+
+      if self.poll_paste(ui, paint_canvas, &net) {
          return KeyShortcutAction::SwitchToThisTool;
       }
 
@@ -384,6 +679,14 @@ impl Tool for SelectionTool {
       paint_canvas: &mut PaintCanvas,
    ) {
       self.poll_peer_pastes(ui.render(), paint_canvas);
+      self.poll_peer_placements(ui.render(), paint_canvas);
+   }
+
+   /// Keeps redrawing while images are being pasted, and while peers' selections are moving.
+   fn needs_redraw(&self) -> bool {
+      self.paste.is_some()
+         || !self.ongoing_paste_jobs.is_empty()
+         || self.peer_selections.values().any(|peer| peer.is_moving())
    }
 
    /// Processes mouse input.
@@ -391,7 +694,7 @@ impl Tool for SelectionTool {
       &mut self,
       ToolArgs { ui, input, net, .. }: ToolArgs,
       paint_canvas: &mut PaintCanvas,
-      viewport: &Viewport,
+      viewport: &mut Viewport,
    ) {
       // Calculate the mouse position.
       let mouse_position = ui.mouse_position(input);
@@ -401,22 +704,32 @@ impl Tool for SelectionTool {
       // Store the mouse position for the bottom bar display.
       self.mouse_position = mouse_position;
 
-      let handle_radius = Self::HANDLE_RADIUS * 3.0 / viewport.zoom();
+      // Handles are hit-tested and dragged in the selection's own, unrotated space.
+      let local_mouse_position = match self.selection.normalized_rect() {
+         Some(rect) => mouse_position.rotate_around(rect.center(), -self.selection.angle),
+         None => mouse_position,
+      };
+
+      let handle_radius = viewport.to_viewport_length(Self::HANDLE_RADIUS * 3.0);
+      let rotation_handle_distance = viewport.to_viewport_length(Self::ROTATION_HANDLE_DISTANCE);
       self.potential_action = Action::Selecting;
       // Only let the user resize or drag the selection if they aren't doing anything at the moment.
       if matches!(self.action, Action::None | Action::DraggingWhole) {
          if let Some(rect) = self.selection.rect {
+            let rotation_handle = Self::rotation_handle(rect, rotation_handle_distance);
             // Check the handles.
-            if let Some(handle) = Self::hovered_handle(rect, mouse_position, handle_radius) {
+            if let Some(handle) = Self::hovered_handle(rect, local_mouse_position, handle_radius) {
                self.potential_action = Action::DraggingHandle(handle);
+            } else if local_mouse_position.is_in_circle(rotation_handle, handle_radius) {
+               self.potential_action = Action::Rotating;
             } else {
                // Check the inside.
                let rect = Rect::new(
-                  rect.position - vector(4.0, 4.0) / viewport.zoom(),
-                  rect.size + vector(8.0, 8.0) / viewport.zoom(),
+                  rect.position - vector(4.0, 4.0) * viewport.to_viewport_length(1.0),
+                  rect.size + vector(8.0, 8.0) * viewport.to_viewport_length(1.0),
                )
                .sort();
-               if mouse_position.is_in_rect(rect) {
+               if local_mouse_position.is_in_rect(rect) {
                   self.potential_action = Action::DraggingWhole;
                }
             }
@@ -430,7 +743,8 @@ impl Tool for SelectionTool {
             // We process the hovered handles for a second time, because the first time around the
             // rectangle was not sorted.
             if let Some(rect) = self.selection.normalized_rect() {
-               if let Some(handle) = Self::hovered_handle(rect, mouse_position, handle_radius) {
+               if let Some(handle) = Self::hovered_handle(rect, local_mouse_position, handle_radius)
+               {
                   match handle {
                      Handle::Left | Handle::Right => CursorIcon::ColResize,
                      Handle::Top | Handle::Bottom => CursorIcon::RowResize,
@@ -445,18 +759,22 @@ impl Tool for SelectionTool {
             }
          }
          Action::DraggingWhole => CursorIcon::AllScroll,
+         Action::Rotating if self.action == Action::Rotating => CursorIcon::Grabbing,
+         Action::Rotating => CursorIcon::Grab,
       });
 
-      // Check if the left mouse button was pressed, and if so, start selecting.
-      match input.action(MouseButton::Left) {
+      // Check if the draw button was pressed, and if so, start selecting.
+      let draw_button = config().keymap.mouse.draw;
+      match input.action(draw_button) {
          (true, ButtonState::Pressed) => {
             if self.potential_action == Action::Selecting {
                // Before we erase the old data, draw the capture back onto the canvas.
                catch!(self.send_rect_packet(&net));
                catch!(net.send(self, PeerId::BROADCAST, Packet::Deselect));
-               self.selection.deselect(ui, paint_canvas);
+               self.deselect_own(ui, paint_canvas);
                // Anchor the selection to the mouse position.
-               self.selection.begin(mouse_position);
+               self.anchor = grid::snap(mouse_position);
+               self.selection.begin(self.anchor);
                catch!(self.send_rect_packet(&net));
             }
             self.action = self.potential_action;
@@ -465,7 +783,7 @@ impl Tool for SelectionTool {
             // After the button is released and the selection's size is close to 0, deselect.
             if let Some(rect) = self.selection.rect {
                if Self::rect_is_smaller_than_a_pixel(rect) {
-                  self.selection.cancel();
+                  self.cancel_own(ui, paint_canvas);
                   catch!(net.send(self, PeerId::BROADCAST, Packet::Cancel));
                }
             }
@@ -478,8 +796,18 @@ impl Tool for SelectionTool {
                catch!(self.send_rect_packet(&net));
                // If there's still a selection after all of this, capture the paint canvas into an
                // image.
-               self.selection.capture(ui, paint_canvas);
+               self.capture_own(ui, paint_canvas);
                catch!(net.send(self, PeerId::BROADCAST, Packet::Capture));
+               if config().selection.capture == SelectionCapture::Merged
+                  && self.merge_peer_selections(ui)
+               {
+                  // Peers only capture their own canvas, so they have to be sent the merged
+                  // result.
+                  if let Some(image) = self.selection.download_rgba(ui) {
+                     let data = catch!(Self::encode_image(&image));
+                     catch!(net.send(self, PeerId::BROADCAST, Packet::Update(data)));
+                  }
+               }
             }
             self.action = Action::None;
          }
@@ -487,11 +815,23 @@ impl Tool for SelectionTool {
       }
 
       // Perform all the actions.
+      let center = self.selection.normalized_rect().map(|rect| rect.center());
       if let Some(rect) = self.selection.rect.as_mut() {
+         let mouse_position = match self.action {
+            Action::DraggingHandle(_) => local_mouse_position,
+            _ => mouse_position,
+         };
          match self.action {
             Action::None => (),
+            // The modifiers are checked every frame, so letting go of them mid-drag goes back to
+            // marking out a free rectangle.
             Action::Selecting => {
-               rect.size = mouse_position - rect.position;
+               *rect = Self::marked_rect(
+                  self.anchor,
+                  grid::snap(mouse_position),
+                  input.shift_is_down(),
+                  input.alt_is_down(),
+               );
             }
             Action::DraggingHandle(handle) => {
                let new_rect = match handle {
@@ -536,6 +876,15 @@ impl Tool for SelectionTool {
                let delta_position = mouse_position - previous_mouse_position;
                rect.position += delta_position;
             }
+            Action::Rotating => {
+               // An angle of zero means the rotation handle is straight above the center.
+               let direction = mouse_position - center.unwrap_or(mouse_position);
+               let mut angle = direction.x.atan2(-direction.y);
+               if input.shift_is_down() {
+                  angle = (angle / Self::ROTATION_SNAP).round() * Self::ROTATION_SNAP;
+               }
+               self.selection.angle = angle;
+            }
          }
       }
    }
@@ -545,17 +894,22 @@ impl Tool for SelectionTool {
       if let Some(rect) = self.selection.normalized_rect() {
          if !Self::rect_is_smaller_than_a_pixel(rect) {
             ui.draw(|ui| {
-               // Oh my.
-               let top_left = viewport.to_screen_space(rect.top_left(), ui.size()).floor();
-               let top = viewport.to_screen_space(rect.top_center(), ui.size()).floor();
-               let top_right = viewport.to_screen_space(rect.top_right(), ui.size()).floor();
-               let right = viewport.to_screen_space(rect.right_center(), ui.size()).floor();
-               let bottom_right = viewport.to_screen_space(rect.bottom_right(), ui.size()).floor();
-               let bottom = viewport.to_screen_space(rect.bottom_center(), ui.size()).floor();
-               let bottom_left = viewport.to_screen_space(rect.bottom_left(), ui.size()).floor();
-               let left = viewport.to_screen_space(rect.left_center(), ui.size()).floor();
-               let rect = Rect::new(top_left, bottom_right - top_left);
+               let window_size = ui.size();
                let renderer = ui.render();
+               renderer.push();
+               viewport.apply_rotation(renderer, window_size);
+               let rect = viewport.to_unrotated_screen_rect(rect, window_size);
+               // Oh my.
+               let top_left = rect.top_left();
+               let top = rect.top_center().floor();
+               let top_right = rect.top_right();
+               let right = rect.right_center().floor();
+               let bottom_right = rect.bottom_right();
+               let bottom = rect.bottom_center().floor();
+               let bottom_left = rect.bottom_left();
+               let left = rect.left_center().floor();
+               let rotation_handle = Self::rotation_handle(rect, Self::ROTATION_HANDLE_DISTANCE);
+               Self::rotate_around_center(renderer, rect, self.selection.angle);
                if let Some(capture) = self.selection.capture.as_ref() {
                   renderer.framebuffer(rect, capture);
                }
@@ -577,6 +931,9 @@ impl Tool for SelectionTool {
                self.draw_handle(renderer, bottom, Handle::Bottom);
                self.draw_handle(renderer, bottom_left, Handle::BottomLeft);
                self.draw_handle(renderer, left, Handle::Left);
+               renderer.line(top, rotation_handle, Self::COLOR, LineCap::Butt, 2.0);
+               self.draw_handle_for(renderer, rotation_handle, Action::Rotating);
+               renderer.pop();
             });
          }
       }
@@ -595,11 +952,12 @@ impl Tool for SelectionTool {
          if let Some(rect) = peer.lerp_normalized_rect() {
             if !Self::rect_is_smaller_than_a_pixel(rect) {
                ui.draw(|ui| {
-                  let top_left = viewport.to_screen_space(rect.top_left(), ui.size());
-                  let bottom_right = viewport.to_screen_space(rect.bottom_right(), ui.size());
-                  let rect = Rect::new(top_left, bottom_right - top_left);
+                  let window_size = ui.size();
+                  let rect = viewport.to_unrotated_screen_rect(rect, window_size);
+                  let top_left = rect.top_left();
 
                   let nickname = net.peer_name(peer_id).unwrap();
+                  let peer_color = net.peer_color(peer_id).unwrap();
                   let text_width = assets.sans.text_width(nickname);
                   let padding = vector(4.0, 4.0);
                   let text_rect = Rect::new(
@@ -608,12 +966,15 @@ impl Tool for SelectionTool {
                   );
 
                   let renderer = ui.render();
+                  renderer.push();
+                  viewport.apply_rotation(renderer, window_size);
+                  Self::rotate_around_center(renderer, rect, peer.selection.angle);
                   if let Some(framebuffer) = peer.selection.capture.as_ref() {
                      renderer.framebuffer(rect, framebuffer);
                   }
-                  renderer.outline(rect, Self::COLOR, 0.0, 2.0);
+                  renderer.outline(rect, peer_color, 0.0, 2.0);
                   if rect.width() > text_rect.width() && rect.height() > text_rect.height() {
-                     renderer.fill(text_rect, Self::COLOR, 2.0);
+                     renderer.fill(text_rect, peer_color, 2.0);
                      renderer.text(
                         text_rect,
                         &assets.sans,
@@ -622,6 +983,7 @@ impl Tool for SelectionTool {
                         (AlignH::Center, AlignV::Middle),
                      );
                   }
+                  renderer.pop();
                });
             }
          }
@@ -667,6 +1029,7 @@ impl Tool for SelectionTool {
    /// Sends out packets containing the selection rectangle.
    fn network_send(&mut self, net: Net, _: &GlobalControls) -> netcanv::Result<()> {
       self.send_rect_packet(&net)?;
+      self.send_rotation_packet(&net)?;
       Ok(())
    }
 
@@ -697,6 +1060,7 @@ impl Tool for SelectionTool {
             ));
             peer.last_rect_packet = Instant::now();
          }
+         Packet::Rotation(angle) => peer.selection.angle = angle,
          Packet::Capture => peer.selection.capture(renderer, paint_canvas),
          Packet::Cancel => peer.selection.cancel(),
          Packet::Deselect => peer.selection.deselect(renderer, paint_canvas),
@@ -717,6 +1081,19 @@ impl Tool for SelectionTool {
             });
          }
          Packet::Update(data) => peer.selection.upload_rgba(renderer, &Self::decode_image(&data)?),
+         Packet::Place((x, y), data) => {
+            tracing::debug!("{} placed image ({} bytes of data)", sender, data.len());
+            let tx = self.peer_placements_tx.clone();
+            tokio::task::spawn_blocking(move || match Self::decode_image(&data) {
+               Ok(image) => {
+                  let _ = tx.send((point(x, y), image));
+                  // Placements aren't tracked as ongoing jobs, so nothing else wakes the app up
+                  // to put them onto the canvas.
+                  wake::wake();
+               }
+               Err(error) => tracing::error!("could not decode placed image: {:?}", error),
+            });
+         }
       }
       Ok(())
    }
@@ -762,6 +1139,8 @@ struct Selection {
    rect: Option<Rect>,
    capture: Option<Framebuffer>,
    deselected_at: Option<Rect>,
+   /// The rotation of the capture around the center of the rectangle, in radians.
+   angle: f32,
 }
 
 impl std::fmt::Debug for Selection {
@@ -769,6 +1148,7 @@ impl std::fmt::Debug for Selection {
       f.debug_struct("Selection")
          .field("rect", &self.rect)
          .field("deselected_at", &self.deselected_at)
+         .field("angle", &self.angle)
          .finish_non_exhaustive()
    }
 }
@@ -781,12 +1161,14 @@ impl Selection {
          rect: None,
          capture: None,
          deselected_at: None,
+         angle: 0.0,
       }
    }
 
    /// Begins the selection at the given anchor.
    fn begin(&mut self, anchor: Point) {
       self.rect = Some(Rect::new(anchor, vector(0.0, 0.0)));
+      self.angle = 0.0;
       self.rect = self.normalized_rect();
    }
 
@@ -794,12 +1176,8 @@ impl Selection {
    /// paint canvas.
    fn capture(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
       if let Some(rect) = self.rect {
-         let viewport = Viewport::from_top_left(rect);
          let capture = renderer.create_framebuffer(rect.width() as u32, rect.height() as u32);
-         renderer.push();
-         renderer.translate(-rect.position);
-         paint_canvas.capture(renderer, &capture, &viewport);
-         renderer.pop();
+         paint_canvas.capture(renderer, &capture, rect.position);
          self.capture = Some(capture);
          // After the capture is taken, erase the rectangle from the paint canvas.
          paint_canvas.draw(renderer, rect, |renderer| {
@@ -813,16 +1191,24 @@ impl Selection {
    fn cancel(&mut self) {
       self.rect = None;
       self.capture = None;
+      self.angle = 0.0;
    }
 
    /// Finishes the selection, transferring the old rectangle to the given paint canvas.
+   ///
+   /// Only the chunks overlapped by the selection rectangle are modified, so that as little as
+   /// possible has to be re-encoded for sending to other peers.
    fn deselect(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
       self.deselected_at = self.rect;
       if let Some(capture) = self.capture.as_ref() {
          tracing::trace!("deselecting at {:?} with capture", self.rect);
-         if let Some(rect) = self.normalized_rect() {
-            paint_canvas.draw(renderer, rect, |renderer| {
+         if let (Some(rect), Some(bounds)) = (self.normalized_rect(), self.bounds()) {
+            let angle = self.angle;
+            paint_canvas.draw(renderer, bounds, |renderer| {
+               renderer.push();
+               SelectionTool::rotate_around_center(renderer, rect, angle);
                renderer.framebuffer(rect, capture);
+               renderer.pop();
             });
          }
       } else {
@@ -864,24 +1250,16 @@ impl Selection {
       self.capture = Some(capture);
    }
 
-   /// Creates a new selection with the given image capture, at the given origin.
+   /// Creates a new selection with the given image capture, with its top-left corner at the
+   /// given position.
+   ///
+   /// The image must already fit within the maximum selection size.
    fn paste(&mut self, renderer: &mut Backend, position: Option<Point>, image: &RgbaImage) {
       if let Some(position) = position {
-         let rect = Rect::new(
+         self.rect = Some(Rect::new(
             position,
             vector(image.width() as f32, image.height() as f32),
-         );
-
-         // Limit the rectangle to a maximum width (or height) of 1024.
-         // These calculations are performed in order to make the shorter dimension scaled
-         // proportionally to the shorter one.
-         let long_side = rect.width().max(rect.height());
-         let scale = long_side.min(Self::MAX_SIZE as f32) / long_side;
-         let rect = Rect::new(rect.position, rect.size * scale);
-
-         // Center the rectangle on the screen.
-         let rect = Rect::new(rect.position - rect.size / 2.0, rect.size);
-         self.rect = Some(rect);
+         ));
          self.normalize();
       }
       self.upload_rgba(renderer, image);
@@ -904,6 +1282,30 @@ impl Selection {
       })
    }
 
+   /// Returns the rectangle covered by the selection on the canvas, taking its rotation into
+   /// account.
+   fn bounds(&self) -> Option<Rect> {
+      let rect = self.normalized_rect()?.sort();
+      if self.angle == 0.0 {
+         return Some(rect);
+      }
+      let center = rect.center();
+      let corners = [
+         rect.top_left(),
+         rect.top_right(),
+         rect.bottom_right(),
+         rect.bottom_left(),
+      ]
+      .map(|corner| corner.rotate_around(center, self.angle));
+      let (mut min, mut max) = (corners[0], corners[0]);
+      for corner in corners {
+         min = point(min.x.min(corner.x), min.y.min(corner.y));
+         max = point(max.x.max(corner.x), max.y.max(corner.y));
+      }
+      let (min, max) = (min.floor(), point(max.x.ceil(), max.y.ceil()));
+      Some(Rect::new(min, max - min))
+   }
+
    /// Normalizes the selection rectangle, such that the corner names match their visual positions.
    fn normalize(&mut self) {
       self.rect = self.normalized_rect().map(|rect| rect.sort());
@@ -919,8 +1321,7 @@ struct PeerSelection {
 
 impl PeerSelection {
    fn lerp_normalized_rect(&self) -> Option<Rect> {
-      let elapsed = self.last_rect_packet.elapsed().as_millis() as f32;
-      let t = (elapsed / paint::State::TIME_PER_UPDATE.as_millis() as f32).clamp(0.0, 1.0);
+      let t = paint::State::peer_interpolation_factor(self.last_rect_packet);
       self.selection.normalized_rect().map(|mut rect| {
          let previous_rect = self.previous_normalized_rect.unwrap_or(rect);
          rect.position = lerp_point(previous_rect.position, rect.position, t);
@@ -928,6 +1329,14 @@ impl PeerSelection {
          rect
       })
    }
+
+   /// Returns whether the selection is still being interpolated towards its most recent
+   /// rectangle.
+   fn is_moving(&self) -> bool {
+      let previous = self.previous_normalized_rect.map(|rect| (rect.position, rect.size));
+      let current = self.selection.normalized_rect().map(|rect| (rect.position, rect.size));
+      previous != current && self.last_rect_packet.elapsed() < paint::State::TIME_PER_UPDATE
+   }
 }
 
 /// A network packet for the selection tool.
@@ -944,10 +1353,18 @@ enum Packet {
    Cancel,
    /// Invoke [`Selection::deselect`].
    Deselect,
-   /// Paste an image at the provided origin, starting a new selection.
+   /// Paste an image centered on the provided point, starting a new selection. The image never
+   /// exceeds the maximum selection size.
    Paste((f32, f32), Vec<u8>),
    /// Update the captured image.
    Update(Vec<u8>),
+   /// Draw an image onto the canvas with its top-left corner at the provided point, without
+   /// selecting it. Sent for all but the last tile of images pasted at their original size.
+   Place((f32, f32), Vec<u8>),
+   /// The rotation of the captured image around the center of the selection, in radians. Only
+   /// sent for rotated selections, or once one is rotated back, such that peers without support
+   /// for rotation only miss out on it when it's actually used.
+   Rotation(f32),
 }
 
 impl Debug for Packet {
@@ -961,6 +1378,8 @@ impl Debug for Packet {
          Packet::Deselect => write!(f, "Deselect"),
          Packet::Paste((_, _), _) => write!(f, "Paste"),
          Packet::Update(_) => write!(f, "Update"),
+         Packet::Place((_, _), _) => write!(f, "Place"),
+         Packet::Rotation(angle) => f.debug_tuple("Rotation").field(angle).finish(),
       }
    }
 }
@@ -972,3 +1391,92 @@ fn format_vector(vector: Vector) -> String {
 fn label_width(font: &Font, text: &str) -> f32 {
    font.text_width(text).max(96.0)
 }
+
+#[cfg(test)]
+mod tests {
+   use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+   use super::*;
+
+   fn selection(rect: Rect, angle: f32) -> Selection {
+      Selection {
+         rect: Some(rect),
+         angle,
+         ..Selection::new()
+      }
+   }
+
+   #[test]
+   fn unrotated_bounds_are_the_sorted_rect() {
+      let selection = selection(Rect::new(point(20.0, 10.0), vector(-20.0, -10.0)), 0.0);
+      assert_eq!(
+         selection.bounds(),
+         Some(Rect::new(point(0.0, 0.0), vector(20.0, 10.0)))
+      );
+   }
+
+   #[test]
+   fn rotated_bounds_cover_the_rotated_rect() {
+      let rect = Rect::new(point(0.0, 0.0), vector(20.0, 10.0));
+      assert_eq!(
+         selection(rect, FRAC_PI_2).bounds(),
+         Some(Rect::new(point(5.0, -5.0), vector(10.0, 20.0)))
+      );
+      // Corners that don't land on whole pixels are rounded outwards.
+      assert_eq!(
+         selection(rect, FRAC_PI_4).bounds(),
+         Some(Rect::new(point(-1.0, -6.0), vector(22.0, 22.0)))
+      );
+   }
+
+   #[test]
+   fn marked_rects_can_be_squares_grown_from_the_center() {
+      let anchor = point(10.0, 10.0);
+      let mouse = point(30.0, 15.0);
+      let marked = |mouse, square, from_center| {
+         SelectionTool::marked_rect(anchor, mouse, square, from_center)
+      };
+      assert_eq!(
+         marked(mouse, false, false),
+         Rect::new(point(10.0, 10.0), vector(20.0, 5.0))
+      );
+      assert_eq!(
+         marked(mouse, true, false),
+         Rect::new(point(10.0, 10.0), vector(20.0, 20.0))
+      );
+      // The square keeps following the mouse when dragging up or to the left.
+      assert_eq!(
+         marked(point(0.0, 25.0), true, false),
+         Rect::new(point(10.0, 10.0), vector(-15.0, 15.0))
+      );
+      assert_eq!(
+         marked(mouse, false, true),
+         Rect::new(point(-10.0, 5.0), vector(40.0, 10.0))
+      );
+      assert_eq!(
+         marked(mouse, true, true),
+         Rect::new(point(-10.0, -10.0), vector(40.0, 40.0))
+      );
+   }
+
+   #[test]
+   fn dropped_image_files_are_loaded() {
+      let directory = tempfile::tempdir().unwrap();
+      // The format is guessed from the contents rather than the extension.
+      let path = directory.path().join("drawing.jpg");
+      let image = RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+      image.save_with_format(&path, ImageFormat::Png).unwrap();
+      assert_eq!(SelectionTool::load_image_file(&path).unwrap(), image);
+   }
+
+   #[test]
+   fn dropped_files_that_are_not_images_are_reported() {
+      let directory = tempfile::tempdir().unwrap();
+      let path = directory.path().join("notes.txt");
+      std::fs::write(&path, "not an image").unwrap();
+      assert!(matches!(
+         SelectionTool::load_image_file(&path),
+         Err(Error::UnsupportedImageFile { file }) if file == "notes.txt"
+      ));
+   }
+}
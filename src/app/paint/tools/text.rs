@@ -0,0 +1,630 @@
+//! The Text tool. Places text onto the canvas.
+//!
+//! Clicking on the canvas opens a text box at the clicked point, with a field for typing the text
+//! right underneath it. Return starts a new line, and the box can be moved around by dragging it
+//! with the left mouse button. Ctrl+Return, clicking outside of the box, or switching to another
+//! tool places the text onto the canvas; Escape discards it.
+//!
+//! Placed text is baked into pixels before it's sent to other peers, so that they see exactly the
+//! same text no matter which fonts they have. The font itself can be changed in the lobby.
+
+use image::RgbaImage;
+use netcanv_protocol::relay::PeerId;
+use netcanv_renderer::paws::{
+   point, vector, AlignH, AlignV, Color, Layout, Point, Rect, Renderer, Vector,
+};
+use netcanv_renderer::{BlendMode, Font as FontTrait, RenderBackend};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::assets::Assets;
+use crate::backend::winit::window::CursorIcon;
+use crate::backend::{Backend, Font, Framebuffer, Image};
+use crate::common::{deserialize_bincode, VectorMath};
+use crate::config::config;
+use crate::keymap::KeyBinding;
+use crate::paint_canvas::PaintCanvas;
+use crate::strings::TextAlignmentStrings;
+use crate::ui::{
+   view, Button, ButtonArgs, ButtonColors, ButtonState, ColorPicker, ColorPickerArgs, Focus,
+   Slider, SliderArgs, SliderStep, TextField, TextFieldArgs, UiElements, VirtualKeyCode,
+};
+use crate::viewport::Viewport;
+
+use super::{Net, SelectionTool, Tool, ToolArgs};
+
+/// How the lines of text are aligned relative to each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum TextAlignment {
+   #[default]
+   Left,
+   Center,
+   Right,
+}
+
+impl TextAlignment {
+   const ALL: [TextAlignment; 3] = [Self::Left, Self::Center, Self::Right];
+
+   fn align_h(self) -> AlignH {
+      match self {
+         Self::Left => AlignH::Left,
+         Self::Center => AlignH::Center,
+         Self::Right => AlignH::Right,
+      }
+   }
+
+   fn name(self, strings: &TextAlignmentStrings) -> &str {
+      match self {
+         Self::Left => &strings.left,
+         Self::Center => &strings.center,
+         Self::Right => &strings.right,
+      }
+   }
+}
+
+/// The text tool's settings, saved between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct TextSettings {
+   /// The font size, in pixels.
+   size: f32,
+   alignment: TextAlignment,
+}
+
+impl Default for TextSettings {
+   fn default() -> Self {
+      Self {
+         size: TextTool::DEFAULT_SIZE,
+         alignment: TextAlignment::Left,
+      }
+   }
+}
+
+/// What should happen to the text being typed once the next frame is processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Finish {
+   Place,
+   Discard,
+}
+
+/// Everything the look of rendered text depends on.
+#[derive(PartialEq)]
+struct TextLook {
+   lines: Vec<String>,
+   size: f32,
+   color: Color,
+   alignment: TextAlignment,
+}
+
+/// Text that's still being typed, and hasn't been placed onto the canvas yet.
+struct PendingText {
+   /// The top-left corner of the text, in viewport space. This always lies on a pixel boundary.
+   position: Point,
+   /// The lines finished by pressing Return.
+   lines: Vec<String>,
+   /// The field the last line is typed into.
+   field: TextField,
+   font: Font,
+   color: Color,
+   alignment: TextAlignment,
+   /// The offset of the mouse from the text's position, while the text is being dragged around.
+   grab: Option<Vector>,
+   finish: Option<Finish>,
+   /// The text rendered to pixels, along with what it was rendered from. The text is only
+   /// rendered again once its look changes.
+   rendered: Option<(TextLook, Framebuffer)>,
+}
+
+impl PendingText {
+   /// Returns all the lines of text, including the one that's still being typed.
+   fn lines(&self) -> impl Iterator<Item = &str> {
+      self.lines.iter().map(|line| line.as_str()).chain(std::iter::once(self.field.text()))
+   }
+
+   /// Returns whether there's no text to place.
+   fn is_empty(&self) -> bool {
+      self.lines().all(|line| line.is_empty())
+   }
+
+   /// Returns the size of the rendered text, in pixels.
+   fn size(&self) -> (u32, u32) {
+      let width = self.lines().map(|line| self.font.text_width(line)).fold(0.0, f32::max);
+      let height = self.font.height() * (self.lines.len() + 1) as f32;
+      ((width.ceil() as u32).max(1), (height.ceil() as u32).max(1))
+   }
+
+   /// Returns the rectangle covered by the text, in viewport space.
+   fn rect(&self) -> Rect {
+      let (width, height) = self.size();
+      Rect::new(self.position, vector(width as f32, height as f32))
+   }
+
+   /// Renders the text into a framebuffer, unless it's been rendered already.
+   fn render(&mut self, renderer: &mut Backend) -> &Framebuffer {
+      let look = TextLook {
+         lines: self.lines().map(String::from).collect(),
+         size: self.font.size(),
+         color: self.color,
+         alignment: self.alignment,
+      };
+      if self.rendered.as_ref().map(|(rendered_look, _)| rendered_look) != Some(&look) {
+         let (width, height) = self.size();
+         tracing::trace!(size = look.size, width, height, "rendering text");
+         let line_height = self.font.height();
+         let framebuffer = renderer.create_framebuffer(width, height);
+         renderer.draw_to(&framebuffer, |renderer| {
+            for (i, line) in look.lines.iter().enumerate() {
+               let rect = Rect::new(
+                  point(0.0, i as f32 * line_height),
+                  vector(width as f32, line_height),
+               );
+               renderer.text(
+                  rect,
+                  &self.font,
+                  line,
+                  look.color,
+                  (look.alignment.align_h(), AlignV::Middle),
+               );
+            }
+         });
+         self.rendered = Some((look, framebuffer));
+      }
+      &self.rendered.as_ref().unwrap().1
+   }
+}
+
+/// Text baked into pixels, placed onto the canvas with its top-left corner at the given position.
+struct BakedText {
+   position: (i32, i32),
+   image: RgbaImage,
+}
+
+pub struct TextTool {
+   icon: Image,
+   size_slider: Slider,
+   alignment: TextAlignment,
+   pending: Option<PendingText>,
+   /// The rectangle of the pending text's field, in screen space. Clicks inside of it are left to
+   /// the field.
+   field_rect: Option<Rect>,
+
+   /// Text placed by the user, encoded and ready to be sent to other peers.
+   encoded_texts_tx: mpsc::UnboundedSender<((i32, i32), Vec<u8>)>,
+   encoded_texts_rx: mpsc::UnboundedReceiver<((i32, i32), Vec<u8>)>,
+   /// Text received from other peers, decoded and ready to be placed onto the canvas.
+   peer_texts_tx: mpsc::UnboundedSender<BakedText>,
+   peer_texts_rx: mpsc::UnboundedReceiver<BakedText>,
+   /// The number of texts still being encoded or decoded.
+   ongoing_jobs: usize,
+}
+
+impl TextTool {
+   const DEFAULT_SIZE: f32 = 24.0;
+   const MIN_SIZE: f32 = 8.0;
+   const MAX_SIZE: f32 = 128.0;
+   /// The width of the field the text is typed into.
+   const FIELD_WIDTH: f32 = 320.0;
+   /// The color of the outline around the pending text.
+   const OUTLINE_COLOR: Color = Color::rgb(0x0397fb);
+
+   /// Creates an instance of the text tool.
+   pub fn new(renderer: &mut Backend) -> Self {
+      let (encoded_texts_tx, encoded_texts_rx) = mpsc::unbounded_channel();
+      let (peer_texts_tx, peer_texts_rx) = mpsc::unbounded_channel();
+      Self {
+         icon: Assets::load_svg(renderer, include_bytes!("../../../assets/icons/text.svg")),
+         size_slider: Slider::new(
+            Self::DEFAULT_SIZE,
+            Self::MIN_SIZE,
+            Self::MAX_SIZE,
+            SliderStep::Discrete(1.0),
+         ),
+         alignment: TextAlignment::Left,
+         pending: None,
+         field_rect: None,
+         encoded_texts_tx,
+         encoded_texts_rx,
+         peer_texts_tx,
+         peer_texts_rx,
+         ongoing_jobs: 0,
+      }
+   }
+
+   /// Starts typing new text, with its top-left corner at the given point.
+   fn begin(&mut self, position: Point, assets: &Assets, color: Color) {
+      let mut field = TextField::new(None);
+      field.set_focus(true);
+      self.pending = Some(PendingText {
+         position: position.floor(),
+         lines: Vec::new(),
+         field,
+         font: assets.text.with_size(self.size_slider.value()),
+         color,
+         alignment: self.alignment,
+         grab: None,
+         finish: None,
+         rendered: None,
+      });
+   }
+
+   /// Bakes the pending text into the paint canvas, and queues it up to be sent to other peers.
+   fn place(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      self.field_rect = None;
+      let Some(mut text) = self.pending.take() else {
+         return;
+      };
+      if text.is_empty() {
+         return;
+      }
+
+      let rect = text.rect();
+      let (width, height) = text.size();
+      let framebuffer = text.render(renderer);
+      paint_canvas.begin_history_step();
+      paint_canvas.record_history(renderer, rect);
+      paint_canvas.draw(renderer, rect, |renderer| {
+         renderer.set_blend_mode(BlendMode::Alpha);
+         renderer.framebuffer(rect, framebuffer);
+      });
+      paint_canvas.finish_history_step(renderer);
+
+      let mut image = RgbaImage::new(width, height);
+      renderer.download_framebuffer(framebuffer, (0, 0), (width, height), &mut image);
+      let position = (rect.position.x as i32, rect.position.y as i32);
+      let tx = self.encoded_texts_tx.clone();
+      self.ongoing_jobs += 1;
+      tokio::task::spawn_blocking(move || {
+         let data = catch!(SelectionTool::encode_image(&image));
+         let _ = tx.send((position, data));
+      });
+   }
+
+   /// Draws text placed by a peer onto the paint canvas.
+   fn place_baked(renderer: &mut Backend, paint_canvas: &mut PaintCanvas, text: &BakedText) {
+      let (width, height) = text.image.dimensions();
+      let framebuffer = renderer.create_framebuffer(width, height);
+      renderer.upload_framebuffer(&framebuffer, (0, 0), (width, height), &text.image);
+      let rect = Rect::new(
+         point(text.position.0 as f32, text.position.1 as f32),
+         vector(width as f32, height as f32),
+      );
+      paint_canvas.draw(renderer, rect, |renderer| {
+         renderer.set_blend_mode(BlendMode::Alpha);
+         renderer.framebuffer(rect, &framebuffer);
+      });
+   }
+}
+
+impl Tool for TextTool {
+   fn name(&self) -> &'static str {
+      "text"
+   }
+
+   fn icon(&self) -> &Image {
+      &self.icon
+   }
+
+   fn key_shortcut(&self) -> KeyBinding {
+      config().keymap.tools.text
+   }
+
+   fn save_settings(&self) -> Option<toml::Value> {
+      let settings = TextSettings {
+         size: self.size_slider.value(),
+         alignment: self.alignment,
+      };
+      toml::Value::try_from(settings).ok()
+   }
+
+   fn load_settings(&mut self, settings: toml::Value) {
+      match settings.try_into::<TextSettings>() {
+         Ok(settings) => {
+            self.size_slider.set_value(settings.size);
+            self.alignment = settings.alignment;
+         }
+         Err(error) => tracing::warn!("ignoring invalid text settings: {}", error),
+      }
+   }
+
+   /// Places the text that's being typed, so that it isn't lost.
+   fn deactivate(&mut self, renderer: &mut Backend, paint_canvas: &mut PaintCanvas) {
+      self.place(renderer, paint_canvas);
+   }
+
+   fn captures_keyboard(&self) -> bool {
+      self.pending.is_some()
+   }
+
+   /// Sends out the user's encoded text, and places the text received from peers.
+   fn process_background_jobs(
+      &mut self,
+      ToolArgs { ui, net, .. }: ToolArgs,
+      paint_canvas: &mut PaintCanvas,
+   ) {
+      while let Ok((position, data)) = self.encoded_texts_rx.try_recv() {
+         self.ongoing_jobs -= 1;
+         catch!(net.send(self, PeerId::BROADCAST, Packet::Text(position, data)));
+      }
+      while let Ok(text) = self.peer_texts_rx.try_recv() {
+         self.ongoing_jobs -= 1;
+         Self::place_baked(ui.render(), paint_canvas, &text);
+      }
+   }
+
+   /// Keeps redrawing while text is being typed, so that the caret blinks, and while text is
+   /// being encoded or decoded.
+   fn needs_redraw(&self) -> bool {
+      self.pending.is_some() || self.ongoing_jobs > 0
+   }
+
+   /// Opens new text boxes, drags them around, and places them onto the canvas.
+   fn process_paint_canvas_input(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         assets,
+         global_controls,
+         ..
+      }: ToolArgs,
+      paint_canvas: &mut PaintCanvas,
+      viewport: &mut Viewport,
+   ) {
+      // The text follows the settings on the bottom bar until it's placed.
+      let color = global_controls.color_picker.color();
+      let mut finish = None;
+      if let Some(text) = &mut self.pending {
+         let size = self.size_slider.value();
+         if text.font.size() != size {
+            text.font = assets.text.with_size(size);
+         }
+         text.color = color;
+         text.alignment = self.alignment;
+         finish = text.finish;
+      }
+      match finish {
+         Some(Finish::Place) => {
+            self.place(ui.render(), paint_canvas);
+            global_controls.color_picker.remember_color();
+         }
+         Some(Finish::Discard) => {
+            self.pending = None;
+            self.field_rect = None;
+         }
+         None => (),
+      }
+
+      if !input.mouse_active()
+         || self.field_rect.is_some_and(|rect| input.mouse_position().is_in_rect(rect))
+      {
+         return;
+      }
+      let mouse_position = viewport.to_viewport_space(input.mouse_position(), ui.size());
+      let over_text =
+         self.pending.as_ref().is_some_and(|text| mouse_position.is_in_rect(text.rect()));
+
+      let draw_button = config().keymap.mouse.draw;
+      match input.action(draw_button) {
+         (true, ButtonState::Pressed) => {
+            if let Some(text) = self.pending.as_mut().filter(|_| over_text) {
+               text.grab = Some(mouse_position - text.position);
+            } else if self.pending.is_some() {
+               self.place(ui.render(), paint_canvas);
+               global_controls.color_picker.remember_color();
+            } else if paint_canvas.point_is_in_bounds(mouse_position) {
+               self.begin(mouse_position, assets, color);
+            }
+         }
+         (_, ButtonState::Released) => {
+            if let Some(text) = &mut self.pending {
+               text.grab = None;
+            }
+         }
+         _ => (),
+      }
+
+      let grab = self.pending.as_ref().and_then(|text| text.grab);
+      if let (Some(text), Some(grab)) = (&mut self.pending, grab) {
+         text.position = (mouse_position - grab).floor();
+      }
+      input.set_cursor(if grab.is_some() || over_text {
+         CursorIcon::AllScroll
+      } else {
+         CursorIcon::Text
+      });
+   }
+
+   /// Shows the pending text along with the field it's typed into.
+   fn process_paint_canvas_overlays(
+      &mut self,
+      ToolArgs {
+         ui, input, assets, ..
+      }: ToolArgs,
+      viewport: &Viewport,
+   ) {
+      let Some(text) = &mut self.pending else {
+         return;
+      };
+      let rect = text.rect();
+      ui.draw(|ui| {
+         let window_size = ui.size();
+         let renderer = ui.render();
+         let framebuffer = text.render(renderer);
+         renderer.push();
+         viewport.apply_rotation(renderer, window_size);
+         let rect = viewport.to_unrotated_screen_rect(rect, window_size);
+         renderer.framebuffer(rect, framebuffer);
+         renderer.outline(rect, Self::OUTLINE_COLOR, 0.0, 1.0);
+         renderer.pop();
+      });
+
+      let field_position =
+         viewport.to_screen_space(rect.bottom_left(), ui.size()) + vector(0.0, 8.0);
+      let field_size = vector(Self::FIELD_WIDTH, TextField::height(&assets.sans));
+      ui.push((0.0, 0.0), Layout::Freeform);
+      ui.set_position(field_position);
+      // Clicks elsewhere on the canvas would unfocus the field, while the text is still open for
+      // typing.
+      text.field.set_focus(true);
+      let field = text.field.process(
+         ui,
+         input,
+         TextFieldArgs {
+            width: field_size.x,
+            colors: &assets.colors.text_field,
+            hint: Some(assets.tr.text_hint.as_str()),
+            font: &assets.sans,
+         },
+      );
+      ui.pop();
+      self.field_rect = Some(Rect::new(field_position, field_size));
+
+      if field.done() {
+         if input.ctrl_is_down() {
+            text.finish = Some(Finish::Place);
+         } else {
+            text.lines.push(text.field.text().to_owned());
+            text.field.set_text(String::new());
+         }
+      }
+      if input.key_just_typed(VirtualKeyCode::Escape) {
+         text.finish = Some(Finish::Discard);
+      }
+   }
+
+   /// Shows the color palette, the font size slider, and the alignment buttons.
+   fn process_bottom_bar(
+      &mut self,
+      ToolArgs {
+         ui,
+         input,
+         assets,
+         wm,
+         canvas_view,
+         global_controls,
+         ..
+      }: ToolArgs,
+   ) {
+      let mut picker_window = ColorPicker::picker_window_view();
+      view::layout::align(
+         &view::layout::padded(canvas_view, 16.0),
+         &mut picker_window,
+         (AlignH::Left, AlignV::Bottom),
+      );
+      global_controls.color_picker.process(
+         ui,
+         input,
+         ColorPickerArgs {
+            assets,
+            wm,
+            window_view: picker_window,
+            show_eraser: false,
+         },
+      );
+      ui.space(16.0);
+
+      ui.horizontal_label(&assets.sans, &assets.tr.text_size, assets.colors.text, None);
+      ui.space(16.0);
+      ui.push((192.0, ui.height()), Layout::Freeform);
+      self.size_slider.process(
+         ui,
+         input,
+         SliderArgs {
+            width: ui.width(),
+            color: assets.colors.slider,
+            focus_outline: assets.colors.focus_outline,
+         },
+      );
+      ui.pop();
+      ui.space(8.0);
+      ui.horizontal_label(
+         &assets.sans_bold,
+         &self.size_slider.value().to_string(),
+         assets.colors.text,
+         Some((40.0, AlignH::Center)),
+      );
+      ui.space(16.0);
+
+      let strings = &assets.tr.text_alignment;
+      for (i, alignment) in TextAlignment::ALL.into_iter().enumerate() {
+         if i > 0 {
+            ui.space(4.0);
+         }
+         let colors = ButtonColors::toggle(
+            self.alignment == alignment,
+            &assets.colors.radio_button.normal,
+            &assets.colors.radio_button.selected,
+         );
+         if Button::with_text(
+            ui,
+            input,
+            &ButtonArgs::new(ui, colors).height(24.0).pill(),
+            &assets.sans,
+            alignment.name(strings),
+         )
+         .clicked()
+         {
+            self.alignment = alignment;
+         }
+      }
+   }
+
+   /// Decodes text placed by other peers.
+   fn network_receive(
+      &mut self,
+      _renderer: &mut Backend,
+      _net: Net,
+      _paint_canvas: &mut PaintCanvas,
+      sender: PeerId,
+      payload: Vec<u8>,
+   ) -> netcanv::Result<()> {
+      let packet = deserialize_bincode(&payload)?;
+      match packet {
+         Packet::Text(position, data) => {
+            tracing::debug!("{} placed text ({} bytes of data)", sender, data.len());
+            let tx = self.peer_texts_tx.clone();
+            self.ongoing_jobs += 1;
+            tokio::task::spawn_blocking(move || match SelectionTool::decode_image(&data) {
+               Ok(image) => {
+                  let _ = tx.send(BakedText { position, image });
+               }
+               Err(error) => tracing::error!("could not decode placed text: {:?}", error),
+            });
+         }
+      }
+      Ok(())
+   }
+}
+
+/// A network packet for the text tool.
+#[derive(Serialize, Deserialize)]
+enum Packet {
+   /// Text was placed. Contains the position of the text's top-left corner, and the text rendered
+   /// to an image, encoded as PNG.
+   Text((i32, i32), Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn missing_settings_are_defaulted() {
+      let settings: TextSettings = toml::from_str("size = 32.0").unwrap();
+      assert_eq!(settings.size, 32.0);
+      assert_eq!(settings.alignment, TextAlignment::Left);
+
+      let settings: TextSettings = toml::from_str(r#"alignment = "Right""#).unwrap();
+      assert_eq!(settings.size, TextTool::DEFAULT_SIZE);
+      assert_eq!(settings.alignment, TextAlignment::Right);
+   }
+
+   #[test]
+   fn alignments_map_to_their_own_side() {
+      let sides = [AlignH::Left, AlignH::Center, AlignH::Right];
+      for (alignment, side) in TextAlignment::ALL.into_iter().zip(sides) {
+         // `AlignH` doesn't implement `Debug`, so `assert_eq!` can't be used here.
+         assert!(alignment.align_h() == side, "{alignment:?}");
+      }
+   }
+}
@@ -20,6 +20,14 @@ pub trait AppState {
    /// _processing_ in the codebase.
    fn process(&mut self, args: StateArgs);
 
+   /// Returns whether the next frame should be processed right away.
+   ///
+   /// When this returns `false`, the app sleeps until input arrives or something [wakes it
+   /// up][crate::wake]. States should return `true` while something is animating or loading.
+   fn needs_redraw(&self) -> bool {
+      false
+   }
+
    /// Returns the next state after this one.
    ///
    /// If no state transitions should occur, this should simply return `self`. Otherwise, another
@@ -2,19 +2,22 @@
 
 use std::io::{Cursor, Write};
 use std::ops::Deref;
+use std::path::Path;
 
 use netcanv_i18n::from_language::FromLanguage;
 use netcanv_i18n::Language;
 use netcanv_renderer::paws::Color;
-use netcanv_renderer::{Image as ImageTrait, RenderBackend};
+use netcanv_renderer::{Font as FontTrait, Image as ImageTrait, RenderBackend};
+use nysa::global as bus;
 use serde::de::Visitor;
 use serde::Deserialize;
 use url::Url;
 
 use crate::app::lobby::LobbyColors;
+use crate::app::paint::connection_status::ConnectionStatusColors;
 use crate::app::paint::tool_bar::ToolbarColors;
 use crate::backend::{Backend, Font, Image};
-use crate::config::config;
+use crate::config::{self, config};
 use crate::strings::Strings;
 use crate::ui::wm::windows::{WindowButtonColors, WindowButtonsColors};
 use crate::ui::{
@@ -44,13 +47,16 @@ const ERASER_SVG: &[u8] = include_bytes!("assets/icons/eraser.svg");
 const MENU_SVG: &[u8] = include_bytes!("assets/icons/menu.svg");
 const COPY_SVG: &[u8] = include_bytes!("assets/icons/copy.svg");
 const DRAG_HORIZONTAL_SVG: &[u8] = include_bytes!("assets/icons/drag-horizontal.svg");
+const DRAG_VERTICAL_SVG: &[u8] = include_bytes!("assets/icons/drag-vertical.svg");
 const INFO_SVG: &[u8] = include_bytes!("assets/icons/info.svg");
 const ERROR_SVG: &[u8] = include_bytes!("assets/icons/error.svg");
+const CONNECTION_SVG: &[u8] = include_bytes!("assets/icons/connection.svg");
 const PEER_CLIENT_SVG: &[u8] = include_bytes!("assets/icons/peer-client.svg");
 const PEER_HOST_SVG: &[u8] = include_bytes!("assets/icons/peer-host.svg");
 const DARK_MODE_SVG: &[u8] = include_bytes!("assets/icons/dark-mode.svg");
 const LIGHT_MODE_SVG: &[u8] = include_bytes!("assets/icons/light-mode.svg");
 const TRANSLATE_SVG: &[u8] = include_bytes!("assets/icons/translate.svg");
+const FONT_SVG: &[u8] = include_bytes!("assets/icons/font.svg");
 const LEGAL_SVG: &[u8] = include_bytes!("assets/icons/legal.svg");
 const WINDOW_CLOSE_SVG: &[u8] = include_bytes!("assets/icons/window-close.svg");
 const WINDOW_PIN_SVG: &[u8] = include_bytes!("assets/icons/window-pin.svg");
@@ -93,12 +99,14 @@ pub struct NavigationIcons {
    pub menu: Image,
    pub copy: Image,
    pub drag_horizontal: Image,
+   pub drag_vertical: Image,
 }
 
 /// Icons for status messages.
 pub struct StatusIcons {
    pub info: Image,
    pub error: Image,
+   pub connection: Image,
 }
 
 /// Icons for peer roles.
@@ -112,6 +120,7 @@ pub struct LobbyIcons {
    pub dark_mode: Image,
    pub light_mode: Image,
    pub translate: Image,
+   pub font: Image,
    pub legal: Image,
 }
 
@@ -146,6 +155,8 @@ pub struct Assets {
    pub sans: Font,
    pub sans_bold: Font,
    pub monospace: Font,
+   /// The font text placed with the text tool is rendered with.
+   pub text: Font,
 
    pub colors: ColorScheme,
    pub icons: Icons,
@@ -185,6 +196,50 @@ impl Assets {
       renderer.create_image_from_rgba(image.width(), image.height(), &image)
    }
 
+   /// Loads one of the fonts bundled into the executable.
+   fn load_bundled_font(renderer: &mut Backend, data: &[u8]) -> Font {
+      renderer.create_font_from_memory(data, 14.0).expect("the bundled font is not a valid font")
+   }
+
+   /// Loads a custom font from the given file, relative to the fonts directory.
+   ///
+   /// Returns `None` if no file is given, or if the font can't be loaded. In the latter case
+   /// a warning is logged, such that the caller can fall back to a bundled font.
+   fn load_custom_font(renderer: &mut Backend, file: Option<&Path>) -> Option<Font> {
+      let path = config::fonts_dir().join(file?);
+      let data = match std::fs::read(&path) {
+         Ok(data) => data,
+         Err(error) => {
+            tracing::warn!("cannot read font file {:?}: {}", path, error);
+            return None;
+         }
+      };
+      let font = renderer.create_font_from_memory(&data, 14.0);
+      if font.is_none() {
+         tracing::warn!("{:?} does not contain a valid font", path);
+      }
+      font
+   }
+
+   /// Loads the regular and bold interface fonts. A custom interface font, if picked in the
+   /// config, is used for both.
+   fn load_ui_fonts(renderer: &mut Backend) -> (Font, Font) {
+      match Self::load_custom_font(renderer, config().fonts.ui.as_deref()) {
+         Some(font) => (font.with_size(14.0), font),
+         None => (
+            Self::load_bundled_font(renderer, SANS_TTF),
+            Self::load_bundled_font(renderer, SANS_BOLD_TTF),
+         ),
+      }
+   }
+
+   /// Loads the text tool's font, falling back to the regular interface font bundled into the
+   /// executable.
+   fn load_text_font(renderer: &mut Backend) -> Font {
+      Self::load_custom_font(renderer, config().fonts.text.as_deref())
+         .unwrap_or_else(|| Self::load_bundled_font(renderer, SANS_TTF))
+   }
+
    /// Loads the mapping from language names to language codes.
    fn load_languages() -> LanguageCodes {
       const LANGUAGE_NAMES_TOML: &str = include_str!("assets/i18n/language-names.toml");
@@ -221,10 +276,12 @@ impl Assets {
 
       let language = Self::load_language(None)?;
       let tr = Strings::from_language(&language);
+      let (sans, sans_bold) = Self::load_ui_fonts(renderer);
       Ok(Self {
-         sans: renderer.create_font_from_memory(SANS_TTF, 14.0),
-         sans_bold: renderer.create_font_from_memory(SANS_BOLD_TTF, 14.0),
-         monospace: renderer.create_font_from_memory(MONOSPACE_TTF, 14.0),
+         sans,
+         sans_bold,
+         monospace: Self::load_bundled_font(renderer, MONOSPACE_TTF),
+         text: Self::load_text_font(renderer),
 
          colors,
          icons: Icons {
@@ -239,16 +296,19 @@ impl Assets {
                dark_mode: Self::load_svg(renderer, DARK_MODE_SVG),
                light_mode: Self::load_svg(renderer, LIGHT_MODE_SVG),
                translate: Self::load_svg(renderer, TRANSLATE_SVG),
+               font: Self::load_svg(renderer, FONT_SVG),
                legal: Self::load_svg(renderer, LEGAL_SVG),
             },
             navigation: NavigationIcons {
                menu: Self::load_svg(renderer, MENU_SVG),
                copy: Self::load_svg(renderer, COPY_SVG),
                drag_horizontal: Self::load_svg(renderer, DRAG_HORIZONTAL_SVG),
+               drag_vertical: Self::load_svg(renderer, DRAG_VERTICAL_SVG),
             },
             status: StatusIcons {
                info: Self::load_svg(renderer, INFO_SVG),
                error: Self::load_svg(renderer, ERROR_SVG),
+               connection: Self::load_svg(renderer, CONNECTION_SVG),
             },
             peer: PeerIcons {
                client: Self::load_svg(renderer, PEER_CLIENT_SVG),
@@ -290,6 +350,12 @@ impl Assets {
       self.tr = tr;
       Ok(())
    }
+
+   /// Reloads the fonts picked in the config file.
+   pub fn reload_fonts(&mut self, renderer: &mut Backend) {
+      (self.sans, self.sans_bold) = Self::load_ui_fonts(renderer);
+      self.text = Self::load_text_font(renderer);
+   }
 }
 
 pub struct LanguageCodes(Vec<(String, String)>);
@@ -345,6 +411,9 @@ struct CommonColors {
    red_10: Color,
    red_30: Color,
 
+   yellow_50: Color,
+   green_50: Color,
+
    blue_30: Color,
    blue_50: Color,
    blue_70: Color,
@@ -366,6 +435,9 @@ impl CommonColors {
          red_10: Color::rgb(0x3d0011),
          red_30: Color::rgb(0x7d0023),
 
+         yellow_50: Color::rgb(0xd39e00),
+         green_50: Color::rgb(0x2e9e44),
+
          blue_30: Color::rgb(0x007ccf),
          blue_50: Color::rgb(0x0397fb),
          blue_70: Color::rgb(0x32aafa),
@@ -374,6 +446,30 @@ impl CommonColors {
       }
    }
 
+   /// The common colors for the high contrast theme.
+   fn high_contrast() -> Self {
+      Self {
+         gray_00: Color::WHITE,
+         gray_20: Color::WHITE,
+         gray_50: Color::WHITE,
+         gray_60: Color::rgb(0xbfbfbf),
+         gray_80: Color::BLACK,
+         gray_90: Color::BLACK,
+
+         red_10: Color::rgb(0xff4f4f),
+         red_30: Color::rgb(0xff8f8f),
+
+         yellow_50: Color::rgb(0xffff00),
+         green_50: Color::rgb(0x00ff66),
+
+         blue_30: Color::rgb(0xffd000),
+         blue_50: Color::rgb(0xffe34d),
+         blue_70: Color::rgb(0x00e5ff),
+
+         white: Color::BLACK,
+      }
+   }
+
    /// The common colors for the dark theme.
    fn dark() -> Self {
       Self {
@@ -387,6 +483,9 @@ impl CommonColors {
          red_10: Color::rgb(0xdb325a),
          red_30: Color::rgb(0xff7593),
 
+         yellow_50: Color::rgb(0xf2c94c),
+         green_50: Color::rgb(0x5fd27a),
+
          blue_30: Color::rgb(0x007ccf),
          blue_50: Color::rgb(0x0397fb),
          blue_70: Color::rgb(0x32aafa),
@@ -394,6 +493,38 @@ impl CommonColors {
          white: Color::WHITE,
       }
    }
+
+   /// Returns the common colors a built-in color scheme is based on.
+   fn builtin(scheme: config::ColorScheme) -> Self {
+      match scheme {
+         config::ColorScheme::Light => Self::light(),
+         config::ColorScheme::Dark => Self::dark(),
+         config::ColorScheme::HighContrast => Self::high_contrast(),
+         // A custom theme cannot be based on another custom theme.
+         config::ColorScheme::Custom => Self::light(),
+      }
+   }
+
+   /// Returns the color with the given name, as used in theme files.
+   fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+      Some(match name {
+         "gray_00" => &mut self.gray_00,
+         "gray_20" => &mut self.gray_20,
+         "gray_50" => &mut self.gray_50,
+         "gray_60" => &mut self.gray_60,
+         "gray_80" => &mut self.gray_80,
+         "gray_90" => &mut self.gray_90,
+         "red_10" => &mut self.red_10,
+         "red_30" => &mut self.red_30,
+         "yellow_50" => &mut self.yellow_50,
+         "green_50" => &mut self.green_50,
+         "blue_30" => &mut self.blue_30,
+         "blue_50" => &mut self.blue_50,
+         "blue_70" => &mut self.blue_70,
+         "white" => &mut self.white,
+         _ => return None,
+      })
+   }
 }
 
 /// A color scheme.
@@ -415,7 +546,10 @@ pub struct ColorScheme {
    pub context_menu: ContextMenuColors,
    pub window_buttons: WindowButtonsColors,
    pub toolbar: ToolbarColors,
+   pub connection_status: ConnectionStatusColors,
    pub drag_handle: Color,
+   /// The outline drawn around elements focused with the keyboard.
+   pub focus_outline: Color,
 
    pub lobby: LobbyColors,
 }
@@ -430,6 +564,50 @@ impl ColorScheme {
    pub fn dark() -> Self {
       Self::from(CommonColors::dark())
    }
+
+   /// Constructs and returns the high contrast color scheme.
+   pub fn high_contrast() -> Self {
+      Self::from(CommonColors::high_contrast())
+   }
+
+   /// Constructs and returns the color scheme described by the user's theme file.
+   pub fn custom() -> netcanv::Result<Self> {
+      fn parse_color(hex_code: &str) -> Option<Color> {
+         let hex_code = hex_code.strip_prefix('#').unwrap_or(hex_code);
+         if hex_code.len() != 6 {
+            return None;
+         }
+         u32::from_str_radix(hex_code, 16).ok().map(Color::rgb)
+      }
+
+      let theme = config::load_theme()?;
+      let mut colors = CommonColors::builtin(theme.base);
+      for (name, hex_code) in &theme.colors {
+         let color = colors
+            .color_mut(name)
+            .ok_or_else(|| Error::UnknownThemeColor { name: name.clone() })?;
+         *color = parse_color(hex_code).ok_or_else(|| Error::InvalidThemeColor {
+            name: name.clone(),
+            color: hex_code.clone(),
+         })?;
+      }
+      Ok(Self::from(colors))
+   }
+
+   /// Constructs and returns the color scheme selected in the config.
+   ///
+   /// If the user's theme file cannot be loaded, the error is reported and the light color scheme
+   /// is used instead.
+   pub fn load(scheme: config::ColorScheme) -> Self {
+      match scheme {
+         config::ColorScheme::Custom => Self::custom().unwrap_or_else(|error| {
+            tracing::error!("cannot load custom theme: {:?}", error);
+            bus::push(crate::common::Error(error));
+            Self::light()
+         }),
+         builtin => Self::from(CommonColors::builtin(builtin)),
+      }
+   }
 }
 
 impl From<CommonColors> for ColorScheme {
@@ -443,6 +621,8 @@ impl From<CommonColors> for ColorScheme {
          gray_90,
          red_10,
          red_30,
+         yellow_50,
+         green_50,
          blue_30,
          blue_50,
          blue_70,
@@ -521,6 +701,7 @@ impl From<CommonColors> for ColorScheme {
             text_hint: gray_50,
             label: gray_00,
             selection: blue_70,
+            focus_outline: blue_50,
          },
          context_menu: ContextMenuColors {
             background: gray_80,
@@ -552,9 +733,15 @@ impl From<CommonColors> for ColorScheme {
             },
          },
          drag_handle: gray_60,
+         focus_outline: blue_50,
          toolbar: ToolbarColors {
             position_highlight: blue_50,
          },
+         connection_status: ConnectionStatusColors {
+            healthy: green_50,
+            degraded: yellow_50,
+            unhealthy: red_30,
+         },
 
          lobby: LobbyColors {
             background: blue_50,
@@ -562,13 +749,3 @@ impl From<CommonColors> for ColorScheme {
       }
    }
 }
-
-impl From<crate::config::ColorScheme> for ColorScheme {
-   fn from(scheme: crate::config::ColorScheme) -> Self {
-      use crate::config::ColorScheme;
-      match scheme {
-         ColorScheme::Light => Self::light(),
-         ColorScheme::Dark => Self::dark(),
-      }
-   }
-}
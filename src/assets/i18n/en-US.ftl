@@ -33,7 +33,13 @@ lobby-host-from-file = from File
 
 switch-to-dark-mode = Switch to dark mode
 switch-to-light-mode = Switch to light mode
+switch-to-high-contrast-mode = Switch to high contrast mode
 language = Language
+fonts =
+   .title = Fonts
+   .interface = Interface
+   .text-tool = Text tool
+   .default = Default
 open-source-licenses = Open source licenses
 
 connecting = Connecting…
@@ -53,27 +59,203 @@ someone-joined-the-room = { $nickname } joined the room
 someone-left-the-room = { $nickname } has left
 someone-is-now-hosting-the-room = { $nickname } is now hosting the room
 you-are-now-hosting-the-room = You are now hosting the room
+copy-peer-tool = Copy tool
+peer-latency = { $milliseconds } ms
+peer-tool-copied = Copied { $nickname }'s tool settings. Changes they make later won't affect yours
+go-to-peer = Go to
+peer-position-unknown = { $nickname } has no position yet. Try again once they move their cursor
+follow-peer = Follow
+stop-following-peer = Unfollow
+following-peer = Following { $nickname }'s view. Pan around to stop
+followed-peer-left = { $nickname } left, so you're no longer following their view
+followed-peer-stopped-sharing = { $nickname } stopped sharing their view
+viewport-shared = Others in the room can now follow your view
+viewport-not-shared = Your view is no longer shared
+connection-status =
+   .title = Connection to the relay
+   .healthy = The connection is good
+   .degraded = The connection is slow
+   .unhealthy = The connection is unstable
+   .round-trip-time = Ping: { $milliseconds } ms
+   .round-trip-time-unknown = Ping: not measured yet
+   .last-received = Last data received { $seconds } s ago
+   .missed-pings = Unanswered pings: { $count }
+   .reconnecting = Reconnecting to the relay (attempt { $attempt } of { $attempts })…
+go-to-coordinates =
+   .title = Go to coordinates
+   .x = X
+   .y = Y
+   .go = Go
+   .invalid = The coordinates must be numbers
+loading-canvas = Loading canvas… { $progress }%
+saving-canvas = Saving canvas…
+canvas-chunks-skipped =
+   { $count ->
+      [one] 1 corrupted chunk could not be loaded and was skipped
+     *[other] { $count } corrupted chunks could not be loaded and were skipped
+   }
+connection-lost = Lost connection to the relay. Reconnecting…
+reconnected = Reconnected to the relay
+reconnected-to-new-room = Reconnected to the relay. Everyone else left the room in the meantime, so the canvas is now hosted in a new room with the ID { $id }
 
 tool-selection = Selection
 tool-brush = Brush
 tool-eyedropper = Eyedropper
+tool-hand = Hand
+tool-bucket = Bucket
+tool-text = Text
+tool-line = Line
 
 brush-thickness = Thickness
+brush-stabilizer = Stabilizer
+brush-blending =
+   .normal = Normal
+   .multiply = Multiply
+   .screen = Screen
+brush-dynamics =
+   .title = Dynamics
+   .position-jitter = Scatter
+   .size-jitter = Size jitter
+   .tip-angle = Tip angle
+   .tip-roundness = Roundness
+bucket-tolerance = Tolerance
+text-size = Size
+text-alignment =
+   .left = Left
+   .center = Center
+   .right = Right
+text-hint = Return adds a line, Ctrl+Return places the text
+line-cap =
+   .round = Round
+   .square = Square
+   .butt = Flat
+undo-conflict = This change can't be undone or redone, because it has been painted over since
+layer-selected = Painting on layer { $layer } of { $count }
 
 action-save-to-file = Save to file
+action-export-viewport = Export visible area
+action-clear-canvas = Clear canvas
+action-share-viewport = Share my view
+action-edit-keymap = Key bindings
+action-open-settings = Settings
 action-leave-the-room = Leave the room
+canvas-saved = Canvas saved to { $path }
+
+overwrite-file =
+   .title = Overwrite file?
+   .description = { $path } already exists. Do you want to replace it?
+   .replace = Replace
+   .replace-without-asking = Replace and don't ask again
+   .cancel = Cancel
+
+trim-borders =
+   .title = Trim borders?
+   .description = Do you want to crop the image to your drawing, leaving out the transparent borders around it?
+   .trim = Trim borders
+   .keep = Keep borders
+   .cancel = Cancel
+
+clear-canvas =
+   .title = Clear canvas?
+   .description = Everything painted on the canvas will be erased for everyone in the room. This can't be undone.
+   .clear = Clear
+   .cancel = Cancel
+clear-canvas-host-only = Only the host can clear the canvas
+canvas-cleared = The host cleared the canvas
+
+export-viewport =
+   .title = Export visible area
+   .description = Do you want to export the visible area at the current zoom ({ $zoom }%), or at its actual size, with one pixel per pixel of the canvas?
+   .current-zoom = Current zoom
+   .actual-size = Actual size
+   .cancel = Cancel
+export-quality =
+   .title = Export quality
+   .description = This format compresses images lossily. Do you want to keep as much detail as possible, or make the file smaller?
+   .high-quality = High quality
+   .smaller-file = Smaller file
+   .cancel = Cancel
+viewport-exported = Visible area exported to { $path }
+
+keymap-editor =
+   .title = Key bindings
+   .hint = Click a binding, then press the new keys
+   .press-keys = Press keys… (Esc to cancel)
+   .conflict = Some keys are bound to more than one action
+   .reset = Reset to defaults
+   .cancel = Cancel
+   .save = Save
+   .saved = Key bindings saved
+keymap-section-edit = Editing
+keymap-section-tools = Tools
+keymap-section-brush = Brush
+keymap-section-color-picker = Color picker
+keymap-section-viewport = View
+keymap-section-file = File
+keymap-section-layers = Layers
+keymap-section-debug = Debugging
+keymap-action-edit-copy = Copy
+keymap-action-edit-cut = Cut
+keymap-action-edit-paste = Paste
+keymap-action-edit-delete = Delete
+keymap-action-edit-select-all = Select all
+keymap-action-edit-undo = Undo
+keymap-action-edit-redo = Redo
+keymap-action-tools-selection = Selection
+keymap-action-tools-brush = Brush
+keymap-action-tools-eyedropper = Eyedropper
+keymap-action-tools-hand = Hand
+keymap-action-tools-bucket = Bucket
+keymap-action-tools-text = Text
+keymap-action-tools-line = Line
+keymap-action-tools-hold-hand = Hand, while held
+keymap-action-brush-decrease-thickness = Decrease thickness
+keymap-action-brush-increase-thickness = Increase thickness
+keymap-action-color-picker-swap-colors = Swap colors
+keymap-action-viewport-rotate-counterclockwise = Rotate counterclockwise
+keymap-action-viewport-rotate-clockwise = Rotate clockwise
+keymap-action-viewport-reset-rotation = Reset rotation
+keymap-action-viewport-go-to-coordinates = Go to coordinates
+keymap-action-viewport-toggle-minimap = Show or hide minimap
+keymap-action-viewport-toggle-grid = Show or hide grid
+keymap-action-viewport-zoom-to-fit = Zoom to fit
+keymap-action-viewport-reset-zoom = Reset zoom
+keymap-action-viewport-pan-left = Pan left
+keymap-action-viewport-pan-right = Pan right
+keymap-action-viewport-pan-up = Pan up
+keymap-action-viewport-pan-down = Pan down
+keymap-action-file-save = Save
+keymap-action-layers-new-layer = New layer
+keymap-action-layers-select-layer-above = Select the layer above
+keymap-action-layers-select-layer-below = Select the layer below
+keymap-action-debug-toggle-verbose-logging = Toggle verbose logging
+
+settings =
+   .title = Settings
+   .theme = Theme
+   .theme-light = Light
+   .theme-dark = Dark
+   .theme-high-contrast = High contrast
+   .theme-custom = Custom
+   .custom-theme-hint = The custom theme is read from theme.toml in the config folder
+   .reload-theme-hint = Pick it again after editing the file to reload it
 
 ## File dialogs
 
 fd-supported-image-files = Supported image files
 fd-png-file = PNG file
+fd-jpeg-file = JPEG file
+fd-webp-file = WebP file
 fd-netcanv-canvas = NetCanv canvas
+fd-ora-file = OpenRaster image
 
 ## Color picker
 
 click-to-edit-color = Click to edit color
 eraser = Eraser
+swap-colors = Swap colors
 rgb-hex-code = RGB hex code
+new-palette = New palette
 
 ## Errors
 
@@ -90,6 +272,7 @@ error-io = I/O: { $error }
 error-failed-to-persist-temporary-file = Failed to persist temporary file: { $error }
 error-image = Image operation error: { $error }
 error-join = Could not join thread: { $error }
+error-zip = ZIP archive error: { $error }
 error-channel-send = Thread communication channel is closed
 error-toml-parse = TOML parse error: { $error }
 error-toml-serialization = TOML serialization error: { $error }
@@ -104,9 +287,17 @@ error-invalid-number = Invalid number (please report this)
 
 error-could-not-initialize-backend = Could not initialize backend: { $error }
 error-could-not-initialize-logger = Could not initialize logger: { $error }
+error-log-level-is-fixed = The log level is set by the NETCANV_LOG environment variable and cannot be toggled
 error-could-not-initialize-clipboard = Could not initialize clipboard: { $error }
 
 error-config-is-already-loaded = User configuration is already loaded. This is a bug, please report this
+error-conflicting-key-bindings = Some keyboard shortcuts are bound to more than one action: { $conflicts }. Edit the keymap in your config file to fix this
+error-conflicting-mouse-buttons = Some mouse buttons are bound to more than one action: { $conflicts }. The default mouse buttons will be used instead
+error-config-dir-is-not-writable = Cannot store the configuration in { $dir } ({ $error }). The default configuration directory will be used instead
+error-invalid-palette-name = "{ $name }" is not a valid palette name
+error-theme-file-does-not-exist = The custom theme file { $path } does not exist
+error-unknown-theme-color = The custom theme sets an unknown color: { $name }
+error-invalid-theme-color = The custom theme sets { $name } to "{ $color }", which is not a valid #RRGGBB hex code
 
 error-cli-args-are-already-parsed = Command line interface arguments were already parsed. This is a bug, please report this
 
@@ -131,12 +322,19 @@ error-non-rgba-chunk-image = Received non-RGBA chunk image
 error-invalid-chunk-image-format = Invalid chunk image format (was not PNG nor WebP)
 error-invalid-chunk-image-size = Received chunk image of invalid size
 error-nothing-to-save = There's nothing to save! Draw something on the canvas and try again
+error-invalid-export-scale = The PNG export scale must be a positive number. Check save_png_scale in your config
 error-invalid-canvas-folder = Please select a valid canvas folder (one whose name ends with .netcanv)
 error-unsupported-save-format = Unsupported save format. Choose either .png or .netcanv
+error-unsupported-export-format = Unsupported export format. Choose .png, .jpg, or .webp
 error-missing-canvas-save-extension = Can't save canvas without an extension. Choose either .png or .netcanv
 error-invalid-chunk-position-pattern = Chunk position must follow the pattern: x,y
 error-trailing-chunk-coordinates-in-filename = Trailing coordinates found after x,y
 error-canvas-toml-version-mismatch = Version mismatch in canvas.toml. Try downloading a newer version of NetCanv
+error-project-loading-interrupted = Loading the canvas was interrupted unexpectedly
+error-invalid-ora-file = Invalid OpenRaster file: { $error }
+error-save-already-in-progress = The canvas is already being saved. Try again once saving is done
+error-save-interrupted = Saving the canvas was interrupted unexpectedly
+error-unsupported-image-file = { $file } is not an image in a supported format
 
 error-invalid-url = Could not parse URL. Please double-check if it's correct
 error-no-version-packet = Did not receive a version packet from the relay
@@ -146,6 +344,7 @@ error-relay-is-too-new = Relay version is too new. Try downloading a newer versi
 error-received-packet-that-is-too-big = Received a packet that exceeds the maximum supported size
 error-tried-to-send-packet-that-is-too-big = Cannot send packet that is bigger than { $max } bytes (got { $size })
 error-tried-to-send-packet-that-is-way-too-big = Cannot send packet that exceeds the 32-bit integer limit
+error-invalid-packet-framing = Received a malformed fragment of a packet: { $error }
 error-relay-has-disconnected = The relay server has disconnected
 error-web-socket = WebSocket error: { $error }
 
@@ -158,6 +357,7 @@ error-relay =
    .no-free-peer-ids = The relay server is full. Try a different server
    .room-does-not-exist = No room with the given ID. Check if you spelled the ID correctly
    .no-such-peer = Internal server error: No such peer
+   .room-full = This room is full. Try again once somebody leaves
 error-unexpected-relay-packet = Bad packet type received from relay; it's probably modified or malicious
 error-client-is-too-old = Your version of NetCanv is too old. Try downloading a newer version
 error-client-is-too-new = Your version of NetCanv is too new. Join a newer room or download an older version
@@ -33,12 +33,21 @@ lobby-host-from-file = z pliku
 
 switch-to-dark-mode = Przełącz na tryb ciemny
 switch-to-light-mode = Przełącz na tryb jasny
+switch-to-high-contrast-mode = Przełącz na tryb wysokiego kontrastu
 language = Język
+fonts =
+   .title = Czcionki
+   .interface = Interfejs
+   .text-tool = Narzędzie tekstu
+   .default = Domyślna
 open-source-licenses = Licencje open source
 
 fd-supported-image-files = Obsługiwane formaty obrazów
 fd-png-file = Obrazek PNG
+fd-jpeg-file = Obrazek JPEG
+fd-webp-file = Obrazek WebP
 fd-netcanv-canvas = Kartka NetCanv
+fd-ora-file = Obrazek OpenRaster
 
 connecting = Łączenie…
 
@@ -57,21 +66,195 @@ someone-joined-the-room = { $nickname } dołączył do pokoju
 someone-left-the-room = { $nickname } opuścił pokój
 someone-is-now-hosting-the-room = { $nickname } został gospodarzem pokoju
 you-are-now-hosting-the-room = Zostałeś gospodarzem pokoju
+copy-peer-tool = Kopiuj narzędzie
+peer-latency = { $milliseconds } ms
+peer-tool-copied = Skopiowano ustawienia narzędzia od { $nickname }. Zmiany, które wprowadzi później, nie wpłyną na twoje
+go-to-peer = Przejdź
+peer-position-unknown = Pozycja użytkownika { $nickname } nie jest jeszcze znana. Spróbuj ponownie, gdy poruszy kursorem
+follow-peer = Obserwuj
+stop-following-peer = Przestań
+following-peer = Obserwujesz widok użytkownika { $nickname }. Przesuń kartkę, aby przestać
+followed-peer-left = { $nickname } opuścił pokój, więc nie obserwujesz już jego widoku
+followed-peer-stopped-sharing = { $nickname } przestał udostępniać swój widok
+viewport-shared = Inni w pokoju mogą teraz obserwować twój widok
+viewport-not-shared = Twój widok nie jest już udostępniany
+connection-status =
+   .title = Połączenie z serwerem Relay
+   .healthy = Połączenie jest dobre
+   .degraded = Połączenie jest wolne
+   .unhealthy = Połączenie jest niestabilne
+   .round-trip-time = Ping: { $milliseconds } ms
+   .round-trip-time-unknown = Ping: jeszcze nie zmierzony
+   .last-received = Ostatnie dane odebrano { $seconds } s temu
+   .missed-pings = Pingi bez odpowiedzi: { $count }
+   .reconnecting = Ponowne łączenie z serwerem (próba { $attempt } z { $attempts })…
+go-to-coordinates =
+   .title = Przejdź do współrzędnych
+   .x = X
+   .y = Y
+   .go = Przejdź
+   .invalid = Współrzędne muszą być liczbami
+loading-canvas = Wczytywanie kartki… { $progress }%
+saving-canvas = Zapisywanie kartki…
+canvas-chunks-skipped =
+   { $count ->
+      [one] Nie udało się wczytać 1 uszkodzonego chunka, więc został pominięty
+      [few] Nie udało się wczytać { $count } uszkodzonych chunków, więc zostały pominięte
+     *[other] Nie udało się wczytać { $count } uszkodzonych chunków, więc zostały pominięte
+   }
+connection-lost = Utracono połączenie z serwerem. Ponowne łączenie…
+reconnected = Ponownie połączono z serwerem
+reconnected-to-new-room = Ponownie połączono z serwerem. W międzyczasie wszyscy opuścili pokój, więc kartka jest teraz w nowym pokoju o ID { $id }
 
 tool-selection = Zaznaczenie
 tool-brush = Pędzel
 tool-eyedropper = Pipeta
+tool-hand = Rączka
+tool-bucket = Wiaderko
+tool-text = Tekst
+tool-line = Linia
 
 brush-thickness = Grubość
+brush-stabilizer = Stabilizacja
+brush-blending =
+   .normal = Normalne
+   .multiply = Mnożenie
+   .screen = Ekran
+brush-dynamics =
+   .title = Dynamika
+   .position-jitter = Rozrzut
+   .size-jitter = Losowy rozmiar
+   .tip-angle = Kąt końcówki
+   .tip-roundness = Okrągłość
+bucket-tolerance = Tolerancja
+text-size = Rozmiar
+text-alignment =
+   .left = Do lewej
+   .center = Do środka
+   .right = Do prawej
+text-hint = Enter dodaje wiersz, Ctrl+Enter umieszcza tekst
+line-cap =
+   .round = Okrągłe
+   .square = Kwadratowe
+   .butt = Płaskie
+undo-conflict = Nie można cofnąć ani ponowić tej zmiany, bo została już zamalowana
+layer-selected = Malowanie na warstwie { $layer } z { $count }
 
 action-save-to-file = Zapisz do pliku
+action-export-viewport = Eksportuj widoczny obszar
+action-clear-canvas = Wyczyść kartkę
+action-share-viewport = Udostępniaj mój widok
+action-edit-keymap = Skróty klawiszowe
+action-open-settings = Ustawienia
 action-leave-the-room = Wyjdź z pokoju
+canvas-saved = Zapisano kartkę do { $path }
+
+overwrite-file =
+   .title = Nadpisać plik?
+   .description = { $path } już istnieje. Czy chcesz go zastąpić?
+   .replace = Zastąp
+   .replace-without-asking = Zastąp i nie pytaj ponownie
+   .cancel = Anuluj
+
+trim-borders =
+   .title = Przyciąć krawędzie?
+   .description = Czy chcesz przyciąć obraz do rysunku, pomijając przezroczyste krawędzie wokół niego?
+   .trim = Przytnij krawędzie
+   .keep = Zachowaj krawędzie
+   .cancel = Anuluj
+
+clear-canvas =
+   .title = Wyczyścić kartkę?
+   .description = Wszystko, co zostało namalowane na kartce, zostanie usunięte dla wszystkich w pokoju. Tej operacji nie można cofnąć.
+   .clear = Wyczyść
+   .cancel = Anuluj
+clear-canvas-host-only = Tylko gospodarz może wyczyścić kartkę
+canvas-cleared = Gospodarz wyczyścił kartkę
+
+export-viewport =
+   .title = Eksportuj widoczny obszar
+   .description = Czy chcesz wyeksportować widoczny obszar w obecnym powiększeniu ({ $zoom }%), czy w rzeczywistym rozmiarze, z jednym pikselem na piksel kartki?
+   .current-zoom = Obecne powiększenie
+   .actual-size = Rzeczywisty rozmiar
+   .cancel = Anuluj
+export-quality =
+   .title = Jakość eksportu
+   .description = Ten format kompresuje obrazy stratnie. Czy chcesz zachować jak najwięcej szczegółów, czy zmniejszyć rozmiar pliku?
+   .high-quality = Wysoka jakość
+   .smaller-file = Mniejszy plik
+   .cancel = Anuluj
+viewport-exported = Wyeksportowano widoczny obszar do { $path }
+
+keymap-editor =
+   .title = Skróty klawiszowe
+   .hint = Kliknij skrót, a następnie wciśnij nowe klawisze
+   .press-keys = Wciśnij klawisze… (Esc aby anulować)
+   .conflict = Niektóre klawisze są przypisane do więcej niż jednej akcji
+   .reset = Przywróć domyślne
+   .cancel = Anuluj
+   .save = Zapisz
+   .saved = Zapisano skróty klawiszowe
+keymap-section-edit = Edycja
+keymap-section-tools = Narzędzia
+keymap-section-brush = Pędzel
+keymap-section-color-picker = Wybór koloru
+keymap-section-viewport = Widok
+keymap-section-file = Plik
+keymap-section-layers = Warstwy
+keymap-section-debug = Debugowanie
+keymap-action-edit-copy = Kopiuj
+keymap-action-edit-cut = Wytnij
+keymap-action-edit-paste = Wklej
+keymap-action-edit-delete = Usuń
+keymap-action-edit-select-all = Zaznacz wszystko
+keymap-action-edit-undo = Cofnij
+keymap-action-edit-redo = Ponów
+keymap-action-tools-selection = Zaznaczenie
+keymap-action-tools-brush = Pędzel
+keymap-action-tools-eyedropper = Pipeta
+keymap-action-tools-hand = Rączka
+keymap-action-tools-bucket = Wiaderko
+keymap-action-tools-text = Tekst
+keymap-action-tools-line = Linia
+keymap-action-tools-hold-hand = Rączka, gdy przytrzymany
+keymap-action-brush-decrease-thickness = Zmniejsz grubość
+keymap-action-brush-increase-thickness = Zwiększ grubość
+keymap-action-color-picker-swap-colors = Zamień kolory
+keymap-action-viewport-rotate-counterclockwise = Obróć w lewo
+keymap-action-viewport-rotate-clockwise = Obróć w prawo
+keymap-action-viewport-reset-rotation = Zresetuj obrót
+keymap-action-viewport-go-to-coordinates = Przejdź do współrzędnych
+keymap-action-viewport-toggle-minimap = Pokaż lub ukryj minimapę
+keymap-action-viewport-toggle-grid = Pokaż lub ukryj siatkę
+keymap-action-viewport-zoom-to-fit = Dopasuj przybliżenie
+keymap-action-viewport-reset-zoom = Zresetuj przybliżenie
+keymap-action-viewport-pan-left = Przesuń w lewo
+keymap-action-viewport-pan-right = Przesuń w prawo
+keymap-action-viewport-pan-up = Przesuń w górę
+keymap-action-viewport-pan-down = Przesuń w dół
+keymap-action-file-save = Zapisz
+keymap-action-layers-new-layer = Nowa warstwa
+keymap-action-layers-select-layer-above = Wybierz warstwę powyżej
+keymap-action-layers-select-layer-below = Wybierz warstwę poniżej
+keymap-action-debug-toggle-verbose-logging = Przełącz szczegółowe logowanie
+
+settings =
+   .title = Ustawienia
+   .theme = Motyw
+   .theme-light = Jasny
+   .theme-dark = Ciemny
+   .theme-high-contrast = Wysoki kontrast
+   .theme-custom = Własny
+   .custom-theme-hint = Własny motyw jest wczytywany z pliku theme.toml w folderze konfiguracji
+   .reload-theme-hint = Wybierz go ponownie po edycji pliku, aby go przeładować
 
 ## Color picker
 
 click-to-edit-color = Kliknij aby edytować kolor
 eraser = Gumka
+swap-colors = Zamień kolory
 rgb-hex-code = Kod koloru RGB
+new-palette = Nowa paleta
 
 ## Errors
 
@@ -82,6 +265,7 @@ error-io = I/O: { $error }
 error-failed-to-persist-temporary-file = Nie udało się zachować pliku tymczasowego: { $error }
 error-image = Błąd operacji na obrazach: { $error }
 error-join = Nie udało się złączyć z wątkiem: { $error }
+error-zip = Błąd archiwum ZIP: { $error }
 error-channel-send = Kanał do komunikacji z wątkiem został zamknięty
 error-toml-parse = Błąd odczytywania TOML: { $error }
 error-toml-serialization = Błąd serializacji TOML: { $error }
@@ -96,9 +280,17 @@ error-invalid-number = Niepoprawna liczba (prosimy zgłosić to jako bug)
 
 error-could-not-initialize-backend = Nie udało się zinicjalizować renderera: { $error }
 error-could-not-initialize-logger = Nie udało się zinicjalizować loggera: { $error }
+error-log-level-is-fixed = Poziom logowania jest ustawiony przez zmienną środowiskową NETCANV_LOG i nie może zostać przełączony
 error-could-not-initialize-clipboard = Nie udało się zinicjalizować schowka: { $error }
 
 error-config-is-already-loaded = Konfiguracja użytkownika została wcześniej załadowana. Prosimy to zgłosić
+error-conflicting-key-bindings = Niektóre skróty klawiszowe są przypisane do więcej niż jednej akcji: { $conflicts }. Popraw mapę klawiszy w pliku konfiguracyjnym
+error-conflicting-mouse-buttons = Niektóre przyciski myszy są przypisane do więcej niż jednej akcji: { $conflicts }. Zamiast nich zostaną użyte domyślne przyciski myszy
+error-config-dir-is-not-writable = Nie można zapisać konfiguracji w { $dir } ({ $error }). Zamiast tego zostanie użyty domyślny katalog konfiguracji
+error-invalid-palette-name = „{ $name }” nie jest poprawną nazwą palety
+error-theme-file-does-not-exist = Plik motywu { $path } nie istnieje
+error-unknown-theme-color = Motyw ustawia nieznany kolor: { $name }
+error-invalid-theme-color = Motyw ustawia { $name } na „{ $color }”, co nie jest poprawnym kodem #RRGGBB
 
 error-cli-args-are-already-parsed = Argumenty interfejsu wiersza poleceń zostały już przeanalizowane. Prosimy to zgłosić
 
@@ -123,12 +315,19 @@ error-non-rgba-chunk-image = Otrzymano obraz chunka w formacie innym niż RGBA
 error-invalid-chunk-image-format = Niepoprawny format obrazu chunka (ani PNG, ani WebP)
 error-invalid-chunk-image-size = Otrzymano obraz chunka o niepoprawnym rozmiarze
 error-nothing-to-save = Nie ma nic do zapisu! Narysuj coś na kartce i spróbuj ponownie.
+error-invalid-export-scale = Skala eksportu PNG musi być liczbą dodatnią. Sprawdź save_png_scale w konfiguracji.
 error-invalid-canvas-folder = Wybierz poprawny folder z kartką (o końcówce .netcanv)
 error-unsupported-save-format = Nieobsługiwany format zapisu. Wybierz .png lub .netcanv
+error-unsupported-export-format = Nieobsługiwany format eksportu. Wybierz .png, .jpg lub .webp
 error-missing-canvas-save-extension = Nie można zapisać kartki bez rozszerzenia pliku. Wybierz .png lub .netcanv
 error-invalid-chunk-position-pattern = Pozycja chunka powinna spełniać wzór: x,y
 error-trailing-chunk-coordinates-in-filename = Dodatkowe współrzędne znalezione po pozycji x,y
 error-canvas-toml-version-mismatch = Niezgodność wersji w canvas.toml. Spróbuj pobrać nowszego NetCanva
+error-project-loading-interrupted = Wczytywanie kartki zostało nieoczekiwanie przerwane
+error-invalid-ora-file = Nieprawidłowy plik OpenRaster: { $error }
+error-save-already-in-progress = Kartka jest już zapisywana. Spróbuj ponownie po zakończeniu zapisu
+error-save-interrupted = Zapisywanie kartki zostało nieoczekiwanie przerwane
+error-unsupported-image-file = { $file } nie jest obrazem w obsługiwanym formacie
 
 error-invalid-url = Niepoprawny URL. Sprawdź czy nie posiada błędów w pisowni
 error-no-version-packet = Nie otrzymano pakietu wersji od serwera
@@ -138,6 +337,7 @@ error-relay-is-too-new = Wersja Relaya jest zbyt nowa. Spróbuj pobrać nowszego
 error-received-packet-that-is-too-big = Otrzymano pakiet, który był zbyt duży
 error-tried-to-send-packet-that-is-too-big = Nie można wysłać pakietu większego niż { $max } bajtów (próbowano wysłać { $size })
 error-tried-to-send-packet-that-is-way-too-big = Nie można wysłać pakietu o rozmiarze większym niż limit 32-bitowych liczb całkowitych
+error-invalid-packet-framing = Otrzymano nieprawidłowy fragment pakietu: { $error }
 error-relay-has-disconnected = Serwer Relay rozłączył się
 error-web-socket = Błąd komunikacji WebSocket: { $error }
 
@@ -150,6 +350,7 @@ error-relay =
    .no-free-peer-ids = Serwer jest pełny. Spróbuj połączyć się z innym serwerem
    .room-does-not-exist = Pokój o podanym kodzie nie istnieje. Sprawdź czy kod nie zawiera literówek
    .no-such-peer = Błąd wewnętrzny serwera: Nie ma takiej osoby
+   .room-full = Ten pokój jest pełny. Spróbuj ponownie, gdy ktoś z niego wyjdzie
 error-unexpected-relay-packet = Serwer wysłał niepoprawny pakiet; prawdopodobnie został zmodyfikowany i jest potencjalnie niebezpieczny
 error-client-is-too-old = Wersja NetCanv jest zbyt stara. Pobierz nowszą wersję aby dołączyć do tego pokoju
 error-client-is-too-new = Wersja NetCanv jest zbyt nowa. Dołącz do innego pokoju lub pobierz starszą wersję
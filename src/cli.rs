@@ -3,6 +3,7 @@ use netcanv::Error;
 use netcanv_protocol::relay::RoomId;
 use std::path::PathBuf;
 use std::sync::OnceLock;
+use tracing_subscriber::filter::LevelFilter;
 
 static CLI_ARGS: OnceLock<Cli> = OnceLock::new();
 
@@ -12,12 +13,45 @@ pub struct Cli {
    #[clap(long)]
    pub trace: Option<PathBuf>,
 
+   /// Record all input events to the given file, for replaying them later with --replay-input.
+   #[clap(long, conflicts_with = "replay_input")]
+   pub record_input: Option<PathBuf>,
+
+   /// Replay input events previously recorded with --record-input, instead of reacting to the
+   /// user's input.
+   #[clap(long)]
+   pub replay_input: Option<PathBuf>,
+
+   /// Store the configuration in the given directory, instead of the platform-specific default.
+   /// Useful for portable installs, or running multiple isolated instances.
+   #[clap(long, alias = "data-dir", global = true)]
+   pub config_dir: Option<PathBuf>,
+
    #[clap(flatten)]
    pub render: crate::backend::cli::RendererCli,
 
    #[command(subcommand)]
    pub command: Option<Commands>,
 
+   /// Sets the minimum level of log messages printed to the terminal (off, error, warn, info,
+   /// debug, trace). The NETCANV_LOG environment variable takes precedence over this.
+   #[clap(long)]
+   pub log_level: Option<LevelFilter>,
+
+   /// Prints debug log messages. Shorthand for --log-level=debug.
+   #[clap(short, long, conflicts_with = "log_level")]
+   pub verbose: bool,
+
+   /// Don't show a dialog when NetCanv crashes, and log the panic along with its backtrace instead.
+   /// Can also be enabled by setting the NETCANV_NO_PANIC_DIALOG environment variable.
+   #[clap(long)]
+   pub no_panic_dialog: bool,
+
+   /// Limits the number of frames rendered per second. By default, frames are rendered as fast as
+   /// the display allows while something on screen is changing.
+   #[clap(long, value_parser = value_parser!(u32).range(1..))]
+   pub max_fps: Option<u32>,
+
    /// Sets the default zoom level (range: -8..20).
    #[clap(long, global = true)]
    #[arg(allow_negative_numbers = true, value_parser = value_parser!(i8).range(-8..20))]
@@ -60,3 +94,28 @@ pub fn parse() -> netcanv::Result<()> {
 pub fn cli_args() -> &'static Cli {
    CLI_ARGS.get().expect("attempt to get cli arguments without parsing them")
 }
+
+/// Returns the CLI arguments, or `None` if they haven't been parsed yet.
+pub fn try_cli_args() -> Option<&'static Cli> {
+   CLI_ARGS.get()
+}
+
+#[cfg(test)]
+mod tests {
+   use clap::CommandFactory;
+
+   use super::*;
+
+   #[test]
+   fn arguments_are_well_formed() {
+      Cli::command().debug_assert();
+   }
+
+   #[test]
+   fn frame_rate_cap_must_be_positive() {
+      assert_eq!(Cli::try_parse_from(["netcanv"]).unwrap().max_fps, None);
+      let cli = Cli::try_parse_from(["netcanv", "--max-fps", "30"]).unwrap();
+      assert_eq!(cli.max_fps, Some(30));
+      assert!(Cli::try_parse_from(["netcanv", "--max-fps", "0"]).is_err());
+   }
+}
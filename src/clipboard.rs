@@ -2,16 +2,25 @@
 
 use parking_lot::Mutex;
 use std::borrow::Cow;
+use std::io::Write;
+use std::path::PathBuf;
 
 use arboard::{Clipboard, ImageData};
 use image::RgbaImage;
 use once_cell::sync::Lazy;
+use tempfile::NamedTempFile;
+use url::Url;
 
+use crate::config::{config, ClipboardImageFormat};
+use crate::image_coder::ImageCoder;
 use crate::Error;
 
 struct ClipboardState {
    string: Mutex<Option<String>>,
    clipboard: Mutex<Option<Clipboard>>,
+   /// The temporary file holding the last image copied as a file. It's kept around for as long
+   /// as it may be pasted, and deleted once another image is copied.
+   file: Mutex<Option<NamedTempFile>>,
 }
 
 impl ClipboardState {
@@ -19,6 +28,7 @@ impl ClipboardState {
       Self {
          string: Mutex::new(None),
          clipboard: Mutex::new(None),
+         file: Mutex::new(None),
       }
    }
 }
@@ -82,22 +92,56 @@ pub async fn copy_string_async(string: String) -> netcanv::Result<()> {
 }
 
 /// Copies the provided image into the clipboard.
+///
+/// The formats listed in the `clipboard.image_formats` config key are tried in order, until one
+/// of them succeeds.
 pub fn copy_image(image: RgbaImage) -> netcanv::Result<()> {
+   let formats = config().clipboard.image_formats.clone();
    let mut clipboard = CLIPBOARD_STATE.clipboard.lock();
-   if let Some(clipboard) = &mut *clipboard {
-      clipboard
-         .set_image(ImageData {
-            width: image.width() as usize,
-            height: image.height() as usize,
-            bytes: Cow::Borrowed(&image),
-         })
-         .map_err(|e| Error::CannotSaveToClipboard {
-            error: e.to_string(),
-         })?;
-      Ok(())
-   } else {
-      Err(Error::ClipboardWasNotInitialized)
+   let clipboard = clipboard.as_mut().ok_or(Error::ClipboardWasNotInitialized)?;
+   let mut result = Err(Error::ClipboardNotSupported);
+   for format in formats {
+      result = match format {
+         ClipboardImageFormat::Bitmap => copy_bitmap(clipboard, &image),
+         ClipboardImageFormat::File => copy_file(clipboard, &image),
+      };
+      match &result {
+         Ok(()) => {
+            tracing::info!("image copied to the clipboard as {:?}", format);
+            break;
+         }
+         Err(error) => tracing::warn!("cannot copy image as {:?}: {:?}", format, error),
+      }
    }
+   result
+}
+
+/// Copies an image into the clipboard in the platform's native bitmap format.
+fn copy_bitmap(clipboard: &mut Clipboard, image: &RgbaImage) -> netcanv::Result<()> {
+   clipboard
+      .set_image(ImageData {
+         width: image.width() as usize,
+         height: image.height() as usize,
+         bytes: Cow::Borrowed(image),
+      })
+      .map_err(|e| Error::CannotSaveToClipboard {
+         error: e.to_string(),
+      })
+}
+
+/// Saves an image to a temporary PNG file, and copies its URL into the clipboard.
+fn copy_file(clipboard: &mut Clipboard, image: &RgbaImage) -> netcanv::Result<()> {
+   let mut file = tempfile::Builder::new().prefix("netcanv-clipboard").suffix(".png").tempfile()?;
+   file.write_all(&ImageCoder::encode_png_data_sync(image.clone())?)?;
+   let path = file.path();
+   let url = Url::from_file_path(path).map_err(|_| Error::CannotSaveToClipboard {
+      error: format!("cannot convert {path:?} to a URL"),
+   })?;
+   clipboard.set_text(url.to_string()).map_err(|e| Error::CannotSaveToClipboard {
+      error: e.to_string(),
+   })?;
+   *CLIPBOARD_STATE.file.lock() = Some(file);
+   Ok(())
 }
 
 /// Pastes the contents of the clipboard into a string.
@@ -116,29 +160,75 @@ pub fn paste_string() -> netcanv::Result<String> {
    }
 }
 
+/// Pastes an image from the clipboard.
+///
+/// The formats listed in the `clipboard.image_formats` config key are tried in order, and the
+/// first one the clipboard contains an image in is used.
 pub fn paste_image() -> netcanv::Result<RgbaImage> {
+   let formats = config().clipboard.image_formats.clone();
    let mut clipboard = CLIPBOARD_STATE.clipboard.lock();
-   if let Some(clipboard) = &mut *clipboard {
-      let image = clipboard
-         .get_image()
-         .map_err(|e| {
-            if let arboard::Error::ContentNotAvailable = e {
-               Error::ClipboardDoesNotContainAnImage
-            } else {
-               e.into()
-            }
-         })?
-         .to_owned_img();
-      Ok(RgbaImage::from_vec(
-         image.width as u32,
-         image.height as u32,
-         match image.bytes {
-            Cow::Borrowed(_) => unreachable!("clipboard data must be owned at this point"),
-            Cow::Owned(data) => data,
-         },
-      )
-      .expect("failed to create clipboard image"))
-   } else {
-      Err(Error::ClipboardWasNotInitialized)
+   let clipboard = clipboard.as_mut().ok_or(Error::ClipboardWasNotInitialized)?;
+   let mut result = Err(Error::ClipboardDoesNotContainAnImage);
+   for format in formats {
+      result = match format {
+         ClipboardImageFormat::Bitmap => paste_bitmap(clipboard),
+         ClipboardImageFormat::File => paste_file(clipboard),
+      };
+      match &result {
+         Ok(_) => {
+            tracing::info!("image pasted from the clipboard as {:?}", format);
+            break;
+         }
+         Err(error) => tracing::debug!("cannot paste image as {:?}: {:?}", format, error),
+      }
+   }
+   result
+}
+
+/// Pastes an image stored in the platform's native bitmap format.
+fn paste_bitmap(clipboard: &mut Clipboard) -> netcanv::Result<RgbaImage> {
+   let image = clipboard
+      .get_image()
+      .map_err(|e| {
+         if let arboard::Error::ContentNotAvailable = e {
+            Error::ClipboardDoesNotContainAnImage
+         } else {
+            e.into()
+         }
+      })?
+      .to_owned_img();
+   Ok(RgbaImage::from_vec(
+      image.width as u32,
+      image.height as u32,
+      match image.bytes {
+         Cow::Borrowed(_) => unreachable!("clipboard data must be owned at this point"),
+         Cow::Owned(data) => data,
+      },
+   )
+   .expect("failed to create clipboard image"))
+}
+
+/// Pastes an image from a file, whose path or `file://` URL is stored in the clipboard as text.
+///
+/// File managers often put a list of URLs into the clipboard, one per line. In that case only the
+/// first file is pasted.
+fn paste_file(clipboard: &mut Clipboard) -> netcanv::Result<RgbaImage> {
+   let text = clipboard.get_text().map_err(|e| {
+      if let arboard::Error::ContentNotAvailable = e {
+         Error::ClipboardDoesNotContainAnImage
+      } else {
+         e.into()
+      }
+   })?;
+   let line = text.lines().next().unwrap_or_default().trim();
+   let path = match Url::parse(line) {
+      Ok(url) if url.scheme() == "file" => {
+         url.to_file_path().map_err(|_| Error::ClipboardDoesNotContainAnImage)?
+      }
+      _ => PathBuf::from(line),
+   };
+   if !path.is_file() {
+      return Err(Error::ClipboardDoesNotContainAnImage);
    }
+   Ok(image::open(path)?.to_rgba8())
 }
@@ -19,6 +19,7 @@ pub enum AnyColor {
    Hsv(Hsv),
    Oklab(Oklab),
    Okhsv(Okhsv),
+   Oklch(Oklch),
 }
 
 impl From<Srgb> for AnyColor {
@@ -35,6 +36,7 @@ impl From<AnyColor> for Srgb {
          AnyColor::Hsv(hsv) => Srgb::from(hsv),
          AnyColor::Oklab(lab) => Srgb::from(LinearRgb::from(lab)),
          AnyColor::Okhsv(hsv) => Srgb::from(LinearRgb::from(Oklab::from(hsv))),
+         AnyColor::Oklch(lch) => Srgb::from(LinearRgb::from(Oklab::from(lch))),
       }
    }
 }
@@ -53,6 +55,7 @@ impl From<AnyColor> for LinearRgb {
          AnyColor::Hsv(hsv) => LinearRgb::from(Srgb::from(hsv)),
          AnyColor::Oklab(lab) => LinearRgb::from(lab),
          AnyColor::Okhsv(hsv) => LinearRgb::from(Oklab::from(hsv)),
+         AnyColor::Oklch(lch) => LinearRgb::from(Oklab::from(lch)),
       }
    }
 }
@@ -71,6 +74,7 @@ impl From<AnyColor> for Hsv {
          AnyColor::Hsv(hsv) => hsv,
          AnyColor::Oklab(lab) => Hsv::from(Srgb::from(LinearRgb::from(lab))),
          AnyColor::Okhsv(hsv) => Hsv::from(Srgb::from(LinearRgb::from(Oklab::from(hsv)))),
+         AnyColor::Oklch(lch) => Hsv::from(Srgb::from(LinearRgb::from(Oklab::from(lch)))),
       }
    }
 }
@@ -89,6 +93,7 @@ impl From<AnyColor> for Oklab {
          AnyColor::Hsv(hsv) => Oklab::from(LinearRgb::from(Srgb::from(hsv))),
          AnyColor::Oklab(lab) => lab,
          AnyColor::Okhsv(hsv) => Oklab::from(hsv),
+         AnyColor::Oklch(lch) => Oklab::from(lch),
       }
    }
 }
@@ -107,6 +112,26 @@ impl From<AnyColor> for Okhsv {
          AnyColor::Hsv(hsv) => Okhsv::from(Oklab::from(LinearRgb::from(Srgb::from(hsv)))),
          AnyColor::Oklab(lab) => Okhsv::from(lab),
          AnyColor::Okhsv(hsv) => hsv,
+         AnyColor::Oklch(lch) => Okhsv::from(Oklab::from(lch)),
+      }
+   }
+}
+
+impl From<Oklch> for AnyColor {
+   fn from(color: Oklch) -> Self {
+      Self::Oklch(color)
+   }
+}
+
+impl From<AnyColor> for Oklch {
+   fn from(color: AnyColor) -> Self {
+      match color {
+         AnyColor::Srgb(srgb) => Oklch::from(Oklab::from(LinearRgb::from(srgb))),
+         AnyColor::LinearRgb(linear_rgb) => Oklch::from(Oklab::from(linear_rgb)),
+         AnyColor::Hsv(hsv) => Oklch::from(Oklab::from(LinearRgb::from(Srgb::from(hsv)))),
+         AnyColor::Oklab(lab) => Oklch::from(lab),
+         AnyColor::Okhsv(hsv) => Oklch::from(Oklab::from(hsv)),
+         AnyColor::Oklch(lch) => lch,
       }
    }
 }
@@ -135,12 +160,15 @@ impl Srgb {
    }
 
    /// Converts an sRGB color to a `Color`. The provided alpha value is used.
+   ///
+   /// Channels are rounded to the nearest integer, such that converting a `Color` to sRGB and back
+   /// always results in the same `Color`.
    pub fn to_color(self, alpha: f32) -> Color {
       Color {
-         r: (self.r * 255.0) as u8,
-         g: (self.g * 255.0) as u8,
-         b: (self.b * 255.0) as u8,
-         a: (alpha * 255.0) as u8,
+         r: (self.r * 255.0).round() as u8,
+         g: (self.g * 255.0).round() as u8,
+         b: (self.b * 255.0).round() as u8,
+         a: (alpha * 255.0).round() as u8,
       }
    }
 }
@@ -159,7 +187,7 @@ impl From<LinearRgb> for Srgb {
 fn linear_to_srgb(x: f32) -> f32 {
    let x = x.abs();
    if x >= 0.0031308 {
-      ((1.055) * x).powf(1.0 / 2.4) - 0.055
+      1.055 * x.powf(1.0 / 2.4) - 0.055
    } else {
       12.92 * x
    }
@@ -306,6 +334,75 @@ impl From<Oklab> for LinearRgb {
    }
 }
 
+/// An OKLCH color. This is Oklab, with the a and b components expressed as chroma and hue.
+///
+/// Not all OKLCH colors can be represented in sRGB. Use [`Oklch::clamp_to_srgb`] to make sure a
+/// color can be displayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+   /// The lightness, in range `0.0..=1.0`.
+   pub l: f32,
+   /// The chroma. Colors that can be represented in sRGB have a chroma no larger than
+   /// [`Oklch::MAX_SRGB_CHROMA`].
+   pub c: f32,
+   /// The hue, in range `0.0..=1.0`. Multiply by 360 to get the number of degrees.
+   pub h: f32,
+}
+
+impl Oklch {
+   /// The largest chroma an sRGB color can have.
+   pub const MAX_SRGB_CHROMA: f32 = 0.33;
+
+   /// Returns whether the color can be represented in sRGB.
+   pub fn is_in_srgb_gamut(self) -> bool {
+      const EPSILON: f32 = 0.0001;
+      let LinearRgb { r, g, b } = LinearRgb::from(Oklab::from(self));
+      [r, g, b].iter().all(|&x| (-EPSILON..=1.0 + EPSILON).contains(&x))
+   }
+
+   /// Reduces the chroma of the color until it can be represented in sRGB, keeping its lightness
+   /// and hue.
+   pub fn clamp_to_srgb(self) -> Self {
+      let l = self.l.clamp(0.0, 1.0);
+      let color = Self { l, ..self };
+      if color.is_in_srgb_gamut() {
+         return color;
+      }
+      // Binary search for the largest chroma that's still within the gamut.
+      let (mut low, mut high) = (0.0, self.c);
+      for _ in 0..16 {
+         let c = (low + high) / 2.0;
+         if (Self { c, ..color }).is_in_srgb_gamut() {
+            low = c;
+         } else {
+            high = c;
+         }
+      }
+      Self { c: low, ..color }
+   }
+}
+
+impl From<Oklab> for Oklch {
+   fn from(Oklab { l, a, b }: Oklab) -> Self {
+      Self {
+         l,
+         c: (a * a + b * b).sqrt(),
+         h: (b.atan2(a) / std::f32::consts::TAU).rem_euclid(1.0),
+      }
+   }
+}
+
+impl From<Oklch> for Oklab {
+   fn from(Oklch { l, c, h }: Oklch) -> Self {
+      let angle = h * std::f32::consts::TAU;
+      Self {
+         l,
+         a: c * angle.cos(),
+         b: c * angle.sin(),
+      }
+   }
+}
+
 // NOTE(liquidev):
 // I like how Oklab was this really easy thing to implement, and then implementing Okhsv is…
 // well, have a look for yourself.
@@ -585,3 +682,50 @@ impl From<Okhsv> for Oklab {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn colors_survive_srgb_round_trips() {
+      for value in 0..=255 {
+         let color = Color::new(value, 255 - value, value / 2, value);
+         let alpha = color.a as f32 / 255.0;
+         assert_eq!(Srgb::from_color(color).to_color(alpha), color);
+      }
+   }
+
+   #[test]
+   fn colors_survive_oklch_round_trips() {
+      for hex in [
+         0x000000, 0xffffff, 0x100820, 0xff003e, 0xff7b00, 0x2f6fd6, 0x3c9a3c,
+      ] {
+         let color = Color::rgb(hex);
+         let lch = Oklch::from(AnyColor::from(Srgb::from_color(color)));
+         assert!(
+            lch.is_in_srgb_gamut(),
+            "{color:?} is out of gamut as {lch:?}"
+         );
+         let srgb = Srgb::from(AnyColor::from(lch));
+         assert_eq!(srgb.to_color(1.0), color, "{lch:?}");
+      }
+   }
+
+   #[test]
+   fn clamping_to_srgb_only_reduces_chroma() {
+      let vivid = Oklch {
+         l: 0.7,
+         c: Oklch::MAX_SRGB_CHROMA,
+         h: 0.45,
+      };
+      assert!(!vivid.is_in_srgb_gamut());
+      let clamped = vivid.clamp_to_srgb();
+      assert!(clamped.is_in_srgb_gamut());
+      assert_eq!((clamped.l, clamped.h), (vivid.l, vivid.h));
+      assert!(clamped.c > 0.0 && clamped.c < vivid.c);
+
+      let gray = Oklch { c: 0.0, ..vivid };
+      assert_eq!(gray.clamp_to_srgb(), gray);
+   }
+}
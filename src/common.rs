@@ -81,6 +81,10 @@ pub trait VectorMath {
 
    /// Returns whether the point is located inside the given rectangle.
    fn is_in_rect(&self, rect: Rect) -> bool;
+
+   /// Rotates the point around the given center by the given angle, in radians. Positive angles
+   /// rotate clockwise, as the Y axis points downwards.
+   fn rotate_around(self, center: Self, angle: f32) -> Self;
 }
 
 impl VectorMath for Vector {
@@ -103,6 +107,12 @@ impl VectorMath for Vector {
          && self.x < rect.right()
          && self.y < rect.bottom()
    }
+
+   fn rotate_around(self, center: Vector, angle: f32) -> Self {
+      let (sin, cos) = angle.sin_cos();
+      let d = self - center;
+      center + vector(d.x * cos - d.y * sin, d.x * sin + d.y * cos)
+   }
 }
 
 /// Coordinates for four sides of a rectangle.
@@ -5,14 +5,20 @@
 //! New keys added to the config _must_ use `#[serde(default)]` to maintain compatibility with
 //! older configs. These keys will be added to the user's configuration automatically.
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{RwLock, RwLockReadGuard};
 
 use crate::assets::Assets;
+use crate::color::{AnyColor, Srgb};
 use crate::keymap::Keymap;
+use crate::paint_canvas::DrawingBounds;
+use crate::ui::wm::WindowLayout;
 use crate::Error;
 use directories::ProjectDirs;
 use netcanv_i18n::unic_langid::LanguageIdentifier;
+use netcanv_renderer::paws::Color;
+use nysa::global as bus;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 
@@ -22,6 +28,14 @@ pub struct LobbyConfig {
    pub nickname: String,
    #[serde(alias = "matchmaker")]
    pub relay: String,
+   /// The region of the canvas that can be drawn to in rooms hosted by this user. `None` means
+   /// the canvas is unbounded.
+   #[serde(default)]
+   pub drawing_bounds: Option<DrawingBounds>,
+   /// Whether the region of the canvas visible on screen is shared with other peers in the room,
+   /// so that they can follow along. Off by default, for privacy.
+   #[serde(default)]
+   pub share_viewport: bool,
 }
 
 /// The color scheme variant.
@@ -29,6 +43,10 @@ pub struct LobbyConfig {
 pub enum ColorScheme {
    Light,
    Dark,
+   /// Pure black and white, with brightly colored highlights.
+   HighContrast,
+   /// Read from the user's [`ThemeFile`].
+   Custom,
 }
 
 /// The position of the toolbar.
@@ -38,6 +56,20 @@ pub enum ToolbarPosition {
    Left,
    /// Vertical on the right side of the screen.
    Right,
+   /// Horizontal on the top side of the screen.
+   Top,
+   /// Horizontal on the bottom side of the screen, above the bottom bar.
+   Bottom,
+   /// Vertical, detached from the edges and floating over the canvas, at the position stored
+   /// in [`UiConfig::floating_toolbar_position`].
+   Floating,
+}
+
+impl ToolbarPosition {
+   /// Returns whether the toolbar is laid out horizontally in this position.
+   pub fn is_horizontal(self) -> bool {
+      matches!(self, Self::Top | Self::Bottom)
+   }
 }
 
 impl Default for ToolbarPosition {
@@ -47,12 +79,404 @@ impl Default for ToolbarPosition {
    }
 }
 
+/// The easing curve used for interpolating peers' cursors and selections between network updates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PeerMotionEasing {
+   /// Constant speed.
+   Linear,
+   /// Fast at first, slowing down towards the end.
+   EaseOut,
+   /// Slow at the start and at the end, fast in the middle.
+   EaseInOut,
+}
+
+impl PeerMotionEasing {
+   /// Applies the easing curve to the interpolation coefficient `t`, which must lie within the
+   /// range `0.0..=1.0`.
+   pub fn apply(self, t: f32) -> f32 {
+      match self {
+         Self::Linear => t,
+         Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+         Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+      }
+   }
+}
+
+impl Default for PeerMotionEasing {
+   /// The default easing is a gentle ease-out.
+   fn default() -> Self {
+      Self::EaseOut
+   }
+}
+
 /// UI-related configuration options.
 #[derive(Deserialize, Serialize)]
 pub struct UiConfig {
    pub color_scheme: ColorScheme,
    #[serde(default)]
    pub toolbar_position: ToolbarPosition,
+   /// The position of the toolbar's top-left corner relative to the canvas's top-left corner, used
+   /// when the toolbar is floating.
+   #[serde(default)]
+   pub floating_toolbar_position: (f32, f32),
+   #[serde(default)]
+   pub peer_motion_easing: PeerMotionEasing,
+   /// Whether a crosshair is drawn at the canvas's origin.
+   #[serde(default = "default_show_origin_marker")]
+   pub show_origin_marker: bool,
+   /// Whether the minimap is shown in the corner of the canvas.
+   #[serde(default = "default_show_minimap")]
+   pub show_minimap: bool,
+   /// Whether frames are only rendered while something on screen is changing. When this is
+   /// disabled, frames are rendered continuously, which keeps the CPU and GPU busy even when idle.
+   #[serde(default = "default_redraw_only_when_needed")]
+   pub redraw_only_when_needed: bool,
+   /// Whether zooming the viewport is animated. When this is disabled, zooming takes effect
+   /// right away.
+   #[serde(default = "default_animate_viewport")]
+   pub animate_viewport: bool,
+   /// How fast the viewport is panned with the keyboard, in screen pixels per second. Holding
+   /// Shift pans faster.
+   #[serde(default = "default_keyboard_pan_speed")]
+   pub keyboard_pan_speed: f32,
+   /// The positions of pinned windows, such as the color picker, keyed by the kind of window.
+   #[serde(default)]
+   pub window_layout: HashMap<String, WindowLayout>,
+   /// The colors used recently in the color picker, most recent first, as RGBA tuples.
+   #[serde(default)]
+   pub recent_colors: Vec<(u8, u8, u8, u8)>,
+   /// The factor the UI is scaled up by. When unset or not a finite number, the window's scale
+   /// factor reported by the operating system is used.
+   #[serde(default)]
+   pub scale: Option<f32>,
+}
+
+/// Colors picked in the color picker, saved between sessions.
+///
+/// Colors are stored as RGBA tuples. A fully transparent color denotes the eraser.
+#[derive(Deserialize, Serialize)]
+pub struct ColorPickerConfig {
+   pub primary: (u8, u8, u8, u8),
+   pub secondary: (u8, u8, u8, u8),
+   /// The name of the palette that was last used.
+   #[serde(default = "default_palette_name")]
+   pub palette: String,
+}
+
+impl Default for ColorPickerConfig {
+   /// The default colors are black as the primary color, and the eraser as the secondary color.
+   fn default() -> Self {
+      Self {
+         primary: (0x10, 0x08, 0x20, 0xff),
+         secondary: (0, 0, 0, 0),
+         palette: default_palette_name(),
+      }
+   }
+}
+
+/// PNG compression level. Higher levels produce smaller files, but take longer to encode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PngCompression {
+   Fast,
+   Default,
+   Best,
+}
+
+/// The image format chunks are encoded in before being sent to other peers. Every peer can decode
+/// both formats, so this only affects the sender.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NetworkImageFormat {
+   /// Lossless PNG, with large chunks also encoded to lossy WebP. Whichever image is smaller
+   /// gets sent.
+   #[default]
+   Auto,
+   /// Always lossless PNG. Best for when WebP encoding is slow.
+   Png,
+   /// Always lossy WebP. Best for when PNG encoding is slow, or bandwidth is scarce.
+   WebP,
+}
+
+/// Image encoding options.
+#[derive(Deserialize, Serialize)]
+pub struct EncodingConfig {
+   /// The PNG compression level used when saving the canvas to disk.
+   pub save_png_compression: PngCompression,
+   /// The PNG compression level used when sending chunks to other peers.
+   pub network_png_compression: PngCompression,
+   /// The image format used when sending chunks to other peers.
+   #[serde(default)]
+   pub network_image_format: NetworkImageFormat,
+   /// Whether the canvas is cropped to the drawn pixels when saving it to a PNG file, leaving out
+   /// any fully transparent borders. This is the default picked in the dialog shown when saving as
+   /// PNG, which decides for the rest of the session.
+   #[serde(default)]
+   pub save_png_trim_borders: bool,
+   /// The factor the canvas is scaled by when saving it to a PNG file. For instance, `2.0` saves
+   /// the canvas at twice its resolution.
+   #[serde(default = "default_save_png_scale")]
+   pub save_png_scale: f32,
+   /// The color transparent areas are filled with when exporting to a format without an alpha
+   /// channel, such as JPEG.
+   #[serde(default = "default_export_background")]
+   pub export_background: (u8, u8, u8),
+}
+
+impl Default for EncodingConfig {
+   /// By default, saves favor smaller files, while network transfers favor lower latency.
+   fn default() -> Self {
+      Self {
+         save_png_compression: PngCompression::Best,
+         network_png_compression: PngCompression::Fast,
+         network_image_format: NetworkImageFormat::Auto,
+         save_png_trim_borders: false,
+         save_png_scale: default_save_png_scale(),
+         export_background: default_export_background(),
+      }
+   }
+}
+
+fn default_save_png_scale() -> f32 {
+   1.0
+}
+
+fn default_export_background() -> (u8, u8, u8) {
+   (255, 255, 255)
+}
+
+/// A format images can be exchanged through the clipboard in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ClipboardImageFormat {
+   /// The platform's native bitmap format.
+   Bitmap,
+   /// A PNG file, referred to by a path or `file://` URL stored in the clipboard as text.
+   ///
+   /// Copying in this format writes the image to a file in the temporary directory.
+   File,
+}
+
+/// How images larger than the maximum selection size are pasted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PasteScaling {
+   /// The image is scaled down to fit in a single selection.
+   FitToMaxSize,
+   /// The image is pasted at its original size. Images larger than the maximum selection size are
+   /// split into tiles; all tiles but the last are placed onto the canvas right away, and the last
+   /// one is left selected.
+   OriginalSize,
+}
+
+/// Where pasted images are placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PastePosition {
+   /// The image is centered on the screen.
+   Center,
+   /// The image is centered on the mouse cursor.
+   Cursor,
+}
+
+/// Clipboard options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+   /// The formats tried when copying and pasting images, in order of preference.
+   pub image_formats: Vec<ClipboardImageFormat>,
+   /// How pasted images are scaled.
+   pub paste_scaling: PasteScaling,
+   /// Where pasted images are placed.
+   pub paste_position: PastePosition,
+}
+
+impl Default for ClipboardConfig {
+   /// By default, the native bitmap format is preferred, and files are used as a fallback.
+   /// Pasted images are scaled down to fit in a single selection, and centered on the screen.
+   fn default() -> Self {
+      Self {
+         image_formats: vec![ClipboardImageFormat::Bitmap, ClipboardImageFormat::File],
+         paste_scaling: PasteScaling::FitToMaxSize,
+         paste_position: PastePosition::Center,
+      }
+   }
+}
+
+/// What ends up in a selection when it's captured from the canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SelectionCapture {
+   /// Only the pixels committed to the canvas are captured.
+   Canvas,
+   /// Other peers' selections overlapping the new one are captured too, such that the selection
+   /// contains everything that's visible on the screen.
+   Merged,
+}
+
+/// Selection tool options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct SelectionConfig {
+   /// What gets captured when a selection is made.
+   pub capture: SelectionCapture,
+}
+
+impl Default for SelectionConfig {
+   fn default() -> Self {
+      Self {
+         capture: SelectionCapture::Canvas,
+      }
+   }
+}
+
+/// How the pressure of a pen is mapped to the brush's thickness.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum PressureCurve {
+   /// The thickness is proportional to the pressure.
+   Linear,
+   /// The pressure is raised to the given exponent. Exponents above 1 make light strokes thinner,
+   /// while exponents below 1 make them thicker.
+   Gamma(f32),
+}
+
+impl PressureCurve {
+   /// Applies the curve to a pressure within the range `0.0..=1.0`.
+   pub fn apply(self, pressure: f32) -> f32 {
+      match self {
+         Self::Linear => pressure,
+         Self::Gamma(exponent) => pressure.powf(exponent.max(0.0)),
+      }
+   }
+}
+
+/// Brush tool options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct BrushConfig {
+   /// How the pressure of a pen affects the thickness of strokes.
+   pub pressure_curve: PressureCurve,
+}
+
+impl Default for BrushConfig {
+   fn default() -> Self {
+      Self {
+         pressure_curve: PressureCurve::Linear,
+      }
+   }
+}
+
+/// Bucket tool options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct BucketConfig {
+   /// The maximum width and height of a filled area, in pixels. The fill is bounded to a square of
+   /// this size centered on the clicked point, as the canvas itself is infinite.
+   pub max_fill_size: u32,
+}
+
+impl Default for BucketConfig {
+   fn default() -> Self {
+      Self {
+         max_fill_size: 2048,
+      }
+   }
+}
+
+/// Autosave options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct AutosaveConfig {
+   /// The number of seconds between autosaves of a canvas saved to a `.netcanv` folder. `0`
+   /// disables autosaving.
+   pub interval_secs: u64,
+   /// The number of previous saves kept as backups next to a `.netcanv` folder. The most recent
+   /// backup of `name.netcanv` is `name.netcanv.bak1`, the one before it `name.netcanv.bak2`,
+   /// and so on.
+   pub backups: u32,
+}
+
+impl Default for AutosaveConfig {
+   fn default() -> Self {
+      Self {
+         interval_secs: 60,
+         backups: 5,
+      }
+   }
+}
+
+/// Undo history options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+   /// The maximum number of actions that can be undone. Each action keeps copies of the chunks it
+   /// touched in memory, so raising this increases memory usage.
+   pub max_steps: usize,
+}
+
+impl Default for HistoryConfig {
+   fn default() -> Self {
+      Self { max_steps: 32 }
+   }
+}
+
+/// Paint canvas options.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct CanvasConfig {
+   /// The number of chunks kept in memory while in someone else's room, after which the chunks
+   /// that were out of view the longest are dropped. They're downloaded again from the host once
+   /// they come back into view. Each chunk takes up 256 KiB of video memory.
+   pub max_resident_chunks: usize,
+}
+
+impl Default for CanvasConfig {
+   fn default() -> Self {
+      Self {
+         max_resident_chunks: 4096,
+      }
+   }
+}
+
+/// The grid drawn over the canvas.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct GridConfig {
+   /// Whether the grid is shown.
+   pub show: bool,
+   /// The distance between grid lines, in pixels. This is rounded to the nearest divisor of the
+   /// chunk size (256), such that chunk boundaries always lie on grid lines.
+   pub spacing: u32,
+   /// The color of the grid lines, as an RGBA tuple.
+   pub color: (u8, u8, u8, u8),
+   /// Whether the selection tool snaps to grid lines while the grid is shown.
+   pub snap: bool,
+}
+
+impl Default for GridConfig {
+   fn default() -> Self {
+      Self {
+         show: false,
+         spacing: 32,
+         color: (0x80, 0x80, 0x80, 0x60),
+         snap: false,
+      }
+   }
+}
+
+/// The paint tools' state, saved between sessions.
+#[derive(Default, Deserialize, Serialize)]
+pub struct ToolsConfig {
+   /// The name of the last used tool.
+   pub current: Option<String>,
+   /// Each tool's settings, keyed by the tool's name.
+   pub settings: HashMap<String, toml::Value>,
+}
+
+/// Custom fonts, picked from the font files in the [`fonts_dir`]. The bundled fonts are used
+/// wherever a custom font isn't picked, or can't be loaded.
+#[derive(Default, Deserialize, Serialize)]
+pub struct FontConfig {
+   /// The font file used for the interface, relative to the fonts directory. The font replaces
+   /// both the regular and the bold font, as custom fonts often cover characters the bundled
+   /// fonts don't.
+   pub ui: Option<PathBuf>,
+   /// The font file used by the text tool, relative to the fonts directory.
+   pub text: Option<PathBuf>,
 }
 
 /// Window position and size.
@@ -76,35 +500,63 @@ pub struct UserConfig {
    pub ui: UiConfig,
    pub window: Option<WindowConfig>,
 
+   #[serde(default)]
+   pub color_picker: ColorPickerConfig,
+
+   #[serde(default)]
+   pub encoding: EncodingConfig,
+
+   #[serde(default)]
+   pub autosave: AutosaveConfig,
+
+   #[serde(default)]
+   pub clipboard: ClipboardConfig,
+
+   #[serde(default)]
+   pub selection: SelectionConfig,
+
+   #[serde(default)]
+   pub brush: BrushConfig,
+
+   #[serde(default)]
+   pub bucket: BucketConfig,
+
+   #[serde(default)]
+   pub history: HistoryConfig,
+
+   #[serde(default)]
+   pub canvas: CanvasConfig,
+
+   #[serde(default)]
+   pub grid: GridConfig,
+
+   #[serde(default)]
+   pub tools: ToolsConfig,
+
+   #[serde(default)]
+   pub fonts: FontConfig,
+
    #[serde(default)]
    pub keymap: Keymap,
 }
 
 impl UserConfig {
-   /// Returns the platform-specific configuration directory.
-   pub fn config_dir() -> PathBuf {
-      let project_dirs =
-         ProjectDirs::from("", "", "NetCanv").expect("cannot determine config directories");
-      project_dirs.config_dir().to_owned()
-   }
-
    /// Returns the path to the `config.toml` file.
    pub fn path() -> PathBuf {
-      Self::config_dir().join("config.toml")
+      config_dir().join("config.toml")
    }
 
-   /// Loads the `config.toml` file.
+   /// Loads the `config.toml` file from the given directory.
    ///
    /// If the `config.toml` doesn't exist, it's created with values inherited from
    /// `UserConfig::default`.
-   fn load_or_create() -> netcanv::Result<Self> {
-      let config_dir = Self::config_dir();
-      let config_file = Self::path();
+   fn load_or_create(config_dir: &Path) -> netcanv::Result<Self> {
+      let config_file = config_dir.join("config.toml");
       tracing::info!("loading config from {:?}", config_file);
       std::fs::create_dir_all(config_dir)?;
       if !config_file.is_file() {
          let config = Self::default();
-         config.save()?;
+         config.save_to(&config_file)?;
          Ok(config)
       } else {
          let file = std::fs::read_to_string(&config_file)?;
@@ -118,7 +570,7 @@ impl UserConfig {
          };
          // Preemptively save the config to the disk if any new keys have been added.
          // I'm not sure if errors should be treated as fatal or not in this case.
-         config.save()?;
+         config.save_to(&config_file)?;
          Ok(config)
       }
    }
@@ -126,7 +578,11 @@ impl UserConfig {
    /// Saves the user configuration to the `config.toml` file.
    fn save(&self) -> netcanv::Result<()> {
       // Assumes that `config_dir` was already created in `load_or_create`.
-      let config_file = Self::path();
+      self.save_to(&Self::path())
+   }
+
+   /// Saves the user configuration to the given file.
+   fn save_to(&self, config_file: &Path) -> netcanv::Result<()> {
       std::fs::write(config_file, toml::to_string(self)?)?;
       Ok(())
    }
@@ -139,17 +595,65 @@ impl Default for UserConfig {
          lobby: LobbyConfig {
             nickname: "Anon".to_owned(),
             relay: option_env!("NETCANV_DEFAULT_RELAY_URL").unwrap_or("ws://localhost").to_owned(),
+            drawing_bounds: None,
+            share_viewport: false,
          },
          ui: UiConfig {
             color_scheme: ColorScheme::Light,
             toolbar_position: ToolbarPosition::Left,
+            floating_toolbar_position: (0.0, 0.0),
+            peer_motion_easing: Default::default(),
+            show_origin_marker: default_show_origin_marker(),
+            show_minimap: default_show_minimap(),
+            redraw_only_when_needed: default_redraw_only_when_needed(),
+            animate_viewport: default_animate_viewport(),
+            keyboard_pan_speed: default_keyboard_pan_speed(),
+            window_layout: HashMap::new(),
+            recent_colors: Vec::new(),
+            scale: None,
          },
          window: None,
+         color_picker: Default::default(),
+         encoding: Default::default(),
+         autosave: Default::default(),
+         clipboard: Default::default(),
+         selection: Default::default(),
+         brush: Default::default(),
+         bucket: Default::default(),
+         history: Default::default(),
+         canvas: Default::default(),
+         grid: Default::default(),
+         tools: Default::default(),
+         fonts: Default::default(),
          keymap: Default::default(),
       }
    }
 }
 
+fn default_palette_name() -> String {
+   DEFAULT_PALETTE_NAME.to_owned()
+}
+
+fn default_show_origin_marker() -> bool {
+   true
+}
+
+fn default_show_minimap() -> bool {
+   true
+}
+
+fn default_redraw_only_when_needed() -> bool {
+   true
+}
+
+fn default_animate_viewport() -> bool {
+   true
+}
+
+fn default_keyboard_pan_speed() -> f32 {
+   800.0
+}
+
 fn default_language() -> String {
    fn inner() -> Option<String> {
       tracing::info!("language not yet determined, checking locale");
@@ -176,13 +680,266 @@ fn default_language() -> String {
    inner().unwrap_or_else(|| "en-US".to_string())
 }
 
+/// The name of the palette the color picker starts out with.
+pub const DEFAULT_PALETTE_NAME: &str = "default";
+
+/// A palette file, as stored in the `palettes` directory.
+///
+/// Colors are stored as `#RRGGBB` hex codes, such that palettes are easy to edit by hand.
+#[derive(Deserialize, Serialize)]
+struct PaletteFile {
+   colors: Vec<String>,
+}
+
+impl PaletteFile {
+   /// Creates a palette file listing the given colors.
+   fn new(colors: &[AnyColor]) -> Self {
+      let colors = colors
+         .iter()
+         .map(|&color| {
+            let Color { r, g, b, .. } = Srgb::from(color).to_color(1.0);
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+         })
+         .collect();
+      Self { colors }
+   }
+
+   /// Parses the colors listed in the file. Returns `None` if any of them is not a valid hex
+   /// code.
+   fn colors(&self) -> Option<Vec<AnyColor>> {
+      fn parse_color(hex_code: &str) -> Option<AnyColor> {
+         let hex_code = hex_code.strip_prefix('#').unwrap_or(hex_code);
+         if hex_code.len() != 6 {
+            return None;
+         }
+         let hex = u32::from_str_radix(hex_code, 16).ok()?;
+         Some(Srgb::from_color(Color::rgb(hex)).into())
+      }
+
+      self.colors.iter().map(|color| parse_color(color)).collect()
+   }
+}
+
+/// Returns the directory palettes are stored in.
+pub fn palettes_dir() -> PathBuf {
+   config_dir().join("palettes")
+}
+
+/// Returns whether the name can be used as a palette's file name.
+fn is_valid_palette_name(name: &str) -> bool {
+   !name.is_empty()
+      && !name.starts_with('.')
+      && name.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+}
+
+/// Returns the path to the file of the palette with the given name.
+fn palette_path(name: &str) -> netcanv::Result<PathBuf> {
+   if !is_valid_palette_name(name) {
+      return Err(Error::InvalidPaletteName {
+         name: name.to_owned(),
+      });
+   }
+   Ok(palettes_dir().join(format!("{}.toml", name)))
+}
+
+/// Returns the names of all palettes saved to disk, sorted alphabetically.
+pub fn palette_names() -> Vec<String> {
+   let Ok(entries) = std::fs::read_dir(palettes_dir()) else {
+      return Vec::new();
+   };
+   let mut names: Vec<_> = entries
+      .filter_map(|entry| {
+         let path = entry.ok()?.path();
+         if path.extension()? != "toml" {
+            return None;
+         }
+         let name = path.file_stem()?.to_str()?;
+         is_valid_palette_name(name).then(|| name.to_owned())
+      })
+      .collect();
+   names.sort();
+   names
+}
+
+/// Loads the palette with the given name.
+///
+/// Returns `None` if the palette doesn't exist. Malformed palettes are treated as if they didn't
+/// exist, and a warning is logged, such that the caller can fall back to the default palette.
+pub fn load_palette(name: &str) -> Option<Vec<AnyColor>> {
+   let path = palette_path(name).ok()?;
+   if !path.is_file() {
+      return None;
+   }
+   let file = match std::fs::read_to_string(&path) {
+      Ok(file) => file,
+      Err(error) => {
+         tracing::warn!("cannot read palette {:?}: {}", path, error);
+         return None;
+      }
+   };
+   let palette: PaletteFile = match toml::from_str(&file) {
+      Ok(palette) => palette,
+      Err(error) => {
+         tracing::warn!("malformed palette {:?}: {}", path, error);
+         return None;
+      }
+   };
+   let colors = palette.colors();
+   if colors.is_none() {
+      tracing::warn!("palette {:?} contains an invalid color", path);
+   }
+   colors
+}
+
+/// Saves the palette under the given name, overwriting the palette previously saved under that
+/// name.
+pub fn save_palette(name: &str, colors: &[AnyColor]) -> netcanv::Result<()> {
+   let path = palette_path(name)?;
+   std::fs::create_dir_all(palettes_dir())?;
+   std::fs::write(path, toml::to_string(&PaletteFile::new(colors))?)?;
+   Ok(())
+}
+
+/// A custom theme file, `theme.toml` in the config directory.
+///
+/// A theme starts out as one of the built-in color schemes, and overrides some of its colors with
+/// `#RRGGBB` hex codes, such that it can be made to match the rest of the desktop. For example:
+///
+/// ```toml
+/// base = "Dark"
+///
+/// [colors]
+/// gray_80 = "#2e3440"
+/// blue_50 = "#88c0d0"
+/// ```
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ThemeFile {
+   /// The color scheme the theme is based on.
+   pub base: ColorScheme,
+   /// The colors overridden by the theme, keyed by their names.
+   pub colors: HashMap<String, String>,
+}
+
+impl Default for ThemeFile {
+   fn default() -> Self {
+      Self {
+         base: ColorScheme::Light,
+         colors: HashMap::new(),
+      }
+   }
+}
+
+/// Returns the path to the [`ThemeFile`].
+pub fn theme_path() -> PathBuf {
+   config_dir().join("theme.toml")
+}
+
+/// Loads the user's [`ThemeFile`].
+pub fn load_theme() -> netcanv::Result<ThemeFile> {
+   let path = theme_path();
+   if !path.is_file() {
+      return Err(Error::ThemeFileDoesNotExist {
+         path: path.display().to_string(),
+      });
+   }
+   let file = std::fs::read_to_string(&path)?;
+   Ok(toml::from_str(&file)?)
+}
+
+/// Returns the directory custom fonts are loaded from.
+pub fn fonts_dir() -> PathBuf {
+   config_dir().join("fonts")
+}
+
+/// Returns the paths of all font files in the [`fonts_dir`], relative to it and sorted
+/// alphabetically.
+///
+/// Only the file extensions are looked at, so the files aren't guaranteed to contain valid fonts.
+pub fn font_files() -> Vec<PathBuf> {
+   font_files_in(&fonts_dir())
+}
+
+/// Returns the paths of all font files in the given directory, relative to it and sorted
+/// alphabetically.
+fn font_files_in(dir: &Path) -> Vec<PathBuf> {
+   let Ok(entries) = std::fs::read_dir(dir) else {
+      return Vec::new();
+   };
+   let mut files: Vec<_> = entries
+      .filter_map(|entry| {
+         let path = entry.ok()?.path();
+         let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+         if !matches!(extension.as_str(), "ttf" | "otf" | "ttc") || !path.is_file() {
+            return None;
+         }
+         path.file_name().map(PathBuf::from)
+      })
+      .collect();
+   files.sort();
+   files
+}
+
+static CONFIG_DIR: OnceCell<PathBuf> = OnceCell::new();
 static CONFIG: OnceCell<RwLock<UserConfig>> = OnceCell::new();
 
+/// Returns the platform-specific configuration directory.
+pub fn default_config_dir() -> PathBuf {
+   let project_dirs =
+      ProjectDirs::from("", "", "NetCanv").expect("cannot determine config directories");
+   project_dirs.config_dir().to_owned()
+}
+
+/// Returns the directory the user config and other persistent data is stored in.
+pub fn config_dir() -> &'static Path {
+   CONFIG_DIR.get().expect("attempt to get the config directory without loading the config")
+}
+
+/// Returns the directory the user config is stored in, or `None` if the config isn't loaded yet.
+pub fn try_config_dir() -> Option<&'static Path> {
+   CONFIG_DIR.get().map(PathBuf::as_path)
+}
+
+/// Creates the directory if it doesn't exist, and checks whether files can be written to it.
+fn ensure_writable(dir: &Path) -> std::io::Result<()> {
+   std::fs::create_dir_all(dir)?;
+   let probe = dir.join(".netcanv-write-test");
+   std::fs::write(&probe, [])?;
+   std::fs::remove_file(&probe)
+}
+
+/// Picks the directory to store the config in.
+///
+/// If the user-provided directory is not writable, an error is reported and the platform-specific
+/// default is used instead.
+fn choose_config_dir(custom: Option<&Path>) -> PathBuf {
+   if let Some(dir) = custom {
+      match ensure_writable(dir) {
+         Ok(()) => return dir.to_owned(),
+         Err(error) => {
+            tracing::error!("config directory {:?} is not writable: {}", dir, error);
+            bus::push(crate::common::Error(Error::ConfigDirIsNotWritable {
+               dir: dir.display().to_string(),
+               error: error.to_string(),
+            }));
+         }
+      }
+   }
+   default_config_dir()
+}
+
 /// Loads or creates the user config.
-pub fn load_or_create() -> netcanv::Result<()> {
+///
+/// `config_dir` overrides the directory the config is stored in. If it's `None`, the
+/// platform-specific default is used.
+pub fn load_or_create(config_dir: Option<&Path>) -> netcanv::Result<()> {
    profiling::scope!("config::load_or_create");
 
-   let config = UserConfig::load_or_create()?;
+   let config_dir = choose_config_dir(config_dir);
+   let config = UserConfig::load_or_create(&config_dir)?;
+   if CONFIG_DIR.set(config_dir).is_err() {
+      return Err(Error::ConfigIsAlreadyLoaded);
+   }
    if CONFIG.set(RwLock::new(config)).is_err() {
       return Err(Error::ConfigIsAlreadyLoaded);
    }
@@ -209,3 +966,62 @@ pub fn write(f: impl FnOnce(&mut UserConfig)) {
    }
    catch!(save());
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn palette_names_are_safe_file_names() {
+      assert!(is_valid_palette_name("Sunset 2"));
+      assert!(is_valid_palette_name("pastel_greens-dark"));
+      assert!(!is_valid_palette_name(""));
+      assert!(!is_valid_palette_name(".hidden"));
+      assert!(!is_valid_palette_name("../theme"));
+      assert!(!is_valid_palette_name("a/b"));
+      assert!(!is_valid_palette_name("C:evil"));
+   }
+
+   #[test]
+   fn palette_files_round_trip() {
+      let colors: Vec<AnyColor> =
+         [0x100820, 0xff7b00, 0xeff5f0].map(|hex| Srgb::from_color(Color::rgb(hex)).into()).into();
+      let file = toml::to_string(&PaletteFile::new(&colors)).unwrap();
+      assert_eq!(file, "colors = [\"#100820\", \"#ff7b00\", \"#eff5f0\"]\n");
+      let file: PaletteFile = toml::from_str(&file).unwrap();
+      assert_eq!(file.colors(), Some(colors));
+   }
+
+   #[test]
+   fn palettes_with_invalid_colors_are_rejected() {
+      for color in ["#12345", "#1234567", "#ggffff", "red"] {
+         let file = PaletteFile {
+            colors: vec!["#ffffff".to_owned(), color.to_owned()],
+         };
+         assert_eq!(file.colors(), None, "{color} was accepted");
+      }
+      // The leading hash is optional.
+      let file = PaletteFile {
+         colors: vec!["ff0000".to_owned()],
+      };
+      assert_eq!(
+         file.colors(),
+         Some(vec![Srgb::from_color(Color::rgb(0xff0000)).into()])
+      );
+   }
+
+   #[test]
+   fn only_font_files_are_listed() {
+      let directory = tempfile::tempdir().unwrap();
+      for file in ["b.otf", "a.TTF", "c.ttc", "notes.txt", "ttf"] {
+         std::fs::write(directory.path().join(file), b"").unwrap();
+      }
+      std::fs::create_dir(directory.path().join("folder.ttf")).unwrap();
+      assert_eq!(
+         font_files_in(directory.path()),
+         [PathBuf::from("a.TTF"), "b.otf".into(), "c.ttc".into()]
+      );
+
+      assert!(font_files_in(&directory.path().join("missing")).is_empty());
+   }
+}
@@ -0,0 +1,215 @@
+//! Crash reports.
+//!
+//! Log output is kept in memory while the app is running, so that when it panics, a report with
+//! the most recent log messages can be written next to the user config. Messages logged at startup
+//! (such as the version and graphics adapter) are always kept, no matter how long the app has
+//! been running.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::{const_mutex, Mutex};
+
+use crate::config;
+
+/// Log messages kept around for crash reports.
+struct LogBuffer {
+   /// The first lines logged after startup.
+   head: Vec<String>,
+   /// The most recent lines.
+   tail: VecDeque<String>,
+   /// The number of lines that were dropped between the head and the tail.
+   dropped: usize,
+   /// A line that hasn't been terminated yet.
+   partial: String,
+}
+
+impl LogBuffer {
+   const HEAD_LINES: usize = 64;
+   const TAIL_LINES: usize = 256;
+
+   fn push_line(&mut self, line: String) {
+      if self.head.len() < Self::HEAD_LINES {
+         self.head.push(line);
+         return;
+      }
+      if self.tail.len() >= Self::TAIL_LINES {
+         self.tail.pop_front();
+         self.dropped += 1;
+      }
+      self.tail.push_back(line);
+   }
+}
+
+static LOG_BUFFER: Mutex<LogBuffer> = const_mutex(LogBuffer {
+   head: Vec::new(),
+   tail: VecDeque::new(),
+   dropped: 0,
+   partial: String::new(),
+});
+
+/// A writer that appends log output to the buffer included in crash reports.
+pub struct LogWriter;
+
+impl io::Write for LogWriter {
+   fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      let mut buffer = LOG_BUFFER.lock();
+      buffer.partial.push_str(&String::from_utf8_lossy(buf));
+      while let Some(newline) = buffer.partial.find('\n') {
+         let line = buffer.partial[..newline].to_owned();
+         buffer.partial.drain(..=newline);
+         buffer.push_line(line);
+      }
+      Ok(buf.len())
+   }
+
+   fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+   }
+}
+
+/// Keys whose values are replaced with a placeholder before a report is written.
+const SENSITIVE_KEYS: &[&str] = &["password", "secret", "token"];
+
+/// Returns whether the character can be a part of a key's name.
+fn is_key_char(c: char) -> bool {
+   c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Replaces the values of sensitive keys (such as `password = hunter2`) in the line.
+///
+/// Keys only match as whole words followed by `=` or `:`, so that lines merely mentioning them
+/// (such as `tokens: 3` or `invalid password`) are left intact.
+fn redact(line: &str) -> String {
+   let lowercase = line.to_ascii_lowercase();
+   let mut redacted = String::with_capacity(line.len());
+   let mut copied = 0;
+   let mut position = 0;
+   while let Some((key_start, key_len)) = SENSITIVE_KEYS
+      .iter()
+      .filter_map(|key| lowercase[position..].find(key).map(|index| (position + index, key.len())))
+      .min()
+   {
+      let key_end = key_start + key_len;
+      position = key_end;
+      if lowercase[..key_start].ends_with(is_key_char)
+         || lowercase[key_end..].starts_with(is_key_char)
+      {
+         continue;
+      }
+      // The key may be quoted, like in JSON.
+      let after_key = line[key_end..].trim_start_matches(['"', '\'']).trim_start();
+      let Some(after_separator) = after_key.strip_prefix(['=', ':']) else {
+         continue;
+      };
+      let value = after_separator.trim_start().trim_start_matches(['"', '\'']);
+      let value_start = line.len() - value.len();
+      let value_len = value
+         .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | '}' | ')'))
+         .unwrap_or(value.len());
+      if value_len == 0 {
+         continue;
+      }
+      redacted.push_str(&line[copied..value_start]);
+      redacted.push_str("[redacted]");
+      copied = value_start + value_len;
+      position = copied;
+   }
+   redacted.push_str(&line[copied..]);
+   redacted
+}
+
+/// Formats the contents of a crash report.
+fn format_report(panic_info: &PanicHookInfo) -> String {
+   let mut report = String::new();
+   let _ = writeln!(report, "NetCanv {}", env!("CARGO_PKG_VERSION"));
+   let _ = writeln!(
+      report,
+      "Platform: {} {}",
+      std::env::consts::OS,
+      std::env::consts::ARCH
+   );
+   let _ = writeln!(report, "\n{}", panic_info);
+   let _ = writeln!(
+      report,
+      "\nBacktrace:\n{}",
+      std::backtrace::Backtrace::force_capture()
+   );
+
+   let _ = writeln!(report, "Log:");
+   // The panic may have happened while the log buffer was locked, so don't wait for it.
+   match LOG_BUFFER.try_lock() {
+      Some(buffer) => {
+         for line in &buffer.head {
+            let _ = writeln!(report, "{}", line);
+         }
+         if buffer.dropped > 0 {
+            let _ = writeln!(report, "[{} lines omitted]", buffer.dropped);
+         }
+         for line in buffer.tail.iter().chain(Some(&buffer.partial)) {
+            let _ = writeln!(report, "{}", line);
+         }
+      }
+      None => {
+         let _ = writeln!(report, "[the log is not available]");
+      }
+   }
+
+   report.lines().map(redact).collect::<Vec<_>>().join("\n")
+}
+
+/// Writes a crash report for the given panic into the config directory, and returns the path to
+/// the report.
+pub fn write(panic_info: &PanicHookInfo) -> io::Result<PathBuf> {
+   let directory =
+      config::try_config_dir().map(|dir| dir.to_owned()).unwrap_or_else(config::default_config_dir);
+   let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+   let path = directory.join(format!("crash-{}.log", timestamp));
+   std::fs::create_dir_all(&directory)?;
+   std::fs::write(&path, format_report(panic_info))?;
+   Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn values_of_sensitive_keys_are_redacted() {
+      assert_eq!(redact("password = hunter2"), "password = [redacted]");
+      assert_eq!(
+         redact("Token: abc123 expires soon"),
+         "Token: [redacted] expires soon"
+      );
+      assert_eq!(
+         redact("login(password=hunter2)"),
+         "login(password=[redacted])"
+      );
+      assert_eq!(
+         redact(r#"{"secret":"s3cr3t","user":"anon"}"#),
+         r#"{"secret":"[redacted]","user":"anon"}"#
+      );
+      assert_eq!(
+         redact("token='a' and secret = b"),
+         "token='[redacted]' and secret = [redacted]"
+      );
+   }
+
+   #[test]
+   fn lines_merely_mentioning_sensitive_keys_are_left_intact() {
+      for line in [
+         "tokens = 3",
+         "tokenizer=whitespace",
+         "the token expired",
+         "invalid password",
+         "secretary: anon",
+         "password =",
+      ] {
+         assert_eq!(redact(line), line);
+      }
+   }
+}
@@ -17,6 +17,7 @@ pub enum Error {
    Io { error: String },
    Image { error: String },
    Join { error: String },
+   Zip { error: String },
    ChannelSend,
    TomlParse { error: String },
    TomlSerialization { error: String },
@@ -41,6 +42,7 @@ pub enum Error {
    //
    CouldNotInitializeBackend { error: String },
    CouldNotInitializeLogger { error: String },
+   LogLevelIsFixed,
 
    //
    // Command line interface
@@ -64,6 +66,13 @@ pub enum Error {
    // User config
    //
    ConfigIsAlreadyLoaded,
+   ConflictingKeyBindings { conflicts: String },
+   ConflictingMouseButtons { conflicts: String },
+   ConfigDirIsNotWritable { dir: String, error: String },
+   InvalidPaletteName { name: String },
+   ThemeFileDoesNotExist { path: String },
+   UnknownThemeColor { name: String },
+   InvalidThemeColor { name: String, color: String },
 
    //
    // Translations
@@ -84,12 +93,19 @@ pub enum Error {
    InvalidChunkImageFormat,
    InvalidChunkImageSize,
    NothingToSave,
+   InvalidExportScale,
    InvalidCanvasFolder,
    UnsupportedSaveFormat,
+   UnsupportedExportFormat,
    MissingCanvasSaveExtension,
    InvalidChunkPositionPattern,
    TrailingChunkCoordinatesInFilename,
    CanvasTomlVersionMismatch,
+   ProjectLoadingInterrupted,
+   InvalidOraFile { error: String },
+   SaveAlreadyInProgress,
+   SaveInterrupted,
+   UnsupportedImageFile { file: String },
 
    //
    // Socket networking
@@ -102,6 +118,7 @@ pub enum Error {
    ReceivedPacketThatIsTooBig,
    TriedToSendPacketThatIsTooBig { max: usize, size: usize },
    TriedToSendPacketThatIsWayTooBig,
+   InvalidPacketFraming { error: String },
    RelayHasDisconnected,
    WebSocket { error: String },
 
@@ -138,6 +155,7 @@ macro_rules! error_from {
 error_from!(std::io::Error, Error::Io);
 error_from!(ImageError, Error::Image);
 error_from!(JoinError, Error::Join);
+error_from!(zip::result::ZipError, Error::Zip);
 error_from!(toml::de::Error, Error::TomlParse);
 error_from!(toml::ser::Error, Error::TomlSerialization);
 error_from!(tungstenite::Error, Error::WebSocket);
@@ -1,14 +1,50 @@
+use std::ffi::OsStr;
 use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use ::image::codecs::png::{PngDecoder, PngEncoder};
+use ::image::codecs::jpeg::JpegEncoder;
+use ::image::codecs::png::{CompressionType, FilterType, PngDecoder, PngEncoder};
 use ::image::codecs::webp::{WebPDecoder, WebPEncoder, WebPQuality};
-use ::image::{ColorType, ImageDecoder, Rgba, RgbaImage};
+use ::image::{ColorType, ImageDecoder, Pixel, RgbImage, Rgba, RgbaImage};
 use image::{DynamicImage, ImageEncoder};
 
+use crate::config::{config, NetworkImageFormat, PngCompression};
 use crate::paint_canvas::cache_layer::CachedChunk;
 use crate::paint_canvas::chunk::Chunk;
 use crate::Error;
 
+/// A file format the user can export images to.
+///
+/// Unlike images sent over the network, exported images may be encoded lossily.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+   Png,
+   Jpeg,
+   WebP,
+}
+
+impl ExportFormat {
+   /// Picks the format based on the extension of the given path.
+   pub fn from_path(path: &Path) -> Option<Self> {
+      let extension = path.extension().and_then(OsStr::to_str)?.to_ascii_lowercase();
+      match extension.as_str() {
+         "png" => Some(Self::Png),
+         "jpg" | "jpeg" => Some(Self::Jpeg),
+         "webp" => Some(Self::WebP),
+         _ => None,
+      }
+   }
+
+   /// Returns whether the format is lossy, and thus takes a quality setting.
+   pub fn is_lossy(self) -> bool {
+      matches!(self, Self::Jpeg | Self::WebP)
+   }
+}
+
+/// Set once encoding a WebP image fails, so that network images stop being encoded to WebP.
+static WEBP_ENCODING_UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
 pub struct ImageCoder;
 
 impl ImageCoder {
@@ -16,70 +52,56 @@ impl ImageCoder {
    /// transmission.
    const MAX_PNG_SIZE: usize = 32 * 1024;
 
-   /// Encodes an image to PNG data asynchronously.
-   pub async fn encode_png_data(image: RgbaImage) -> netcanv::Result<Vec<u8>> {
-      tokio::task::spawn_blocking(move || {
-         let mut bytes: Vec<u8> = Vec::new();
-         match PngEncoder::new(Cursor::new(&mut bytes)).write_image(
-            &image,
-            image.width(),
-            image.height(),
-            ColorType::Rgba8,
-         ) {
-            Ok(()) => (),
-            Err(error) => {
-               tracing::error!("error while encoding: {}", error);
-               return Err(error.into());
-            }
+   /// Encodes an image to PNG data with the given compression level.
+   pub fn encode_png(image: &RgbaImage, compression: PngCompression) -> netcanv::Result<Vec<u8>> {
+      let (compression, filter) = match compression {
+         PngCompression::Fast => (CompressionType::Fast, FilterType::NoFilter),
+         PngCompression::Default => (CompressionType::Default, FilterType::Adaptive),
+         PngCompression::Best => (CompressionType::Best, FilterType::Adaptive),
+      };
+      let mut bytes: Vec<u8> = Vec::new();
+      let encoder = PngEncoder::new_with_quality(Cursor::new(&mut bytes), compression, filter);
+      match encoder.write_image(image, image.width(), image.height(), ColorType::Rgba8) {
+         Ok(()) => (),
+         Err(error) => {
+            tracing::error!("error while encoding: {}", error);
+            return Err(error.into());
          }
-         Ok(bytes)
-      })
-      .await?
+      }
+      Ok(bytes)
    }
 
-   /// Encodes an image to WebP asynchronously.
-   async fn encode_webp_data(image: RgbaImage) -> netcanv::Result<Vec<u8>> {
-      tokio::task::spawn_blocking(move || {
-         let mut bytes: Vec<u8> = Vec::new();
-         match WebPEncoder::new_with_quality(
-            Cursor::new(&mut bytes),
-            WebPQuality::lossy(WebPQuality::DEFAULT),
-         )
-         .write_image(&image, image.width(), image.height(), ColorType::Rgba8)
-         {
-            Ok(()) => (),
-            Err(error) => {
-               tracing::error!("error while encoding: {}", error);
-               return Err(error.into());
-            }
+   /// Encodes an image to WebP data with the given quality.
+   fn encode_webp(image: &RgbaImage, quality: WebPQuality) -> netcanv::Result<Vec<u8>> {
+      let mut bytes: Vec<u8> = Vec::new();
+      let encoder = WebPEncoder::new_with_quality(Cursor::new(&mut bytes), quality);
+      match encoder.write_image(image, image.width(), image.height(), ColorType::Rgba8) {
+         Ok(()) => (),
+         Err(error) => {
+            tracing::error!("error while encoding: {}", error);
+            return Err(error.into());
          }
-         Ok(bytes)
-      })
-      .await?
-   }
-
-   /// Encodes a network image asynchronously. This encodes PNG, as well as WebP if the PNG is too
-   /// large, and returns both images.
-   pub async fn encode_network_data(image: RgbaImage) -> netcanv::Result<CachedChunk> {
-      let png = Self::encode_png_data(image.clone()).await?;
-      let webp = if png.len() > Self::MAX_PNG_SIZE {
-         tracing::debug!("webp");
-         Some(Self::encode_webp_data(image).await?)
-      } else {
-         None
-      };
-      Ok(CachedChunk { png, webp })
+      }
+      Ok(bytes)
    }
 
-   /// Encodes an image to PNG data synchronously.
-   pub fn encode_png_data_sync(image: RgbaImage) -> netcanv::Result<Vec<u8>> {
+   /// Encodes an image to JPEG data with the given quality.
+   ///
+   /// JPEG has no alpha channel, so the image is composited onto the given background color.
+   fn encode_jpeg(
+      image: &RgbaImage,
+      quality: u8,
+      background: (u8, u8, u8),
+   ) -> netcanv::Result<Vec<u8>> {
+      let (r, g, b) = background;
+      let image = RgbImage::from_fn(image.width(), image.height(), |x, y| {
+         let mut pixel = Rgba([r, g, b, 255]);
+         pixel.blend(image.get_pixel(x, y));
+         pixel.to_rgb()
+      });
       let mut bytes: Vec<u8> = Vec::new();
-      match PngEncoder::new(Cursor::new(&mut bytes)).write_image(
-         &image,
-         image.width(),
-         image.height(),
-         ColorType::Rgba8,
-      ) {
+      let encoder = JpegEncoder::new_with_quality(Cursor::new(&mut bytes), quality);
+      match encoder.write_image(&image, image.width(), image.height(), ColorType::Rgb8) {
          Ok(()) => (),
          Err(error) => {
             tracing::error!("error while encoding: {}", error);
@@ -89,6 +111,129 @@ impl ImageCoder {
       Ok(bytes)
    }
 
+   /// Encodes an image for exporting to a file, in the given format.
+   ///
+   /// `quality` ranges from 1 to 100, and is only used by lossy formats. PNGs are compressed with
+   /// the level from the user's config, and JPEGs are put onto the export background color from
+   /// the user's config.
+   pub fn encode_image(
+      image: &RgbaImage,
+      format: ExportFormat,
+      quality: u8,
+   ) -> netcanv::Result<Vec<u8>> {
+      let quality = quality.clamp(1, 100);
+      match format {
+         ExportFormat::Png => Self::encode_png(image, config().encoding.save_png_compression),
+         ExportFormat::Jpeg => {
+            Self::encode_jpeg(image, quality, config().encoding.export_background)
+         }
+         ExportFormat::WebP => Self::encode_webp(image, WebPQuality::lossy(quality)),
+      }
+   }
+
+   /// Encodes a network image synchronously, in the given format and with the given PNG
+   /// compression level.
+   ///
+   /// With [`NetworkImageFormat::Auto`], the image is encoded to PNG, and if the PNG is too large,
+   /// also to lossy WebP; the smaller of the two is returned. If WebP encoding turns out to be
+   /// unsupported, PNG is used from then on.
+   ///
+   /// The output depends only on the arguments, and not on the user's config.
+   pub fn encode_network_data_sync(
+      image: &RgbaImage,
+      compression: PngCompression,
+      format: NetworkImageFormat,
+   ) -> netcanv::Result<CachedChunk> {
+      let data = match format {
+         NetworkImageFormat::Png => Self::encode_png(image, compression)?,
+         NetworkImageFormat::WebP => match Self::try_encode_network_webp(image) {
+            Some(webp) => webp,
+            None => Self::encode_png(image, compression)?,
+         },
+         NetworkImageFormat::Auto => {
+            let png = Self::encode_png(image, compression)?;
+            if png.len() > Self::MAX_PNG_SIZE {
+               match Self::try_encode_network_webp(image) {
+                  Some(webp) if webp.len() < png.len() => webp,
+                  _ => png,
+               }
+            } else {
+               png
+            }
+         }
+      };
+      Ok(CachedChunk { data })
+   }
+
+   /// Encodes a network image to lossy WebP, or returns `None` if WebP encoding is not supported.
+   fn try_encode_network_webp(image: &RgbaImage) -> Option<Vec<u8>> {
+      if WEBP_ENCODING_UNSUPPORTED.load(Ordering::Relaxed) {
+         return None;
+      }
+      match Self::encode_webp(image, WebPQuality::lossy(WebPQuality::DEFAULT)) {
+         Ok(webp) => Some(webp),
+         Err(error) => {
+            tracing::warn!("WebP encoding failed, falling back to PNG: {}", error);
+            WEBP_ENCODING_UNSUPPORTED.store(true, Ordering::Relaxed);
+            None
+         }
+      }
+   }
+
+   /// Encodes a network image asynchronously, using the PNG compression level and image format
+   /// from the user's config. See [`ImageCoder::encode_network_data_sync`].
+   pub async fn encode_network_data(image: RgbaImage) -> netcanv::Result<CachedChunk> {
+      let (compression, format) = {
+         let config = config();
+         (
+            config.encoding.network_png_compression,
+            config.encoding.network_image_format,
+         )
+      };
+      tokio::task::spawn_blocking(move || {
+         Self::encode_network_data_sync(&image, compression, format)
+      })
+      .await?
+   }
+
+   /// Encodes a region of a chunk to PNG data for sending over the network, using the PNG
+   /// compression level from the user's config.
+   ///
+   /// Patches are always lossless, as lossy artifacts would accumulate in chunks that are patched
+   /// repeatedly.
+   pub fn encode_network_patch(image: &RgbaImage) -> netcanv::Result<Vec<u8>> {
+      Self::encode_png(image, config().encoding.network_png_compression)
+   }
+
+   /// Decodes a PNG image replacing a region of a chunk, whose top-left corner lies at `offset`.
+   /// The region must lie within the chunk.
+   pub fn decode_network_patch(data: &[u8], offset: (u32, u32)) -> netcanv::Result<RgbaImage> {
+      let decoder = PngDecoder::new(Cursor::new(data))?;
+      if decoder.color_type() != ColorType::Rgba8 {
+         tracing::warn!("received non-RGBA patch data, ignoring");
+         return Err(Error::NonRgbaChunkImage);
+      }
+      let (width, height) = decoder.dimensions();
+      let fits_horizontally = offset.0.checked_add(width).map_or(false, |x| x <= Chunk::SIZE.0);
+      let fits_vertically = offset.1.checked_add(height).map_or(false, |y| y <= Chunk::SIZE.1);
+      if !fits_horizontally || !fits_vertically {
+         tracing::warn!(
+            "chunk patch of size {:?} at {:?} does not fit in a chunk",
+            (width, height),
+            offset
+         );
+         return Err(Error::InvalidChunkImageSize);
+      }
+      let mut image = RgbaImage::new(width, height);
+      decoder.read_image(&mut image)?;
+      Ok(image)
+   }
+
+   /// Encodes an image to PNG data synchronously, for saving to disk.
+   pub fn encode_png_data_sync(image: RgbaImage) -> netcanv::Result<Vec<u8>> {
+      Self::encode_png(&image, config().encoding.save_png_compression)
+   }
+
    /// Decodes a PNG file into the given sub-chunk.
    pub fn decode_png_data(data: &[u8]) -> netcanv::Result<RgbaImage> {
       let decoder = PngDecoder::new(Cursor::new(data))?;
@@ -96,6 +241,12 @@ impl ImageCoder {
          tracing::warn!("received non-RGBA image data, ignoring");
          return Err(Error::NonRgbaChunkImage);
       }
+      // Reading an image into a buffer of the wrong size panics, so this has to be checked
+      // beforehand.
+      if decoder.dimensions() != Chunk::SIZE {
+         tracing::warn!("chunk image has an invalid size: {:?}", decoder.dimensions());
+         return Err(Error::InvalidChunkImageSize);
+      }
       let mut image = RgbaImage::from_pixel(Chunk::SIZE.0, Chunk::SIZE.1, Rgba([0, 0, 0, 0]));
       decoder.read_image(&mut image)?;
       Ok(image)
@@ -125,3 +276,156 @@ impl ImageCoder {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   /// Returns a chunk-sized image in which no two neighboring pixels are the same. None of the
+   /// pixels are fully transparent, as encoders are free to change the color of those.
+   fn test_image() -> RgbaImage {
+      RgbaImage::from_fn(Chunk::SIZE.0, Chunk::SIZE.1, |x, y| {
+         Rgba([x as u8, y as u8, (x ^ y) as u8, 255 - (x / 2) as u8])
+      })
+   }
+
+   #[test]
+   fn png_round_trip_is_lossless() {
+      let image = test_image();
+      for compression in [
+         PngCompression::Fast,
+         PngCompression::Default,
+         PngCompression::Best,
+      ] {
+         let png = ImageCoder::encode_png(&image, compression).unwrap();
+         assert_eq!(ImageCoder::decode_png_data(&png).unwrap(), image);
+      }
+   }
+
+   /// Returns a chunk-sized image resembling a typical painted chunk: mostly transparent, with a
+   /// few solid, antialiased strokes running across it.
+   fn painted_chunk() -> RgbaImage {
+      RgbaImage::from_fn(Chunk::SIZE.0, Chunk::SIZE.1, |x, y| {
+         let (x, y) = (x as f32, y as f32);
+         let strokes = [
+            ((y - 0.5 * x - 40.0).abs(), Rgba([32, 32, 32, 255])),
+            (
+               ((x - 180.0).powi(2) + (y - 90.0).powi(2)).sqrt() - 50.0,
+               Rgba([200, 40, 40, 255]),
+            ),
+            ((x + y - 300.0).abs(), Rgba([40, 90, 220, 255])),
+         ];
+         let mut pixel = Rgba([0, 0, 0, 0]);
+         for (distance, color) in strokes {
+            let coverage = (4.0 - distance.abs()).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+               let mut color = color;
+               color.0[3] = (coverage * 255.0) as u8;
+               pixel.blend(&color);
+            }
+         }
+         pixel
+      })
+   }
+
+   /// Checks that every PNG compression level round-trips a painted chunk, and that the best one
+   /// doesn't produce a larger file than the fastest one.
+   #[test]
+   fn png_compression_levels_are_lossless_and_ordered_by_size() {
+      let image = painted_chunk();
+      let mut sizes = Vec::new();
+      for compression in [
+         PngCompression::Fast,
+         PngCompression::Default,
+         PngCompression::Best,
+      ] {
+         let png = ImageCoder::encode_png(&image, compression).unwrap();
+         assert_eq!(ImageCoder::decode_png_data(&png).unwrap(), image);
+         sizes.push(png.len());
+      }
+      let (fast, best) = (sizes[0], sizes[2]);
+      assert!(
+         best <= fast,
+         "best compression ({best} bytes) is larger than fast ({fast} bytes)"
+      );
+   }
+
+   #[test]
+   fn lossless_webp_round_trip_is_lossless() {
+      let image = test_image();
+      let webp = ImageCoder::encode_webp(&image, WebPQuality::lossless()).unwrap();
+      assert_eq!(ImageCoder::decode_webp_data(&webp).unwrap(), image);
+   }
+
+   #[test]
+   fn network_png_round_trip_is_lossless() {
+      let image = test_image();
+      let encoded = ImageCoder::encode_network_data_sync(
+         &image,
+         PngCompression::Fast,
+         NetworkImageFormat::Png,
+      )
+      .unwrap();
+      assert_eq!(
+         ImageCoder::decode_network_data(&encoded.data).unwrap(),
+         image
+      );
+   }
+
+   #[test]
+   fn export_format_is_picked_by_extension() {
+      let format = |path: &str| ExportFormat::from_path(Path::new(path));
+      assert_eq!(format("canvas.png"), Some(ExportFormat::Png));
+      assert_eq!(format("canvas.JPG"), Some(ExportFormat::Jpeg));
+      assert_eq!(format("canvas.jpeg"), Some(ExportFormat::Jpeg));
+      assert_eq!(format("canvas.webp"), Some(ExportFormat::WebP));
+      assert_eq!(format("canvas.gif"), None);
+      assert_eq!(format("canvas"), None);
+      assert!(!ExportFormat::Png.is_lossy());
+      assert!(ExportFormat::Jpeg.is_lossy() && ExportFormat::WebP.is_lossy());
+   }
+
+   #[test]
+   fn jpeg_export_is_put_onto_the_background() {
+      let mut image = RgbaImage::new(16, 16);
+      for x in 8..16 {
+         for y in 0..16 {
+            image.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+         }
+      }
+      let jpeg = ImageCoder::encode_jpeg(&image, 95, (255, 255, 255)).unwrap();
+      let decoded = ::image::load_from_memory(&jpeg).unwrap().into_rgb8();
+      assert_eq!(decoded.dimensions(), image.dimensions());
+      let near = |(x, y), expected: [u8; 3]| {
+         let pixel = decoded.get_pixel(x, y).0;
+         pixel.iter().zip(expected).all(|(&channel, expected)| channel.abs_diff(expected) <= 8)
+      };
+      assert!(
+         near((2, 8), [255, 255, 255]),
+         "transparent area is not white"
+      );
+      assert!(near((13, 8), [0, 0, 255]), "opaque area lost its color");
+   }
+
+   #[test]
+   fn lossy_webp_export_keeps_the_image_size() {
+      let image = painted_chunk();
+      let webp = ImageCoder::encode_webp(&image, WebPQuality::lossy(75)).unwrap();
+      let decoded = ImageCoder::decode_webp_data(&webp).unwrap();
+      assert_eq!(decoded.dimensions(), image.dimensions());
+   }
+
+   #[test]
+   fn fully_transparent_image_is_empty() {
+      // Leftover color doesn't matter as long as the pixels are fully transparent.
+      let image = RgbaImage::from_pixel(Chunk::SIZE.0, Chunk::SIZE.1, Rgba([255, 0, 0, 0]));
+      assert!(Chunk::image_is_empty(&image));
+   }
+
+   #[test]
+   fn image_with_a_single_visible_pixel_is_not_empty() {
+      let mut image = RgbaImage::new(Chunk::SIZE.0, Chunk::SIZE.1);
+      image.put_pixel(Chunk::SIZE.0 - 1, Chunk::SIZE.1 - 1, Rgba([0, 0, 0, 1]));
+      assert!(!Chunk::image_is_empty(&image));
+   }
+}
@@ -1,6 +1,8 @@
 //! Keyboard shortcut mappings.
 
-use crate::backend::winit::event::VirtualKeyCode;
+use std::fmt::Write;
+
+use crate::backend::winit::event::{MouseButton, VirtualKeyCode};
 use serde::{Deserialize, Serialize};
 
 use crate::ui::Modifier;
@@ -15,6 +17,19 @@ pub struct Keymap {
    #[serde(default)]
    pub tools: ToolKeymap,
    pub brush: BrushKeymap,
+   #[serde(default)]
+   pub color_picker: ColorPickerKeymap,
+   #[serde(default)]
+   pub viewport: ViewportKeymap,
+   #[serde(default)]
+   pub mouse: MouseKeymap,
+   #[serde(default)]
+   pub file: FileKeymap,
+   #[cfg(feature = "layers")]
+   #[serde(default)]
+   pub layers: LayerKeymap,
+   #[serde(default)]
+   pub debug: DebugKeymap,
 }
 
 /// The key map for common editing actions, such as copying and pasting.
@@ -25,6 +40,22 @@ pub struct EditKeymap {
    pub paste: KeyBinding,
    pub delete: KeyBinding,
    pub select_all: KeyBinding,
+   /// Undoes the last action performed by the user.
+   #[serde(default = "EditKeymap::default_undo")]
+   pub undo: KeyBinding,
+   /// Redoes the last action undone by the user.
+   #[serde(default = "EditKeymap::default_redo")]
+   pub redo: KeyBinding,
+}
+
+impl EditKeymap {
+   fn default_undo() -> KeyBinding {
+      (Modifier::CTRL, VirtualKeyCode::Z)
+   }
+
+   fn default_redo() -> KeyBinding {
+      (Modifier::CTRL | Modifier::SHIFT, VirtualKeyCode::Z)
+   }
 }
 
 /// The key map for selecting tools.
@@ -33,6 +64,39 @@ pub struct ToolKeymap {
    pub selection: KeyBinding,
    pub brush: KeyBinding,
    pub eyedropper: KeyBinding,
+   #[serde(default = "ToolKeymap::default_hand")]
+   pub hand: KeyBinding,
+   #[serde(default = "ToolKeymap::default_bucket")]
+   pub bucket: KeyBinding,
+   #[serde(default = "ToolKeymap::default_text")]
+   pub text: KeyBinding,
+   #[serde(default = "ToolKeymap::default_line")]
+   pub line: KeyBinding,
+   /// Switches to the hand tool for as long as the key is held down.
+   #[serde(default = "ToolKeymap::default_hold_hand")]
+   pub hold_hand: KeyBinding,
+}
+
+impl ToolKeymap {
+   fn default_hand() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Key4)
+   }
+
+   fn default_bucket() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Key5)
+   }
+
+   fn default_text() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Key6)
+   }
+
+   fn default_line() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Key7)
+   }
+
+   fn default_hold_hand() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Space)
+   }
 }
 
 impl Default for ToolKeymap {
@@ -41,6 +105,11 @@ impl Default for ToolKeymap {
          selection: (Modifier::NONE, VirtualKeyCode::Key1),
          brush: (Modifier::NONE, VirtualKeyCode::Key2),
          eyedropper: (Modifier::NONE, VirtualKeyCode::Key3),
+         hand: Self::default_hand(),
+         bucket: Self::default_bucket(),
+         text: Self::default_text(),
+         line: Self::default_line(),
+         hold_hand: Self::default_hold_hand(),
       }
    }
 }
@@ -52,6 +121,216 @@ pub struct BrushKeymap {
    pub increase_thickness: KeyBinding,
 }
 
+/// The key mappings for the color picker.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ColorPickerKeymap {
+   pub swap_colors: KeyBinding,
+}
+
+impl Default for ColorPickerKeymap {
+   fn default() -> Self {
+      Self {
+         swap_colors: (Modifier::NONE, VirtualKeyCode::X),
+      }
+   }
+}
+
+/// The key mappings for navigating the canvas.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ViewportKeymap {
+   pub rotate_counterclockwise: KeyBinding,
+   pub rotate_clockwise: KeyBinding,
+   pub reset_rotation: KeyBinding,
+   /// Opens the popup for jumping to a point on the canvas.
+   #[serde(default = "ViewportKeymap::default_go_to_coordinates")]
+   pub go_to_coordinates: KeyBinding,
+   /// Shows or hides the minimap.
+   #[serde(default = "ViewportKeymap::default_toggle_minimap")]
+   pub toggle_minimap: KeyBinding,
+   /// Shows or hides the grid.
+   #[serde(default = "ViewportKeymap::default_toggle_grid")]
+   pub toggle_grid: KeyBinding,
+   /// Zooms out or in, such that the whole painting is visible.
+   #[serde(default = "ViewportKeymap::default_zoom_to_fit")]
+   pub zoom_to_fit: KeyBinding,
+   /// Resets the zoom to 100%.
+   #[serde(default = "ViewportKeymap::default_reset_zoom")]
+   pub reset_zoom: KeyBinding,
+   /// Pan the viewport for as long as the key is held down. Modifiers are ignored, as Shift
+   /// speeds up panning.
+   #[serde(default = "ViewportKeymap::default_pan_left")]
+   pub pan_left: KeyBinding,
+   #[serde(default = "ViewportKeymap::default_pan_right")]
+   pub pan_right: KeyBinding,
+   #[serde(default = "ViewportKeymap::default_pan_up")]
+   pub pan_up: KeyBinding,
+   #[serde(default = "ViewportKeymap::default_pan_down")]
+   pub pan_down: KeyBinding,
+}
+
+impl ViewportKeymap {
+   fn default_go_to_coordinates() -> KeyBinding {
+      (Modifier::CTRL, VirtualKeyCode::G)
+   }
+
+   fn default_toggle_minimap() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::M)
+   }
+
+   fn default_toggle_grid() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::G)
+   }
+
+   fn default_zoom_to_fit() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::F)
+   }
+
+   fn default_reset_zoom() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Key0)
+   }
+
+   fn default_pan_left() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Left)
+   }
+
+   fn default_pan_right() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Right)
+   }
+
+   fn default_pan_up() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Up)
+   }
+
+   fn default_pan_down() -> KeyBinding {
+      (Modifier::NONE, VirtualKeyCode::Down)
+   }
+}
+
+impl Default for ViewportKeymap {
+   fn default() -> Self {
+      Self {
+         rotate_counterclockwise: (Modifier::NONE, VirtualKeyCode::Q),
+         rotate_clockwise: (Modifier::NONE, VirtualKeyCode::E),
+         reset_rotation: (Modifier::NONE, VirtualKeyCode::R),
+         go_to_coordinates: Self::default_go_to_coordinates(),
+         toggle_minimap: Self::default_toggle_minimap(),
+         toggle_grid: Self::default_toggle_grid(),
+         zoom_to_fit: Self::default_zoom_to_fit(),
+         reset_zoom: Self::default_reset_zoom(),
+         pan_left: Self::default_pan_left(),
+         pan_right: Self::default_pan_right(),
+         pan_up: Self::default_pan_up(),
+         pan_down: Self::default_pan_down(),
+      }
+   }
+}
+
+/// The mouse button mappings for the canvas.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MouseKeymap {
+   /// Paints, and uses the current tool in general.
+   pub draw: MouseButton,
+   /// Pans the viewport for as long as the button is held down.
+   pub pan: MouseButton,
+   /// Erases with the brush, picks the secondary color with the eyedropper, and cancels lines.
+   pub alternate: MouseButton,
+}
+
+impl MouseKeymap {
+   /// Finds mouse buttons that are bound to more than one action, along with the names of the
+   /// actions sharing them.
+   pub fn conflicts(&self) -> Vec<(MouseButton, Vec<&'static str>)> {
+      group_conflicts([
+         ("mouse.draw", self.draw),
+         ("mouse.pan", self.pan),
+         ("mouse.alternate", self.alternate),
+      ])
+   }
+
+   /// Logs conflicting mouse buttons, and returns a human-readable description of them, if there
+   /// are any.
+   pub fn check_conflicts(&self) -> Option<String> {
+      let conflicts = self.conflicts();
+      if conflicts.is_empty() {
+         return None;
+      }
+      let mut description = String::new();
+      for (button, actions) in conflicts {
+         tracing::warn!(
+            "mouse button {:?} is bound to multiple actions: {:?}",
+            button,
+            actions
+         );
+         if !description.is_empty() {
+            description.push_str("; ");
+         }
+         let _ = write!(description, "{:?} ({})", button, actions.join(", "));
+      }
+      Some(description)
+   }
+}
+
+impl Default for MouseKeymap {
+   fn default() -> Self {
+      Self {
+         draw: MouseButton::Left,
+         pan: MouseButton::Middle,
+         alternate: MouseButton::Right,
+      }
+   }
+}
+
+/// The key mappings for saving the canvas.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FileKeymap {
+   /// Saves to the last used path, or asks for one if the canvas wasn't saved yet.
+   pub save: KeyBinding,
+}
+
+impl Default for FileKeymap {
+   fn default() -> Self {
+      Self {
+         save: (Modifier::CTRL, VirtualKeyCode::S),
+      }
+   }
+}
+
+/// The key mappings for managing the canvas's layers.
+#[cfg(feature = "layers")]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LayerKeymap {
+   /// Adds a new layer above the active one, and makes it active.
+   pub new_layer: KeyBinding,
+   pub select_layer_above: KeyBinding,
+   pub select_layer_below: KeyBinding,
+}
+
+#[cfg(feature = "layers")]
+impl Default for LayerKeymap {
+   fn default() -> Self {
+      Self {
+         new_layer: (Modifier::CTRL | Modifier::SHIFT, VirtualKeyCode::N),
+         select_layer_above: (Modifier::NONE, VirtualKeyCode::PageUp),
+         select_layer_below: (Modifier::NONE, VirtualKeyCode::PageDown),
+      }
+   }
+}
+
+/// The key mappings for debugging aids.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DebugKeymap {
+   /// Switches between the default log level and verbose logging.
+   pub toggle_verbose_logging: KeyBinding,
+}
+
+impl Default for DebugKeymap {
+   fn default() -> Self {
+      Self {
+         toggle_verbose_logging: (Modifier::CTRL | Modifier::SHIFT, VirtualKeyCode::L),
+      }
+   }
+}
+
 impl Default for Keymap {
    fn default() -> Self {
       Self {
@@ -61,12 +340,348 @@ impl Default for Keymap {
             paste: (Modifier::CTRL, VirtualKeyCode::V),
             delete: (Modifier::NONE, VirtualKeyCode::Delete),
             select_all: (Modifier::CTRL, VirtualKeyCode::A),
+            undo: EditKeymap::default_undo(),
+            redo: EditKeymap::default_redo(),
          },
          tools: Default::default(),
          brush: BrushKeymap {
             decrease_thickness: (Modifier::NONE, VirtualKeyCode::LBracket),
             increase_thickness: (Modifier::NONE, VirtualKeyCode::RBracket),
          },
+         color_picker: Default::default(),
+         viewport: Default::default(),
+         mouse: Default::default(),
+         file: Default::default(),
+         #[cfg(feature = "layers")]
+         layers: Default::default(),
+         debug: Default::default(),
+      }
+   }
+}
+
+impl Keymap {
+   /// Returns all key bindings, along with the config names of the actions they're bound to.
+   pub fn bindings(&self) -> Vec<(&'static str, KeyBinding)> {
+      vec![
+         ("edit.copy", self.edit.copy),
+         ("edit.cut", self.edit.cut),
+         ("edit.paste", self.edit.paste),
+         ("edit.delete", self.edit.delete),
+         ("edit.select_all", self.edit.select_all),
+         ("edit.undo", self.edit.undo),
+         ("edit.redo", self.edit.redo),
+         ("tools.selection", self.tools.selection),
+         ("tools.brush", self.tools.brush),
+         ("tools.eyedropper", self.tools.eyedropper),
+         ("tools.hand", self.tools.hand),
+         ("tools.bucket", self.tools.bucket),
+         ("tools.text", self.tools.text),
+         ("tools.line", self.tools.line),
+         ("tools.hold_hand", self.tools.hold_hand),
+         ("brush.decrease_thickness", self.brush.decrease_thickness),
+         ("brush.increase_thickness", self.brush.increase_thickness),
+         ("color_picker.swap_colors", self.color_picker.swap_colors),
+         (
+            "viewport.rotate_counterclockwise",
+            self.viewport.rotate_counterclockwise,
+         ),
+         ("viewport.rotate_clockwise", self.viewport.rotate_clockwise),
+         ("viewport.reset_rotation", self.viewport.reset_rotation),
+         (
+            "viewport.go_to_coordinates",
+            self.viewport.go_to_coordinates,
+         ),
+         ("viewport.toggle_minimap", self.viewport.toggle_minimap),
+         ("viewport.toggle_grid", self.viewport.toggle_grid),
+         ("viewport.zoom_to_fit", self.viewport.zoom_to_fit),
+         ("viewport.reset_zoom", self.viewport.reset_zoom),
+         ("viewport.pan_left", self.viewport.pan_left),
+         ("viewport.pan_right", self.viewport.pan_right),
+         ("viewport.pan_up", self.viewport.pan_up),
+         ("viewport.pan_down", self.viewport.pan_down),
+         ("file.save", self.file.save),
+         #[cfg(feature = "layers")]
+         ("layers.new_layer", self.layers.new_layer),
+         #[cfg(feature = "layers")]
+         ("layers.select_layer_above", self.layers.select_layer_above),
+         #[cfg(feature = "layers")]
+         ("layers.select_layer_below", self.layers.select_layer_below),
+         (
+            "debug.toggle_verbose_logging",
+            self.debug.toggle_verbose_logging,
+         ),
+      ]
+   }
+
+   /// Returns the key binding of the action with the given config name, as listed by
+   /// [`Keymap::bindings`], for rebinding it.
+   pub fn binding_mut(&mut self, action: &str) -> Option<&mut KeyBinding> {
+      Some(match action {
+         "edit.copy" => &mut self.edit.copy,
+         "edit.cut" => &mut self.edit.cut,
+         "edit.paste" => &mut self.edit.paste,
+         "edit.delete" => &mut self.edit.delete,
+         "edit.select_all" => &mut self.edit.select_all,
+         "edit.undo" => &mut self.edit.undo,
+         "edit.redo" => &mut self.edit.redo,
+         "tools.selection" => &mut self.tools.selection,
+         "tools.brush" => &mut self.tools.brush,
+         "tools.eyedropper" => &mut self.tools.eyedropper,
+         "tools.hand" => &mut self.tools.hand,
+         "tools.bucket" => &mut self.tools.bucket,
+         "tools.text" => &mut self.tools.text,
+         "tools.line" => &mut self.tools.line,
+         "tools.hold_hand" => &mut self.tools.hold_hand,
+         "brush.decrease_thickness" => &mut self.brush.decrease_thickness,
+         "brush.increase_thickness" => &mut self.brush.increase_thickness,
+         "color_picker.swap_colors" => &mut self.color_picker.swap_colors,
+         "viewport.rotate_counterclockwise" => &mut self.viewport.rotate_counterclockwise,
+         "viewport.rotate_clockwise" => &mut self.viewport.rotate_clockwise,
+         "viewport.reset_rotation" => &mut self.viewport.reset_rotation,
+         "viewport.go_to_coordinates" => &mut self.viewport.go_to_coordinates,
+         "viewport.toggle_minimap" => &mut self.viewport.toggle_minimap,
+         "viewport.toggle_grid" => &mut self.viewport.toggle_grid,
+         "viewport.zoom_to_fit" => &mut self.viewport.zoom_to_fit,
+         "viewport.reset_zoom" => &mut self.viewport.reset_zoom,
+         "viewport.pan_left" => &mut self.viewport.pan_left,
+         "viewport.pan_right" => &mut self.viewport.pan_right,
+         "viewport.pan_up" => &mut self.viewport.pan_up,
+         "viewport.pan_down" => &mut self.viewport.pan_down,
+         "file.save" => &mut self.file.save,
+         #[cfg(feature = "layers")]
+         "layers.new_layer" => &mut self.layers.new_layer,
+         #[cfg(feature = "layers")]
+         "layers.select_layer_above" => &mut self.layers.select_layer_above,
+         #[cfg(feature = "layers")]
+         "layers.select_layer_below" => &mut self.layers.select_layer_below,
+         "debug.toggle_verbose_logging" => &mut self.debug.toggle_verbose_logging,
+         _ => return None,
+      })
+   }
+
+   /// Returns the sections the actions are grouped into, eg. `edit` for `edit.copy`, in the order
+   /// they appear in the config.
+   pub fn sections(&self) -> Vec<&'static str> {
+      let mut sections: Vec<&'static str> = Vec::new();
+      for (action, _) in self.bindings() {
+         let section = Self::section(action);
+         if !sections.contains(&section) {
+            sections.push(section);
+         }
       }
+      sections
+   }
+
+   /// Returns the section the action with the given config name belongs to.
+   pub fn section(action: &str) -> &str {
+      action.split_once('.').map_or(action, |(section, _)| section)
+   }
+
+   /// Finds key bindings that are bound to more than one action.
+   ///
+   /// Each conflict is returned along with the names of all actions sharing the binding, in the
+   /// order they appear in the config.
+   pub fn conflicts(&self) -> Vec<(KeyBinding, Vec<&'static str>)> {
+      group_conflicts(self.bindings())
+   }
+
+   /// Logs conflicting key bindings, and returns a human-readable description of them, if there
+   /// are any.
+   pub fn check_conflicts(&self) -> Option<String> {
+      let conflicts = self.conflicts();
+      if conflicts.is_empty() {
+         return None;
+      }
+      let mut description = String::new();
+      for (binding, actions) in conflicts {
+         let binding = describe_binding(binding);
+         tracing::warn!(
+            "key binding {} is bound to multiple actions: {:?}",
+            binding,
+            actions
+         );
+         if !description.is_empty() {
+            description.push_str("; ");
+         }
+         let _ = write!(description, "{} ({})", binding, actions.join(", "));
+      }
+      Some(description)
+   }
+}
+
+/// Groups the bindings by what they're bound to, and returns the groups with more than one action.
+fn group_conflicts<T: PartialEq>(
+   bindings: impl IntoIterator<Item = (&'static str, T)>,
+) -> Vec<(T, Vec<&'static str>)> {
+   let mut groups: Vec<(T, Vec<&'static str>)> = Vec::new();
+   for (action, binding) in bindings {
+      match groups.iter_mut().find(|(other, _)| *other == binding) {
+         Some((_, actions)) => actions.push(action),
+         None => groups.push((binding, vec![action])),
+      }
+   }
+   groups.retain(|(_, actions)| actions.len() > 1);
+   groups
+}
+
+/// Formats a key binding for display, eg. `Ctrl+Shift+C`.
+pub fn describe_binding((modifier, key): KeyBinding) -> String {
+   let mut description = String::new();
+   if modifier.ctrl() {
+      description.push_str("Ctrl+");
+   }
+   if modifier.shift() {
+      description.push_str("Shift+");
+   }
+   let _ = write!(description, "{:?}", key);
+   description
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn every_binding_can_be_rebound() {
+      let mut keymap = Keymap::default();
+      let rebound = (Modifier::CTRL | Modifier::SHIFT, VirtualKeyCode::F12);
+      for (action, binding) in Keymap::default().bindings() {
+         let binding_mut = keymap.binding_mut(action).expect(action);
+         assert_eq!(*binding_mut, binding, "{action}");
+         *binding_mut = rebound;
+      }
+      assert!(keymap.bindings().iter().all(|&(_, binding)| binding == rebound));
+      assert!(keymap.binding_mut("edit.nonexistent").is_none());
+   }
+
+   #[test]
+   fn sections_are_listed_in_config_order() {
+      let sections = Keymap::default().sections();
+      assert_eq!(sections.first(), Some(&"edit"));
+      assert_eq!(sections.last(), Some(&"debug"));
+      assert_eq!(Keymap::section("brush.increase_thickness"), "brush");
+   }
+
+   #[test]
+   fn mouse_buttons_default_when_missing_from_the_config() {
+      let mut value = toml::Value::try_from(Keymap::default()).unwrap();
+      value.as_table_mut().unwrap().remove("mouse");
+      let keymap: Keymap = value.try_into().unwrap();
+      assert_eq!(keymap.mouse.draw, MouseButton::Left);
+      assert_eq!(keymap.mouse.pan, MouseButton::Middle);
+      assert_eq!(keymap.mouse.alternate, MouseButton::Right);
+   }
+
+   #[test]
+   fn rebinding_to_a_used_binding_is_a_conflict() {
+      let mut keymap = Keymap::default();
+      assert!(keymap.conflicts().is_empty());
+      *keymap.binding_mut("tools.brush").unwrap() = keymap.edit.copy;
+      assert_eq!(
+         keymap.conflicts(),
+         vec![(keymap.edit.copy, vec!["edit.copy", "tools.brush"])]
+      );
+   }
+
+   #[test]
+   fn modifiers_are_part_of_the_binding() {
+      let mut keymap = Keymap::default();
+      // Undo and redo only differ by Shift.
+      assert_ne!(keymap.edit.undo, keymap.edit.redo);
+      assert!(keymap.check_conflicts().is_none());
+      keymap.edit.redo = (Modifier::CTRL, VirtualKeyCode::Z);
+      assert_eq!(
+         keymap.conflicts(),
+         vec![(keymap.edit.undo, vec!["edit.undo", "edit.redo"])]
+      );
+      assert_eq!(
+         keymap.check_conflicts().as_deref(),
+         Some("Ctrl+Z (edit.undo, edit.redo)")
+      );
+   }
+
+   #[test]
+   fn every_conflict_is_reported_with_all_of_its_actions() {
+      let mut keymap = Keymap::default();
+      *keymap.binding_mut("tools.brush").unwrap() = keymap.edit.copy;
+      *keymap.binding_mut("tools.line").unwrap() = keymap.edit.copy;
+      *keymap.binding_mut("viewport.toggle_grid").unwrap() = keymap.file.save;
+      assert_eq!(
+         keymap.conflicts(),
+         vec![
+            (
+               keymap.edit.copy,
+               vec!["edit.copy", "tools.brush", "tools.line"]
+            ),
+            (keymap.file.save, vec!["viewport.toggle_grid", "file.save"]),
+         ]
+      );
+      assert_eq!(
+         keymap.check_conflicts().as_deref(),
+         Some(
+            "Ctrl+C (edit.copy, tools.brush, tools.line); Ctrl+S (viewport.toggle_grid, file.save)"
+         )
+      );
+   }
+
+   #[test]
+   fn overlapping_mouse_buttons_are_conflicts() {
+      let mut mouse = MouseKeymap::default();
+      assert!(mouse.conflicts().is_empty());
+      assert!(mouse.check_conflicts().is_none());
+      mouse.pan = MouseButton::Left;
+      assert_eq!(
+         mouse.conflicts(),
+         vec![(MouseButton::Left, vec!["mouse.draw", "mouse.pan"])]
+      );
+      mouse.alternate = MouseButton::Left;
+      assert_eq!(
+         mouse.conflicts(),
+         vec![(
+            MouseButton::Left,
+            vec!["mouse.draw", "mouse.pan", "mouse.alternate"]
+         )]
+      );
+      assert!(mouse.check_conflicts().is_some());
+   }
+
+   #[cfg(feature = "layers")]
+   #[test]
+   fn layer_bindings_are_listed_before_debugging() {
+      let keymap = Keymap::default();
+      let bindings = keymap.bindings();
+      assert!(bindings.contains(&(
+         "layers.new_layer",
+         (Modifier::CTRL | Modifier::SHIFT, VirtualKeyCode::N)
+      )));
+      assert!(bindings.contains(&(
+         "layers.select_layer_above",
+         (Modifier::NONE, VirtualKeyCode::PageUp)
+      )));
+      assert!(bindings.contains(&(
+         "layers.select_layer_below",
+         (Modifier::NONE, VirtualKeyCode::PageDown)
+      )));
+      let sections = keymap.sections();
+      assert_eq!(&sections[sections.len() - 2..], ["layers", "debug"]);
+   }
+
+   #[cfg(feature = "layers")]
+   #[test]
+   fn layer_bindings_take_part_in_conflicts() {
+      let mut keymap = Keymap::default();
+      assert!(keymap.conflicts().is_empty());
+      *keymap.binding_mut("layers.new_layer").unwrap() = keymap.file.save;
+      *keymap.binding_mut("tools.brush").unwrap() = keymap.layers.select_layer_above;
+      assert_eq!(
+         keymap.conflicts(),
+         vec![
+            (
+               keymap.layers.select_layer_above,
+               vec!["tools.brush", "layers.select_layer_above"]
+            ),
+            (keymap.file.save, vec!["file.save", "layers.new_layer"]),
+         ]
+      );
    }
 }
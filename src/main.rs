@@ -45,25 +45,29 @@ pub extern crate self as netcanv;
 
 use std::env;
 use std::fmt::Write;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use crate::backend::winit::dpi::{PhysicalPosition, PhysicalSize};
 use crate::backend::winit::event::{Event, WindowEvent};
-use crate::backend::winit::event_loop::{ControlFlow, EventLoop};
-use crate::backend::winit::window::{CursorIcon, WindowBuilder};
+use crate::backend::winit::event_loop::{ControlFlow, EventLoopBuilder};
+use crate::backend::winit::window::{CursorIcon, Window, WindowBuilder};
 use crate::config::WindowConfig;
 use crate::net::socket::SocketSystem;
+use crate::ui::accessibility::Accessibility;
 use crate::ui::view::{self, View};
+use crate::wake::UserEvent;
 use backend::Backend;
 use netcanv_i18n::translate_enum::TranslateEnum;
 use netcanv_i18n::{Formatted, Language};
 use netcanv_renderer::paws::{vector, Layout};
+use netcanv_renderer::RenderBackend;
 use nysa::global as bus;
 use rfd::{MessageDialog, MessageLevel};
 use tracing::{error, info, warn};
 use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{EnvFilter, Layer};
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
 use web_time::{Duration, Instant};
 
 use crate::backend::UiRenderFrame;
@@ -80,20 +84,23 @@ mod cli;
 mod clipboard;
 mod color;
 mod config;
+mod crash_report;
 mod image_coder;
 mod keymap;
 mod net;
+mod ora;
 mod paint_canvas;
 mod project_file;
 mod strings;
 mod token;
 mod ui;
 mod viewport;
+mod wake;
 
 use app::*;
 use assets::*;
 use config::config;
-use ui::{Input, Ui};
+use ui::{Input, InputRecorder, InputReplay, Ui};
 
 pub use errors::*;
 use netcanv::cli::cli_args;
@@ -124,19 +131,21 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
    }
 
    // Load user configuration.
-   config::load_or_create()?;
+   config::load_or_create(cli.config_dir.as_deref())?;
 
    // Set up the winit event loop and open the window.
    let (renderer, event_loop) = {
       profiling::scope!("init_renderer");
 
-      let event_loop = EventLoop::new();
+      let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
       let window_builder = {
          let b = WindowBuilder::new()
             .with_min_inner_size(PhysicalSize::<u32>::new(256, 150)) // not usable, but it's better than a crash.
             .with_inner_size(PhysicalSize::<u32>::new(1024, 600))
             .with_title("NetCanv")
-            .with_resizable(true);
+            .with_resizable(true)
+            // The window is shown once the accessibility adapter is created.
+            .with_visible(false);
          if let Some(window) = &config().window {
             b.with_inner_size(PhysicalSize::new(window.width, window.height))
          } else {
@@ -161,9 +170,15 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
       renderer.window().set_maximized(window.maximized);
    }
 
+   // Let other threads wake the event loop up once they have something for it.
+   wake::init(event_loop.create_proxy());
+
+   // Expose the UI to assistive technologies.
+   let accessibility = Accessibility::new(renderer.window(), event_loop.create_proxy());
+   renderer.window().set_visible(true);
+
    // Load color scheme.
-   // TODO: User-definable color schemes, anyone?
-   let color_scheme = ColorScheme::from(config().ui.color_scheme);
+   let color_scheme = ColorScheme::load(config().ui.color_scheme);
 
    // Build the UI.
    let mut ui = Ui::new(renderer);
@@ -175,6 +190,10 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
    let mut app: Option<Box<dyn AppState>> =
       Some(boot::State::new_state(assets, Arc::clone(&socket_system)));
    let mut input = Input::new();
+   if let Some(path) = &cli.record_input {
+      input.start_recording(InputRecorder::create(path)?);
+   }
+   let mut input_replay = cli.replay_input.as_deref().map(InputReplay::open).transpose()?;
 
    // Initialize the clipboard because we now have a window handle and translation strings.
    match clipboard::init() {
@@ -185,6 +204,18 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
       }
    }
 
+   // Warn about key bindings that would shadow each other.
+   if let Some(conflicts) = config().keymap.check_conflicts() {
+      bus::push(common::Error(Error::ConflictingKeyBindings { conflicts }));
+   }
+   // Mouse buttons shared between actions would leave some of them unusable, so they're reset to
+   // the defaults instead.
+   let mouse_conflicts = config().keymap.mouse.check_conflicts();
+   if let Some(conflicts) = mouse_conflicts {
+      config::write(|config| config.keymap.mouse = Default::default());
+      bus::push(common::Error(Error::ConflictingMouseButtons { conflicts }));
+   }
+
    let (mut last_window_size, mut last_window_position) = {
       if let Some(window) = &config().window {
          let size = PhysicalSize::new(window.width, window.height);
@@ -197,13 +228,18 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
       }
    };
 
+   // The shortest time allowed between two frames, if the frame rate is capped.
+   let min_frame_time = cli.max_fps.map(|fps| Duration::from_secs_f64(1.0 / f64::from(fps)));
+   let mut last_frame = Instant::now();
+
    profiling::finish_frame!();
 
    event_loop.run(move |event, _, control_flow| {
-      *control_flow = ControlFlow::Poll;
-
       match event {
          Event::WindowEvent { event, .. } => {
+            if !accessibility.process_event(ui.window(), &event) {
+               return;
+            }
             match event {
                // Ignore resize event if window is maximized, and move event if position is lower than 0,
                // because it isn't what we want, when saving window's size and position to config file.
@@ -216,6 +252,12 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
                WindowEvent::CloseRequested => {
                   *control_flow = ControlFlow::Exit;
                }
+               // While replaying, the user's input is ignored so that it doesn't interfere with the
+               // recording.
+               _ if input_replay.is_some() => (),
+               WindowEvent::DroppedFile(path) => {
+                  input.drop_file(&path);
+               }
                _ => {
                   input.process_event(&event);
                }
@@ -223,10 +265,34 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
          }
 
          Event::MainEventsCleared => {
+            // Input arriving too early is kept around until the next frame.
+            if let Some(min_frame_time) = min_frame_time {
+               let next_frame = last_frame + min_frame_time;
+               if Instant::now() < next_frame {
+                  *control_flow = ControlFlow::WaitUntil(next_frame);
+                  return;
+               }
+            }
+            last_frame = Instant::now();
+
+            if let Some(replay) = &mut input_replay {
+               if !replay.replay_frame(&mut input) {
+                  input_replay = None;
+               }
+            }
+            if input.action(config().keymap.debug.toggle_verbose_logging) == (true, true) {
+               toggle_verbose_logging();
+            }
             let window_size = ui.window().inner_size();
+            let scale = ui_scale(ui.window());
+            ui.render().set_screen_scale(scale);
+            input.set_ui_scale(scale);
             if let Err(error) = ui.render_frame(|ui| {
                ui.root(
-                  vector(window_size.width as f32, window_size.height as f32),
+                  vector(
+                     window_size.width as f32 / scale,
+                     window_size.height as f32 / scale,
+                  ),
                   Layout::Freeform,
                );
                let mut root_view = View::group_sized(ui);
@@ -243,6 +309,33 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
                error!("render error: {}", error)
             }
             input.finish_frame(ui.window());
+            accessibility.finish_frame();
+
+            // When nothing is going on, sleep until input arrives, something gets done in the
+            // background, or a timer set by the app runs out.
+            let redraw = input_replay.is_some()
+               || !config().ui.redraw_only_when_needed
+               || app.as_ref().unwrap().needs_redraw();
+            let deadline = wake::next_deadline();
+            *control_flow = match (redraw, min_frame_time, deadline) {
+               (true, None, _) => ControlFlow::Poll,
+               (true, Some(min_frame_time), _) => {
+                  ControlFlow::WaitUntil(last_frame + min_frame_time)
+               }
+               (false, _, Some(deadline)) => ControlFlow::WaitUntil(deadline),
+               (false, _, None) => ControlFlow::Wait,
+            };
+         }
+
+         // Waking up is all that's needed, the frame itself is processed once the event is
+         // cleared.
+         Event::UserEvent(UserEvent::Wake) => (),
+
+         Event::UserEvent(UserEvent::Accessibility(event)) => {
+            tracing::debug!(
+               "unhandled accessibility action request: {:?}",
+               event.request
+            );
          }
 
          Event::LoopDestroyed => {
@@ -265,6 +358,7 @@ async fn inner_main(language: &mut Option<Language>) -> errors::Result<()> {
 
             let app = app.take().unwrap();
             app.exit();
+            input.stop_recording();
 
             let _ = log_guards.take();
          }
@@ -309,20 +403,57 @@ async fn async_main() {
 static ALLOCATOR: profiling::tracy_client::ProfiledAllocator<std::alloc::System> =
    profiling::tracy_client::ProfiledAllocator::new(std::alloc::System, 100);
 
+/// Returns the factor the UI is scaled up by. This is the window's scale factor, unless it's
+/// overridden in the config with a finite number.
+fn ui_scale(window: &Window) -> f32 {
+   // NaN would make it through the clamp below, so non-finite scales are ignored.
+   let scale = config().ui.scale.filter(|scale| scale.is_finite());
+   scale.unwrap_or(window.scale_factor() as f32).clamp(0.5, 4.0)
+}
+
+/// Returns whether a dialog should be shown when the app panics.
+///
+/// The dialog can be disabled with `--no-panic-dialog`, or by setting the `NETCANV_NO_PANIC_DIALOG`
+/// environment variable, which also works for panics that happen before the CLI is parsed.
+#[cfg(not(debug_assertions))]
+fn panic_dialog_enabled() -> bool {
+   let disabled_by_cli = cli::try_cli_args().map(|cli| cli.no_panic_dialog).unwrap_or(false);
+   let disabled_by_env = env::var_os("NETCANV_NO_PANIC_DIALOG").is_some();
+   !disabled_by_cli && !disabled_by_env
+}
+
 fn main() {
    let default_panic_hook = std::panic::take_hook();
    std::panic::set_hook(Box::new(move |panic_info| {
+      let crash_report = crash_report::write(panic_info);
+      match &crash_report {
+         Ok(path) => error!("crash report saved to {:?}", path),
+         Err(error) => error!("could not save crash report: {}", error),
+      }
       // Pretty panic messages are only enabled in release mode, as they hinder debugging.
       #[cfg(not(debug_assertions))]
-      {
+      if panic_dialog_enabled() {
          let mut message = heapless::String::<8192>::new();
          let _ = write!(message, "Oh no! A fatal error occured.\n{}", panic_info);
          let _ = write!(message, "\n\nThis is most definitely a bug, so please file an issue on GitHub. https://github.com/netcanv/netcanv");
+         if let Ok(path) = &crash_report {
+            let _ = write!(
+               message,
+               "\n\nA crash report was saved to {}. Please attach it to the issue.",
+               path.display()
+            );
+         }
          let _ = MessageDialog::new()
             .set_title("NetCanv - Fatal Error")
             .set_description(message.to_string())
             .set_level(MessageLevel::Error)
             .show();
+      } else {
+         error!(
+            "{}\n{}",
+            panic_info,
+            std::backtrace::Backtrace::force_capture()
+         );
       }
       default_panic_hook(panic_info);
    }));
@@ -350,6 +481,53 @@ struct LogGuards {
    _chrome: Option<tracing_chrome::FlushGuard>,
 }
 
+/// Handle used for changing the log level while the app is running.
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static VERBOSE_LOGGING: AtomicBool = AtomicBool::new(false);
+
+/// Returns the log level picked on the command line, or `INFO` if none was picked.
+fn default_log_level() -> LevelFilter {
+   let cli = cli_args();
+   match cli.log_level {
+      Some(level) => level,
+      None if cli.verbose => LevelFilter::DEBUG,
+      None => LevelFilter::INFO,
+   }
+}
+
+/// Creates the filter for log output. The `NETCANV_LOG` environment variable takes precedence over
+/// the given level.
+fn log_filter(level: LevelFilter) -> EnvFilter {
+   EnvFilter::builder()
+      .with_default_directive(level.into())
+      .with_env_var("NETCANV_LOG")
+      .from_env_lossy()
+}
+
+/// Switches between the default log level and verbose logging, without having to restart the app.
+///
+/// `NETCANV_LOG` takes precedence over the log level, so it can't be toggled while the variable is
+/// set.
+fn toggle_verbose_logging() {
+   if env::var_os("NETCANV_LOG").is_some() {
+      warn!("the log level is fixed by NETCANV_LOG and cannot be toggled");
+      bus::push(common::Error(Error::LogLevelIsFixed));
+      return;
+   }
+   let verbose = !VERBOSE_LOGGING.fetch_xor(true, Ordering::Relaxed);
+   let level = if verbose {
+      default_log_level().max(LevelFilter::DEBUG)
+   } else {
+      default_log_level()
+   };
+   if let Some(handle) = LOG_FILTER.get() {
+      match handle.reload(log_filter(level)) {
+         Ok(()) => info!("log level changed to {}", level),
+         Err(error) => error!("could not change the log level: {}", error),
+      }
+   }
+}
+
 fn init_logging() -> errors::Result<LogGuards> {
    let cli = cli_args();
 
@@ -365,14 +543,21 @@ fn init_logging() -> errors::Result<LogGuards> {
       (Some(chrome_trace), guard)
    });
 
+   let (filter, filter_handle) = reload::Layer::new(log_filter(default_log_level()));
+   let _ = LOG_FILTER.set(filter_handle);
+
    let subscriber = tracing_subscriber::registry()
       .with(
-         tracing_subscriber::fmt::layer().without_time().with_writer(std::io::stderr).with_filter(
-            EnvFilter::builder()
-               .with_default_directive(LevelFilter::INFO.into())
-               .with_env_var("NETCANV_LOG")
-               .from_env_lossy(),
-         ),
+         tracing_subscriber::fmt::layer()
+            .without_time()
+            .with_writer(std::io::stderr)
+            .with_filter(filter),
+      )
+      .with(
+         tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(|| crash_report::LogWriter)
+            .with_filter(LevelFilter::INFO),
       )
       .with(chrome_trace.as_mut().and_then(|(ct, _)| ct.take()));
 
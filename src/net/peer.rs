@@ -1,15 +1,19 @@
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use netcanv_protocol::relay::{PeerId, RoomId};
 use netcanv_protocol::{client as cl, relay};
+use netcanv_renderer::paws::Color;
 use nysa::global as bus;
 use tokio::sync::oneshot;
+use web_time::{Duration, Instant};
 
-use super::socket::{Socket, SocketSystem};
+use super::socket::{ConnectionHealth, Socket, SocketSystem};
 use crate::common::{deserialize_bincode, serialize_bincode, Fatal};
 use crate::token::Token;
+use crate::wake;
 use crate::Error;
 
 /// A unique token identifying a peer connection.
@@ -37,16 +41,37 @@ pub enum MessageKind {
       nickname: String,
       last_tool: Option<String>,
    },
-   /// The host role has been transferred to another peer in the room.
-   NewHost(String),
+   /// The host role has been transferred to another peer in the room. The nickname is `None` if
+   /// the new host hasn't introduced themselves yet.
+   NewHost {
+      peer_id: PeerId,
+      nickname: Option<String>,
+   },
    /// The host role has been transferred to the peer (you).
    NowHosting,
-   /// The host sent us the chunk positions for the room.
-   ChunkPositions(Vec<(i32, i32)>),
+   /// The connection to the relay was lost, and is being reestablished.
+   Reconnecting,
+   /// The connection to the relay was reestablished, and the peer is back in a room. If the room
+   /// closed in the meantime, this is a new room with a different ID, hosted by the peer.
+   ///
+   /// `away` is how long ago data was last received from the relay before the connection was lost,
+   /// with some leeway for packets that were already underway at the time.
+   Reconnected { away: Duration },
+   /// The host sent us the region of the canvas that can be drawn to.
+   DrawingBounds((i32, i32, i32, i32)),
+   /// The host cleared the canvas.
+   ClearCanvas,
+   /// Somebody sent us the positions of chunks they have. This is normally sent by the host to
+   /// newly joined peers, but after a host transfer, the other peers send it to the new host.
+   ChunkPositions(PeerId, Vec<(i32, i32)>),
    /// Somebody requested chunk positions from the host.
    GetChunks(PeerId, Vec<(i32, i32)>),
+   /// Somebody asked the host for the positions of the chunks modified within the given duration.
+   GetChangedChunks(PeerId, Duration),
    /// Somebody sent us chunk image data.
    Chunks(Vec<((i32, i32), Vec<u8>)>),
+   /// Somebody sent us image data replacing a region of a chunk, at the given offset within it.
+   ChunkPatch((i32, i32), (u32, u32), Vec<u8>),
    /// A tool packet was received from an address.
    Tool(PeerId, String, Vec<u8>),
    /// The client selected a tool.
@@ -61,19 +86,146 @@ pub enum MessageKind {
 pub struct Mate {
    pub nickname: String,
    pub tool: Option<String>,
+   /// The color used for the peer's cursor, selection, and name tag.
+   pub color: Color,
+   /// The peer's protocol version, or `None` if they haven't told us yet.
+   version: Option<u32>,
+   /// The round trip time measured by the most recently answered ping.
+   latency: Option<Duration>,
+   /// The ID of the ping awaiting a pong, and the time it was sent at.
+   pending_ping: Option<(u64, Instant)>,
+   /// The region of the canvas visible in the peer's viewport, as its left, top, right, and
+   /// bottom sides. `None` if the peer doesn't share it.
+   pub viewport: Option<(i32, i32, i32, i32)>,
+}
+
+impl Mate {
+   /// The colors assigned to peers. They're dark enough for white text to be readable on top of
+   /// them, while still standing out against dark backgrounds.
+   const COLORS: [Color; 8] = [
+      Color::rgb(0xd03a52),
+      Color::rgb(0x2f6fd6),
+      Color::rgb(0x3c9a3c),
+      Color::rgb(0x7b4fd6),
+      Color::rgb(0xd9622b),
+      Color::rgb(0x1f8a8a),
+      Color::rgb(0xc2409e),
+      Color::rgb(0x9a7400),
+   ];
+
+   /// Returns the color assigned to the peer with the given ID.
+   ///
+   /// The color only depends on the ID, so it stays the same for the whole session, and every
+   /// peer in the room sees the same color.
+   pub fn color_for(peer_id: PeerId) -> Color {
+      Self::COLORS[(peer_id.0 % Self::COLORS.len() as u64) as usize]
+   }
+
+   /// Returns whether the peer understands ping packets.
+   fn can_be_pinged(&self) -> bool {
+      self.version.map_or(false, |version| version >= cl::PING_VERSION)
+   }
+
+   /// Returns whether the peer understands the packet for clearing the canvas.
+   fn can_clear_canvas(&self) -> bool {
+      self.version.map_or(false, |version| version >= cl::CLEAR_CANVAS_VERSION)
+   }
+
+   /// Returns whether the peer understands viewport packets.
+   fn can_receive_viewport(&self) -> bool {
+      self.version.map_or(false, |version| version >= cl::VIEWPORT_VERSION)
+   }
+
+   /// Returns whether the peer can be asked for the chunks that changed recently.
+   fn can_send_changed_chunks(&self) -> bool {
+      self.version.map_or(false, |version| version >= cl::CHANGED_CHUNKS_VERSION)
+   }
+
+   /// Returns whether the peer understands chunk patch packets.
+   fn can_receive_chunk_patches(&self) -> bool {
+      self.version.map_or(false, |version| version >= cl::CHUNK_PATCH_VERSION)
+   }
+
+   /// Registers a ping with the given ID. If the previous ping wasn't answered yet, the peer's
+   /// latency becomes unknown, as they're not responding in time.
+   fn ping_sent(&mut self, id: u64) {
+      if self.pending_ping.is_some() {
+         self.latency = None;
+      }
+      self.pending_ping = Some((id, Instant::now()));
+   }
+
+   /// Registers a pong with the given ID, measuring the latency.
+   fn pong_received(&mut self, id: u64) {
+      if let Some((pending_id, sent_at)) = self.pending_ping {
+         if pending_id == id {
+            self.latency = Some(sent_at.elapsed());
+            self.pending_ping = None;
+         }
+      }
+   }
+}
+
+/// Exponential backoff between attempts at reconnecting to the relay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Backoff {
+   /// The number of attempts that failed so far.
+   failed_attempts: u32,
+}
+
+impl Backoff {
+   /// The number of attempts after which reconnecting is given up on.
+   const MAX_ATTEMPTS: u32 = 6;
+   /// The delay before the first attempt. Each following attempt waits twice as long as the
+   /// previous one, up to [`Self::MAX_DELAY`].
+   const INITIAL_DELAY: Duration = Duration::from_secs(1);
+   const MAX_DELAY: Duration = Duration::from_secs(15);
+
+   /// Returns the delay before the next attempt.
+   fn delay(&self) -> Duration {
+      // The delay reaches its cap long before the multiplier could overflow.
+      let multiplier = 2u32.pow(self.failed_attempts.min(16));
+      (Self::INITIAL_DELAY * multiplier).min(Self::MAX_DELAY)
+   }
+
+   /// Registers a failed attempt. Returns `false` once there were too many of them, in which case
+   /// reconnecting should be given up on.
+   fn fail(&mut self) -> bool {
+      self.failed_attempts += 1;
+      self.failed_attempts < Self::MAX_ATTEMPTS
+   }
+
+   /// Returns which attempt is underway, counting from 1.
+   fn attempt(&self) -> u32 {
+      self.failed_attempts + 1
+   }
 }
 
 enum State {
    WaitingForRelay(oneshot::Receiver<netcanv::Result<Socket>>),
    ConnectedToRelay,
    InRoom,
+   /// The connection to the relay was lost, and the next attempt at reestablishing it is
+   /// scheduled for the given time.
+   Reconnecting(Instant),
+   /// The connection to the relay was lost for good.
+   Disconnected,
 }
 
 /// A connection to the relay.
 pub struct Peer {
    token: PeerToken,
    state: State,
+   socket_system: Arc<SocketSystem>,
+   relay_address: String,
    relay_socket: Option<Socket>,
+   /// The attempts at reconnecting to the relay, or `None` if the connection isn't being
+   /// reestablished.
+   reconnect: Option<Backoff>,
+   /// When data was last received from the relay before the connection was lost.
+   last_received_before_loss: Option<Instant>,
+   /// Client packets sent while reconnecting, which are sent out once the peer is back in a room.
+   outbox: RefCell<Vec<(PeerId, cl::Packet)>>,
 
    is_host: bool,
 
@@ -82,24 +234,45 @@ pub struct Peer {
    peer_id: Option<PeerId>,
    host: Option<PeerId>,
    mates: HashMap<PeerId, Mate>,
+
+   /// The ID of the next ping sent to other peers.
+   next_ping_id: u64,
+   /// The time other peers were last pinged.
+   last_ping: Instant,
 }
 
 static PEER_TOKEN: Token = Token::new(0);
 
 impl Peer {
+   /// The interval at which other peers are pinged to measure their latency.
+   const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+   /// The number of attempts at reconnecting to the relay after which the peer gives up.
+   pub const RECONNECT_ATTEMPTS: u32 = Backoff::MAX_ATTEMPTS;
+   /// How much earlier than the connection was lost chunks have to be modified to be downloaded
+   /// again after reconnecting. This covers packets that were underway when the connection broke.
+   const RESYNC_LEEWAY: Duration = Duration::from_secs(5);
+
    /// Host a new room on the given relay server.
    pub fn host(socket_system: Arc<SocketSystem>, nickname: &str, relay_address: &str) -> Self {
-      let socket_receiver = socket_system.connect(relay_address.to_owned());
+      let socket_receiver = Arc::clone(&socket_system).connect(relay_address.to_owned());
       Self {
          token: PeerToken(PEER_TOKEN.next()),
          state: State::WaitingForRelay(socket_receiver),
+         socket_system,
+         relay_address: relay_address.to_owned(),
          relay_socket: None,
+         reconnect: None,
+         last_received_before_loss: None,
+         outbox: RefCell::new(Vec::new()),
          is_host: true,
          nickname: nickname.into(),
          room_id: None,
          peer_id: None,
          mates: HashMap::new(),
          host: None,
+         next_ping_id: 0,
+         last_ping: Instant::now(),
       }
    }
 
@@ -110,17 +283,24 @@ impl Peer {
       relay_address: &str,
       room_id: RoomId,
    ) -> Self {
-      let socket_receiver = socket_system.connect(relay_address.to_owned());
+      let socket_receiver = Arc::clone(&socket_system).connect(relay_address.to_owned());
       Self {
          token: PeerToken(PEER_TOKEN.next()),
          state: State::WaitingForRelay(socket_receiver),
+         socket_system,
+         relay_address: relay_address.to_owned(),
          relay_socket: None,
+         reconnect: None,
+         last_received_before_loss: None,
+         outbox: RefCell::new(Vec::new()),
          is_host: false,
          nickname: nickname.into(),
          room_id: Some(room_id),
          peer_id: None,
          mates: HashMap::new(),
          host: None,
+         next_ping_id: 0,
+         last_ping: Instant::now(),
       }
    }
 
@@ -137,11 +317,15 @@ impl Peer {
    }
 
    /// Sends a client packet to the peer with the given address.
+   ///
+   /// While the connection to the relay is being reestablished, the packet is queued up and sent
+   /// once the peer is back in the room, so that nothing done in the meantime is lost.
    fn send_to_client(&self, to: PeerId, packet: cl::Packet) -> netcanv::Result<()> {
       match &self.state {
          State::InRoom => {
             self.send_to_relay(relay::Packet::Relay(to, serialize_bincode(&packet)?))?;
          }
+         _ if self.reconnect.is_some() => self.outbox.borrow_mut().push((to, packet)),
          _ => return Err(Error::NotConnectedToHost),
       }
       Ok(())
@@ -159,10 +343,112 @@ impl Peer {
    fn poll_for_new_connections(&mut self) -> netcanv::Result<()> {
       if let State::WaitingForRelay(socket) = &mut self.state {
          if let Ok(socket) = socket.try_recv() {
-            let socket = catch!(socket, as Fatal, return Ok(()));
-            self.connected_to_relay(socket)?;
+            match socket {
+               Ok(socket) => self.connected_to_relay(socket)?,
+               Err(error) if self.reconnect.is_some() => {
+                  tracing::warn!("could not reconnect to the relay: {:?}", error);
+                  self.reconnect_failed();
+               }
+               Err(error) => bus::push(Fatal(error)),
+            }
+         }
+      }
+      Ok(())
+   }
+
+   /// Checks whether the connection to the relay was lost.
+   ///
+   /// If the peer was in a room, it starts reconnecting. Otherwise, if it was already
+   /// reconnecting, the attempt counts as failed, and if it was still joining its first room,
+   /// the loss is fatal.
+   fn check_relay_connection(&mut self) {
+      let lost = matches!(self.state, State::ConnectedToRelay | State::InRoom)
+         && self.relay_socket.as_ref().map_or(false, |socket| socket.is_disconnected());
+      if !lost {
+         return;
+      }
+      let socket = self.relay_socket.take().unwrap();
+      if self.reconnect.is_some() {
+         tracing::warn!("lost connection to the relay while reconnecting");
+         self.reconnect_failed();
+      } else if matches!(self.state, State::InRoom) {
+         tracing::warn!("lost connection to the relay, reconnecting");
+         self.reconnect = Some(Backoff::default());
+         self.last_received_before_loss = Some(socket.health().last_received);
+         self.send_message(MessageKind::Reconnecting);
+         // Peers may come and go while we're away, so everyone is forgotten. The ones still in the
+         // room will introduce themselves again after we say hello.
+         let peer_ids: Vec<PeerId> = self.mates.keys().copied().collect();
+         for peer_id in peer_ids {
+            self.remove_mate(peer_id);
+         }
+         self.schedule_reconnect();
+      } else {
+         tracing::error!("lost connection to the relay");
+         self.state = State::Disconnected;
+         bus::push(Fatal(Error::RelayHasDisconnected));
+      }
+   }
+
+   /// Schedules the next attempt at reconnecting to the relay, backing off exponentially with
+   /// each failed attempt.
+   fn schedule_reconnect(&mut self) {
+      let delay = self.reconnect.unwrap_or_default().delay();
+      tracing::info!("reconnecting in {:?}", delay);
+      let at = Instant::now() + delay;
+      self.state = State::Reconnecting(at);
+      wake::at(at);
+   }
+
+   /// Registers a failed attempt at reconnecting, and gives up once there were too many of them.
+   fn reconnect_failed(&mut self) {
+      let Some(backoff) = &mut self.reconnect else {
+         return;
+      };
+      if backoff.fail() {
+         self.schedule_reconnect();
+      } else {
+         tracing::error!(
+            "giving up on reconnecting after {} attempts",
+            backoff.failed_attempts
+         );
+         self.reconnect = None;
+         self.last_received_before_loss = None;
+         self.outbox.get_mut().clear();
+         self.state = State::Disconnected;
+         bus::push(Fatal(Error::RelayHasDisconnected));
+      }
+   }
+
+   /// Starts connecting to the relay again once the scheduled time comes.
+   fn poll_for_reconnect(&mut self) {
+      if let State::Reconnecting(at) = self.state {
+         if Instant::now() >= at {
+            tracing::info!("reconnecting to {}", self.relay_address);
+            let socket_receiver =
+               Arc::clone(&self.socket_system).connect(self.relay_address.clone());
+            self.state = State::WaitingForRelay(socket_receiver);
          }
       }
+   }
+
+   /// Handles the state transition from reconnecting to being back in a room, sending out the
+   /// packets queued up in the meantime.
+   fn reconnected(&mut self) -> netcanv::Result<()> {
+      tracing::info!("reconnected to the relay");
+      self.reconnect = None;
+      let away = self
+         .last_received_before_loss
+         .take()
+         .map_or(Duration::ZERO, |last_received| last_received.elapsed());
+      self.send_message(MessageKind::Reconnected {
+         away: away + Self::RESYNC_LEEWAY,
+      });
+      let outbox = self.outbox.take();
+      tracing::debug!("sending {} packets queued while reconnecting", outbox.len());
+      for (to, packet) in outbox {
+         self.send_to_client(to, packet)?;
+      }
       Ok(())
    }
 
@@ -175,7 +461,10 @@ impl Peer {
       self.state = State::ConnectedToRelay;
       tracing::info!("connected to relay");
       self.relay_socket = Some(socket);
-      self.send_to_relay(if self.is_host {
+      // A reconnecting host joins its old room rather than hosting a new one. The relay transfers
+      // the host role to another peer once the old connection is dropped, so hosting again would
+      // leave the room with two hosts.
+      self.send_to_relay(if self.is_host && self.reconnect.is_none() {
          relay::Packet::Host
       } else {
          relay::Packet::Join(self.room_id.unwrap())
@@ -186,7 +475,7 @@ impl Peer {
    /// Polls for any incoming packets.
    fn poll_for_incoming_packets(&mut self) -> netcanv::Result<()> {
       match &self.state {
-         State::WaitingForRelay(_) => (),
+         State::WaitingForRelay(_) | State::Reconnecting(_) | State::Disconnected => (),
          State::ConnectedToRelay | State::InRoom => {
             while let Some(packet) = self.relay_socket.as_mut().unwrap().recv() {
                self.relay_packet(packet)?;
@@ -203,16 +492,29 @@ impl Peer {
             tracing::info!("got free room ID: {:?}", room_id);
             self.room_id = Some(room_id);
             self.peer_id = Some(peer_id);
+            self.is_host = true;
+            self.host = None;
             self.state = State::InRoom;
-            bus::push(Connected { peer: self.token });
+            if self.reconnect.is_some() {
+               self.reconnected()?;
+            } else {
+               bus::push(Connected { peer: self.token });
+            }
          }
          relay::Packet::Joined { peer_id, host_id } => {
             tracing::info!("got host ID: {:?}", host_id);
             self.peer_id = Some(peer_id);
+            self.is_host = false;
             self.host = Some(host_id);
             self.state = State::InRoom;
-            bus::push(Connected { peer: self.token });
+            // Packets queued up while reconnecting are only sent after saying hello, so that
+            // everyone knows who they're from.
             self.say_hello()?;
+            if self.reconnect.is_some() {
+               self.reconnected()?;
+            } else {
+               bus::push(Connected { peer: self.token });
+            }
          }
          relay::Packet::HostTransfer(host_id) => {
             if self.peer_id == Some(host_id) {
@@ -220,10 +522,12 @@ impl Peer {
                self.host = None;
                self.is_host = true;
             } else {
-               if let Some(mate) = self.mates.get(&host_id) {
-                  self.send_message(MessageKind::NewHost(mate.nickname.clone()))
-               }
+               let nickname = self.mates.get(&host_id).map(|mate| mate.nickname.clone());
                self.host = Some(host_id);
+               self.send_message(MessageKind::NewHost {
+                  peer_id: host_id,
+                  nickname,
+               });
             }
          }
          relay::Packet::Relayed(author, payload) => {
@@ -234,6 +538,12 @@ impl Peer {
             self.remove_mate(address);
          }
          relay::Packet::Error(error) => match error {
+            relay::Error::RoomDoesNotExist if self.reconnect.is_some() => {
+               // Everyone else left while we were away, so the room was closed. The canvas is
+               // still there though, so it's hosted in a new room.
+               tracing::info!("the room was closed while reconnecting, hosting a new one");
+               self.send_to_relay(relay::Packet::Host)?;
+            }
             relay::Error::NoSuchPeer { address } => {
                // Remove the peer when relay tells us that they are no longer
                // in the room.
@@ -275,6 +585,9 @@ impl Peer {
          }
          cl::Packet::HiThere(nickname) => {
             tracing::info!("{} ({:?}) is in the room", nickname, author);
+            // Peers only learn about each other's versions in response to Hello, so the version
+            // is sent back to let the other side know which packets we understand.
+            self.send_to_client(author, cl::Packet::Version(cl::PROTOCOL_VERSION))?;
             self.add_mate(author, nickname);
          }
          cl::Packet::Reserved1 => (),
@@ -288,9 +601,13 @@ impl Peer {
                Ordering::Equal => unreachable!(),
             }));
          }
-         cl::Packet::Version(_) => (),
+         cl::Packet::Version(version) => {
+            if let Some(mate) = self.mates.get_mut(&author) {
+               mate.version = Some(version);
+            }
+         }
          cl::Packet::ChunkPositions(positions) => {
-            self.send_message(MessageKind::ChunkPositions(positions))
+            self.send_message(MessageKind::ChunkPositions(author, positions))
          }
          cl::Packet::GetChunks(positions) => {
             self.send_message(MessageKind::GetChunks(author, positions))
@@ -302,6 +619,15 @@ impl Peer {
          cl::Packet::Tool(name, payload) => {
             self.send_message(MessageKind::Tool(author, name, payload))
          }
+         cl::Packet::DrawingBounds(bounds) if Some(author) == self.host => {
+            self.send_message(MessageKind::DrawingBounds(bounds))
+         }
+         cl::Packet::DrawingBounds(_) => {
+            tracing::warn!(
+               "{:?} tried to set the drawing bounds, but is not the host",
+               author
+            );
+         }
          cl::Packet::SelectTool(tool) => {
             let mut old_tool = None;
             if let Some(mate) = self.mates.get_mut(&author) {
@@ -313,15 +639,96 @@ impl Peer {
                tool,
             });
          }
+         // -----
+         // 0.4.1
+         // -----
+         cl::Packet::Ping(id) => self.send_to_client(author, cl::Packet::Pong(id))?,
+         cl::Packet::Pong(id) => {
+            if let Some(mate) = self.mates.get_mut(&author) {
+               mate.pong_received(id);
+            }
+         }
+         // -----
+         // 0.4.2
+         // -----
+         cl::Packet::ClearCanvas if Some(author) == self.host => {
+            self.send_message(MessageKind::ClearCanvas)
+         }
+         cl::Packet::ClearCanvas => {
+            tracing::warn!(
+               "{:?} tried to clear the canvas, but is not the host",
+               author
+            );
+         }
+         // -----
+         // 0.4.3
+         // -----
+         cl::Packet::ChunkPatch(position, offset, image_data) => {
+            self.send_message(MessageKind::ChunkPatch(position, offset, image_data))
+         }
+         // -----
+         // 0.4.4
+         // -----
+         cl::Packet::Viewport(viewport) => {
+            if let Some(mate) = self.mates.get_mut(&author) {
+               mate.viewport = viewport;
+            }
+         }
+         // -----
+         // 0.4.5
+         // -----
+         cl::Packet::GetChangedChunks(millis) if self.is_host => self.send_message(
+            MessageKind::GetChangedChunks(author, Duration::from_millis(millis)),
+         ),
+         cl::Packet::GetChangedChunks(_) => {
+            tracing::warn!(
+               "{:?} asked us for changed chunks, but we're not the host",
+               author
+            );
+         }
       }
 
       Ok(())
    }
 
+   /// Pings the other peers in the room every [`Self::PING_INTERVAL`].
+   ///
+   /// Peers that don't respond aren't waited for; their latency simply becomes unknown once the
+   /// next ping is sent.
+   fn ping_mates(&mut self) -> netcanv::Result<()> {
+      if !matches!(self.state, State::InRoom) {
+         return Ok(());
+      }
+      if self.last_ping.elapsed() < Self::PING_INTERVAL {
+         wake::at(self.last_ping + Self::PING_INTERVAL);
+         return Ok(());
+      }
+      self.last_ping = Instant::now();
+      wake::at(self.last_ping + Self::PING_INTERVAL);
+      let id = self.next_ping_id;
+      self.next_ping_id = self.next_ping_id.wrapping_add(1);
+      let pinged: Vec<PeerId> = self
+         .mates
+         .iter_mut()
+         .filter(|(_, mate)| mate.can_be_pinged())
+         .map(|(&peer_id, mate)| {
+            mate.ping_sent(id);
+            peer_id
+         })
+         .collect();
+      for peer_id in pinged {
+         self.send_to_client(peer_id, cl::Packet::Ping(id))?;
+      }
+      Ok(())
+   }
+
    /// Ticks the peer's network connection.
    pub fn communicate(&mut self) -> netcanv::Result<()> {
+      self.poll_for_reconnect();
       self.poll_for_new_connections()?;
       self.poll_for_incoming_packets()?;
+      self.check_relay_connection();
+      self.ping_mates()?;
       Ok(())
    }
 
@@ -332,6 +739,11 @@ impl Peer {
          Mate {
             nickname,
             tool: None,
+            color: Mate::color_for(peer_id),
+            version: None,
+            latency: None,
+            pending_ping: None,
+            viewport: None,
          },
       );
    }
@@ -357,6 +769,24 @@ impl Peer {
       self.send_to_client(to, cl::Packet::ChunkPositions(positions))
    }
 
+   /// Sends the region of the canvas that can be drawn to.
+   pub fn send_drawing_bounds(
+      &self,
+      to: PeerId,
+      bounds: (i32, i32, i32, i32),
+   ) -> netcanv::Result<()> {
+      self.send_to_client(to, cl::Packet::DrawingBounds(bounds))
+   }
+
+   /// Tells all peers that understand it to clear their canvas. Only the host may do this.
+   pub fn send_clear_canvas(&self) -> netcanv::Result<()> {
+      assert!(self.is_host, "only the host can clear the canvas");
+      for (&peer_id, _) in self.mates.iter().filter(|(_, mate)| mate.can_clear_canvas()) {
+         self.send_to_client(peer_id, cl::Packet::ClearCanvas)?;
+      }
+      Ok(())
+   }
+
    /// Requests chunk data from the host.
    pub fn download_chunks(&self, positions: Vec<(i32, i32)>) -> netcanv::Result<()> {
       assert!(self.host.is_some(), "only non-hosts can download chunks");
@@ -365,6 +795,24 @@ impl Peer {
       self.send_to_client(self.host.unwrap(), cl::Packet::GetChunks(positions))
    }
 
+   /// Requests chunk data from the given peer. This is used by a new host to download the chunks
+   /// it's missing from other peers after a host transfer.
+   pub fn request_chunks(&self, from: PeerId, positions: Vec<(i32, i32)>) -> netcanv::Result<()> {
+      tracing::info!("requesting {} chunks from {:?}", positions.len(), from);
+      self.send_to_client(from, cl::Packet::GetChunks(positions))
+   }
+
+   /// Asks the host for the positions of the chunks modified within the given duration. Returns
+   /// `false` without asking if the host doesn't understand the request.
+   pub fn request_changed_chunks(&self, host: PeerId, within: Duration) -> netcanv::Result<bool> {
+      if !self.mates.get(&host).map_or(false, |mate| mate.can_send_changed_chunks()) {
+         return Ok(false);
+      }
+      let millis = within.as_millis().try_into().unwrap_or(u64::MAX);
+      self.send_to_client(host, cl::Packet::GetChangedChunks(millis))?;
+      Ok(true)
+   }
+
    /// Sends chunks to the given peer.
    pub fn send_chunks(
       &self,
@@ -374,6 +822,40 @@ impl Peer {
       self.send_to_client(to, cl::Packet::Chunks(chunks))
    }
 
+   /// Sends a patch replacing a region of a chunk to all peers that understand it. Returns the IDs
+   /// of the peers that don't, which have to be sent the full chunk instead.
+   ///
+   /// While reconnecting, nobody's versions are known, so everyone has to be sent the full chunk.
+   pub fn send_chunk_patch(
+      &self,
+      chunk_position: (i32, i32),
+      offset: (u32, u32),
+      image_data: Vec<u8>,
+   ) -> netcanv::Result<Vec<PeerId>> {
+      if self.reconnect.is_some() {
+         return Ok(vec![PeerId::BROADCAST]);
+      }
+      let mut outdated = Vec::new();
+      for (&peer_id, mate) in &self.mates {
+         if mate.can_receive_chunk_patches() {
+            let packet = cl::Packet::ChunkPatch(chunk_position, offset, image_data.clone());
+            self.send_to_client(peer_id, packet)?;
+         } else {
+            outdated.push(peer_id);
+         }
+      }
+      Ok(outdated)
+   }
+
+   /// Sends the region of the canvas visible in the viewport to all peers that understand it.
+   /// `None` tells them that the viewport is no longer shared.
+   pub fn send_viewport(&self, viewport: Option<(i32, i32, i32, i32)>) -> netcanv::Result<()> {
+      for (&peer_id, _) in self.mates.iter().filter(|(_, mate)| mate.can_receive_viewport()) {
+         self.send_to_client(peer_id, cl::Packet::Viewport(viewport))?;
+      }
+      Ok(())
+   }
+
    /// Sends a tool-specific packet.
    pub fn send_tool(&self, peer_id: PeerId, name: String, payload: Vec<u8>) -> netcanv::Result<()> {
       self.send_to_client(peer_id, cl::Packet::Tool(name, payload))
@@ -394,6 +876,11 @@ impl Peer {
       self.is_host
    }
 
+   /// Returns the ID of the host, or `None` if this peer is the host.
+   pub fn host(&self) -> Option<PeerId> {
+      self.host
+   }
+
    /// Returns the name of the host, or `None` if this peer is the host (or if the host's name isn't
    /// yet known).
    pub fn host_name(&self) -> Option<&str> {
@@ -411,8 +898,130 @@ impl Peer {
       self.room_id
    }
 
+   /// Returns whether the connection to the relay is being reestablished.
+   pub fn is_reconnecting(&self) -> bool {
+      self.reconnect.is_some()
+   }
+
+   /// Returns which attempt at reconnecting to the relay is currently underway, counting from 1,
+   /// or `None` if the peer isn't reconnecting.
+   pub fn reconnect_attempt(&self) -> Option<u32> {
+      self.reconnect.map(|backoff| backoff.attempt())
+   }
+
+   /// Returns whether the peer needs frames to be processed continuously, which is the case while
+   /// the connection to the relay is being established.
+   pub fn needs_redraw(&self) -> bool {
+      matches!(self.state, State::WaitingForRelay(_))
+   }
+
+   /// Returns the health of the connection to the relay, or `None` if the connection isn't
+   /// established yet.
+   pub fn connection_health(&self) -> Option<ConnectionHealth> {
+      self.relay_socket.as_ref().map(|socket| socket.health())
+   }
+
+   /// Returns the round trip time between us and the given peer, or `None` if it isn't known.
+   pub fn latency(&self, peer_id: PeerId) -> Option<Duration> {
+      self.mates.get(&peer_id)?.latency
+   }
+
    /// Returns the list of peers connected to the same room.
    pub fn mates(&self) -> &HashMap<PeerId, Mate> {
       &self.mates
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn mate(version: Option<u32>) -> Mate {
+      Mate {
+         nickname: "mate".into(),
+         tool: None,
+         color: Mate::color_for(PeerId(1)),
+         version,
+         latency: None,
+         pending_ping: None,
+         viewport: None,
+      }
+   }
+
+   #[test]
+   fn only_peers_that_understand_pings_are_pinged() {
+      assert!(!mate(None).can_be_pinged());
+      assert!(!mate(Some(cl::PING_VERSION - 1)).can_be_pinged());
+      assert!(mate(Some(cl::PING_VERSION)).can_be_pinged());
+   }
+
+   #[test]
+   fn answered_ping_measures_latency() {
+      let mut mate = mate(Some(cl::PROTOCOL_VERSION));
+      mate.ping_sent(3);
+      // A pong for a different ping must not be taken as the answer.
+      mate.pong_received(2);
+      assert_eq!(mate.latency, None);
+      mate.pong_received(3);
+      assert!(mate.latency.is_some());
+      assert_eq!(mate.pending_ping, None);
+   }
+
+   #[test]
+   fn unanswered_ping_forgets_latency() {
+      let mut mate = mate(Some(cl::PROTOCOL_VERSION));
+      mate.ping_sent(0);
+      mate.pong_received(0);
+      assert!(mate.latency.is_some());
+
+      mate.ping_sent(1);
+      assert!(mate.latency.is_some());
+      mate.ping_sent(2);
+      assert_eq!(mate.latency, None);
+      // The pong for the abandoned ping arrives too late to count.
+      mate.pong_received(1);
+      assert_eq!(mate.latency, None);
+   }
+
+   #[test]
+   fn only_peers_that_understand_clearing_are_told_to_clear() {
+      assert!(!mate(None).can_clear_canvas());
+      assert!(!mate(Some(cl::CLEAR_CANVAS_VERSION - 1)).can_clear_canvas());
+      assert!(mate(Some(cl::CLEAR_CANVAS_VERSION)).can_clear_canvas());
+      // Peers that aren't told can still join the room.
+      assert!(cl::versions_compatible(
+         cl::CLEAR_CANVAS_VERSION - 1,
+         cl::PROTOCOL_VERSION
+      ));
+   }
+
+   #[test]
+   fn reconnect_delay_doubles_up_to_the_cap() {
+      let mut backoff = Backoff::default();
+      let mut delays = Vec::new();
+      for _ in 0..Backoff::MAX_ATTEMPTS {
+         delays.push(backoff.delay().as_secs());
+         backoff.fail();
+      }
+      assert_eq!(delays, [1, 2, 4, 8, 15, 15]);
+   }
+
+   #[test]
+   fn reconnecting_is_given_up_after_the_last_attempt() {
+      let mut backoff = Backoff::default();
+      for attempt in 1..Backoff::MAX_ATTEMPTS {
+         assert_eq!(backoff.attempt(), attempt);
+         assert!(backoff.fail(), "gave up after attempt {attempt}");
+      }
+      assert_eq!(backoff.attempt(), Backoff::MAX_ATTEMPTS);
+      assert!(!backoff.fail());
+   }
+
+   #[test]
+   fn reconnect_delay_does_not_overflow() {
+      let backoff = Backoff {
+         failed_attempts: u32::MAX,
+      };
+      assert_eq!(backoff.delay(), Backoff::MAX_DELAY);
+   }
+}
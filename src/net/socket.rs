@@ -5,19 +5,20 @@ use std::sync::Arc;
 
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
+use netcanv_protocol::framing::{self, FramingError, Reassembler};
 use netcanv_protocol::relay;
-use nysa::global as bus;
 use parking_lot::Mutex;
 use tokio::net::TcpStream;
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::time::{interval, timeout};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStream};
 use url::Url;
-use web_time::Duration;
+use web_time::{Duration, Instant};
 
-use crate::common::{deserialize_bincode, serialize_bincode, Fatal};
+use crate::common::{deserialize_bincode, serialize_bincode};
+use crate::wake;
 use crate::Error;
 
 /// Runtime for managing active connections.
@@ -70,25 +71,40 @@ impl SocketSystem {
       tracing::debug!("version ok");
 
       let (quit_tx, _) = broadcast::channel(1);
+      let health = Arc::new(Mutex::new(ConnectionHealth::new()));
 
       tracing::debug!("starting receiver loop");
       let (recv_tx, recv_rx) = mpsc::unbounded_channel();
       let (recv_quit_tx, recv_quit_rx) = (quit_tx.clone(), quit_tx.subscribe());
+      let recv_health = Arc::clone(&health);
       let recv_join_handle = tokio::spawn(async move {
-         if let Err(error) =
-            Socket::receiver_loop(stream, recv_tx, recv_quit_tx, recv_quit_rx).await
+         if let Err(error) = Socket::receiver_loop(
+            stream,
+            recv_tx,
+            recv_quit_tx,
+            recv_quit_rx,
+            Arc::clone(&recv_health),
+         )
+         .await
          {
             tracing::error!("receiver loop error: {:?}", error);
          }
+         recv_health.lock().disconnected = true;
+         wake::wake();
       });
 
       tracing::debug!("starting sender loop");
       let (send_tx, send_rx) = mpsc::unbounded_channel();
       let send_quit_rx = quit_tx.subscribe();
+      let send_health = Arc::clone(&health);
       let send_join_handle = tokio::spawn(async move {
-         if let Err(error) = Socket::sender_loop(sink, send_rx, send_quit_rx).await {
+         if let Err(error) =
+            Socket::sender_loop(sink, send_rx, send_quit_rx, Arc::clone(&send_health)).await
+         {
             tracing::error!("sender loop error: {:?}", error);
          }
+         send_health.lock().disconnected = true;
+         wake::wake();
       });
 
       tracing::debug!("registering quitters");
@@ -102,6 +118,7 @@ impl SocketSystem {
       Ok(Socket {
          tx: send_tx,
          rx: recv_rx,
+         health,
       })
    }
 
@@ -138,6 +155,91 @@ impl Drop for SocketSystem {
 pub struct Socket {
    tx: mpsc::UnboundedSender<relay::Packet>,
    rx: mpsc::UnboundedReceiver<relay::Packet>,
+   health: Arc<Mutex<ConnectionHealth>>,
+}
+
+/// How healthy a connection is, judging by its latency and unanswered pings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionStatus {
+   Healthy,
+   Degraded,
+   Unhealthy,
+}
+
+/// Telemetry about a connection, updated as data flows through the socket.
+#[derive(Clone, Debug)]
+pub struct ConnectionHealth {
+   /// The round trip time measured by the most recently answered ping.
+   pub round_trip_time: Option<Duration>,
+   /// The time data was last received from the relay.
+   pub last_received: Instant,
+   /// The number of pings in a row the relay hasn't answered.
+   pub missed_pings: u32,
+   /// Set once the connection is closed, be it by the relay or due to an error.
+   disconnected: bool,
+
+   /// The ID of the ping awaiting a pong, and the time it was sent at.
+   pending_ping: Option<(u64, Instant)>,
+   next_ping_id: u64,
+}
+
+impl ConnectionHealth {
+   /// The interval at which pings are sent to the relay.
+   const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+   /// Round trip times above these are considered degraded and unhealthy, respectively.
+   const DEGRADED_ROUND_TRIP_TIME: Duration = Duration::from_millis(250);
+   const UNHEALTHY_ROUND_TRIP_TIME: Duration = Duration::from_millis(1000);
+
+   /// After this many unanswered pings in a row, the connection is considered dropped, even if
+   /// it was never closed.
+   const DROPPED_MISSED_PINGS: u32 = 5;
+
+   fn new() -> Self {
+      Self {
+         round_trip_time: None,
+         last_received: Instant::now(),
+         missed_pings: 0,
+         disconnected: false,
+         pending_ping: None,
+         next_ping_id: 0,
+      }
+   }
+
+   /// Registers a new ping and returns its ID. If the previous ping wasn't answered yet, it's
+   /// counted as missed.
+   fn ping_sent(&mut self) -> u64 {
+      if self.pending_ping.is_some() {
+         self.missed_pings += 1;
+      }
+      let id = self.next_ping_id;
+      self.next_ping_id = self.next_ping_id.wrapping_add(1);
+      self.pending_ping = Some((id, Instant::now()));
+      id
+   }
+
+   /// Registers a pong with the given ID, measuring the round trip time.
+   fn pong_received(&mut self, id: u64) {
+      if let Some((pending_id, sent_at)) = self.pending_ping {
+         if pending_id == id {
+            self.round_trip_time = Some(sent_at.elapsed());
+            self.missed_pings = 0;
+            self.pending_ping = None;
+         }
+      }
+   }
+
+   /// Returns the overall status of the connection.
+   pub fn status(&self) -> ConnectionStatus {
+      let round_trip_time = self.round_trip_time.unwrap_or_default();
+      if self.missed_pings >= 2 || round_trip_time >= Self::UNHEALTHY_ROUND_TRIP_TIME {
+         ConnectionStatus::Unhealthy
+      } else if self.missed_pings == 1 || round_trip_time >= Self::DEGRADED_ROUND_TRIP_TIME {
+         ConnectionStatus::Degraded
+      } else {
+         ConnectionStatus::Healthy
+      }
+   }
 }
 
 type Stream = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
@@ -149,17 +251,30 @@ impl Socket {
       message: tungstenite::Result<Message>,
       output: &mut mpsc::UnboundedSender<relay::Packet>,
       signal: &broadcast::Sender<Signal>,
+      health: &Mutex<ConnectionHealth>,
+      reassembler: &mut Reassembler,
    ) -> netcanv::Result<bool> {
+      if message.is_ok() {
+         health.lock().last_received = Instant::now();
+      }
       match message {
          Ok(Message::Binary(data)) => {
-            if data.len() > relay::MAX_PACKET_SIZE as usize {
-               return Err(Error::ReceivedPacketThatIsTooBig);
+            let message = reassembler.push(data).map_err(|error| match error {
+               FramingError::FrameTooBig | FramingError::MessageTooBig => {
+                  Error::ReceivedPacketThatIsTooBig
+               }
+               other => Error::InvalidPacketFraming {
+                  error: other.to_string(),
+               },
+            })?;
+            if let Some(data) = message {
+               let packet = deserialize_bincode(&data)?;
+               output.send(packet)?;
+               wake::wake();
             }
-            let packet = deserialize_bincode(&data)?;
-            output.send(packet)?;
          }
          Ok(Message::Close(frame)) => {
-            bus::push(Fatal(Error::RelayHasDisconnected));
+            health.lock().disconnected = true;
 
             if let Some(frame) = frame {
                tracing::warn!(
@@ -178,6 +293,11 @@ impl Socket {
          Ok(Message::Ping(ping)) => {
             signal.send(Signal::SendPong(ping))?;
          }
+         Ok(Message::Pong(pong)) => {
+            if let Ok(id) = <[u8; 8]>::try_from(pong) {
+               health.lock().pong_received(u64::from_le_bytes(id));
+            }
+         }
          Err(e) => {
             use tokio_tungstenite::tungstenite::error::ProtocolError;
             use tokio_tungstenite::tungstenite::Error as WsError;
@@ -188,7 +308,7 @@ impl Socket {
                WsError::AlreadyClosed
                | WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake) => {
                   tracing::error!("the connection was closed without a closing handshake (relay probably crashed)");
-                  bus::push(Fatal(Error::RelayHasDisconnected));
+                  health.lock().disconnected = true;
                   return Ok(true);
                }
                other => {
@@ -209,7 +329,9 @@ impl Socket {
       mut output: mpsc::UnboundedSender<relay::Packet>,
       signal_tx: broadcast::Sender<Signal>,
       mut signal_rx: broadcast::Receiver<Signal>,
+      health: Arc<Mutex<ConnectionHealth>>,
    ) -> netcanv::Result<()> {
+      let mut reassembler = Reassembler::new();
       loop {
          tokio::select! {
             biased;
@@ -220,7 +342,7 @@ impl Socket {
                }
             },
             Some(message) = stream.next() => {
-               if Self::read_packet(message, &mut output, &signal_tx).await? {
+               if Self::read_packet(message, &mut output, &signal_tx, &health, &mut reassembler).await? {
                   break
                }
             },
@@ -233,15 +355,18 @@ impl Socket {
 
    async fn write_packet(sink: &mut Sink, packet: relay::Packet) -> netcanv::Result<()> {
       let bytes = serialize_bincode(&packet)?;
-      if bytes.len() > relay::MAX_PACKET_SIZE as usize {
-         return Err(Error::TriedToSendPacketThatIsTooBig {
-            max: relay::MAX_PACKET_SIZE as usize,
-            size: bytes.len(),
-         });
+      let size = bytes.len();
+      u32::try_from(size).map_err(|_| Error::TriedToSendPacketThatIsWayTooBig)?;
+      // Packets larger than a single frame are split into fragments, which the relay puts back
+      // together before handling them.
+      let frames = framing::split(bytes).ok_or(Error::TriedToSendPacketThatIsTooBig {
+         max: framing::MAX_MESSAGE_SIZE as usize,
+         size,
+      })?;
+
+      for frame in frames {
+         sink.send(Message::Binary(frame)).await?;
       }
-      u32::try_from(bytes.len()).map_err(|_| Error::TriedToSendPacketThatIsWayTooBig)?;
-
-      sink.send(Message::Binary(bytes)).await?;
       Ok(())
    }
 
@@ -249,7 +374,9 @@ impl Socket {
       mut sink: Sink,
       mut input: mpsc::UnboundedReceiver<relay::Packet>,
       mut signal: broadcast::Receiver<Signal>,
+      health: Arc<Mutex<ConnectionHealth>>,
    ) -> netcanv::Result<()> {
+      let mut ping_interval = interval(ConnectionHealth::PING_INTERVAL);
       loop {
          tokio::select! {
             biased;
@@ -271,6 +398,10 @@ impl Socket {
                   break;
                }
             },
+            _ = ping_interval.tick() => {
+               let id = health.lock().ping_sent();
+               sink.send(Message::Ping(id.to_le_bytes().to_vec())).await?;
+            },
             else => (),
          }
       }
@@ -278,15 +409,29 @@ impl Socket {
       Ok(())
    }
 
-   /// Sends a packet to the receiving end of the socket.
+   /// Sends a packet to the receiving end of the socket. If the sender loop has already quit, the
+   /// packet is dropped and the socket is marked as disconnected.
    pub fn send(&self, packet: relay::Packet) {
-      catch!(self.tx.send(packet).map_err(|_| Error::RelayHasDisconnected), as Fatal)
+      if self.tx.send(packet).is_err() {
+         self.health.lock().disconnected = true;
+      }
    }
 
    /// Receives packets from the sending end of the socket.
    pub fn recv(&mut self) -> Option<relay::Packet> {
       self.rx.try_recv().ok()
    }
+
+   /// Returns whether the connection was closed, or has stopped responding to pings.
+   pub fn is_disconnected(&self) -> bool {
+      let health = self.health.lock();
+      health.disconnected || health.missed_pings >= ConnectionHealth::DROPPED_MISSED_PINGS
+   }
+
+   /// Returns a snapshot of the connection's health.
+   pub fn health(&self) -> ConnectionHealth {
+      self.health.lock().clone()
+   }
 }
 
 #[derive(Clone, Debug)]
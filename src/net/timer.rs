@@ -20,11 +20,14 @@ impl Timer {
    }
 
    /// Sets the timer up such that `update()` can be called to process ticks.
+   ///
+   /// At most one tick is processed no matter how much time has passed, as the app may have been
+   /// asleep for a long while and there's no point in catching up on all the ticks it missed.
    pub fn tick(&mut self) {
       let now = Instant::now();
       let elapsed = now - self.last_tick;
       self.last_tick = now;
-      self.lag += elapsed.as_micros() as i64;
+      self.lag = (self.lag + elapsed.as_micros() as i64).min(self.interval);
    }
 
    /// Processes a single tick, and returns whether there are more ticks to be done.
@@ -37,4 +40,34 @@ impl Timer {
          false
       }
    }
+
+   /// Returns when the next tick is due.
+   pub fn next_update(&self) -> Instant {
+      let remaining = (self.interval - self.lag).max(0);
+      self.last_tick + Duration::from_micros(remaining as u64)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn missed_ticks_are_not_caught_up_on() {
+      let mut timer = Timer::new(Duration::from_millis(50));
+      timer.last_tick -= Duration::from_secs(10);
+      timer.tick();
+      assert!(timer.update());
+      assert!(!timer.update());
+   }
+
+   #[test]
+   fn next_update_is_one_interval_after_the_last_update() {
+      let interval = Duration::from_millis(50);
+      let mut timer = Timer::new(interval);
+      timer.last_tick -= interval;
+      timer.tick();
+      assert!(timer.update());
+      assert_eq!(timer.next_update(), timer.last_tick + interval);
+   }
 }
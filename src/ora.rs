@@ -0,0 +1,315 @@
+//! Reading and writing OpenRaster (`.ora`) files, for exchanging canvases with painting programs
+//! such as Krita and MyPaint.
+//!
+//! An OpenRaster file is a ZIP archive containing:
+//!
+//! - a `mimetype` file, which must be the first file in the archive and must be stored without
+//!   compression,
+//! - `stack.xml`, which describes the image's size and its stack of layers,
+//! - a PNG file for each layer,
+//! - `mergedimage.png`, the layers composited together,
+//! - `Thumbnails/thumbnail.png`, a downscaled version of the merged image.
+//!
+//! NetCanv's canvas only has a single layer, but images with multiple layers can be written, and
+//! are flattened when they're read.
+
+use std::io::{Read, Seek, Write};
+
+use image::imageops::FilterType;
+use image::{Pixel, RgbaImage};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::PngCompression;
+use crate::image_coder::ImageCoder;
+use crate::Error;
+
+/// The MIME type stored in the `mimetype` file.
+const MIME_TYPE: &str = "image/openraster";
+
+/// The version of the OpenRaster specification the written files conform to.
+const ORA_VERSION: &str = "0.0.5";
+
+/// The maximum width and height of the thumbnail.
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// The maximum width and height of an image that can be read. This prevents malformed files from
+/// making NetCanv allocate arbitrary amounts of memory.
+const MAX_IMAGE_SIZE: u32 = 32768;
+
+/// A single layer of an OpenRaster image.
+pub struct Layer {
+   pub name: String,
+   /// The position of the layer's top-left corner within the image.
+   pub position: (i32, i32),
+   /// The layer's opacity, from 0 to 1.
+   pub opacity: f32,
+   pub image: RgbaImage,
+}
+
+/// An OpenRaster image.
+pub struct OraImage {
+   pub width: u32,
+   pub height: u32,
+   /// The layers, from the topmost to the bottommost one, the same as in `stack.xml`.
+   pub layers: Vec<Layer>,
+}
+
+impl OraImage {
+   /// Composites all the layers onto a single, transparent image.
+   pub fn merge(&self) -> RgbaImage {
+      let mut merged = RgbaImage::new(self.width, self.height);
+      for layer in self.layers.iter().rev() {
+         for (x, y, pixel) in layer.image.enumerate_pixels() {
+            let (x, y) = (layer.position.0 + x as i32, layer.position.1 + y as i32);
+            if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+               continue;
+            }
+            let mut pixel = *pixel;
+            pixel[3] = (pixel[3] as f32 * layer.opacity.clamp(0.0, 1.0)).round() as u8;
+            merged.get_pixel_mut(x as u32, y as u32).blend(&pixel);
+         }
+      }
+      merged
+   }
+
+   /// Writes the image as an OpenRaster file. PNGs inside the file are compressed with the given
+   /// compression level.
+   pub fn write(
+      &self,
+      writer: impl Write + Seek,
+      compression: PngCompression,
+   ) -> netcanv::Result<()> {
+      let mut zip = ZipWriter::new(writer);
+      let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+      zip.start_file("mimetype", stored)?;
+      zip.write_all(MIME_TYPE.as_bytes())?;
+
+      zip.start_file("stack.xml", SimpleFileOptions::default())?;
+      zip.write_all(self.stack_xml().as_bytes())?;
+
+      // PNGs are compressed already, so there's no use in compressing them again.
+      for (index, layer) in self.layers.iter().enumerate() {
+         zip.start_file(Self::layer_path(index), stored)?;
+         zip.write_all(&ImageCoder::encode_png(&layer.image, compression)?)?;
+      }
+
+      let merged = self.merge();
+      let scale = THUMBNAIL_SIZE as f32 / self.width.max(self.height).max(1) as f32;
+      let thumbnail = if scale < 1.0 {
+         let width = ((self.width as f32 * scale) as u32).max(1);
+         let height = ((self.height as f32 * scale) as u32).max(1);
+         image::imageops::resize(&merged, width, height, FilterType::Triangle)
+      } else {
+         merged.clone()
+      };
+      zip.start_file("mergedimage.png", stored)?;
+      zip.write_all(&ImageCoder::encode_png(&merged, compression)?)?;
+      zip.start_file("Thumbnails/thumbnail.png", stored)?;
+      zip.write_all(&ImageCoder::encode_png(&thumbnail, compression)?)?;
+
+      zip.finish()?;
+      Ok(())
+   }
+
+   /// Reads an OpenRaster file.
+   ///
+   /// Layers that are hidden are skipped, and nested stacks are flattened into a single stack of
+   /// layers. All layers are treated as if they were blended normally.
+   pub fn read(reader: impl Read + Seek) -> netcanv::Result<Self> {
+      let mut zip = ZipArchive::new(reader)?;
+      let mut mime_type = String::new();
+      zip.by_name("mimetype")?.read_to_string(&mut mime_type)?;
+      if mime_type.trim() != MIME_TYPE {
+         return Err(Error::InvalidOraFile {
+            error: format!("unexpected MIME type {:?}", mime_type),
+         });
+      }
+
+      let mut stack_xml = String::new();
+      zip.by_name("stack.xml")?.read_to_string(&mut stack_xml)?;
+      let document =
+         roxmltree::Document::parse(&stack_xml).map_err(|error| Error::InvalidOraFile {
+            error: error.to_string(),
+         })?;
+      let root = document.root_element();
+      if !root.has_tag_name("image") {
+         return Err(Error::InvalidOraFile {
+            error: "the root element of stack.xml is not <image>".into(),
+         });
+      }
+      let width: u32 = Self::parse_attribute(root, "w")?;
+      let height: u32 = Self::parse_attribute(root, "h")?;
+      if width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+         return Err(Error::InvalidOraFile {
+            error: format!("the image is too big ({}×{})", width, height),
+         });
+      }
+      let stack = root.children().find(|node| node.has_tag_name("stack")).ok_or_else(|| {
+         Error::InvalidOraFile {
+            error: "stack.xml does not contain a stack".into(),
+         }
+      })?;
+
+      let mut layers = Vec::new();
+      Self::read_stack(&mut zip, stack, (0, 0), &mut layers)?;
+      Ok(Self {
+         width,
+         height,
+         layers,
+      })
+   }
+
+   /// Reads the layers of a stack, offsetting them by the position of the stack.
+   fn read_stack<R: Read + Seek>(
+      zip: &mut ZipArchive<R>,
+      stack: roxmltree::Node,
+      offset: (i32, i32),
+      layers: &mut Vec<Layer>,
+   ) -> netcanv::Result<()> {
+      for node in stack.children().filter(|node| node.is_element()) {
+         if node.attribute("visibility") == Some("hidden") {
+            continue;
+         }
+         let x: i32 = Self::parse_optional_attribute(node, "x")?.unwrap_or(0);
+         let y: i32 = Self::parse_optional_attribute(node, "y")?.unwrap_or(0);
+         let position = (offset.0 + x, offset.1 + y);
+         if node.has_tag_name("stack") {
+            Self::read_stack(zip, node, position, layers)?;
+         } else if node.has_tag_name("layer") {
+            let src = node.attribute("src").ok_or_else(|| Error::InvalidOraFile {
+               error: "a layer is missing its source image".into(),
+            })?;
+            let mut data = Vec::new();
+            zip.by_name(src)?.read_to_end(&mut data)?;
+            let image = image::load_from_memory(&data)?.into_rgba8();
+            if image.width() > MAX_IMAGE_SIZE || image.height() > MAX_IMAGE_SIZE {
+               return Err(Error::InvalidOraFile {
+                  error: format!("layer {:?} is too big", src),
+               });
+            }
+            layers.push(Layer {
+               name: node.attribute("name").unwrap_or_default().to_owned(),
+               position,
+               opacity: Self::parse_optional_attribute(node, "opacity")?.unwrap_or(1.0),
+               image,
+            });
+         }
+      }
+      Ok(())
+   }
+
+   /// Returns the path of the PNG file storing the layer with the given index.
+   fn layer_path(index: usize) -> String {
+      format!("data/layer{}.png", index)
+   }
+
+   /// Generates the contents of the `stack.xml` file.
+   fn stack_xml(&self) -> String {
+      let mut xml = String::new();
+      xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+      xml.push_str(&format!(
+         "<image version=\"{}\" w=\"{}\" h=\"{}\">\n",
+         ORA_VERSION, self.width, self.height
+      ));
+      xml.push_str("  <stack>\n");
+      for (index, layer) in self.layers.iter().enumerate() {
+         xml.push_str(&format!(
+            "    <layer name=\"{}\" src=\"{}\" x=\"{}\" y=\"{}\" opacity=\"{}\" \
+               visibility=\"visible\" composite-op=\"svg:src-over\"/>\n",
+            Self::escape_xml(&layer.name),
+            Self::layer_path(index),
+            layer.position.0,
+            layer.position.1,
+            layer.opacity,
+         ));
+      }
+      xml.push_str("  </stack>\n");
+      xml.push_str("</image>\n");
+      xml
+   }
+
+   /// Escapes characters that have a special meaning inside XML attribute values.
+   fn escape_xml(text: &str) -> String {
+      let mut escaped = String::with_capacity(text.len());
+      for c in text.chars() {
+         match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+         }
+      }
+      escaped
+   }
+
+   /// Parses an attribute of an element, returning `None` if the element doesn't have it.
+   fn parse_optional_attribute<T: std::str::FromStr>(
+      node: roxmltree::Node,
+      name: &str,
+   ) -> netcanv::Result<Option<T>> {
+      node
+         .attribute(name)
+         .map(|value| {
+            value.trim().parse().map_err(|_| Error::InvalidOraFile {
+               error: format!("invalid value of attribute {:?}: {:?}", name, value),
+            })
+         })
+         .transpose()
+   }
+
+   /// Parses a required attribute of an element.
+   fn parse_attribute<T: std::str::FromStr>(
+      node: roxmltree::Node,
+      name: &str,
+   ) -> netcanv::Result<T> {
+      Self::parse_optional_attribute(node, name)?.ok_or_else(|| Error::InvalidOraFile {
+         error: format!("missing attribute {:?}", name),
+      })
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::io::Cursor;
+
+   use image::Rgba;
+
+   use super::*;
+
+   #[test]
+   fn images_round_trip_through_ora() {
+      let mut image = RgbaImage::new(300, 200);
+      for (x, y, pixel) in image.enumerate_pixels_mut() {
+         // Fully transparent pixels lose their color when layers are merged, so they're kept
+         // black.
+         *pixel = match x % 3 {
+            0 => Rgba([0, 0, 0, 0]),
+            1 => Rgba([x as u8, y as u8, (x + y) as u8, 128]),
+            _ => Rgba([x as u8, y as u8, (x + y) as u8, 255]),
+         };
+      }
+      let ora = OraImage {
+         width: image.width(),
+         height: image.height(),
+         layers: vec![Layer {
+            name: "Canvas & <friends>".into(),
+            position: (0, 0),
+            opacity: 1.0,
+            image: image.clone(),
+         }],
+      };
+
+      let mut file = Cursor::new(Vec::new());
+      ora.write(&mut file, PngCompression::Fast).unwrap();
+      file.set_position(0);
+      let read = OraImage::read(file).unwrap();
+
+      assert_eq!((read.width, read.height), (300, 200));
+      assert_eq!(read.layers.len(), 1);
+      assert_eq!(read.layers[0].name, "Canvas & <friends>");
+      assert_eq!(read.merge(), image);
+   }
+}
@@ -4,12 +4,13 @@ use web_time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct CachedChunk {
-   pub png: Vec<u8>,
-   pub webp: Option<Vec<u8>>,
+   /// The encoded image, in the format picked for sending it over the network.
+   pub data: Vec<u8>,
 }
 
+/// Encoded chunk images, along with the chunk revision they were encoded from.
 pub struct CacheLayer {
-   chunks: HashMap<(i32, i32), CachedChunk>,
+   chunks: HashMap<(i32, i32), (u64, CachedChunk)>,
    chunk_cache_timers: HashMap<(i32, i32), Instant>,
 }
 
@@ -25,16 +26,31 @@ impl CacheLayer {
       }
    }
 
-   pub fn chunk(&mut self, position: (i32, i32)) -> Option<&CachedChunk> {
-      self.chunk_cache_timers.insert(position, Instant::now());
-      self.chunks.get(&position)
+   /// Returns the cached images of the chunk at the given position, but only if they were encoded
+   /// from the given revision of the chunk. Stale images are never returned.
+   pub fn chunk(&mut self, position: (i32, i32), revision: u64) -> Option<&CachedChunk> {
+      match self.chunks.get(&position) {
+         Some((cached_revision, chunk)) if *cached_revision == revision => {
+            self.chunk_cache_timers.insert(position, Instant::now());
+            Some(chunk)
+         }
+         _ => None,
+      }
    }
 
-   pub fn set_chunk(&mut self, position: (i32, i32), chunk: CachedChunk) {
-      self.chunks.insert(position, chunk);
+   /// Caches the images of the chunk at the given position, encoded from the given revision.
+   pub fn set_chunk(&mut self, position: (i32, i32), revision: u64, chunk: CachedChunk) {
+      self.chunks.insert(position, (revision, chunk));
       self.chunk_cache_timers.insert(position, Instant::now());
    }
 
+   /// Drops the cached images of the chunk at the given position, such that it gets re-encoded
+   /// the next time it's requested.
+   pub fn invalidate_chunk(&mut self, position: (i32, i32)) {
+      self.chunks.remove(&position);
+      self.chunk_cache_timers.remove(&position);
+   }
+
    pub fn update_timers(&mut self) {
       for (position, instant) in &self.chunk_cache_timers {
          if instant.elapsed() > Self::CHUNK_CACHE_DURATION {
@@ -43,3 +59,33 @@ impl CacheLayer {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn encoded(byte: u8) -> CachedChunk {
+      CachedChunk { data: vec![byte] }
+   }
+
+   #[test]
+   fn unmodified_chunk_reuses_its_encoding() {
+      let mut cache = CacheLayer::new();
+      cache.set_chunk((0, 0), 3, encoded(1));
+      let chunk = cache.chunk((0, 0), 3).expect("the chunk should be cached");
+      assert_eq!(chunk.data, [1]);
+   }
+
+   #[test]
+   fn modified_chunk_gets_reencoded() {
+      let mut cache = CacheLayer::new();
+      cache.set_chunk((0, 0), 3, encoded(1));
+      // The chunk has been drawn to since it was encoded, so the cached image is stale.
+      assert!(cache.chunk((0, 0), 4).is_none());
+
+      cache.set_chunk((0, 0), 4, encoded(2));
+      let chunk = cache.chunk((0, 0), 4).expect("the new encoding should be cached");
+      assert_eq!(chunk.data, [2]);
+      assert!(cache.chunk((0, 0), 3).is_none());
+   }
+}
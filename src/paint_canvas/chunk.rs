@@ -1,6 +1,7 @@
 use ::image::{ImageBuffer, Rgba, RgbaImage};
 use netcanv_renderer::paws::Point;
 use netcanv_renderer::{Framebuffer as FramebufferTrait, RenderBackend};
+use web_time::Instant;
 
 use crate::backend::{Backend, Framebuffer};
 
@@ -8,6 +9,9 @@ use crate::backend::{Backend, Framebuffer};
 pub struct Chunk {
    pub framebuffer: Framebuffer,
    dirty: bool,
+   revision: u64,
+   /// When the chunk was last modified.
+   modified: Instant,
 }
 
 impl Chunk {
@@ -16,9 +20,14 @@ impl Chunk {
 
    /// Creates a new chunk, using the given canvas as a Skia surface allocator.
    pub fn new(renderer: &mut Backend) -> Self {
+      let mut framebuffer = renderer.create_framebuffer(Self::SIZE.0, Self::SIZE.1);
+      // Mipmaps prevent the canvas from shimmering when zoomed out.
+      framebuffer.set_mipmapping(true);
       Self {
-         framebuffer: renderer.create_framebuffer(Self::SIZE.0, Self::SIZE.1),
+         framebuffer,
          dirty: false,
+         revision: 0,
+         modified: Instant::now(),
       }
    }
 
@@ -43,17 +52,39 @@ impl Chunk {
       image_buffer
    }
 
-   /// Uploads the image of the chunk to the graphics card, at the given offset in the master
-   /// chunk.
+   /// Uploads the image to the graphics card, at the given offset in the master chunk. The image
+   /// may be smaller than the chunk, in which case only the region it covers is replaced.
    pub fn upload_image(&mut self, renderer: &mut Backend, image: &RgbaImage, offset: (u32, u32)) {
       self.mark_dirty();
-      renderer.upload_framebuffer(&self.framebuffer, offset, Self::SIZE, image);
+      renderer.upload_framebuffer(&self.framebuffer, offset, image.dimensions(), image);
    }
 
    /// Marks the chunk as dirty - that is, invalidates any cached PNG and WebP data,
    /// and marks it as unsaved.
    pub fn mark_dirty(&mut self) {
       self.dirty = true;
+      self.revision += 1;
+      self.modified = Instant::now();
+   }
+
+   /// Returns the chunk's revision, which changes every time the chunk is modified.
+   ///
+   /// This can be used to tell whether the chunk was modified since some earlier point in time.
+   pub fn revision(&self) -> u64 {
+      self.revision
+   }
+
+   /// Returns when the chunk was last modified, or created if it was never modified.
+   pub fn modified(&self) -> Instant {
+      self.modified
+   }
+
+   /// Copies the chunk's image into a new framebuffer. The pixels are copied directly on the
+   /// graphics card.
+   pub fn duplicate(&self, renderer: &mut Backend) -> Framebuffer {
+      let framebuffer = renderer.create_framebuffer(Self::SIZE.0, Self::SIZE.1);
+      renderer.copy_framebuffer(&self.framebuffer, (0, 0), Self::SIZE, &framebuffer, (0, 0));
+      framebuffer
    }
 
    /// Marks the given sub-chunk within this master chunk as saved.
@@ -63,7 +94,34 @@ impl Chunk {
 
    /// Iterates through all pixels within the image and checks whether any pixels in the image are
    /// not transparent.
+   ///
+   /// Only the alpha channel is taken into account, as fully transparent pixels are invisible no
+   /// matter what color they carry.
    pub fn image_is_empty(image: &RgbaImage) -> bool {
-      image.iter().all(|x| *x == 0)
+      image.pixels().all(|pixel| pixel.0[3] == 0)
+   }
+
+   /// Returns the smallest rectangle containing all pixels that differ between the two images, as
+   /// its x, y, width, and height. Returns `None` if the images are identical.
+   ///
+   /// Both images must be of the same size.
+   pub fn changed_region(old: &RgbaImage, new: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+      assert_eq!(
+         old.dimensions(),
+         new.dimensions(),
+         "images must be of the same size"
+      );
+      let mut changed: Option<(u32, u32, u32, u32)> = None;
+      for ((x, y, old), new) in old.enumerate_pixels().zip(new.pixels()) {
+         if old != new {
+            changed = Some(match changed {
+               Some((left, top, right, bottom)) => {
+                  (left.min(x), top.min(y), right.max(x), bottom.max(y))
+               }
+               None => (x, y, x, y),
+            });
+         }
+      }
+      changed.map(|(left, top, right, bottom)| (left, top, right - left + 1, bottom - top + 1))
    }
 }
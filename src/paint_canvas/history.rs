@@ -0,0 +1,398 @@
+//! The local user's undo history.
+//!
+//! Each step in the history holds copies of the chunks touched by a single action, such as a brush
+//! stroke or placing a selection, from both before and after the action. Only the local user's
+//! actions are recorded, so undoing never touches chunks that were only painted by other peers.
+//!
+//! Undoing a step only restores the pixels the step changed, so anything other peers painted
+//! elsewhere on the same chunks is left intact. If somebody else painted over any of those pixels
+//! after the step was recorded, the step can no longer be undone (or redone), as restoring them
+//! would wipe out their work. The same goes for steps whose layers were removed since.
+
+use std::collections::{HashMap, VecDeque};
+
+use image::RgbaImage;
+use netcanv_renderer::RenderBackend;
+
+use super::chunk::Chunk;
+use super::layer::{Layer, LayerId};
+use crate::backend::{Backend, Framebuffer};
+
+/// A chunk's images from before and after a step.
+struct ChunkSnapshot {
+   before: Framebuffer,
+   /// `None` while the step is still being recorded.
+   after: Option<Framebuffer>,
+}
+
+/// A single undoable action.
+#[derive(Default)]
+struct Step {
+   chunks: HashMap<(LayerId, (i32, i32)), ChunkSnapshot>,
+}
+
+/// A chunk restored by undoing or redoing a step.
+pub struct RestoredChunk {
+   pub layer: LayerId,
+   pub position: (i32, i32),
+   /// The chunk's new image.
+   pub image: RgbaImage,
+   /// The region of the chunk that changed, as its x, y, width, and height. `None` if the chunk
+   /// looks the same as before.
+   pub changed: Option<(u32, u32, u32, u32)>,
+}
+
+/// The result of undoing or redoing a step.
+pub enum HistoryOutcome {
+   /// There was nothing to undo or redo.
+   Empty,
+   /// Pixels changed by the step were painted over by somebody else since, so the step was
+   /// dropped.
+   Conflict,
+   /// The step's chunks were restored.
+   Restored(Vec<RestoredChunk>),
+}
+
+/// A bounded stack of undoable steps, along with the steps that can be redone.
+struct Stacks<T> {
+   undo: VecDeque<T>,
+   redo: Vec<T>,
+   /// The maximum number of steps that can be undone. Older steps are forgotten.
+   capacity: usize,
+}
+
+impl<T> Stacks<T> {
+   fn new(capacity: usize) -> Self {
+      Self {
+         undo: VecDeque::new(),
+         redo: Vec::new(),
+         capacity,
+      }
+   }
+
+   /// Pushes a newly finished step, clearing the steps that could be redone.
+   fn push_finished(&mut self, step: T) {
+      self.redo.clear();
+      self.push_undo(step);
+   }
+
+   /// Pushes a step onto the undo stack, forgetting the oldest step if there are too many.
+   fn push_undo(&mut self, step: T) {
+      self.undo.push_back(step);
+      while self.undo.len() > self.capacity {
+         self.undo.pop_front();
+      }
+   }
+
+   fn pop_undo(&mut self) -> Option<T> {
+      self.undo.pop_back()
+   }
+
+   fn push_redo(&mut self, step: T) {
+      self.redo.push(step);
+   }
+
+   fn pop_redo(&mut self) -> Option<T> {
+      self.redo.pop()
+   }
+}
+
+/// The local user's undo history.
+pub struct History {
+   stacks: Stacks<Step>,
+   /// The step being recorded.
+   current: Option<Step>,
+}
+
+impl History {
+   /// Creates an empty history, which remembers up to `capacity` steps.
+   pub fn new(capacity: usize) -> Self {
+      Self {
+         stacks: Stacks::new(capacity),
+         current: None,
+      }
+   }
+
+   /// Returns whether a step is being recorded.
+   pub fn is_recording(&self) -> bool {
+      self.current.is_some()
+   }
+
+   /// Starts recording a new step. Does nothing if a step is already being recorded, in which case
+   /// the new changes become part of that step.
+   pub fn begin(&mut self) {
+      if self.current.is_none() {
+         self.current = Some(Step::default());
+      }
+   }
+
+   /// Backs up the layer's chunks at the given positions that weren't backed up yet, if a step is
+   /// being recorded. This must be called before the chunks are drawn to.
+   pub fn back_up(
+      &mut self,
+      renderer: &mut Backend,
+      layer: &Layer,
+      positions: impl Iterator<Item = (i32, i32)>,
+   ) {
+      let Some(step) = &mut self.current else {
+         return;
+      };
+      for position in positions {
+         step.chunks.entry((layer.id(), position)).or_insert_with(|| ChunkSnapshot {
+            before: Self::copy_chunk(renderer, Some(layer), position),
+            after: None,
+         });
+      }
+   }
+
+   /// Finishes recording the current step, making it the one that gets undone next.
+   ///
+   /// Steps that didn't touch any chunks are discarded. Finishing a step clears the steps that
+   /// could be redone.
+   pub fn finish(&mut self, renderer: &mut Backend, layers: &[Layer]) {
+      let Some(mut step) = self.current.take() else {
+         return;
+      };
+      if step.chunks.is_empty() {
+         return;
+      }
+      for (&(layer_id, position), snapshot) in &mut step.chunks {
+         let layer = Self::layer(layers, layer_id);
+         snapshot.after = Some(Self::copy_chunk(renderer, layer, position));
+      }
+      self.stacks.push_finished(step);
+   }
+
+   /// Undoes the last step, restoring the pixels it changed to how they looked before it.
+   pub fn undo(&mut self, renderer: &mut Backend, layers: &mut [Layer]) -> HistoryOutcome {
+      let Some(step) = self.stacks.pop_undo() else {
+         return HistoryOutcome::Empty;
+      };
+      match Self::restore(renderer, layers, &step, Self::after, Self::before) {
+         Some(restored) => {
+            self.stacks.push_redo(step);
+            HistoryOutcome::Restored(restored)
+         }
+         None => HistoryOutcome::Conflict,
+      }
+   }
+
+   /// Redoes the last undone step, restoring the pixels it changed to how they looked after it.
+   pub fn redo(&mut self, renderer: &mut Backend, layers: &mut [Layer]) -> HistoryOutcome {
+      let Some(step) = self.stacks.pop_redo() else {
+         return HistoryOutcome::Empty;
+      };
+      match Self::restore(renderer, layers, &step, Self::before, Self::after) {
+         Some(restored) => {
+            self.stacks.push_undo(step);
+            HistoryOutcome::Restored(restored)
+         }
+         None => HistoryOutcome::Conflict,
+      }
+   }
+
+   fn before(snapshot: &ChunkSnapshot) -> &Framebuffer {
+      &snapshot.before
+   }
+
+   fn after(snapshot: &ChunkSnapshot) -> &Framebuffer {
+      snapshot.after.as_ref().expect("recorded steps must have their after images")
+   }
+
+   /// Restores the pixels that differ between the step's `expected` and `target` images to the
+   /// `target` image. Returns `None` if any of those pixels no longer look as `expected`, or any
+   /// of the layers were removed, in which case nothing is restored.
+   fn restore(
+      renderer: &mut Backend,
+      layers: &mut [Layer],
+      step: &Step,
+      expected: impl Fn(&ChunkSnapshot) -> &Framebuffer,
+      target: impl Fn(&ChunkSnapshot) -> &Framebuffer,
+   ) -> Option<Vec<RestoredChunk>> {
+      // All chunks are checked before any of them is modified, such that a conflict in one of them
+      // leaves the others untouched.
+      let mut images = Vec::with_capacity(step.chunks.len());
+      for (&(layer_id, position), snapshot) in &step.chunks {
+         let layer = Self::layer(layers, layer_id)?;
+         let current = match layer.chunks().get(&position) {
+            Some(chunk) => chunk.download_image(renderer),
+            None => RgbaImage::new(Chunk::SIZE.0, Chunk::SIZE.1),
+         };
+         let expected = Self::download(renderer, expected(snapshot));
+         let target = Self::download(renderer, target(snapshot));
+         let restored = restore_pixels(&current, &expected, &target)?;
+         images.push((layer_id, position, current, restored));
+      }
+
+      let mut restored = Vec::with_capacity(images.len());
+      for (layer_id, position, current, image) in images {
+         let changed = Chunk::changed_region(&current, &image);
+         if changed.is_some() {
+            let layer = layers
+               .iter_mut()
+               .find(|layer| layer.id() == layer_id)
+               .expect("layers must exist after checking for conflicts");
+            let chunk = layer.chunks.entry(position).or_insert_with(|| Chunk::new(renderer));
+            chunk.upload_image(renderer, &image, (0, 0));
+         }
+         restored.push(RestoredChunk {
+            layer: layer_id,
+            position,
+            image,
+            changed,
+         });
+      }
+      Some(restored)
+   }
+
+   /// Downloads a snapshot's image from the graphics card.
+   fn download(renderer: &mut Backend, framebuffer: &Framebuffer) -> RgbaImage {
+      let mut image = RgbaImage::new(Chunk::SIZE.0, Chunk::SIZE.1);
+      renderer.download_framebuffer(framebuffer, (0, 0), Chunk::SIZE, &mut image);
+      image
+   }
+
+   /// Copies the image of the layer's chunk at the given position. Chunks that don't exist, and
+   /// chunks of layers that don't exist, are blank.
+   fn copy_chunk(
+      renderer: &mut Backend,
+      layer: Option<&Layer>,
+      position: (i32, i32),
+   ) -> Framebuffer {
+      match layer.and_then(|layer| layer.chunks().get(&position)) {
+         Some(chunk) => chunk.duplicate(renderer),
+         None => renderer.create_framebuffer(Chunk::SIZE.0, Chunk::SIZE.1),
+      }
+   }
+
+   fn layer(layers: &[Layer], id: LayerId) -> Option<&Layer> {
+      layers.iter().find(|layer| layer.id() == id)
+   }
+}
+
+/// Changes the pixels that differ between `expected` and `target` to the ones from `target`, and
+/// leaves all other pixels of `current` as they are.
+///
+/// Returns `None` if any of the changed pixels differ between `current` and `expected`, as that
+/// means somebody painted over them.
+fn restore_pixels(
+   current: &RgbaImage,
+   expected: &RgbaImage,
+   target: &RgbaImage,
+) -> Option<RgbaImage> {
+   let mut restored = current.clone();
+   for ((pixel, expected), target) in
+      restored.pixels_mut().zip(expected.pixels()).zip(target.pixels())
+   {
+      if expected != target {
+         if pixel != expected {
+            return None;
+         }
+         *pixel = *target;
+      }
+   }
+   Some(restored)
+}
+
+#[cfg(test)]
+mod tests {
+   use image::Rgba;
+
+   use super::*;
+
+   const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+   const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+   /// Returns a blank image with the given pixels painted in.
+   fn image(pixels: &[((u32, u32), Rgba<u8>)]) -> RgbaImage {
+      let mut image = RgbaImage::new(4, 4);
+      for &((x, y), color) in pixels {
+         image.put_pixel(x, y, color);
+      }
+      image
+   }
+
+   #[test]
+   fn steps_move_between_undo_and_redo() {
+      let mut stacks = Stacks::new(8);
+      stacks.push_finished(1);
+      stacks.push_finished(2);
+
+      let step = stacks.pop_undo().unwrap();
+      assert_eq!(step, 2);
+      stacks.push_redo(step);
+      let step = stacks.pop_undo().unwrap();
+      assert_eq!(step, 1);
+      stacks.push_redo(step);
+      assert_eq!(stacks.pop_undo(), None);
+
+      let step = stacks.pop_redo().unwrap();
+      assert_eq!(step, 1);
+      stacks.push_undo(step);
+      assert_eq!(stacks.pop_redo(), Some(2));
+      assert_eq!(stacks.pop_redo(), None);
+   }
+
+   #[test]
+   fn finishing_a_step_forgets_undone_steps() {
+      let mut stacks = Stacks::new(8);
+      stacks.push_finished(1);
+      let step = stacks.pop_undo().unwrap();
+      stacks.push_redo(step);
+
+      stacks.push_finished(2);
+      assert_eq!(stacks.pop_redo(), None);
+      assert_eq!(stacks.pop_undo(), Some(2));
+      assert_eq!(stacks.pop_undo(), None);
+   }
+
+   #[test]
+   fn oldest_steps_are_forgotten_past_capacity() {
+      let mut stacks = Stacks::new(2);
+      for step in 1..=3 {
+         stacks.push_finished(step);
+      }
+      assert_eq!(stacks.pop_undo(), Some(3));
+      assert_eq!(stacks.pop_undo(), Some(2));
+      assert_eq!(stacks.pop_undo(), None);
+   }
+
+   #[test]
+   fn undoing_restores_only_the_changed_pixels() {
+      let before = image(&[]);
+      let after = image(&[((0, 0), RED)]);
+      let restored = restore_pixels(&after, &after, &before).unwrap();
+      assert_eq!(restored, before);
+      let redone = restore_pixels(&restored, &before, &after).unwrap();
+      assert_eq!(redone, after);
+   }
+
+   #[test]
+   fn peer_work_elsewhere_on_the_chunk_is_kept() {
+      let before = image(&[]);
+      let after = image(&[((0, 0), RED)]);
+      let current = image(&[((0, 0), RED), ((3, 3), BLUE)]);
+      let restored = restore_pixels(&current, &after, &before).unwrap();
+      assert_eq!(restored, image(&[((3, 3), BLUE)]));
+   }
+
+   #[test]
+   fn peer_work_over_the_changed_pixels_is_a_conflict() {
+      let before = image(&[]);
+      let after = image(&[((0, 0), RED)]);
+      let current = image(&[((0, 0), BLUE)]);
+      assert!(restore_pixels(&current, &after, &before).is_none());
+      // Redoing is refused just the same after the pixels were painted over.
+      assert!(restore_pixels(&current, &before, &after).is_none());
+   }
+
+   #[test]
+   fn consecutive_steps_on_the_same_pixels_can_all_be_undone() {
+      let blank = image(&[]);
+      let red = image(&[((0, 0), RED)]);
+      let blue = image(&[((0, 0), BLUE)]);
+      let restored = restore_pixels(&blue, &blue, &red).unwrap();
+      let restored = restore_pixels(&restored, &red, &blank).unwrap();
+      assert_eq!(restored, blank);
+   }
+}
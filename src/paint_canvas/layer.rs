@@ -0,0 +1,82 @@
+//! Layers of the paint canvas.
+//!
+//! Each layer holds its own grid of chunks, which is composited on top of the layers below it
+//! using the layer's opacity and blend mode. The bottommost layer is the _base layer_; it always
+//! exists, and it's the only layer that is synchronized with other peers for the time being.
+
+use std::collections::HashMap;
+
+use netcanv_renderer::BlendMode;
+
+use super::chunk::Chunk;
+
+/// The ID of a layer. IDs stay the same for the entire lifetime of a layer, even as other layers
+/// are added or removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId(pub u32);
+
+impl LayerId {
+   /// The ID of the base layer.
+   pub const BASE: Self = Self(0);
+}
+
+/// A single layer of the paint canvas.
+pub struct Layer {
+   id: LayerId,
+   pub(super) chunks: HashMap<(i32, i32), Chunk>,
+   /// The layer's opacity, from 0 to 1.
+   pub opacity: f32,
+   /// The blend mode used for compositing the layer onto the layers below it.
+   pub blend_mode: BlendMode,
+}
+
+impl Layer {
+   /// Creates a new, empty layer with the given ID.
+   pub fn new(id: LayerId) -> Self {
+      Self {
+         id,
+         chunks: HashMap::new(),
+         opacity: 1.0,
+         blend_mode: BlendMode::Alpha,
+      }
+   }
+
+   /// Returns the layer's ID.
+   pub fn id(&self) -> LayerId {
+      self.id
+   }
+
+   /// Returns whether the layer is fully opaque and blends normally, in which case it covers the
+   /// layers below it completely wherever its pixels are opaque.
+   pub fn is_plain(&self) -> bool {
+      self.opacity >= 1.0 && self.blend_mode == BlendMode::Alpha
+   }
+
+   /// Returns the chunks of the layer.
+   pub fn chunks(&self) -> &HashMap<(i32, i32), Chunk> {
+      &self.chunks
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn new_layers_are_empty_and_plain() {
+      let layer = Layer::new(LayerId(3));
+      assert_eq!(layer.id(), LayerId(3));
+      assert!(layer.chunks().is_empty());
+      assert!(layer.is_plain());
+   }
+
+   #[test]
+   fn translucent_or_blended_layers_are_not_plain() {
+      let mut layer = Layer::new(LayerId::BASE);
+      layer.opacity = 0.5;
+      assert!(!layer.is_plain());
+      layer.opacity = 1.0;
+      layer.blend_mode = BlendMode::Multiply;
+      assert!(!layer.is_plain());
+   }
+}
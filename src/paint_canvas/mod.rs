@@ -2,55 +2,279 @@
 
 pub mod cache_layer;
 pub mod chunk;
+pub mod history;
+pub mod layer;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use web_time::{Duration, Instant};
 
 use image::RgbaImage;
-use netcanv_renderer::paws::{vector, Color, Rect, Renderer, Vector};
-use netcanv_renderer::{Framebuffer as FramebufferTrait, RenderBackend};
+use netcanv_renderer::paws::{point, vector, Color, Point, Rect, Renderer, Vector};
+use netcanv_renderer::{BlendMode, Framebuffer as FramebufferTrait, RenderBackend};
+use serde::{Deserialize, Serialize};
 
 use crate::backend::{Backend, Framebuffer};
+use crate::config::config;
 use crate::viewport::Viewport;
 use chunk::Chunk;
+use history::{History, HistoryOutcome};
+use layer::{Layer, LayerId};
+
+/// The region of a paint canvas that can be drawn to.
+///
+/// The sides are expressed in chunk coordinates, and are inclusive, so `left = 0, right = 0`
+/// allows drawing to a single column of chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DrawingBounds {
+   pub left: i32,
+   pub top: i32,
+   pub right: i32,
+   pub bottom: i32,
+}
+
+impl DrawingBounds {
+   /// Returns the smallest bounds containing all the chunks at the given positions, or `None` if
+   /// there are no chunks.
+   pub fn around_chunks(chunk_positions: impl Iterator<Item = (i32, i32)>) -> Option<Self> {
+      chunk_positions.fold(None, |bounds: Option<Self>, (x, y)| {
+         Some(match bounds {
+            Some(bounds) => Self {
+               left: bounds.left.min(x),
+               top: bounds.top.min(y),
+               right: bounds.right.max(x),
+               bottom: bounds.bottom.max(y),
+            },
+            None => Self {
+               left: x,
+               top: y,
+               right: x,
+               bottom: y,
+            },
+         })
+      })
+   }
 
-/// A paint canvas built out of [`Chunk`]s.
+   /// Returns whether the chunk at the given position lies within the bounds.
+   pub fn contains_chunk(&self, (x, y): (i32, i32)) -> bool {
+      (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+   }
+
+   /// Returns whether the point, in pixels, lies within the bounds.
+   pub fn contains_point(&self, point: Point) -> bool {
+      self.contains_chunk((
+         (point.x / Chunk::SIZE.0 as f32).floor() as i32,
+         (point.y / Chunk::SIZE.1 as f32).floor() as i32,
+      ))
+   }
+
+   /// Returns the rectangle covered by the bounds, in pixels.
+   pub fn rect(&self) -> Rect {
+      let top_left = Chunk::screen_position((self.left, self.top));
+      let bottom_right = Chunk::screen_position((self.right + 1, self.bottom + 1));
+      Rect::new(top_left, bottom_right - top_left)
+   }
+
+   /// Converts the bounds to the representation used by the network protocol.
+   pub fn to_tuple(self) -> (i32, i32, i32, i32) {
+      (self.left, self.top, self.right, self.bottom)
+   }
+
+   /// Converts bounds received over the network back to their usual representation.
+   pub fn from_tuple((left, top, right, bottom): (i32, i32, i32, i32)) -> Self {
+      Self {
+         left,
+         top,
+         right,
+         bottom,
+      }
+   }
+}
+
+/// A paint canvas built out of [`Layer`]s of [`Chunk`]s.
+///
+/// Painting always happens on the _active_ layer. Other peers only know about the base layer, so
+/// functions dealing with individual chunks rather than the composited canvas, such as
+/// [`PaintCanvas::chunks`] or [`PaintCanvas::set_chunk`], operate on the base layer.
 pub struct PaintCanvas {
-   chunks: HashMap<(i32, i32), Chunk>,
+   /// The layers, from the bottommost to the topmost one. The base layer always comes first.
+   layers: Vec<Layer>,
+   /// The layer drawn to by [`PaintCanvas::draw`].
+   active_layer: LayerId,
+   /// The ID given to the next layer that gets added.
+   #[cfg(feature = "layers")]
+   next_layer_id: u32,
+   /// Base layer chunks modified since the last call to [`PaintCanvas::take_modified_chunks`].
+   modified_chunks: HashSet<(i32, i32)>,
+   /// The region that can be drawn to, or `None` if the canvas is unbounded.
+   bounds: Option<DrawingBounds>,
+   /// The local user's undo history.
+   history: History,
+   /// The last time each base layer chunk was visible, used for picking the chunks to evict.
+   last_visible: HashMap<(i32, i32), Instant>,
 }
 
 impl PaintCanvas {
+   /// The largest framebuffer allocated by [`PaintCanvas::render_region`]. Larger regions are
+   /// rendered in multiple tiles of this size.
+   const MAX_RENDER_TILE_SIZE: u32 = 2048;
+
+   /// The furthest a chunk can lie from the origin on either axis, in chunks.
+   ///
+   /// This puts the edge of the canvas about 8 million pixels away from the origin, which is as
+   /// far as `f32` coordinates stay precise to a pixel. Chunks further away than that can't be
+   /// drawn to properly anyway, so they're never created, and they're rejected when other peers
+   /// send them.
+   pub const MAX_CHUNK_COORDINATE: i32 = 1 << 15;
+
    /// Creates a new, empty paint canvas.
    pub fn new() -> Self {
       Self {
-         chunks: HashMap::new(),
+         layers: vec![Layer::new(LayerId::BASE)],
+         active_layer: LayerId::BASE,
+         #[cfg(feature = "layers")]
+         next_layer_id: LayerId::BASE.0 + 1,
+         modified_chunks: HashSet::new(),
+         bounds: None,
+         history: History::new(config().history.max_steps),
+         last_visible: HashMap::new(),
       }
    }
 
-   /// Creates the chunk at the given position, if it doesn't already exist.
+   /// Returns the region that can be drawn to, or `None` if the canvas is unbounded.
+   pub fn bounds(&self) -> Option<DrawingBounds> {
+      self.bounds
+   }
+
+   /// Sets the region that can be drawn to. Chunks that already exist outside of the new bounds
+   /// are kept, but can no longer be drawn to.
+   pub fn set_bounds(&mut self, bounds: Option<DrawingBounds>) {
+      self.bounds = bounds;
+   }
+
+   /// Returns whether a chunk at the given position may exist at all, that is whether it's no
+   /// further from the origin than [`PaintCanvas::MAX_CHUNK_COORDINATE`].
+   pub fn chunk_is_within_limits((x, y): (i32, i32)) -> bool {
+      let limits = -Self::MAX_CHUNK_COORDINATE..=Self::MAX_CHUNK_COORDINATE;
+      limits.contains(&x) && limits.contains(&y)
+   }
+
+   /// Returns whether the chunk at the given position can be drawn to.
+   pub fn chunk_is_in_bounds(&self, position: (i32, i32)) -> bool {
+      Self::chunk_is_within_limits(position)
+         && self.bounds.map_or(true, |bounds| bounds.contains_chunk(position))
+   }
+
+   /// Returns whether the point, in pixels, lies in a region that can be drawn to.
+   pub fn point_is_in_bounds(&self, point: Point) -> bool {
+      self.bounds.map_or(true, |bounds| bounds.contains_point(point))
+   }
+
+   /// Returns the index of the layer with the given ID in the stack of layers.
+   fn layer_index(&self, id: LayerId) -> Option<usize> {
+      self.layers.iter().position(|layer| layer.id() == id)
+   }
+
+   /// Returns the index of the active layer in the stack of layers.
+   fn active_layer_index(&self) -> usize {
+      self.layer_index(self.active_layer).expect("the active layer must exist")
+   }
+
+   fn base_layer(&self) -> &Layer {
+      &self.layers[0]
+   }
+
+   fn base_layer_mut(&mut self) -> &mut Layer {
+      &mut self.layers[0]
+   }
+
+   /// Returns the layers, from the bottommost to the topmost one.
+   #[cfg(feature = "layers")]
+   pub fn layers(&self) -> &[Layer] {
+      &self.layers
+   }
+
+   /// Returns the ID of the layer that is drawn to.
+   #[cfg(feature = "layers")]
+   pub fn active_layer(&self) -> LayerId {
+      self.active_layer
+   }
+
+   /// Sets the layer that is drawn to. Does nothing if there's no layer with the given ID.
+   #[cfg(feature = "layers")]
+   pub fn set_active_layer(&mut self, id: LayerId) {
+      if self.layer_index(id).is_some() {
+         self.active_layer = id;
+      }
+   }
+
+   /// Adds a new, empty layer right above the active layer, and returns its ID.
+   #[cfg(feature = "layers")]
+   pub fn add_layer(&mut self) -> LayerId {
+      let id = LayerId(self.next_layer_id);
+      self.next_layer_id += 1;
+      let index = self.active_layer_index() + 1;
+      self.layers.insert(index, Layer::new(id));
+      id
+   }
+
+   /// Calls `f` with the base layer temporarily made active, such that everything `f` draws ends
+   /// up on the base layer.
+   ///
+   /// This is used for drawing on behalf of other peers, who only know about the base layer.
+   pub fn with_base_layer_active<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+      let active_layer = std::mem::replace(&mut self.active_layer, LayerId::BASE);
+      let result = f(self);
+      self.active_layer = active_layer;
+      result
+   }
+
+   /// Creates the chunk at the given position on the base layer, if it doesn't already exist.
    #[must_use]
    pub fn ensure_chunk(&mut self, renderer: &mut Backend, position: (i32, i32)) -> &mut Chunk {
-      self.chunks.entry(position).or_insert_with(|| Chunk::new(renderer))
+      self.base_layer_mut().chunks.entry(position).or_insert_with(|| Chunk::new(renderer))
    }
 
    /// Returns the left, top, bottom, right sides covered by the rectangle, in chunk
    /// coordinates.
+   ///
+   /// The right and bottom edges of the rectangle are exclusive, such that a rectangle ending
+   /// exactly on a chunk boundary does not cover the chunk after it.
+   ///
+   /// The sides are clamped to [`PaintCanvas::MAX_CHUNK_COORDINATE`], so a rectangle lying
+   /// entirely outside of the limits ends up with its left side past its right side (or its top
+   /// past its bottom), covering no chunks at all.
    fn chunk_coverage(coverage: Rect) -> (i32, i32, i32, i32) {
       let coverage = coverage.sort();
+      let left = (coverage.left() / Chunk::SIZE.0 as f32).floor() as i32;
+      let top = (coverage.top() / Chunk::SIZE.1 as f32).floor() as i32;
+      let right = (coverage.right() / Chunk::SIZE.0 as f32).ceil() as i32 - 1;
+      let bottom = (coverage.bottom() / Chunk::SIZE.1 as f32).ceil() as i32 - 1;
+      // Empty rectangles lying on a chunk boundary would otherwise end up covering nothing.
+      let (right, bottom) = (right.max(left), bottom.max(top));
+      let limit = Self::MAX_CHUNK_COORDINATE;
       (
-         (coverage.left() / Chunk::SIZE.0 as f32).floor() as i32,
-         (coverage.top() / Chunk::SIZE.1 as f32).floor() as i32,
-         (coverage.bottom() / Chunk::SIZE.0 as f32).floor() as i32,
-         (coverage.right() / Chunk::SIZE.1 as f32).floor() as i32,
+         left.max(-limit),
+         top.max(-limit),
+         bottom.min(limit),
+         right.min(limit),
       )
    }
 
-   /// Draws to the paint canvas's chunks.
+   /// Returns the positions of all chunks covered by the rectangle, in chunk coordinates.
+   pub fn chunks_covered_by(coverage: Rect) -> impl Iterator<Item = (i32, i32)> {
+      let (left, top, bottom, right) = Self::chunk_coverage(coverage);
+      (top..=bottom).flat_map(move |y| (left..=right).map(move |x| (x, y)))
+   }
+
+   /// Draws to the chunks of the active layer.
    ///
    /// The provided `coverage` rectangle is used to determine which chunks should be drawn to, and
    /// thus should cover the entire area of the thing being drawn. Note that the coordinates here
    /// are expressed in _pixels_ rather than _chunks_.
    ///
-   /// The callback may be called multiple times, once for each chunk being drawn to.
+   /// The callback may be called multiple times, once for each chunk being drawn to. Chunks
+   /// outside of the canvas's bounds are never drawn to.
    pub fn draw(
       &mut self,
       renderer: &mut Backend,
@@ -58,12 +282,17 @@ impl PaintCanvas {
       mut callback: impl FnMut(&mut Backend),
    ) {
       let (left, top, bottom, right) = Self::chunk_coverage(coverage);
-      assert!(left <= right);
-      assert!(top <= bottom);
+      let layer_index = self.active_layer_index();
       for y in top..=bottom {
          for x in left..=right {
             let chunk_position = (x, y);
-            let chunk = self.ensure_chunk(renderer, chunk_position);
+            if !self.chunk_is_in_bounds(chunk_position) {
+               continue;
+            }
+            let chunk = self.layers[layer_index]
+               .chunks
+               .entry(chunk_position)
+               .or_insert_with(|| Chunk::new(renderer));
             renderer.push();
             renderer.translate(vector(
                -x as f32 * Chunk::SIZE.0 as f32,
@@ -74,24 +303,258 @@ impl PaintCanvas {
             });
             renderer.pop();
             chunk.mark_dirty();
+            if layer_index == 0 {
+               self.modified_chunks.insert(chunk_position);
+            }
+         }
+      }
+   }
+
+   /// Records that the base layer chunks at the given positions are visible right now.
+   pub fn mark_visible(&mut self, positions: impl Iterator<Item = (i32, i32)>) {
+      let now = Instant::now();
+      for position in positions {
+         if self.chunk(position).is_some() {
+            self.last_visible.insert(position, now);
+         }
+      }
+   }
+
+   /// Evicts base layer chunks that aren't visible at the moment, until no more than
+   /// `max_resident` chunks are left on the base layer. The chunks that were out of view the
+   /// longest are evicted first, and chunks that were never visible go before all others.
+   ///
+   /// Evicted chunks are simply dropped, so this must only be used when they can be downloaded
+   /// again. Returns the positions of the evicted chunks.
+   pub fn evict_chunks(
+      &mut self,
+      max_resident: usize,
+      is_visible: impl Fn((i32, i32)) -> bool,
+   ) -> Vec<(i32, i32)> {
+      let resident = self.chunks().len();
+      if resident <= max_resident {
+         return Vec::new();
+      }
+      let candidates = self
+         .chunks()
+         .keys()
+         .filter(|&&position| !is_visible(position))
+         .map(|&position| (position, self.last_visible.get(&position).copied()))
+         .collect();
+      let evicted = Self::least_recently_visible(candidates, resident - max_resident);
+      for position in &evicted {
+         self.base_layer_mut().chunks.remove(position);
+         self.last_visible.remove(position);
+      }
+      tracing::debug!("evicted {} chunks", evicted.len());
+      evicted
+   }
+
+   /// Picks up to `count` chunks out of the candidates, in the order they were last visible in.
+   /// Chunks that were never visible come first.
+   fn least_recently_visible(
+      mut candidates: Vec<((i32, i32), Option<Instant>)>,
+      count: usize,
+   ) -> Vec<(i32, i32)> {
+      candidates.sort_by_key(|&(_, last_visible)| last_visible);
+      candidates.into_iter().take(count).map(|(position, _)| position).collect()
+   }
+
+   /// Removes all chunks from all layers, leaving the canvas empty.
+   ///
+   /// The undo history is forgotten, as the chunks it would restore no longer exist.
+   pub fn clear(&mut self) {
+      let positions = self.chunk_positions();
+      self.modified_chunks.extend(positions);
+      for layer in &mut self.layers {
+         layer.chunks.clear();
+      }
+      self.last_visible.clear();
+      self.history = History::new(config().history.max_steps);
+   }
+
+   /// Starts recording an undoable action. Does nothing if an action is already being recorded.
+   pub fn begin_history_step(&mut self) {
+      self.history.begin();
+   }
+
+   /// Returns whether an undoable action is being recorded.
+   pub fn is_recording_history(&self) -> bool {
+      self.history.is_recording()
+   }
+
+   /// Backs up the active layer's chunks covered by the rectangle into the action being recorded,
+   /// such that they can be restored later. This must be called before drawing to the chunks.
+   ///
+   /// Chunks outside of the canvas's bounds are skipped, as they can't be drawn to anyway.
+   pub fn record_history(&mut self, renderer: &mut Backend, coverage: Rect) {
+      let bounds = self.bounds;
+      let positions = Self::chunks_covered_by(coverage)
+         .filter(|&position| bounds.map_or(true, |bounds| bounds.contains_chunk(position)));
+      let layer = &self.layers[self.active_layer_index()];
+      self.history.back_up(renderer, layer, positions);
+   }
+
+   /// Finishes recording the current action, making it the one that gets undone next.
+   pub fn finish_history_step(&mut self, renderer: &mut Backend) {
+      self.history.finish(renderer, &self.layers);
+   }
+
+   /// Undoes the user's last action.
+   pub fn undo(&mut self, renderer: &mut Backend) -> HistoryOutcome {
+      let outcome = self.history.undo(renderer, &mut self.layers);
+      self.mark_restored(&outcome);
+      outcome
+   }
+
+   /// Redoes the user's last undone action.
+   pub fn redo(&mut self, renderer: &mut Backend) -> HistoryOutcome {
+      let outcome = self.history.redo(renderer, &mut self.layers);
+      self.mark_restored(&outcome);
+      outcome
+   }
+
+   /// Marks the base layer chunks restored by undoing or redoing an action as modified.
+   fn mark_restored(&mut self, outcome: &HistoryOutcome) {
+      if let HistoryOutcome::Restored(chunks) = outcome {
+         self.modified_chunks.extend(
+            chunks.iter().filter(|chunk| chunk.layer == LayerId::BASE).map(|chunk| chunk.position),
+         );
+      }
+   }
+
+   /// Returns the set of base layer chunks modified since the last time this function was called.
+   ///
+   /// This can be used to invalidate data derived from chunk images, such as cached encodings.
+   pub fn take_modified_chunks(&mut self) -> HashSet<(i32, i32)> {
+      std::mem::take(&mut self.modified_chunks)
+   }
+
+   /// Captures a fragment of the active layer onto a framebuffer.
+   ///
+   /// The fragment's top-left corner lies at `position`, and its size is the size of the
+   /// framebuffer. The pixels are copied directly on the graphics card; areas without any chunks
+   /// are left untouched.
+   pub fn capture(&self, renderer: &mut Backend, framebuffer: &Framebuffer, position: Point) {
+      let (left, top) = (position.x.round() as i64, position.y.round() as i64);
+      let (width, height) = framebuffer.size();
+      let (right, bottom) = (left + width as i64, top + height as i64);
+      let coverage = Rect::new(
+         point(left as f32, top as f32),
+         vector(width as f32, height as f32),
+      );
+      let (chunk_left, chunk_top, chunk_bottom, chunk_right) = Self::chunk_coverage(coverage);
+      let layer = &self.layers[self.active_layer_index()];
+      for y in chunk_top..=chunk_bottom {
+         for x in chunk_left..=chunk_right {
+            if let Some(chunk) = layer.chunks.get(&(x, y)) {
+               let chunk_x = x as i64 * Chunk::SIZE.0 as i64;
+               let chunk_y = y as i64 * Chunk::SIZE.1 as i64;
+               // The part of the chunk that overlaps the captured fragment.
+               let overlap_left = left.max(chunk_x);
+               let overlap_top = top.max(chunk_y);
+               let overlap_right = right.min(chunk_x + Chunk::SIZE.0 as i64);
+               let overlap_bottom = bottom.min(chunk_y + Chunk::SIZE.1 as i64);
+               if overlap_left >= overlap_right || overlap_top >= overlap_bottom {
+                  continue;
+               }
+               renderer.copy_framebuffer(
+                  &chunk.framebuffer,
+                  (
+                     (overlap_left - chunk_x) as u32,
+                     (overlap_top - chunk_y) as u32,
+                  ),
+                  (
+                     (overlap_right - overlap_left) as u32,
+                     (overlap_bottom - overlap_top) as u32,
+                  ),
+                  framebuffer,
+                  ((overlap_left - left) as u32, (overlap_top - top) as u32),
+               );
+            }
+         }
+      }
+   }
+
+   /// Splits an image of the given size into tiles no larger than
+   /// [`PaintCanvas::MAX_RENDER_TILE_SIZE`]. Returns the position and size of each tile, in
+   /// pixels.
+   fn render_tiles((width, height): (u32, u32)) -> Vec<((u32, u32), (u32, u32))> {
+      let tile_step = Self::MAX_RENDER_TILE_SIZE as usize;
+      let mut tiles = Vec::new();
+      for tile_y in (0..height).step_by(tile_step) {
+         for tile_x in (0..width).step_by(tile_step) {
+            let tile_size = (
+               (width - tile_x).min(Self::MAX_RENDER_TILE_SIZE),
+               (height - tile_y).min(Self::MAX_RENDER_TILE_SIZE),
+            );
+            tiles.push(((tile_x, tile_y), tile_size));
          }
       }
+      tiles
    }
 
-   /// Captures a fragment of the paint canvas onto a framebuffer.
-   pub fn capture(&self, renderer: &mut Backend, framebuffer: &Framebuffer, viewport: &Viewport) {
-      renderer.draw_to(framebuffer, |renderer| {
-         self.draw_to(
-            renderer,
-            viewport,
-            vector(framebuffer.width() as f32, framebuffer.height() as f32),
+   /// Renders a region of the paint canvas into an image, scaled by the given factor. All layers
+   /// are composited together.
+   ///
+   /// The `region` is expressed in pixels, and the size of the resulting image is the region's
+   /// size multiplied by `scale`, rounded to whole pixels. Areas without any chunks are left
+   /// transparent.
+   ///
+   /// The region is rendered offscreen, in tiles no larger than
+   /// [`PaintCanvas::MAX_RENDER_TILE_SIZE`], which are then stitched together. Each tile draws
+   /// every chunk it overlaps, so chunk and tile boundaries don't leave any seams.
+   pub fn render_region(&self, renderer: &mut Backend, region: Rect, scale: f32) -> RgbaImage {
+      assert!(scale > 0.0 && scale.is_finite());
+      let region = region.sort();
+      let width = (region.width() * scale).round() as u32;
+      let height = (region.height() * scale).round() as u32;
+      let mut image = RgbaImage::new(width, height);
+      for ((tile_x, tile_y), tile_size) in Self::render_tiles((width, height)) {
+         // The part of the canvas covered by the tile, in pixels.
+         let tile_origin = region.position + vector(tile_x as f32, tile_y as f32) / scale;
+         let tile_coverage = Rect::new(
+            tile_origin,
+            vector(tile_size.0 as f32, tile_size.1 as f32) / scale,
          );
-      });
+
+         let framebuffer = renderer.create_framebuffer(tile_size.0, tile_size.1);
+         renderer.push();
+         renderer.scale(vector(scale, scale));
+         renderer.translate(vector(-tile_origin.x, -tile_origin.y));
+         renderer.draw_to(&framebuffer, |renderer| {
+            for (index, layer) in self.layers.iter().enumerate() {
+               // Chunks never overlap, so replacing the pixels of the bottommost layer copies
+               // them over exactly, without blending them with the transparent background.
+               let blend_mode = if index == 0 && layer.is_plain() {
+                  BlendMode::Replace
+               } else {
+                  layer.blend_mode
+               };
+               renderer.push();
+               renderer.set_blend_mode(blend_mode);
+               for chunk_position in Self::chunks_covered_by(tile_coverage) {
+                  if let Some(chunk) = layer.chunks.get(&chunk_position) {
+                     let rect = chunk.framebuffer.rect(Chunk::screen_position(chunk_position));
+                     renderer.framebuffer_with_opacity(rect, &chunk.framebuffer, layer.opacity);
+                  }
+               }
+               renderer.pop();
+            }
+         });
+         renderer.pop();
+
+         let mut tile = RgbaImage::new(tile_size.0, tile_size.1);
+         renderer.download_framebuffer(&framebuffer, (0, 0), tile_size, &mut tile);
+         image::imageops::replace(&mut image, &tile, tile_x as i64, tile_y as i64);
+      }
+      image
    }
 
-   /// Downloads the color of the pixel at the provided position.
+   /// Downloads the color of the active layer's pixel at the provided position.
    pub fn get_pixel(&self, renderer: &mut Backend, position: (i64, i64)) -> Color {
-      if let Some(chunk) = self.chunks.get(&(
+      let layer = &self.layers[self.active_layer_index()];
+      if let Some(chunk) = layer.chunks.get(&(
          (position.0.div_euclid(Chunk::SIZE.0 as i64)) as i32,
          (position.1.div_euclid(Chunk::SIZE.1 as i64)) as i32,
       )) {
@@ -108,19 +571,27 @@ impl PaintCanvas {
       }
    }
 
-   /// Draws the paint canvas using the given renderer.
+   /// Draws the paint canvas using the given renderer, compositing its layers on top of each
+   /// other.
    ///
    /// The provided viewport and window size are used to only render chunks that are visible at a
    /// given moment.
    pub fn draw_to(&self, renderer: &mut Backend, viewport: &Viewport, window_size: Vector) {
-      for chunk_position in viewport.visible_tiles(Chunk::SIZE, window_size) {
-         if let Some(chunk) = self.chunks.get(&chunk_position) {
-            let screen_position = Chunk::screen_position(chunk_position);
-            renderer.framebuffer(chunk.framebuffer.rect(screen_position), &chunk.framebuffer);
+      let visible_tiles: Vec<_> = viewport.visible_tiles(Chunk::SIZE, window_size).collect();
+      for layer in &self.layers {
+         renderer.push();
+         renderer.set_blend_mode(layer.blend_mode);
+         for chunk_position in &visible_tiles {
+            if let Some(chunk) = layer.chunks.get(chunk_position) {
+               let rect = chunk.framebuffer.rect(Chunk::screen_position(*chunk_position));
+               renderer.framebuffer_with_opacity(rect, &chunk.framebuffer, layer.opacity);
+            }
          }
+         renderer.pop();
       }
    }
 
+   /// Replaces the image of the base layer's chunk at the given position.
    pub fn set_chunk(
       &mut self,
       renderer: &mut Backend,
@@ -129,22 +600,176 @@ impl PaintCanvas {
    ) {
       let chunk = self.ensure_chunk(renderer, chunk_position);
       chunk.upload_image(renderer, &image, (0, 0));
+      self.modified_chunks.insert(chunk_position);
    }
 
+   /// Replaces the region of the base layer chunk at the given position with the image, whose
+   /// top-left corner is placed at `offset` within the chunk.
+   pub fn patch_chunk(
+      &mut self,
+      renderer: &mut Backend,
+      chunk_position: (i32, i32),
+      offset: (u32, u32),
+      image: &RgbaImage,
+   ) {
+      let chunk = self.ensure_chunk(renderer, chunk_position);
+      chunk.upload_image(renderer, image, offset);
+      self.modified_chunks.insert(chunk_position);
+   }
+
+   /// Returns the chunks of the base layer.
    pub fn chunks(&self) -> &HashMap<(i32, i32), Chunk> {
-      &self.chunks
+      &self.base_layer().chunks
    }
 
+   /// Returns the chunks of the base layer, for modification.
    pub fn chunks_mut(&mut self) -> &mut HashMap<(i32, i32), Chunk> {
-      &mut self.chunks
+      &mut self.base_layer_mut().chunks
    }
 
-   /// Returns a vector containing all the chunk positions in the paint canvas.
+   /// Returns a vector containing the positions of all the base layer's chunks.
    pub fn chunk_positions(&self) -> Vec<(i32, i32)> {
-      self.chunks.keys().copied().collect()
+      self.chunks().keys().copied().collect()
+   }
+
+   /// Returns the positions of the base layer's chunks that were modified within the given
+   /// duration.
+   pub fn chunks_modified_within(&self, duration: Duration) -> Vec<(i32, i32)> {
+      self
+         .chunks()
+         .iter()
+         .filter(|(_, chunk)| chunk.modified().elapsed() <= duration)
+         .map(|(&position, _)| position)
+         .collect()
    }
 
+   /// Iterates over the positions of the chunks of all layers. Positions of chunks that exist on
+   /// multiple layers are repeated.
+   pub fn all_chunk_positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+      self.layers.iter().flat_map(|layer| layer.chunks.keys().copied())
+   }
+
+   /// Returns the base layer's chunk at the given position.
    pub fn chunk(&self, position: (i32, i32)) -> Option<&Chunk> {
-      self.chunks.get(&position)
+      self.chunks().get(&position)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use image::Rgba;
+
+   use super::*;
+
+   /// Positions that a buggy or malicious peer might send, in hopes of allocating chunks far out.
+   const PATHOLOGICAL_POSITIONS: [(i32, i32); 6] = [
+      (i32::MIN, i32::MIN),
+      (i32::MAX, i32::MAX),
+      (0, i32::MIN),
+      (i32::MAX, 0),
+      (PaintCanvas::MAX_CHUNK_COORDINATE + 1, 0),
+      (0, -PaintCanvas::MAX_CHUNK_COORDINATE - 1),
+   ];
+
+   #[test]
+   fn render_tiles_split_the_whole_image() {
+      let max = PaintCanvas::MAX_RENDER_TILE_SIZE;
+      assert!(PaintCanvas::render_tiles((0, 100)).is_empty());
+      assert_eq!(PaintCanvas::render_tiles((10, 20)), [((0, 0), (10, 20))]);
+      assert_eq!(
+         PaintCanvas::render_tiles((max + 5, max)),
+         [((0, 0), (max, max)), ((max, 0), (5, max))]
+      );
+
+      let (width, height) = (2 * max + 1, max + 1);
+      let tiles = PaintCanvas::render_tiles((width, height));
+      assert_eq!(tiles.len(), 6);
+      let area: u32 =
+         tiles.iter().map(|(_, (tile_width, tile_height))| tile_width * tile_height).sum();
+      assert_eq!(area, width * height);
+      for &((x, y), (tile_width, tile_height)) in &tiles {
+         assert!(x + tile_width <= width && y + tile_height <= height);
+         assert!(tile_width <= max && tile_height <= max);
+      }
+   }
+
+   #[test]
+   fn chunks_outside_of_the_limits_are_rejected() {
+      for position in PATHOLOGICAL_POSITIONS {
+         assert!(
+            !PaintCanvas::chunk_is_within_limits(position),
+            "{position:?}"
+         );
+      }
+      let limit = PaintCanvas::MAX_CHUNK_COORDINATE;
+      for position in [(0, 0), (limit, limit), (-limit, -limit), (limit, -limit)] {
+         assert!(
+            PaintCanvas::chunk_is_within_limits(position),
+            "{position:?}"
+         );
+      }
+   }
+
+   #[test]
+   fn coverage_never_leaves_the_limits() {
+      let far = 1.0e12;
+      for origin in [
+         point(far, far),
+         point(-far, -far),
+         point(far, 0.0),
+         point(0.0, -far),
+      ] {
+         let coverage = Rect::new(origin, vector(512.0, 512.0));
+         assert_eq!(PaintCanvas::chunks_covered_by(coverage).count(), 0);
+      }
+
+      // A rectangle straddling the edge only covers the chunks on the inside.
+      let edge = Chunk::screen_position((PaintCanvas::MAX_CHUNK_COORDINATE, 0));
+      let coverage = Rect::new(edge, vector(far, 1.0));
+      let covered: Vec<_> = PaintCanvas::chunks_covered_by(coverage).collect();
+      assert_eq!(covered, vec![(PaintCanvas::MAX_CHUNK_COORDINATE, 0)]);
+   }
+
+   #[test]
+   fn eviction_keeps_the_number_of_chunks_under_the_cap() {
+      const MAX_RESIDENT: usize = 64;
+
+      // Every third chunk was never visible, and the rest became visible one after another.
+      let start = Instant::now();
+      let last_visible: HashMap<(i32, i32), Option<Instant>> = (0..1000)
+         .map(|i| {
+            let position = (i % 50 - 25, i / 50 - 10);
+            let last_visible = (i % 3 != 0).then(|| start + Duration::from_millis(i as u64));
+            (position, last_visible)
+         })
+         .collect();
+      let resident = last_visible.len();
+
+      let candidates = last_visible.iter().map(|(&position, &time)| (position, time)).collect();
+      let evicted = PaintCanvas::least_recently_visible(candidates, resident - MAX_RESIDENT);
+      assert_eq!(resident - evicted.len(), MAX_RESIDENT);
+
+      // Chunks that were never visible are evicted before all others, and out of the rest, the
+      // ones that were visible most recently are kept.
+      let newest_evicted = evicted.iter().filter_map(|position| last_visible[position]).max();
+      for (position, time) in &last_visible {
+         if !evicted.contains(position) {
+            assert!(time.is_some() && *time > newest_evicted, "{position:?}");
+         }
+      }
+   }
+
+   #[test]
+   fn changed_region_covers_exactly_the_changed_pixels() {
+      let old = RgbaImage::new(Chunk::SIZE.0, Chunk::SIZE.1);
+      assert_eq!(Chunk::changed_region(&old, &old), None);
+
+      let mut new = old.clone();
+      new.put_pixel(10, 200, Rgba([255, 0, 0, 255]));
+      new.put_pixel(40, 30, Rgba([0, 0, 255, 128]));
+      assert_eq!(Chunk::changed_region(&old, &new), Some((10, 30, 31, 171)));
+
+      new.put_pixel(255, 255, Rgba([0, 0, 0, 1]));
+      assert_eq!(Chunk::changed_region(&old, &new), Some((10, 30, 246, 226)));
    }
 }
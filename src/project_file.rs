@@ -1,11 +1,17 @@
-use std::ffi::OsStr;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{Cursor, Write};
 use std::path::{Path, PathBuf};
 
 use image::{GenericImage, GenericImageView, Rgba, RgbaImage};
+use netcanv_renderer::paws::Rect;
 use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
 
 use crate::backend::Backend;
+use crate::config::{config, PngCompression};
 use crate::image_coder::ImageCoder;
+use crate::ora::{self, OraImage};
 use crate::paint_canvas::chunk::Chunk;
 use crate::paint_canvas::PaintCanvas;
 use crate::Error;
@@ -23,23 +29,154 @@ struct CanvasToml {
 pub struct ProjectFile {
    /// The path to the `.netcanv` directory this paint canvas was saved to.
    filename: Option<PathBuf>,
+   /// The path the user last chose to save the canvas to. Unlike `filename`, this may also be
+   /// a PNG file.
+   last_save_path: Option<PathBuf>,
+   /// The save that's currently being written to disk.
+   save_job: Option<SaveJob>,
+   /// Whether PNG saves are cropped to the drawn pixels, as picked by the user when saving as PNG.
+   /// If the user wasn't asked yet, `encoding.save_png_trim_borders` is used.
+   trim_borders: Option<bool>,
+}
+
+/// The images of a canvas save, downloaded from the graphics card.
+///
+/// Encoding the images and writing them to disk doesn't need the graphics card, so it's done on a
+/// background thread.
+enum SaveData {
+   Png {
+      path: PathBuf,
+      image: RgbaImage,
+      trim_borders: bool,
+      compression: PngCompression,
+   },
+   Ora {
+      path: PathBuf,
+      image: RgbaImage,
+      compression: PngCompression,
+   },
+   Netcanv {
+      path: PathBuf,
+      chunks: Vec<((i32, i32), RgbaImage)>,
+      compression: PngCompression,
+      backups: u32,
+   },
+}
+
+impl SaveData {
+   /// Returns the path the canvas is saved to.
+   fn path(&self) -> &Path {
+      match self {
+         Self::Png { path, .. } | Self::Ora { path, .. } | Self::Netcanv { path, .. } => path,
+      }
+   }
+
+   /// Encodes the images and writes them to disk. This is run on a background thread.
+   fn write(self) -> netcanv::Result<()> {
+      match self {
+         Self::Png {
+            path,
+            image,
+            trim_borders,
+            compression,
+         } => {
+            let image = if trim_borders {
+               ProjectFile::trim_transparent_borders(&image).ok_or(Error::NothingToSave)?
+            } else {
+               image
+            };
+            let data = ImageCoder::encode_png(&image, compression)?;
+            ProjectFile::write_file_atomically(&path, &data)?;
+            tracing::debug!("image {:?} saved successfully", path);
+         }
+         Self::Ora {
+            path,
+            image,
+            compression,
+         } => {
+            let ora = OraImage {
+               width: image.width(),
+               height: image.height(),
+               layers: vec![ora::Layer {
+                  name: "Canvas".to_owned(),
+                  position: (0, 0),
+                  opacity: 1.0,
+                  image,
+               }],
+            };
+            let mut data = Cursor::new(Vec::new());
+            ora.write(&mut data, compression)?;
+            ProjectFile::write_file_atomically(&path, data.get_ref())?;
+            tracing::debug!("image {:?} saved successfully", path);
+         }
+         Self::Netcanv {
+            path,
+            chunks,
+            compression,
+            backups,
+         } => ProjectFile::replace_dir_atomically(&path, backups, |directory| {
+            ProjectFile::write_netcanv(directory, &chunks, compression)
+         })?,
+      }
+      Ok(())
+   }
+}
+
+/// A save that's being written to disk in the background.
+struct SaveJob {
+   rx: oneshot::Receiver<netcanv::Result<()>>,
+   path: PathBuf,
+   /// Whether the canvas is being saved to a `.netcanv` directory.
+   is_netcanv: bool,
+   /// Whether the save was requested by the user, as opposed to being an autosave.
+   explicit: bool,
+   /// The revisions of the chunks at the time the save was started.
+   revisions: HashMap<(i32, i32), u64>,
+}
+
+/// A save that finished successfully.
+pub struct FinishedSave {
+   path: PathBuf,
+   explicit: bool,
+   revisions: HashMap<(i32, i32), u64>,
+}
+
+impl FinishedSave {
+   /// Returns the path the canvas was saved to.
+   pub fn path(&self) -> &Path {
+      &self.path
+   }
+
+   /// Returns whether the save was requested by the user, as opposed to being an autosave.
+   pub fn is_explicit(&self) -> bool {
+      self.explicit
+   }
+
+   /// Marks the saved chunks as saved, except for ones that were modified while the save was in
+   /// progress.
+   pub fn mark_chunks_saved(&self, canvas: &mut PaintCanvas) {
+      for (chunk_position, chunk) in canvas.chunks_mut() {
+         if self.revisions.get(chunk_position) == Some(&chunk.revision()) {
+            chunk.mark_saved();
+         }
+      }
+   }
 }
 
 impl ProjectFile {
    pub fn new() -> Self {
-      ProjectFile { filename: None }
+      ProjectFile {
+         filename: None,
+         last_save_path: None,
+         save_job: None,
+         trim_borders: None,
+      }
    }
 
-   /// Saves the entire paint canvas to a PNG file.
-   fn save_as_png(
-      &self,
-      renderer: &mut Backend,
-      path: &Path,
-      canvas: &mut PaintCanvas,
-   ) -> netcanv::Result<()> {
-      tracing::info!("saving png {:?}", path);
+   /// Returns the region covered by the canvas's chunks, in pixels.
+   fn canvas_region(canvas: &PaintCanvas) -> netcanv::Result<Rect> {
       let (mut left, mut top, mut right, mut bottom) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
-      for chunk_position in canvas.chunks_mut().keys() {
+      for chunk_position in canvas.chunks().keys() {
          left = left.min(chunk_position.0);
          top = top.min(chunk_position.1);
          right = right.max(chunk_position.0);
@@ -55,30 +192,69 @@ impl ProjectFile {
       if left == i32::MAX {
          return Err(Error::NothingToSave);
       }
-      let width = ((right - left + 1) * Chunk::SIZE.0 as i32) as u32;
-      let height = ((bottom - top + 1) * Chunk::SIZE.1 as i32) as u32;
-      tracing::debug!("size: {:?}", (width, height));
-      let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
-      for (chunk_position, chunk) in canvas.chunks() {
-         tracing::debug!("writing chunk {:?}", chunk_position);
-         let pixel_position = (
-            (Chunk::SIZE.0 as i32 * (chunk_position.0 - left)) as u32,
-            (Chunk::SIZE.1 as i32 * (chunk_position.1 - top)) as u32,
-         );
-         tracing::debug!("   - pixel position: {:?}", pixel_position);
-
-         let chunk_image = chunk.download_image(renderer);
-         let mut sub_image = image.sub_image(
-            pixel_position.0,
-            pixel_position.1,
-            Chunk::SIZE.0,
-            Chunk::SIZE.1,
-         );
-         sub_image.copy_from(&chunk_image, 0, 0)?;
+      let top_left = Chunk::screen_position((left, top));
+      let bottom_right = Chunk::screen_position((right + 1, bottom + 1));
+      Ok(Rect::new(top_left, bottom_right - top_left))
+   }
+
+   /// Renders the entire paint canvas, for saving it to a PNG file.
+   fn png_save_data(
+      renderer: &mut Backend,
+      path: &Path,
+      canvas: &PaintCanvas,
+      trim_borders: bool,
+   ) -> netcanv::Result<SaveData> {
+      tracing::info!("saving png {:?}", path);
+      let region = Self::canvas_region(canvas)?;
+      let scale = config().encoding.save_png_scale;
+      if !(scale > 0.0 && scale.is_finite()) {
+         return Err(Error::InvalidExportScale);
       }
-      image.save(path)?;
-      tracing::debug!("image {:?} saved successfully", path);
-      Ok(())
+      tracing::debug!("scale: {}", scale);
+      Ok(SaveData::Png {
+         path: path.to_owned(),
+         image: canvas.render_region(renderer, region, scale),
+         trim_borders,
+         compression: config().encoding.save_png_compression,
+      })
+   }
+
+   /// Renders the entire paint canvas at its actual size, for saving it to an OpenRaster file.
+   ///
+   /// Unlike PNGs, OpenRaster files are meant for editing the canvas further, so the image covers
+   /// whole chunks and isn't scaled or trimmed.
+   fn ora_save_data(
+      renderer: &mut Backend,
+      path: &Path,
+      canvas: &PaintCanvas,
+   ) -> netcanv::Result<SaveData> {
+      tracing::info!("saving ora {:?}", path);
+      let region = Self::canvas_region(canvas)?;
+      Ok(SaveData::Ora {
+         path: path.to_owned(),
+         image: canvas.render_region(renderer, region, 1.0),
+         compression: config().encoding.save_png_compression,
+      })
+   }
+
+   /// Crops the image to the bounding box of its non-transparent pixels.
+   ///
+   /// Returns `None` if the image is fully transparent.
+   fn trim_transparent_borders(image: &RgbaImage) -> Option<RgbaImage> {
+      let is_row_empty = |y| (0..image.width()).all(|x| image.get_pixel(x, y)[3] == 0);
+      let top = (0..image.height()).find(|&y| !is_row_empty(y))?;
+      let bottom = (top..image.height()).rev().find(|&y| !is_row_empty(y))?;
+      let is_column_empty = |x| (top..=bottom).all(|y| image.get_pixel(x, y)[3] == 0);
+      let left = (0..image.width()).find(|&x| !is_column_empty(x))?;
+      let right = (left..image.width()).rev().find(|&x| !is_column_empty(x))?;
+      tracing::debug!(
+         "trimming to left={}, top={}, right={}, bottom={}",
+         left,
+         top,
+         right,
+         bottom
+      );
+      Some(image.view(left, top, right - left + 1, bottom - top + 1).to_image())
    }
 
    /// Validates the `.netcanv` save path. This strips away the `canvas.toml` if present, and makes
@@ -96,35 +272,99 @@ impl ProjectFile {
       Ok(result)
    }
 
-   /// Clears the existing `.netcanv` save at the given path.
-   fn clear_netcanv_save(path: &Path) -> netcanv::Result<()> {
-      tracing::info!("clearing older netcanv save {:?}", path);
-      for entry in std::fs::read_dir(path)? {
-         let path = entry?.path();
-         if path.is_file()
-            && (path.extension() == Some(OsStr::new("png"))
-               || path.file_name() == Some(OsStr::new("canvas.toml")))
-         {
-            std::fs::remove_file(path)?;
+   /// Returns the path of a file or directory next to `path`, whose name is `path`'s name with
+   /// the given prefix and suffix.
+   fn sibling_path(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+      let mut name = OsString::from(prefix);
+      name.push(path.file_name().unwrap_or_default());
+      name.push(suffix);
+      path.with_file_name(name)
+   }
+
+   /// Returns the path of the `n`th most recent backup of the `.netcanv` save at `path`.
+   fn backup_path(path: &Path, n: u32) -> PathBuf {
+      Self::sibling_path(path, "", &format!(".bak{}", n))
+   }
+
+   /// Writes a file by writing to a temporary file first, and then renaming it to `path`.
+   ///
+   /// This way, if writing gets interrupted, the previous version of the file is left intact.
+   fn write_file_atomically(path: &Path, data: &[u8]) -> netcanv::Result<()> {
+      let directory = path.parent().unwrap_or(Path::new("."));
+      let mut file = tempfile::Builder::new().prefix(".netcanv-save").tempfile_in(directory)?;
+      file.write_all(data)?;
+      file.persist(path).map_err(|error| Error::FailedToPersistTemporaryFile {
+         error: error.to_string(),
+      })?;
+      Ok(())
+   }
+
+   /// Shifts the backups of the `.netcanv` save at `path` by one, such that the first backup's
+   /// path is free. The oldest backup is removed if there would be more than `backups` of them.
+   fn rotate_backups(path: &Path, backups: u32) -> netcanv::Result<()> {
+      let oldest = Self::backup_path(path, backups);
+      if oldest.exists() {
+         std::fs::remove_dir_all(&oldest)?;
+      }
+      for n in (1..backups).rev() {
+         let backup = Self::backup_path(path, n);
+         if backup.exists() {
+            std::fs::rename(&backup, Self::backup_path(path, n + 1))?;
          }
       }
       Ok(())
    }
 
-   /// Saves the paint canvas as a `.netcanv` canvas.
-   fn save_as_netcanv(
-      &mut self,
-      renderer: &mut Backend,
+   /// Replaces the `.netcanv` directory at `path` with a new one, whose contents are written by
+   /// `write`.
+   ///
+   /// The new save is written to a temporary directory first, which is then renamed to `path`,
+   /// such that an interrupted save never leaves a half-written canvas behind. The previous save
+   /// is kept as the first of up to `backups` backups.
+   fn replace_dir_atomically(
       path: &Path,
-      canvas: &mut PaintCanvas,
+      backups: u32,
+      write: impl FnOnce(&Path) -> netcanv::Result<()>,
    ) -> netcanv::Result<()> {
-      // create the directory
-      tracing::info!("creating or reusing existing directory ({:?})", path);
-      let path = Self::validate_netcanv_save_path(path)?;
-      std::fs::create_dir_all(path.clone())?; // use create_dir_all to not fail if the dir already exists
-      if self.filename != Some(path.clone()) {
-         Self::clear_netcanv_save(&path)?;
+      let temporary = Self::sibling_path(path, ".", ".tmp");
+      // A temporary directory may be left over from a save that was interrupted.
+      if temporary.exists() {
+         std::fs::remove_dir_all(&temporary)?;
+      }
+      std::fs::create_dir_all(&temporary)?;
+      if let Err(error) = write(&temporary) {
+         let _ = std::fs::remove_dir_all(&temporary);
+         return Err(error);
       }
+
+      let previous = if path.exists() {
+         let previous = if backups > 0 {
+            Self::rotate_backups(path, backups)?;
+            Self::backup_path(path, 1)
+         } else {
+            Self::sibling_path(path, ".", ".old")
+         };
+         if previous.exists() {
+            std::fs::remove_dir_all(&previous)?;
+         }
+         std::fs::rename(path, &previous)?;
+         Some(previous)
+      } else {
+         None
+      };
+      std::fs::rename(&temporary, path)?;
+      if let (Some(previous), 0) = (previous, backups) {
+         std::fs::remove_dir_all(previous)?;
+      }
+      Ok(())
+   }
+
+   /// Writes the `canvas.toml` manifest and the chunks into the directory at `path`.
+   fn write_netcanv(
+      path: &Path,
+      chunks: &[((i32, i32), RgbaImage)],
+      compression: PngCompression,
+   ) -> netcanv::Result<()> {
       // save the canvas.toml manifest
       tracing::info!("saving canvas.toml");
       let canvas_toml = CanvasToml {
@@ -136,45 +376,130 @@ impl ProjectFile {
       )?;
       // save all the chunks
       tracing::info!("saving chunks");
-      for (chunk_position, chunk) in canvas.chunks_mut() {
+      for (chunk_position, image) in chunks {
          tracing::debug!("chunk {:?}", chunk_position);
-         let image = chunk.download_image(renderer);
-         let image_data = ImageCoder::encode_png_data_sync(image)?;
+         let image_data = ImageCoder::encode_png(image, compression)?;
          let filename = format!("{},{}.png", chunk_position.0, chunk_position.1);
          let filepath = path.join(Path::new(&filename));
          tracing::debug!("saving to {:?}", filepath);
          std::fs::write(filepath, image_data)?;
-         chunk.mark_saved();
       }
-      self.filename = Some(path);
       Ok(())
    }
 
-   /// Saves the canvas to a PNG file or a `.netcanv` directory.
+   /// Downloads the images of all the chunks, for saving them to a `.netcanv` directory.
+   fn netcanv_save_data(
+      renderer: &mut Backend,
+      path: &Path,
+      canvas: &PaintCanvas,
+   ) -> netcanv::Result<SaveData> {
+      let path = Self::validate_netcanv_save_path(path)?;
+      tracing::info!("saving canvas to {:?}", path);
+      let chunks = canvas
+         .chunks()
+         .iter()
+         .map(|(&chunk_position, chunk)| (chunk_position, chunk.download_image(renderer)))
+         .collect();
+      Ok(SaveData::Netcanv {
+         path,
+         chunks,
+         compression: config().encoding.save_png_compression,
+         backups: config().autosave.backups,
+      })
+   }
+
+   /// Sets whether PNG saves are cropped to the drawn pixels, leaving out transparent borders.
+   pub fn set_trim_borders(&mut self, trim_borders: bool) {
+      self.trim_borders = Some(trim_borders);
+   }
+
+   /// Starts saving the canvas to a PNG file or a `.netcanv` directory.
    ///
    /// If `path` is `None`, this performs an autosave of an already saved `.netcanv` directory.
+   ///
+   /// The canvas's images are downloaded right away, but they're encoded and written to disk in
+   /// the background. [`ProjectFile::poll_save`] reports when the save is done.
    pub fn save(
       &mut self,
       renderer: &mut Backend,
       path: Option<&Path>,
-      canvas: &mut PaintCanvas,
+      canvas: &PaintCanvas,
    ) -> netcanv::Result<()> {
+      if self.is_saving() {
+         return Err(Error::SaveAlreadyInProgress);
+      }
+      let explicit = path.is_some();
       let path = path
          .map(|p| p.to_path_buf())
          .or_else(|| self.filename.clone())
          .expect("no save path provided");
-      if let Some(ext) = path.extension() {
+      let data = if let Some(ext) = path.extension() {
          match ext.to_str() {
-            Some("png") => self.save_as_png(renderer, &path, canvas),
-            Some("netcanv") | Some("toml") => {
-               // TODO: Saving should be asynchronous.
-               self.save_as_netcanv(renderer, &path, canvas)
+            Some("png") => {
+               let trim_borders =
+                  self.trim_borders.unwrap_or(config().encoding.save_png_trim_borders);
+               Self::png_save_data(renderer, &path, canvas, trim_borders)?
             }
-            _ => Err(Error::UnsupportedSaveFormat),
+            Some("ora") => Self::ora_save_data(renderer, &path, canvas)?,
+            Some("netcanv") | Some("toml") => Self::netcanv_save_data(renderer, &path, canvas)?,
+            _ => return Err(Error::UnsupportedSaveFormat),
          }
       } else {
-         Err(Error::MissingCanvasSaveExtension)
+         return Err(Error::MissingCanvasSaveExtension);
+      };
+      let revisions = canvas
+         .chunks()
+         .iter()
+         .map(|(&chunk_position, chunk)| (chunk_position, chunk.revision()))
+         .collect();
+      self.start_save(data, explicit, revisions);
+      Ok(())
+   }
+
+   /// Writes the save data to disk on a background thread.
+   fn start_save(&mut self, data: SaveData, explicit: bool, revisions: HashMap<(i32, i32), u64>) {
+      let (tx, rx) = oneshot::channel();
+      self.save_job = Some(SaveJob {
+         rx,
+         path: data.path().to_owned(),
+         is_netcanv: matches!(data, SaveData::Netcanv { .. }),
+         explicit,
+         revisions,
+      });
+      tokio::task::spawn_blocking(move || {
+         let _ = tx.send(data.write());
+      });
+   }
+
+   /// Returns whether a save is being written to disk.
+   pub fn is_saving(&self) -> bool {
+      self.save_job.is_some()
+   }
+
+   /// Checks whether the save that's in progress has finished. Returns `None` if there's no save
+   /// in progress, or if it hasn't finished yet.
+   pub fn poll_save(&mut self) -> Option<netcanv::Result<FinishedSave>> {
+      let job = self.save_job.as_mut()?;
+      let result = match job.rx.try_recv() {
+         Ok(result) => result,
+         Err(oneshot::error::TryRecvError::Empty) => return None,
+         Err(oneshot::error::TryRecvError::Closed) => Err(Error::SaveInterrupted),
+      };
+      let job = self.save_job.take()?;
+      if let Err(error) = result {
+         return Some(Err(error));
+      }
+      if job.is_netcanv {
+         self.filename = Some(job.path.clone());
       }
+      if job.explicit {
+         self.last_save_path = Some(job.path.clone());
+      }
+      Some(Ok(FinishedSave {
+         path: job.path,
+         explicit: job.explicit,
+         revisions: job.revisions,
+      }))
    }
 
    /// Extracts the `!org` origin part from an image file's name.
@@ -186,27 +511,40 @@ impl ProjectFile {
       Self::parse_chunk_position(chunk_position).ok()
    }
 
-   /// Loads chunks from an image file.
-   fn load_from_image_file(
-      &mut self,
-      renderer: &mut Backend,
-      path: &Path,
-      canvas: &mut PaintCanvas,
-   ) -> netcanv::Result<()> {
+   /// Decodes an image file and splits it into chunks. This is run on a background thread.
+   fn load_from_image_file(path: &Path, tx: &LoadSender) -> netcanv::Result<()> {
       use ::image::io::Reader as ImageReader;
 
       let image = ImageReader::open(path)?.decode()?.into_rgba8();
+      let origin = Self::extract_chunk_origin_from_filename(path).unwrap_or((0, 0));
+      Self::split_into_chunks(&image, origin, tx)
+   }
+
+   /// Reads an OpenRaster file, flattens its layers, and splits the result into chunks. This is
+   /// run on a background thread.
+   fn load_from_ora(path: &Path, tx: &LoadSender) -> netcanv::Result<()> {
+      let file = std::io::BufReader::new(std::fs::File::open(path)?);
+      let image = OraImage::read(file)?.merge();
+      Self::split_into_chunks(&image, (0, 0), tx)
+   }
+
+   /// Splits an image into chunks, and sends them to the paint canvas. The image's top-left
+   /// corner is placed at the top-left corner of the chunk at `-origin`.
+   fn split_into_chunks(
+      image: &RgbaImage,
+      (origin_x, origin_y): (i32, i32),
+      tx: &LoadSender,
+   ) -> netcanv::Result<()> {
       tracing::debug!("image size: {:?}", image.dimensions());
       let chunks_x = (image.width() as f32 / Chunk::SIZE.0 as f32).ceil() as i32;
       let chunks_y = (image.height() as f32 / Chunk::SIZE.1 as f32).ceil() as i32;
       tracing::debug!("n. chunks: x={}, y={}", chunks_x, chunks_y);
-      let (origin_x, origin_y) = Self::extract_chunk_origin_from_filename(path).unwrap_or((0, 0));
+      Self::send_load_message(tx, LoadMessage::ChunkCount((chunks_x * chunks_y) as usize))?;
 
       for y in 0..chunks_y {
          for x in 0..chunks_x {
             let chunk_position = (x, y);
             let offset_chunk_position = (x - origin_x, y - origin_y);
-            let chunk = canvas.ensure_chunk(renderer, offset_chunk_position);
             let pixel_position = (
                Chunk::SIZE.0 * chunk_position.0 as u32,
                Chunk::SIZE.1 * chunk_position.1 as u32,
@@ -216,19 +554,23 @@ impl ProjectFile {
                offset_chunk_position,
                pixel_position
             );
-            let right = (pixel_position.0 + Chunk::SIZE.0).min(image.width() - 1);
-            let bottom = (pixel_position.1 + Chunk::SIZE.1).min(image.height() - 1);
+            let right = (pixel_position.0 + Chunk::SIZE.0).min(image.width());
+            let bottom = (pixel_position.1 + Chunk::SIZE.1).min(image.height());
             let width = right - pixel_position.0;
             let height = bottom - pixel_position.1;
             let mut chunk_image =
                RgbaImage::from_pixel(Chunk::SIZE.0, Chunk::SIZE.1, Rgba([0, 0, 0, 0]));
             let sub_image = image.view(pixel_position.0, pixel_position.1, width, height);
             chunk_image.copy_from(&*sub_image, 0, 0)?;
-            if Chunk::image_is_empty(&chunk_image) {
-               continue;
-            }
-            chunk.mark_dirty();
-            chunk.upload_image(renderer, &chunk_image, (0, 0));
+            let image = (!Chunk::image_is_empty(&chunk_image)).then_some(chunk_image);
+            Self::send_load_message(
+               tx,
+               LoadMessage::Chunk {
+                  position: offset_chunk_position,
+                  image,
+                  saved: false,
+               },
+            )?;
          }
       }
 
@@ -254,13 +596,10 @@ impl ProjectFile {
       Ok((x, y))
    }
 
-   /// Loads chunks from a `.netcanv` directory.
-   fn load_from_netcanv(
-      &mut self,
-      renderer: &mut Backend,
-      path: &Path,
-      canvas: &mut PaintCanvas,
-   ) -> netcanv::Result<()> {
+   /// Validates a `.netcanv` directory and lists the chunk images inside of it.
+   ///
+   /// This is done up front, so that errors with the canvas as a whole are reported immediately.
+   fn list_netcanv_chunks(path: &Path) -> netcanv::Result<(PathBuf, Vec<((i32, i32), PathBuf)>)> {
       let path = Self::validate_netcanv_save_path(path)?;
       tracing::info!("loading canvas from {:?}", path);
       // load canvas.toml
@@ -270,47 +609,385 @@ impl ProjectFile {
       if canvas_toml.version > CANVAS_TOML_VERSION {
          return Err(Error::CanvasTomlVersionMismatch);
       }
-      // load chunks
-      tracing::debug!("loading chunks");
+      // list chunks
+      tracing::debug!("listing chunks");
+      let mut chunks = Vec::new();
       for entry in std::fs::read_dir(path.clone())? {
          let path = entry?.path();
          // Please let me have if let chains.
          if path.is_file() && path.extension() == Some(OsStr::new("png")) {
             if let Some(position_osstr) = path.file_stem() {
                if let Some(position_str) = position_osstr.to_str() {
-                  let chunk_position = Self::parse_chunk_position(position_str)?;
-                  tracing::debug!("chunk {:?}", chunk_position);
-                  let chunk = canvas.ensure_chunk(renderer, chunk_position);
-                  let image_data = ImageCoder::decode_png_data(&std::fs::read(path)?)?;
-                  chunk.upload_image(renderer, &image_data, (0, 0));
-                  chunk.mark_saved();
+                  match Self::parse_chunk_position(position_str) {
+                     Ok(chunk_position) => chunks.push((chunk_position, path)),
+                     Err(error) => tracing::warn!(
+                        "ignoring file with invalid chunk name {:?}: {:?}",
+                        path,
+                        error
+                     ),
+                  }
                }
             }
          }
       }
-      self.filename = Some(path);
+      Ok((path, chunks))
+   }
+
+   /// Decodes the chunks of a `.netcanv` directory. This is run on a background thread.
+   fn load_from_netcanv(
+      chunks: Vec<((i32, i32), PathBuf)>,
+      tx: &LoadSender,
+   ) -> netcanv::Result<()> {
+      Self::send_load_message(tx, LoadMessage::ChunkCount(chunks.len()))?;
+      for (chunk_position, path) in chunks {
+         tracing::debug!("chunk {:?}", chunk_position);
+         // A single corrupted chunk shouldn't prevent the rest of the canvas from loading.
+         let image = match std::fs::read(&path)
+            .map_err(Error::from)
+            .and_then(|data| ImageCoder::decode_png_data(&data))
+         {
+            Ok(image) => image,
+            Err(error) => {
+               tracing::error!(
+                  "skipping corrupted chunk {:?} ({:?}): {:?}",
+                  chunk_position,
+                  path,
+                  error
+               );
+               Self::send_load_message(tx, LoadMessage::SkippedChunk(chunk_position))?;
+               continue;
+            }
+         };
+         Self::send_load_message(
+            tx,
+            LoadMessage::Chunk {
+               position: chunk_position,
+               image: Some(image),
+               saved: true,
+            },
+         )?;
+      }
       Ok(())
    }
 
-   /// Loads a paint canvas from the given path.
-   pub fn load(
+   /// Starts loading a paint canvas from the given path.
+   ///
+   /// Chunks are decoded on a background thread, and have to be uploaded to the paint canvas
+   /// using the returned [`ProjectLoad`].
+   pub fn load(&mut self, path: &Path) -> netcanv::Result<ProjectLoad> {
+      let is_netcanv = matches!(
+         path.extension().and_then(|ext| ext.to_str()),
+         Some("netcanv") | Some("toml")
+      );
+      let (tx, rx) = mpsc::channel(ProjectLoad::MAX_PENDING_MESSAGES);
+      let netcanv_path = if is_netcanv {
+         let (netcanv_path, chunks) = Self::list_netcanv_chunks(path)?;
+         tokio::task::spawn_blocking(move || {
+            let result = Self::load_from_netcanv(chunks, &tx);
+            Self::finish_loading(result, &tx);
+         });
+         Some(netcanv_path)
+      } else {
+         let path = path.to_owned();
+         let is_ora = path.extension() == Some(OsStr::new("ora"));
+         tokio::task::spawn_blocking(move || {
+            let result = if is_ora {
+               Self::load_from_ora(&path, &tx)
+            } else {
+               Self::load_from_image_file(&path, &tx)
+            };
+            Self::finish_loading(result, &tx);
+         });
+         None
+      };
+      Ok(ProjectLoad {
+         rx,
+         netcanv_path,
+         chunk_count: None,
+         chunks_loaded: 0,
+         skipped_chunks: Vec::new(),
+         created_chunks: Vec::new(),
+      })
+   }
+
+   /// Sends a message from a background loading thread, waiting until there's room for it.
+   /// Fails if the load was abandoned, such that the thread can stop early.
+   fn send_load_message(tx: &LoadSender, message: LoadMessage) -> netcanv::Result<()> {
+      tx.blocking_send(message).map_err(|_| Error::ProjectLoadingInterrupted)
+   }
+
+   /// Sends the final message of a background load.
+   fn finish_loading(result: netcanv::Result<()>, tx: &LoadSender) {
+      let _ = tx.blocking_send(match result {
+         Ok(()) => LoadMessage::Done,
+         Err(error) => LoadMessage::Error(error),
+      });
+   }
+
+   /// Returns what filename the canvas was saved under.
+   pub fn filename(&self) -> Option<&Path> {
+      self.filename.as_deref()
+   }
+
+   /// Returns the path a quick save should write to, or `None` if the user should be asked for
+   /// one first.
+   ///
+   /// This is the path the canvas was last saved to, or the `.netcanv` directory it was loaded
+   /// from.
+   pub fn quick_save_path(&self) -> Option<&Path> {
+      self.last_save_path.as_deref().or(self.filename.as_deref())
+   }
+}
+
+type LoadSender = mpsc::Sender<LoadMessage>;
+
+/// A message sent from a background loading thread.
+enum LoadMessage {
+   /// The number of chunks that are going to be sent.
+   ChunkCount(usize),
+   /// A decoded chunk. Empty chunks are sent as `None`, such that they still count towards
+   /// progress.
+   Chunk {
+      position: (i32, i32),
+      image: Option<RgbaImage>,
+      saved: bool,
+   },
+   /// A chunk could not be decoded, and was skipped.
+   SkippedChunk((i32, i32)),
+   /// Loading failed.
+   Error(Error),
+   /// All chunks have been sent.
+   Done,
+}
+
+/// The status of a [`ProjectLoad`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStatus {
+   Loading,
+   Done,
+}
+
+/// A paint canvas being loaded in the background.
+pub struct ProjectLoad {
+   rx: mpsc::Receiver<LoadMessage>,
+   /// The path to the `.netcanv` directory being loaded, if it is one.
+   netcanv_path: Option<PathBuf>,
+   chunk_count: Option<usize>,
+   chunks_loaded: usize,
+   skipped_chunks: Vec<(i32, i32)>,
+   /// The chunks that didn't exist in the canvas before they were loaded.
+   created_chunks: Vec<(i32, i32)>,
+}
+
+impl ProjectLoad {
+   /// The maximum number of chunks uploaded to the paint canvas per frame, so that loading doesn't
+   /// stall the UI.
+   const MAX_CHUNKS_PER_FRAME: usize = 16;
+
+   /// The maximum number of messages waiting to be received from the loading thread. Decoding is
+   /// paused while the canvas catches up, which bounds the memory used by loading large canvases.
+   const MAX_PENDING_MESSAGES: usize = 4 * Self::MAX_CHUNKS_PER_FRAME;
+
+   /// Uploads the chunks decoded so far to the paint canvas.
+   ///
+   /// Once all chunks are loaded, the project file is pointed at the loaded canvas, such that
+   /// autosaving can take place.
+   ///
+   /// If an error occurs, the chunks that were already uploaded are left in the canvas;
+   /// [`ProjectLoad::remove_created_chunks`] can be used to get rid of them.
+   pub fn upload_chunks(
       &mut self,
       renderer: &mut Backend,
-      path: &Path,
+      project_file: &mut ProjectFile,
       canvas: &mut PaintCanvas,
-   ) -> netcanv::Result<()> {
-      if let Some(ext) = path.extension() {
-         match ext.to_str() {
-            Some("netcanv") | Some("toml") => self.load_from_netcanv(renderer, path, canvas),
-            _ => self.load_from_image_file(renderer, path, canvas),
+   ) -> netcanv::Result<LoadStatus> {
+      for _ in 0..Self::MAX_CHUNKS_PER_FRAME {
+         match self.rx.try_recv() {
+            Ok(LoadMessage::ChunkCount(count)) => self.chunk_count = Some(count),
+            Ok(LoadMessage::Chunk {
+               position,
+               image,
+               saved,
+            }) => {
+               self.chunks_loaded += 1;
+               if let Some(image) = image {
+                  if canvas.chunk(position).is_none() {
+                     self.created_chunks.push(position);
+                  }
+                  let chunk = canvas.ensure_chunk(renderer, position);
+                  chunk.upload_image(renderer, &image, (0, 0));
+                  if saved {
+                     chunk.mark_saved();
+                  }
+               }
+            }
+            Ok(LoadMessage::SkippedChunk(position)) => {
+               self.chunks_loaded += 1;
+               self.skipped_chunks.push(position);
+            }
+            Ok(LoadMessage::Error(error)) => return Err(error),
+            Ok(LoadMessage::Done) => {
+               project_file.filename = self.netcanv_path.take();
+               return Ok(LoadStatus::Done);
+            }
+            Err(mpsc::error::TryRecvError::Empty) => break,
+            Err(mpsc::error::TryRecvError::Disconnected) => {
+               return Err(Error::ProjectLoadingInterrupted)
+            }
          }
-      } else {
-         self.load_from_image_file(renderer, path, canvas)
       }
+      Ok(LoadStatus::Loading)
    }
 
-   /// Returns what filename the canvas was saved under.
-   pub fn filename(&self) -> Option<&Path> {
-      self.filename.as_deref()
+   /// Removes the chunks that were created in the canvas by this load, leaving the chunks that
+   /// existed before it. Returns the positions of the removed chunks.
+   pub fn remove_created_chunks(&mut self, canvas: &mut PaintCanvas) -> Vec<(i32, i32)> {
+      for position in &self.created_chunks {
+         canvas.chunks_mut().remove(position);
+      }
+      std::mem::take(&mut self.created_chunks)
+   }
+
+   /// Returns the positions of chunks that could not be loaded because their data was corrupted.
+   pub fn skipped_chunks(&self) -> &[(i32, i32)] {
+      &self.skipped_chunks
+   }
+
+   /// Returns the loading progress, from 0 to 1.
+   pub fn progress(&self) -> f32 {
+      match self.chunk_count {
+         Some(count) if count > 0 => self.chunks_loaded as f32 / count as f32,
+         _ => 0.0,
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::time::Duration;
+
+   use super::*;
+
+   /// Writes a save containing only a `canvas.toml` with the given contents.
+   fn write_save(path: &Path, contents: &'static str) -> netcanv::Result<()> {
+      ProjectFile::replace_dir_atomically(path, 2, |directory| {
+         std::fs::write(directory.join("canvas.toml"), contents)?;
+         Ok(())
+      })
+   }
+
+   fn read_save(path: &Path) -> String {
+      std::fs::read_to_string(path.join("canvas.toml")).unwrap()
+   }
+
+   #[test]
+   fn interrupted_save_keeps_the_previous_save_intact() {
+      let directory = tempfile::tempdir().unwrap();
+      let path = directory.path().join("canvas.netcanv");
+      write_save(&path, "first").unwrap();
+
+      // The save fails halfway through writing, before the new save would replace the old one.
+      let result = ProjectFile::replace_dir_atomically(&path, 2, |directory| {
+         std::fs::write(directory.join("canvas.toml"), "second")?;
+         Err(Error::ProjectLoadingInterrupted)
+      });
+      assert!(result.is_err());
+
+      assert_eq!(read_save(&path), "first");
+      assert!(!ProjectFile::backup_path(&path, 1).exists());
+      assert!(!ProjectFile::sibling_path(&path, ".", ".tmp").exists());
+   }
+
+   #[test]
+   fn only_the_configured_number_of_backups_is_kept() {
+      let directory = tempfile::tempdir().unwrap();
+      let path = directory.path().join("canvas.netcanv");
+      for contents in ["first", "second", "third", "fourth"] {
+         write_save(&path, contents).unwrap();
+      }
+
+      assert_eq!(read_save(&path), "fourth");
+      assert_eq!(read_save(&ProjectFile::backup_path(&path, 1)), "third");
+      assert_eq!(read_save(&ProjectFile::backup_path(&path, 2)), "second");
+      assert!(!ProjectFile::backup_path(&path, 3).exists());
+   }
+
+   #[tokio::test]
+   async fn saving_within_a_runtime_finishes_in_the_background() {
+      let directory = tempfile::tempdir().unwrap();
+      let path = directory.path().join("canvas.netcanv");
+      let mut project_file = ProjectFile::new();
+      let data = SaveData::Netcanv {
+         path: path.clone(),
+         chunks: vec![((1, -2), RgbaImage::new(Chunk::SIZE.0, Chunk::SIZE.1))],
+         compression: PngCompression::Fast,
+         backups: 0,
+      };
+      project_file.start_save(data, true, HashMap::new());
+      assert!(project_file.is_saving());
+
+      let save = loop {
+         if let Some(result) = project_file.poll_save() {
+            break result.unwrap();
+         }
+         tokio::time::sleep(Duration::from_millis(1)).await;
+      };
+      assert!(!project_file.is_saving());
+      assert_eq!(save.path(), path);
+      assert_eq!(project_file.filename(), Some(path.as_path()));
+      assert!(path.join("canvas.toml").is_file());
+      assert!(path.join("1,-2.png").is_file());
+   }
+
+   #[test]
+   fn corrupted_chunks_are_skipped_when_loading() {
+      let directory = tempfile::tempdir().unwrap();
+      let path = directory.path().join("canvas.netcanv");
+      std::fs::create_dir(&path).unwrap();
+      std::fs::write(
+         path.join("canvas.toml"),
+         format!("version = {CANVAS_TOML_VERSION}"),
+      )
+      .unwrap();
+      let image = RgbaImage::from_pixel(Chunk::SIZE.0, Chunk::SIZE.1, Rgba([1, 2, 3, 255]));
+      let png = ImageCoder::encode_png(&image, PngCompression::Fast).unwrap();
+      std::fs::write(path.join("0,0.png"), &png).unwrap();
+      // Not a PNG at all.
+      std::fs::write(path.join("1,0.png"), b"definitely not a png").unwrap();
+      // Cut off halfway through.
+      std::fs::write(path.join("2,0.png"), &png[..png.len() / 2]).unwrap();
+      // A valid PNG, but not the size of a chunk.
+      let small = ImageCoder::encode_png(&RgbaImage::new(16, 16), PngCompression::Fast).unwrap();
+      std::fs::write(path.join("3,0.png"), small).unwrap();
+      // Files whose names aren't chunk positions are ignored altogether.
+      std::fs::write(path.join("thumbnail.png"), &png).unwrap();
+
+      let (_, chunks) = ProjectFile::list_netcanv_chunks(&path).unwrap();
+      assert_eq!(chunks.len(), 4);
+      let (tx, mut rx) = mpsc::channel(16);
+      ProjectFile::load_from_netcanv(chunks, &tx).unwrap();
+      drop(tx);
+
+      let mut loaded = Vec::new();
+      let mut skipped = Vec::new();
+      while let Ok(message) = rx.try_recv() {
+         match message {
+            LoadMessage::ChunkCount(count) => assert_eq!(count, 4),
+            LoadMessage::Chunk {
+               position,
+               image: chunk_image,
+               ..
+            } => {
+               assert_eq!(chunk_image.as_ref(), Some(&image));
+               loaded.push(position);
+            }
+            LoadMessage::SkippedChunk(position) => skipped.push(position),
+            LoadMessage::Error(error) => panic!("loading failed: {error:?}"),
+            LoadMessage::Done => unreachable!(),
+         }
+      }
+      skipped.sort();
+      assert_eq!(loaded, [(0, 0)]);
+      assert_eq!(skipped, [(1, 0), (2, 0), (3, 0)]);
    }
 }
@@ -27,7 +27,9 @@ pub struct Strings {
 
    pub switch_to_dark_mode: String,
    pub switch_to_light_mode: String,
+   pub switch_to_high_contrast_mode: String,
    pub language: String,
+   pub fonts: FontsStrings,
    pub open_source_licenses: String,
 
    pub connecting: String,
@@ -46,25 +48,74 @@ pub struct Strings {
    pub someone_left_the_room: Formatted,
    pub someone_is_now_hosting_the_room: Formatted,
    pub you_are_now_hosting_the_room: String,
+   pub copy_peer_tool: String,
+   pub peer_tool_copied: Formatted,
+   pub peer_latency: Formatted,
+   pub go_to_peer: String,
+   pub peer_position_unknown: Formatted,
+   pub follow_peer: String,
+   pub stop_following_peer: String,
+   pub following_peer: Formatted,
+   pub followed_peer_left: Formatted,
+   pub followed_peer_stopped_sharing: Formatted,
+   pub viewport_shared: String,
+   pub viewport_not_shared: String,
+   pub connection_status: ConnectionStatusStrings,
+   pub go_to_coordinates: GoToCoordinatesStrings,
+   pub loading_canvas: Formatted,
+   pub saving_canvas: String,
+   pub canvas_chunks_skipped: Formatted,
+   pub connection_lost: String,
+   pub reconnected: String,
+   pub reconnected_to_new_room: Formatted,
 
    pub tool: Map<String>,
    pub brush_thickness: String,
+   pub brush_stabilizer: String,
+   pub brush_blending: BrushBlendingStrings,
+   pub brush_dynamics: BrushDynamicsStrings,
+   pub bucket_tolerance: String,
+   pub text_size: String,
+   pub text_alignment: TextAlignmentStrings,
+   pub line_cap: LineCapStrings,
+   pub text_hint: String,
+   pub undo_conflict: String,
+   #[cfg(feature = "layers")]
+   pub layer_selected: Formatted,
 
    pub action: Map<String>,
+   pub canvas_saved: Formatted,
+   pub overwrite_file: OverwriteFileDialog,
+   pub trim_borders: TrimBordersDialog,
+   pub clear_canvas: ClearCanvasDialog,
+   pub clear_canvas_host_only: String,
+   pub canvas_cleared: String,
+   pub export_viewport: ExportViewportDialog,
+   pub export_quality: ExportQualityDialog,
+   pub viewport_exported: Formatted,
+   pub keymap_editor: KeymapEditorStrings,
+   pub keymap_section: Map<String>,
+   pub keymap_action: Map<String>,
+   pub settings: SettingsStrings,
 
    //
    // Color picker
    //
    pub click_to_edit_color: String,
    pub eraser: String,
+   pub swap_colors: String,
    pub rgb_hex_code: String,
+   pub new_palette: String,
 
    //
    // File dialogs
    //
    pub fd_supported_image_files: String,
    pub fd_png_file: String,
+   pub fd_jpeg_file: String,
+   pub fd_webp_file: String,
    pub fd_netcanv_canvas: String,
+   pub fd_ora_file: String,
 
    //
    // Errors
@@ -89,3 +140,131 @@ pub struct LabelledTextField {
    pub label: String,
    pub hint: String,
 }
+
+#[derive(FromLanguage)]
+pub struct BrushBlendingStrings {
+   pub normal: String,
+   pub multiply: String,
+   pub screen: String,
+}
+
+#[derive(FromLanguage)]
+pub struct FontsStrings {
+   pub title: String,
+   pub interface: String,
+   pub text_tool: String,
+   pub default: String,
+}
+
+#[derive(FromLanguage)]
+pub struct TextAlignmentStrings {
+   pub left: String,
+   pub center: String,
+   pub right: String,
+}
+
+#[derive(FromLanguage)]
+pub struct LineCapStrings {
+   pub round: String,
+   pub square: String,
+   pub butt: String,
+}
+
+#[derive(FromLanguage)]
+pub struct BrushDynamicsStrings {
+   pub title: String,
+   pub position_jitter: String,
+   pub size_jitter: String,
+   pub tip_angle: String,
+   pub tip_roundness: String,
+}
+
+#[derive(FromLanguage)]
+pub struct ConnectionStatusStrings {
+   pub title: String,
+   pub healthy: String,
+   pub degraded: String,
+   pub unhealthy: String,
+   pub round_trip_time: Formatted,
+   pub round_trip_time_unknown: String,
+   pub last_received: Formatted,
+   pub missed_pings: Formatted,
+   pub reconnecting: Formatted,
+}
+
+#[derive(FromLanguage)]
+pub struct KeymapEditorStrings {
+   pub title: String,
+   pub hint: String,
+   pub press_keys: String,
+   pub conflict: String,
+   pub reset: String,
+   pub cancel: String,
+   pub save: String,
+   pub saved: String,
+}
+
+#[derive(FromLanguage)]
+pub struct SettingsStrings {
+   pub title: String,
+   pub theme: String,
+   pub theme_light: String,
+   pub theme_dark: String,
+   pub theme_high_contrast: String,
+   pub theme_custom: String,
+   pub custom_theme_hint: String,
+   pub reload_theme_hint: String,
+}
+
+#[derive(FromLanguage)]
+pub struct GoToCoordinatesStrings {
+   pub title: String,
+   pub x: String,
+   pub y: String,
+   pub go: String,
+   pub invalid: String,
+}
+
+#[derive(FromLanguage)]
+pub struct OverwriteFileDialog {
+   pub title: String,
+   pub description: Formatted,
+   pub replace: String,
+   pub replace_without_asking: String,
+   pub cancel: String,
+}
+
+#[derive(FromLanguage)]
+pub struct ClearCanvasDialog {
+   pub title: String,
+   pub description: String,
+   pub clear: String,
+   pub cancel: String,
+}
+
+#[derive(FromLanguage)]
+pub struct ExportViewportDialog {
+   pub title: String,
+   pub description: Formatted,
+   pub current_zoom: String,
+   pub actual_size: String,
+   pub cancel: String,
+}
+
+#[derive(FromLanguage)]
+pub struct TrimBordersDialog {
+   pub title: String,
+   pub description: String,
+   pub trim: String,
+   pub keep: String,
+   pub cancel: String,
+}
+
+#[derive(FromLanguage)]
+pub struct ExportQualityDialog {
+   pub title: String,
+   pub description: String,
+   pub high_quality: String,
+   pub smaller_file: String,
+   pub cancel: String,
+}
@@ -0,0 +1,139 @@
+//! Exposing the UI to assistive technologies, such as screen readers.
+//!
+//! Since the UI is immediate mode, the accessibility tree is rebuilt from scratch every frame.
+//! While being processed, widgets [`describe`] themselves, and once the frame is finished, the
+//! collected elements are sent over to the platform through AccessKit.
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU128;
+
+use accesskit::{NodeBuilder, NodeClassSet, NodeId, Role, Tree, TreeUpdate};
+use accesskit_winit::Adapter;
+use netcanv_renderer::paws::Rect;
+
+use crate::backend::winit::event::WindowEvent;
+use crate::backend::winit::event_loop::EventLoopProxy;
+use crate::backend::winit::window::Window;
+use crate::wake::UserEvent;
+
+/// An element described by a widget during the current frame.
+struct Element {
+   role: Role,
+   name: String,
+   rect: Rect,
+   focused: bool,
+}
+
+thread_local! {
+   static ELEMENTS: RefCell<Vec<Element>> = RefCell::new(Vec::new());
+}
+
+/// Describes an element of the UI to assistive technologies.
+///
+/// `rect` is the element's rectangle in window coordinates, and `name` is the label a screen
+/// reader announces for it.
+pub fn describe(role: Role, name: &str, rect: Rect, focused: bool) {
+   ELEMENTS.with(|elements| {
+      elements.borrow_mut().push(Element {
+         role,
+         name: name.to_owned(),
+         rect,
+         focused,
+      })
+   });
+}
+
+/// The connection between the UI and the platform's accessibility APIs.
+pub struct Accessibility {
+   adapter: Adapter,
+}
+
+impl Accessibility {
+   /// The ID of the root node, representing the window.
+   const ROOT: NodeId = NodeId(match NonZeroU128::new(1) {
+      Some(id) => id,
+      None => unreachable!(),
+   });
+
+   /// Creates the accessibility adapter for the given window.
+   ///
+   /// On some platforms the window must not be visible yet when this is called, so it should be
+   /// shown only after the adapter is created.
+   pub fn new(window: &Window, event_loop_proxy: EventLoopProxy<UserEvent>) -> Self {
+      Self {
+         adapter: Adapter::new(window, || Self::tree_update(Vec::new()), event_loop_proxy),
+      }
+   }
+
+   /// Processes a window event. Returns whether the event should be handled further by the app.
+   pub fn process_event(&self, window: &Window, event: &WindowEvent) -> bool {
+      self.adapter.on_event(window, event)
+   }
+
+   /// Sends the elements described during this frame to the platform.
+   ///
+   /// This must be called once per frame, even if no assistive technology is active, so that the
+   /// described elements don't pile up.
+   pub fn finish_frame(&self) {
+      let elements = ELEMENTS.with(|elements| std::mem::take(&mut *elements.borrow_mut()));
+      self.adapter.update_if_active(|| Self::tree_update(elements));
+   }
+
+   /// Builds a full tree update out of the given elements.
+   fn tree_update(elements: Vec<Element>) -> TreeUpdate {
+      let mut classes = NodeClassSet::new();
+      let mut nodes = Vec::with_capacity(elements.len() + 1);
+      let mut children = Vec::with_capacity(elements.len());
+      let mut ids = Vec::with_capacity(elements.len());
+      let mut focus = Self::ROOT;
+
+      for element in elements {
+         let id = Self::element_id(&element, &ids);
+         ids.push((element.role, element.name.clone()));
+         if element.focused {
+            focus = id;
+         }
+
+         let mut builder = NodeBuilder::new(element.role);
+         builder.set_name(element.name);
+         builder.set_bounds(accesskit::Rect {
+            x0: element.rect.left() as f64,
+            y0: element.rect.top() as f64,
+            x1: element.rect.right() as f64,
+            y1: element.rect.bottom() as f64,
+         });
+         nodes.push((id, builder.build(&mut classes)));
+         children.push(id);
+      }
+
+      let mut root = NodeBuilder::new(Role::Window);
+      root.set_name("NetCanv");
+      root.set_children(children);
+      nodes.push((Self::ROOT, root.build(&mut classes)));
+
+      TreeUpdate {
+         nodes,
+         tree: Some(Tree::new(Self::ROOT)),
+         focus: Some(focus),
+      }
+   }
+
+   /// Derives a node ID for an element from its role and name, so that it stays the same between
+   /// frames. `previous` contains the roles and names of elements described before this one,
+   /// and is used to tell apart elements that would otherwise be identical.
+   fn element_id(element: &Element, previous: &[(Role, String)]) -> NodeId {
+      let occurrence = previous
+         .iter()
+         .filter(|(role, name)| *role == element.role && *name == element.name)
+         .count();
+      let mut hasher = DefaultHasher::new();
+      element.role.hash(&mut hasher);
+      element.name.hash(&mut hasher);
+      occurrence.hash(&mut hasher);
+      // The upper bits are always set, so that the ID never collides with the root's.
+      let id = (1 << 64) | hasher.finish() as u128;
+      NodeId(NonZeroU128::new(id).unwrap())
+   }
+}
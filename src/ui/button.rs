@@ -1,5 +1,6 @@
 //! Pressable buttons.
 
+use accesskit::Role;
 use netcanv_renderer::paws::{AlignH, AlignV, Color, Layout, Rect};
 use netcanv_renderer::Font as FontTrait;
 
@@ -39,6 +40,8 @@ pub struct ButtonArgs<'a> {
    colors: &'a ButtonColors,
    corner_radius: f32,
    tooltip: Option<(&'a Font, Tooltip<'a>)>,
+   focus_outline: Option<Color>,
+   label: Option<&'a str>,
 }
 
 impl<'a> ButtonArgs<'a> {
@@ -49,6 +52,8 @@ impl<'a> ButtonArgs<'a> {
          colors,
          corner_radius: 0.0,
          tooltip: None,
+         focus_outline: None,
+         label: None,
       }
    }
 
@@ -70,6 +75,15 @@ impl<'a> ButtonArgs<'a> {
       self
    }
 
+   /// Marks the button as focused with the keyboard, if `focus` is focused.
+   ///
+   /// A focused button is outlined with the given color, and can be clicked by pressing
+   /// `Return` or `Space`.
+   pub fn focus(mut self, focus: &impl Focus, outline: Color) -> Self {
+      self.focus_outline = focus.focused().then_some(outline);
+      self
+   }
+
    /// Makes the button pill-shaped.
    pub fn pill(self) -> Self {
       let height = self.height;
@@ -98,6 +112,8 @@ impl Button {
          colors,
          corner_radius,
          tooltip,
+         focus_outline,
+         label,
       }: &ButtonArgs,
       width_hint: Option<f32>,
       extra: impl FnOnce(&mut Ui),
@@ -120,9 +136,22 @@ impl Button {
       if let Some((font, tooltip)) = tooltip {
          tooltip.process(ui, input, font);
       }
-      let clicked = ui.clicked(input, MouseButton::Left);
+      let mut clicked = ui.clicked(input, MouseButton::Left);
+      if let Some(color) = focus_outline {
+         ui.focus_outline(*color, *corner_radius);
+         clicked |= input.key_just_typed(VirtualKeyCode::Return)
+            || input.key_just_typed(VirtualKeyCode::Space);
+      }
 
       let group = ui.rect();
+      // Buttons without any text are named after their tooltips.
+      let name = label.or_else(|| tooltip.as_ref().map(|(_, tooltip)| &*tooltip.text));
+      accessibility::describe(
+         Role::Button,
+         name.unwrap_or_default(),
+         group,
+         focus_outline.is_some(),
+      );
 
       ui.pop();
 
@@ -139,7 +168,11 @@ impl Button {
       width: f32,
    ) -> ButtonProcessResult {
       let color = args.colors.text;
-      Self::process(ui, input, args, Some(width), |ui| {
+      let args = ButtonArgs {
+         label: Some(text),
+         ..args.clone()
+      };
+      Self::process(ui, input, &args, Some(width), |ui| {
          ui.push((width, ui.height()), Layout::Freeform);
          ui.text(font, text, color, (AlignH::Center, AlignV::Middle));
          ui.pop();
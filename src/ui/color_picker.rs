@@ -5,13 +5,16 @@ use image::{Rgba, RgbaImage};
 use netcanv_renderer::paws::{
    point, vector, AlignH, AlignV, Color, Layout, Padding, Rect, Renderer, Vector,
 };
-use netcanv_renderer::{Font, Framebuffer as FramebufferTrait, RenderBackend, ScalingFilter};
+use netcanv_renderer::{
+   Font, Framebuffer as FramebufferTrait, Image as ImageTrait, RenderBackend, ScalingFilter,
+};
 use strum::{EnumIter, EnumMessage};
 
 use crate::assets::Assets;
 use crate::backend::{Backend, Framebuffer, Image};
-use crate::color::{AnyColor, Hsv, Okhsv, Srgb};
+use crate::color::{AnyColor, Hsv, Okhsv, Oklch, Srgb};
 use crate::common::ColorMath;
+use crate::config::{self, config, ColorPickerConfig, DEFAULT_PALETTE_NAME};
 use crate::ui::ValueSlider;
 
 use super::view::{Dimension, Dimensions, View};
@@ -20,9 +23,9 @@ use super::wm::{
    HitTest, WindowContent, WindowContentArgs, WindowContentWrappers, WindowId, WindowManager,
 };
 use super::{
-   Button, ButtonArgs, ButtonColors, ButtonState, Focus, Input, RadioButton, RadioButtonArgs,
-   SliderStep, TextField, TextFieldArgs, TextFieldColors, Tooltip, Ui, UiInput, ValueSliderArgs,
-   ValueUnit,
+   chain_focus, Button, ButtonArgs, ButtonColors, ButtonState, ContextMenu, ContextMenuArgs, Focus,
+   Input, RadioButton, RadioButtonArgs, SliderStep, TextField, TextFieldArgs, TextFieldColors,
+   Tooltip, Ui, UiInput, ValueSliderArgs, ValueUnit,
 };
 
 /// Arguments for processing the color picker.
@@ -39,10 +42,24 @@ pub struct ColorPickerIcons {
 }
 
 /// A color picker.
+///
+/// The picker holds two colors: the _primary_ color, which is selected from the palette, and the
+/// _secondary_ color, which can be swapped with the primary color at any time.
+///
+/// Colors that have been painted with are remembered in a row of recent colors, shown next to the
+/// palette.
+///
+/// Palettes are saved to disk under a name, and can be switched between in the picker window.
 pub struct ColorPicker {
    palette: [AnyColor; Self::NUM_COLORS],
+   /// The name the palette is saved under.
+   palette_name: String,
    index: usize,
    pub eraser: bool,
+   /// The secondary color. A fully transparent color denotes the eraser.
+   secondary: Color,
+   /// The recently used colors, most recent first.
+   recent: Vec<Color>,
 
    window_state: Option<PickerWindowState>,
 }
@@ -50,6 +67,8 @@ pub struct ColorPicker {
 impl ColorPicker {
    /// The number of colors in a palette.
    const NUM_COLORS: usize = 10;
+   /// The number of recently used colors that are remembered.
+   const NUM_RECENT_COLORS: usize = 8;
 
    const DEFAULT_PALETTE: [Color; Self::NUM_COLORS] = [
       Color::rgb(0x100820), // Black
@@ -64,19 +83,45 @@ impl ColorPicker {
       Color::rgb(0xa315d7), // Purple
    ];
 
-   /// Creates a new color picker.
-   pub fn new() -> Self {
-      let palette = Self::DEFAULT_PALETTE.map(|color| Srgb::from_color(color).into());
+   /// Creates a color picker with the default palette, without loading anything from the user
+   /// config.
+   fn with_default_palette() -> Self {
       Self {
-         palette,
+         palette: Self::DEFAULT_PALETTE.map(|color| Srgb::from_color(color).into()),
+         palette_name: DEFAULT_PALETTE_NAME.to_owned(),
          index: 0,
          eraser: false,
-         window_state: Some(PickerWindowState::Closed(PickerWindow::new_data(
-            palette[0],
-         ))),
+         secondary: Color::TRANSPARENT,
+         recent: Vec::new(),
+         window_state: None,
       }
    }
 
+   /// Creates a new color picker, with the primary and secondary colors loaded from the user
+   /// config.
+   pub fn new() -> Self {
+      let mut this = Self::with_default_palette();
+      let ColorPickerConfig {
+         primary, secondary, ..
+      } = config().color_picker;
+      let palette_name = config().color_picker.palette.clone();
+      this.load_palette(&palette_name);
+      this.window_state = Some(PickerWindowState::Closed(PickerWindow::new_data(
+         this.palette[0],
+         this.palette_name.clone(),
+      )));
+      this.set_color_or_eraser(tuple_to_color(primary));
+      this.secondary = tuple_to_color(secondary);
+      this.recent = config()
+         .ui
+         .recent_colors
+         .iter()
+         .map(|&color| tuple_to_color(color))
+         .take(Self::NUM_RECENT_COLORS)
+         .collect();
+      this
+   }
+
    /// Returns a view for the picker window. This view should be laid out and then passed back to
    /// `process` via [`ColorPickerArgs`].
    pub fn picker_window_view() -> View {
@@ -110,6 +155,95 @@ impl ColorPicker {
       self.eraser = enabled;
    }
 
+   /// Sets the currently selected color, or enables the eraser if the color is fully transparent.
+   pub fn set_color_or_eraser(&mut self, color: Color) {
+      if color.a == 0 {
+         self.set_eraser(true);
+      } else {
+         self.set_color(color);
+      }
+   }
+
+   /// Returns the (paws) secondary color.
+   pub fn secondary_color(&self) -> Color {
+      self.secondary
+   }
+
+   /// Sets the secondary color. A fully transparent color means the eraser.
+   pub fn set_secondary_color(&mut self, color: Color) {
+      self.secondary = color;
+   }
+
+   /// Swaps the primary and secondary colors.
+   pub fn swap_colors(&mut self) {
+      let primary = self.color();
+      let secondary = self.secondary;
+      self.set_color_or_eraser(secondary);
+      self.secondary = primary;
+   }
+
+   /// Remembers the primary color as recently used. Does nothing if the eraser is selected.
+   ///
+   /// The color is moved to the front of the recent colors, and the oldest color is forgotten if
+   /// there are too many.
+   pub fn remember_color(&mut self) {
+      if self.eraser {
+         return;
+      }
+      let color = self.color();
+      self.recent.retain(|&recent| recent != color);
+      self.recent.insert(0, color);
+      self.recent.truncate(Self::NUM_RECENT_COLORS);
+   }
+
+   /// Switches to the palette saved under the given name.
+   ///
+   /// If the palette doesn't exist or is malformed, the default palette is used instead. Palettes
+   /// with fewer colors than the color picker has slots are padded with the default colors.
+   fn load_palette(&mut self, name: &str) {
+      let colors = match config::load_palette(name) {
+         Some(colors) => colors,
+         None if name != DEFAULT_PALETTE_NAME => return self.load_palette(DEFAULT_PALETTE_NAME),
+         None => Vec::new(),
+      };
+      self.palette = std::array::from_fn(|index| {
+         colors
+            .get(index)
+            .copied()
+            .unwrap_or_else(|| Srgb::from_color(Self::DEFAULT_PALETTE[index]).into())
+      });
+      self.palette_name = name.to_owned();
+   }
+
+   /// Saves the palette to disk, under its current name.
+   fn save_palette(&self) {
+      catch!(config::save_palette(&self.palette_name, &self.palette));
+   }
+
+   /// Returns a name for a new palette, which isn't used by any other palette yet.
+   fn unused_palette_name() -> String {
+      let names = config::palette_names();
+      (1..).map(|number| format!("palette {}", number)).find(|name| !names.contains(name)).unwrap()
+   }
+
+   /// Saves the primary and secondary colors, as well as the recent colors and the palette, to the
+   /// user config.
+   pub fn save_colors(&self) {
+      let primary = color_to_tuple(self.color());
+      let secondary = color_to_tuple(self.secondary);
+      let palette = self.palette_name.clone();
+      let recent_colors = self.recent.iter().map(|&color| color_to_tuple(color)).collect();
+      self.save_palette();
+      config::write(|config| {
+         config.color_picker = ColorPickerConfig {
+            primary,
+            secondary,
+            palette,
+         };
+         config.ui.recent_colors = recent_colors;
+      });
+   }
+
    /// Processes the color palette.
    pub fn process(
       &mut self,
@@ -122,6 +256,10 @@ impl ColorPicker {
          show_eraser,
       }: ColorPickerArgs,
    ) {
+      // The primary and secondary colors.
+      self.process_swatches(ui, input, assets);
+      ui.space(16.0);
+
       // The palette.
       for (index, &color) in self.palette.clone().iter().enumerate() {
          ui.push((16.0, ui.height()), Layout::Freeform);
@@ -153,6 +291,12 @@ impl ColorPicker {
          ui.pop();
       }
 
+      // The recently used colors.
+      if !self.recent.is_empty() {
+         ui.space(16.0);
+         self.process_recent_colors(ui, input, assets, wm);
+      }
+
       if show_eraser {
          ui.space(16.0);
          if Button::with_icon(
@@ -182,6 +326,23 @@ impl ColorPicker {
          self.window_data_mut(wm).color = self.palette[self.index];
       }
 
+      // Switching between palettes, as requested from the picker window. The current palette is
+      // saved first, such that changes made to it aren't lost.
+      if let Some(request) = self.window_data_mut(wm).palette_request.take() {
+         self.save_palette();
+         match request {
+            PaletteRequest::Switch(name) => self.load_palette(&name),
+            PaletteRequest::Create => {
+               self.palette_name = Self::unused_palette_name();
+               self.save_palette();
+            }
+         }
+         let (palette_name, color) = (self.palette_name.clone(), self.palette[self.index]);
+         let data = self.window_data_mut(wm);
+         data.palette_name = palette_name;
+         data.color = color;
+      }
+
       if let Some(window_id) = self.window_id() {
          // If the window is unpinned, move it to the window_view.
          if !wm.pinned(window_id) {
@@ -194,6 +355,80 @@ impl ColorPicker {
       }
    }
 
+   /// Processes the primary/secondary color swatch pair. Clicking it swaps the two colors.
+   fn process_swatches(&mut self, ui: &mut Ui, input: &Input, assets: &Assets) {
+      const SWATCH_SIZE: f32 = 16.0;
+      const SWATCH_OFFSET: f32 = 8.0;
+
+      let size = SWATCH_SIZE + SWATCH_OFFSET;
+      ui.push((size, ui.height()), Layout::Freeform);
+      ui.push((size, size), Layout::Freeform);
+      ui.align((AlignH::Center, AlignV::Middle));
+      Tooltip::top(&assets.tr.swap_colors).process(ui, input, &assets.sans);
+      if ui.clicked(input, MouseButton::Left) {
+         self.swap_colors();
+      }
+      let (primary, secondary) = (self.color(), self.secondary);
+      ui.draw(|ui| {
+         let swatch = vector(SWATCH_SIZE, SWATCH_SIZE);
+         let secondary_rect = Rect::new(point(SWATCH_OFFSET, SWATCH_OFFSET), swatch);
+         let primary_rect = Rect::new(point(0.0, 0.0), swatch);
+         Self::draw_swatch(ui.render(), secondary_rect, secondary, assets);
+         Self::draw_swatch(ui.render(), primary_rect, primary, assets);
+      });
+      ui.pop();
+      ui.pop();
+   }
+
+   /// Processes the row of recently used colors. Clicking a color puts it into the palette, in
+   /// place of the currently selected color.
+   fn process_recent_colors(
+      &mut self,
+      ui: &mut Ui,
+      input: &Input,
+      assets: &Assets,
+      wm: &mut WindowManager,
+   ) {
+      const SWATCH_SIZE: f32 = 12.0;
+      const SWATCH_SPACING: f32 = 4.0;
+
+      for (index, &color) in self.recent.clone().iter().enumerate() {
+         if index > 0 {
+            ui.space(SWATCH_SPACING);
+         }
+         ui.push((SWATCH_SIZE, ui.height()), Layout::Freeform);
+         ui.push((SWATCH_SIZE, SWATCH_SIZE), Layout::Freeform);
+         ui.align((AlignH::Center, AlignV::Middle));
+         let hovered = ui.hover(input);
+         if ui.clicked(input, MouseButton::Left) {
+            self.eraser = false;
+            self.palette[self.index] = Srgb::from_color(color).into();
+            self.window_data_mut(wm).color = self.palette[self.index];
+         }
+         ui.draw(|ui| {
+            let rect = Rect::new(point(0.0, 0.0), ui.size());
+            ui.render().fill(rect, color, 2.0);
+            if hovered {
+               ui.render().outline(rect, assets.colors.text, 2.0, 1.0);
+            }
+         });
+         ui.pop();
+         ui.pop();
+      }
+   }
+
+   /// Draws a single color swatch. The eraser is drawn using its icon.
+   fn draw_swatch(renderer: &mut Backend, rect: Rect, color: Color, assets: &Assets) {
+      if color.a == 0 {
+         renderer.fill(rect, assets.colors.panel, 4.0);
+         let icon = assets.icons.color_picker.eraser.colorized(assets.colors.text);
+         renderer.image(rect, &icon);
+      } else {
+         renderer.fill(rect, color, 4.0);
+      }
+      renderer.outline(rect, assets.colors.text, 4.0, 1.0);
+   }
+
    /// Toggles the picker window on or off, depending on whether it's already open or not.
    fn toggle_picker_window(&mut self, renderer: &mut Backend, wm: &mut WindowManager, view: View) {
       match self.window_state.take().unwrap() {
@@ -206,7 +441,7 @@ impl ColorPicker {
                PickerWindow::new(renderer, &data).background().buttons(WindowButtonStyle {
                   padding: Padding::even(12.0),
                });
-            let window_id = wm.open_window(view, content, data).finish();
+            let window_id = wm.open_window(view, content, data).set_kind("color_picker").finish();
             self.window_state = Some(PickerWindowState::Open(window_id));
          }
       }
@@ -240,6 +475,16 @@ impl ColorPicker {
    }
 }
 
+/// Converts an RGBA tuple, as stored in the user config, to a (paws) color.
+fn tuple_to_color((r, g, b, a): (u8, u8, u8, u8)) -> Color {
+   Color::new(r, g, b, a)
+}
+
+/// Converts a (paws) color to an RGBA tuple, as stored in the user config.
+fn color_to_tuple(Color { r, g, b, a }: Color) -> (u8, u8, u8, u8) {
+   (r, g, b, a)
+}
+
 enum PickerWindowState {
    Open(WindowId<PickerWindowData>),
    Closed(PickerWindowData),
@@ -251,12 +496,25 @@ enum ColorSpace {
    Oklab,
    #[strum(message = "RGB")]
    Rgb,
+   #[strum(message = "OKLCH")]
+   Oklch,
+}
+
+/// A request to switch palettes, made from the picker window.
+enum PaletteRequest {
+   /// Switch to the palette with the given name.
+   Switch(String),
+   /// Save the current colors as a new palette, and switch to it.
+   Create,
 }
 
 struct PickerWindowData {
    color: AnyColor,
    color_space: ColorSpace,
    color_changed: bool,
+   /// The name of the palette in use, shown on the palette selector.
+   palette_name: String,
+   palette_request: Option<PaletteRequest>,
 }
 
 struct PickerWindow {
@@ -275,22 +533,33 @@ struct PickerWindow {
 
    /// The text field containing the color's `#RRGGBB` hex code.
    hex_code: TextField,
-   /// The channel and HSV sliders.
-   sliders: [ValueSlider; 6],
+   /// The channel, HSV, and LCH sliders.
+   sliders: [ValueSlider; 9],
 
    /// The previously selected color. If different from the previous frame, the widgets are
    /// updated to reflect the changes.
    previous_color: AnyColor,
    previous_color_space: ColorSpace,
+
+   /// The dropdown menu listing the palettes.
+   palette_menu: ContextMenu,
+   /// The names of the palettes listed in the menu, updated whenever the menu is opened.
+   palette_names: Vec<String>,
 }
 
 impl PickerWindow {
    /// The dimensions of the picker window.
    const DIMENSIONS: Dimensions = Dimensions {
       horizontal: Dimension::Constant(448.0),
-      vertical: Dimension::Constant(268.0),
+      vertical: Dimension::Constant(268.0 + Self::FOOTER_HEIGHT),
    };
 
+   /// The height of the footer, which holds the palette selector.
+   const FOOTER_HEIGHT: f32 = 36.0;
+   /// The maximum number of palettes listed in the palette menu, such that the menu fits inside
+   /// the window.
+   const MAX_LISTED_PALETTES: usize = 7;
+
    // The three sliders "I", "J", and "K" are called like that to represent their dual purpose.
 
    /// The R channel adjustment slider.
@@ -307,6 +576,13 @@ impl PickerWindow {
    /// The value adjustment slider.
    const V_SLIDER: usize = 5;
 
+   /// The OKLCH lightness adjustment slider.
+   const LCH_L_SLIDER: usize = 6;
+   /// The OKLCH chroma adjustment slider.
+   const LCH_C_SLIDER: usize = 7;
+   /// The OKLCH hue adjustment slider.
+   const LCH_H_SLIDER: usize = 8;
+
    /// Creates the picker window's inner data.
    fn new(renderer: &mut Backend, data: &PickerWindowData) -> Self {
       const CANVAS_RESOLUTION: u32 = 32;
@@ -324,6 +600,9 @@ impl PickerWindow {
 
          previous_color: data.color,
          previous_color_space: data.color_space,
+
+         palette_menu: ContextMenu::new((0.0, 0.0)),
+         palette_names: Vec::new(),
       };
       this.slider_image.set_scaling_filter(ScalingFilter::Linear);
       this.canvas_image.set_scaling_filter(ScalingFilter::Linear);
@@ -334,29 +613,50 @@ impl PickerWindow {
    }
 
    /// Creates the picker window's outer data.
-   fn new_data(default_color: AnyColor) -> PickerWindowData {
+   fn new_data(default_color: AnyColor, palette_name: String) -> PickerWindowData {
       PickerWindowData {
          color: default_color,
          color_space: ColorSpace::Oklab,
          color_changed: false,
+         palette_name,
+         palette_request: None,
       }
    }
 
-   /// Creates a set of RGB and HSV sliders for the given color.
-   fn create_sliders(color: Srgb) -> [ValueSlider; 6] {
+   /// Creates a set of RGB, HSV, and LCH sliders for the given color.
+   fn create_sliders(color: Srgb) -> [ValueSlider; 9] {
       let Srgb { r, g, b } = color;
       let Hsv { h, s, v } = Hsv::from(color);
       let h = h * 60.0;
+      let lch = Oklch::from(AnyColor::from(color));
       let rgb = ValueUnit::new("", 0);
       let degrees = ValueUnit::new("°", 0);
       let percent = ValueUnit::new("%", 0);
+      let chroma = ValueUnit::new("", 3);
       [
          ValueSlider::new("R", rgb.clone(), r, 0.0, 255.0, SliderStep::Discrete(1.0)),
          ValueSlider::new("G", rgb.clone(), g, 0.0, 255.0, SliderStep::Discrete(1.0)),
          ValueSlider::new("B", rgb, b, 0.0, 255.0, SliderStep::Discrete(1.0)),
          ValueSlider::new("H", degrees, h, 0.0, 360.0, SliderStep::Discrete(1.0)),
          ValueSlider::new("S", percent.clone(), s, 0.0, 100.0, SliderStep::Smooth),
-         ValueSlider::new("V", percent, v, 0.0, 100.0, SliderStep::Smooth),
+         ValueSlider::new("V", percent.clone(), v, 0.0, 100.0, SliderStep::Smooth),
+         ValueSlider::new("L", percent, lch.l * 100.0, 0.0, 100.0, SliderStep::Smooth),
+         ValueSlider::new(
+            "C",
+            chroma,
+            lch.c,
+            0.0,
+            Oklch::MAX_SRGB_CHROMA,
+            SliderStep::Smooth,
+         ),
+         ValueSlider::new(
+            "H",
+            ValueUnit::new("°", 0),
+            lch.h * 360.0,
+            0.0,
+            360.0,
+            SliderStep::Smooth,
+         ),
       ]
    }
 
@@ -384,6 +684,19 @@ impl PickerWindow {
             .to_color(1.0);
             Rgba([color.r, color.g, color.b, color.a])
          }),
+         ColorSpace::Oklch => RgbaImage::from_fn(width, height, |_x, y| {
+            let hue = y as f32 / height as f32;
+            let color = Srgb::from(AnyColor::from(
+               Oklch {
+                  l: 0.75,
+                  c: 0.15,
+                  h: hue,
+               }
+               .clamp_to_srgb(),
+            ))
+            .to_color(1.0);
+            Rgba([color.r, color.g, color.b, color.a])
+         }),
       };
       renderer.upload_framebuffer(framebuffer, (0, 0), (width, height), &image);
    }
@@ -399,6 +712,7 @@ impl PickerWindow {
       let hue = match color_space {
          ColorSpace::Rgb => Hsv::from(color).h,
          ColorSpace::Oklab => Okhsv::from(color).h,
+         ColorSpace::Oklch => Oklch::from(color).h,
       };
       let image = match color_space {
          ColorSpace::Rgb => RgbaImage::from_fn(width, height, |x, y| {
@@ -423,6 +737,21 @@ impl PickerWindow {
             .to_color(1.0);
             Rgba([color.r, color.g, color.b, color.a])
          }),
+         // The chroma grows to the right, and the lightness grows upwards. Colors outside of the
+         // sRGB gamut are left transparent.
+         ColorSpace::Oklch => RgbaImage::from_fn(width, height, |x, y| {
+            let color = Oklch {
+               l: 1.0 - y as f32 / (height - 1) as f32,
+               c: x as f32 / (width - 1) as f32 * Oklch::MAX_SRGB_CHROMA,
+               h: hue,
+            };
+            if color.is_in_srgb_gamut() {
+               let color = Srgb::from(AnyColor::from(color)).to_color(1.0);
+               Rgba([color.r, color.g, color.b, color.a])
+            } else {
+               Rgba([0, 0, 0, 0])
+            }
+         }),
       };
       renderer.upload_framebuffer(framebuffer, (0, 0), (width, height), &image);
    }
@@ -438,6 +767,7 @@ impl PickerWindow {
             match data.color_space {
                ColorSpace::Rgb => Hsv::from(data.color).h / 6.0,
                ColorSpace::Oklab => Okhsv::from(data.color).h,
+               ColorSpace::Oklch => Oklch::from(data.color).h,
             } * ui.height(),
          );
          let width = ui.width();
@@ -482,6 +812,11 @@ impl PickerWindow {
                let h = y;
                AnyColor::from(Okhsv { h, s, v })
             }
+            ColorSpace::Oklch => {
+               let Oklch { l, c, .. } = Oklch::from(data.color);
+               let h = y;
+               AnyColor::from(Oklch { l, c, h }.clamp_to_srgb())
+            }
          };
       }
 
@@ -499,12 +834,14 @@ impl PickerWindow {
             match data.color_space {
                ColorSpace::Rgb => Hsv::from(data.color).s,
                ColorSpace::Oklab => Okhsv::from(data.color).s,
+               ColorSpace::Oklch => Oklch::from(data.color).c / Oklch::MAX_SRGB_CHROMA,
             } * ui.width(),
          );
          let y = f32::round(
             match data.color_space {
                ColorSpace::Rgb => 1.0 - Hsv::from(data.color).v,
                ColorSpace::Oklab => 1.0 - Okhsv::from(data.color).v,
+               ColorSpace::Oklch => 1.0 - Oklch::from(data.color).l,
             } * ui.height(),
          );
          let radius = 4.0;
@@ -531,6 +868,11 @@ impl PickerWindow {
                let h = Okhsv::from(data.color).h;
                AnyColor::from(Okhsv { h, s, v })
             }
+            ColorSpace::Oklch => {
+               let h = Oklch::from(data.color).h;
+               let (c, l) = (s * Oklch::MAX_SRGB_CHROMA, v);
+               AnyColor::from(Oklch { l, c, h }.clamp_to_srgb())
+            }
          };
       }
 
@@ -573,8 +915,12 @@ impl PickerWindow {
       );
       if hex_code.done() || hex_code.unfocused() {
          if let Some(color) = Self::parse_hex_code(self.hex_code.text()) {
-            let color = AnyColor::from(Srgb::from_color(color));
-            data.color = color;
+            // The hex code is only precise to 8 bits per channel, so the color is only replaced
+            // if the hex code was actually edited. Otherwise colors picked in other color spaces
+            // would drift each time the text field loses focus.
+            if color != Srgb::from(data.color).to_color(1.0) {
+               data.color = AnyColor::from(Srgb::from_color(color));
+            }
          }
          self.update_widgets(ui, data);
       }
@@ -583,20 +929,26 @@ impl PickerWindow {
       // The value sliders below the text field.
       let value_slider = ValueSliderArgs {
          color: assets.colors.slider,
+         focus_outline: assets.colors.focus_outline,
          font: &assets.sans,
          label_width: Some(16.0),
          value_width: Some(40.0),
       };
-      let mut sliders_changed = [false; 6];
+      // The second row of sliders depends on the color space.
+      let second_row = match data.color_space {
+         ColorSpace::Oklab | ColorSpace::Rgb => Self::H_SLIDER,
+         ColorSpace::Oklch => Self::LCH_L_SLIDER,
+      };
+      let mut sliders_changed = [false; 9];
       for (i, slider) in self.sliders[Self::R_SLIDER..=Self::B_SLIDER].iter_mut().enumerate() {
          if slider.process(ui, input, value_slider).changed() {
             sliders_changed[i] = true;
          }
       }
       ui.space(8.0);
-      for (i, slider) in self.sliders[Self::H_SLIDER..=Self::V_SLIDER].iter_mut().enumerate() {
+      for (i, slider) in self.sliders[second_row..second_row + 3].iter_mut().enumerate() {
          if slider.process(ui, input, value_slider).changed() {
-            sliders_changed[i + Self::H_SLIDER] = true;
+            sliders_changed[i + second_row] = true;
          }
       }
 
@@ -625,12 +977,38 @@ impl PickerWindow {
             update_color_channel!(Self::S_SLIDER, Hsv, s, 100.0);
             update_color_channel!(Self::V_SLIDER, Hsv, v, 100.0);
          }
+         ColorSpace::Oklch => {
+            update_color_channel!(Self::LCH_L_SLIDER, Oklch, l, 100.0);
+            update_color_channel!(Self::LCH_C_SLIDER, Oklch, c, 1.0);
+            update_color_channel!(Self::LCH_H_SLIDER, Oklch, h, 360.0);
+            if sliders_changed[Self::LCH_L_SLIDER..=Self::LCH_H_SLIDER].contains(&true) {
+               data.color = AnyColor::from(Oklch::from(data.color).clamp_to_srgb());
+            }
+         }
       }
 
       if sliders_changed.iter().any(|&changed| changed) {
          self.update_widgets(ui, data);
       }
 
+      let [r, g, b, h, s, v, lch_l, lch_c, lch_h] = &mut self.sliders;
+      let [i, j, k] = match data.color_space {
+         ColorSpace::Oklab | ColorSpace::Rgb => [h, s, v],
+         ColorSpace::Oklch => [lch_l, lch_c, lch_h],
+      };
+      chain_focus(
+         input,
+         &mut [
+            &mut self.hex_code,
+            &mut **r,
+            &mut **g,
+            &mut **b,
+            &mut **i,
+            &mut **j,
+            &mut **k,
+         ],
+      );
+
       ui.pop();
    }
 
@@ -712,6 +1090,113 @@ impl PickerWindow {
       }
    }
 
+   /// Processes the footer, which holds the button that opens the palette menu.
+   fn process_footer(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      data: &PickerWindowData,
+   ) {
+      ui.push((ui.width(), Self::FOOTER_HEIGHT), Layout::Horizontal);
+      ui.pad(Padding {
+         top: 0.0,
+         ..Padding::even(12.0)
+      });
+      let button = Button::with_text(
+         ui,
+         input,
+         &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+         &assets.sans,
+         &data.palette_name,
+      );
+      let button_rect = button.group();
+      if button.clicked() {
+         if self.palette_menu.is_open() {
+            self.palette_menu.close();
+         } else {
+            self.open_palette_menu(button_rect);
+         }
+      }
+      ui.pop();
+   }
+
+   /// Opens the palette menu above the given button, listing the palettes currently on disk.
+   fn open_palette_menu(&mut self, button_rect: Rect) {
+      let mut names = config::palette_names();
+      if !names.iter().any(|name| name == DEFAULT_PALETTE_NAME) {
+         names.insert(0, DEFAULT_PALETTE_NAME.to_owned());
+      }
+      names.truncate(Self::MAX_LISTED_PALETTES);
+      // One more item for creating a new palette.
+      let item_count = names.len() + 1;
+      let height = 16.0 + item_count as f32 * 24.0 + (item_count - 1) as f32 * 4.0;
+      self.palette_menu = ContextMenu::new((160.0, height));
+      self.palette_menu.view.position = point(button_rect.left(), button_rect.top() - height - 4.0);
+      self.palette_menu.open();
+      self.palette_names = names;
+   }
+
+   /// Processes the palette menu, if it's open.
+   fn process_palette_menu(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      assets: &Assets,
+      data: &mut PickerWindowData,
+   ) {
+      if self
+         .palette_menu
+         .begin(
+            ui,
+            input,
+            ContextMenuArgs {
+               colors: &assets.colors.context_menu,
+            },
+         )
+         .is_open()
+      {
+         ui.pad(8.0);
+         for name in &self.palette_names {
+            ui.push((ui.width(), 24.0), Layout::Horizontal);
+            let colors = ButtonColors::toggle(
+               *name == data.palette_name,
+               &assets.colors.radio_button.normal,
+               &assets.colors.radio_button.selected,
+            );
+            if Button::with_text(
+               ui,
+               input,
+               &ButtonArgs::new(ui, colors).height(24.0).pill(),
+               &assets.sans,
+               name,
+            )
+            .clicked()
+            {
+               data.palette_request = Some(PaletteRequest::Switch(name.clone()));
+               self.palette_menu.close();
+            }
+            ui.pop();
+            ui.space(4.0);
+         }
+         ui.push((ui.width(), 24.0), Layout::Horizontal);
+         if Button::with_text(
+            ui,
+            input,
+            &ButtonArgs::new(ui, &assets.colors.button).height(24.0).pill(),
+            &assets.sans,
+            &assets.tr.new_palette,
+         )
+         .clicked()
+         {
+            data.palette_request = Some(PaletteRequest::Create);
+            self.palette_menu.close();
+         }
+         ui.pop();
+         self.palette_menu.end(ui);
+      }
+   }
+
    /// Parses a hex code into a color. If the given text is not a valid hex code, returns `None`.
    fn parse_hex_code(text: &str) -> Option<Color> {
       // Empty string? Not a hex code.
@@ -774,6 +1259,12 @@ impl PickerWindow {
             self.sliders[Self::S_SLIDER].set_value(s * 100.0);
             self.sliders[Self::V_SLIDER].set_value(v * 100.0);
          }
+         ColorSpace::Oklch => {
+            let Oklch { l, c, h } = Oklch::from(data.color);
+            self.sliders[Self::LCH_L_SLIDER].set_value(l * 100.0);
+            self.sliders[Self::LCH_C_SLIDER].set_value(c);
+            self.sliders[Self::LCH_H_SLIDER].set_value(h * 360.0);
+         }
       }
    }
 }
@@ -797,7 +1288,10 @@ impl WindowContent for PickerWindow {
       self.process_header_bar(ui, input, assets, hit_test, data);
 
       // Process the group encompassing the color canvas and slider.
-      ui.push(ui.remaining_size(), Layout::Horizontal);
+      ui.push(
+         ui.remaining_size() - vector(0.0, Self::FOOTER_HEIGHT),
+         Layout::Horizontal,
+      );
       ui.pad(Padding {
          top: 0.0,
          ..Padding::even(12.0)
@@ -811,8 +1305,13 @@ impl WindowContent for PickerWindow {
 
       ui.pop();
 
+      self.process_footer(ui, input, assets, data);
+
       ui.pop();
 
+      // The palette menu is processed last, such that it's drawn over everything else.
+      self.process_palette_menu(ui, input, assets, data);
+
       data.color_changed = false;
       if data.color != self.previous_color || data.color_space != self.previous_color_space {
          self.update_widgets(ui, data);
@@ -822,3 +1321,39 @@ impl WindowContent for PickerWindow {
       self.previous_color_space = data.color_space;
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn remembered_colors_are_moved_to_the_front() {
+      let mut picker = ColorPicker::with_default_palette();
+      let [red, green, blue] = [0xff0000, 0x00ff00, 0x0000ff].map(Color::rgb);
+      for color in [red, green, blue, green] {
+         picker.set_color(color);
+         picker.remember_color();
+      }
+      assert_eq!(picker.recent, [green, blue, red]);
+   }
+
+   #[test]
+   fn oldest_remembered_colors_are_forgotten() {
+      let mut picker = ColorPicker::with_default_palette();
+      for index in 0..=ColorPicker::NUM_RECENT_COLORS as u32 {
+         picker.set_color(Color::rgb(index));
+         picker.remember_color();
+      }
+      assert_eq!(picker.recent.len(), ColorPicker::NUM_RECENT_COLORS);
+      assert_eq!(picker.recent.first(), Some(&Color::rgb(8)));
+      assert_eq!(picker.recent.last(), Some(&Color::rgb(1)));
+   }
+
+   #[test]
+   fn eraser_is_not_remembered() {
+      let mut picker = ColorPicker::with_default_palette();
+      picker.set_eraser(true);
+      picker.remember_color();
+      assert!(picker.recent.is_empty());
+   }
+}
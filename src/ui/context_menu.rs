@@ -47,6 +47,11 @@ impl ContextMenu {
       self.is_open = false;
    }
 
+   /// Returns whether the context menu is open.
+   pub fn is_open(&self) -> bool {
+      self.is_open
+   }
+
    /// Toggles the context menu open.
    pub fn toggle(&mut self) {
       if self.is_open {
@@ -48,6 +48,11 @@ impl Expand {
       Self { expanded }
    }
 
+   /// Returns whether the Expand is currently expanded.
+   pub fn is_expanded(&self) -> bool {
+      self.expanded
+   }
+
    /// Processes an Expand.
    #[must_use]
    pub fn process(
@@ -2,17 +2,20 @@
 
 use std::borrow::Cow;
 use std::ops::{BitAnd, BitOr};
+use std::path::{Path, PathBuf};
 use web_time::Instant;
 
 use crate::backend::winit::dpi::PhysicalPosition;
 pub use crate::backend::winit::event::{ElementState, MouseButton, VirtualKeyCode};
-use crate::backend::winit::event::{KeyboardInput, WindowEvent};
+use crate::backend::winit::event::{KeyboardInput, Touch, TouchPhase, WindowEvent};
 use crate::backend::winit::window::{CursorIcon, Window};
 use netcanv_renderer::paws::{point, vector, Point, Vector};
 use serde::de::Visitor;
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Serialize};
 
+use super::InputRecorder;
+
 const MOUSE_BUTTON_COUNT: usize = 8;
 const KEY_CODE_COUNT: usize = 256;
 
@@ -22,6 +25,8 @@ pub struct Input {
    mouse_position: Point,
    previous_mouse_position: Point,
    mouse_scroll: Vector,
+   /// The pressure of the pen touching the screen or tablet, normalized to `0.0..=1.0`.
+   pen_pressure: Option<f32>,
 
    mouse_button_is_down: [bool; MOUSE_BUTTON_COUNT],
    mouse_button_just_pressed: [bool; MOUSE_BUTTON_COUNT],
@@ -39,9 +44,120 @@ pub struct Input {
 
    key_just_typed: [bool; KEY_CODE_COUNT],
    key_is_down: [bool; KEY_CODE_COUNT],
+   /// The key typed most recently during this frame.
+   last_key_typed: Option<VirtualKeyCode>,
+
+   /// The factor the UI is scaled up by. Cursor positions are divided by this, so that they're
+   /// expressed in the same logical pixels the UI is laid out in.
+   ui_scale: f32,
+
+   // files
+   /// Files dropped onto the window during this frame. These are not recorded, as the files
+   /// might not exist anymore by the time the recording is replayed.
+   dropped_files: Vec<PathBuf>,
 
    // time
    time_origin: Instant,
+   /// The time reported by [`Input::time_in_seconds`], when replaying a recording.
+   replay_time: Option<f32>,
+
+   // debugging
+   recorder: Option<InputRecorder>,
+}
+
+/// An input event. This is the subset of winit's window events that [`Input`] reacts to.
+///
+/// Unlike window events, input events can be serialized, which is used for recording input.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum InputEvent {
+   CursorMoved {
+      x: f32,
+      y: f32,
+   },
+   MouseInput {
+      button: MouseButton,
+      state: ElementState,
+   },
+   MouseWheel {
+      x: f32,
+      y: f32,
+   },
+   Character(char),
+   Keyboard {
+      key: VirtualKeyCode,
+      state: ElementState,
+   },
+   Focused(bool),
+   /// The pressure of a pen or touch, normalized to `0.0..=1.0`. `None` once the pen is lifted,
+   /// or if the device doesn't report pressure.
+   PenPressure(Option<f32>),
+}
+
+impl InputEvent {
+   /// Converts a window event to an input event, or returns `None` if the event is not relevant
+   /// to input handling.
+   pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+      Some(match event {
+         WindowEvent::CursorMoved { position, .. } => {
+            let PhysicalPosition { x, y } = position;
+            Self::CursorMoved {
+               x: *x as _,
+               y: *y as _,
+            }
+         }
+
+         WindowEvent::MouseInput { button, state, .. } => Self::MouseInput {
+            button: *button,
+            state: *state,
+         },
+
+         WindowEvent::MouseWheel { delta, .. } => {
+            use crate::backend::winit::event::MouseScrollDelta::*;
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let (x, y) = match *delta {
+               LineDelta(x, y) => (x, y),
+               PixelDelta(PhysicalPosition { x, y }) => (x as f32, y as f32),
+            };
+
+            #[cfg(target_arch = "wasm32")]
+            let (x, y) = match *delta {
+               LineDelta(x, y) => (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0)),
+               PixelDelta(PhysicalPosition { x, y }) => {
+                  (x.clamp(-1.0, 1.0) as f32, y.clamp(-1.0, 1.0) as f32)
+               }
+            };
+
+            Self::MouseWheel { x, y }
+         }
+
+         WindowEvent::ReceivedCharacter(c) => Self::Character(*c),
+
+         WindowEvent::KeyboardInput {
+            input:
+               KeyboardInput {
+                  state,
+                  virtual_keycode: Some(key),
+                  ..
+               },
+            ..
+         } => Self::Keyboard {
+            key: *key,
+            state: *state,
+         },
+
+         WindowEvent::Focused(focused) => Self::Focused(*focused),
+
+         WindowEvent::Touch(Touch { phase, force, .. }) => Self::PenPressure(match phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+               force.map(|force| force.normalized().clamp(0.0, 1.0) as f32)
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => None,
+         }),
+
+         _ => return None,
+      })
+   }
 }
 
 impl Input {
@@ -51,6 +167,7 @@ impl Input {
          mouse_position: point(0.0, 0.0),
          previous_mouse_position: point(0.0, 0.0),
          mouse_scroll: vector(0.0, 0.0),
+         pen_pressure: None,
 
          mouse_button_is_down: [false; MOUSE_BUTTON_COUNT],
          mouse_button_just_pressed: [false; MOUSE_BUTTON_COUNT],
@@ -66,8 +183,16 @@ impl Input {
          char_buffer: Vec::new(),
          key_just_typed: [false; KEY_CODE_COUNT],
          key_is_down: [false; KEY_CODE_COUNT],
+         last_key_typed: None,
+
+         ui_scale: 1.0,
+
+         dropped_files: Vec::new(),
 
          time_origin: Instant::now(),
+         replay_time: None,
+
+         recorder: None,
       }
    }
 
@@ -90,6 +215,12 @@ impl Input {
       }
    }
 
+   /// Returns the pressure of the pen, if a pen (or another pressure-sensitive device) is currently
+   /// touching the tablet or screen.
+   pub fn pen_pressure(&self) -> Option<f32> {
+      self.pen_pressure
+   }
+
    /// Returns whether mouse clicks are locked.
    fn mouse_buttons_locked(&self) -> bool {
       self.active_mouse_area != self.frame_mouse_area
@@ -169,6 +300,16 @@ impl Input {
       &self.char_buffer
    }
 
+   /// Returns the paths of the files that were dropped onto the window during this frame.
+   pub fn dropped_files(&self) -> &[PathBuf] {
+      &self.dropped_files
+   }
+
+   /// Registers a file that was dropped onto the window.
+   pub fn drop_file(&mut self, path: &Path) {
+      self.dropped_files.push(path.to_owned());
+   }
+
    /// Returns whether the provided key was just typed.
    pub fn key_just_typed(&self, key: VirtualKeyCode) -> bool {
       if let Some(i) = Self::key_index(key) {
@@ -178,6 +319,13 @@ impl Input {
       }
    }
 
+   /// Returns the key that was typed most recently during this frame, if any.
+   ///
+   /// This is useful for capturing arbitrary keys, eg. when rebinding a keyboard shortcut.
+   pub fn last_key_typed(&self) -> Option<VirtualKeyCode> {
+      self.last_key_typed
+   }
+
    /// Returns wheter the provided key is down
    pub fn key_is_down(&self, key: VirtualKeyCode) -> bool {
       if let Some(i) = Self::key_index(key) {
@@ -197,62 +345,69 @@ impl Input {
       self.key_is_down(VirtualKeyCode::LShift) || self.key_is_down(VirtualKeyCode::RShift)
    }
 
+   /// Returns whether the Alt key is being held down.
+   pub fn alt_is_down(&self) -> bool {
+      self.key_is_down(VirtualKeyCode::LAlt) || self.key_is_down(VirtualKeyCode::RAlt)
+   }
+
    /// Returns the time elapsed since this `Input` was created, in seconds.
+   ///
+   /// When replaying a recording, this is the time the current frame was recorded at instead.
    pub fn time_in_seconds(&self) -> f32 {
+      if let Some(time) = self.replay_time {
+         return time;
+      }
       let now = self.time_origin.elapsed();
       now.as_millis() as f32 / 1_000.0
    }
 
-   /// Processes a `WindowEvent`.
-   pub fn process_event(&mut self, event: &WindowEvent) {
-      match event {
-         WindowEvent::CursorMoved { position, .. } => {
-            let PhysicalPosition { x, y } = position;
-            self.mouse_position = Point::new(*x as _, *y as _);
-         }
-
-         WindowEvent::MouseInput { button, state, .. } => self.process_mouse_input(*button, *state),
-
-         WindowEvent::MouseWheel { delta, .. } => {
-            use crate::backend::winit::event::MouseScrollDelta::*;
+   /// Sets the time reported by [`Input::time_in_seconds`] while replaying a recording.
+   /// `None` goes back to using the real time.
+   pub fn set_replay_time(&mut self, time: Option<f32>) {
+      self.replay_time = time;
+   }
 
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-               self.mouse_scroll = match *delta {
-                  LineDelta(x, y) => Vector::new(x, y),
-                  PixelDelta(PhysicalPosition { x, y }) => Vector::new(x as f32, y as f32),
-               };
-            }
+   /// Starts recording all processed input events into the given recorder.
+   pub fn start_recording(&mut self, recorder: InputRecorder) {
+      self.recorder = Some(recorder);
+   }
 
-            #[cfg(target_arch = "wasm32")]
-            {
-               self.mouse_scroll = match *delta {
-                  LineDelta(x, y) => Vector::new(x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0)),
-                  PixelDelta(PhysicalPosition { x, y }) => {
-                     Vector::new(x.clamp(-1.0, 1.0) as f32, y.clamp(-1.0, 1.0) as f32)
-                  }
-               };
-            }
-         }
+   /// Stops recording input, flushing any remaining recorded data.
+   pub fn stop_recording(&mut self) {
+      if let Some(mut recorder) = self.recorder.take() {
+         recorder.flush();
+      }
+   }
 
-         WindowEvent::ReceivedCharacter(c) => self.char_buffer.push(*c),
+   /// Processes a `WindowEvent`.
+   pub fn process_event(&mut self, event: &WindowEvent) {
+      if let Some(event) = InputEvent::from_window_event(event) {
+         self.process_input_event(event);
+      }
+   }
 
-         WindowEvent::KeyboardInput {
-            input:
-               KeyboardInput {
-                  state,
-                  virtual_keycode: Some(key),
-                  ..
-               },
-            ..
-         } => self.process_keyboard_input(*key, *state),
+   /// Sets the factor the UI is scaled up by. Cursor positions arriving after this call are
+   /// converted to logical pixels using the new factor.
+   pub fn set_ui_scale(&mut self, scale: f32) {
+      self.ui_scale = scale;
+   }
 
+   /// Processes an `InputEvent`.
+   pub fn process_input_event(&mut self, event: InputEvent) {
+      if let Some(recorder) = &mut self.recorder {
+         recorder.record(event);
+      }
+      match event {
+         InputEvent::CursorMoved { x, y } => self.mouse_position = Point::new(x, y) / self.ui_scale,
+         InputEvent::MouseInput { button, state } => self.process_mouse_input(button, state),
+         InputEvent::MouseWheel { x, y } => self.mouse_scroll = Vector::new(x, y),
+         InputEvent::Character(c) => self.char_buffer.push(c),
+         InputEvent::Keyboard { key, state } => self.process_keyboard_input(key, state),
          // We don't have a guarantee that we will get all input events. For example, we might get
          // an event that Shift got pressed, but not that it got released. To avoid this issue,
          // reset the input state after gaining or losing focus.
-         WindowEvent::Focused(_) => self.reset_input_state(),
-
-         _ => (),
+         InputEvent::Focused(_) => self.reset_input_state(),
+         InputEvent::PenPressure(pressure) => self.pen_pressure = pressure,
       }
    }
 
@@ -260,6 +415,10 @@ impl Input {
    /// position, scroll delta, among other things, so this must be called at the end of each
    /// frame.
    pub fn finish_frame(&mut self, window: &Window) {
+      let time = self.time_in_seconds();
+      if let Some(recorder) = &mut self.recorder {
+         recorder.finish_frame(time);
+      }
       for state in &mut self.mouse_button_just_pressed {
          *state = false;
       }
@@ -276,7 +435,9 @@ impl Input {
       for state in &mut self.key_just_typed {
          *state = false;
       }
+      self.last_key_typed = None;
       self.char_buffer.clear();
+      self.dropped_files.clear();
    }
 
    /// Returns the numeric index of the mouse given button, or `None` if the mouse button is not
@@ -331,6 +492,7 @@ impl Input {
          if state == ElementState::Pressed {
             self.key_just_typed[i] = true;
             self.key_is_down[i] = true;
+            self.last_key_typed = Some(key);
          }
 
          if state == ElementState::Released {
@@ -346,6 +508,8 @@ impl Input {
       self.mouse_button_just_released.fill(false);
       self.key_just_typed.fill(false);
       self.key_is_down.fill(false);
+      self.last_key_typed = None;
+      self.pen_pressure = None;
    }
 }
 
@@ -0,0 +1,109 @@
+//! Recording and replaying input, for reproducing bugs.
+//!
+//! A recording is a sequence of bincode-encoded [`InputFrame`]s, one per frame rendered while
+//! recording.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Input, InputEvent};
+
+/// The input events processed during a single frame.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputFrame {
+   /// The time at the end of the frame, as reported by [`Input::time_in_seconds`].
+   pub time: f32,
+   pub events: Vec<InputEvent>,
+}
+
+/// Writes processed input events to a file.
+pub struct InputRecorder {
+   writer: BufWriter<File>,
+   events: Vec<InputEvent>,
+}
+
+impl InputRecorder {
+   /// Creates a new recorder writing to the file at the given path.
+   pub fn create(path: &Path) -> netcanv::Result<Self> {
+      tracing::info!("recording input to {:?}", path);
+      Ok(Self {
+         writer: BufWriter::new(File::create(path)?),
+         events: Vec::new(),
+      })
+   }
+
+   /// Records an event for the current frame.
+   pub fn record(&mut self, event: InputEvent) {
+      self.events.push(event);
+   }
+
+   /// Writes out the events recorded during the current frame.
+   pub fn finish_frame(&mut self, time: f32) {
+      let frame = InputFrame {
+         time,
+         events: std::mem::take(&mut self.events),
+      };
+      if let Err(error) = bincode::serialize_into(&mut self.writer, &frame) {
+         tracing::error!("could not write recorded input frame: {}", error);
+      }
+   }
+
+   /// Flushes the recorded frames to the file.
+   pub fn flush(&mut self) {
+      if let Err(error) = self.writer.flush() {
+         tracing::error!("could not flush input recording: {}", error);
+      }
+   }
+}
+
+/// Reads back input events recorded by an [`InputRecorder`].
+pub struct InputReplay {
+   reader: BufReader<File>,
+   frame_count: usize,
+}
+
+impl InputReplay {
+   /// Opens the recording at the given path.
+   pub fn open(path: &Path) -> netcanv::Result<Self> {
+      tracing::info!("replaying input from {:?}", path);
+      Ok(Self {
+         reader: BufReader::new(File::open(path)?),
+         frame_count: 0,
+      })
+   }
+
+   /// Feeds the events of the next recorded frame into `input`, and makes it report the time
+   /// the frame was recorded at.
+   ///
+   /// Returns `false` once there are no more frames to replay, after which `input` goes back to
+   /// using the real time.
+   pub fn replay_frame(&mut self, input: &mut Input) -> bool {
+      match bincode::deserialize_from::<_, InputFrame>(&mut self.reader) {
+         Ok(frame) => {
+            self.frame_count += 1;
+            input.set_replay_time(Some(frame.time));
+            for event in frame.events {
+               input.process_input_event(event);
+            }
+            true
+         }
+         Err(error) => {
+            match *error {
+               bincode::ErrorKind::Io(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                  tracing::info!("input replay finished after {} frames", self.frame_count)
+               }
+               error => tracing::error!(
+                  "input replay stopped after {} frames: {}",
+                  self.frame_count,
+                  error
+               ),
+            }
+            input.set_replay_time(None);
+            false
+         }
+      }
+   }
+}
@@ -1,15 +1,19 @@
 //! UI controls.
 
-use netcanv_renderer::paws::{self, vector, AlignH, AlignV, Color, Layout, Point, Vector};
+use netcanv_renderer::paws::{
+   self, point, vector, AlignH, AlignV, Color, Layout, Point, Rect, Renderer, Vector,
+};
 use netcanv_renderer::{Font as FontTrait, Image as ImageTrait, RenderBackend};
 
 use crate::backend::{Backend, Font, Image};
 
+pub mod accessibility;
 mod button;
 mod color_picker;
 mod context_menu;
 mod expand;
 mod input;
+mod input_recording;
 mod radio_button;
 mod slider;
 mod text_field;
@@ -22,6 +26,7 @@ pub use color_picker::*;
 pub use context_menu::*;
 pub use expand::*;
 pub use input::*;
+pub use input_recording::*;
 pub use radio_button::*;
 pub use slider::*;
 pub use text_field::*;
@@ -112,6 +117,12 @@ pub trait UiElements {
    ) where
       T: IntoIterator<Item = S>,
       S: AsRef<str>;
+
+   /// Draws an outline around the current group, marking it as focused with the keyboard.
+   ///
+   /// The outline is drawn just outside of the group, so that it doesn't cover the group's own
+   /// outline.
+   fn focus_outline(&mut self, color: Color, corner_radius: f32);
 }
 
 impl UiElements for Ui {
@@ -167,6 +178,18 @@ impl UiElements for Ui {
       self.fit();
       self.pop();
    }
+
+   fn focus_outline(&mut self, color: Color, corner_radius: f32) {
+      const OFFSET: f32 = 3.0;
+      const THICKNESS: f32 = 2.0;
+      self.draw(|ui| {
+         let rect = Rect::new(
+            point(-OFFSET, -OFFSET),
+            ui.size() + vector(OFFSET, OFFSET) * 2.0,
+         );
+         ui.render().outline(rect, color, corner_radius + OFFSET, THICKNESS);
+      });
+   }
 }
 
 /// A trait implemented by elements that can be (un)focused.
@@ -175,6 +198,22 @@ pub trait Focus {
    fn set_focus(&mut self, focused: bool);
 }
 
+/// Focus state for elements that don't have any state of their own, such as buttons.
+#[derive(Default)]
+pub struct FocusHandle {
+   focused: bool,
+}
+
+impl Focus for FocusHandle {
+   fn focused(&self) -> bool {
+      self.focused
+   }
+
+   fn set_focus(&mut self, focused: bool) {
+      self.focused = focused;
+   }
+}
+
 /// Creates a _focus chain_, that is, a list of elements that can be `Tab`bed between.
 pub fn chain_focus(input: &Input, fields: &mut [&mut dyn Focus]) {
    macro_rules! process_focus_change {
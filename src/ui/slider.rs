@@ -23,6 +23,7 @@ pub struct Slider {
    max: f32,
    step: SliderStep,
    sliding: bool,
+   focused: bool,
 }
 
 /// Slider processing arguments.
@@ -30,6 +31,7 @@ pub struct Slider {
 pub struct SliderArgs {
    pub width: f32,
    pub color: Color,
+   pub focus_outline: Color,
 }
 
 impl Slider {
@@ -41,6 +43,7 @@ impl Slider {
          max,
          step,
          sliding: false,
+         focused: false,
       }
    }
 
@@ -58,7 +61,11 @@ impl Slider {
       &mut self,
       ui: &mut Ui,
       input: &Input,
-      SliderArgs { width, color }: SliderArgs,
+      SliderArgs {
+         width,
+         color,
+         focus_outline,
+      }: SliderArgs,
    ) -> SliderProcessResult {
       let previous_value = self.value();
 
@@ -84,6 +91,20 @@ impl Slider {
          }
       }
 
+      if self.focused {
+         let key_amount = match self.step {
+            SliderStep::Discrete(_) => 1.0 / self.step_count() as f32,
+            SliderStep::Smooth => 0.01,
+         };
+         if input.key_just_typed(VirtualKeyCode::Left) {
+            self.value -= key_amount;
+         }
+         if input.key_just_typed(VirtualKeyCode::Right) {
+            self.value += key_amount;
+         }
+         ui.focus_outline(focus_outline, 4.0);
+      }
+
       self.value = self.value.clamp(0.0, 1.0);
 
       ui.draw(|ui| {
@@ -149,6 +170,16 @@ impl Slider {
    }
 }
 
+impl Focus for Slider {
+   fn focused(&self) -> bool {
+      self.focused
+   }
+
+   fn set_focus(&mut self, focused: bool) {
+      self.focused = focused;
+   }
+}
+
 /// The result of processing a slider.
 pub struct SliderProcessResult {
    changed: bool,
@@ -180,6 +211,7 @@ impl ValueUnit {
 #[derive(Clone, Copy)]
 pub struct ValueSliderArgs<'f> {
    pub color: Color,
+   pub focus_outline: Color,
    pub font: &'f Font,
    pub label_width: Option<f32>,
    pub value_width: Option<f32>,
@@ -216,6 +248,7 @@ impl ValueSlider {
       input: &Input,
       ValueSliderArgs {
          color,
+         focus_outline,
          font,
          label_width,
          value_width,
@@ -244,6 +277,7 @@ impl ValueSlider {
          SliderArgs {
             width: ui.remaining_width() - value_width,
             color,
+            focus_outline,
          },
       );
       ui.horizontal_label(font, &value, color, Some((value_width, AlignH::Right)));
@@ -3,13 +3,16 @@
 use std::ops::Range;
 
 use crate::backend::winit::window::CursorIcon;
+use accesskit::Role;
 use netcanv_renderer::Font as FontTrait;
 use paws::{point, vector, AlignH, AlignV, Color, Layout, LineCap, Rect, Renderer};
+use web_time::{Duration, Instant};
 
 use crate::backend::Font;
 use crate::clipboard;
 use crate::config::config;
 use crate::ui::*;
+use crate::wake;
 
 /// A text field's state.
 pub struct TextField {
@@ -32,6 +35,7 @@ pub struct TextFieldColors {
    pub text_hint: Color,
    pub label: Color,
    pub selection: Color,
+   pub focus_outline: Color,
 }
 
 /// Processing arguments for a text field.
@@ -74,6 +78,16 @@ impl TextField {
 
    /// Processes a text field.
    pub fn process(
+      &mut self,
+      ui: &mut Ui,
+      input: &mut Input,
+      args: TextFieldArgs,
+   ) -> TextFieldProcessResult {
+      self.process_named(ui, input, args, args.hint)
+   }
+
+   /// Processes a text field, exposing it to assistive technologies under the given name.
+   fn process_named(
       &mut self,
       ui: &mut Ui,
       input: &mut Input,
@@ -83,8 +97,15 @@ impl TextField {
          colors,
          hint,
       }: TextFieldArgs,
+      name: Option<&str>,
    ) -> TextFieldProcessResult {
       ui.push((width, Self::height(font)), Layout::Freeform);
+      accessibility::describe(
+         Role::TextField,
+         name.unwrap_or_default(),
+         ui.rect(),
+         self.focused,
+      );
 
       // Rendering: box
       let outline_color = if self.focused {
@@ -94,6 +115,9 @@ impl TextField {
       };
       ui.fill_rounded(colors.fill, 4.0);
       ui.outline_rounded(outline_color, 4.0, 1.0);
+      if self.focused {
+         ui.focus_outline(colors.focus_outline, 4.0);
+      }
 
       ui.push(ui.size(), Layout::Freeform);
       ui.pad((8.0, 0.0));
@@ -112,9 +136,17 @@ impl TextField {
          }
       }
 
-      if self.focused
-         && (input.time_in_seconds() - self.blink_start) % Self::BLINK_PERIOD < Self::HALF_BLINK
-      {
+      let blink_phase = (input.time_in_seconds() - self.blink_start) % Self::BLINK_PERIOD;
+      if self.focused {
+         // Wake up once the caret has to appear or disappear.
+         let until_toggle = if blink_phase < Self::HALF_BLINK {
+            Self::HALF_BLINK - blink_phase
+         } else {
+            Self::BLINK_PERIOD - blink_phase
+         };
+         wake::at(Instant::now() + Duration::from_secs_f32(until_toggle));
+      }
+      if self.focused && blink_phase < Self::HALF_BLINK {
          ui.draw(|ui| {
             let current_text = &self.text[..self.selection.cursor()];
             let x = font.text_width(current_text);
@@ -201,7 +233,7 @@ impl TextField {
       ui.pop();
 
       // field
-      let process_result = self.process(ui, input, args);
+      let process_result = self.process_named(ui, input, args, Some(label));
 
       ui.pop();
 
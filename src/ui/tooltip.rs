@@ -14,6 +14,7 @@ use super::{Input, Ui, UiInput};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TooltipPosition {
    Top,
+   Bottom,
    Left,
    Right,
 }
@@ -47,6 +48,7 @@ impl TooltipPosition {
       let group_center = group.center();
       let center = match self {
          TooltipPosition::Top => group_center - vector(0.0, height / 2.0 + spacing),
+         TooltipPosition::Bottom => group_center + vector(0.0, height / 2.0 + spacing),
          TooltipPosition::Left => group_center - vector(width / 2.0 + spacing, 0.0),
          TooltipPosition::Right => group_center + vector(width / 2.0 + spacing, 0.0),
       };
@@ -14,7 +14,8 @@ use super::{ButtonState, Input, Ui};
 
 use crate::backend::winit::event::MouseButton;
 use crate::backend::winit::window::CursorIcon;
-use netcanv_renderer::paws::Layout;
+use netcanv_renderer::paws::{point, Layout};
+use serde::{Deserialize, Serialize};
 pub use windows::WindowContentWrappers;
 
 /// A window.
@@ -36,6 +37,25 @@ struct Window {
    focused: bool,
    /// Whether the window _can_ be focused.
    focusable: bool,
+   /// The identifier the window's layout is saved under, or `None` if it isn't saved.
+   kind: Option<&'static str>,
+}
+
+impl Window {
+   /// Returns the window's current layout.
+   fn layout(&self) -> WindowLayout {
+      WindowLayout {
+         position: (self.view.position.x, self.view.position.y),
+         pinned: self.pinned,
+      }
+   }
+}
+
+/// The position and pinned state of a window, saved between sessions.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct WindowLayout {
+   pub position: (f32, f32),
+   pub pinned: bool,
 }
 
 /// A window manager.
@@ -43,6 +63,8 @@ pub struct WindowManager {
    window_id: Token,
    windows: HashMap<UntypedWindowId, Window>,
    stack: Vec<UntypedWindowId>,
+   /// The last known layouts of pinned windows, keyed by their kind.
+   layouts: HashMap<String, WindowLayout>,
 }
 
 impl WindowManager {
@@ -52,6 +74,35 @@ impl WindowManager {
          window_id: Token::new(0),
          windows: HashMap::new(),
          stack: Vec::new(),
+         layouts: HashMap::new(),
+      }
+   }
+
+   /// Restores window layouts saved with [`Self::save_layout`]. The layouts are applied to windows
+   /// as they're opened, using [`WindowSettings::set_kind`].
+   pub fn restore_layout(&mut self, layouts: HashMap<String, WindowLayout>) {
+      self.layouts = layouts;
+   }
+
+   /// Returns the layouts of pinned windows, including ones that were closed while pinned.
+   ///
+   /// Unpinned windows are transient, so their layouts are not saved.
+   pub fn save_layout(&self) -> HashMap<String, WindowLayout> {
+      let mut layouts = self.layouts.clone();
+      for window in self.windows.values() {
+         Self::remember_layout(&mut layouts, window);
+      }
+      layouts
+   }
+
+   /// Remembers the layout of the window if it's pinned, or forgets it otherwise.
+   fn remember_layout(layouts: &mut HashMap<String, WindowLayout>, window: &Window) {
+      if let Some(kind) = window.kind {
+         if window.pinned {
+            layouts.insert(kind.to_owned(), window.layout());
+         } else {
+            layouts.remove(kind);
+         }
       }
    }
 
@@ -128,6 +179,7 @@ impl WindowManager {
             dragging: false,
             focused: true,
             focusable: true,
+            kind: None,
          },
       );
       self.stack.push(id);
@@ -146,6 +198,7 @@ impl WindowManager {
    {
       self.stack.retain(|&f| f != window.0);
       let window = self.windows.remove(&window.0).unwrap();
+      Self::remember_layout(&mut self.layouts, &window);
       *window.data.downcast().unwrap()
    }
 
@@ -265,6 +318,17 @@ impl<'wm, D> WindowSettings<'wm, D> {
       self
    }
 
+   /// Sets the identifier the window's layout is saved under. If a layout was saved for this kind
+   /// of window, the window is moved and pinned according to it.
+   pub fn set_kind(mut self, kind: &'static str) -> Self {
+      self.window().kind = Some(kind);
+      if let Some(&WindowLayout { position, pinned }) = self.wm.layouts.get(kind) {
+         self.window().view.position = point(position.0, position.1);
+         self.window().pinned = pinned;
+      }
+      self
+   }
+
    /// Finishes setting up a window.
    pub fn finish(self) -> WindowId<D> {
       WindowId(self.id, PhantomData)
@@ -357,3 +421,54 @@ impl Default for HitTest {
       Self::Content
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::ui::view::Dimensions;
+
+   struct Empty;
+
+   impl WindowContent for Empty {
+      type Data = ();
+
+      fn process(&mut self, _: &mut WindowContentArgs, _: &mut ()) {}
+   }
+
+   fn open(wm: &mut WindowManager, kind: &'static str) -> WindowId<()> {
+      let view = View::new(Dimensions::new(100.0, 100.0));
+      wm.open_window(view, Empty, ()).set_kind(kind).finish()
+   }
+
+   #[test]
+   fn pinned_window_reopens_where_it_was_closed() {
+      let mut wm = WindowManager::new();
+      let window = open(&mut wm, "picker");
+      wm.view_mut(&window).position = point(32.0, 64.0);
+      wm.windows.get_mut(&window.0).unwrap().pinned = true;
+      wm.close_window(window);
+
+      let mut restarted = WindowManager::new();
+      restarted.restore_layout(wm.save_layout());
+      let window = open(&mut restarted, "picker");
+      assert_eq!(restarted.view(&window).position, point(32.0, 64.0));
+      assert!(restarted.pinned(&window));
+   }
+
+   #[test]
+   fn unpinned_windows_are_forgotten() {
+      let mut wm = WindowManager::new();
+      let window = open(&mut wm, "picker");
+      wm.windows.get_mut(&window.0).unwrap().pinned = true;
+      assert!(wm.save_layout().contains_key("picker"));
+
+      // Unpinning the window drops the layout remembered for it.
+      wm.windows.get_mut(&window.0).unwrap().pinned = false;
+      assert!(wm.save_layout().is_empty());
+      wm.close_window(window);
+      assert!(wm.save_layout().is_empty());
+
+      let window = open(&mut wm, "picker");
+      assert!(!wm.pinned(&window));
+   }
+}
@@ -1,12 +1,31 @@
-//! Panning and zooming.
+//! Panning, zooming, and rotating.
 
-use netcanv_renderer::paws::{point, vector, Point, Rect, Vector};
+use std::f32::consts::TAU;
 
-/// A viewport that can be panned around and zoomed into.
+use netcanv_renderer::paws::{point, vector, Point, Rect, Renderer, Vector};
+use netcanv_renderer::RenderBackend;
+use web_time::Instant;
+
+use crate::backend::Backend;
+use crate::common::VectorMath;
+
+/// A viewport that can be panned around, zoomed into, and rotated.
+///
+/// Zooming doesn't take effect right away. Instead, it sets the pan and zoom level the viewport
+/// should end up at, and [`Viewport::animate`] then moves the viewport towards them each frame.
+/// Panning by dragging is always applied right away, such that the canvas sticks to the mouse.
 #[derive(Debug, Clone)]
 pub struct Viewport {
    pan: Vector,
    zoom_level: f32,
+   /// The rotation angle, in radians. Positive angles rotate the canvas clockwise on the screen.
+   rotation: f32,
+   /// The pan the viewport is animating towards.
+   target_pan: Vector,
+   /// The zoom level the viewport is animating towards.
+   target_zoom_level: f32,
+   /// The time of the last call to [`Viewport::animate`].
+   last_animated: Instant,
 }
 
 /// A rectangle with integer coordinates.
@@ -26,20 +45,27 @@ pub struct Tiles {
 }
 
 impl Viewport {
+   const MIN_ZOOM_LEVEL: f32 = -8.0;
+   const MAX_ZOOM_LEVEL: f32 = 20.0;
+   /// The space left around rectangles zoomed to fit, in screen pixels.
+   const FIT_PADDING: f32 = 32.0;
+   /// The time it takes an animation to cover roughly two thirds of the remaining distance to
+   /// its target, in seconds.
+   const ANIMATION_TIME_CONSTANT: f32 = 0.05;
+   /// The longest time a single animation step can cover, in seconds. Frames aren't rendered
+   /// while the app is idle, so without this limit, an animation starting after a while of
+   /// idling would finish within its first frame.
+   const MAX_ANIMATION_STEP: f32 = 1.0 / 30.0;
+
    /// Creates a new viewport with given zoom level.
    pub fn with_zoom_level(zoom_level: f32) -> Self {
       Self {
          pan: vector(0.0, 0.0),
          zoom_level,
-      }
-   }
-
-   /// Creates a new viewport, panned such that the top-left of the given rectangle corner lands at
-   /// the given point.
-   pub fn from_top_left(rect: Rect) -> Self {
-      Self {
-         pan: rect.center(),
-         zoom_level: 0.0,
+         rotation: 0.0,
+         target_pan: vector(0.0, 0.0),
+         target_zoom_level: zoom_level,
+         last_animated: Instant::now(),
       }
    }
 
@@ -50,12 +76,47 @@ impl Viewport {
 
    /// Returns the zoom factor.
    pub fn zoom(&self) -> f32 {
-      f32::powf(2.0, self.zoom_level * 0.25)
+      Self::zoom_factor(self.zoom_level)
+   }
+
+   /// Returns the zoom factor the viewport is animating towards.
+   pub fn target_zoom(&self) -> f32 {
+      Self::zoom_factor(self.target_zoom_level)
+   }
+
+   /// Converts a zoom level to a zoom factor.
+   fn zoom_factor(zoom_level: f32) -> f32 {
+      f32::powf(2.0, zoom_level * 0.25)
+   }
+
+   /// Returns the rotation angle, in radians.
+   pub fn rotation(&self) -> f32 {
+      self.rotation
    }
 
    /// Pans the viewport around by the given vector.
+   ///
+   /// The vector is in screen space, so panning follows the mouse even if the viewport is rotated.
    pub fn pan_around(&mut self, by: Vector) {
-      self.pan += by * (1.0 / self.zoom());
+      let delta = rotate(by, -self.rotation) * (1.0 / self.zoom());
+      self.pan += delta;
+      self.target_pan += delta;
+   }
+
+   /// Pans the viewport such that the given point ends up in its center.
+   pub fn center_on(&mut self, point: Point) {
+      self.pan = vector(point.x, point.y);
+      self.target_pan = self.pan;
+   }
+
+   /// Rotates the viewport by the given angle, in radians.
+   pub fn rotate(&mut self, angle: f32) {
+      self.rotation = (self.rotation + angle).rem_euclid(TAU);
+   }
+
+   /// Resets the rotation back to zero.
+   pub fn reset_rotation(&mut self) {
+      self.rotation = 0.0;
    }
 
    /// Zooms in or out of the viewport by the given delta.
@@ -64,15 +125,82 @@ impl Viewport {
    /// _zoom level_, which is linear, and this zoom level is later converted into the
    /// exponential _zoom factor_.
    pub fn zoom_in(&mut self, delta: f32) {
-      self.zoom_level += delta;
-      self.zoom_level = self.zoom_level.clamp(-8.0, 20.0);
+      self.target_zoom_level += delta;
+      self.target_zoom_level =
+         self.target_zoom_level.clamp(Self::MIN_ZOOM_LEVEL, Self::MAX_ZOOM_LEVEL);
+   }
+
+   /// Resets the zoom factor back to 100%, keeping the center of the viewport in place.
+   pub fn reset_zoom(&mut self) {
+      self.target_zoom_level = 0.0;
+   }
+
+   /// Pans back to the origin and resets the zoom factor back to 100%.
+   pub fn reset_pan_and_zoom(&mut self) {
+      self.target_pan = vector(0.0, 0.0);
+      self.target_zoom_level = 0.0;
+   }
+
+   /// Pans and zooms the viewport such that the whole rectangle is visible, given the provided
+   /// window size.
+   ///
+   /// The zoom level is rounded down to a whole step, so that zooming with the scroll wheel
+   /// afterwards lands on the same zoom factors as usual.
+   pub fn zoom_to_fit(&mut self, rect: Rect, window_size: Vector) {
+      let center = rect.center();
+      self.target_pan = vector(center.x, center.y);
+      // The rectangle's bounding box grows when it's rotated on the screen.
+      let (sin, cos) = self.rotation.sin_cos();
+      let (sin, cos) = (sin.abs(), cos.abs());
+      let width = rect.width() * cos + rect.height() * sin;
+      let height = rect.width() * sin + rect.height() * cos;
+      let available = window_size - vector(Self::FIT_PADDING, Self::FIT_PADDING) * 2.0;
+      let zoom = f32::min(available.x / width.max(1.0), available.y / height.max(1.0));
+      self.target_zoom_level = (zoom.max(f32::MIN_POSITIVE).log2() * 4.0).floor();
+      self.target_zoom_level =
+         self.target_zoom_level.clamp(Self::MIN_ZOOM_LEVEL, Self::MAX_ZOOM_LEVEL);
+   }
+
+   /// Moves the pan and zoom level towards their targets, with an exponential ease-out.
+   ///
+   /// This should be called once per frame, after handling input and before anything reads the
+   /// viewport. If `smooth` is false, the targets are reached right away.
+   pub fn animate(&mut self, smooth: bool) {
+      let now = Instant::now();
+      let elapsed = (now - self.last_animated).as_secs_f32().min(Self::MAX_ANIMATION_STEP);
+      self.last_animated = now;
+
+      let t = match smooth {
+         true => 1.0 - (-elapsed / Self::ANIMATION_TIME_CONSTANT).exp(),
+         false => 1.0,
+      };
+      self.pan += (self.target_pan - self.pan) * t;
+      self.zoom_level += (self.target_zoom_level - self.zoom_level) * t;
+
+      // The ease-out never quite reaches the target, so the last bit is skipped.
+      let pan_distance = self.target_pan - self.pan;
+      if pan_distance.x.hypot(pan_distance.y) * self.zoom() < 0.1 {
+         self.pan = self.target_pan;
+      }
+      if (self.target_zoom_level - self.zoom_level).abs() < 0.001 {
+         self.zoom_level = self.target_zoom_level;
+      }
+   }
+
+   /// Returns whether the viewport is still animating towards its target pan or zoom level.
+   pub fn is_animating(&self) -> bool {
+      self.pan != self.target_pan || self.zoom_level != self.target_zoom_level
    }
 
    /// Returns the rectangle visible from the viewport, given the provided window size.
+   ///
+   /// If the viewport is rotated, this is the bounding box of the rotated window.
    pub fn visible_rect(&self, window_size: Vector) -> Rect {
       let inv_zoom = 1.0 / self.zoom();
-      let width = window_size.x * inv_zoom;
-      let height = window_size.y * inv_zoom;
+      let (sin, cos) = self.rotation.sin_cos();
+      let (sin, cos) = (sin.abs(), cos.abs());
+      let width = (window_size.x * cos + window_size.y * sin) * inv_zoom;
+      let height = (window_size.x * sin + window_size.y * cos) * inv_zoom;
       Rect::new(
          point(self.pan.x - width / 2.0, self.pan.y - height / 2.0),
          vector(width, height),
@@ -97,15 +225,73 @@ impl Viewport {
    ///
    /// This can be used to pick things on the canvas, given a mouse position.
    pub fn to_viewport_space(&self, point: Point, window_size: Vector) -> Point {
-      (point - window_size / 2.0) * (1.0 / self.zoom()) + self.pan
+      let point = rotate(point - window_size / 2.0, -self.rotation) + window_size / 2.0;
+      self.to_unrotated_viewport_space(point, window_size)
    }
 
    /// Converts a point from viewport space to screen space.
    ///
    /// This transformation is the inverse of [`Viewport::to_viewport_space`].
    pub fn to_screen_space(&self, point: Point, window_size: Vector) -> Point {
+      let point = self.to_unrotated_screen_space(point, window_size);
+      rotate(point - window_size / 2.0, self.rotation) + window_size / 2.0
+   }
+
+   /// Converts a length from viewport space to screen space.
+   pub fn to_screen_length(&self, length: f32) -> f32 {
+      length * self.zoom()
+   }
+
+   /// Converts a length from screen space to viewport space.
+   pub fn to_viewport_length(&self, length: f32) -> f32 {
+      length / self.zoom()
+   }
+
+   /// Converts a point from viewport space to screen space, ignoring the rotation.
+   ///
+   /// This is useful for drawing axis-aligned shapes inside of a transform set up by
+   /// [`Viewport::apply_rotation`].
+   pub fn to_unrotated_screen_space(&self, point: Point, window_size: Vector) -> Point {
       (point - self.pan) * self.zoom() + window_size / 2.0
    }
+
+   /// Converts a rectangle from viewport space to screen space, ignoring the rotation.
+   ///
+   /// The corners of the resulting rectangle are snapped to whole pixels, such that overlays
+   /// drawn around it line up with the canvas's pixels.
+   pub fn to_unrotated_screen_rect(&self, rect: Rect, window_size: Vector) -> Rect {
+      let top_left = self.to_unrotated_screen_space(rect.top_left(), window_size).floor();
+      let bottom_right = self.to_unrotated_screen_space(rect.bottom_right(), window_size).floor();
+      Rect::new(top_left, bottom_right - top_left)
+   }
+
+   /// The inverse of [`Viewport::to_unrotated_screen_space`].
+   fn to_unrotated_viewport_space(&self, point: Point, window_size: Vector) -> Point {
+      (point - window_size / 2.0) * (1.0 / self.zoom()) + self.pan
+   }
+
+   /// Applies the full viewport transform to the renderer, such that shapes drawn in viewport
+   /// space end up where [`Viewport::to_screen_space`] would put them.
+   pub fn apply_transform(&self, renderer: &mut Backend, window_size: Vector) {
+      renderer.translate(window_size / 2.0);
+      renderer.rotate(self.rotation);
+      renderer.scale(vector(self.zoom(), self.zoom()));
+      renderer.translate(-self.pan);
+   }
+
+   /// Rotates the renderer's transform around the center of the window, such that shapes drawn
+   /// in unrotated screen space line up with the canvas.
+   pub fn apply_rotation(&self, renderer: &mut Backend, window_size: Vector) {
+      renderer.translate(window_size / 2.0);
+      renderer.rotate(self.rotation);
+      renderer.translate(-window_size / 2.0);
+   }
+}
+
+/// Rotates a vector by the given angle, in radians.
+fn rotate(v: Vector, angle: f32) -> Vector {
+   let (sin, cos) = angle.sin_cos();
+   vector(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
 }
 
 impl Iterator for Tiles {
@@ -125,3 +311,216 @@ impl Iterator for Tiles {
       Some(pos)
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use web_time::Duration;
+
+   use super::*;
+
+   fn window_size() -> Vector {
+      vector(800.0, 600.0)
+   }
+
+   /// Returns a viewport that's zoomed in, panned away from the origin, and rotated by the given
+   /// angle.
+   fn transformed_viewport(rotation: f32) -> Viewport {
+      let mut viewport = Viewport::with_zoom_level(3.0);
+      viewport.center_on(point(-120.5, 64.25));
+      viewport.rotate(rotation);
+      viewport
+   }
+
+   fn assert_close(a: Point, b: Point) {
+      let d = a - b;
+      assert!(d.x.hypot(d.y) < 1e-3, "{a:?} is not close to {b:?}");
+   }
+
+   #[test]
+   fn screen_space_round_trip_with_rotation() {
+      for rotation in [0.0, 0.3, TAU / 4.0, 2.0, TAU * 0.75 + 0.1] {
+         let viewport = transformed_viewport(rotation);
+         for screen in [point(0.0, 0.0), point(400.0, 300.0), point(799.0, 17.5)] {
+            let canvas = viewport.to_viewport_space(screen, window_size());
+            assert_close(viewport.to_screen_space(canvas, window_size()), screen);
+         }
+         for canvas in [point(0.0, 0.0), point(-130.0, 70.0), point(1000.0, -3.0)] {
+            let screen = viewport.to_screen_space(canvas, window_size());
+            assert_close(viewport.to_viewport_space(screen, window_size()), canvas);
+         }
+      }
+   }
+
+   #[test]
+   fn positive_rotation_is_clockwise() {
+      let mut viewport = Viewport::with_zoom_level(0.0);
+      viewport.rotate(TAU / 4.0);
+      let center = window_size() / 2.0;
+      // With the Y axis pointing downwards, a point to the right of the center ends up below it.
+      let screen = viewport.to_screen_space(point(10.0, 0.0), window_size());
+      assert_close(screen, center + vector(0.0, 10.0));
+   }
+
+   #[test]
+   fn rotation_wraps_around_and_resets() {
+      let mut viewport = Viewport::with_zoom_level(0.0);
+      viewport.rotate(TAU + 0.5);
+      assert!((viewport.rotation() - 0.5).abs() < 1e-5);
+      viewport.rotate(-1.0);
+      assert!((viewport.rotation() - (TAU - 0.5)).abs() < 1e-5);
+      viewport.reset_rotation();
+      assert_eq!(viewport.rotation(), 0.0);
+   }
+
+   #[test]
+   fn panning_follows_the_mouse_when_rotated() {
+      let mut viewport = transformed_viewport(1.0);
+      let mouse = point(250.0, 410.0);
+      let under_mouse = viewport.to_viewport_space(mouse, window_size());
+      let delta = vector(30.0, -12.0);
+      viewport.pan_around(delta);
+      assert_close(
+         viewport.to_screen_space(under_mouse, window_size()),
+         mouse - delta,
+      );
+   }
+
+   #[test]
+   fn unrotated_screen_space_round_trip() {
+      // The rotation must not affect the unrotated conversions.
+      let viewport = transformed_viewport(1.0);
+      for canvas in [point(0.0, 0.0), point(-130.0, 70.0), point(1000.0, -3.0)] {
+         let screen = viewport.to_unrotated_screen_space(canvas, window_size());
+         assert_close(
+            viewport.to_unrotated_viewport_space(screen, window_size()),
+            canvas,
+         );
+      }
+   }
+
+   #[test]
+   fn unrotated_conversion_matches_the_full_one_without_rotation() {
+      let viewport = transformed_viewport(0.0);
+      for canvas in [point(0.0, 0.0), point(-130.0, 70.0), point(1000.0, -3.0)] {
+         assert_close(
+            viewport.to_unrotated_screen_space(canvas, window_size()),
+            viewport.to_screen_space(canvas, window_size()),
+         );
+      }
+   }
+
+   #[test]
+   fn length_round_trip() {
+      let viewport = transformed_viewport(0.0);
+      assert_eq!(viewport.to_screen_length(10.0), 10.0 * viewport.zoom());
+      let length = viewport.to_viewport_length(viewport.to_screen_length(12.5));
+      assert!((length - 12.5).abs() < 1e-4);
+   }
+
+   #[test]
+   fn screen_rects_are_snapped_to_whole_pixels() {
+      let viewport = transformed_viewport(0.0);
+      let rect = Rect::new(point(-125.3, 60.1), vector(7.7, 3.3));
+      let screen_rect = viewport.to_unrotated_screen_rect(rect, window_size());
+      for corner in [screen_rect.top_left(), screen_rect.bottom_right()] {
+         assert_eq!(corner, corner.floor());
+      }
+      // Each corner is at most a pixel away from where it would be without snapping.
+      let exact_top_left = viewport.to_unrotated_screen_space(rect.top_left(), window_size());
+      let exact_bottom_right =
+         viewport.to_unrotated_screen_space(rect.bottom_right(), window_size());
+      for (snapped, exact) in [
+         (screen_rect.top_left(), exact_top_left),
+         (screen_rect.bottom_right(), exact_bottom_right),
+      ] {
+         let d = exact - snapped;
+         assert!((0.0..1.0).contains(&d.x) && (0.0..1.0).contains(&d.y));
+      }
+   }
+
+   /// Returns whether the rectangle lies within the window on the screen, leaving at least the
+   /// padding around it.
+   fn fits_on_screen(viewport: &Viewport, rect: Rect) -> bool {
+      let bottom_left = point(rect.left(), rect.bottom());
+      let top_right = point(rect.right(), rect.top());
+      let padding = Viewport::FIT_PADDING - 1e-3;
+      [rect.top_left(), top_right, rect.bottom_right(), bottom_left].into_iter().all(|corner| {
+         let corner = viewport.to_screen_space(corner, window_size());
+         (padding..=window_size().x - padding).contains(&corner.x)
+            && (padding..=window_size().y - padding).contains(&corner.y)
+      })
+   }
+
+   #[test]
+   fn zoom_to_fit_shows_the_whole_rect() {
+      let rect = Rect::new(point(100.0, -50.0), vector(400.0, 300.0));
+      for rotation in [0.0, TAU / 8.0, TAU / 4.0] {
+         let mut viewport = Viewport::with_zoom_level(0.0);
+         viewport.rotate(rotation);
+         viewport.zoom_to_fit(rect, window_size());
+         viewport.animate(false);
+         assert_close(viewport.pan(), rect.center());
+         assert_eq!(viewport.zoom_level.fract(), 0.0);
+         assert!(fits_on_screen(&viewport, rect));
+
+         // One more step of zooming in would be too much.
+         viewport.zoom_in(1.0);
+         viewport.animate(false);
+         assert!(!fits_on_screen(&viewport, rect));
+      }
+   }
+
+   #[test]
+   fn zoom_to_fit_stays_within_the_zoom_limits() {
+      let mut viewport = Viewport::with_zoom_level(0.0);
+      viewport.zoom_to_fit(Rect::new(point(0.0, 0.0), vector(0.0, 0.0)), window_size());
+      assert_eq!(viewport.target_zoom_level, Viewport::MAX_ZOOM_LEVEL);
+      viewport.zoom_to_fit(Rect::new(point(0.0, 0.0), vector(1e9, 1e9)), window_size());
+      assert_eq!(viewport.target_zoom_level, Viewport::MIN_ZOOM_LEVEL);
+   }
+
+   /// Makes the next animation step behave as though the app was idle for a while.
+   fn idle(viewport: &mut Viewport) {
+      viewport.last_animated = Instant::now() - Duration::from_secs(10);
+   }
+
+   #[test]
+   fn animation_eases_out_towards_the_target() {
+      let mut viewport = Viewport::with_zoom_level(0.0);
+      viewport.zoom_in(4.0);
+      assert!(viewport.is_animating());
+
+      // Idling doesn't let the first step skip straight to the target.
+      idle(&mut viewport);
+      viewport.animate(true);
+      let first_step = viewport.zoom_level;
+      assert!(0.0 < first_step && first_step < 4.0);
+
+      let mut steps = 1;
+      while viewport.is_animating() {
+         let previous = viewport.zoom_level;
+         idle(&mut viewport);
+         viewport.animate(true);
+         // The remaining steps get shorter and shorter as the target gets closer.
+         assert!(viewport.zoom_level - previous < first_step);
+         steps += 1;
+         assert!(steps < 100, "the animation never finishes");
+      }
+      assert_eq!(viewport.zoom_level, 4.0);
+   }
+
+   #[test]
+   fn animation_can_be_turned_off() {
+      let mut viewport = Viewport::with_zoom_level(0.0);
+      viewport.zoom_in(-3.0);
+      viewport.pan_around(vector(50.0, 0.0));
+      viewport.zoom_to_fit(
+         Rect::new(point(10.0, 10.0), vector(40.0, 40.0)),
+         window_size(),
+      );
+      viewport.animate(false);
+      assert!(!viewport.is_animating());
+      assert_eq!(viewport.zoom(), viewport.target_zoom());
+      assert_eq!(viewport.pan(), vector(30.0, 30.0));
+   }
+}
@@ -0,0 +1,90 @@
+//! Waking up the event loop while the app is idle.
+//!
+//! When nothing is going on, the event loop sleeps until an event arrives. Work that finishes
+//! outside of the main thread, such as a packet arriving from the relay, has to [`wake`] the loop
+//! up so that it gets handled. Work that needs to happen at some point in the future, such as
+//! pinging other peers, has to ask for a wake-up [`at`] that time instead.
+
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use accesskit_winit::ActionRequestEvent;
+use parking_lot::{const_mutex, Mutex};
+use web_time::Instant;
+
+use crate::backend::winit::event_loop::EventLoopProxy;
+
+/// Events sent to the event loop from outside of it.
+pub enum UserEvent {
+   /// An action requested by an assistive technology.
+   Accessibility(ActionRequestEvent),
+   /// Something is waiting to be handled by the next frame.
+   Wake,
+}
+
+impl From<ActionRequestEvent> for UserEvent {
+   fn from(event: ActionRequestEvent) -> Self {
+      Self::Accessibility(event)
+   }
+}
+
+static PROXY: OnceLock<Mutex<EventLoopProxy<UserEvent>>> = OnceLock::new();
+static DEADLINES: Mutex<BTreeSet<Instant>> = const_mutex(BTreeSet::new());
+
+/// Sets up waking for the given event loop. Before this is called, waking does nothing.
+pub fn init(proxy: EventLoopProxy<UserEvent>) {
+   if PROXY.set(Mutex::new(proxy)).is_err() {
+      tracing::warn!("waking was initialized more than once");
+   }
+}
+
+/// Wakes the event loop up, so that the next frame is processed as soon as possible.
+///
+/// This can be called from any thread.
+pub fn wake() {
+   if let Some(proxy) = PROXY.get() {
+      // Sending only fails when the event loop is gone, in which case there's nothing to wake.
+      let _ = proxy.lock().send_event(UserEvent::Wake);
+   }
+}
+
+/// Makes sure a frame is processed once the given time comes.
+///
+/// Asking for the same time more than once results in a single wake-up.
+pub fn at(time: Instant) {
+   DEADLINES.lock().insert(time);
+}
+
+/// Forgets about the times that have already passed, and returns the nearest one of the remaining
+/// ones.
+pub fn next_deadline() -> Option<Instant> {
+   let mut deadlines = DEADLINES.lock();
+   let now = Instant::now();
+   // Everything that's due is handled by the frame that was just processed.
+   *deadlines = deadlines.split_off(&now);
+   deadlines.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+   use web_time::Duration;
+
+   use super::*;
+
+   // The deadlines are global, so all the checks live in a single test to keep them from
+   // interfering with each other.
+   #[test]
+   fn passed_deadlines_are_forgotten() {
+      let now = Instant::now();
+      at(now - Duration::from_secs(1));
+      assert!(next_deadline().is_none());
+
+      let soon = now + Duration::from_secs(60);
+      let later = now + Duration::from_secs(120);
+      at(later);
+      at(soon);
+      at(soon);
+      assert_eq!(next_deadline(), Some(soon));
+      assert_eq!(DEADLINES.lock().len(), 2);
+   }
+}